@@ -9,11 +9,36 @@
 //! eliminated. Refer to <https://github.com/filecoin-project/fvm/issues/229> for
 //! details.
 
+use anyhow::Context as _;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::CborStore;
 use fvm_ipld_encoding::tuple::*;
 use fvm_shared::address::Address;
 
+use crate::machine::Manifest;
+use crate::state_tree::ActorState;
+
 /// State specifies the key address for the actor.
 #[derive(Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct State {
     pub address: Address,
 }
+
+/// Returns the key (f1/f3) address of an account actor, read directly from its state. This agrees
+/// with the account actor's `PubkeyAddress` method (method 2), without invoking the actor.
+///
+/// Returns `None` if the actor isn't an account actor.
+pub(crate) fn key_address(
+    store: &impl Blockstore,
+    builtin_actors: &Manifest,
+    actor: &ActorState,
+) -> anyhow::Result<Option<Address>> {
+    if !builtin_actors.is_account_actor(&actor.code) {
+        return Ok(None);
+    }
+    let state: State = store
+        .get_cbor(&actor.state)?
+        .context("account actor state not found")?;
+    Ok(Some(state.address))
+}
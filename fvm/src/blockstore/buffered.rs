@@ -3,8 +3,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
+use std::num::NonZeroUsize;
+use std::ops::AddAssign;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use anyhow::{Result, anyhow};
 use cid::Cid;
@@ -18,6 +21,40 @@ use fvm_shared::commcid::{FIL_COMMITMENT_SEALED, FIL_COMMITMENT_UNSEALED};
 pub struct BufferedBlockstore<BS> {
     base: BS,
     write: RefCell<HashMap<Cid, Vec<u8>>>,
+    /// The blocks added to the write buffer in the current generation, if one has begun (see
+    /// [`BufferedBlockstore::begin_generation`]).
+    generation: RefCell<Option<Generation>>,
+}
+
+/// Statistics on the blocks written to a [`BufferedBlockstore`] over a generation (e.g., while
+/// executing a message), used to measure write amplification.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteStats {
+    /// The number of new blocks written to the write buffer.
+    pub blocks_written: u64,
+    /// The total size of the new blocks written to the write buffer, in bytes.
+    pub bytes_written: u64,
+    /// The number of new blocks that are reachable from the state root at the end of the
+    /// generation, and would therefore be flushed.
+    pub blocks_flushed: u64,
+    /// The total size of the new blocks that would be flushed, in bytes.
+    pub bytes_flushed: u64,
+}
+
+impl AddAssign for WriteStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.blocks_written += rhs.blocks_written;
+        self.bytes_written += rhs.bytes_written;
+        self.blocks_flushed += rhs.blocks_flushed;
+        self.bytes_flushed += rhs.bytes_flushed;
+    }
+}
+
+/// The blocks added to the write buffer over a generation.
+#[derive(Default, Debug)]
+struct Generation {
+    written: HashSet<Cid>,
+    bytes_written: u64,
 }
 
 impl<BS> BufferedBlockstore<BS>
@@ -28,6 +65,7 @@ where
         Self {
             base,
             write: Default::default(),
+            generation: Default::default(),
         }
     }
 
@@ -45,14 +83,115 @@ where
             self.buffer_len()
         );
 
-        self.base.put_many_keyed(self.write.borrow_mut().drain())?;
+        // Only clear the buffer once everything has been written, so a failed flush can be retried.
+        let mut cache = self.write.borrow_mut();
+        self.base
+            .put_many_keyed(cache.iter().map(|(k, v)| (*k, v)))?;
+        cache.clear();
 
         Ok(())
     }
 
+    /// Like [`BufferedBlockstore::flush_all`], but writes the blocks from up to `workers` threads,
+    /// in batches of at most `batch_size` blocks (see [`BufferedBlockstore::flush_parallel`]).
+    pub fn flush_all_parallel(&self, workers: NonZeroUsize, batch_size: NonZeroUsize) -> Result<()>
+    where
+        BS: Sync,
+    {
+        let mut cache = self.write.borrow_mut();
+        let blocks: Vec<Cid> = cache.keys().copied().collect();
+        write_parallel(&self.base, &cache, &blocks, workers, batch_size)?;
+        cache.clear();
+        Ok(())
+    }
+
+    /// Like [`Buffered::flush`], but writes the reachable blocks to the base store from up to
+    /// `workers` threads, each handing batches of at most `batch_size` blocks to
+    /// [`Blockstore::put_many_keyed`]. This helps when the base store has a high write latency
+    /// (e.g., it's backed by a disk or a remote service).
+    ///
+    /// The blocks are found by the same deterministic walk as `flush`, and are written by
+    /// reference, so the only additional memory used is the list of CIDs. As with `flush`, blocks
+    /// are only removed from the write buffer once they've all been written, and no new batch is
+    /// started once one fails. If several batches fail, the first one's error (in walk order) is
+    /// returned.
+    pub fn flush_parallel(
+        &self,
+        root: &Cid,
+        workers: NonZeroUsize,
+        batch_size: NonZeroUsize,
+    ) -> Result<()>
+    where
+        BS: Sync,
+    {
+        let mut cache = self.write.borrow_mut();
+        let reachable = find_reachable(&cache, root, &HashSet::new())?;
+        write_parallel(&self.base, &cache, &reachable, workers, batch_size)?;
+        for k in &reachable {
+            cache.remove(k);
+        }
+        Ok(())
+    }
+
+    /// Begins a new generation: blocks added to the write buffer from now on are attributed to it,
+    /// until [`BufferedBlockstore::end_generation`]. Any current generation is discarded.
+    pub fn begin_generation(&self) {
+        *self.generation.borrow_mut() = Some(Generation::default());
+    }
+
+    /// Ends the current generation, returning statistics on the blocks added to the write buffer
+    /// during it, and on how many of those are reachable from `root` (i.e., the blocks that would
+    /// be flushed along with it). Blocks that were already buffered aren't counted.
+    ///
+    /// Returns empty statistics if no generation has begun.
+    pub fn end_generation(&self, root: &Cid) -> Result<WriteStats> {
+        let Some(generation) = self.generation.borrow_mut().take() else {
+            return Ok(WriteStats::default());
+        };
+        let cache = self.write.borrow();
+        let (blocks_flushed, bytes_flushed) = find_reachable(&cache, root, &HashSet::new())?
+            .iter()
+            .filter(|k| generation.written.contains(k))
+            .fold((0, 0), |(blocks, bytes), k| {
+                (blocks + 1, bytes + cache[k].len() as u64)
+            });
+        Ok(WriteStats {
+            blocks_written: generation.written.len() as u64,
+            bytes_written: generation.bytes_written,
+            blocks_flushed,
+            bytes_flushed,
+        })
+    }
+
+    /// Adds a block to the write buffer, attributing it to the current generation (if any) unless
+    /// it was already buffered.
+    fn insert(&self, cache: &mut HashMap<Cid, Vec<u8>>, k: Cid, block: Vec<u8>) {
+        let len = block.len();
+        if cache.insert(k, block).is_none()
+            && let Some(generation) = &mut *self.generation.borrow_mut()
+        {
+            generation.written.insert(k);
+            generation.bytes_written += len as u64;
+        }
+    }
+
     pub fn buffer_len(&self) -> usize {
         self.write.borrow().len()
     }
+
+    /// Returns the blocks of the IPLD DAG under `root` that are in the write buffer, without
+    /// flushing them. Blocks in `skip` aren't returned nor traversed.
+    pub fn buffered_blocks(&self, root: &Cid, skip: &HashSet<Cid>) -> Result<Vec<(Cid, Vec<u8>)>> {
+        let cache = self.write.borrow();
+        let reachable = find_reachable(&cache, root, skip)?;
+        Ok(reachable
+            .into_iter()
+            .map(|k| {
+                let block = cache[&k].clone();
+                (k, block)
+            })
+            .collect())
+    }
 }
 
 impl<BS> Buffered for BufferedBlockstore<BS>
@@ -62,9 +201,63 @@ where
     /// Flushes the buffered cache based on the root node.
     /// This will recursively traverse the cache and write all data connected by links to this
     /// root Cid, moving the reachable blocks from the write buffer to the backing store.
+    ///
+    /// Blocks are only removed from the write buffer once they've all been written, so a failed
+    /// flush leaves the buffer untouched and can be retried.
     fn flush(&self, root: &Cid) -> Result<()> {
+        let mut cache = self.write.borrow_mut();
+        let reachable = find_reachable(&cache, root, &HashSet::new())?;
         self.base
-            .put_many_keyed(take_reachable(&mut self.write.borrow_mut(), root)?)
+            .put_many_keyed(reachable.iter().map(|k| (*k, &cache[k])))?;
+        for k in &reachable {
+            cache.remove(k);
+        }
+        Ok(())
+    }
+}
+
+/// Writes the given blocks from the cache to the base store, from up to `workers` threads, in
+/// batches of at most `batch_size` blocks. Batches are handed out in order, and none are started
+/// after one fails. Returns the error of the first failed batch, if any.
+fn write_parallel<BS: Blockstore + Sync>(
+    base: &BS,
+    cache: &HashMap<Cid, Vec<u8>>,
+    blocks: &[Cid],
+    workers: NonZeroUsize,
+    batch_size: NonZeroUsize,
+) -> Result<()> {
+    let batches: Vec<&[Cid]> = blocks.chunks(batch_size.get()).collect();
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    let errors = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.get().min(batches.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut errors = Vec::new();
+                    while !failed.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(batch) = batches.get(i) else {
+                            break;
+                        };
+                        if let Err(e) = base.put_many_keyed(batch.iter().map(|k| (*k, &cache[k]))) {
+                            failed.store(true, Ordering::Relaxed);
+                            errors.push((i, e));
+                        }
+                    }
+                    errors
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+
+    match errors.into_iter().min_by_key(|(i, _)| *i) {
+        Some((_, e)) => Err(e),
+        None => Ok(()),
     }
 }
 
@@ -154,8 +347,13 @@ fn scan_for_links(mut buf: &[u8], out: &mut Vec<Cid>) -> Result<()> {
     Ok(())
 }
 
-/// Moves the IPLD DAG under `root` from the cache to the base store.
-fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(Cid, Vec<u8>)>> {
+/// Finds the blocks of the IPLD DAG under `root` that are in the cache, in a deterministic order.
+/// Blocks in `skip` are ignored, along with the blocks only reachable through them.
+fn find_reachable(
+    cache: &HashMap<Cid, Vec<u8>>,
+    root: &Cid,
+    skip: &HashSet<Cid>,
+) -> Result<Vec<Cid>> {
     const BLAKE2B_256: u64 = 0xb220;
     const BLAKE2B_LEN: u8 = 32;
     const IDENTITY: u64 = 0x0;
@@ -167,6 +365,7 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
     //    perf impact.
 
     let mut stack = vec![*root];
+    let mut seen = HashSet::new();
     let mut result = Vec::new();
 
     while let Some(k) = stack.pop() {
@@ -200,17 +399,20 @@ fn take_reachable(cache: &mut HashMap<Cid, Vec<u8>>, root: &Cid) -> Result<Vec<(
             //
             // The alternative would be to check if it's in the datastore, but that's likely even more
             // expensive. And there wouldn't be much we could do at that point but abort the block.
-            let Some(block) = cache.remove(&k) else {
+            let Some(block) = cache.get(&k) else {
                 continue;
             };
+            if skip.contains(&k) || !seen.insert(k) {
+                continue;
+            }
 
             // At the moment, only DAG_CBOR can link to other blocks.
             if k.codec() == DAG_CBOR {
-                scan_for_links(&block, &mut stack)?;
+                scan_for_links(block, &mut stack)?;
             }
 
             // Record the block so we can write it back.
-            result.push((k, block));
+            result.push(k);
         };
     }
 
@@ -230,7 +432,7 @@ where
     }
 
     fn put_keyed(&self, cid: &Cid, buf: &[u8]) -> Result<()> {
-        self.write.borrow_mut().insert(*cid, Vec::from(buf));
+        self.insert(&mut self.write.borrow_mut(), *cid, Vec::from(buf));
         Ok(())
     }
 
@@ -242,15 +444,24 @@ where
         }
     }
 
+    fn get_size(&self, k: &Cid) -> Result<Option<usize>> {
+        if let Some(data) = self.write.borrow().get(k) {
+            Ok(Some(data.len()))
+        } else {
+            self.base.get_size(k)
+        }
+    }
+
     fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
     where
         Self: Sized,
         D: AsRef<[u8]>,
         I: IntoIterator<Item = (Cid, D)>,
     {
-        self.write
-            .borrow_mut()
-            .extend(blocks.into_iter().map(|(k, v)| (k, v.as_ref().into())));
+        let mut cache = self.write.borrow_mut();
+        for (k, v) in blocks {
+            self.insert(&mut cache, k, v.as_ref().into());
+        }
         Ok(())
     }
 }
@@ -260,7 +471,7 @@ mod tests {
     use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
     use fvm_ipld_encoding::CborStore;
     use fvm_shared::{IDENTITY_HASH, commcid};
-    use multihash_codetable::{Code, Multihash};
+    use multihash_codetable::{Code, Multihash, MultihashDigest};
     use serde::{Deserialize, Serialize};
 
     use super::*;
@@ -279,6 +490,24 @@ mod tests {
         assert_eq!(mem.get_cbor::<u8>(&cid).unwrap(), Some(8));
     }
 
+    #[test]
+    fn buffered_store_has_and_size() {
+        let mem = MemoryBlockstore::default();
+        let base_cid = mem.put_cbor(&"base", Code::Blake2b256).unwrap();
+        let buf_store = BufferedBlockstore::new(&mem);
+        let buf_cid = buf_store.put_cbor(&[1u8, 2, 3], Code::Blake2b256).unwrap();
+        let missing = Cid::new_v1(CBOR, Code::Blake2b256.digest(b"missing"));
+
+        // Blocks in both the write buffer and the base store are visible.
+        assert!(buf_store.has(&base_cid).unwrap());
+        assert!(buf_store.has(&buf_cid).unwrap());
+        assert!(!buf_store.has(&missing).unwrap());
+
+        assert_eq!(buf_store.get_size(&base_cid).unwrap(), Some(5));
+        assert_eq!(buf_store.get_size(&buf_cid).unwrap(), Some(4));
+        assert_eq!(buf_store.get_size(&missing).unwrap(), None);
+    }
+
     #[test]
     fn buffered_store_with_links() {
         let mem = MemoryBlockstore::default();
@@ -349,6 +578,58 @@ mod tests {
         assert_eq!(mem.get_cbor::<u8>(&unconnected).unwrap(), None);
     }
 
+    #[test]
+    fn generation_write_stats() {
+        let mem = MemoryBlockstore::default();
+        let buf_store = BufferedBlockstore::new(&mem);
+        let size = |k: &Cid| buf_store.get_size(k).unwrap().unwrap() as u64;
+
+        // Without a generation, nothing is tracked.
+        let old = buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        assert_eq!(
+            buf_store.end_generation(&old).unwrap(),
+            WriteStats::default()
+        );
+
+        buf_store.begin_generation();
+        // Re-writing an already buffered block isn't a new write.
+        buf_store.put_cbor(&1u8, Code::Blake2b256).unwrap();
+        let leaf = buf_store.put_cbor(&"leaf", Code::Blake2b256).unwrap();
+        let orphan = buf_store.put_cbor(&"orphan", Code::Blake2b256).unwrap();
+        let root = buf_store.put_cbor(&(leaf, old), Code::Blake2b256).unwrap();
+        buf_store
+            .put_many_keyed([(leaf, buf_store.get(&leaf).unwrap().unwrap())])
+            .unwrap();
+
+        assert_eq!(
+            buf_store.end_generation(&root).unwrap(),
+            WriteStats {
+                blocks_written: 3,
+                bytes_written: size(&leaf) + size(&orphan) + size(&root),
+                blocks_flushed: 2,
+                bytes_flushed: size(&leaf) + size(&root),
+            }
+        );
+
+        // Blocks flushed to the base store are new again when re-written to the buffer.
+        buf_store.flush(&root).unwrap();
+        buf_store.begin_generation();
+        buf_store.put_cbor(&(leaf, old), Code::Blake2b256).unwrap();
+        assert_eq!(
+            buf_store.end_generation(&root).unwrap(),
+            WriteStats {
+                blocks_written: 1,
+                bytes_written: size(&root),
+                blocks_flushed: 1,
+                bytes_flushed: size(&root),
+            }
+        );
+        assert_eq!(
+            buf_store.end_generation(&root).unwrap(),
+            WriteStats::default()
+        );
+    }
+
     #[test]
     fn test_flush_vs_flush_all() {
         fn setup(
@@ -443,4 +724,176 @@ mod tests {
             assert_eq!(buf_store.buffer_len(), 0);
         }
     }
+
+    /// A blockstore that fails after accepting a given number of writes.
+    struct FailingBlockstore {
+        inner: MemoryBlockstore,
+        writes_left: std::cell::Cell<usize>,
+    }
+
+    impl Blockstore for FailingBlockstore {
+        fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+            self.inner.get(k)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+            let left = self.writes_left.get();
+            if left == 0 {
+                return Err(anyhow!("write failed"));
+            }
+            self.writes_left.set(left - 1);
+            self.inner.put_keyed(k, block)
+        }
+    }
+
+    #[test]
+    fn failed_flush_can_be_retried() {
+        let base = FailingBlockstore {
+            inner: MemoryBlockstore::default(),
+            writes_left: 1.into(),
+        };
+        let buf_store = BufferedBlockstore::new(&base);
+
+        // A chain of blocks, plus a block linked twice.
+        let shared = buf_store.put_cbor(&"shared", Code::Blake2b256).unwrap();
+        let mut root = buf_store
+            .put_cbor(&(shared, 0u8), Code::Blake2b256)
+            .unwrap();
+        for i in 1..5u8 {
+            root = buf_store
+                .put_cbor(&(root, shared, i), Code::Blake2b256)
+                .unwrap();
+        }
+        let unconnected = buf_store
+            .put_cbor(&"unconnected", Code::Blake2b256)
+            .unwrap();
+        assert_eq!(buf_store.buffer_len(), 7);
+
+        // The first flush fails part way through, but leaves the buffer intact.
+        buf_store.flush(&root).expect_err("flush should fail");
+        assert_eq!(buf_store.buffer_len(), 7);
+
+        // So retrying writes the whole DAG.
+        base.writes_left.set(usize::MAX);
+        buf_store.flush(&root).unwrap();
+        assert_eq!(buf_store.buffer_len(), 1);
+        assert!(base.inner.has(&root).unwrap());
+        assert!(base.inner.has(&shared).unwrap());
+        assert!(!base.inner.has(&unconnected).unwrap());
+
+        // Same for flush_all.
+        base.writes_left.set(0);
+        buf_store.flush_all().expect_err("flush should fail");
+        assert_eq!(buf_store.buffer_len(), 1);
+        base.writes_left.set(usize::MAX);
+        buf_store.flush_all().unwrap();
+        assert_eq!(buf_store.buffer_len(), 0);
+        assert!(base.inner.has(&unconnected).unwrap());
+    }
+
+    /// A thread-safe blockstore that fails to write the given blocks.
+    #[derive(Default)]
+    struct SyncBlockstore {
+        blocks: std::sync::Mutex<HashMap<Cid, Vec<u8>>>,
+        fail: HashSet<Cid>,
+    }
+
+    impl Blockstore for SyncBlockstore {
+        fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+            Ok(self.blocks.lock().unwrap().get(k).cloned())
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+            if self.fail.contains(k) {
+                return Err(anyhow!("failed to write {k}"));
+            }
+            // Give the other workers a chance to interleave their writes.
+            std::thread::yield_now();
+            self.blocks.lock().unwrap().insert(*k, block.to_vec());
+            Ok(())
+        }
+    }
+
+    /// Buffers a tree of blocks, plus an unconnected block, returning the tree's root and all
+    /// its CIDs.
+    fn buffer_tree(buf_store: &BufferedBlockstore<&SyncBlockstore>) -> (Cid, Vec<Cid>) {
+        let mut cids = Vec::new();
+        let mut level: Vec<Cid> = (0..64u32)
+            .map(|i| buf_store.put_cbor(&i, Code::Blake2b256).unwrap())
+            .collect();
+        cids.extend(&level);
+        while level.len() > 1 {
+            level = level
+                .chunks(4)
+                .map(|links| buf_store.put_cbor(&links, Code::Blake2b256).unwrap())
+                .collect();
+            cids.extend(&level);
+        }
+        buf_store
+            .put_cbor(&"unconnected", Code::Blake2b256)
+            .unwrap();
+        (level[0], cids)
+    }
+
+    #[test]
+    fn parallel_flush_is_deterministic() {
+        let n = |n| NonZeroUsize::new(n).unwrap();
+
+        let serial = SyncBlockstore::default();
+        let buf_store = BufferedBlockstore::new(&serial);
+        let (root, cids) = buffer_tree(&buf_store);
+        buf_store.flush(&root).unwrap();
+        assert_eq!(buf_store.buffer_len(), 1);
+        let expected = serial.blocks.into_inner().unwrap();
+        assert_eq!(expected.len(), cids.len());
+
+        for (workers, batch_size) in [(1, 1), (2, 3), (8, 1), (8, 7), (32, 100)] {
+            let base = SyncBlockstore::default();
+            let buf_store = BufferedBlockstore::new(&base);
+            buffer_tree(&buf_store);
+            buf_store
+                .flush_parallel(&root, n(workers), n(batch_size))
+                .unwrap();
+            assert_eq!(buf_store.buffer_len(), 1);
+            assert_eq!(base.blocks.lock().unwrap().clone(), expected);
+
+            buf_store
+                .flush_all_parallel(n(workers), n(batch_size))
+                .unwrap();
+            assert_eq!(buf_store.buffer_len(), 0);
+            assert_eq!(base.blocks.lock().unwrap().len(), expected.len() + 1);
+        }
+    }
+
+    #[test]
+    fn failed_parallel_flush_is_deterministic() {
+        let n = |n| NonZeroUsize::new(n).unwrap();
+        let (root, cids) = buffer_tree(&BufferedBlockstore::new(&SyncBlockstore::default()));
+        // Fail on a leaf and on an inner node.
+        let fail: HashSet<Cid> = [cids[10], cids[70]].into();
+
+        // A serial flush stops at the first failure in walk order.
+        let serial = SyncBlockstore {
+            fail: fail.clone(),
+            ..Default::default()
+        };
+        let buf_store = BufferedBlockstore::new(&serial);
+        buffer_tree(&buf_store);
+        let expected = buf_store.flush(&root).unwrap_err().to_string();
+
+        for (workers, batch_size) in [(1, 1), (2, 3), (8, 1), (8, 7), (32, 100)] {
+            let base = SyncBlockstore {
+                fail: fail.clone(),
+                ..Default::default()
+            };
+            let buf_store = BufferedBlockstore::new(&base);
+            buffer_tree(&buf_store);
+            let err = buf_store
+                .flush_parallel(&root, n(workers), n(batch_size))
+                .unwrap_err();
+            assert_eq!(err.to_string(), expected);
+            // Nothing is removed from the write buffer.
+            assert_eq!(buf_store.buffer_len(), cids.len() + 1);
+        }
+    }
 }
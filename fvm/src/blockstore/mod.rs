@@ -5,5 +5,5 @@
 mod buffered;
 mod discard;
 
-pub use buffered::BufferedBlockstore;
+pub use buffered::{BufferedBlockstore, WriteStats};
 pub(crate) use discard::DiscardBlockstore;
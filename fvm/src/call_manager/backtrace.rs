@@ -6,7 +6,7 @@ use fvm_shared::address::Address;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::{ActorID, MethodNum};
 
-use crate::kernel::SyscallError;
+use crate::kernel::{BlockRegistrySnapshot, SyscallError};
 
 /// A call backtrace records the actors an error was propagated through, from
 /// the moment it was emitted. The original error is the _cause_. Backtraces are
@@ -17,10 +17,17 @@ pub struct Backtrace {
     pub frames: Vec<Frame>,
     /// The last syscall error before the first actor in `frames` aborted.
     pub cause: Option<Cause>,
+    /// The number of frames dropped from the top of the backtrace because it reached the
+    /// configured limit (see
+    /// [`MachineContext::max_backtrace_frames`](crate::machine::MachineContext::max_backtrace_frames)).
+    pub dropped_frames: usize,
 }
 
 impl Display for Backtrace {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.dropped_frames > 0 {
+            writeln!(f, "..: ({} more frames dropped)", self.dropped_frames)?;
+        }
         for (i, frame) in self.frames.iter().rev().enumerate() {
             writeln!(f, "{:02}: {}", i, frame)?;
         }
@@ -41,6 +48,7 @@ impl Backtrace {
     pub fn clear(&mut self) {
         self.cause = None;
         self.frames.clear();
+        self.dropped_frames = 0;
     }
 
     /// Begins a new backtrace. If there is an existing backtrace, this will clear it.
@@ -52,6 +60,7 @@ impl Backtrace {
     pub fn begin(&mut self, cause: Cause) {
         self.cause = Some(cause);
         self.frames.clear();
+        self.dropped_frames = 0;
     }
 
     /// Sets the cause of a backtrace.
@@ -69,6 +78,16 @@ impl Backtrace {
     pub fn push_frame(&mut self, frame: Frame) {
         self.frames.push(frame)
     }
+
+    /// Push a "frame" onto the backtrace, unless it already has `max_frames` frames. Dropped frames
+    /// are counted in `dropped_frames`.
+    pub fn push_frame_bounded(&mut self, frame: Frame, max_frames: usize) {
+        if self.frames.len() < max_frames {
+            self.frames.push(frame)
+        } else {
+            self.dropped_frames += 1;
+        }
+    }
 }
 
 /// A "frame" in a call backtrace.
@@ -97,6 +116,37 @@ impl Display for Frame {
     }
 }
 
+/// Diagnostic context attached to a fatal error as it propagates out of an actor invocation.
+///
+/// Every invocation on the call stack at the failure point adds one of these to the error's
+/// context chain (innermost first), so the rendered error includes the call stack along with each
+/// invocation's block registry. The outermost can be retrieved from the error with
+/// [`anyhow::Error::downcast_ref`].
+#[derive(Clone, Debug)]
+pub struct InvocationSnapshot {
+    /// The invoked actor.
+    pub actor: ActorID,
+    /// The invoked entrypoint (e.g., the method).
+    pub entrypoint: String,
+    /// The depth of the invocation in the call stack.
+    pub call_depth: u32,
+    /// The state of the invocation's block registry when the fatal error was returned.
+    pub blocks: BlockRegistrySnapshot,
+}
+
+impl Display for InvocationSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "in {}::{} at call depth {} (block registry: {})",
+            Address::new_id(self.actor),
+            self.entrypoint,
+            self.call_depth,
+            self.blocks,
+        )
+    }
+}
+
 /// The ultimate "cause" of a failed message.
 #[derive(Clone, Debug)]
 pub enum Cause {
@@ -5,13 +5,13 @@ use std::rc::Rc;
 use anyhow::{Context, anyhow};
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
-use fvm_ipld_amt::Amt;
 use fvm_ipld_encoding::{CBOR, to_vec};
 use fvm_shared::address::{Address, Payload};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::event::StampedEvent;
 use fvm_shared::sys::BlockId;
+use fvm_shared::version::NetworkVersion;
 use fvm_shared::{ActorID, METHOD_SEND};
 use num_traits::Zero;
 
@@ -19,21 +19,26 @@ use super::state_access_tracker::{ActorAccessState, StateAccessTracker};
 use super::{Backtrace, CallManager, Entrypoint, InvocationResult, NO_DATA_BLOCK_ID};
 use crate::blockstore::DiscardBlockstore;
 use crate::call_manager::FinishRet;
-use crate::call_manager::backtrace::Frame;
+use crate::call_manager::backtrace::{Frame, InvocationSnapshot};
 use crate::eam_actor::EAM_ACTOR_ID;
 use crate::engine::Engine;
 use crate::gas::{Gas, GasTracker};
 use crate::kernel::{
-    Block, BlockRegistry, ClassifyResult, ExecutionError, Kernel, Result, SyscallError,
+    Block, BlockRegistry, BlsPubKeyCache, ClassifyResult, ExecutionError, Kernel, Result,
+    SyscallError,
 };
-use crate::machine::Machine;
 use crate::machine::limiter::MemoryLimiter;
+use crate::machine::{Machine, build_events_root};
 use crate::state_tree::ActorState;
 use crate::syscalls::error::Abort;
 use crate::syscalls::{charge_for_exec, update_gas_available};
 use crate::trace::{ExecutionEvent, ExecutionTrace};
 use crate::{syscall_error, system_actor};
 
+/// The maximum number of bytes of each block to include in the diagnostics of a fatal error, when
+/// actor debugging is enabled.
+const BLOCK_PREVIEW_LEN: usize = 32;
+
 /// The default [`CallManager`] implementation.
 #[repr(transparent)]
 pub struct DefaultCallManager<M: Machine>(Option<Box<InnerDefaultCallManager<M>>>);
@@ -71,12 +76,20 @@ pub struct InnerDefaultCallManager<M: Machine> {
     exec_trace: ExecutionTrace,
     /// Number of actors that have been invoked in this message execution.
     invocation_count: u64,
+    /// Number of sends (internal calls) made in this message execution, including failed ones.
+    send_count: u64,
+    /// Total size of the blocks created in this message execution, including reverted ones.
+    block_bytes: u64,
     /// Limits on memory throughout the execution.
     limits: M::Limiter,
     /// Accumulator for events emitted in this call stack.
     events: EventsAccumulator,
+    /// Accumulator for the actors created and deleted in this call stack.
+    lifecycle: LifecycleAccumulator,
     /// The actor call stack (ActorID and entrypoint name tuple).
     actor_call_stack: Vec<(ActorID, &'static str)>,
+    /// The BLS public keys validated in this message.
+    bls_pub_keys: BlsPubKeyCache,
 }
 
 #[doc(hidden)]
@@ -114,7 +127,10 @@ where
     ) -> Self {
         let limits = machine.new_limiter();
         let gas_tracker =
-            GasTracker::new(Gas::new(gas_limit), Gas::zero(), machine.context().tracing);
+            GasTracker::new(Gas::new(gas_limit), Gas::zero(), machine.context().tracing)
+                .with_proof_gas_limit(machine.context().network.proof_gas_limit);
+        #[cfg(feature = "metrics")]
+        let gas_tracker = gas_tracker.with_metrics(machine.context().metrics);
 
         let state_access_tracker =
             StateAccessTracker::new(&machine.context().price_list.preloaded_actors);
@@ -145,6 +161,17 @@ where
         // cannot be triggered by an actor on-chain, so it's not a concern (for now).
         state_access_tracker.record_lookup_address(&receiver_address);
 
+        let mut exec_trace = ExecutionTrace::new();
+        if machine.context().tracing {
+            exec_trace.push(ExecutionEvent::NetworkContext {
+                epoch: machine.context().epoch,
+                timestamp: machine.context().timestamp,
+            });
+        }
+
+        let bls_pub_keys =
+            BlsPubKeyCache::new(machine.context().price_list.bls_pub_key_cache_size());
+
         DefaultCallManager(Some(Box::new(InnerDefaultCallManager {
             engine: Rc::new(engine),
             machine,
@@ -156,15 +183,34 @@ where
             num_actors_created: 0,
             call_stack_depth: 0,
             backtrace: Backtrace::default(),
-            exec_trace: vec![],
+            exec_trace,
             invocation_count: 0,
+            send_count: 0,
+            block_bytes: 0,
             limits,
             events: Default::default(),
+            lifecycle: Default::default(),
             state_access_tracker,
             actor_call_stack: vec![],
+            bls_pub_keys,
         })))
     }
 
+    fn record_block_create(&mut self, size: usize) -> Result<()> {
+        let block_bytes = self.block_bytes.saturating_add(size as u64);
+        if let Some(max) = self.machine.context().max_block_bytes_per_message
+            && block_bytes > max
+        {
+            return Err(syscall_error!(
+                LimitExceeded;
+                "message execution exceeds the limit of {max} bytes of created blocks"
+            )
+            .into());
+        }
+        self.block_bytes = block_bytes;
+        Ok(())
+    }
+
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter {
         &mut self.limits
     }
@@ -182,6 +228,21 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
+        // Every call but the message's own is a send. From nv29, a message's sends are limited.
+        if self.call_stack_depth > 0
+            && self.machine.context().network_version >= NetworkVersion::V29
+        {
+            let max_sends = self.machine.context().max_sends_per_message;
+            if self.send_count >= max_sends {
+                return Err(syscall_error!(
+                    LimitExceeded;
+                    "message execution exceeds the limit of {max_sends} sends"
+                )
+                .into());
+            }
+            self.send_count += 1;
+        }
+
         if self.machine.context().tracing
             && let Entrypoint::Invoke(method) = &entrypoint
         {
@@ -248,15 +309,27 @@ where
     ) -> Result<InvocationResult> {
         self.state_tree_mut().begin_transaction();
         self.events.begin_transaction();
+        self.lifecycle.begin_transaction();
         self.state_access_tracker.begin_transaction();
+        let trace_start = self.exec_trace.len();
 
         let (revert, res) = match f(self) {
             Ok(v) => (!v.exit_code.is_success(), Ok(v)),
             Err(e) => (true, Err(e)),
         };
 
+        // Keep the state roots set by the reverted calls in the trace, but mark them as such.
+        if revert {
+            for event in &mut self.exec_trace[trace_start..] {
+                if let ExecutionEvent::SetRoot { reverted, .. } = event {
+                    *reverted = true;
+                }
+            }
+        }
+
         self.state_tree_mut().end_transaction(revert)?;
         self.events.end_transaction(revert)?;
+        self.lifecycle.end_transaction(revert)?;
         self.state_access_tracker.end_transaction(revert)?;
 
         res
@@ -269,16 +342,35 @@ where
             gas_tracker,
             mut exec_trace,
             events,
+            lifecycle,
             ..
         } = *self.0.take().expect("call manager is poisoned");
 
         let gas_used = gas_tracker.gas_used().round_up();
+        let proof_gas_used = gas_tracker.proof_gas_used().round_up();
 
         // Finalize any trace events, if we're tracing.
         if machine.context().tracing {
-            exec_trace.extend(gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
+            let max_events = machine.context().max_trace_events;
+            for charge in gas_tracker.drain_trace() {
+                push_trace_event(
+                    &mut exec_trace,
+                    max_events,
+                    ExecutionEvent::GasCharge(charge),
+                );
+            }
         }
 
+        // Record the message's timings.
+        #[cfg(feature = "metrics")]
+        let machine = {
+            let mut machine = machine;
+            if let Some(metrics) = machine.metrics_mut() {
+                metrics.merge(&gas_tracker.take_metrics());
+            }
+            machine
+        };
+
         let res = events.finish();
         let Events {
             events,
@@ -287,14 +379,21 @@ where
             Ok(events) => events,
             Err(err) => return (Err(err), machine),
         };
+        let (created_actors, deleted_actors) = match lifecycle.finish() {
+            Ok(actors) => actors,
+            Err(err) => return (Err(err), machine),
+        };
 
         (
             Ok(FinishRet {
                 gas_used,
+                proof_gas_used,
                 backtrace,
                 exec_trace,
                 events,
                 events_root,
+                created_actors,
+                deleted_actors,
             }),
             machine,
         )
@@ -402,10 +501,22 @@ where
                 // We charge for creating the actor (storage) but not for address assignment as the
                 // init actor has already handled that for us.
                 self.charge_gas(self.price_list().on_create_actor(false))?;
+                // The init actor assigns the next actor address to the actor it's creating.
+                let address = self.next_actor_address();
+                self.lifecycle.record_created(actor_id, address);
                 ActorState::new_empty(code_id, delegated_address)
             }
         };
         self.set_actor(actor_id, actor)?;
+        if self.machine.context().tracing {
+            self.trace(ExecutionEvent::CreateActor {
+                id: actor_id,
+                code: code_id,
+                delegated_address,
+                actors_created: self.num_actors_created,
+            });
+        }
+        // Like Lotus, this isn't reverted if the call creating the actor fails.
         self.num_actors_created += 1;
         Ok(())
     }
@@ -419,6 +530,10 @@ where
         self.invocation_count
     }
 
+    fn bls_pub_keys(&self) -> &BlsPubKeyCache {
+        &self.bls_pub_keys
+    }
+
     /// Resolve an address and charge for it.
     fn resolve_address(&self, address: &Address) -> Result<Option<ActorID>> {
         if let Ok(id) = address.id() {
@@ -473,6 +588,7 @@ where
         }
         self.state_tree_mut().delete_actor(id);
         self.state_access_tracker.record_actor_update(id);
+        self.lifecycle.record_deleted(id);
         Ok(())
     }
 
@@ -521,6 +637,16 @@ where
     fn trace_ipld(&mut self, op: crate::trace::IpldOperation, cid: Cid, size: usize) {
         self.trace(ExecutionEvent::Ipld { op, cid, size })
     }
+
+    fn trace_set_root(&mut self, id: ActorID, root: Cid) {
+        if self.machine.context().tracing {
+            self.trace(ExecutionEvent::SetRoot {
+                id,
+                root,
+                reverted: false,
+            })
+        }
+    }
 }
 
 impl<M> DefaultCallManager<M>
@@ -532,10 +658,15 @@ where
         // fine.
         let s = &mut **self;
 
-        s.exec_trace
-            .extend(s.gas_tracker.drain_trace().map(ExecutionEvent::GasCharge));
-
-        s.exec_trace.push(trace);
+        let max_events = s.machine.context().max_trace_events;
+        for charge in s.gas_tracker.drain_trace() {
+            push_trace_event(
+                &mut s.exec_trace,
+                max_events,
+                ExecutionEvent::GasCharge(charge),
+            );
+        }
+        push_trace_event(&mut s.exec_trace, max_events, trace);
     }
 
     /// Helper method to create an uninitialized actor due to a send.
@@ -545,6 +676,7 @@ where
         self.charge_gas(self.price_list().on_create_actor(true))?;
         let addr_id = self.state_tree_mut().register_new_address(addr)?;
         self.state_access_tracker.record_lookup_address(addr);
+        self.lifecycle.record_created(addr_id, *addr);
 
         // Now we actually set the actor state, charging for reads/writes as necessary and recording
         // the fact that the actor has been updated.
@@ -566,10 +698,18 @@ where
             );
         }
 
+        // Don't create an account actor that can't be constructed.
+        let code_cid = *self.builtin_actors().get_account_code();
+        if self.machine.context().is_code_denied(&code_cid) {
+            return Err(
+                syscall_error!(Forbidden; "cannot create account {}: account code is denied", addr)
+                    .into(),
+            );
+        }
+
         // Create the actor in the state tree.
         let id = {
-            let code_cid = self.builtin_actors().get_account_code();
-            let state = ActorState::new_empty(*code_cid, None);
+            let state = ActorState::new_empty(code_cid, None);
             self.create_actor_from_send(addr, state)?
         };
 
@@ -619,6 +759,22 @@ where
     where
         K: Kernel<CallManager = Self>,
     {
+        // From nv29, refuse oversized parameters before resolving (or creating) the receiver.
+        // Actors can't create such blocks, but top-level messages can carry them, and they'd
+        // otherwise be copied into the receiving actor.
+        if let Some(params) = &params {
+            let max_block_size = self.machine.context().max_block_size;
+            let size = params.data().len();
+            if size > max_block_size
+                && self.machine.context().network_version >= NetworkVersion::V29
+            {
+                return Err(syscall_error!(LimitExceeded;
+                    "parameters of {} bytes exceed the maximum block size of {} bytes",
+                    size, max_block_size)
+                .into());
+            }
+        }
+
         // Get the receiver; this will resolve the address.
         let to = match self.resolve_address(&to)? {
             Some(addr) => addr,
@@ -638,6 +794,11 @@ where
                     }
                     self.create_placeholder_actor_from_send(&to)?
                 }
+                // We only auto-create placeholders for addresses in the EAM's namespace.
+                Payload::Delegated(da) => return Err(
+                    syscall_error!(NotFound; "actor does not exist and cannot be created: {} (namespace {} is not managed by the EAM)", to, da.namespace())
+                        .into(),
+                ),
                 _ => return Err(
                     syscall_error!(NotFound; "actor does not exist or cannot be created: {}", to)
                         .into(),
@@ -706,6 +867,40 @@ where
                 .on_method_invocation(param_size, param_link_count),
         )?;
 
+        // Refuse to invoke denied code. We check this after charging for the invocation, but before
+        // loading the code, so that the outcome doesn't depend on the code itself.
+        if self.machine.context().is_code_denied(&state.code) {
+            log::debug!(
+                "refusing to call {} -> {}: code {} is denied",
+                from,
+                to,
+                &state.code
+            );
+            if self.machine.context().tracing {
+                self.trace(ExecutionEvent::CodeDenied {
+                    id: to,
+                    code: state.code,
+                });
+            }
+            if let &Entrypoint::Invoke(method) = &entrypoint {
+                let max_frames = self.machine.context().max_backtrace_frames;
+                self.backtrace.push_frame_bounded(
+                    Frame {
+                        source: to,
+                        method,
+                        message: format!("actor code {} is denied", state.code),
+                        code: ExitCode::SYS_DENIED_CODE,
+                    },
+                    max_frames,
+                );
+            }
+            t.stop();
+            return Ok(InvocationResult {
+                exit_code: ExitCode::SYS_DENIED_CODE,
+                value: None,
+            });
+        }
+
         // Store the parametrs, and initialize the block registry for the target actor.
         let mut block_registry = BlockRegistry::new();
         let params_id = if let Some(blk) = params {
@@ -859,11 +1054,21 @@ where
                             "out of gas".to_owned(),
                             Err(ExecutionError::OutOfGas),
                         ),
-                        Abort::Fatal(err) => (
-                            ExitCode::SYS_ASSERTION_FAILED,
-                            "fatal error".to_owned(),
-                            Err(ExecutionError::Fatal(err)),
-                        ),
+                        Abort::Fatal(err) => {
+                            let snapshot = InvocationSnapshot {
+                                actor: to,
+                                entrypoint: entrypoint.to_string(),
+                                call_depth: cm.call_stack_depth,
+                                blocks: block_registry.snapshot(
+                                    cm.context().actor_debugging.then_some(BLOCK_PREVIEW_LEN),
+                                ),
+                            };
+                            (
+                                ExitCode::SYS_ASSERTION_FAILED,
+                                "fatal error".to_owned(),
+                                Err(ExecutionError::Fatal(err.context(snapshot))),
+                            )
+                        }
                     };
 
                     if !code.is_success() {
@@ -875,12 +1080,16 @@ where
                                 cm.backtrace.begin(err);
                             }
 
-                            cm.backtrace.push_frame(Frame {
-                                source: to,
-                                method,
-                                message,
-                                code,
-                            });
+                            let max_frames = cm.machine.context().max_backtrace_frames;
+                            cm.backtrace.push_frame_bounded(
+                                Frame {
+                                    source: to,
+                                    method,
+                                    message,
+                                    code,
+                                },
+                                max_frames,
+                            );
                         }
                     }
 
@@ -959,6 +1168,20 @@ where
     }
 }
 
+/// Appends an event to the execution trace, unless the trace already has `max_events` events. The
+/// first dropped event is replaced by an [`ExecutionEvent::TraceTruncated`] marker, which counts the
+/// dropped events.
+fn push_trace_event(trace: &mut ExecutionTrace, max_events: usize, event: ExecutionEvent) {
+    if trace.len() < max_events {
+        trace.push(event);
+        return;
+    }
+    match trace.last_mut() {
+        Some(ExecutionEvent::TraceTruncated { dropped }) => *dropped += 1,
+        _ => trace.push(ExecutionEvent::TraceTruncated { dropped: 1 }),
+    }
+}
+
 /// Stores events in layers as they are emitted by actors. As the call stack progresses, when an
 /// actor exits normally, its events should be merged onto the previous layer (merge_last_layer).
 /// If an actor aborts, the last layer should be discarded (discard_last_layer). This will also
@@ -1013,19 +1236,7 @@ impl EventsAccumulator {
             )));
         }
 
-        let root = if !self.events.is_empty() {
-            const EVENTS_AMT_BITWIDTH: u32 = 5;
-            let root = Amt::new_from_iter_with_bit_width(
-                DiscardBlockstore,
-                EVENTS_AMT_BITWIDTH,
-                self.events.iter(),
-            )
-            .context("failed to construct events AMT")
-            .or_fatal()?;
-            Some(root)
-        } else {
-            None
-        };
+        let root = build_events_root(&DiscardBlockstore, &self.events).or_fatal()?;
 
         Ok(Events {
             root,
@@ -1033,3 +1244,68 @@ impl EventsAccumulator {
         })
     }
 }
+
+/// Records the actors created and deleted as the call stack progresses, in layers like the
+/// [`EventsAccumulator`], so that changes made by reverted calls are discarded.
+#[derive(Default)]
+struct LifecycleAccumulator {
+    changes: Vec<LifecycleChange>,
+    idxs: Vec<usize>,
+}
+
+enum LifecycleChange {
+    Created(ActorID, Address),
+    Deleted(ActorID),
+}
+
+impl LifecycleAccumulator {
+    fn record_created(&mut self, id: ActorID, address: Address) {
+        self.changes.push(LifecycleChange::Created(id, address))
+    }
+
+    fn record_deleted(&mut self, id: ActorID) {
+        self.changes.push(LifecycleChange::Deleted(id))
+    }
+
+    fn begin_transaction(&mut self) {
+        self.idxs.push(self.changes.len());
+    }
+
+    fn end_transaction(&mut self, revert: bool) -> Result<()> {
+        let idx = self.idxs.pop().ok_or_else(|| {
+            ExecutionError::Fatal(anyhow!(
+                "no index in the lifecycle accumulator when ending a transaction"
+            ))
+        })?;
+        if revert {
+            self.changes.truncate(idx);
+        }
+        Ok(())
+    }
+
+    /// Returns the created and deleted actors. Actors both created and deleted are left out.
+    fn finish(self) -> Result<(Vec<(ActorID, Address)>, Vec<ActorID>)> {
+        if !self.idxs.is_empty() {
+            return Err(ExecutionError::Fatal(anyhow!(
+                "bad lifecycle accumulator state; expected layer indices to be empty, had {} items",
+                self.idxs.len()
+            )));
+        }
+
+        let mut created: Vec<(ActorID, Address)> = Vec::new();
+        let mut deleted = Vec::new();
+        for change in self.changes {
+            match change {
+                LifecycleChange::Created(id, address) => created.push((id, address)),
+                LifecycleChange::Deleted(id) => {
+                    if let Some(pos) = created.iter().position(|&(c, _)| c == id) {
+                        created.remove(pos);
+                    } else if !deleted.contains(&id) {
+                        deleted.push(id);
+                    }
+                }
+            }
+        }
+        Ok((created, deleted))
+    }
+}
@@ -11,7 +11,7 @@ use fvm_shared::{ActorID, METHOD_CONSTRUCTOR, MethodNum};
 use crate::Kernel;
 use crate::engine::Engine;
 use crate::gas::{Gas, GasCharge, GasTimer, GasTracker, PriceList};
-use crate::kernel::{self, BlockRegistry, ClassifyResult, Context, Result};
+use crate::kernel::{self, BlockRegistry, BlsPubKeyCache, ClassifyResult, Context, Result};
 use crate::machine::{Machine, MachineContext};
 use crate::state_tree::ActorState;
 
@@ -137,14 +137,22 @@ pub trait CallManager: 'static {
     fn delete_actor(&mut self, id: ActorID) -> Result<()>;
 
     /// Transfers tokens from one actor to another, charging gas as appropriate.
+    ///
+    /// Fails with [`InsufficientFunds`](fvm_shared::error::ErrorNumber::InsufficientFunds) if the
+    /// sender can't cover the value, before touching either actor. Transfers from an actor to itself
+    /// are checked the same way, but otherwise don't change anything.
     fn transfer(&mut self, from: ActorID, to: ActorID, value: &TokenAmount) -> Result<()>;
 
-    /// Getter for message nonce.
+    /// Getter for the nonce of the top-level message sent by the origin. This is constant across
+    /// all nested calls made while executing the message.
     fn nonce(&self) -> u64;
 
     /// Gets the total invocations done on this call stack.
     fn invocation_count(&self) -> u64;
 
+    /// Returns the BLS public keys validated so far in this message.
+    fn bls_pub_keys(&self) -> &BlsPubKeyCache;
+
     /// Returns the current price list.
     fn price_list(&self) -> &PriceList {
         self.machine().context().price_list
@@ -170,6 +178,11 @@ pub trait CallManager: 'static {
         self.gas_tracker().apply_charge(charge)
     }
 
+    /// Records the creation of a block of the given size, failing with
+    /// [`LimitExceeded`](fvm_shared::error::ErrorNumber::LimitExceeded) (without recording it) if it
+    /// would exceed [`NetworkConfig::max_block_bytes_per_message`](crate::machine::NetworkConfig::max_block_bytes_per_message).
+    fn record_block_create(&mut self, size: usize) -> Result<()>;
+
     /// Limit memory usage throughout a message execution.
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter;
 
@@ -180,6 +193,9 @@ pub trait CallManager: 'static {
     fn log(&mut self, msg: String);
 
     fn trace_ipld(&mut self, op: IpldOperation, cid: Cid, size: usize);
+
+    /// Records that an actor set its state root (when tracing).
+    fn trace_set_root(&mut self, id: ActorID, root: Cid);
 }
 
 /// The result of calling actor's entrypoint
@@ -203,10 +219,17 @@ impl Default for InvocationResult {
 /// The returned values upon finishing a call manager.
 pub struct FinishRet {
     pub gas_used: u64,
+    /// The gas used verifying proofs, included in `gas_used`.
+    pub proof_gas_used: u64,
     pub backtrace: Backtrace,
     pub exec_trace: ExecutionTrace,
     pub events: Vec<StampedEvent>,
     pub events_root: Option<Cid>,
+    /// The actors created (and not deleted) by the message, in order of creation, along with the
+    /// addresses they were created for.
+    pub created_actors: Vec<(ActorID, Address)>,
+    /// The pre-existing actors deleted by the message, in order of deletion.
+    pub deleted_actors: Vec<ActorID>,
 }
 
 #[derive(Clone, Debug, Copy)]
@@ -1,9 +1,14 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashMap;
 use std::sync::{Condvar, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
 
 /// An engine concurrency manages the concurrency available for a single engine. It's basically a
 /// semaphore that also assigns IDs to new engines.
+///
+/// Engines are handed out fairly: callers are admitted in the order in which they started waiting.
 pub(super) struct EngineConcurrency {
     inner: Mutex<EngineConcurrencyInner>,
     condv: Condvar,
@@ -12,6 +17,72 @@ pub(super) struct EngineConcurrency {
 struct EngineConcurrencyInner {
     next_id: u64,
     limit: u32,
+    /// The configured concurrency limit.
+    max: u32,
+    /// The next ticket to hand out to a waiting caller.
+    next_ticket: u64,
+    /// The ticket of the next caller to be admitted.
+    now_serving: u64,
+    /// The thread each engine is executing on, by permit ID. Engines may move between threads, so
+    /// they're only attributed to a thread while in use (see [`EngineConcurrency::enter`]).
+    active: HashMap<u64, ThreadId>,
+    /// The total time callers have spent waiting for an engine.
+    total_wait: Duration,
+}
+
+/// An acquired engine ID, which must be passed back to [`EngineConcurrency::release`].
+pub(super) struct Permit {
+    pub id: u64,
+}
+
+/// Returned when an engine can't be acquired without waiting.
+#[derive(thiserror::Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AcquireError {
+    /// All engines are in use (or other callers are already waiting for one).
+    #[error("all engines are in use")]
+    WouldBlock,
+    /// All engines are in use (or other callers are already waiting for one), and the current
+    /// thread is executing with one. Waiting could deadlock, as that engine can't be released
+    /// until this call returns (e.g., when called from an extern).
+    #[error("all engines are in use, and the current thread is already executing with one")]
+    Reentrant,
+}
+
+/// A snapshot of an [`EnginePool`](super::EnginePool)'s occupancy.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EnginePoolStats {
+    /// The maximum number of engines that may be in use at once.
+    pub limit: u32,
+    /// The number of engines currently in use.
+    pub in_use: u32,
+    /// The number of callers currently waiting for an engine.
+    pub waiting: u64,
+    /// The total time callers have spent waiting for an engine.
+    pub total_wait: Duration,
+}
+
+impl EngineConcurrencyInner {
+    fn admit(&mut self) -> Permit {
+        let id = self.next_id;
+
+        self.limit -= 1;
+        self.next_id += 1;
+
+        Permit { id }
+    }
+
+    /// Returns true if a new caller would have to wait to be admitted.
+    fn would_block(&self) -> bool {
+        self.limit == 0 || self.now_serving != self.next_ticket
+    }
+
+    fn check_reentrant(&self) -> Result<(), AcquireError> {
+        let thread = thread::current().id();
+        if self.would_block() && self.active.values().any(|&t| t == thread) {
+            return Err(AcquireError::Reentrant);
+        }
+        Ok(())
+    }
 }
 
 impl EngineConcurrency {
@@ -20,56 +91,269 @@ impl EngineConcurrency {
             inner: Mutex::new(EngineConcurrencyInner {
                 next_id: 0,
                 limit: concurrency,
+                max: concurrency,
+                next_ticket: 0,
+                now_serving: 0,
+                active: HashMap::new(),
+                total_wait: Duration::ZERO,
             }),
             condv: Condvar::new(),
         }
     }
 
     /// Acquire a new engine (well, an engine ID). This function blocks until we're below the
-    /// maximum engine concurrency limit.
-    pub fn acquire(&self) -> u64 {
+    /// maximum engine concurrency limit and all callers that started waiting before us have been
+    /// admitted.
+    ///
+    /// Fails with [`AcquireError::Reentrant`] (instead of potentially deadlocking) if we'd have to
+    /// wait and the current thread is executing with an engine. Nothing else fails.
+    pub fn acquire(&self) -> Result<Permit, AcquireError> {
+        let start = Instant::now();
+        let mut guard = self.inner.lock().unwrap();
+        guard.check_reentrant()?;
+
+        let ticket = guard.next_ticket;
+        guard.next_ticket += 1;
         let mut guard = self
             .condv
-            .wait_while(self.inner.lock().unwrap(), |inner| inner.limit == 0)
+            .wait_while(guard, |inner| {
+                inner.now_serving != ticket || inner.limit == 0
+            })
             .unwrap();
-        let id = guard.next_id;
 
-        guard.limit -= 1;
-        guard.next_id += 1;
+        guard.now_serving += 1;
+        guard.total_wait += start.elapsed();
+        let permit = guard.admit();
+
+        // Let the next caller in line check whether it can be admitted.
+        self.condv.notify_all();
+        Ok(permit)
+    }
+
+    /// Acquire a new engine ID without waiting. Fails if all engines are in use, or if other
+    /// callers are already waiting.
+    pub fn try_acquire(&self) -> Result<Permit, AcquireError> {
+        let mut guard = self.inner.lock().unwrap();
+        guard.check_reentrant()?;
+        if guard.would_block() {
+            return Err(AcquireError::WouldBlock);
+        }
+        guard.next_ticket += 1;
+        guard.now_serving += 1;
+        Ok(guard.admit())
+    }
+
+    /// Marks the engine as executing on the current thread, until [`exit`](Self::exit) (or
+    /// [`release`](Self::release)) is called.
+    pub fn enter(&self, permit: &Permit) {
+        let mut guard = self.inner.lock().unwrap();
+        guard.active.insert(permit.id, thread::current().id());
+    }
 
-        id
+    /// Marks the engine with the given ID as no longer executing.
+    pub fn exit(&self, id: u64) {
+        let mut guard = self.inner.lock().unwrap();
+        guard.active.remove(&id);
     }
 
     /// Release the engine. After this is called, the caller should not allocate any more instances
     /// or continue to use their engine ID.
-    pub fn release(&self) {
+    pub fn release(&self, permit: &Permit) {
         let mut guard = self.inner.lock().unwrap();
         guard.limit += 1;
-        self.condv.notify_one();
+        guard.active.remove(&permit.id);
+        self.condv.notify_all();
+    }
+
+    /// Returns a snapshot of the current occupancy.
+    pub fn stats(&self) -> EnginePoolStats {
+        let guard = self.inner.lock().unwrap();
+        EnginePoolStats {
+            limit: guard.max,
+            in_use: guard.max - guard.limit,
+            waiting: guard.next_ticket - guard.now_serving,
+            total_wait: guard.total_wait,
+        }
     }
 }
 
-#[test]
-fn test_engine_concurrency() {
-    let concurrency = EngineConcurrency::new(2);
-    std::thread::scope(|scope| {
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 2);
-        assert_eq!(concurrency.acquire(), 0);
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 1);
-        assert_eq!(concurrency.acquire(), 1);
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 0);
-        let threads: Vec<_> = std::iter::repeat_with(|| scope.spawn(|| concurrency.acquire()))
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Waits until the given number of callers are waiting for an engine.
+    fn wait_for_waiters(concurrency: &EngineConcurrency, waiting: u64) {
+        while concurrency.stats().waiting < waiting {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_engine_concurrency() {
+        let concurrency = EngineConcurrency::new(2);
+        std::thread::scope(|scope| {
+            assert_eq!(concurrency.inner.lock().unwrap().limit, 2);
+            let p0 = concurrency.acquire().unwrap();
+            assert_eq!(p0.id, 0);
+            assert_eq!(concurrency.inner.lock().unwrap().limit, 1);
+            let p1 = concurrency.acquire().unwrap();
+            assert_eq!(p1.id, 1);
+            assert_eq!(concurrency.inner.lock().unwrap().limit, 0);
+            let threads: Vec<_> = std::iter::repeat_with(|| {
+                scope.spawn(|| {
+                    let permit = concurrency.acquire().unwrap();
+                    concurrency.release(&permit);
+                    permit.id
+                })
+            })
             .take(10)
             .collect();
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 0);
-        for _ in &threads {
-            concurrency.release();
-        }
-        let mut ids: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
-        ids.sort();
-        assert_eq!(ids, (2..12).collect::<Vec<_>>());
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 0);
-        concurrency.release();
-        assert_eq!(concurrency.inner.lock().unwrap().limit, 1);
-    });
+            wait_for_waiters(&concurrency, 10);
+            assert_eq!(concurrency.inner.lock().unwrap().limit, 0);
+            concurrency.release(&p0);
+            concurrency.release(&p1);
+            let mut ids: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+            ids.sort();
+            assert_eq!(ids, (2..12).collect::<Vec<_>>());
+            assert_eq!(concurrency.inner.lock().unwrap().limit, 2);
+            assert!(concurrency.inner.lock().unwrap().active.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_try_acquire() {
+        let concurrency = EngineConcurrency::new(1);
+        std::thread::scope(|scope| {
+            let permit = scope
+                .spawn(|| concurrency.try_acquire().unwrap())
+                .join()
+                .unwrap();
+            assert_eq!(
+                concurrency.try_acquire().err(),
+                Some(AcquireError::WouldBlock)
+            );
+
+            // Once someone is waiting, a released engine goes to them, not to try_acquire.
+            let waiter = scope.spawn(|| concurrency.acquire().unwrap());
+            wait_for_waiters(&concurrency, 1);
+            concurrency.release(&permit);
+            assert_eq!(
+                concurrency.try_acquire().err(),
+                Some(AcquireError::WouldBlock)
+            );
+            let permit = waiter.join().unwrap();
+            assert_eq!(permit.id, 1);
+
+            concurrency.release(&permit);
+            assert_eq!(concurrency.try_acquire().unwrap().id, 2);
+        });
+    }
+
+    #[test]
+    fn test_reentrant() {
+        let concurrency = EngineConcurrency::new(2);
+        let p0 = concurrency.acquire().unwrap();
+        concurrency.enter(&p0);
+
+        // A free engine may be acquired even if this thread is executing with one.
+        let p1 = concurrency.acquire().unwrap();
+
+        // But waiting for another engine would deadlock, as this thread can't release its engine
+        // until the call returns.
+        assert_eq!(concurrency.acquire().err(), Some(AcquireError::Reentrant));
+        assert_eq!(
+            concurrency.try_acquire().err(),
+            Some(AcquireError::Reentrant)
+        );
+
+        // Other threads just wait.
+        std::thread::scope(|scope| {
+            let other = scope.spawn(|| concurrency.acquire().unwrap());
+            wait_for_waiters(&concurrency, 1);
+            concurrency.release(&p0);
+            concurrency.release(&other.join().unwrap());
+        });
+        concurrency.release(&p1);
+
+        let stats = concurrency.stats();
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.waiting, 0);
+    }
+
+    #[test]
+    fn test_reentrant_follows_engine() {
+        let concurrency = EngineConcurrency::new(1);
+
+        // Holding an engine that isn't executing isn't re-entrant: it can be released while we
+        // wait (e.g., by the thread it was handed to).
+        let permit = concurrency.acquire().unwrap();
+        assert_eq!(
+            concurrency.try_acquire().err(),
+            Some(AcquireError::WouldBlock)
+        );
+
+        std::thread::scope(|scope| {
+            // The engine executes on another thread, which may not wait for another.
+            scope
+                .spawn(|| {
+                    concurrency.enter(&permit);
+                    assert_eq!(concurrency.acquire().err(), Some(AcquireError::Reentrant));
+                    concurrency.exit(permit.id);
+                })
+                .join()
+                .unwrap();
+
+            // While the thread that acquired it may.
+            assert_eq!(
+                concurrency.try_acquire().err(),
+                Some(AcquireError::WouldBlock)
+            );
+            let waiter = scope.spawn(|| concurrency.acquire().unwrap());
+            wait_for_waiters(&concurrency, 1);
+            concurrency.release(&permit);
+            concurrency.release(&waiter.join().unwrap());
+        });
+
+        let stats = concurrency.stats();
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.waiting, 0);
+    }
+
+    #[test]
+    fn test_bounded_occupancy() {
+        const LIMIT: u32 = 3;
+        const THREADS: usize = 16;
+        const ROUNDS: usize = 20;
+
+        let concurrency = EngineConcurrency::new(LIMIT);
+        let occupancy = AtomicU32::new(0);
+        let max_occupancy = AtomicU32::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..ROUNDS {
+                        let permit = concurrency.acquire().unwrap();
+                        let current = occupancy.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_occupancy.fetch_max(current, Ordering::SeqCst);
+                        assert!(concurrency.stats().in_use <= LIMIT);
+                        std::thread::sleep(Duration::from_micros(100));
+                        occupancy.fetch_sub(1, Ordering::SeqCst);
+                        concurrency.release(&permit);
+                    }
+                });
+            }
+        });
+
+        assert!(max_occupancy.load(Ordering::SeqCst) <= LIMIT);
+        let stats = concurrency.stats();
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.waiting, 0);
+        assert_eq!(
+            concurrency.inner.lock().unwrap().next_id,
+            (THREADS * ROUNDS) as u64
+        );
+    }
 }
@@ -13,6 +13,8 @@ use anyhow::{Context, anyhow};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
 use fvm_shared::error::ExitCode;
+use fvm_shared::sys::DEFAULT_SYSCALL_ABI_VERSION;
+use fvm_shared::version::NetworkVersion;
 use fvm_wasm_instrument::gas_metering::GAS_COUNTER_NAME;
 use num_traits::Zero;
 use wasmtime::OptLevel::Speed;
@@ -27,21 +29,30 @@ use crate::machine::limiter::MemoryLimiter;
 use crate::machine::{Machine, NetworkConfig};
 use crate::syscalls::error::Abort;
 use crate::syscalls::{
-    InvocationData, Linker, charge_for_exec, charge_for_init, record_init_time,
-    update_gas_available,
+    InvocationData, Linker, SUPPORTED_SYSCALL_ABI_VERSIONS, SyscallExtension, charge_for_exec,
+    charge_for_init, declared_abi_version, record_init_time, update_gas_available,
 };
 
-use self::concurrency::EngineConcurrency;
+pub use self::concurrency::{AcquireError, EnginePoolStats};
+use self::concurrency::{EngineConcurrency, Permit};
 use self::instance_pool::InstancePool;
 
 /// The expected max stack depth used to determine the number of instances needed for a given
 /// concurrency level.
 const EXPECTED_MAX_STACK_DEPTH: u32 = 20;
 
+/// The default [`EngineConfig::max_pooled_instances`]. Most machines simply can't handle any more
+/// than 48k instances (fails to allocate address space).
+pub const DEFAULT_MAX_POOLED_INSTANCES: u32 = 48 * 1024;
+
+/// The maximum number of elements in an instance's table.
+const MAX_TABLE_ELEMENTS: usize = 20_000;
+
 /// Container managing [`Engine`]s with different consensus-affecting configurations.
 pub struct MultiEngine {
     engines: Mutex<HashMap<EngineConfig, EnginePool>>,
     concurrency: u32,
+    max_pooled_instances: u32,
 }
 
 /// The proper way of getting this struct is to convert from `NetworkConfig`
@@ -51,20 +62,36 @@ pub struct EngineConfig {
     pub max_wasm_stack: u32,
     pub max_inst_memory_bytes: u64,
     pub concurrency: u32,
+    /// The maximum number of instances to pre-allocate with wasmtime's pooling allocator. If the
+    /// pool can't hold a full call stack ([`EngineConfig::max_call_depth`] instances), instances
+    /// are allocated on demand instead, so 0 disables pooling.
+    ///
+    /// Not consensus-critical: pooled and on-demand instances behave identically.
+    pub max_pooled_instances: u32,
     pub wasm_prices: &'static WasmGasPrices,
     pub actor_redirect: Vec<(Cid, Cid)>,
+    /// Syscall modules registered by the embedder, linked after the built-in syscalls.
+    pub syscall_extensions: Vec<SyscallExtension>,
 }
 
 impl EngineConfig {
+    /// Returns true if instances are allocated from a pool, i.e., if the pool can hold at least
+    /// one full call stack. Otherwise, a deep enough call stack would exhaust the pool.
+    fn pooling(&self) -> bool {
+        self.max_call_depth <= self.max_pooled_instances
+    }
+
     fn instance_pool_size(&self) -> u32 {
-        std::cmp::min(
-            // Allocate at least one full call depth worth of stack, plus some per concurrent call
-            // we allow.
-            self.max_call_depth + EXPECTED_MAX_STACK_DEPTH * self.concurrency,
-            // Most machines simply can't handle any more than 48k instances (fails to allocate
-            // address space).
-            48 * 1024,
-        )
+        // Allocate at least one full call depth worth of stack, plus some per concurrent call we
+        // allow.
+        let instances = self
+            .max_call_depth
+            .saturating_add(EXPECTED_MAX_STACK_DEPTH.saturating_mul(self.concurrency));
+        if self.pooling() {
+            std::cmp::min(instances, self.max_pooled_instances)
+        } else {
+            instances
+        }
     }
 }
 
@@ -76,7 +103,9 @@ impl From<&NetworkConfig> for EngineConfig {
             max_inst_memory_bytes: nc.max_inst_memory_bytes,
             wasm_prices: &nc.price_list.wasm_rules,
             actor_redirect: nc.actor_redirect.clone(),
+            syscall_extensions: nc.syscall_extensions.clone(),
             concurrency: 1,
+            max_pooled_instances: DEFAULT_MAX_POOLED_INSTANCES,
         }
     }
 }
@@ -91,9 +120,16 @@ impl MultiEngine {
         MultiEngine {
             engines: Mutex::new(HashMap::new()),
             concurrency,
+            max_pooled_instances: DEFAULT_MAX_POOLED_INSTANCES,
         }
     }
 
+    /// Sets the [`EngineConfig::max_pooled_instances`] of the engines this multi-engine creates.
+    pub fn with_max_pooled_instances(mut self, max_pooled_instances: u32) -> MultiEngine {
+        self.max_pooled_instances = max_pooled_instances;
+        self
+    }
+
     /// Get an [`EnginePool`] for the given [`NetworkConfig`], creating one if it doesn't already
     /// exist.
     pub fn get(&self, nc: &NetworkConfig) -> anyhow::Result<EnginePool> {
@@ -104,6 +140,7 @@ impl MultiEngine {
 
         let mut ec: EngineConfig = nc.into();
         ec.concurrency = self.concurrency;
+        ec.max_pooled_instances = self.max_pooled_instances;
 
         let pool = match engines.entry(ec.clone()) {
             Occupied(entry) => entry.into_mut(),
@@ -142,25 +179,41 @@ fn wasmtime_config(ec: &EngineConfig) -> anyhow::Result<wasmtime::Config> {
 
     // wasmtime default: OnDemand
     // We want to pre-allocate all permissible memory to support the maximum allowed recursion limit.
-
-    let mut alloc_strat_cfg = wasmtime::PoolingAllocationConfig::default();
-    alloc_strat_cfg.total_core_instances(instance_count);
-    alloc_strat_cfg.total_memories(instance_count);
-    alloc_strat_cfg.max_memories_per_module(1);
-    alloc_strat_cfg.total_tables(instance_count);
-    alloc_strat_cfg.max_tables_per_module(1);
-    alloc_strat_cfg.table_elements(20_000);
-    // we don't use components.
-    alloc_strat_cfg.total_component_instances(0);
-    alloc_strat_cfg.max_component_instance_size(0);
-    alloc_strat_cfg.max_core_instances_per_component(0);
-    alloc_strat_cfg.max_memories_per_component(0);
-    alloc_strat_cfg.max_tables_per_component(0);
-
-    // Adjust the maximum amount of host memory that can be committed to an instance to
-    // match the static linear memory size we reserve for each slot.
-    alloc_strat_cfg.max_memory_size(instance_memory_maximum_size as usize);
-    c.allocation_strategy(InstanceAllocationStrategy::Pooling(alloc_strat_cfg));
+    //
+    // Note: pooled slots only recycle allocations, never instances. Every invocation instantiates
+    // the module afresh in a new store, and wasmtime re-initializes all instance state (zeroed
+    // memory plus active data segments, tables plus active element segments, globals, and
+    // dropped passive segments) on instantiation. Execution is therefore identical to running in a
+    // freshly allocated instance.
+    //
+    // If the pool can't hold a full call stack, we allocate on demand instead: wasmtime can't fall
+    // back from a pool to on-demand allocation once the pool is exhausted, and modules compiled
+    // for one engine can't be used by another.
+    if ec.pooling() {
+        let mut alloc_strat_cfg = wasmtime::PoolingAllocationConfig::default();
+        alloc_strat_cfg.total_core_instances(instance_count);
+        alloc_strat_cfg.total_memories(instance_count);
+        alloc_strat_cfg.max_memories_per_module(1);
+        alloc_strat_cfg.total_tables(instance_count);
+        alloc_strat_cfg.max_tables_per_module(1);
+        alloc_strat_cfg.table_elements(MAX_TABLE_ELEMENTS);
+        // we don't use components.
+        alloc_strat_cfg.total_component_instances(0);
+        alloc_strat_cfg.max_component_instance_size(0);
+        alloc_strat_cfg.max_core_instances_per_component(0);
+        alloc_strat_cfg.max_memories_per_component(0);
+        alloc_strat_cfg.max_tables_per_component(0);
+
+        // Adjust the maximum amount of host memory that can be committed to an instance to
+        // match the static linear memory size we reserve for each slot.
+        alloc_strat_cfg.max_memory_size(instance_memory_maximum_size as usize);
+        c.allocation_strategy(InstanceAllocationStrategy::Pooling(alloc_strat_cfg));
+    } else {
+        // Enforce the same per-instance limits as the pool's slots: memories can't move, so they
+        // can't grow past their reservation, and the limiter caps table sizes.
+        c.allocation_strategy(InstanceAllocationStrategy::OnDemand);
+        c.memory_reservation(instance_memory_maximum_size);
+    }
 
     // Explicitly disable custom page sizes, we always assume 64KiB.
     c.wasm_custom_page_sizes(false);
@@ -292,6 +345,9 @@ struct ModuleRecord {
     module: Module,
     /// Byte size of the original Wasm.
     size: usize,
+    /// The syscall ABI version declared by the original Wasm, or the reason the declaration is
+    /// invalid.
+    abi_version: Result<u32, &'static str>,
 }
 
 struct EngineInner {
@@ -309,7 +365,8 @@ struct EngineInner {
     dummy_memory: Memory,
 
     module_cache: Mutex<HashMap<Cid, ModuleRecord>>,
-    instance_cache: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+    /// Linkers, by kernel type, syscall ABI version and network version.
+    instance_cache: Mutex<HashMap<(TypeId, u32, NetworkVersion), Box<dyn Any + Send>>>,
     config: EngineConfig,
 
     actor_redirect: HashMap<Cid, Cid>,
@@ -321,16 +378,47 @@ pub struct EnginePool(Arc<EngineInner>);
 
 impl EnginePool {
     /// Acquire an [`Engine`]. This method will block until an [`Engine`] is available, and will
-    /// release the engine on drop.
-    pub fn acquire(&self) -> Engine {
-        Engine {
-            id: self.0.concurrency_limit.acquire(),
+    /// release the engine on drop. Engines are handed out in the order in which they were
+    /// requested.
+    ///
+    /// Fails with [`AcquireError::Reentrant`] (instead of potentially deadlocking) if this method
+    /// would have to wait, and the current thread is executing with an [`Engine`] from this pool
+    /// (see [`Engine::enter`]), e.g., when executing a message from within an extern.
+    pub fn acquire(&self) -> Result<Engine, AcquireError> {
+        let permit = self.0.concurrency_limit.acquire()?;
+        Ok(Engine {
+            permit,
             inner: self.0.clone(),
-        }
+        })
+    }
+
+    /// Acquire an [`Engine`] without waiting, failing with [`AcquireError::WouldBlock`] if all
+    /// engines are in use (or other callers are already waiting for one), or with
+    /// [`AcquireError::Reentrant`] if, additionally, the current thread is executing with an
+    /// [`Engine`] from this pool.
+    pub fn try_acquire(&self) -> Result<Engine, AcquireError> {
+        let permit = self.0.concurrency_limit.try_acquire()?;
+        Ok(Engine {
+            permit,
+            inner: self.0.clone(),
+        })
+    }
+
+    /// Returns the pool's current occupancy, and the total time callers have spent waiting to
+    /// [acquire](EnginePool::acquire) an [`Engine`].
+    pub fn stats(&self) -> EnginePoolStats {
+        self.0.concurrency_limit.stats()
     }
 
     /// Create a new [`EnginePool`].
     pub fn new(ec: EngineConfig) -> anyhow::Result<Self> {
+        if !ec.pooling() && ec.max_pooled_instances > 0 {
+            log::warn!(
+                "an instance pool of {} can't hold a full call stack ({} calls); allocating instances on demand",
+                ec.max_pooled_instances,
+                ec.max_call_depth
+            );
+        }
         let c = wasmtime_config(&ec)?;
         let engine = wasmtime::Engine::new(&c)?;
 
@@ -367,18 +455,42 @@ struct Cache<K> {
 ///
 /// The `Engine` will be returned to the [`EnginePool`] on drop.
 pub struct Engine {
-    id: u64,
+    permit: Permit,
     inner: Arc<EngineInner>,
 }
 
 /// Release the engine back into the [`EnginePool`].
 impl Drop for Engine {
     fn drop(&mut self) {
-        self.inner.concurrency_limit.release();
+        self.inner.concurrency_limit.release(&self.permit);
+    }
+}
+
+/// Marks an [`Engine`] as executing on the current thread, until dropped. See [`Engine::enter`].
+pub struct EngineGuard {
+    id: u64,
+    inner: Arc<EngineInner>,
+}
+
+impl Drop for EngineGuard {
+    fn drop(&mut self) {
+        self.inner.concurrency_limit.exit(self.id);
     }
 }
 
 impl Engine {
+    /// Marks this engine as executing on the current thread until the returned guard is dropped.
+    /// While it is, acquiring another engine from the same pool on this thread fails with
+    /// [`AcquireError::Reentrant`] instead of waiting. Engines may move between threads, so this
+    /// should be called by whoever executes with the engine, for the duration of the execution.
+    pub fn enter(&self) -> EngineGuard {
+        self.inner.concurrency_limit.enter(&self.permit);
+        EngineGuard {
+            id: self.permit.id,
+            inner: self.inner.clone(),
+        }
+    }
+
     /// Loads an actor's Wasm code from the blockstore by CID, and prepares
     /// it for execution by instantiating and caching the Wasm module. This
     /// method errors if the code CID is not found in the store.
@@ -443,6 +555,9 @@ impl Engine {
             .map_err(anyhow::Error::msg)
             .with_context(|| "failed to validate actor wasm")?;
 
+        // Read the ABI version before instrumenting, as instrumentation drops custom sections.
+        let abi_version = declared_abi_version(raw_wasm);
+
         // Note: when adding debug mode support (with recorded syscall replay) don't instrument to
         // avoid breaking debug info
 
@@ -474,16 +589,26 @@ impl Engine {
         Ok(ModuleRecord {
             module,
             size: raw_wasm.len(),
+            abi_version,
         })
     }
 
     /// Load compiled wasm code into the engine.
     ///
+    /// Compiled modules don't retain custom sections, so `abi_version` must be the syscall ABI
+    /// version declared by the original Wasm (see [`declared_abi_version`]), persisted alongside
+    /// the compiled code.
+    ///
     /// # Safety
     ///
     /// See [`wasmtime::Module::deserialize`] for safety information.
     #[allow(dead_code)]
-    unsafe fn load_compiled(&self, k: &Cid, compiled: &[u8]) -> anyhow::Result<Module> {
+    unsafe fn load_compiled(
+        &self,
+        k: &Cid,
+        compiled: &[u8],
+        abi_version: Result<u32, &'static str>,
+    ) -> anyhow::Result<Module> {
         let k = self.with_redirect(k);
         let mut cache = self
             .inner
@@ -499,6 +624,7 @@ impl Engine {
                     ModuleRecord {
                         module: module.clone(),
                         size: compiled.len(),
+                        abi_version,
                     },
                 );
                 module
@@ -510,6 +636,11 @@ impl Engine {
     /// Lookup and instantiate a loaded wasmtime module with the given store. This will cache the
     /// linker, syscalls, etc.
     ///
+    /// From nv29, modules are linked against the syscalls of the ABI version they declare, and
+    /// modules declaring an unsupported (or malformed) version fail with
+    /// [`ExitCode::SYS_UNSUPPORTED_ABI`]. Before, declarations are ignored and all modules are
+    /// linked against [`DEFAULT_SYSCALL_ABI_VERSION`].
+    ///
     /// This returns an `Abort` as it may need to execute initialization code, charge gas, etc.
     pub(crate) fn instantiate<K: Kernel>(
         &self,
@@ -518,18 +649,71 @@ impl Engine {
     ) -> Result<Option<wasmtime::Instance>, Abort> {
         let k = self.with_redirect(k);
         let mut instance_cache = self.inner.instance_cache.lock().expect("cache poisoned");
+        let mut module_cache = self
+            .inner
+            .module_cache
+            .lock()
+            .expect("module_cache poisoned");
+
+        let record = match module_cache.entry(*k) {
+            Occupied(v) => v.into_mut(),
+            Vacant(v) => match store
+                .data()
+                .kernel
+                .machine()
+                .blockstore()
+                .get(k)
+                .context("failed to lookup wasm module in blockstore")
+                .map_err(Abort::Fatal)?
+            {
+                Some(raw_wasm) => v.insert(self.load_raw(&raw_wasm).map_err(Abort::Fatal)?),
+                None => return Ok(None),
+            },
+        };
+        let module = &record.module;
+
+        let network_version = store.data().kernel.machine().context().network_version;
+        let abi_version = match record.abi_version {
+            _ if network_version < NetworkVersion::V29 => DEFAULT_SYSCALL_ABI_VERSION,
+            Ok(v) if SUPPORTED_SYSCALL_ABI_VERSIONS.contains(&v) => v,
+            Ok(v) => {
+                return Err(Abort::Exit(
+                    ExitCode::SYS_UNSUPPORTED_ABI,
+                    format!(
+                        "actor targets syscall ABI version {v}, supported versions are {}..={}",
+                        SUPPORTED_SYSCALL_ABI_VERSIONS.start(),
+                        SUPPORTED_SYSCALL_ABI_VERSIONS.end()
+                    ),
+                    0,
+                ));
+            }
+            Err(e) => {
+                return Err(Abort::Exit(
+                    ExitCode::SYS_UNSUPPORTED_ABI,
+                    format!("invalid syscall ABI declaration: {e}"),
+                    0,
+                ));
+            }
+        };
 
-        let type_id = TypeId::of::<K>();
-        let cache: &mut Cache<K> = match instance_cache.entry(type_id) {
+        let cache_key = (TypeId::of::<K>(), abi_version, network_version);
+        let cache: &mut Cache<K> = match instance_cache.entry(cache_key) {
             Occupied(e) => &mut *e
                 .into_mut()
                 .downcast_mut()
                 .expect("invalid instance cache entry"),
             Vacant(e) => &mut *e
                 .insert({
-                    let mut linker = Linker(wasmtime::Linker::new(&self.inner.engine));
+                    let mut linker = Linker(
+                        wasmtime::Linker::new(&self.inner.engine),
+                        abi_version,
+                        network_version,
+                    );
                     linker.0.allow_shadowing(true);
                     K::link_syscalls(&mut linker).map_err(Abort::Fatal)?;
+                    for extension in &self.inner.config.syscall_extensions {
+                        extension.link(&mut linker).map_err(Abort::Fatal)?;
+                    }
                     Box::new(Cache { linker: linker.0 })
                 })
                 .downcast_mut()
@@ -542,70 +726,42 @@ impl Engine {
             .context("failed to define gas counter")
             .map_err(Abort::Fatal)?;
 
-        let mut module_cache = self
-            .inner
-            .module_cache
-            .lock()
-            .expect("module_cache poisoned");
+        // Before we instantiate the module, we should make sure the user has sufficient gas to
+        // pay for the minimum memory requirements. The module instrumentation in `inject` only
+        // adds code to charge for _growing_ the memory, but not for the amount made accessible
+        // initially. The limits are checked by wasmtime during instantiation, though.
+        let t = charge_for_init(store, module).map_err(Abort::from_error_as_fatal)?;
 
-        let instantiate = |store: &mut wasmtime::Store<InvocationData<K>>, module| {
-            // Before we instantiate the module, we should make sure the user has sufficient gas to
-            // pay for the minimum memory requirements. The module instrumentation in `inject` only
-            // adds code to charge for _growing_ the memory, but not for the amount made accessible
-            // initially. The limits are checked by wasmtime during instantiation, though.
-            let t = charge_for_init(store, module).map_err(Abort::from_error_as_fatal)?;
-
-            // Pre-instantiate to catch any linker errors. These are considered fatal as it means
-            // the wasm module wasn't properly validated.
-            let pre_instance = cache
-                .linker
-                .instantiate_pre(module)
-                .context("failed to link actor module")?;
-
-            // Update the gas _just_ in case.
-            update_gas_available(store)?;
-            let res = pre_instance.instantiate(&mut *store);
-            charge_for_exec(store)?;
-
-            let inst = res.map_err(|e| {
-                // We can't really tell what type of error happened, so we have to assume that we
-                // either ran out of memory or trapped. Given that we've already type-checked the
-                // module, this is the most likely case anyways. That or there'a a bug in the FVM.
-                Abort::Exit(
-                    ExitCode::SYS_ILLEGAL_INSTRUCTION,
-                    format!("failed to instantiate module: {e}"),
-                    0,
-                )
-            })?;
-
-            // Record the time it took for the linker to instantiate the module.
-            // This should also include everything that happens above in this method.
-            // Note that this does _not_ contain the time it took the load the Wasm file,
-            // which could have been cached already.
-            record_init_time(store, t);
-
-            Ok(Some(inst))
-        };
-
-        match module_cache.entry(*k) {
-            Occupied(v) => instantiate(store, &v.get().module),
-            Vacant(v) => match store
-                .data()
-                .kernel
-                .machine()
-                .blockstore()
-                .get(k)
-                .context("failed to lookup wasm module in blockstore")
-                .map_err(Abort::Fatal)?
-            {
-                Some(raw_wasm) => instantiate(
-                    store,
-                    &v.insert(self.load_raw(&raw_wasm).map_err(Abort::Fatal)?)
-                        .module,
-                ),
-                None => Ok(None),
-            },
-        }
+        // Pre-instantiate to catch any linker errors. These are considered fatal as it means
+        // the wasm module wasn't properly validated.
+        let pre_instance = cache
+            .linker
+            .instantiate_pre(module)
+            .context("failed to link actor module")?;
+
+        // Update the gas _just_ in case.
+        update_gas_available(store)?;
+        let res = pre_instance.instantiate(&mut *store);
+        charge_for_exec(store)?;
+
+        let inst = res.map_err(|e| {
+            // We can't really tell what type of error happened, so we have to assume that we
+            // either ran out of memory or trapped. Given that we've already type-checked the
+            // module, this is the most likely case anyways. That or there'a a bug in the FVM.
+            Abort::Exit(
+                ExitCode::SYS_ILLEGAL_INSTRUCTION,
+                format!("failed to instantiate module: {e}"),
+                0,
+            )
+        })?;
+
+        // Record the time it took for the linker to instantiate the module.
+        // This should also include everything that happens above in this method.
+        // Note that this does _not_ contain the time it took the load the Wasm file,
+        // which could have been cached already.
+        record_init_time(store, t);
+
+        Ok(Some(inst))
     }
 
     /// Construct a new wasmtime "store" from the given kernel.
@@ -621,7 +777,7 @@ impl Engine {
             }
         }
 
-        self.inner.instance_limit.take(self.id);
+        self.inner.instance_limit.take(self.permit.id);
         let reservation = InstanceReservation(self.inner.clone());
 
         let memory_bytes = kernel.limiter_mut().memory_used();
@@ -691,7 +847,7 @@ impl<L: MemoryLimiter> wasmtime::ResourceLimiter for WasmtimeLimiter<L> {
         desired: usize,
         maximum: Option<usize>,
     ) -> anyhow::Result<bool> {
-        if maximum.is_some_and(|m| desired > m) {
+        if maximum.is_some_and(|m| desired > m) || desired > MAX_TABLE_ELEMENTS {
             return Ok(false);
         }
         Ok(self.0.grow_instance_table(current, desired))
@@ -710,9 +866,11 @@ impl<L: MemoryLimiter> wasmtime::ResourceLimiter for WasmtimeLimiter<L> {
 
 #[cfg(test)]
 mod tests {
+    use fvm_shared::version::NetworkVersion;
     use wasmtime::ResourceLimiter;
 
-    use crate::engine::WasmtimeLimiter;
+    use crate::engine::{EngineConfig, MAX_TABLE_ELEMENTS, WasmtimeLimiter};
+    use crate::machine::NetworkConfig;
     use crate::machine::limiter::MemoryLimiter;
 
     #[derive(Default)]
@@ -738,6 +896,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn instance_pool_size() {
+        let mut ec: EngineConfig = (&NetworkConfig::new(NetworkVersion::V21)).into();
+        ec.max_call_depth = 100;
+        ec.concurrency = 2;
+
+        // The pool holds a full call stack, plus some for each concurrent call.
+        assert!(ec.pooling());
+        assert_eq!(ec.instance_pool_size(), 140);
+
+        // Capped by the maximum pool size.
+        ec.max_pooled_instances = 120;
+        assert!(ec.pooling());
+        assert_eq!(ec.instance_pool_size(), 120);
+
+        // Unless the pool can't hold a full call stack, in which case instances are allocated on
+        // demand.
+        ec.max_pooled_instances = 99;
+        assert!(!ec.pooling());
+        assert_eq!(ec.instance_pool_size(), 140);
+        ec.max_pooled_instances = 0;
+        assert!(!ec.pooling());
+    }
+
     #[test]
     fn memory() {
         let mut limits = WasmtimeLimiter(Limiter::default());
@@ -766,5 +948,13 @@ mod tests {
         // Increase by 2.
         assert!(limits.table_growing(2, 4, None).unwrap());
         assert_eq!(limits.0.memory, 5 * 8);
+
+        // Tables are capped, like the pooling allocator's.
+        assert!(
+            !limits
+                .table_growing(4, MAX_TABLE_ELEMENTS + 1, None)
+                .unwrap()
+        );
+        assert_eq!(limits.0.memory, 5 * 8);
     }
 }
@@ -0,0 +1,208 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+
+use cid::Cid;
+use futures::SinkExt;
+use futures::channel::{mpsc, oneshot};
+use fvm_shared::message::Message;
+use thiserror::Error;
+
+use super::threaded::EXEC_STACK_SIZE;
+use super::{ApplyKind, ApplyRet, Executor};
+
+/// An error returned by the [`AsyncExecutor`].
+#[derive(Debug, Error)]
+pub enum AsyncExecutorError {
+    /// The executor failed with a fatal error. The executor remains usable, just like after a
+    /// fatal error from a synchronous [`Executor`].
+    #[error("fatal error: {0}")]
+    Fatal(anyhow::Error),
+    /// The executor panicked. The executor may have been left in an inconsistent state, so it is
+    /// shut down.
+    #[error("executor panicked: {0}")]
+    Panicked(String),
+    /// The executor has shut down, after panicking.
+    #[error("executor has shut down")]
+    ShutDown,
+}
+
+type Reply<T> = oneshot::Sender<Result<T, AsyncExecutorError>>;
+
+enum Request {
+    Execute {
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+        reply: Reply<ApplyRet>,
+    },
+    Flush {
+        reply: Reply<Cid>,
+    },
+}
+
+/// An adapter for driving an [`Executor`] from async code, without blocking the async runtime.
+///
+/// The executor is created on, and owned by, a dedicated thread with the same stack size as the
+/// [`ThreadedExecutor`](super::ThreadedExecutor)'s threads. It therefore doesn't need to be `Send`.
+/// Requests are sent to that thread over a bounded channel, and are processed one at a time, in the
+/// order in which they were submitted.
+///
+/// Dropping the `AsyncExecutor` shuts the thread down once all previously submitted requests have
+/// been processed, dropping the executor (without flushing it).
+pub struct AsyncExecutor {
+    requests: mpsc::Sender<Request>,
+}
+
+impl AsyncExecutor {
+    /// Creates an executor on a new thread with `make_executor`, queueing at most (approximately)
+    /// `capacity` requests before callers have to wait.
+    pub async fn new<E, F>(capacity: usize, make_executor: F) -> Result<Self, AsyncExecutorError>
+    where
+        E: Executor + 'static,
+        F: FnOnce() -> anyhow::Result<E> + Send + 'static,
+    {
+        let (requests, rx) = mpsc::channel(capacity);
+        let (ready, ready_rx) = oneshot::channel();
+        thread::Builder::new()
+            .name("fvm-async-executor".into())
+            .stack_size(EXEC_STACK_SIZE)
+            .spawn(move || run(make_executor, ready, rx))
+            .map_err(|e| AsyncExecutorError::Fatal(e.into()))?;
+        ready_rx
+            .await
+            .unwrap_or(Err(AsyncExecutorError::ShutDown))?;
+        Ok(Self { requests })
+    }
+
+    /// Queues a message for execution (see [`Executor::execute_message`]), returning once it has
+    /// been queued. The returned future resolves to the result of applying the message.
+    ///
+    /// This lets callers pipeline messages while keeping them in order: messages are applied in
+    /// the order in which they were queued.
+    pub async fn submit_message(
+        &self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> Result<PendingApply, AsyncExecutorError> {
+        let (reply, rx) = oneshot::channel();
+        self.send(Request::Execute {
+            msg,
+            apply_kind,
+            raw_length,
+            reply,
+        })
+        .await?;
+        Ok(PendingApply(rx))
+    }
+
+    /// Executes a message. See [`Executor::execute_message`].
+    pub async fn execute_message(
+        &self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> Result<ApplyRet, AsyncExecutorError> {
+        self.submit_message(msg, apply_kind, raw_length)
+            .await?
+            .await
+    }
+
+    /// Flushes the state tree, once all previously submitted messages have been applied. See
+    /// [`Executor::flush`].
+    pub async fn flush(&self) -> Result<Cid, AsyncExecutorError> {
+        let (reply, rx) = oneshot::channel();
+        self.send(Request::Flush { reply }).await?;
+        rx.await.unwrap_or(Err(AsyncExecutorError::ShutDown))
+    }
+
+    async fn send(&self, request: Request) -> Result<(), AsyncExecutorError> {
+        self.requests
+            .clone()
+            .send(request)
+            .await
+            .map_err(|_| AsyncExecutorError::ShutDown)
+    }
+}
+
+/// A message queued with [`AsyncExecutor::submit_message`], resolving to the result of applying
+/// it.
+pub struct PendingApply(oneshot::Receiver<Result<ApplyRet, AsyncExecutorError>>);
+
+impl Future for PendingApply {
+    type Output = Result<ApplyRet, AsyncExecutorError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|res| res.unwrap_or(Err(AsyncExecutorError::ShutDown)))
+    }
+}
+
+/// The executor thread's main loop.
+fn run<E, F>(make_executor: F, ready: Reply<()>, requests: mpsc::Receiver<Request>)
+where
+    E: Executor,
+    F: FnOnce() -> anyhow::Result<E>,
+{
+    let mut executor = match call(make_executor) {
+        Ok(executor) => {
+            let _ = ready.send(Ok(()));
+            executor
+        }
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+
+    for request in futures::executor::block_on_stream(requests) {
+        let panicked = match request {
+            Request::Execute {
+                msg,
+                apply_kind,
+                raw_length,
+                reply,
+            } => respond(
+                reply,
+                call(|| executor.execute_message(msg, apply_kind, raw_length)),
+            ),
+            Request::Flush { reply } => respond(reply, call(|| executor.flush())),
+        };
+        if panicked {
+            // Drop the remaining requests, failing them.
+            break;
+        }
+    }
+}
+
+/// Calls the function, turning errors and panics into [`AsyncExecutorError`]s.
+fn call<T>(f: impl FnOnce() -> anyhow::Result<T>) -> Result<T, AsyncExecutorError> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res.map_err(AsyncExecutorError::Fatal),
+        Err(payload) => Err(AsyncExecutorError::Panicked(panic_message(payload))),
+    }
+}
+
+/// Sends the result to the caller (if still waiting), returning true if the executor panicked.
+fn respond<T>(reply: Reply<T>, res: Result<T, AsyncExecutorError>) -> bool {
+    let panicked = matches!(res, Err(AsyncExecutorError::Panicked(_)));
+    let _ = reply.send(res);
+    panicked
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".into()
+    }
+}
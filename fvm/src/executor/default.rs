@@ -6,7 +6,7 @@ use std::result::Result as StdResult;
 use anyhow::{Result, anyhow};
 use cid::Cid;
 use fvm_ipld_encoding::{CBOR, RawBytes};
-use fvm_shared::address::Payload;
+use fvm_shared::address::{Address, Payload};
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::{ErrorNumber, ExitCode};
 use fvm_shared::event::StampedEvent;
@@ -16,6 +16,7 @@ use fvm_shared::{ActorID, IPLD_RAW, METHOD_SEND};
 use num_traits::Zero;
 
 use super::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+use crate::account_actor;
 use crate::call_manager::{Backtrace, CallManager, Entrypoint, InvocationResult, backtrace};
 use crate::eam_actor::EAM_ACTOR_ID;
 use crate::engine::EnginePool;
@@ -63,6 +64,91 @@ where
         msg: Message,
         apply_kind: ApplyKind,
         raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        let track_changes = self.context().track_actor_changes;
+        if track_changes {
+            self.state_tree_mut().begin_tracking_changes();
+        }
+        let track_writes = self.context().tracing;
+        if track_writes {
+            self.begin_message_writes();
+        }
+        let mut ret = self.apply_message(msg, apply_kind, raw_length)?;
+        if track_changes {
+            ret.actor_changes = self
+                .state_tree_mut()
+                .take_changes()
+                .context("failed to determine the actors changed by the message")?;
+        }
+        if track_writes {
+            ret.write_stats = self
+                .end_message_writes()
+                .context("failed to determine the blocks written by the message")?;
+        }
+        Ok(ret)
+    }
+
+    /// Flush the state-tree to the underlying blockstore.
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        let k = (**self).flush()?;
+        Ok(k)
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
+    pub fn new(
+        engine_pool: EnginePool,
+        machine: <K::CallManager as CallManager>::Machine,
+    ) -> anyhow::Result<Self> {
+        // Skip preloading all builtin actors when testing.
+        #[cfg(not(any(test, feature = "testing")))]
+        {
+            // Preload any uncached modules.
+            // This interface works for now because we know all actor CIDs
+            // ahead of time, but with user-supplied code, we won't have that
+            // guarantee.
+            engine_pool.acquire()?.preload_all(
+                machine.blockstore(),
+                machine.builtin_actors().builtin_actor_codes(),
+            )?;
+        }
+        Ok(Self {
+            engine_pool,
+            machine: Some(machine),
+        })
+    }
+
+    /// Consume consumes the executor and returns the Machine. If the Machine had
+    /// been poisoned during execution, the Option will be None.
+    pub fn into_machine(self) -> Option<<K::CallManager as CallManager>::Machine> {
+        self.machine
+    }
+
+    /// Resolves an account actor to its key (f1/f3) address by reading the actor's state directly
+    /// from the state tree. This agrees with the account actor's `PubkeyAddress` method (method 2),
+    /// but doesn't invoke the actor, so it's cheap enough to use when validating message
+    /// signatures.
+    ///
+    /// Fails if the actor doesn't exist or isn't an account actor.
+    pub fn resolve_to_key_address(&self, id: ActorID) -> Result<Address> {
+        let actor = self
+            .state_tree()
+            .get_actor(id)?
+            .ok_or_else(|| anyhow!("actor {} not found", id))?;
+        account_actor::key_address(self.state_tree().store(), self.builtin_actors(), &actor)?
+            .ok_or_else(|| anyhow!("actor {} is not an account actor", id))
+    }
+
+    /// Applies a message. See [`Executor::execute_message`].
+    fn apply_message(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
     ) -> anyhow::Result<ApplyRet> {
         // Validate if the message was correct, charge for it, and extract some preliminary data.
         let (sender_id, gas_cost, inclusion_cost) =
@@ -74,10 +160,13 @@ where
         struct MachineExecRet {
             result: crate::kernel::Result<InvocationResult>,
             gas_used: u64,
+            proof_gas_used: u64,
             backtrace: Backtrace,
             exec_trace: ExecutionTrace,
             events_root: Option<Cid>,
             events: Vec<StampedEvent>, // TODO consider removing if nothing in the client ends up using it.
+            created_actors: Vec<(ActorID, Address)>,
+            deleted_actors: Vec<ActorID>,
         }
 
         // Pre-resolve the message receiver's address, if known.
@@ -95,8 +184,10 @@ where
             .max(TokenAmount::zero());
 
         // Acquire an engine from the pool. This may block if there are concurrently executing
-        // messages inside other executors sharing the same pool.
-        let engine = self.engine_pool.acquire();
+        // messages inside other executors sharing the same pool, and fails instead if this thread
+        // is already executing a message with an engine from the pool (e.g., from an extern).
+        let engine = self.engine_pool.acquire()?;
+        let _executing = engine.enter();
 
         // Apply the message.
         let ret = self.map_machine(|machine| {
@@ -164,10 +255,13 @@ where
                 Ok(MachineExecRet {
                     result,
                     gas_used: res.gas_used,
+                    proof_gas_used: res.proof_gas_used,
                     backtrace: res.backtrace,
                     exec_trace: res.exec_trace,
                     events_root: res.events_root,
                     events: res.events,
+                    created_actors: res.created_actors,
+                    deleted_actors: res.deleted_actors,
                 }),
                 machine,
             )
@@ -176,10 +270,13 @@ where
         let MachineExecRet {
             result: res,
             gas_used,
+            proof_gas_used,
             mut backtrace,
             exec_trace,
             events_root,
             events,
+            created_actors,
+            deleted_actors,
         } = ret;
 
         // Extract the exit code and build the result of the message application.
@@ -276,67 +373,36 @@ where
                 receipt,
                 failure_info,
                 gas_cost,
+                proof_gas_used,
                 exec_trace,
                 events,
                 return_codec,
+                created_actors,
+                deleted_actors,
             ),
             ApplyKind::Implicit => Ok(ApplyRet {
                 msg_receipt: receipt,
                 penalty: TokenAmount::zero(),
                 miner_tip: TokenAmount::zero(),
+                miner_tip_recipient: None,
                 base_fee_burn: TokenAmount::zero(),
                 over_estimation_burn: TokenAmount::zero(),
                 refund: TokenAmount::zero(),
                 gas_refund: 0,
                 gas_burned: 0,
+                proof_gas_used,
                 failure_info,
                 exec_trace,
                 events,
                 return_codec,
+                actor_changes: None,
+                created_actors,
+                deleted_actors,
+                write_stats: None,
             }),
         }
     }
 
-    /// Flush the state-tree to the underlying blockstore.
-    fn flush(&mut self) -> anyhow::Result<Cid> {
-        let k = (**self).flush()?;
-        Ok(k)
-    }
-}
-
-impl<K> DefaultExecutor<K>
-where
-    K: Kernel,
-{
-    /// Create a new [`DefaultExecutor`] for executing messages on the [`Machine`].
-    pub fn new(
-        engine_pool: EnginePool,
-        machine: <K::CallManager as CallManager>::Machine,
-    ) -> anyhow::Result<Self> {
-        // Skip preloading all builtin actors when testing.
-        #[cfg(not(any(test, feature = "testing")))]
-        {
-            // Preload any uncached modules.
-            // This interface works for now because we know all actor CIDs
-            // ahead of time, but with user-supplied code, we won't have that
-            // guarantee.
-            engine_pool.acquire().preload_all(
-                machine.blockstore(),
-                machine.builtin_actors().builtin_actor_codes(),
-            )?;
-        }
-        Ok(Self {
-            engine_pool,
-            machine: Some(machine),
-        })
-    }
-
-    /// Consume consumes the executor and returns the Machine. If the Machine had
-    /// been poisoned during execution, the Option will be None.
-    pub fn into_machine(self) -> Option<<K::CallManager as CallManager>::Machine> {
-        self.machine
-    }
-
     // TODO: The return type here is very strange because we have three cases:
     //  1. Continue: Return sender ID, & gas.
     //  2. Short-circuit: Return ApplyRet.
@@ -348,8 +414,6 @@ where
         apply_kind: ApplyKind,
         raw_length: usize,
     ) -> Result<StdResult<(ActorID, TokenAmount, GasCharge), ApplyRet>> {
-        msg.check().or_fatal()?;
-
         // TODO We don't like having price lists _inside_ the FVM, but passing
         //  these across the boundary is also a no-go.
         let pl = &self.context().price_list;
@@ -377,6 +441,11 @@ where
             }
         };
 
+        // This must come after the inclusion cost check: an explicit message with a zero gas limit
+        // can't pay for its inclusion, and must be rejected like any other such message instead of
+        // failing the whole block.
+        msg.check().or_fatal()?;
+
         // Load sender actor state.
         let sender_id = match self
             .state_tree()
@@ -483,9 +552,12 @@ where
         receipt: Receipt,
         failure_info: Option<ApplyFailure>,
         gas_cost: TokenAmount,
+        proof_gas_used: u64,
         exec_trace: ExecutionTrace,
         events: Vec<StampedEvent>,
         return_codec: Option<u64>,
+        created_actors: Vec<(ActorID, Address)>,
+        deleted_actors: Vec<ActorID>,
     ) -> anyhow::Result<ApplyRet> {
         // NOTE: we don't support old network versions in the FVM, so we always burn.
         let GasOutputs {
@@ -504,6 +576,8 @@ where
             &msg.gas_premium,
         );
 
+        let tip_recipient = self.tip_recipient()?;
+
         let mut transfer_to_actor = |addr: ActorID, amt: &TokenAmount| -> anyhow::Result<()> {
             if amt.is_negative() {
                 return Err(anyhow!("attempted to transfer negative value into actor"));
@@ -520,7 +594,8 @@ where
 
         transfer_to_actor(BURNT_FUNDS_ACTOR_ID, &base_fee_burn)?;
 
-        transfer_to_actor(REWARD_ACTOR_ID, &miner_tip)?;
+        let miner_tip_recipient = (!miner_tip.is_zero()).then_some(tip_recipient);
+        transfer_to_actor(tip_recipient, &miner_tip)?;
 
         transfer_to_actor(BURNT_FUNDS_ACTOR_ID, &over_estimation_burn)?;
 
@@ -535,18 +610,41 @@ where
             msg_receipt: receipt,
             penalty: miner_penalty,
             miner_tip,
+            miner_tip_recipient,
             base_fee_burn,
             over_estimation_burn,
             refund,
             gas_refund,
             gas_burned,
+            proof_gas_used,
             failure_info,
             exec_trace,
             events,
             return_codec,
+            actor_changes: None,
+            created_actors,
+            deleted_actors,
+            write_stats: None,
         })
     }
 
+    /// Returns the actor to pay miner tips to: the block miner, if set (see
+    /// [`MachineContext::block_miner`](crate::machine::MachineContext::block_miner)), and the reward
+    /// actor otherwise. Like Lotus, tips for a block miner that can't be resolved are burnt.
+    fn tip_recipient(&self) -> anyhow::Result<ActorID> {
+        let Some(miner) = &self.context().block_miner else {
+            return Ok(REWARD_ACTOR_ID);
+        };
+        let state_tree = self.state_tree();
+        if let Some(id) = state_tree.lookup_id(miner)?
+            && state_tree.get_actor(id)?.is_some()
+        {
+            return Ok(id);
+        }
+        log::debug!("block miner {} not found, burning the miner tip", miner);
+        Ok(BURNT_FUNDS_ACTOR_ID)
+    }
+
     fn map_machine<F, T>(&mut self, f: F) -> T
     where
         F: FnOnce(
@@ -0,0 +1,284 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashSet;
+use std::io::{ErrorKind, Read, Write};
+
+use anyhow::{Context as _, anyhow};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{RawBytes, from_slice, to_vec};
+use fvm_shared::ActorID;
+use fvm_shared::receipt::Receipt;
+use multihash_codetable::{Code, MultihashDigest};
+
+use super::{ApplyKind, ApplyRet, ChainMessage, DefaultExecutor, Executor};
+use crate::Kernel;
+use crate::blockstore::BufferedBlockstore;
+use crate::call_manager::CallManager;
+use crate::machine::{Machine, message_cid};
+use crate::state_tree::ActorState;
+
+/// A record appended to a [`Journal`] after each message applied by
+/// [`DefaultExecutor::apply_journaled`].
+///
+/// Records are written as DAG-CBOR, each preceded by its length as a big-endian u64.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct JournalRecord {
+    /// The index of the message in the batch.
+    pub index: u64,
+    /// The CID of the message.
+    pub message: Cid,
+    /// The message's receipt.
+    pub receipt: Receipt,
+    /// The gas used by the batch's messages so far, including this one.
+    pub gas_used: u64,
+    /// The state root after applying the message.
+    pub state_root: Cid,
+    /// The actors changed by the message, ordered by ID, with their new states (`None` if they
+    /// were deleted).
+    pub actors: Vec<(ActorID, Option<ActorState>)>,
+    /// The blocks written while applying the message that are reachable from the new state root
+    /// and weren't included in a previous record.
+    pub blocks: Vec<(Cid, RawBytes)>,
+}
+
+/// A journal of the messages of a batch applied so far, written to an embedder-provided writer so
+/// that execution can be resumed (see [`DefaultExecutor::resume_from_journal`]) instead of
+/// restarted if the node stops midway.
+///
+/// The journal doesn't make the writer crash-consistent: that's up to the embedder.
+pub struct Journal<W> {
+    writer: W,
+    next_index: usize,
+    gas_used: u64,
+    receipts: Vec<Receipt>,
+    /// The blocks included in records so far.
+    journaled_blocks: HashSet<Cid>,
+}
+
+impl<W: Write> Journal<W> {
+    /// Creates an empty journal, for a batch that hasn't been started.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            next_index: 0,
+            gas_used: 0,
+            receipts: Vec::new(),
+            journaled_blocks: HashSet::new(),
+        }
+    }
+
+    /// Returns the index of the next message to apply.
+    pub fn next_index(&self) -> usize {
+        self.next_index
+    }
+
+    /// Returns the gas used by the messages applied so far.
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// Returns the receipts of the messages applied so far, in order.
+    pub fn receipts(&self) -> &[Receipt] {
+        &self.receipts
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    fn append(&mut self, record: JournalRecord) -> anyhow::Result<()> {
+        let data = to_vec(&record).context("failed to encode journal record")?;
+        self.writer
+            .write_all(&(data.len() as u64).to_be_bytes())
+            .and_then(|_| self.writer.write_all(&data))
+            .and_then(|_| self.writer.flush())
+            .context("failed to write journal record")?;
+        self.record_applied(record);
+        Ok(())
+    }
+
+    fn record_applied(&mut self, record: JournalRecord) {
+        self.journaled_blocks
+            .extend(record.blocks.iter().map(|(k, _)| *k));
+        self.next_index += 1;
+        self.gas_used = record.gas_used;
+        self.receipts.push(record.receipt);
+    }
+}
+
+impl<K, B> DefaultExecutor<K>
+where
+    K: Kernel,
+    <K::CallManager as CallManager>::Machine: Machine<Blockstore = BufferedBlockstore<B>>,
+    B: Blockstore,
+{
+    /// Applies the batch's messages as explicit messages, starting from the journal's next index,
+    /// appending a [`JournalRecord`] to the journal after each. Returns the results of the messages
+    /// applied by this call.
+    ///
+    /// The state tree is flushed (but not written to the underlying blockstore) after each message.
+    /// Only state reachable from the state root is journaled, so blocks that aren't (e.g., events)
+    /// are lost when execution is resumed.
+    pub fn apply_journaled<W: Write>(
+        &mut self,
+        journal: &mut Journal<W>,
+        messages: &[ChainMessage],
+    ) -> anyhow::Result<Vec<ApplyRet>> {
+        let remaining = messages.get(journal.next_index..).ok_or_else(|| {
+            anyhow!(
+                "journal is at message {}, but the batch only has {} messages",
+                journal.next_index,
+                messages.len()
+            )
+        })?;
+        remaining
+            .iter()
+            .map(|msg| self.execute_journaled(journal, msg))
+            .collect()
+    }
+
+    /// Restores the state reached by a previous (interrupted) run of
+    /// [`apply_journaled`](Self::apply_journaled) over the same batch, reading its journal from
+    /// `reader`. The executor must be fresh, with a machine constructed exactly as for the
+    /// interrupted run.
+    ///
+    /// Returns a journal that appends to `writer`, to resume the batch with `apply_journaled`. A
+    /// truncated record at the end of the journal (e.g., one written during a crash) is ignored.
+    ///
+    /// Fails if the journal doesn't match the messages, or if replaying a record doesn't lead to
+    /// its state root.
+    pub fn resume_from_journal<R: Read, W: Write>(
+        &mut self,
+        mut reader: R,
+        messages: &[ChainMessage],
+        writer: W,
+    ) -> anyhow::Result<Journal<W>> {
+        let mut journal = Journal::new(writer);
+        while let Some(record) = read_record(&mut reader)? {
+            let index = journal.next_index;
+            if record.index != index as u64 {
+                return Err(anyhow!(
+                    "expected journal record {index}, found record {}",
+                    record.index
+                ));
+            }
+            let msg = messages.get(index).ok_or_else(|| {
+                anyhow!(
+                    "journal has more records than the batch's {} messages",
+                    messages.len()
+                )
+            })?;
+            let expected = message_cid(&msg.message)?;
+            if record.message != expected {
+                return Err(anyhow!(
+                    "journal record {index} is for message {}, expected {expected}",
+                    record.message
+                ));
+            }
+            if Some(record.gas_used) != journal.gas_used.checked_add(record.receipt.gas_used) {
+                return Err(anyhow!(
+                    "journal record {index} has inconsistent gas used {}",
+                    record.gas_used
+                ));
+            }
+
+            for (k, block) in &record.blocks {
+                if *k != Cid::new_v1(k.codec(), Code::Blake2b256.digest(block)) {
+                    return Err(anyhow!("journal record {index} has a corrupt block {k}"));
+                }
+                self.blockstore().put_keyed(k, block)?;
+            }
+            for (id, actor) in &record.actors {
+                match actor {
+                    Some(actor) => self.state_tree_mut().set_actor(*id, actor.clone()),
+                    None => self.state_tree_mut().delete_actor(*id),
+                }
+            }
+            let state_root = self.state_tree_mut().flush()?;
+            if state_root != record.state_root {
+                return Err(anyhow!(
+                    "replaying journal record {index} led to state root {state_root}, expected {}",
+                    record.state_root
+                ));
+            }
+            journal.record_applied(record);
+        }
+        Ok(journal)
+    }
+
+    /// Applies a message, then appends its record to the journal.
+    fn execute_journaled<W: Write>(
+        &mut self,
+        journal: &mut Journal<W>,
+        msg: &ChainMessage,
+    ) -> anyhow::Result<ApplyRet> {
+        let message = message_cid(&msg.message)?;
+
+        // If the machine tracks actor changes, the executor restarts tracking and reports the
+        // changes itself.
+        self.state_tree_mut().begin_tracking_changes();
+        let ret = self.execute_message(msg.message.clone(), ApplyKind::Explicit, msg.raw_length)?;
+        let changes = match &ret.actor_changes {
+            Some(changes) => changes.clone(),
+            None => self
+                .state_tree_mut()
+                .take_changes()?
+                .context("actor changes weren't tracked")?,
+        };
+
+        let mut actors = changes
+            .modified
+            .iter()
+            .chain(&changes.created)
+            .chain(&changes.deleted)
+            .map(|&id| Ok((id, self.state_tree().get_actor(id)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        actors.sort_by_key(|(id, _)| *id);
+
+        let state_root = self.state_tree_mut().flush()?;
+        let blocks = self
+            .blockstore()
+            .buffered_blocks(&state_root, &journal.journaled_blocks)?
+            .into_iter()
+            .map(|(k, block)| (k, RawBytes::new(block)))
+            .collect();
+
+        journal.append(JournalRecord {
+            index: journal.next_index as u64,
+            message,
+            receipt: ret.msg_receipt.clone(),
+            gas_used: journal.gas_used + ret.msg_receipt.gas_used,
+            state_root,
+            actors,
+            blocks,
+        })?;
+        Ok(ret)
+    }
+}
+
+/// Reads the next journal record, returning `None` at the end of the journal, or if the rest of
+/// the journal is a truncated record.
+fn read_record(reader: &mut impl Read) -> anyhow::Result<Option<JournalRecord>> {
+    let mut len = [0u8; 8];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(anyhow!(e).context("failed to read journal")),
+    }
+    let len = u64::from_be_bytes(len);
+    let mut data = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut data)
+        .context("failed to read journal")?;
+    if (data.len() as u64) < len {
+        log::warn!("ignoring a truncated record at the end of the journal");
+        return Ok(None);
+    }
+    Ok(Some(
+        from_slice(&data).context("failed to decode journal record")?,
+    ))
+}
@@ -1,23 +1,37 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+#[cfg(feature = "async")]
+mod async_executor;
 mod default;
+mod journal;
 mod threaded;
+mod tipset;
 
 use std::fmt::Display;
 
+#[cfg(feature = "async")]
+pub use async_executor::{AsyncExecutor, AsyncExecutorError, PendingApply};
 use cid::Cid;
 pub use default::DefaultExecutor;
 use fvm_ipld_encoding::RawBytes;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::event::StampedEvent;
 use fvm_shared::message::Message;
 use fvm_shared::receipt::Receipt;
+pub use journal::{Journal, JournalRecord};
 use num_traits::Zero;
 pub use threaded::ThreadedExecutor;
+pub use tipset::{
+    AwardBlockRewardParams, BLOCK_GAS_LIMIT, BlockMessages, BlockRet, ChainMessage, TipsetRet,
+};
 
 use crate::Kernel;
 use crate::call_manager::Backtrace;
+use crate::machine::WriteStats;
+use crate::state_tree::ActorChanges;
 use crate::trace::ExecutionTrace;
 
 /// An executor executes messages on the underlying machine/kernel. It's responsible for:
@@ -79,6 +93,10 @@ pub struct ApplyRet {
     pub penalty: TokenAmount,
     /// Tip given to miner from message.
     pub miner_tip: TokenAmount,
+    /// The actor credited with the miner tip, if a tip was paid. This is the reward actor unless
+    /// [`MachineContext::block_miner`](crate::machine::MachineContext::block_miner) is set, in
+    /// which case it's the block miner (or the burnt funds actor, if the miner can't be resolved).
+    pub miner_tip_recipient: Option<ActorID>,
 
     // Gas stuffs
     pub base_fee_burn: TokenAmount,
@@ -86,6 +104,10 @@ pub struct ApplyRet {
     pub refund: TokenAmount,
     pub gas_refund: u64,
     pub gas_burned: u64,
+    /// Gas used verifying proofs, limited by
+    /// [`NetworkConfig::proof_gas_limit`](crate::machine::NetworkConfig::proof_gas_limit). This is
+    /// included in the receipt's `gas_used`.
+    pub proof_gas_used: u64,
 
     /// Additional failure information for debugging, if any.
     pub failure_info: Option<ApplyFailure>,
@@ -95,6 +117,23 @@ pub struct ApplyRet {
     pub events: Vec<StampedEvent>,
     /// The IPLD codec of the return data, if any.
     pub return_codec: Option<u64>,
+    /// The actors changed by the message (including gas payments), if
+    /// [`MachineContext::track_actor_changes`](crate::machine::MachineContext::track_actor_changes)
+    /// is enabled.
+    pub actor_changes: Option<ActorChanges>,
+    /// The actors created by the message, in order of creation, along with the addresses they
+    /// were created for: the robust (f2) address assigned by the init actor, or the address an
+    /// account or placeholder actor was implicitly created for by a value transfer. Actors created
+    /// by reverted calls, or deleted by the same message, aren't included.
+    pub created_actors: Vec<(ActorID, Address)>,
+    /// The actors deleted by the message (e.g., self-destructed), in order of deletion. Deletions
+    /// by reverted calls aren't included.
+    pub deleted_actors: Vec<ActorID>,
+    /// Statistics on the blocks written by the message, including those written when flushing the
+    /// state-tree after it, and on how many of them are reachable from the new state root, if
+    /// [`MachineContext::tracing`](crate::machine::MachineContext::tracing) is enabled. Reported by
+    /// [`Machine::end_message_writes`](crate::machine::Machine::end_message_writes).
+    pub write_stats: Option<WriteStats>,
 }
 
 impl ApplyRet {
@@ -113,19 +152,33 @@ impl ApplyRet {
             },
             penalty: miner_penalty,
             miner_tip: TokenAmount::zero(),
+            miner_tip_recipient: None,
             base_fee_burn: TokenAmount::zero(),
             over_estimation_burn: TokenAmount::zero(),
             refund: TokenAmount::zero(),
             gas_refund: 0,
             gas_burned: 0,
+            proof_gas_used: 0,
             failure_info: Some(ApplyFailure::PreValidation(message.into())),
             exec_trace: vec![],
             events: vec![],
             return_codec: None,
+            actor_changes: None,
+            created_actors: vec![],
+            deleted_actors: vec![],
+            write_stats: None,
         }
     }
 }
 
+impl From<ApplyRet> for Receipt {
+    /// Extracts the on-chain receipt, discarding the off-chain execution details (fees, traces,
+    /// events, etc.).
+    fn from(ret: ApplyRet) -> Self {
+        ret.msg_receipt
+    }
+}
+
 /// The kind of message being applied:
 ///
 /// 1. Explicit messages may only come from account actors and charge the sending account for gas
@@ -137,3 +190,22 @@ pub enum ApplyKind {
     Explicit,
     Implicit,
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::to_vec;
+
+    use super::*;
+
+    #[test]
+    fn prevalidation_failure_receipt() {
+        let ret = ApplyRet::prevalidation_fail(
+            ExitCode::SYS_SENDER_INVALID,
+            "unknown sender",
+            TokenAmount::from_atto(100),
+        );
+        let receipt = Receipt::from(ret);
+        // [exit_code, return_data, gas_used, events_root]
+        assert_eq!(to_vec(&receipt).unwrap(), [0x84, 0x01, 0x40, 0x00, 0xf6]);
+    }
+}
@@ -7,6 +7,9 @@ use lazy_static::lazy_static;
 
 use super::{ApplyKind, ApplyRet, Executor};
 
+/// The stack size of the threads messages are executed on (see `EXEC_POOL` for how it was chosen).
+pub(super) const EXEC_STACK_SIZE: usize = 64 << 20;
+
 lazy_static! {
     static ref EXEC_POOL: yastl::Pool = yastl::Pool::with_config(
         std::thread::available_parallelism().map(|n|n.get()).unwrap_or(8),
@@ -17,7 +20,7 @@ lazy_static! {
             // - Roughly 20KiB overhead per actor call
             // - max 1024 nested calls, which means that in the worst case we need ~36MiB of stack
             // We also want some more space just to be conservative, so 64MiB seems like a reasonable choice
-            .stack_size(64 << 20),
+            .stack_size(EXEC_STACK_SIZE),
     );
 }
 
@@ -0,0 +1,255 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use anyhow::{Context as _, anyhow};
+use cid::Cid;
+use fvm_ipld_encoding::RawBytes;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::{ActorID, MethodNum};
+use num_traits::Zero;
+
+use super::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
+use crate::Kernel;
+use crate::machine::{CRON_ACTOR_ID, Machine, REWARD_ACTOR_ID};
+use crate::system_actor::SYSTEM_ACTOR_ID;
+
+/// The maximum amount of gas the messages of a single block may use.
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000_000;
+
+/// The gas limit of the implicit block reward messages.
+const REWARD_GAS_LIMIT: u64 = 1 << 30;
+
+/// The gas limit of the implicit cron message.
+const CRON_GAS_LIMIT: u64 = BLOCK_GAS_LIMIT * 10_000;
+
+/// The reward actor's `AwardBlockReward` method.
+const AWARD_BLOCK_REWARD_METHOD: MethodNum = 2;
+
+/// The cron actor's `EpochTick` method.
+const EPOCH_TICK_METHOD: MethodNum = 2;
+
+/// The parameters of the reward actor's `AwardBlockReward` method.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, Debug, PartialEq, Eq)]
+pub struct AwardBlockRewardParams {
+    pub miner: Address,
+    pub penalty: TokenAmount,
+    pub gas_reward: TokenAmount,
+    pub win_count: i64,
+}
+
+/// A message included in a block.
+#[derive(Clone, Debug)]
+pub struct ChainMessage {
+    pub message: Message,
+    /// The size of the message as it appears on-chain (including the signature, if any), used to
+    /// charge message inclusion gas.
+    pub raw_length: usize,
+}
+
+/// The messages of a single block in a tipset.
+#[derive(Clone, Debug)]
+pub struct BlockMessages {
+    /// The block's miner, who receives the block's gas rewards and pays its penalties.
+    pub miner: Address,
+    /// The number of elections the block's miner won.
+    pub win_count: i64,
+    /// The block's messages, in execution order (BLS messages before secp256k1 messages).
+    pub messages: Vec<ChainMessage>,
+}
+
+/// The result of applying a block's messages as part of a tipset.
+#[derive(Clone, Debug)]
+pub struct BlockRet {
+    /// The results of the block's applied messages, along with their indices in
+    /// [`BlockMessages::messages`]. Skipped messages have no result.
+    pub messages: Vec<(usize, ApplyRet)>,
+    /// The total penalty paid by the block's miner for its messages.
+    pub penalty: TokenAmount,
+    /// The total gas reward (tips) earned by the block's miner for its messages.
+    pub gas_reward: TokenAmount,
+    /// The result of the implicit message awarding the block reward.
+    pub reward: ApplyRet,
+}
+
+/// The result of applying a tipset with [`DefaultExecutor::apply_tipset`].
+#[derive(Clone, Debug)]
+pub struct TipsetRet {
+    /// The results of each block, in order.
+    pub blocks: Vec<BlockRet>,
+    /// The result of the implicit cron message run at the end of the epoch.
+    pub cron: ApplyRet,
+    /// The state root after applying the tipset.
+    pub state_root: Cid,
+}
+
+impl TipsetRet {
+    /// Returns the receipts of the applied messages, in order. These are the receipts committed to
+    /// by the next tipset (see [`build_receipts_root`](crate::machine::build_receipts_root)).
+    pub fn receipts(&self) -> Vec<Receipt> {
+        self.blocks
+            .iter()
+            .flat_map(|block| &block.messages)
+            .map(|(_, ret)| ret.msg_receipt.clone())
+            .collect()
+    }
+}
+
+impl<K> DefaultExecutor<K>
+where
+    K: Kernel,
+{
+    /// Applies a tipset at the machine's epoch: the messages of each block in order, followed by
+    /// the block's reward, then cron. The state tree is flushed once, at the end.
+    ///
+    /// Following the Filecoin tipset rules, a message is skipped (and gets no receipt) unless its
+    /// nonce is the next one expected from its sender. This means that a message included in
+    /// multiple blocks is only applied once, and that messages following a gap in a sender's
+    /// nonces are never applied.
+    ///
+    /// Fails if a reward or cron message fails, as the tipset can't be applied in that case. Also
+    /// fails if [`MachineContext::block_miner`](crate::machine::MachineContext::block_miner) is set,
+    /// as tips must be paid to the reward actor to be paid out to each block's miner.
+    pub fn apply_tipset(&mut self, blocks: &[BlockMessages]) -> anyhow::Result<TipsetRet> {
+        if let Some(miner) = &self.context().block_miner {
+            return Err(anyhow!(
+                "can't apply a tipset with the block miner set to {}",
+                miner
+            ));
+        }
+        let epoch = self.context().epoch;
+
+        // The next nonce expected from each sender.
+        let mut next_nonces = HashMap::new();
+
+        let mut block_rets = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let mut messages = Vec::new();
+            let mut penalty = TokenAmount::zero();
+            let mut gas_reward = TokenAmount::zero();
+            for (i, msg) in block.messages.iter().enumerate() {
+                if !self.select_message(&mut next_nonces, &msg.message)? {
+                    log::debug!(
+                        "skipping message from {} with nonce {}",
+                        msg.message.from,
+                        msg.message.sequence
+                    );
+                    continue;
+                }
+                let ret =
+                    self.execute_message(msg.message.clone(), ApplyKind::Explicit, msg.raw_length)?;
+                penalty += &ret.penalty;
+                gas_reward += &ret.miner_tip;
+                messages.push((i, ret));
+            }
+
+            let params = RawBytes::serialize(AwardBlockRewardParams {
+                miner: block.miner,
+                penalty: penalty.clone(),
+                gas_reward: gas_reward.clone(),
+                win_count: block.win_count,
+            })?;
+            let reward = self
+                .apply_system_message(
+                    epoch,
+                    REWARD_ACTOR_ID,
+                    AWARD_BLOCK_REWARD_METHOD,
+                    params,
+                    REWARD_GAS_LIMIT,
+                )
+                .with_context(|| format!("failed to award block reward to {}", block.miner))?;
+
+            block_rets.push(BlockRet {
+                messages,
+                penalty,
+                gas_reward,
+                reward,
+            });
+        }
+
+        let cron = self
+            .apply_system_message(
+                epoch,
+                CRON_ACTOR_ID,
+                EPOCH_TICK_METHOD,
+                RawBytes::default(),
+                CRON_GAS_LIMIT,
+            )
+            .context("failed to run cron")?;
+
+        let state_root = Executor::flush(self)?;
+
+        Ok(TipsetRet {
+            blocks: block_rets,
+            cron,
+            state_root,
+        })
+    }
+
+    /// Returns true if the message's nonce is the next one expected from its sender, recording that
+    /// the nonce has been used.
+    fn select_message(
+        &self,
+        next_nonces: &mut HashMap<ActorID, u64>,
+        msg: &Message,
+    ) -> anyhow::Result<bool> {
+        // Messages from missing senders can't be ordered, but are applied anyways (and fail).
+        let Some(sender) = self.state_tree().lookup_id(&msg.from)? else {
+            return Ok(true);
+        };
+        let next_nonce = match next_nonces.entry(sender) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => match self.state_tree().get_actor(sender)? {
+                Some(actor) => e.insert(actor.sequence),
+                None => return Ok(true),
+            },
+        };
+        if msg.sequence != *next_nonce {
+            return Ok(false);
+        }
+        *next_nonce += 1;
+        Ok(true)
+    }
+
+    /// Applies an implicit message from the system actor, failing if the message fails.
+    fn apply_system_message(
+        &mut self,
+        epoch: ChainEpoch,
+        to: ActorID,
+        method_num: MethodNum,
+        params: RawBytes,
+        gas_limit: u64,
+    ) -> anyhow::Result<ApplyRet> {
+        let msg = Message {
+            version: 0,
+            from: Address::new_id(SYSTEM_ACTOR_ID),
+            to: Address::new_id(to),
+            sequence: epoch as u64,
+            value: TokenAmount::zero(),
+            method_num,
+            params,
+            gas_limit,
+            gas_fee_cap: TokenAmount::zero(),
+            gas_premium: TokenAmount::zero(),
+        };
+        let ret = self.execute_message(msg, ApplyKind::Implicit, 0)?;
+        if !ret.msg_receipt.exit_code.is_success() {
+            return Err(anyhow!(
+                "message to actor {} failed with exit code {}{}",
+                to,
+                ret.msg_receipt.exit_code,
+                ret.failure_info
+                    .as_ref()
+                    .map(|info| format!(": {info}"))
+                    .unwrap_or_default()
+            ));
+        }
+        Ok(ret)
+    }
+}
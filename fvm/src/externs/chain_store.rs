@@ -0,0 +1,495 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! A reference implementation of the randomness and chain externs over a chain store.
+
+use anyhow::{Context, anyhow};
+use cid::Cid;
+use fvm_shared::ActorID;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, MinerPower};
+use fvm_shared::randomness::RANDOMNESS_LENGTH;
+use multihash_codetable::{Code, MultihashDigest};
+
+use super::{Chain, Consensus, Externs, Rand};
+
+/// A beacon entry, as included in block headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeaconEntry {
+    /// The beacon round.
+    pub round: u64,
+    /// The beacon signature.
+    pub data: Vec<u8>,
+}
+
+/// The parts of a (non-null) tipset needed by [`ChainExterns`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TipsetInfo {
+    /// The CID of the tipset key.
+    pub cid: Cid,
+    /// The VRF proof of the smallest ticket in the tipset.
+    pub min_ticket: Vec<u8>,
+    /// The beacon entries included in the tipset's blocks, ordered by round. Blocks don't include
+    /// any entries when the beacon didn't produce any since the previous tipset.
+    pub beacon_entries: Vec<BeaconEntry>,
+}
+
+/// Read access to a chain, by epoch.
+pub trait ChainStore {
+    /// Returns the epoch of the latest tipset in the chain (i.e., the parent of the tipset being
+    /// executed). Randomness can't be drawn from later epochs.
+    fn head_epoch(&self) -> ChainEpoch;
+
+    /// Returns the tipset at the given epoch (at most [`head_epoch`](Self::head_epoch)), or `None`
+    /// if the epoch was a null round.
+    fn tipset(&self, epoch: ChainEpoch) -> anyhow::Result<Option<TipsetInfo>>;
+
+    /// Returns the beacon round whose entry provides the beacon randomness for the given epoch
+    /// (Lotus' `MaxBeaconRoundForEpoch`). See [`DrandSchedule`].
+    fn max_beacon_round_for_epoch(&self, epoch: ChainEpoch) -> u64;
+}
+
+/// The timing of a drand beacon relative to the chain, used to compute the beacon round for an
+/// epoch the way Lotus does from network version 16.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrandSchedule {
+    /// The chain's genesis timestamp, in seconds.
+    pub genesis_time: u64,
+    /// The chain's block time, in seconds.
+    pub block_time: u64,
+    /// The beacon's genesis timestamp, in seconds.
+    pub drand_genesis_time: u64,
+    /// The beacon's period, in seconds.
+    pub drand_period: u64,
+}
+
+impl DrandSchedule {
+    /// Returns the latest beacon round produced before the given epoch's parent was mined.
+    pub fn max_beacon_round_for_epoch(&self, epoch: ChainEpoch) -> u64 {
+        // Lotus computes this with wrapping unsigned arithmetic.
+        let latest_ts = (epoch as u64)
+            .wrapping_mul(self.block_time)
+            .wrapping_add(self.genesis_time)
+            .wrapping_sub(self.block_time);
+        if latest_ts < self.drand_genesis_time {
+            return 1;
+        }
+        // Round 1 starts at the beacon's genesis.
+        (latest_ts - self.drand_genesis_time) / self.drand_period + 1
+    }
+}
+
+/// The maximum number of tipsets walked back when searching for a beacon entry, as in Lotus.
+const MAX_BEACON_LOOKBACK: usize = 20;
+
+/// Implements the [`Rand`] and [`Chain`] externs on top of a [`ChainStore`]. If the store also
+/// implements [`Consensus`], these are [`Externs`].
+///
+/// This follows Lotus' lookups from network version 14: randomness drawn at a null round comes from
+/// the closest later non-null tipset, while tipset CIDs resolve to the closest earlier one. Lookups
+/// at negative epochs resolve to genesis. Beacon randomness comes from the entry for the epoch's
+/// [`max_beacon_round_for_epoch`](ChainStore::max_beacon_round_for_epoch), searched for in at most
+/// 20 tipsets. The randomness externs return the blake2b-256 digest of the ticket or beacon entry:
+/// actors mix in the domain separation tag, epoch and entropy themselves (see
+/// [`fvm_shared::randomness::draw_randomness`]).
+#[derive(Clone, Debug)]
+pub struct ChainExterns<C> {
+    store: C,
+}
+
+impl<C: ChainStore> ChainExterns<C> {
+    pub fn new(store: C) -> Self {
+        Self { store }
+    }
+
+    /// Returns the underlying chain store.
+    pub fn store(&self) -> &C {
+        &self.store
+    }
+
+    /// Returns the epoch and tipset of the closest non-null tipset at or before the given epoch.
+    pub fn lookback(&self, epoch: ChainEpoch) -> anyhow::Result<(ChainEpoch, TipsetInfo)> {
+        self.tipset_by_height(epoch, true)
+    }
+
+    /// Returns the epoch and tipset of the closest non-null tipset at or after the given epoch (up
+    /// to the chain head).
+    pub fn lookforward(&self, epoch: ChainEpoch) -> anyhow::Result<(ChainEpoch, TipsetInfo)> {
+        self.tipset_by_height(epoch, false)
+    }
+
+    fn tipset_by_height(
+        &self,
+        epoch: ChainEpoch,
+        prev: bool,
+    ) -> anyhow::Result<(ChainEpoch, TipsetInfo)> {
+        let head = self.store.head_epoch();
+        if epoch > head {
+            return Err(anyhow!(
+                "cannot look up epoch {epoch} after the chain head {head}"
+            ));
+        }
+        let start = epoch.max(0);
+        let mut epochs: Box<dyn Iterator<Item = ChainEpoch>> = if prev {
+            Box::new((0..=start).rev())
+        } else {
+            Box::new(start..=head)
+        };
+        epochs
+            .find_map(|epoch| {
+                self.store
+                    .tipset(epoch)
+                    .with_context(|| format!("failed to load the tipset at epoch {epoch}"))
+                    .map(|tipset| tipset.map(|tipset| (epoch, tipset)))
+                    .transpose()
+            })
+            .unwrap_or_else(|| Err(anyhow!("no tipset found from epoch {epoch}")))
+    }
+
+    /// Returns the parent of the tipset at the given (non-null) epoch, if it isn't genesis.
+    fn parent(&self, epoch: ChainEpoch) -> anyhow::Result<Option<(ChainEpoch, TipsetInfo)>> {
+        if epoch == 0 {
+            return Ok(None);
+        }
+        self.lookback(epoch - 1).map(Some)
+    }
+
+    /// Returns the latest beacon entry included in the tipset at the given epoch (or after it, if
+    /// it's a null round), or in one of its 19 closest ancestors.
+    fn latest_beacon_entry(&self, epoch: ChainEpoch) -> anyhow::Result<BeaconEntry> {
+        let mut next = Some(self.lookforward(epoch)?);
+        for _ in 0..MAX_BEACON_LOOKBACK {
+            let Some((found, tipset)) = next else {
+                return Err(anyhow!("no beacon entry at or before epoch {epoch}"));
+            };
+            if let Some(entry) = tipset.beacon_entries.into_iter().last() {
+                return Ok(entry);
+            }
+            next = self.parent(found)?;
+        }
+        Err(anyhow!(
+            "no beacon entry in the {MAX_BEACON_LOOKBACK} tipsets before epoch {epoch}"
+        ))
+    }
+
+    /// Returns the entry for the epoch's beacon round, included in the tipset at the given epoch
+    /// (or after it, if it's a null round), or in one of its 19 closest ancestors.
+    fn beacon_entry_for_epoch(&self, epoch: ChainEpoch) -> anyhow::Result<BeaconEntry> {
+        let round = self.store.max_beacon_round_for_epoch(epoch);
+        let mut next = Some(self.lookforward(epoch)?);
+        for _ in 0..MAX_BEACON_LOOKBACK {
+            let Some((found, tipset)) = next else {
+                break;
+            };
+            if let Some(entry) = tipset.beacon_entries.into_iter().find(|e| e.round == round) {
+                return Ok(entry);
+            }
+            next = self.parent(found)?;
+        }
+        Err(anyhow!(
+            "no entry for beacon round {round} (epoch {epoch}) in the {MAX_BEACON_LOOKBACK} tipsets \
+             before it"
+        ))
+    }
+}
+
+impl<C: ChainStore> Rand for ChainExterns<C> {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        let (_, tipset) = self.lookforward(round)?;
+        Ok(digest(&tipset.min_ticket))
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        let entry = if round < 0 {
+            self.latest_beacon_entry(round)?
+        } else {
+            self.beacon_entry_for_epoch(round)?
+        };
+        Ok(digest(&entry.data))
+    }
+}
+
+impl<C: ChainStore> Chain for ChainExterns<C> {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        let (_, tipset) = self.lookback(epoch)?;
+        Ok(tipset.cid)
+    }
+}
+
+impl<C: ChainStore + Consensus> Consensus for ChainExterns<C> {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        self.store.verify_consensus_fault(h1, h2, extra)
+    }
+
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower> {
+        self.store.get_miner_power(miner, epoch)
+    }
+}
+
+impl<C: ChainStore + Consensus> Externs for ChainExterns<C> {}
+
+fn digest(data: &[u8]) -> [u8; RANDOMNESS_LENGTH] {
+    Code::Blake2b256
+        .digest(data)
+        .digest()
+        .try_into()
+        .expect("blake2b-256 digests are 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fvm_ipld_encoding::DAG_CBOR;
+    use fvm_shared::randomness::{DomainSeparationTag, draw_randomness};
+
+    use super::*;
+
+    /// A chain from genesis to epoch 10, with null rounds at epochs 3, 4, and 7. The beacon round
+    /// for epoch `e` is `100 + e`, and each tipset includes the entries produced since its parent,
+    /// except the tipset at epoch 6, which missed entry 106.
+    struct TestChain(BTreeMap<ChainEpoch, TipsetInfo>);
+
+    impl TestChain {
+        fn new() -> Self {
+            let beacon_rounds = |epoch| match epoch {
+                5 => vec![103, 104, 105],
+                6 => vec![],
+                8 => vec![107, 108],
+                _ => vec![100 + epoch as u64],
+            };
+            Self::with_beacon_rounds(10, &[3, 4, 7], beacon_rounds)
+        }
+
+        fn with_beacon_rounds(
+            head: ChainEpoch,
+            null_rounds: &[ChainEpoch],
+            beacon_rounds: impl Fn(ChainEpoch) -> Vec<u64>,
+        ) -> Self {
+            let tipsets = (0..=head)
+                .filter(|epoch| !null_rounds.contains(epoch))
+                .map(|epoch| {
+                    let beacon_entries = beacon_rounds(epoch)
+                        .into_iter()
+                        .map(|round| BeaconEntry {
+                            round,
+                            data: format!("beacon-{round}").into_bytes(),
+                        })
+                        .collect();
+                    let tipset = TipsetInfo {
+                        cid: tipset_cid(epoch),
+                        min_ticket: format!("ticket-{epoch}").into_bytes(),
+                        beacon_entries,
+                    };
+                    (epoch, tipset)
+                })
+                .collect();
+            Self(tipsets)
+        }
+    }
+
+    impl ChainStore for TestChain {
+        fn head_epoch(&self) -> ChainEpoch {
+            *self.0.keys().last().unwrap()
+        }
+
+        fn tipset(&self, epoch: ChainEpoch) -> anyhow::Result<Option<TipsetInfo>> {
+            Ok(self.0.get(&epoch).cloned())
+        }
+
+        fn max_beacon_round_for_epoch(&self, epoch: ChainEpoch) -> u64 {
+            100 + epoch as u64
+        }
+    }
+
+    fn tipset_cid(epoch: ChainEpoch) -> Cid {
+        Cid::new_v1(
+            DAG_CBOR,
+            Code::Blake2b256.digest(format!("tipset-{epoch}").as_bytes()),
+        )
+    }
+
+    fn hex(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    /// blake2b-256("ticket-0")
+    const TICKET_0: &str = "8c25c063c631c0d4cd0206159b3495deb761cc057e06192fe5497dc9b3744c7b";
+    /// blake2b-256("ticket-2")
+    const TICKET_2: &str = "036479afe8e29f97741a1e3c2b59c217dc67d8a25f7ce12bf1febf6b4a5b2646";
+    /// blake2b-256("ticket-5")
+    const TICKET_5: &str = "d5ca7a57adaffc68011b46ec02ff8baad3f1539068f642b6af2cfcaca639d96e";
+    /// blake2b-256("ticket-8")
+    const TICKET_8: &str = "3559f53a0ad2e4462b2d1805ac28a665e2181fdc27e5acc6561fd6e713e7d498";
+    /// blake2b-256("ticket-10")
+    const TICKET_10: &str = "fdee093e7fd5cace92b7eb9f41d3ee7154aabcf84b797a6eb2e951ae11ed921c";
+    /// blake2b-256("beacon-100")
+    const BEACON_100: &str = "5de09857e1c15c69f3cd42d968787be975ed5920cfbd7072a5888d50bf30d7fc";
+    /// blake2b-256("beacon-104")
+    const BEACON_104: &str = "75edb4e654fa6b055923e189fe444978a5e64a409f20ffb93afcccf0346c26bb";
+    /// blake2b-256("beacon-105")
+    const BEACON_105: &str = "0c36c37c44a0a5147433acc3f993afbd87f37799f2ec31821014c8717899f87a";
+    /// blake2b-256("beacon-107")
+    const BEACON_107: &str = "240296cb3398a85f69b06c24099e2ca5884ad80acee26baaba83581190be4dbb";
+    /// blake2b-256("beacon-110")
+    const BEACON_110: &str = "3ab89384fec785166b0580a5082bc3c58d3a3551f4ce825d99e5eea4cc8c28e4";
+    /// blake2b-256("beacon-119")
+    const BEACON_119: &str = "a67785175d3eb6344558af0c682efd7e657d9bd2496b5dd0460908e60f778487";
+
+    #[test]
+    fn lookback() {
+        let externs = ChainExterns::new(TestChain::new());
+        for (epoch, expected) in [
+            (-5, 0),
+            (0, 0),
+            (2, 2),
+            (3, 2),
+            (4, 2),
+            (5, 5),
+            (7, 6),
+            (10, 10),
+        ] {
+            let (found, tipset) = externs.lookback(epoch).unwrap();
+            assert_eq!(found, expected, "lookback from epoch {epoch}");
+            assert_eq!(tipset.cid, tipset_cid(expected));
+            assert_eq!(externs.get_tipset_cid(epoch).unwrap(), tipset_cid(expected));
+        }
+        externs.lookback(11).unwrap_err();
+        externs.get_tipset_cid(11).unwrap_err();
+    }
+
+    #[test]
+    fn lookforward() {
+        let externs = ChainExterns::new(TestChain::new());
+        for (epoch, expected) in [
+            (-5, 0),
+            (0, 0),
+            (2, 2),
+            (3, 5),
+            (4, 5),
+            (5, 5),
+            (7, 8),
+            (10, 10),
+        ] {
+            let (found, tipset) = externs.lookforward(epoch).unwrap();
+            assert_eq!(found, expected, "lookforward from epoch {epoch}");
+            assert_eq!(tipset.cid, tipset_cid(expected));
+        }
+        externs.lookforward(11).unwrap_err();
+    }
+
+    #[test]
+    fn chain_randomness() {
+        let externs = ChainExterns::new(TestChain::new());
+        for (epoch, expected) in [
+            (-1, TICKET_0),
+            (0, TICKET_0),
+            (2, TICKET_2),
+            (3, TICKET_5),
+            (4, TICKET_5),
+            (7, TICKET_8),
+            (10, TICKET_10),
+        ] {
+            assert_eq!(
+                externs.get_chain_randomness(epoch).unwrap(),
+                hex(expected),
+                "chain randomness at epoch {epoch}"
+            );
+        }
+        externs.get_chain_randomness(11).unwrap_err();
+    }
+
+    #[test]
+    fn beacon_randomness() {
+        let externs = ChainExterns::new(TestChain::new());
+        // Entry 104 is included in the tipset after the null round at epoch 4, and entry 107 in the
+        // tipset after the null round at epoch 7. Negative epochs use the latest entry at genesis.
+        for (epoch, expected) in [
+            (-1, BEACON_100),
+            (0, BEACON_100),
+            (4, BEACON_104),
+            (5, BEACON_105),
+            (7, BEACON_107),
+            (10, BEACON_110),
+        ] {
+            assert_eq!(
+                externs.get_beacon_randomness(epoch).unwrap(),
+                hex(expected),
+                "beacon randomness at epoch {epoch}"
+            );
+        }
+        // Entry 106 was never included.
+        externs.get_beacon_randomness(6).unwrap_err();
+        externs.get_beacon_randomness(11).unwrap_err();
+    }
+
+    #[test]
+    fn beacon_lookback_is_capped() {
+        // Only genesis includes beacon entries.
+        let chain = TestChain::with_beacon_rounds(30, &[], |epoch| {
+            if epoch == 0 {
+                (100..=130).collect()
+            } else {
+                vec![]
+            }
+        });
+        let externs = ChainExterns::new(chain);
+        // Genesis is the 20th tipset walked from epoch 19, but the 21st from epoch 20.
+        assert_eq!(externs.get_beacon_randomness(19).unwrap(), hex(BEACON_119));
+        externs.get_beacon_randomness(20).unwrap_err();
+    }
+
+    #[test]
+    fn no_beacon_entries() {
+        let mut chain = TestChain::new();
+        for tipset in chain.0.values_mut() {
+            tipset.beacon_entries.clear();
+        }
+        let externs = ChainExterns::new(chain);
+        externs.get_beacon_randomness(10).unwrap_err();
+        externs.get_beacon_randomness(-1).unwrap_err();
+    }
+
+    #[test]
+    fn drand_schedule() {
+        let schedule = DrandSchedule {
+            genesis_time: 1000,
+            block_time: 30,
+            drand_genesis_time: 1100,
+            drand_period: 3,
+        };
+        // Epoch 4's parent was mined at 1090, before the beacon's genesis.
+        assert_eq!(schedule.max_beacon_round_for_epoch(4), 1);
+        // Epoch 5's parent was mined at 1120, in the beacon's 7th period.
+        assert_eq!(schedule.max_beacon_round_for_epoch(5), 7);
+        assert_eq!(schedule.max_beacon_round_for_epoch(6), 17);
+    }
+
+    #[test]
+    fn drawn_randomness() {
+        let externs = ChainExterns::new(TestChain::new());
+        let chain_rand = externs.get_chain_randomness(4).unwrap();
+        assert_eq!(
+            draw_randomness(
+                &chain_rand,
+                DomainSeparationTag::SealRandomness,
+                4,
+                b"entropy"
+            ),
+            hex("5b6a8f2753f224a4aa576d92d98a29df5044b5ee50b72e7cd87b6e9b7b7e265b"),
+        );
+        let beacon_rand = externs.get_beacon_randomness(7).unwrap();
+        assert_eq!(
+            draw_randomness(&beacon_rand, DomainSeparationTag::EvmPrevRandao, 7, &[]),
+            hex("7482c5ebec76bd9a7a17939f92d7cc4f5a53040ab076c41d9d8ac79c2ba8b295"),
+        );
+    }
+}
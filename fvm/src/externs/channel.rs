@@ -0,0 +1,166 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Externs serviced by another thread, through requests sent over a channel.
+
+use std::sync::mpsc;
+
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_shared::ActorID;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, MinerPower};
+
+use super::{Chain, Consensus, Externs, Rand};
+
+/// The reply to an [`ExternsRequest`].
+#[derive(Debug)]
+pub struct Reply<T>(mpsc::SyncSender<anyhow::Result<T>>);
+
+impl<T> Reply<T> {
+    /// Answers the request. Dropping a reply without answering fails the request.
+    pub fn send(self, result: anyhow::Result<T>) {
+        // The FVM only stops waiting for a reply if it's dropped, so there's nobody to tell.
+        let _ = self.0.send(result);
+    }
+}
+
+/// A call to the externs, made by [`SyncExterns`]. Each variant corresponds to an [`Externs`]
+/// method.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ExternsRequest {
+    /// [`Rand::get_chain_randomness`].
+    ChainRandomness {
+        round: ChainEpoch,
+        reply: Reply<[u8; 32]>,
+    },
+    /// [`Rand::get_beacon_randomness`].
+    BeaconRandomness {
+        round: ChainEpoch,
+        reply: Reply<[u8; 32]>,
+    },
+    /// [`Chain::get_tipset_cid`].
+    TipsetCid {
+        epoch: ChainEpoch,
+        reply: Reply<Cid>,
+    },
+    /// [`Consensus::verify_consensus_fault`].
+    VerifyConsensusFault {
+        h1: Vec<u8>,
+        h2: Vec<u8>,
+        extra: Vec<u8>,
+        reply: Reply<(Option<ConsensusFault>, i64)>,
+    },
+    /// [`Consensus::get_miner_power`].
+    MinerPower {
+        miner: ActorID,
+        epoch: ChainEpoch,
+        reply: Reply<MinerPower>,
+    },
+}
+
+impl ExternsRequest {
+    /// Answers the request by calling the given externs.
+    pub fn serve(self, externs: &impl Externs) {
+        match self {
+            ExternsRequest::ChainRandomness { round, reply } => {
+                reply.send(externs.get_chain_randomness(round))
+            }
+            ExternsRequest::BeaconRandomness { round, reply } => {
+                reply.send(externs.get_beacon_randomness(round))
+            }
+            ExternsRequest::TipsetCid { epoch, reply } => reply.send(externs.get_tipset_cid(epoch)),
+            ExternsRequest::VerifyConsensusFault {
+                h1,
+                h2,
+                extra,
+                reply,
+            } => reply.send(externs.verify_consensus_fault(&h1, &h2, &extra)),
+            ExternsRequest::MinerPower {
+                miner,
+                epoch,
+                reply,
+            } => reply.send(externs.get_miner_power(miner, epoch)),
+        }
+    }
+}
+
+/// [`Externs`] that hand each call to `send` as an [`ExternsRequest`] (e.g., by sending it over a
+/// channel), then block until it's answered. This lets nodes service the externs elsewhere, e.g.
+/// from a task on an async runtime, without the FVM knowing.
+///
+/// Calls block until they're answered, so execution stays deterministic. If a request can't be
+/// sent, or is dropped without being answered, the call fails like any other extern failure. The
+/// requests must be serviced by some other thread than the one executing messages, or execution
+/// will deadlock.
+pub struct SyncExterns<S> {
+    send: S,
+}
+
+impl<S> SyncExterns<S>
+where
+    S: Fn(ExternsRequest) -> anyhow::Result<()>,
+{
+    pub fn new(send: S) -> Self {
+        Self { send }
+    }
+
+    /// Sends the request built around a new reply, and waits for the reply.
+    fn call<T>(&self, request: impl FnOnce(Reply<T>) -> ExternsRequest) -> anyhow::Result<T> {
+        let (tx, rx) = mpsc::sync_channel(1);
+        (self.send)(request(Reply(tx))).context("failed to send externs request")?;
+        rx.recv()
+            .context("externs request dropped without a reply")?
+    }
+}
+
+impl<S> Externs for SyncExterns<S> where S: Fn(ExternsRequest) -> anyhow::Result<()> {}
+
+impl<S> Rand for SyncExterns<S>
+where
+    S: Fn(ExternsRequest) -> anyhow::Result<()>,
+{
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.call(|reply| ExternsRequest::ChainRandomness { round, reply })
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        self.call(|reply| ExternsRequest::BeaconRandomness { round, reply })
+    }
+}
+
+impl<S> Chain for SyncExterns<S>
+where
+    S: Fn(ExternsRequest) -> anyhow::Result<()>,
+{
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self.call(|reply| ExternsRequest::TipsetCid { epoch, reply })
+    }
+}
+
+impl<S> Consensus for SyncExterns<S>
+where
+    S: Fn(ExternsRequest) -> anyhow::Result<()>,
+{
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        self.call(|reply| ExternsRequest::VerifyConsensusFault {
+            h1: h1.to_vec(),
+            h2: h2.to_vec(),
+            extra: extra.to_vec(),
+            reply,
+        })
+    }
+
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower> {
+        self.call(|reply| ExternsRequest::MinerPower {
+            miner,
+            epoch,
+            reply,
+        })
+    }
+}
@@ -3,20 +3,41 @@
 //! This module contains the logic to invoke the node by traversing Boundary A.
 
 use cid::Cid;
+use fvm_shared::ActorID;
 use fvm_shared::clock::ChainEpoch;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, MinerPower};
 
+mod chain_store;
+mod channel;
+pub use chain_store::{BeaconEntry, ChainExterns, ChainStore, DrandSchedule, TipsetInfo};
+pub use channel::{ExternsRequest, Reply, SyncExterns};
+
+/// The methods through which the FVM queries the node.
+///
+/// Extern failures are node problems, not actor problems: the kernel treats every error returned
+/// by an extern as fatal, so actors can't observe (or recover from) them. The message is aborted
+/// with [`SYS_ASSERTION_FAILED`](fvm_shared::error::ExitCode::SYS_ASSERTION_FAILED), consuming its
+/// entire gas limit, and the error is recorded in the message's backtrace. Externs must therefore
+/// only fail when they can't answer, never to reject their inputs.
+///
+/// Externs may block (e.g., on disk or RPC), and can be serviced by another thread with
+/// [`SyncExterns`].
 pub trait Externs: Rand + Consensus + Chain {}
 
 /// Consensus related methods.
 pub trait Consensus {
-    /// Verify a consensus fault.
+    /// Verify a consensus fault. Headers that don't prove a fault, including malformed ones, are
+    /// reported as `None`, not as errors.
     fn verify_consensus_fault(
         &self,
         h1: &[u8],
         h2: &[u8],
         extra: &[u8],
     ) -> anyhow::Result<(Option<ConsensusFault>, i64)>;
+
+    /// Gets the power of a miner at the given epoch (zero if the miner had no power). This is used
+    /// to report consensus faults, which are judged by the miner's power at the fault epoch.
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower>;
 }
 
 /// Randomness provider trait
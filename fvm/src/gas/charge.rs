@@ -25,6 +25,11 @@ pub struct GasCharge {
     /// This is split into a separate field to facilitate benchmarking.
     pub other_gas: Gas,
 
+    /// Whether this charge is for proof verification, counting against the message's proof gas
+    /// limit (see [`NetworkConfig::proof_gas_limit`](crate::machine::NetworkConfig::proof_gas_limit))
+    /// as well as its gas limit.
+    pub proof: bool,
+
     /// Execution time related to this charge, if traced and successfully measured.
     pub elapsed: GasDuration,
 }
@@ -49,10 +54,19 @@ impl GasCharge {
             name,
             compute_gas,
             other_gas,
+            proof: false,
             elapsed: GasDuration::default(),
         }
     }
 
+    /// Creates a proof verification charge (see [`GasCharge::proof`]).
+    pub fn new_proof(name: impl Into<Cow<'static, str>>, compute_gas: Gas, other_gas: Gas) -> Self {
+        Self {
+            proof: true,
+            ..Self::new(name, compute_gas, other_gas)
+        }
+    }
+
     /// Calculates total gas charge (in milligas) by summing compute and
     /// storage gas associated with this charge.
     pub fn total(&self) -> Gas {
@@ -2,6 +2,8 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+#[cfg(feature = "metrics")]
+use std::borrow::Cow;
 use std::cell::{Cell, RefCell};
 use std::fmt::{Debug, Display};
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
@@ -11,9 +13,14 @@ use num_traits::Zero;
 
 pub use self::charge::GasCharge;
 pub use self::outputs::GasOutputs;
-pub use self::price_list::{PriceList, WasmGasPrices, price_list_by_network_version};
+pub use self::price_list::{
+    PriceList, WasmGasPrices, price_list_by_network_version, try_price_list_by_network_version,
+};
 pub use self::timer::{GasDuration, GasInstant, GasTimer};
 use crate::kernel::{ClassifyResult, ExecutionError, Result};
+#[cfg(feature = "metrics")]
+use crate::machine::MachineMetrics;
+use crate::syscall_error;
 
 mod charge;
 mod outputs;
@@ -22,9 +29,17 @@ mod timer;
 
 pub const MILLIGAS_PRECISION: u64 = 1000;
 
+/// The maximum number of charges whose timers may still be running that a [`GasTracker`] keeps,
+/// when collecting metrics.
+#[cfg(feature = "metrics")]
+const MAX_PENDING_TIMINGS: usize = 1 << 10;
+
 /// A typesafe representation of gas (internally stored as milligas).
 ///
-/// - All math operations are _saturating_ and never overflow.
+/// - All math operations are _saturating_ and never overflow. As the [`GasTracker`] caps gas limits
+///   at `i64::MAX` milligas, a charge that saturates (e.g., a per-byte price multiplied by an
+///   absurd length) always exhausts the available gas.
+/// - It's unsigned, so charges can't be negative.
 /// - Enforces correct units by making it impossible to, e.g., get gas squared (by multiplying gas
 ///   by gas).
 /// - Makes it harder to confuse gas and milligas.
@@ -168,7 +183,52 @@ pub struct GasTracker {
     gas_limit: Gas,
     gas_used: Cell<Gas>,
     gas_snapshots: Vec<GasSnapshot>,
+    /// The limit on proof verification gas, counted across the whole message (independent of the
+    /// gas limit snapshots).
+    proof_gas_limit: Option<Gas>,
+    proof_gas_used: Cell<Gas>,
     trace: Option<RefCell<Vec<GasCharge>>>,
+    /// The durations of all charges, if collecting metrics.
+    #[cfg(feature = "metrics")]
+    timings: Option<RefCell<Timings>>,
+}
+
+/// The durations of the charges made while collecting metrics. Charges are aggregated by name as
+/// their timers stop, so memory use doesn't grow with the number of charges.
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct Timings {
+    /// Charges whose timers may still be running.
+    pending: Vec<(Cow<'static, str>, GasDuration)>,
+    /// The durations of the charges whose timers have stopped.
+    recorded: MachineMetrics,
+}
+
+#[cfg(feature = "metrics")]
+impl Timings {
+    fn push(&mut self, name: Cow<'static, str>, elapsed: GasDuration) {
+        if self.pending.len() >= MAX_PENDING_TIMINGS {
+            self.collect();
+            // Timers dropped without being stopped never finish, so drop the oldest charges
+            // rather than growing without bound.
+            if self.pending.len() >= MAX_PENDING_TIMINGS {
+                self.pending.drain(..MAX_PENDING_TIMINGS / 2);
+            }
+        }
+        self.pending.push((name, elapsed));
+    }
+
+    /// Records the durations of the pending charges whose timers have stopped.
+    fn collect(&mut self) {
+        let recorded = &mut self.recorded;
+        self.pending.retain(|(name, elapsed)| match elapsed.get() {
+            Some(elapsed) => {
+                recorded.record(name.clone(), *elapsed);
+                false
+            }
+            None => true,
+        });
+    }
 }
 
 impl GasTracker {
@@ -185,10 +245,41 @@ impl GasTracker {
             gas_limit,
             gas_used: Cell::new(gas_used),
             gas_snapshots: Vec::new(),
+            proof_gas_limit: None,
+            proof_gas_used: Cell::new(Gas::zero()),
             trace: enable_tracing.then_some(Default::default()),
+            #[cfg(feature = "metrics")]
+            timings: None,
         }
     }
 
+    /// Collects the durations of all charges, to be retrieved with
+    /// [`GasTracker::take_metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, enable: bool) -> Self {
+        self.timings = enable.then_some(Default::default());
+        self
+    }
+
+    /// Returns true if charges are timed, for the trace or the metrics.
+    fn timing(&self) -> bool {
+        #[cfg(feature = "metrics")]
+        if self.timings.is_some() {
+            return true;
+        }
+        self.trace.is_some()
+    }
+
+    /// Limits the gas charged for proof verification (see [`GasCharge::proof`]), in addition to the
+    /// gas limit. Proof verification charges that would exceed it fail with
+    /// [`LimitExceeded`](fvm_shared::error::ErrorNumber::LimitExceeded), without charging any gas.
+    ///
+    /// `None` (the default) means unlimited.
+    pub fn with_proof_gas_limit(mut self, limit: Option<Gas>) -> Self {
+        self.proof_gas_limit = limit;
+        self
+    }
+
     fn charge_gas_inner(&self, to_use: Gas) -> Result<()> {
         // The gas type uses saturating math.
         let gas_used = self.gas_used.get() + to_use;
@@ -207,10 +298,8 @@ impl GasTracker {
     pub fn charge_gas(&self, name: &str, to_use: Gas) -> Result<GasTimer> {
         log::trace!("charging gas: {} {}", name, to_use);
         let res = self.charge_gas_inner(to_use);
-        if let Some(trace) = &self.trace {
-            let mut charge = GasCharge::new(name.to_owned(), to_use, Gas::zero());
-            let timer = GasTimer::new(&mut charge.elapsed);
-            trace.borrow_mut().push(charge);
+        if self.timing() {
+            let timer = self.start_timer(GasCharge::new(name.to_owned(), to_use, Gas::zero()));
             res.map(|_| timer)
         } else {
             res.map(|_| GasTimer::empty())
@@ -218,19 +307,46 @@ impl GasTracker {
     }
 
     /// Applies the specified gas charge, where quantities are supplied in milligas.
-    pub fn apply_charge(&self, mut charge: GasCharge) -> Result<GasTimer> {
+    pub fn apply_charge(&self, charge: GasCharge) -> Result<GasTimer> {
         let to_use = charge.total();
         log::trace!("charging gas: {} {}", &charge.name, to_use);
+        if charge.proof
+            && let Some(limit) = self.proof_gas_limit
+            && self.proof_gas_used.get() + to_use > limit
+        {
+            log::trace!("proof gas limit reached");
+            return Err(
+                syscall_error!(LimitExceeded; "proof gas limit of {} exceeded", limit).into(),
+            );
+        }
         let res = self.charge_gas_inner(to_use);
-        if let Some(trace) = &self.trace {
-            let timer = GasTimer::new(&mut charge.elapsed);
-            trace.borrow_mut().push(charge);
+        if charge.proof && res.is_ok() {
+            self.proof_gas_used.set(self.proof_gas_used.get() + to_use);
+        }
+        if self.timing() {
+            let timer = self.start_timer(charge);
             res.map(|_| timer)
         } else {
             res.map(|_| GasTimer::empty())
         }
     }
 
+    /// Starts timing a charge, recording it in the trace (if tracing) and the metrics (if
+    /// collecting metrics).
+    fn start_timer(&self, mut charge: GasCharge) -> GasTimer {
+        let timer = GasTimer::new(&mut charge.elapsed);
+        #[cfg(feature = "metrics")]
+        if let Some(timings) = &self.timings {
+            timings
+                .borrow_mut()
+                .push(charge.name.clone(), charge.elapsed.clone());
+        }
+        if let Some(trace) = &self.trace {
+            trace.borrow_mut().push(charge);
+        }
+        timer
+    }
+
     /// Push a new gas limit.
     pub fn push_limit(&mut self, new_limit: Gas) {
         self.gas_snapshots.push(GasSnapshot {
@@ -269,6 +385,17 @@ impl GasTracker {
         self.gas_limit - self.gas_used.get()
     }
 
+    /// Getter for the limit on proof verification gas, if any.
+    pub fn proof_gas_limit(&self) -> Option<Gas> {
+        self.proof_gas_limit
+    }
+
+    /// Getter for the gas charged for proof verification. This is also included in
+    /// [`GasTracker::gas_used`].
+    pub fn proof_gas_used(&self) -> Gas {
+        self.proof_gas_used.get()
+    }
+
     pub fn drain_trace(&self) -> impl Iterator<Item = GasCharge> + '_ {
         self.trace
             .as_ref()
@@ -276,6 +403,19 @@ impl GasTracker {
             .into_iter()
             .flatten()
     }
+
+    /// Takes the durations of all timed charges, skipping charges whose timer was never stopped.
+    /// This is empty unless collecting metrics (see [`GasTracker::with_metrics`]).
+    #[cfg(feature = "metrics")]
+    pub fn take_metrics(&self) -> MachineMetrics {
+        let Some(timings) = &self.timings else {
+            return MachineMetrics::default();
+        };
+        let mut timings = timings.borrow_mut();
+        timings.collect();
+        timings.pending.clear();
+        std::mem::take(&mut timings.recorded)
+    }
 }
 
 /// Converts the specified fractional gas units into gas units
@@ -291,9 +431,28 @@ pub(crate) const fn milligas_to_gas(milligas: u64, round_up: bool) -> u64 {
 #[cfg(test)]
 mod tests {
     use num_traits::Zero;
+    use quickcheck_macros::quickcheck;
 
     use super::*;
 
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn metrics_are_bounded() {
+        let t = GasTracker::new(Gas::new(100), Gas::zero(), false).with_metrics(true);
+        let charges = MAX_PENDING_TIMINGS * 4;
+        for _ in 0..charges {
+            t.charge_gas("stopped", Gas::zero()).unwrap().stop();
+            // Never stopped.
+            drop(t.charge_gas("dropped", Gas::zero()).unwrap());
+        }
+        assert!(t.timings.as_ref().unwrap().borrow().pending.len() <= MAX_PENDING_TIMINGS);
+
+        let metrics = t.take_metrics();
+        assert_eq!(metrics.get("stopped").unwrap().count, charges as u64);
+        assert!(metrics.get("dropped").is_none());
+        assert_eq!(t.take_metrics(), MachineMetrics::default());
+    }
+
     #[test]
     #[allow(clippy::identity_op)]
     fn basic_gas_tracker() -> Result<()> {
@@ -309,6 +468,91 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn proof_gas_limit() -> Result<()> {
+        let proof = |gas| GasCharge::new_proof("OnVerifyPost", Gas::new(gas), Gas::zero());
+        let other = |gas| GasCharge::new("OnBlockCreate", Gas::new(gas), Gas::zero());
+
+        let t = GasTracker::new(Gas::new(100), Gas::zero(), false)
+            .with_proof_gas_limit(Some(Gas::new(30)));
+        t.apply_charge(proof(20))?;
+        t.apply_charge(other(50))?;
+        assert_eq!(t.proof_gas_used(), Gas::new(20));
+        assert_eq!(t.gas_used(), Gas::new(70));
+
+        // Exceeding the proof gas limit fails without charging anything, even though there's gas
+        // left.
+        assert!(matches!(
+            t.apply_charge(proof(11)),
+            Err(ExecutionError::Syscall(e)) if e.1 == fvm_shared::error::ErrorNumber::LimitExceeded
+        ));
+        assert_eq!(t.proof_gas_used(), Gas::new(20));
+        assert_eq!(t.gas_used(), Gas::new(70));
+
+        // Other charges, and proof charges that fit, still go through.
+        t.apply_charge(proof(10))?;
+        t.apply_charge(other(10))?;
+        assert_eq!(t.proof_gas_used(), Gas::new(30));
+        assert_eq!(t.gas_used(), Gas::new(90));
+
+        // Proof gas is only counted if the charge fits in the gas limit.
+        let t = GasTracker::new(Gas::new(10), Gas::zero(), false)
+            .with_proof_gas_limit(Some(Gas::new(30)));
+        assert!(matches!(
+            t.apply_charge(proof(20)),
+            Err(ExecutionError::OutOfGas)
+        ));
+        assert!(t.proof_gas_used().is_zero());
+
+        // Without a limit, proof gas is only tracked.
+        let t = GasTracker::new(Gas::new(100), Gas::zero(), false);
+        assert_eq!(t.proof_gas_limit(), None);
+        t.apply_charge(proof(60))?;
+        assert_eq!(t.proof_gas_used(), Gas::new(60));
+        Ok(())
+    }
+
+    #[test]
+    fn saturating_charges_exhaust_gas() {
+        let per_byte = Gas::new(u64::MAX / MILLIGAS_PRECISION);
+        let charge = per_byte * u32::MAX;
+        assert_eq!(charge, Gas::from_milligas(u64::MAX));
+        assert_eq!(Gas::new(1) + charge, charge);
+
+        let t = GasTracker::new(Gas::new(u64::MAX), Gas::new(10), false);
+        assert!(matches!(
+            t.apply_charge(GasCharge::new("", charge, Gas::zero())),
+            Err(ExecutionError::OutOfGas)
+        ));
+        assert_eq!(t.gas_used(), t.gas_limit());
+        assert!(t.gas_available().is_zero());
+    }
+
+    /// The gas tracker never charges more than the limit, and only runs out of gas if a charge
+    /// would take it over the limit.
+    #[quickcheck]
+    fn charges_never_exceed_limit(limit: u64, used: u64, charges: Vec<u64>) {
+        let t = GasTracker::new(Gas::from_milligas(limit), Gas::from_milligas(used), false);
+        let limit = t.gas_limit().as_milligas() as u128;
+        let mut total = t.gas_used().as_milligas() as u128;
+        for charge in charges {
+            total += charge as u128;
+            let res = t.apply_charge(GasCharge::new("", Gas::from_milligas(charge), Gas::zero()));
+            assert!(t.gas_used() <= t.gas_limit());
+            match res {
+                Ok(_) => assert_eq!(t.gas_used().as_milligas() as u128, total),
+                Err(ExecutionError::OutOfGas) => {
+                    // Everything charged so far only exceeds the limit because of the last charge.
+                    assert!(total > limit);
+                    assert!(total - charge as u128 <= limit);
+                    assert_eq!(t.gas_used(), t.gas_limit());
+                    break;
+                }
+                Err(e) => panic!("unexpected error: {e:?}"),
+            }
+        }
+    }
+
     #[test]
     fn milligas_to_gas_round() {
         assert_eq!(milligas_to_gas(100, false), 0);
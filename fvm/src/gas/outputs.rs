@@ -161,4 +161,8 @@ fn gas_outputs_test() {
     do_test(100, 130, 11, 1, 1_000, 60, 0, 130, 240);
     do_test(100, 110, 10, 1, 1_000, 0, 0, 0, 100);
     do_test(100, 110, 6, 1, 600, 0, 400, 0, 60);
+    // Tiny gas limits.
+    do_test(1, 1, 11, 1, 10, 0, 0, 1, 0);
+    do_test(0, 1, 11, 1, 0, 10, 0, 1, 0);
+    do_test(0, 1, 6, 1, 0, 6, 4, 0, 0);
 }
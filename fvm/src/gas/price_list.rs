@@ -122,6 +122,9 @@ lazy_static! {
             flat: Gas::zero(),
             scale: Gas::new(7),
         },
+        // Included in the pairing cost.
+        bls_pub_key_validation: Gas::zero(),
+        bls_pub_key_cache_size: 1024,
         hashing_cost: total_enum_map! {
             SupportedHashes {
                 Sha2_256 => ScalingCost {
@@ -303,11 +306,15 @@ lazy_static! {
             scale: Gas::new(16),
         },
 
+        abort_message: ScalingCost::zero(),
+
         // Preloaded actor IDs per FIP-0055.
         preloaded_actors: vec![0, 1, 2, 3, 4, 5, 6, 7, 10, 99],
 
         ipld_cbor_scan_per_cid: Gas::new(400),
         ipld_cbor_scan_per_field: Gas::new(35),
+        // DAG-CBOR blocks aren't validated before nv29.
+        ipld_dag_cbor_validation: ScalingCost::zero(),
         ipld_link_tracked: Gas::new(300),
         ipld_link_checked: Gas::new(300),
     };
@@ -394,6 +401,25 @@ lazy_static! {
         ].iter().cloned().collect(),
         ..WATERMELON_PRICES.clone()
     };
+
+    #[cfg(feature = "nv29-dev")]
+    static ref NV29_PRICES: PriceList = PriceList {
+        // Copying the message out of memory plus utf8 validation.
+        abort_message: ScalingCost {
+            flat: Gas::new(500),
+            scale: Gas::from_milligas(16_400),
+        },
+        // Public key validation is split out of the pairing cost, so that it's only charged once per
+        // key per message. Each new key still costs one (full) pairing, as before.
+        bls_pairing_cost: Gas::new(6_799_302),
+        bls_pub_key_validation: Gas::new(1_500_000),
+        // A single pass over the block, like utf8 validation.
+        ipld_dag_cbor_validation: ScalingCost {
+            flat: Gas::new(500),
+            scale: Gas::new(16),
+        },
+        ..TEEP_PRICES.clone()
+    };
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
@@ -403,7 +429,8 @@ pub(crate) struct ScalingCost {
 }
 
 impl ScalingCost {
-    /// Computes the scaled cost for the given value, or saturates.
+    /// Computes the scaled cost for the given value, or saturates (exhausting the available gas,
+    /// see [`Gas`]).
     pub fn apply<V>(&self, value: V) -> Gas
     where
         Gas: Mul<V, Output = Gas>,
@@ -437,6 +464,12 @@ pub(crate) struct Step {
     cost: Gas,
 }
 
+/// Converts a lookback into a number of epochs to charge for. A negative lookback (i.e., a future
+/// epoch) saturates, exhausting the available gas.
+fn lookback_epochs(lookback: ChainEpoch) -> u64 {
+    lookback.try_into().unwrap_or(u64::MAX)
+}
+
 impl StepCost {
     pub(crate) fn lookup(&self, x: u64) -> Gas {
         self.0
@@ -495,6 +528,12 @@ pub struct PriceList {
 
     pub(crate) bls_pairing_cost: Gas,
     pub(crate) bls_hashing_cost: ScalingCost,
+    /// Gas cost of validating (deserializing and subgroup-checking) a BLS public key. Charged once
+    /// per key per message (see [`BlsPubKeyCache`](crate::kernel::BlsPubKeyCache)).
+    pub(crate) bls_pub_key_validation: Gas,
+    /// The maximum number of BLS public keys cached per message. Cached keys are charged less gas,
+    /// so changing this changes gas charges.
+    pub(crate) bls_pub_key_cache_size: usize,
 
     pub(crate) hashing_cost: HashMap<SupportedHashes, ScalingCost>,
 
@@ -546,6 +585,9 @@ pub struct PriceList {
     /// Gas cost of utf8 parsing.
     pub(crate) utf8_validation: ScalingCost,
 
+    /// Gas cost of reading an abort message out of actor memory.
+    pub(crate) abort_message: ScalingCost,
+
     /// Gas cost of accessing the network context.
     pub(crate) network_context: Gas,
     /// Gas cost of accessing the message context.
@@ -563,6 +605,9 @@ pub struct PriceList {
     /// Gas cost per CID encountered when parsing CBOR.
     pub(crate) ipld_cbor_scan_per_cid: Gas,
 
+    /// Gas cost of validating a DAG-CBOR block on creation.
+    pub(crate) ipld_dag_cbor_validation: ScalingCost,
+
     /// Gas cost for tracking new reachable links.
     pub(crate) ipld_link_tracked: Gas,
 
@@ -620,6 +665,12 @@ impl PriceList {
     /// Returns the gas required for storing a message of a given size in the chain, plus the cost
     /// of updating the sending actor's nonce and balance in the state-tree.
     #[inline]
+    /// Returns the maximum number of BLS public keys cached per message (see
+    /// [`BlsPubKeyCache`](crate::kernel::BlsPubKeyCache)).
+    pub fn bls_pub_key_cache_size(&self) -> usize {
+        self.bls_pub_key_cache_size
+    }
+
     pub fn on_chain_message(&self, msg_size: usize) -> GasCharge {
         GasCharge::new(
             "OnChainMessage",
@@ -691,9 +742,15 @@ impl PriceList {
         GasCharge::new("OnVerifySignature", gas, Zero::zero())
     }
 
-    /// Returns gas required for BLS aggregate signature verification.
+    /// Returns gas required for BLS aggregate signature verification, where `num_new_keys` of the
+    /// signers' public keys haven't been validated yet in this message.
     #[inline]
-    pub fn on_verify_aggregate_signature(&self, num_sigs: usize, data_len: usize) -> GasCharge {
+    pub fn on_verify_aggregate_signature(
+        &self,
+        num_sigs: usize,
+        num_new_keys: usize,
+        data_len: usize,
+    ) -> GasCharge {
         // When `num_sigs` BLS signatures are aggregated into a single signature, the aggregate
         // signature verifier must perform `num_sigs + 1` expensive pairing operations (one
         // pairing on the aggregate signature, and one pairing for each signed plaintext's digest).
@@ -701,14 +758,15 @@ impl PriceList {
         // Note that `bls_signatures` rearranges the textbook verifier equation (containing
         // `num_sigs + 1` full pairings) into a more efficient equation containing `num_sigs + 1`
         // Miller loops and one final exponentiation.
-        let num_pairings = num_sigs as u64 + 1;
+        let num_pairings = (num_sigs as u64).saturating_add(1);
 
         let gas_pairings = self.bls_pairing_cost * num_pairings;
         let gas_hashing = self.bls_hashing_cost.apply(data_len);
+        let gas_validation = self.bls_pub_key_validation * num_new_keys;
 
         GasCharge::new(
             "OnVerifyBlsAggregateSignature",
-            gas_pairings + gas_hashing,
+            gas_pairings + gas_hashing + gas_validation,
             Zero::zero(),
         )
     }
@@ -754,10 +812,28 @@ impl PriceList {
         )
     }
 
+    /// Returns the gas required for computing a batch of unsealed sector CIDs: the sum of the
+    /// individual computations plus a flat overhead of one host call.
+    pub fn on_compute_unsealed_sector_cids(
+        &self,
+        batch: &[(RegisteredSealProof, Vec<PieceInfo>)],
+    ) -> GasCharge {
+        let compute =
+            batch
+                .iter()
+                .fold(self.wasm_rules.host_call_cost, |total, (proof, pieces)| {
+                    total
+                        + self
+                            .on_compute_unsealed_sector_cid(*proof, pieces)
+                            .compute_gas
+                });
+        GasCharge::new("OnComputeUnsealedSectorCids", compute, Zero::zero())
+    }
+
     /// Returns gas required for seal verification.
     #[inline]
     pub fn on_verify_seal(&self, _info: &SealVerifyInfo) -> GasCharge {
-        GasCharge::new("OnVerifySeal", self.verify_seal_base, Zero::zero())
+        GasCharge::new_proof("OnVerifySeal", self.verify_seal_base, Zero::zero())
     }
     #[inline]
     pub fn on_verify_aggregate_seals(
@@ -788,7 +864,7 @@ impl PriceList {
             });
         // Should be safe because there is a limit to how much seals get aggregated
         let num = aggregate.infos.len() as u64;
-        GasCharge::new(
+        GasCharge::new_proof(
             "OnVerifyAggregateSeals",
             per_proof * num + step.lookup(num),
             Zero::zero(),
@@ -798,7 +874,7 @@ impl PriceList {
     /// Returns gas required for replica verification.
     #[inline]
     pub fn on_verify_replica_update(&self, _replica: &ReplicaUpdateInfo) -> GasCharge {
-        GasCharge::new(
+        GasCharge::new_proof(
             "OnVerifyReplicaUpdate",
             self.verify_replica_update,
             Zero::zero(),
@@ -821,7 +897,7 @@ impl PriceList {
 
         let gas_used = cost.apply(info.challenged_sectors.len());
 
-        GasCharge::new("OnVerifyPost", gas_used, Zero::zero())
+        GasCharge::new_proof("OnVerifyPost", gas_used, Zero::zero())
     }
 
     /// Returns gas required for verifying consensus fault.
@@ -845,7 +921,7 @@ impl PriceList {
         GasCharge::new(
             "OnGetRandomness",
             Zero::zero(),
-            self.lookback_cost.apply(lookback as u64),
+            self.lookback_cost.apply(lookback_epochs(lookback)),
         )
     }
 
@@ -904,6 +980,16 @@ impl PriceList {
         GasCharge::new("OnBlockCreate", compute, retention_surcharge)
     }
 
+    /// Returns the gas required for validating a new DAG-CBOR block.
+    #[inline]
+    pub fn on_block_validate(&self, data_size: usize) -> GasCharge {
+        GasCharge::new(
+            "OnBlockValidate",
+            self.ipld_dag_cbor_validation.apply(data_size),
+            Zero::zero(),
+        )
+    }
+
     /// Returns the gas required for committing an object to the state blockstore.
     #[inline]
     pub fn on_block_link(&self, hash_code: SupportedHashes, data_size: usize) -> GasCharge {
@@ -932,6 +1018,18 @@ impl PriceList {
         GasCharge::new("OnBlockStat", Zero::zero(), Zero::zero())
     }
 
+    /// Returns the gas required for looking up the size of a reachable block by CID. This costs
+    /// the same as the lookup performed when opening a block, but without the per-byte costs as
+    /// the block is never loaded.
+    #[inline]
+    pub fn on_block_stat_by_cid(&self) -> GasCharge {
+        GasCharge::new(
+            "OnBlockStatByCid",
+            self.ipld_link_checked,
+            self.block_open.flat,
+        )
+    }
+
     /// Returns the gas required to lookup an actor in the state-tree.
     #[inline]
     pub fn on_actor_lookup(&self) -> GasCharge {
@@ -1012,7 +1110,18 @@ impl PriceList {
         GasCharge::new(
             "OnTipsetCid",
             Zero::zero(),
-            self.lookback_cost.apply(lookback as u64),
+            self.lookback_cost.apply(lookback_epochs(lookback)),
+        )
+    }
+
+    /// Returns the gas required for looking up a miner's power the given number of epochs ago, when
+    /// reporting a consensus fault.
+    #[inline]
+    pub fn on_miner_power_lookup(&self, lookback: ChainEpoch) -> GasCharge {
+        GasCharge::new(
+            "OnMinerPowerLookup",
+            Zero::zero(),
+            self.lookback_cost.apply(lookback_epochs(lookback)),
         )
     }
 
@@ -1068,11 +1177,24 @@ impl PriceList {
         )
     }
 
+    /// Returns the gas required for reading `len` bytes of an abort message out of actor memory.
+    #[inline]
+    pub fn on_abort(&self, len: usize) -> GasCharge {
+        GasCharge::new("OnAbort", self.abort_message.apply(len), Zero::zero())
+    }
+
     #[inline]
     pub fn on_get_root(&self) -> GasCharge {
         GasCharge::new("OnActorGetRoot", self.ipld_link_tracked, Gas::zero())
     }
 
+    /// Returns the gas required for getting the state root of another actor. Like
+    /// [`PriceList::on_get_root`], this makes the root reachable.
+    #[inline]
+    pub fn on_get_actor_state_root(&self) -> GasCharge {
+        GasCharge::new("OnGetActorStateRoot", self.ipld_link_tracked, Gas::zero())
+    }
+
     #[inline]
     pub fn on_set_root(&self) -> GasCharge {
         GasCharge::new("OnActorSetRoot", self.ipld_link_checked, Gas::zero())
@@ -1080,17 +1202,28 @@ impl PriceList {
 }
 
 /// Returns gas price list by NetworkVersion for gas consumption.
+///
+/// Panics if the network version isn't supported.
 pub fn price_list_by_network_version(network_version: NetworkVersion) -> &'static PriceList {
+    try_price_list_by_network_version(network_version)
+        .unwrap_or_else(|| panic!("network version {nv} not supported", nv = network_version))
+}
+
+/// Returns gas price list by NetworkVersion for gas consumption, or `None` if the network version
+/// isn't supported.
+pub fn try_price_list_by_network_version(
+    network_version: NetworkVersion,
+) -> Option<&'static PriceList> {
     match network_version {
         NetworkVersion::V21 | NetworkVersion::V22 | NetworkVersion::V23 | NetworkVersion::V24 => {
-            &WATERMELON_PRICES
+            Some(&*WATERMELON_PRICES)
         }
         NetworkVersion::V25 | NetworkVersion::V26 | NetworkVersion::V27 | NetworkVersion::V28 => {
-            &TEEP_PRICES
+            Some(&*TEEP_PRICES)
         }
         #[cfg(feature = "nv29-dev")]
-        NetworkVersion::V29 => &TEEP_PRICES,
-        _ => panic!("network version {nv} not supported", nv = network_version),
+        NetworkVersion::V29 => Some(&*NV29_PRICES),
+        _ => None,
     }
 }
 
@@ -1461,9 +1594,132 @@ mod tests {
         randomness::Randomness,
         sector::{
             AggregateSealVerifyInfo, AggregateSealVerifyProofAndInfos, RegisteredAggregateProof,
+            SectorNumber,
         },
     };
 
+    /// Computes a scaling cost with u128 math, saturating at u64::MAX milligas.
+    fn scaled(cost: ScalingCost, value: u64) -> Gas {
+        let milligas =
+            cost.flat.as_milligas() as u128 + cost.scale.as_milligas() as u128 * value as u128;
+        Gas::from_milligas(milligas.try_into().unwrap_or(u64::MAX))
+    }
+
+    #[test]
+    fn test_max_length_charges() {
+        // Per-byte prices multiplied by absurd lengths must scale (or saturate), never wrap.
+        let pl = &*WATERMELON_PRICES;
+        let len = u32::MAX as usize;
+
+        assert_eq!(pl.on_block_open(len, 0).total(), Gas::new(10 * len as u64));
+        assert_eq!(
+            pl.on_block_create(len, 0).total(),
+            Gas::new(10 * len as u64)
+        );
+        assert_eq!(
+            pl.on_chain_message(len).total(),
+            pl.on_chain_message_compute.flat
+                + pl.actor_update
+                + scaled(pl.on_chain_message_storage, len as u64)
+        );
+        assert_eq!(
+            pl.on_hashing(SupportedHashes::Blake2b256, len).total(),
+            scaled(pl.hashing_cost[&SupportedHashes::Blake2b256], len as u64)
+        );
+        assert_eq!(
+            pl.on_verify_aggregate_signature(len, 0, len).total(),
+            scaled(
+                ScalingCost {
+                    flat: Gas::zero(),
+                    scale: pl.bls_pairing_cost
+                },
+                len as u64 + 1
+            ) + scaled(pl.bls_hashing_cost, len as u64)
+        );
+        assert_eq!(
+            pl.on_abort(len).total(),
+            scaled(pl.abort_message, len as u64)
+        );
+        assert_eq!(
+            pl.on_block_validate(len).total(),
+            scaled(pl.ipld_dag_cbor_validation, len as u64)
+        );
+    }
+
+    #[test]
+    fn test_saturated_charges() {
+        let pl = &*WATERMELON_PRICES;
+        let max = Gas::from_milligas(u64::MAX);
+
+        assert_eq!(
+            pl.on_hashing(SupportedHashes::Blake2b256, usize::MAX)
+                .total(),
+            max
+        );
+        assert_eq!(
+            pl.on_verify_aggregate_signature(usize::MAX, 0, 0).total(),
+            max
+        );
+        assert_eq!(pl.on_actor_event(usize::MAX, 0, 0).total(), max);
+        assert_eq!(
+            pl.on_block_link(SupportedHashes::Blake2b256, usize::MAX)
+                .total(),
+            max
+        );
+        // Future epochs have a negative lookback.
+        assert_eq!(pl.on_get_randomness(-1).total(), max);
+        assert_eq!(pl.on_get_randomness(ChainEpoch::MIN).total(), max);
+    }
+
+    #[test]
+    fn test_bls_pub_key_validation_charges() {
+        let pl = PriceList {
+            bls_pub_key_validation: Gas::new(1000),
+            ..WATERMELON_PRICES.clone()
+        };
+        let verification = pl.on_verify_aggregate_signature(3, 0, 100).total();
+        assert_eq!(
+            verification,
+            pl.bls_pairing_cost * 4u64 + pl.bls_hashing_cost.apply(100usize)
+        );
+
+        // Each new key adds a validation charge on top of the verification itself.
+        for new_keys in 1..=3usize {
+            assert_eq!(
+                pl.on_verify_aggregate_signature(3, new_keys, 100).total(),
+                verification + Gas::new(1000) * new_keys
+            );
+        }
+
+        // Which is free in the network versions that include it in the pairing cost.
+        let pl = &*WATERMELON_PRICES;
+        assert_eq!(
+            pl.on_verify_aggregate_signature(3, 3, 100).total(),
+            pl.on_verify_aggregate_signature(3, 0, 100).total()
+        );
+    }
+
+    #[cfg(feature = "nv29-dev")]
+    #[test]
+    fn test_nv29_bls_charges() {
+        // From nv29, validation is split out of the pairing cost: verifying against new keys costs
+        // one pairing per key (as before), while verifying against cached keys costs less.
+        let old = price_list_by_network_version(NetworkVersion::V28);
+        let new = price_list_by_network_version(NetworkVersion::V29);
+        assert_eq!(
+            new.bls_pairing_cost + new.bls_pub_key_validation,
+            old.bls_pairing_cost
+        );
+        assert!(
+            new.on_verify_aggregate_signature(3, 0, 100).total()
+                < new.on_verify_aggregate_signature(3, 3, 100).total()
+        );
+        assert!(
+            new.on_verify_aggregate_signature(3, 3, 100).total()
+                <= old.on_verify_aggregate_signature(3, 3, 100).total()
+        );
+    }
+
     fn create_mock_aggregate(
         proof_type: RegisteredSealProof,
         sector_count: usize,
@@ -1471,9 +1727,9 @@ mod tests {
         let mut infos = Vec::with_capacity(sector_count);
         for i in 0..sector_count {
             infos.push(AggregateSealVerifyInfo {
-                sector_number: i as u64,
-                randomness: Randomness(vec![0u8; 32]),
-                interactive_randomness: Randomness(vec![0u8; 32]),
+                sector_number: SectorNumber::new(i as u64).unwrap(),
+                randomness: Randomness([0u8; 32]),
+                interactive_randomness: Randomness([0u8; 32]),
                 sealed_cid: EMPTY_ARR_CID,
                 unsealed_cid: EMPTY_ARR_CID,
             });
@@ -1487,6 +1743,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_proof_charges_are_tagged() {
+        use fvm_shared::sector::{RegisteredUpdateProof, SectorID};
+
+        let pl = &*WATERMELON_PRICES;
+        let seal = SealVerifyInfo {
+            registered_proof: RegisteredSealProof::StackedDRG32GiBV1P1,
+            sector_id: SectorID::default(),
+            deal_ids: vec![],
+            randomness: Randomness([0u8; 32]),
+            interactive_randomness: Randomness([0u8; 32]),
+            proof: vec![],
+            sealed_cid: EMPTY_ARR_CID,
+            unsealed_cid: EMPTY_ARR_CID,
+        };
+        let replica = ReplicaUpdateInfo {
+            update_proof_type: RegisteredUpdateProof::StackedDRG32GiBV1,
+            old_sealed_cid: EMPTY_ARR_CID,
+            new_sealed_cid: EMPTY_ARR_CID,
+            new_unsealed_cid: EMPTY_ARR_CID,
+            proof: vec![],
+        };
+        let aggregate = create_mock_aggregate(RegisteredSealProof::StackedDRG32GiBV1P1, 4);
+
+        // Proof verification counts against the proof gas limit.
+        for charge in [
+            pl.on_verify_seal(&seal),
+            pl.on_verify_aggregate_seals(&aggregate),
+            pl.on_verify_replica_update(&replica),
+            pl.on_verify_post(&WindowPoStVerifyInfo::default()),
+        ] {
+            assert!(charge.proof, "{} should be a proof charge", charge.name);
+        }
+
+        // Other (crypto) charges don't.
+        for charge in [
+            pl.on_verify_consensus_fault(10, 10, 10),
+            pl.on_miner_power_lookup(10),
+            pl.on_verify_aggregate_signature(2, 2, 10),
+            pl.on_compute_unsealed_sector_cids(&[]),
+            pl.on_hashing(SupportedHashes::Blake2b256, 10),
+            pl.on_block_create(10, 0),
+        ] {
+            assert!(
+                !charge.proof,
+                "{} should not be a proof charge",
+                charge.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_unsealed_sector_cids_gas() {
+        let pricelist = price_list_by_network_version(NetworkVersion::V21);
+        let batch = vec![
+            (RegisteredSealProof::StackedDRG32GiBV1P1, vec![]),
+            (RegisteredSealProof::StackedDRG2KiBV1P1, vec![]),
+            (RegisteredSealProof::Invalid(1000), vec![]),
+        ];
+
+        // A batch costs the sum of the individual computations, plus one host call.
+        let single: Gas = batch
+            .iter()
+            .map(|(proof, pieces)| {
+                pricelist
+                    .on_compute_unsealed_sector_cid(*proof, pieces)
+                    .compute_gas
+            })
+            .fold(Gas::zero(), |a, b| a + b);
+        let host_call = pricelist.wasm_rules.host_call_cost;
+        let batched = pricelist.on_compute_unsealed_sector_cids(&batch);
+        assert_eq!(batched.compute_gas, single + host_call);
+        assert_eq!(batched.other_gas, Gas::zero());
+
+        // Which is cheaper than making each call individually (each of which is a host call).
+        let individual = single + host_call * batch.len() as u32;
+        assert!(batched.total() < individual);
+
+        // An empty batch only pays the overhead.
+        assert_eq!(
+            pricelist.on_compute_unsealed_sector_cids(&[]).compute_gas,
+            host_call
+        );
+    }
+
     #[test]
     fn test_aggregate_porep_gas_charges() {
         for nv in [
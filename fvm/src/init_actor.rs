@@ -31,6 +31,7 @@ pub const INIT_ACTOR_ID: ActorID = 1;
 use crate::kernel::{ClassifyResult, Result};
 
 #[derive(Serialize_tuple, Deserialize_tuple, Debug)]
+#[serde_tuple(optional_trailing)]
 pub struct State {
     pub address_map: Cid,
     pub next_id: ActorID,
@@ -87,7 +88,13 @@ impl State {
 
     /// Allocates a new ID address and stores a mapping of the argument address to it.
     /// Returns the newly-allocated address.
-    pub fn map_address_to_new_id<B>(&mut self, store: B, addr: &Address) -> Result<ActorID>
+    ///
+    /// IDs are allocated sequentially, so they depend only on the order in which addresses are
+    /// registered. This must only be called through [`StateTree::register_new_address`], the single
+    /// allocation path, so that auto-created accounts are assigned IDs in execution order.
+    ///
+    /// [`StateTree::register_new_address`]: crate::state_tree::StateTree::register_new_address
+    pub(crate) fn map_address_to_new_id<B>(&mut self, store: B, addr: &Address) -> Result<ActorID>
     where
         B: Blockstore,
     {
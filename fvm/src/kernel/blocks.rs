@@ -1,6 +1,9 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 use cid::Cid;
@@ -11,10 +14,19 @@ use crate::syscall_error;
 
 /// A registry of open blocks (per-kernel). Think "file descriptor" table. At the moment, there's no
 /// way to close/remove a block from this table.
+///
+/// Blocks with identical contents share a single buffer, but are still assigned distinct handles.
+/// This is purely a memory optimization (e.g., for actors that send the same parameters to many
+/// receivers) and has no effect on gas.
 #[derive(Default)]
 pub struct BlockRegistry {
     blocks: Vec<Block>,
+    origins: Vec<BlockOrigin>,
     reachable: HashSet<Cid>,
+    /// Indices (into `blocks`) of blocks with distinct buffers, keyed by a hash of their contents.
+    by_content: HashMap<u64, Vec<usize>>,
+    /// The total size of the distinct buffers held by this registry.
+    buffered_bytes: usize,
 }
 
 /// Blocks in the block registry are addressed by an ordinal, starting from 1 (`FIRST_ID`).
@@ -25,6 +37,9 @@ pub type BlockId = u32;
 const FIRST_ID: BlockId = 1;
 const MAX_BLOCKS: u32 = i32::MAX as u32; // TODO(M2): Limit
 
+/// The maximum number of blocks listed in a [`BlockRegistrySnapshot`].
+const MAX_SNAPSHOT_BLOCKS: usize = 64;
+
 #[derive(Debug, Copy, Clone)]
 pub struct BlockStat {
     pub codec: u64,
@@ -78,6 +93,25 @@ impl Block {
             size: self.size(),
         }
     }
+
+    /// Returns true if both blocks share the same underlying buffer.
+    pub fn shares_buffer(&self, other: &Block) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// A cheap (non-cryptographic) hash of the block's contents.
+    fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.codec().hash(&mut hasher);
+        self.data().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn same_contents(&self, other: &Block) -> bool {
+        self.codec() == other.codec()
+            && self.data() == other.data()
+            && self.links() == other.links()
+    }
 }
 
 impl From<&Block> for IpldBlock {
@@ -141,10 +175,33 @@ impl BlockRegistry {
         }
 
         let id = FIRST_ID + self.blocks.len() as u32;
+        let block = self.dedup(block);
         self.blocks.push(block);
+        self.origins.push(if check_reachable {
+            BlockOrigin::Created
+        } else {
+            BlockOrigin::Opened
+        });
         Ok(id)
     }
 
+    /// Returns an existing block with the same contents as `block` (sharing its buffer) if there is
+    /// one. Otherwise, records `block` as the canonical copy of its contents and returns it as-is.
+    ///
+    /// Must be called immediately before pushing the returned block.
+    fn dedup(&mut self, block: Block) -> Block {
+        let candidates = self.by_content.entry(block.content_hash()).or_default();
+        if let Some(&idx) = candidates
+            .iter()
+            .find(|&&idx| self.blocks[idx].same_contents(&block))
+        {
+            return self.blocks[idx].clone();
+        }
+        candidates.push(self.blocks.len());
+        self.buffered_bytes += block.data().len();
+        block
+    }
+
     /// Gets the block associated with a block handle.
     pub fn get(&self, id: BlockId) -> Result<&Block> {
         if id < FIRST_ID {
@@ -174,4 +231,115 @@ impl BlockRegistry {
     pub fn is_full(&self) -> bool {
         self.blocks.len() as u32 == MAX_BLOCKS
     }
+
+    /// Returns the total size of the distinct block buffers held by this registry. Blocks with
+    /// identical contents are only counted once.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Takes a diagnostic snapshot of the registry's block metadata, listing at most the 64 most
+    /// recently added blocks. If `preview_len` is specified, each listed block also includes (at
+    /// most) that many bytes of its data.
+    pub fn snapshot(&self, preview_len: Option<usize>) -> BlockRegistrySnapshot {
+        let skip = self.blocks.len().saturating_sub(MAX_SNAPSHOT_BLOCKS);
+        let blocks = (FIRST_ID..)
+            .zip(self.blocks.iter().zip(&self.origins))
+            .skip(skip)
+            .map(|(id, (block, &origin))| BlockSnapshot {
+                id,
+                codec: block.codec(),
+                size: block.size(),
+                links: block.links().len(),
+                origin,
+                preview: preview_len.map(|len| block.data()[..len.min(block.data().len())].into()),
+            })
+            .collect();
+        BlockRegistrySnapshot {
+            block_count: self.blocks.len(),
+            reachable_count: self.reachable.len(),
+            blocks,
+        }
+    }
+}
+
+/// How a block entered the [`BlockRegistry`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlockOrigin {
+    /// The block was opened from the state, or received from another actor (e.g., as parameters
+    /// or a return value).
+    Opened,
+    /// The block was created by the actor.
+    Created,
+}
+
+impl Display for BlockOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockOrigin::Opened => f.write_str("opened"),
+            BlockOrigin::Created => f.write_str("created"),
+        }
+    }
+}
+
+/// A diagnostic snapshot of a [`BlockRegistry`], taken with [`BlockRegistry::snapshot`].
+#[derive(Debug, Clone)]
+pub struct BlockRegistrySnapshot {
+    /// The total number of blocks in the registry.
+    pub block_count: usize,
+    /// The number of CIDs in the registry's reachable set.
+    pub reachable_count: usize,
+    /// The most recently added blocks, in order of their IDs.
+    pub blocks: Vec<BlockSnapshot>,
+}
+
+/// The metadata of a single block in a [`BlockRegistrySnapshot`].
+#[derive(Debug, Clone)]
+pub struct BlockSnapshot {
+    pub id: BlockId,
+    pub codec: u64,
+    pub size: u32,
+    /// The number of CIDs the block links to.
+    pub links: usize,
+    pub origin: BlockOrigin,
+    /// A prefix of the block's data, if requested.
+    pub preview: Option<Box<[u8]>>,
+}
+
+impl Display for BlockRegistrySnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} blocks, {} reachable CIDs",
+            self.block_count, self.reachable_count
+        )?;
+        let omitted = self.block_count - self.blocks.len();
+        if omitted > 0 {
+            write!(f, "; {omitted} earlier blocks omitted")?;
+        }
+        for block in &self.blocks {
+            write!(f, "; {block}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for BlockSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{}: {}, codec {:#x}, {} bytes, {} links",
+            self.id, self.origin, self.codec, self.size, self.links
+        )?;
+        if let Some(preview) = &self.preview {
+            f.write_str(", data ")?;
+            for b in preview.iter() {
+                write!(f, "{b:02x}")?;
+            }
+            if preview.len() < self.size as usize {
+                f.write_str("...")?;
+            }
+        }
+        Ok(())
+    }
 }
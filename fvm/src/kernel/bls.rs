@@ -0,0 +1,138 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use fvm_shared::crypto::signature::BLS_PUB_LEN;
+use fvm_shared::crypto::signature::ops::{BlsPubKey, validate_bls_pub_key};
+
+/// A bounded cache of the BLS public keys validated (deserialized and subgroup-checked) so far in a
+/// message, so that repeated verifications against the same keys skip the validation and are
+/// charged less (see [`PriceList::on_verify_aggregate_signature`](crate::gas::PriceList::on_verify_aggregate_signature)).
+///
+/// Kernels only live for a single call, so the cache is held by the call manager and shared by all
+/// the calls in a message. It starts empty with every message.
+///
+/// Keys are never evicted: once full, further keys are validated (and charged for) once per
+/// verification. The capacity is set by the price list (see
+/// [`PriceList::bls_pub_key_cache_size`](crate::gas::PriceList::bls_pub_key_cache_size)).
+#[derive(Debug)]
+pub struct BlsPubKeyCache {
+    capacity: usize,
+    keys: RefCell<HashMap<[u8; BLS_PUB_LEN], BlsPubKey>>,
+}
+
+impl BlsPubKeyCache {
+    /// Creates a cache holding at most `capacity` keys. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keys: Default::default(),
+        }
+    }
+
+    /// Returns the number of distinct keys in `pub_keys` that aren't cached. This is the number of
+    /// keys [`validate`](Self::validate) may have to validate.
+    pub fn count_uncached(&self, pub_keys: &[[u8; BLS_PUB_LEN]]) -> usize {
+        let keys = self.keys.borrow();
+        pub_keys
+            .iter()
+            .filter(|key| !keys.contains_key(*key))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Validates the given public keys, in order, skipping the cached ones and validating each
+    /// distinct key at most once. Newly validated keys are cached while there's room.
+    ///
+    /// Returns `String` error at the first invalid key.
+    pub fn validate(&self, pub_keys: &[[u8; BLS_PUB_LEN]]) -> Result<Vec<BlsPubKey>, String> {
+        let mut keys = self.keys.borrow_mut();
+        // Keys validated in this call that didn't fit in the cache.
+        let mut overflow = HashMap::new();
+        pub_keys
+            .iter()
+            .map(|bytes| {
+                if let Some(key) = keys.get(bytes).or_else(|| overflow.get(bytes)) {
+                    return Ok(*key);
+                }
+                let key = validate_bls_pub_key(bytes)?;
+                if keys.len() < self.capacity {
+                    keys.insert(*bytes, key);
+                } else {
+                    overflow.insert(*bytes, key);
+                }
+                Ok(key)
+            })
+            .collect()
+    }
+
+    /// Returns the number of cached keys.
+    pub fn len(&self) -> usize {
+        self.keys.borrow().len()
+    }
+
+    /// Returns true if no keys are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: [u8; BLS_PUB_LEN] = [
+        173, 154, 145, 188, 114, 85, 101, 250, 129, 225, 3, 205, 128, 61, 161, 185, 210, 18, 147,
+        84, 160, 15, 233, 114, 178, 113, 115, 142, 4, 221, 81, 215, 188, 151, 11, 87, 4, 110, 23,
+        219, 125, 143, 122, 176, 207, 123, 66, 146,
+    ];
+    const KEY_2: [u8; BLS_PUB_LEN] = [
+        166, 188, 253, 186, 140, 16, 193, 46, 218, 161, 3, 28, 70, 112, 192, 253, 195, 179, 167,
+        181, 197, 130, 19, 216, 51, 188, 86, 179, 88, 40, 161, 215, 116, 189, 157, 29, 27, 61, 144,
+        111, 195, 221, 100, 87, 107, 239, 25, 189,
+    ];
+    /// Not even a compressed curve point.
+    const INVALID_KEY: [u8; BLS_PUB_LEN] = [0; BLS_PUB_LEN];
+
+    #[test]
+    fn caches_valid_keys() {
+        let cache = BlsPubKeyCache::new(16);
+        let keys = [KEY_1, KEY_2, KEY_1];
+        assert_eq!(cache.count_uncached(&keys), 2);
+
+        let validated = cache.validate(&keys).unwrap();
+        assert_eq!(validated.len(), 3);
+        assert_eq!(validated[0], validated[2]);
+        assert_eq!(validated[0], validate_bls_pub_key(&KEY_1).unwrap());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.count_uncached(&keys), 0);
+    }
+
+    #[test]
+    fn invalid_keys_are_not_cached() {
+        let cache = BlsPubKeyCache::new(16);
+        let keys = [KEY_1, INVALID_KEY, KEY_2];
+        assert_eq!(cache.count_uncached(&keys), 3);
+
+        // Validation stops at the invalid key.
+        cache.validate(&keys).unwrap_err();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.count_uncached(&keys), 2);
+    }
+
+    #[test]
+    fn bounded() {
+        let cache = BlsPubKeyCache::new(1);
+        let keys = [KEY_1, KEY_2, KEY_2];
+        cache.validate(&keys).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.count_uncached(&keys), 1);
+
+        // A zero capacity disables caching.
+        let cache = BlsPubKeyCache::new(0);
+        cache.validate(&keys).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(cache.count_uncached(&keys), 2);
+    }
+}
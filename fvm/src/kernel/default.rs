@@ -6,7 +6,7 @@ use std::path::PathBuf;
 use anyhow::{Context as _, anyhow};
 use cid::Cid;
 use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{CBOR, IPLD_RAW};
+use fvm_ipld_encoding::{CBOR, DAG_CBOR, IPLD_RAW, validate_dag_cbor};
 use fvm_shared::crypto::signature;
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::event::{ActorEvent, Entry, Flags};
@@ -25,7 +25,7 @@ use crate::call_manager::{
 use crate::externs::{Chain, Rand};
 use crate::gas::GasTimer;
 use crate::init_actor::INIT_ACTOR_ID;
-use crate::machine::{BURNT_FUNDS_ACTOR_ID, MachineContext, NetworkConfig};
+use crate::machine::{BURNT_FUNDS_ACTOR_ID, MachineContext, NetworkConfig, RandomnessSource};
 use crate::state_tree::ActorState;
 use crate::trace::IpldOperation;
 use crate::{ipld, syscall_error};
@@ -118,6 +118,52 @@ where
     fn get_self(&self) -> Result<Option<ActorState>> {
         self.call_manager.get_actor(self.actor_id)
     }
+
+    /// Returns true if signature verification must reject `signature` because it's a malleable
+    /// (high-s) secp256k1 signature. This is only enforced from nv29 onwards.
+    ///
+    /// Public key recovery never checks this: callers of `recover_secp_public_key` decide for
+    /// themselves whether to accept high-s signatures.
+    #[cfg(feature = "verify-signature")]
+    fn is_malleable_signature(&self, sig_type: SignatureType, signature: &[u8]) -> bool {
+        if sig_type != SignatureType::Secp256k1
+            || self.call_manager.context().network.network_version < NetworkVersion::V29
+        {
+            return false;
+        }
+        // Signatures of the wrong length fail verification anyways.
+        <&[u8; SECP_SIG_LEN]>::try_from(signature).is_ok_and(|signature| {
+            signature::ops::check_low_s(signature) == Err(signature::Error::MalleableSignature)
+        })
+    }
+
+    /// Fetches randomness with `fetch` (from the externs), going through the machine's randomness
+    /// cache (if any). Gas must be charged by the caller, whether or not the randomness is cached.
+    fn cached_randomness(
+        &self,
+        source: RandomnessSource,
+        rand_epoch: ChainEpoch,
+        fetch: impl FnOnce() -> anyhow::Result<[u8; RANDOMNESS_LENGTH]>,
+    ) -> anyhow::Result<[u8; RANDOMNESS_LENGTH]> {
+        match self.call_manager.machine().randomness_cache() {
+            Some(cache) => cache.get_or_fetch(source, rand_epoch, fetch),
+            None => fetch(),
+        }
+    }
+
+    /// Returns how far back the given randomness epoch is. Future epochs are rejected up-front as
+    /// illegal arguments, instead of failing in the externs (which is fatal). Negative epochs and
+    /// the current epoch are left to the externs.
+    fn randomness_lookback(&self, rand_epoch: ChainEpoch) -> Result<ChainEpoch> {
+        let lookback = self.call_manager.context().epoch.saturating_sub(rand_epoch);
+        if lookback < 0 {
+            return Err(
+                syscall_error!(IllegalArgument; "randomness epoch {} is in the future", rand_epoch)
+                    .into(),
+            );
+        }
+        Ok(lookback)
+    }
 }
 
 impl<K> SendOps<K> for DefaultKernel<K::CallManager>
@@ -210,6 +256,23 @@ where
             );
         }
 
+        // Actors may not become builtin actors: the builtin actors are trusted and their code
+        // can't be instantiated by arbitrary actors.
+        if self
+            .call_manager
+            .machine()
+            .builtin_actors()
+            .id_by_code(&new_code_cid)
+            != 0
+        {
+            return Err(syscall_error!(
+                Forbidden,
+                "cannot upgrade to builtin actor code {}",
+                new_code_cid
+            )
+            .into());
+        }
+
         // check if this actor is already on the call stack
         //
         // We first find the first position of this actor on the call stack, and then make sure that
@@ -251,16 +314,14 @@ where
             // sent a message to this actor which in turn called upgrade)
             let code = state.code;
 
-            // update the code cid of the actor to new_code_cid
+            // update the code cid of the actor to new_code_cid, keeping everything else
+            // (including the delegated address)
             cm.set_actor(
                 self.actor_id,
-                ActorState::new(
-                    new_code_cid,
-                    state.state,
-                    state.balance,
-                    state.sequence,
-                    None,
-                ),
+                ActorState {
+                    code: new_code_cid,
+                    ..state
+                },
             )?;
 
             // run the upgrade entrypoint
@@ -338,6 +399,7 @@ where
             .ok_or_else(|| syscall_error!(IllegalOperation; "actor deleted"))?;
         state.state = new;
         self.call_manager.set_actor(self.actor_id, state)?;
+        self.call_manager.trace_set_root(self.actor_id, new);
         Ok(())
     }
 
@@ -434,15 +496,59 @@ where
         Ok((id, stat))
     }
 
+    fn block_stat_by_cid(&self, cid: &Cid) -> Result<BlockStat> {
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_block_stat_by_cid())?;
+
+        if !self.blocks.is_reachable(cid) {
+            return Err(syscall_error!(NotFound; "block not reachable: {cid}").into());
+        }
+
+        let size = self
+            .call_manager
+            .blockstore()
+            .get_size(cid)
+            // Treat missing blocks as errors as well.
+            .and_then(|s| s.ok_or_else(|| anyhow!("missing reachable state: {}", cid)))
+            .or_fatal()?;
+
+        t.stop();
+
+        Ok(BlockStat {
+            codec: cid.codec(),
+            size: size
+                .try_into()
+                .context("reachable block exceeds the maximum block size")
+                .or_fatal()?,
+        })
+    }
+
     fn block_create(&mut self, codec: u64, data: &[u8]) -> Result<BlockId> {
-        if data.len() > self.machine().context().max_block_size {
-            return Err(syscall_error!(LimitExceeded; "blocks may not be larger than 1MiB").into());
+        let max_block_size = self.machine().context().max_block_size;
+        if data.len() > max_block_size {
+            return Err(syscall_error!(LimitExceeded;
+                "blocks may not be larger than {} bytes", max_block_size)
+            .into());
         }
 
         if !ipld::ALLOWED_CODECS.contains(&codec) {
             return Err(syscall_error!(IllegalCodec; "codec {} not allowed", codec).into());
         }
 
+        // From nv29, DAG-CBOR blocks must actually be DAG-CBOR, so that they don't fail to decode
+        // far from where they were created. CBOR and raw blocks are opaque.
+        if codec == DAG_CBOR
+            && self.call_manager.context().network.network_version >= NetworkVersion::V29
+        {
+            let t = self
+                .call_manager
+                .charge_gas(self.call_manager.price_list().on_block_validate(data.len()))?;
+            t.record(validate_dag_cbor(data).map_err(
+                |e| syscall_error!(Serialization; "invalid dag-cbor block: {}", e.description),
+            ))?;
+        }
+
         let children = ipld::scan_for_reachable_links(
             codec,
             data,
@@ -456,6 +562,10 @@ where
                 .on_block_create(data.len(), children.len()),
         )?;
 
+        // Check the per-message limit last, so that only blocks that are actually created count,
+        // but before copying the data out of the actor's memory.
+        self.call_manager.record_block_create(data.len())?;
+
         let blk = Block::new(codec, data, children);
 
         t.record(Ok(self.blocks.put_check_reachable(blk)?))
@@ -539,6 +649,16 @@ where
 
         t.record(Ok(self.blocks.stat(id)?))
     }
+
+    fn block_stat_links(&self, id: BlockId) -> Result<(BlockStat, u32)> {
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_block_stat())?;
+
+        // The links were already scanned (and charged for) when the block was created or opened.
+        let block = self.blocks.get(id)?;
+        t.record(Ok((block.stat(), block.links().len() as u32)))
+    }
 }
 
 impl<C> MessageOps for DefaultKernel<C>
@@ -588,46 +708,88 @@ where
         signature: &[u8],
         signer: &Address,
         plaintext: &[u8],
+    ) -> Result<bool> {
+        self.verify_signature_tagged(sig_type, signature, signer, &[], plaintext)
+    }
+
+    #[cfg(feature = "verify-signature")]
+    fn verify_signature_tagged(
+        &self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        signer: &Address,
+        tag: &[u8],
+        plaintext: &[u8],
     ) -> Result<bool> {
         use fvm_shared::address::Payload;
-        use std::panic::{self, UnwindSafe};
-
-        fn catch_and_log_panic<F: FnOnce() -> Result<R> + UnwindSafe, R>(
-            context: &str,
-            f: F,
-        ) -> Result<R> {
-            match panic::catch_unwind(f) {
-                Ok(v) => v,
-                Err(e) => {
-                    log::error!("caught panic when {}: {:?}", context, e);
-                    Err(
-                        syscall_error!(IllegalArgument; "caught panic when {}: {:?}", context, e)
-                            .into(),
-                    )
-                }
-            }
-        }
+        use std::borrow::Cow;
 
         let t = self.call_manager.charge_gas(
             self.call_manager
                 .price_list()
-                .on_verify_signature(sig_type, plaintext.len()),
+                .on_verify_signature(sig_type, tag.len() + plaintext.len()),
         )?;
 
         // We only support key addresses (f1/f3). This change does not require a FIP, because no
         // actors invoke this method with non-key addresses.
         let signing_addr = match signer.payload() {
             Payload::BLS(_) | Payload::Secp256k1(_) => *signer,
-            // Not a key address.
+            // Not a key address (e.g., an f4 address, whose signature scheme is defined by the
+            // address manager, not the FVM).
             _ => {
-                return Err(syscall_error!(IllegalArgument; "address protocol {} not supported", signer.protocol()).into());
+                return Err(syscall_error!(IllegalArgument; "cannot verify signatures for address protocol {} (not a key address): {}", signer.protocol(), signer).into());
             }
         };
 
+        if self.is_malleable_signature(sig_type, signature) {
+            return t.record(Ok(false));
+        }
+
+        // Avoid copying the plaintext in the common (untagged) case.
+        let message: Cow<[u8]> = if tag.is_empty() {
+            Cow::Borrowed(plaintext)
+        } else {
+            Cow::Owned([tag, plaintext].concat())
+        };
+
         // Verify signature, catching errors. Signature verification can include some complicated
         // math.
         t.record(catch_and_log_panic("verifying signature", || {
-            Ok(signature::verify(sig_type, signature, plaintext, &signing_addr).is_ok())
+            Ok(signature::verify(sig_type, signature, &message, &signing_addr).is_ok())
+        }))
+    }
+
+    #[cfg(feature = "verify-signature")]
+    fn verify_signature_hashed(
+        &self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        signer: &Address,
+        digest: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+    ) -> Result<bool> {
+        use fvm_shared::address::Protocol;
+
+        // The digest has already been computed so we only charge the flat verification cost.
+        let t = self.call_manager.charge_gas(
+            self.call_manager
+                .price_list()
+                .on_verify_signature(sig_type, 0),
+        )?;
+
+        // BLS signatures sign the message itself, so there's no digest to verify against.
+        if sig_type != SignatureType::Secp256k1 {
+            return Err(syscall_error!(IllegalArgument; "hashed signature verification is only supported for secp256k1 signatures").into());
+        }
+        if signer.protocol() != Protocol::Secp256k1 {
+            return Err(syscall_error!(IllegalArgument; "hashed signature verification is not supported for address protocol {}", signer.protocol()).into());
+        }
+
+        if self.is_malleable_signature(sig_type, signature) {
+            return t.record(Ok(false));
+        }
+
+        t.record(catch_and_log_panic("verifying hashed signature", || {
+            Ok(signature::ops::verify_secp256k1_sig_hashed(signature, digest, signer).is_ok())
         }))
     }
 
@@ -648,10 +810,16 @@ where
             .into());
         }
 
+        // Keys validated earlier in the message are cached, and only charged for once.
+        let bls_pub_keys = self.call_manager.bls_pub_keys();
         let t = self.call_manager.charge_gas(
             self.call_manager
                 .price_list()
-                .on_verify_aggregate_signature(num_signers, plaintexts_concat.len()),
+                .on_verify_aggregate_signature(
+                    num_signers,
+                    bls_pub_keys.count_uncached(pub_keys),
+                    plaintexts_concat.len(),
+                ),
         )?;
 
         let mut offset: usize = 0;
@@ -676,7 +844,15 @@ where
         }
 
         t.record(
-            signature::ops::verify_bls_aggregate(aggregate_sig, pub_keys, &plaintexts)
+            bls_pub_keys
+                .validate(pub_keys)
+                .and_then(|pub_keys| {
+                    signature::ops::verify_bls_aggregate_validated(
+                        aggregate_sig,
+                        &pub_keys,
+                        &plaintexts,
+                    )
+                })
                 .or(Ok(false)),
         )
     }
@@ -711,6 +887,20 @@ where
     }
 }
 
+#[cfg(feature = "verify-signature")]
+fn catch_and_log_panic<F: FnOnce() -> Result<R> + std::panic::UnwindSafe, R>(
+    context: &str,
+    f: F,
+) -> Result<R> {
+    match std::panic::catch_unwind(f) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("caught panic when {}: {:?}", context, e);
+            Err(syscall_error!(IllegalArgument; "caught panic when {}: {:?}", context, e).into())
+        }
+    }
+}
+
 impl<C> NetworkOps for DefaultKernel<C>
 where
     C: CallManager,
@@ -778,23 +968,17 @@ where
         &self,
         rand_epoch: ChainEpoch,
     ) -> Result<[u8; RANDOMNESS_LENGTH]> {
-        let lookback = self
-            .call_manager
-            .context()
-            .epoch
-            .checked_sub(rand_epoch)
-            .ok_or_else(|| syscall_error!(IllegalArgument; "randomness epoch {} is in the future", rand_epoch)
-            )?;
+        let lookback = self.randomness_lookback(rand_epoch)?;
 
         let t = self
             .call_manager
             .charge_gas(self.call_manager.price_list().on_get_randomness(lookback))?;
 
         t.record(
-            self.call_manager
-                .externs()
-                .get_chain_randomness(rand_epoch)
-                .or_illegal_argument(),
+            self.cached_randomness(RandomnessSource::Chain, rand_epoch, || {
+                self.call_manager.externs().get_chain_randomness(rand_epoch)
+            })
+            .or_fatal(),
         )
     }
 
@@ -802,22 +986,19 @@ where
         &self,
         rand_epoch: ChainEpoch,
     ) -> Result<[u8; RANDOMNESS_LENGTH]> {
-        let lookback = self
-            .call_manager
-            .context()
-            .epoch
-            .checked_sub(rand_epoch)
-            .ok_or_else(|| syscall_error!(IllegalArgument; "randomness epoch {} is in the future", rand_epoch))?;
+        let lookback = self.randomness_lookback(rand_epoch)?;
 
         let t = self
             .call_manager
             .charge_gas(self.call_manager.price_list().on_get_randomness(lookback))?;
 
         t.record(
-            self.call_manager
-                .externs()
-                .get_beacon_randomness(rand_epoch)
-                .or_illegal_argument(),
+            self.cached_randomness(RandomnessSource::Beacon, rand_epoch, || {
+                self.call_manager
+                    .externs()
+                    .get_beacon_randomness(rand_epoch)
+            })
+            .or_fatal(),
         )
     }
 }
@@ -955,6 +1136,40 @@ where
             .ok_or_else(|| syscall_error!(NotFound; "actor not found"))?
             .delegated_address)
     }
+
+    fn get_actor_state_root(&mut self, actor_id: ActorID) -> Result<Cid> {
+        let t = self
+            .call_manager
+            .charge_gas(self.call_manager.price_list().on_get_actor_state_root())?;
+
+        if !self.call_manager.context().actor_state_inspection {
+            return Err(syscall_error!(Forbidden; "actor state inspection is disabled").into());
+        }
+
+        let state = self
+            .call_manager
+            .get_actor(actor_id)?
+            .ok_or_else(|| syscall_error!(NotFound; "actor not found"))?;
+
+        // Only builtin actors have state layouts stable enough to be read by other actors.
+        if self
+            .call_manager
+            .machine()
+            .builtin_actors()
+            .id_by_code(&state.code)
+            == 0
+        {
+            return Err(syscall_error!(Forbidden;
+                "cannot inspect the state of actor {actor_id}: not a builtin actor")
+            .into());
+        }
+
+        self.blocks.mark_reachable(&state.state);
+
+        t.stop();
+
+        Ok(state.state)
+    }
 }
 
 impl<C> DebugOps for DefaultKernel<C>
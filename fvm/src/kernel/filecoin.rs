@@ -2,28 +2,31 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::collections::BTreeMap;
 use std::convert::TryInto;
-use std::panic::{self, UnwindSafe};
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
 
 use filecoin_proofs_api::{self as proofs, ProverId, PublicReplicaInfo, SectorId};
 
-use fvm_ipld_encoding::bytes_32;
 use fvm_shared::commcid;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultReport};
+use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::{PaddedPieceSize, PieceInfo, zero_piece_commitment};
 use fvm_shared::randomness::Randomness;
 use fvm_shared::sector::{
-    AggregateSealVerifyProofAndInfos, RegisteredPoStProof, RegisteredSealProof, ReplicaUpdateInfo,
-    SealVerifyInfo, SectorInfo, WindowPoStVerifyInfo,
+    AggregateSealVerifyProofAndInfos, InvalidSectorNumber, RegisteredPoStProof,
+    RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo, SectorInfo, SectorNumber,
+    WindowPoStVerifyInfo,
 };
+use fvm_shared::version::NetworkVersion;
 use lazy_static::lazy_static;
 use rayon::iter::{
     IndexedParallelIterator, IntoParallelRefIterator, ParallelDrainRange, ParallelIterator,
 };
 
 use super::Result;
-use super::{ClassifyResult, Context};
+use super::{ClassifyResult, Context, ExecutionError, SyscallError};
 use crate::call_manager::CallManager;
 use crate::externs::Consensus;
+use crate::gas::GasCharge;
 use crate::machine::Machine;
 use crate::{DefaultKernel, Kernel, syscall_error};
 
@@ -43,9 +46,28 @@ pub trait FilecoinKernel: Kernel {
         pieces: &[PieceInfo],
     ) -> Result<cid::Cid>;
 
+    /// Computes the unsealed sector CIDs (CommDs) of a batch of sectors, given each sector's seal
+    /// proof type and pieces.
+    ///
+    /// Returns one result per entry (in order). An entry that fails validation or computation
+    /// (e.g., due to an invalid proof type) yields the error number that
+    /// [`FilecoinKernel::compute_unsealed_sector_cid`] would have returned for it, without failing
+    /// the rest of the batch.
+    fn compute_unsealed_sector_cids(
+        &self,
+        batch: &[(RegisteredSealProof, Vec<PieceInfo>)],
+    ) -> Result<Vec<std::result::Result<cid::Cid, ErrorNumber>>>;
+
     /// Verifies a window proof of spacetime.
     fn verify_post(&self, verify_info: &WindowPoStVerifyInfo) -> Result<bool>;
 
+    /// Verifies a batch of window proofs of spacetime, returning one result per entry (in order).
+    ///
+    /// Gas is charged for every entry (as for [`FilecoinKernel::verify_post`]) before any proofs
+    /// are verified. An entry that's malformed or fails to verify yields `false` without failing
+    /// the rest of the batch.
+    fn verify_post_batch(&self, verify_infos: &[WindowPoStVerifyInfo]) -> Result<Vec<bool>>;
+
     /// Verifies that two block headers provide proof of a consensus fault:
     /// - both headers mined by the same actor
     /// - headers are different
@@ -64,6 +86,16 @@ pub trait FilecoinKernel: Kernel {
         extra: &[u8],
     ) -> Result<Option<ConsensusFault>>;
 
+    /// Verifies a consensus fault like [`FilecoinKernel::verify_consensus_fault`] then, if the
+    /// headers prove a fault, looks up the at-fault miner's power at the fault epoch through the
+    /// externs. Gas is charged for the verification, then for the lookup.
+    fn verify_consensus_fault_report(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFaultReport>>;
+
     /// Verifies a batch of seals. This is a privledged syscall, may _only_ be called by the
     /// power actor during cron.
     ///
@@ -127,6 +159,39 @@ where
         }))
     }
 
+    fn compute_unsealed_sector_cids(
+        &self,
+        batch: &[(RegisteredSealProof, Vec<PieceInfo>)],
+    ) -> Result<Vec<std::result::Result<Cid, ErrorNumber>>> {
+        let t = self.0.call_manager.charge_gas(
+            self.0
+                .call_manager
+                .price_list()
+                .on_compute_unsealed_sector_cids(batch),
+        )?;
+
+        t.record(
+            batch
+                .iter()
+                .enumerate()
+                .map(|(i, (proof_type, pieces))| {
+                    match catch_and_log_panic("computing unsealed sector CID", || {
+                        compute_unsealed_sector_cid(*proof_type, pieces)
+                    }) {
+                        Ok(cid) => Ok(Ok(cid)),
+                        Err(ExecutionError::Syscall(SyscallError(msg, code))) => {
+                            log::debug!(
+                                "failed to compute unsealed sector CID for batch entry {i}: {msg}"
+                            );
+                            Ok(Err(code))
+                        }
+                        Err(e) => Err(e),
+                    }
+                })
+                .collect(),
+        )
+    }
+
     /// Verifies a window proof of spacetime.
     fn verify_post(&self, verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
         let t = self
@@ -135,8 +200,24 @@ where
             .charge_gas(self.0.call_manager.price_list().on_verify_post(verify_info))?;
 
         // This is especially important to catch as, otherwise, a bad "post" could be undisputable.
+        let nv = self.0.call_manager.context().network_version;
         t.record(catch_and_log_panic("verifying post", || {
-            verify_post(verify_info)
+            verify_post(verify_info, nv)
+        }))
+    }
+
+    fn verify_post_batch(&self, verify_infos: &[WindowPoStVerifyInfo]) -> Result<Vec<bool>> {
+        let mut timers = Vec::with_capacity(verify_infos.len());
+        for info in verify_infos {
+            timers.push(
+                self.0
+                    .call_manager
+                    .charge_gas(self.0.call_manager.price_list().on_verify_post(info))?,
+            );
+        }
+        let nv = self.0.call_manager.context().network_version;
+        Ok(verify_post_batch(verify_infos, timers, |info| {
+            verify_post(info, nv)
         }))
     }
 
@@ -161,29 +242,67 @@ where
                 .call_manager
                 .externs()
                 .verify_consensus_fault(h1, h2, extra)
-                .or_illegal_argument(),
+                .or_fatal(),
         )?;
 
         Ok(fault)
     }
 
+    fn verify_consensus_fault_report(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFaultReport>> {
+        let Some(fault) = self.verify_consensus_fault(h1, h2, extra)? else {
+            return Ok(None);
+        };
+        let target = fault
+            .target
+            .id()
+            .or_fatal()
+            .context("externs returned a non-id fault target")?;
+
+        // The fault can't be in the future, but we don't trust the externs to check that.
+        let lookback = (self.0.call_manager.context().epoch - fault.epoch).max(0);
+        let t = self.0.call_manager.charge_gas(
+            self.0
+                .call_manager
+                .price_list()
+                .on_miner_power_lookup(lookback),
+        )?;
+
+        // Like the fault itself, this can't be resolved inside the FVM.
+        let power = t.record(
+            self.0
+                .call_manager
+                .externs()
+                .get_miner_power(target, fault.epoch)
+                .or_fatal(),
+        )?;
+        Ok(Some(ConsensusFaultReport { fault, power }))
+    }
+
     fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
         // NOTE: gas has already been charged by the power actor when the batch verify was enqueued.
-        // Lotus charges "virtual" gas here for tracing only.
+        // Lotus charges "virtual" gas here for tracing only. For the same reason, and because cron
+        // must verify every enqueued seal, this doesn't count against the proof gas limit.
         let mut items = Vec::new();
         for vi in vis {
-            let t = self
-                .0
-                .call_manager
-                .charge_gas(self.0.call_manager.price_list().on_verify_seal(vi))?;
+            let charge = self.0.call_manager.price_list().on_verify_seal(vi);
+            let t = self.0.call_manager.charge_gas(GasCharge {
+                proof: false,
+                ..charge
+            })?;
             items.push((vi, t));
         }
+        let nv = self.0.call_manager.context().network_version;
         log::debug!("batch verify seals start");
         let out = items.par_drain(..)
             .with_min_len(vis.len() / *AVAILABLE_PARALLELISM)
             .map(|(seal, timer)| {
                 let start = GasTimer::start();
-                let verify_seal_result = std::panic::catch_unwind(|| verify_seal(seal));
+                let verify_seal_result = std::panic::catch_unwind(|| verify_seal(seal, nv));
                 let ok = match verify_seal_result {
                     Ok(res) => {
                         match res {
@@ -226,8 +345,9 @@ where
                 .price_list()
                 .on_verify_aggregate_seals(aggregate),
         )?;
+        let nv = self.0.call_manager.context().network_version;
         t.record(catch_and_log_panic("verifying aggregate seals", || {
-            verify_aggregate_seals(aggregate)
+            verify_aggregate_seals(aggregate, nv)
         }))
     }
 
@@ -309,7 +429,53 @@ fn catch_and_log_panic<F: FnOnce() -> Result<R> + UnwindSafe, R>(context: &str,
     }
 }
 
-fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
+/// Verifies a batch of window PoSts in parallel with the given verifier, stopping each entry's gas
+/// timer once it has been verified. Entries that fail to verify, are malformed, or cause the
+/// verifier to panic yield `false`.
+fn verify_post_batch<F>(
+    verify_infos: &[WindowPoStVerifyInfo],
+    mut timers: Vec<GasTimer>,
+    verify: F,
+) -> Vec<bool>
+where
+    F: Fn(&WindowPoStVerifyInfo) -> Result<bool> + Sync,
+{
+    log::debug!("batch verify post start");
+    let out = verify_infos
+        .par_iter()
+        .zip(timers.par_drain(..))
+        .with_min_len(verify_infos.len() / *AVAILABLE_PARALLELISM)
+        .map(|(info, timer)| {
+            let start = GasTimer::start();
+            let verify = AssertUnwindSafe(|| verify(info));
+            let ok = match catch_and_log_panic("verifying post", verify) {
+                Ok(correct) => {
+                    if !correct {
+                        log::debug!(
+                            "post verify in batch failed (miner: {}) (err: Invalid PoSt proof)",
+                            info.prover
+                        );
+                    }
+                    correct
+                }
+                Err(err) => {
+                    log::debug!(
+                        "post verify in batch failed (miner: {}) (err: {})",
+                        info.prover,
+                        err
+                    );
+                    false
+                }
+            };
+            timer.stop_with(start);
+            ok
+        })
+        .collect();
+    log::debug!("batch verify post end");
+    out
+}
+
+fn verify_post(verify_info: &WindowPoStVerifyInfo, nv: NetworkVersion) -> Result<bool> {
     let WindowPoStVerifyInfo {
         proofs,
         challenged_sectors,
@@ -317,7 +483,7 @@ fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
         ..
     } = verify_info;
 
-    let Randomness(mut randomness) = verify_info.randomness.clone();
+    let Randomness(mut randomness) = verify_info.randomness;
 
     // Necessary to be valid bls12 381 element.
     randomness[31] &= 0x3f;
@@ -333,7 +499,7 @@ fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
         }
     }
     // Convert sector info into public replica
-    let replicas = to_fil_public_replica_infos(challenged_sectors, proof_type)?;
+    let replicas = to_fil_public_replica_infos(challenged_sectors, proof_type, nv)?;
 
     // Convert PoSt proofs into proofs-api format
     let proofs: Vec<(proofs::RegisteredPoStProof, _)> = proofs
@@ -346,13 +512,14 @@ fn verify_post(verify_info: &WindowPoStVerifyInfo) -> Result<bool> {
     let prover_id = prover_id_from_u64(*prover);
 
     // Verify Proof
-    proofs::post::verify_window_post(&bytes_32(&randomness), &proofs, &replicas, prover_id)
+    proofs::post::verify_window_post(&randomness, &proofs, &replicas, prover_id)
         .or_illegal_argument()
 }
 
 fn to_fil_public_replica_infos(
     src: &[SectorInfo],
     typ: RegisteredPoStProof,
+    nv: NetworkVersion,
 ) -> Result<BTreeMap<SectorId, PublicReplicaInfo>> {
     let replicas = src
         .iter()
@@ -362,8 +529,10 @@ fn to_fil_public_replica_infos(
                 if !check_valid_proof_type(typ, sector_info.proof) {
                     return Err("invalid proof type".to_string());
                 }
+                let sector_id =
+                    sector_id(sector_info.sector_number, nv).map_err(|e| e.to_string())?;
                 let replica = PublicReplicaInfo::new(typ.try_into()?, commr);
-                Ok((SectorId::from(sector_info.sector_number), replica))
+                Ok((sector_id, replica))
             },
         )
         .collect::<core::result::Result<BTreeMap<SectorId, PublicReplicaInfo>, _>>()
@@ -371,6 +540,18 @@ fn to_fil_public_replica_infos(
     Ok(replicas)
 }
 
+/// Converts a sector number to a sector ID for the proofs API. From nv29, sector numbers above
+/// [`SectorNumber::MAX`] are rejected.
+fn sector_id(
+    number: SectorNumber,
+    nv: NetworkVersion,
+) -> core::result::Result<SectorId, InvalidSectorNumber> {
+    if nv >= NetworkVersion::V29 {
+        number.validate()?;
+    }
+    Ok(SectorId::from(u64::from(number)))
+}
+
 fn check_valid_proof_type(post_type: RegisteredPoStProof, seal_type: RegisteredSealProof) -> bool {
     if let Ok(proof_type_v1p1) = seal_type.registered_window_post_proof() {
         proof_type_v1p1 == post_type
@@ -408,10 +589,11 @@ fn get_required_padding(
     (pad_pieces, PaddedPieceSize(sum))
 }
 
-fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
+fn verify_seal(vi: &SealVerifyInfo, nv: NetworkVersion) -> Result<bool> {
     let commr = commcid::cid_to_replica_commitment_v1(&vi.sealed_cid).or_illegal_argument()?;
     let commd = commcid::cid_to_data_commitment_v1(&vi.unsealed_cid).or_illegal_argument()?;
     let prover_id = prover_id_from_u64(vi.sector_id.miner);
+    let sector_id = sector_id(vi.sector_id.number, nv).or_illegal_argument()?;
 
     proofs::seal::verify_seal(
         vi.registered_proof
@@ -421,9 +603,9 @@ fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
         commr,
         commd,
         prover_id,
-        SectorId::from(vi.sector_id.number),
-        bytes_32(&vi.randomness.0),
-        bytes_32(&vi.interactive_randomness.0),
+        sector_id,
+        vi.randomness.0,
+        vi.interactive_randomness.0,
         &vi.proof,
     )
     .or_illegal_argument()
@@ -434,7 +616,10 @@ fn verify_seal(vi: &SealVerifyInfo) -> Result<bool> {
     .context("failed to verify seal proof")
 }
 
-fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
+fn verify_aggregate_seals(
+    aggregate: &AggregateSealVerifyProofAndInfos,
+    nv: NetworkVersion,
+) -> Result<bool> {
     if aggregate.infos.is_empty() {
         return Err(syscall_error!(IllegalArgument; "no seal verify infos").into());
     }
@@ -455,12 +640,14 @@ fn verify_aggregate_seals(aggregate: &AggregateSealVerifyProofAndInfos) -> Resul
         .map(|info| {
             let commr = commcid::cid_to_replica_commitment_v1(&info.sealed_cid)?;
             let commd = commcid::cid_to_data_commitment_v1(&info.unsealed_cid)?;
+            let sector_id =
+                sector_id(info.sector_number, nv).map_err(|_| "sector number out of range")?;
             Ok(AggregationInputs {
                 commr,
                 commd,
-                ticket: bytes_32(&info.randomness.0),
-                seed: bytes_32(&info.interactive_randomness.0),
-                sector_id: SectorId::from(info.sector_number),
+                ticket: info.randomness.0,
+                seed: info.interactive_randomness.0,
+                sector_id,
             })
         })
         .collect::<core::result::Result<Vec<_>, &'static str>>()
@@ -567,3 +754,102 @@ fn compute_unsealed_sector_cid(
 
     commcid::data_commitment_v1_to_cid(&comm_d).or_illegal_argument()
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::sector::{MAX_SECTOR_NUMBER, PoStProof};
+
+    use super::*;
+
+    fn post_info(proofs: &[(RegisteredPoStProof, &[u8])]) -> WindowPoStVerifyInfo {
+        WindowPoStVerifyInfo {
+            randomness: Randomness([0u8; 32]),
+            proofs: proofs
+                .iter()
+                .map(|&(post_proof, proof_bytes)| PoStProof {
+                    post_proof,
+                    proof_bytes: proof_bytes.to_vec(),
+                })
+                .collect(),
+            challenged_sectors: vec![],
+            prover: 1000,
+        }
+    }
+
+    fn verify_batch(
+        infos: &[WindowPoStVerifyInfo],
+        verify: impl Fn(&WindowPoStVerifyInfo) -> Result<bool> + Sync,
+    ) -> Vec<bool> {
+        let timers = infos.iter().map(|_| GasTimer::empty()).collect();
+        verify_post_batch(infos, timers, verify)
+    }
+
+    #[test]
+    fn verify_post_batch_partial_failures() {
+        const PROOF: RegisteredPoStProof = RegisteredPoStProof::StackedDRGWindow2KiBV1P1;
+
+        // A mock verifier that decides based on the proof bytes.
+        let verify = |info: &WindowPoStVerifyInfo| -> Result<bool> {
+            match &*info.proofs[0].proof_bytes {
+                b"valid" => Ok(true),
+                b"corrupt" => Ok(false),
+                b"malformed" => Err(syscall_error!(IllegalArgument; "malformed proof").into()),
+                _ => panic!("unexpected proof"),
+            }
+        };
+
+        let infos: Vec<_> = [
+            &b"valid"[..],
+            b"corrupt",
+            b"valid",
+            b"malformed",
+            b"panic",
+            b"valid",
+        ]
+        .into_iter()
+        .map(|proof| post_info(&[(PROOF, proof)]))
+        .collect();
+
+        assert_eq!(
+            verify_batch(&infos, verify),
+            [true, false, true, false, false, true]
+        );
+        assert_eq!(verify_batch(&[], verify), Vec::<bool>::new());
+    }
+
+    #[test]
+    fn sector_numbers_checked_from_nv29() {
+        for nv in [NetworkVersion::V28, NetworkVersion::V29] {
+            assert_eq!(
+                sector_id(SectorNumber::MAX, nv).unwrap(),
+                SectorId::from(MAX_SECTOR_NUMBER)
+            );
+        }
+
+        // Out of range sector numbers still decode, and are only rejected from nv29.
+        let number: SectorNumber =
+            fvm_ipld_encoding::from_slice(&fvm_ipld_encoding::to_vec(&u64::MAX).unwrap()).unwrap();
+        assert_eq!(
+            sector_id(number, NetworkVersion::V28).unwrap(),
+            SectorId::from(u64::MAX)
+        );
+        sector_id(number, NetworkVersion::V29).unwrap_err();
+    }
+
+    #[test]
+    fn verify_post_batch_malformed() {
+        let infos = [
+            // Mixed proof types.
+            post_info(&[
+                (RegisteredPoStProof::StackedDRGWindow2KiBV1P1, &[]),
+                (RegisteredPoStProof::StackedDRGWindow8MiBV1P1, &[]),
+            ]),
+            // No proofs at all.
+            post_info(&[]),
+        ];
+        assert_eq!(
+            verify_batch(&infos, |info| verify_post(info, NetworkVersion::V29)),
+            [false, false]
+        );
+    }
+}
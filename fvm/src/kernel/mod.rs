@@ -9,13 +9,17 @@ use crate::machine::limiter::MemoryLimiter;
 use crate::syscalls::Linker;
 
 mod blocks;
+mod bls;
 mod error;
 mod hash;
 
 pub mod default;
 pub mod filecoin;
 
-pub use blocks::{Block, BlockId, BlockRegistry, BlockStat};
+pub use blocks::{
+    Block, BlockId, BlockOrigin, BlockRegistry, BlockRegistrySnapshot, BlockSnapshot, BlockStat,
+};
+pub use bls::BlsPubKeyCache;
 pub use error::{ClassifyResult, Context, ExecutionError, Result, SyscallError};
 pub use hash::SupportedHashes;
 
@@ -148,15 +152,32 @@ pub trait IpldBlockOps {
     /// This method will fail if the block handle is invalid.
     fn block_link(&mut self, id: BlockId, hash_fun: u64, hash_len: u32) -> Result<Cid>;
 
-    /// Read data from a block.
+    /// Read data from a block into `buf`, starting at `offset`. Returns the length of the block
+    /// minus `offset + buf.len()`.
     ///
-    /// This method will fail if the block handle is invalid.
+    /// Reads straddling the end of the block are truncated, and reads starting at or past the end
+    /// of the block copy nothing: neither is an error. Gas is charged on the bytes actually copied,
+    /// not on the size of the buffer.
+    ///
+    /// This method will fail if the block handle is invalid, or if `offset + buf.len()` doesn't
+    /// fit in an `i32`.
     fn block_read(&self, id: BlockId, offset: u32, buf: &mut [u8]) -> Result<i32>;
 
     /// Returns the blocks codec & size.
     ///
     /// This method will fail if the block handle is invalid.
     fn block_stat(&self, id: BlockId) -> Result<BlockStat>;
+
+    /// Returns the blocks codec & size, along with the number of reachable blocks it links to.
+    ///
+    /// This method will fail if the block handle is invalid.
+    fn block_stat_links(&self, id: BlockId) -> Result<(BlockStat, u32)>;
+
+    /// Returns the codec & size of a block by CID, without loading the block.
+    ///
+    /// This method will fail if the requested block isn't reachable, so it can't be used to probe
+    /// for arbitrary blocks in the underlying blockstore.
+    fn block_stat_by_cid(&self, cid: &Cid) -> Result<BlockStat>;
 }
 
 /// Actor state access and manipulation.
@@ -218,6 +239,13 @@ pub trait ActorOps {
 
     /// Returns the balance associated with an actor id
     fn balance_of(&self, actor_id: ActorID) -> Result<TokenAmount>;
+
+    /// Returns the state root of a builtin actor, making it reachable so the calling actor can
+    /// read (but not modify) the actor's state through the IPLD operations. Fails with
+    /// `Forbidden` unless [`NetworkConfig::actor_state_inspection`] is enabled.
+    ///
+    /// [`NetworkConfig::actor_state_inspection`]: crate::machine::NetworkConfig::actor_state_inspection
+    fn get_actor_state_root(&mut self, actor_id: ActorID) -> Result<Cid>;
 }
 
 /// Cryptographic primitives provided by the kernel.
@@ -233,9 +261,38 @@ pub trait CryptoOps {
         plaintext: &[u8],
     ) -> Result<bool>;
 
+    /// Verifies that a signature is valid for an address over the domain-separated plaintext
+    /// `tag || plaintext`. The kernel performs the concatenation (and any hashing required by the
+    /// signature type) itself, charging for every byte of the tag and the plaintext.
+    #[cfg(feature = "verify-signature")]
+    fn verify_signature_tagged(
+        &self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        signer: &Address,
+        tag: &[u8],
+        plaintext: &[u8],
+    ) -> Result<bool>;
+
+    /// Verifies that a signature is valid for an address over an already computed blake2b-256
+    /// message digest.
+    ///
+    /// BLS signatures are verified over the message itself, not its digest, so this method returns
+    /// `Err(IllegalArgument)` for BLS signatures and BLS signers.
+    #[cfg(feature = "verify-signature")]
+    fn verify_signature_hashed(
+        &self,
+        sig_type: SignatureType,
+        signature: &[u8],
+        signer: &Address,
+        digest: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+    ) -> Result<bool>;
+
     /// Verifies a BLS aggregate signature. In the case where there is one signer/signed plaintext,
     /// this is equivalent to verifying a non-aggregated BLS signature.
     ///
+    /// Public keys are validated (and charged for) at most once per message, see [`BlsPubKeyCache`].
+    ///
     /// Returns:
     /// - `Ok(true)` on a valid signature.
     /// - `Ok(false)` on an invalid signature or if the signature or public keys' bytes represent an
@@ -18,6 +18,7 @@ pub mod executor;
 pub mod externs;
 pub mod kernel;
 pub mod machine;
+pub mod migration;
 pub mod syscalls;
 
 pub mod gas;
@@ -51,6 +52,7 @@ mod test {
     use fvm_ipld_encoding::{CborStore, DAG_CBOR};
     use fvm_shared::IDENTITY_HASH;
     use fvm_shared::state::StateTreeVersion;
+    use fvm_shared::version::NetworkVersion;
     use multihash_codetable::{Code, Multihash};
 
     use crate::call_manager::DefaultCallManager;
@@ -58,7 +60,9 @@ mod test {
     use crate::executor;
     use crate::externs::{Chain, Consensus, Externs, Rand};
     use crate::kernel::filecoin::DefaultFilecoinKernel;
-    use crate::machine::{DefaultMachine, Manifest, NetworkConfig};
+    use crate::machine::{
+        ConfigError, DefaultMachine, Machine, MachineContext, Manifest, NetworkConfig,
+    };
     use crate::state_tree::StateTree;
 
     struct DummyExterns;
@@ -94,6 +98,14 @@ mod test {
             // consensus is always valid for tests :)
             Ok((None, 0))
         }
+
+        fn get_miner_power(
+            &self,
+            _miner: fvm_shared::ActorID,
+            _epoch: fvm_shared::clock::ChainEpoch,
+        ) -> anyhow::Result<fvm_shared::consensus::MinerPower> {
+            Ok(Default::default())
+        }
     }
 
     impl Chain for DummyExterns {
@@ -105,8 +117,9 @@ mod test {
         }
     }
 
-    #[test]
-    fn test_constructor() {
+    /// Returns a machine context (for the given network version) with an empty state tree and an
+    /// empty built-in actors manifest, along with the blockstore containing them.
+    fn empty_machine_context(nv: NetworkVersion) -> (MachineContext, MemoryBlockstore) {
         let mut bs = MemoryBlockstore::default();
         let mut st = StateTree::new(bs, StateTreeVersion::V5).unwrap();
         let root = st.flush().unwrap();
@@ -120,9 +133,15 @@ mod test {
 
         let actors_cid = bs.put_cbor(&(1, manifest_cid), Code::Blake2b256).unwrap();
 
-        let mc = NetworkConfig::new(fvm_shared::version::NetworkVersion::V21)
+        let mc = NetworkConfig::new(nv)
             .override_actors(actors_cid)
             .for_epoch(0, 0, root);
+        (mc, bs)
+    }
+
+    #[test]
+    fn test_constructor() {
+        let (mc, bs) = empty_machine_context(NetworkVersion::V21);
 
         let machine = DefaultMachine::new(&mc, bs, DummyExterns).unwrap();
         let engine = EnginePool::new((&mc.network).into()).unwrap();
@@ -131,4 +150,50 @@ mod test {
             Box::new(machine),
         );
     }
+
+    #[test]
+    fn test_constructor_invalid_config() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_call_depth(0);
+        let mc = nc.for_epoch(0, 0, Cid::default());
+
+        let err = DefaultMachine::new(&mc, MemoryBlockstore::default(), DummyExterns)
+            .err()
+            .expect("expected an invalid config error");
+        let err = err.downcast::<ConfigError>().unwrap();
+        assert_eq!(err.field, "max_call_depth");
+    }
+
+    #[test]
+    fn test_build() {
+        let (mc21, bs) = empty_machine_context(NetworkVersion::V21);
+        let machine21 = mc21.build(bs, DummyExterns).unwrap();
+        assert_eq!(machine21.context().network_version, NetworkVersion::V21);
+
+        let (mc25, bs) = empty_machine_context(NetworkVersion::V25);
+        let machine25 = mc25.build(bs, DummyExterns).unwrap();
+        assert_eq!(machine25.context().network_version, NetworkVersion::V25);
+
+        // Each machine gets the price list for its network version.
+        assert!(!std::ptr::eq(
+            machine21.context().price_list,
+            machine25.context().price_list
+        ));
+    }
+
+    #[test]
+    fn test_build_unsupported_network_version() {
+        let err = NetworkConfig::try_new(NetworkVersion::V15).unwrap_err();
+        assert_eq!(err.field, "network_version");
+
+        // Building a machine fails if the network version is changed to an unsupported one.
+        let (mut mc, bs) = empty_machine_context(NetworkVersion::V21);
+        mc.network.network_version = NetworkVersion::V15;
+        let err = mc
+            .build(bs, DummyExterns)
+            .err()
+            .expect("expected an unsupported network version error");
+        let err = err.downcast::<ConfigError>().unwrap();
+        assert_eq!(err.field, "network_version");
+    }
 }
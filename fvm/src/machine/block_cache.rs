@@ -0,0 +1,332 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+
+/// The default maximum total size (in bytes) of the blocks held by a [`CachingBlockstore`].
+pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 64 << 20;
+
+/// Hit and miss counters of a [`CachingBlockstore`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockCacheStats {
+    /// Number of reads served from the cache.
+    pub hits: usize,
+    /// Number of reads forwarded to the underlying blockstore.
+    pub misses: usize,
+}
+
+/// A least-recently-used cache of blocks in front of a (node) blockstore.
+///
+/// Machines are created per epoch (and their write buffer starts empty), so the hot nodes of the
+/// state tree (the HAMT root, the init actor's state, the system actors, etc.) are otherwise read
+/// from the node's blockstore again by every machine. Share a single cache between machines (e.g.,
+/// through an `Rc`) to keep them in memory.
+///
+/// Blocks are immutable, so the cache never needs to be invalidated for correctness: writes go
+/// through to the underlying blockstore and are cached, and [`CachingBlockstore::purge`] only
+/// exists for nodes that delete (e.g., garbage collect) blocks from the underlying store.
+///
+/// The total size of the cached blocks is bounded by `capacity` bytes. Blocks larger than the
+/// capacity aren't cached. This type is not threadsafe and can only be used in synchronous
+/// contexts.
+#[derive(Debug)]
+pub struct CachingBlockstore<BS> {
+    base: BS,
+    capacity: usize,
+    inner: RefCell<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The cached blocks, along with the tick at which they were last used.
+    entries: HashMap<Cid, (Vec<u8>, u64)>,
+    /// The cached CIDs, by the tick at which they were last used.
+    order: BTreeMap<u64, Cid>,
+    /// The total size of the cached blocks.
+    size: usize,
+    tick: u64,
+    stats: BlockCacheStats,
+}
+
+impl Inner {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Returns the cached block, marking it as the most recently used.
+    fn get(&mut self, k: &Cid) -> Option<Vec<u8>> {
+        let tick = self.next_tick();
+        let (block, last_used) = self.entries.get_mut(k)?;
+        self.order.remove(last_used);
+        self.order.insert(tick, *k);
+        *last_used = tick;
+        Some(block.clone())
+    }
+
+    fn insert(&mut self, k: Cid, block: Vec<u8>, capacity: usize) {
+        if capacity == 0 || block.len() > capacity {
+            return;
+        }
+        if self.entries.contains_key(&k) {
+            // Already cached: just mark it as used.
+            self.get(&k);
+            return;
+        }
+        let tick = self.next_tick();
+        self.size += block.len();
+        self.entries.insert(k, (block, tick));
+        self.order.insert(tick, k);
+        while self.size > capacity {
+            let (_, oldest) = self.order.pop_first().expect("cache order is non-empty");
+            self.remove(&oldest);
+        }
+    }
+
+    fn remove(&mut self, k: &Cid) {
+        if let Some((block, last_used)) = self.entries.remove(k) {
+            self.order.remove(&last_used);
+            self.size -= block.len();
+        }
+    }
+}
+
+impl<BS> CachingBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    /// Creates a cache holding at most `capacity` bytes of blocks. A capacity of 0 disables
+    /// caching.
+    pub fn new(base: BS, capacity: usize) -> Self {
+        Self {
+            base,
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    /// Returns the underlying blockstore.
+    pub fn inner(&self) -> &BS {
+        &self.base
+    }
+
+    pub fn into_inner(self) -> BS {
+        self.base
+    }
+
+    /// Removes a block from the cache (but not from the underlying blockstore). Call this when
+    /// deleting the block from the underlying blockstore.
+    pub fn purge(&self, k: &Cid) {
+        self.inner.borrow_mut().remove(k);
+    }
+
+    /// Removes all blocks from the cache. The counters aren't reset.
+    pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.entries.clear();
+        inner.order.clear();
+        inner.size = 0;
+    }
+
+    /// Returns the hit and miss counters.
+    pub fn stats(&self) -> BlockCacheStats {
+        self.inner.borrow().stats
+    }
+
+    /// Returns the total size (in bytes) of the cached blocks.
+    pub fn size(&self) -> usize {
+        self.inner.borrow().size
+    }
+
+    /// Returns the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<BS> Blockstore for CachingBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        {
+            let mut inner = self.inner.borrow_mut();
+            if let Some(block) = inner.get(k) {
+                inner.stats.hits += 1;
+                return Ok(Some(block));
+            }
+            inner.stats.misses += 1;
+        }
+
+        // Don't hold the borrow while reading from the underlying blockstore. Missing blocks and
+        // errors aren't cached.
+        let block = self.base.get(k)?;
+        if let Some(block) = &block {
+            self.inner
+                .borrow_mut()
+                .insert(*k, block.clone(), self.capacity);
+        }
+        Ok(block)
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        if self.inner.borrow().entries.contains_key(k) {
+            return Ok(true);
+        }
+        self.base.has(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        self.base.put_keyed(k, block)?;
+        self.inner
+            .borrow_mut()
+            .insert(*k, block.to_vec(), self.capacity);
+        Ok(())
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        let blocks: Vec<_> = blocks.into_iter().collect();
+        self.base
+            .put_many_keyed(blocks.iter().map(|(k, block)| (*k, block)))?;
+        let mut inner = self.inner.borrow_mut();
+        for (k, block) in blocks {
+            inner.insert(k, block.as_ref().to_vec(), self.capacity);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::tracking::{BSStats, TrackingBlockstore};
+    use fvm_ipld_blockstore::{Block, MemoryBlockstore};
+    use multihash_codetable::Code;
+
+    use super::*;
+
+    fn put(bs: &impl Blockstore, data: &[u8]) -> Cid {
+        bs.put(
+            Code::Blake2b256,
+            &Block::new(fvm_ipld_encoding::IPLD_RAW, data),
+        )
+        .unwrap()
+    }
+
+    fn reads<BS: Blockstore>(bs: &CachingBlockstore<TrackingBlockstore<BS>>) -> usize {
+        bs.inner().stats.borrow().r
+    }
+
+    #[test]
+    fn caches_reads() {
+        let base = MemoryBlockstore::default();
+        let cid = put(&base, b"block");
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(base), 1024);
+
+        for _ in 0..3 {
+            assert_eq!(bs.get(&cid).unwrap().unwrap(), b"block");
+        }
+        assert_eq!(reads(&bs), 1);
+        assert_eq!(bs.stats(), BlockCacheStats { hits: 2, misses: 1 });
+        assert_eq!(bs.size(), 5);
+    }
+
+    #[test]
+    fn writes_through() {
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(MemoryBlockstore::default()), 1024);
+        let cid = put(&bs, b"block");
+        assert_eq!(
+            *bs.inner().stats.borrow(),
+            BSStats {
+                w: 1,
+                bw: 5,
+                ..Default::default()
+            }
+        );
+
+        assert!(bs.has(&cid).unwrap());
+        assert_eq!(bs.get(&cid).unwrap().unwrap(), b"block");
+        assert_eq!(reads(&bs), 0);
+        assert!(bs.inner().has(&cid).unwrap());
+    }
+
+    #[test]
+    fn missing_blocks_are_not_cached() {
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(MemoryBlockstore::default()), 1024);
+        let cid = put(&MemoryBlockstore::default(), b"block");
+        for _ in 0..2 {
+            assert_eq!(bs.get(&cid).unwrap(), None);
+        }
+        assert_eq!(reads(&bs), 2);
+        assert_eq!(bs.stats(), BlockCacheStats { hits: 0, misses: 2 });
+        assert!(bs.is_empty());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let base = MemoryBlockstore::default();
+        let [a, b, c] = [b"aaaa", b"bbbb", b"cccc"].map(|data| put(&base, data));
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(base), 8);
+
+        bs.get(&a).unwrap();
+        bs.get(&b).unwrap();
+        // Use `a` again, so `b` is the least recently used block when `c` is cached.
+        bs.get(&a).unwrap();
+        bs.get(&c).unwrap();
+        assert_eq!(bs.len(), 2);
+        assert_eq!(bs.size(), 8);
+        assert_eq!(reads(&bs), 3);
+
+        bs.get(&a).unwrap();
+        bs.get(&c).unwrap();
+        assert_eq!(reads(&bs), 3);
+        bs.get(&b).unwrap();
+        assert_eq!(reads(&bs), 4);
+    }
+
+    #[test]
+    fn oversized_blocks_are_not_cached() {
+        let base = MemoryBlockstore::default();
+        let cid = put(&base, b"too large");
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(base), 4);
+        bs.get(&cid).unwrap();
+        bs.get(&cid).unwrap();
+        assert_eq!(reads(&bs), 2);
+        assert!(bs.is_empty());
+
+        // A capacity of 0 disables caching.
+        let bs = CachingBlockstore::new(MemoryBlockstore::default(), 0);
+        put(&bs, b"");
+        assert!(bs.is_empty());
+    }
+
+    #[test]
+    fn purge() {
+        let base = MemoryBlockstore::default();
+        let cid = put(&base, b"block");
+        let bs = CachingBlockstore::new(TrackingBlockstore::new(base), 1024);
+        bs.get(&cid).unwrap();
+        bs.purge(&cid);
+        assert!(bs.is_empty());
+        assert_eq!(bs.size(), 0);
+        bs.get(&cid).unwrap();
+        assert_eq!(reads(&bs), 2);
+
+        // Purging a block that isn't cached is a no-op.
+        bs.purge(&put(&MemoryBlockstore::default(), b"other"));
+        assert_eq!(bs.len(), 1);
+    }
+}
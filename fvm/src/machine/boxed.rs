@@ -62,4 +62,36 @@ impl<M: Machine> Machine for Box<M> {
     fn new_limiter(&self) -> Self::Limiter {
         (**self).new_limiter()
     }
+
+    #[inline(always)]
+    fn randomness_cache(&self) -> Option<&super::RandomnessCache> {
+        (**self).randomness_cache()
+    }
+
+    #[inline(always)]
+    fn begin_message_writes(&mut self) {
+        (**self).begin_message_writes()
+    }
+
+    #[inline(always)]
+    fn end_message_writes(&mut self) -> Result<Option<super::WriteStats>> {
+        (**self).end_message_writes()
+    }
+
+    #[inline(always)]
+    fn write_stats(&self) -> Option<&super::WriteStats> {
+        (**self).write_stats()
+    }
+
+    #[cfg(feature = "metrics")]
+    #[inline(always)]
+    fn metrics(&self) -> Option<&super::MachineMetrics> {
+        (**self).metrics()
+    }
+
+    #[cfg(feature = "metrics")]
+    #[inline(always)]
+    fn metrics_mut(&mut self) -> Option<&mut super::MachineMetrics> {
+        (**self).metrics_mut()
+    }
 }
@@ -1,19 +1,20 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use std::ops::RangeInclusive;
+use std::num::NonZeroUsize;
 
 use anyhow::{Context as _, anyhow};
 use cid::Cid;
 use fvm_ipld_blockstore::{Block, Blockstore, Buffered};
 use fvm_ipld_encoding::{CborStore, DAG_CBOR};
-use fvm_shared::version::NetworkVersion;
 use log::debug;
 use multihash_codetable::Code::Blake2b256;
 
-use super::{Machine, MachineContext};
+use super::{Machine, MachineContext, RandomnessCache, WriteStats};
 use crate::blockstore::BufferedBlockstore;
 use crate::externs::Externs;
 use crate::kernel::{ClassifyResult, Result};
+#[cfg(feature = "metrics")]
+use crate::machine::MachineMetrics;
 use crate::machine::Manifest;
 use crate::machine::limiter::DefaultMemoryLimiter;
 use crate::state_tree::StateTree;
@@ -35,6 +36,13 @@ pub struct DefaultMachine<B, E> {
     /// Somewhat unique ID of the machine consisting of (epoch, randomness)
     /// randomness is generated with `initial_state_root`
     id: String,
+    /// Randomness fetched through the externs, shared across messages.
+    randomness_cache: RandomnessCache,
+    /// The totals of the write statistics of the messages executed so far, when tracing.
+    write_stats: WriteStats,
+    /// Execution timings, collected across messages.
+    #[cfg(feature = "metrics")]
+    metrics: MachineMetrics,
 }
 
 impl<B, E> DefaultMachine<B, E>
@@ -51,25 +59,21 @@ where
     /// * `blockstore`: The underlying [blockstore][`Blockstore`] for reading/writing state.
     /// * `externs`: Client-provided ["external"][`Externs`] methods for accessing chain state.
     pub fn new(context: &MachineContext, blockstore: B, externs: E) -> anyhow::Result<Self> {
-        #[cfg(not(feature = "nv29-dev"))]
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V21..=NetworkVersion::V28;
-        #[cfg(feature = "nv29-dev")]
-        const SUPPORTED_VERSIONS: RangeInclusive<NetworkVersion> =
-            NetworkVersion::V21..=NetworkVersion::V29;
-
         debug!(
             "initializing a new machine, epoch={}, base_fee={}, nv={:?}, root={}",
             context.epoch, &context.base_fee, context.network_version, context.initial_state_root
         );
 
-        if !SUPPORTED_VERSIONS.contains(&context.network_version) {
-            return Err(anyhow!(
-                "unsupported network version: {}",
-                context.network_version
-            ));
+        if !context.is_consensus_safe() {
+            log::warn!(
+                "machine configured with a local code denylist ({} entries); execution results may diverge from consensus",
+                context.local_code_denylist.len()
+            );
         }
 
+        // This also rejects unsupported network versions.
+        context.validate()?;
+
         // Sanity check that the blockstore contains the supplied state root.
         if !blockstore
             .has(&context.initial_state_root)
@@ -119,8 +123,32 @@ where
                 context.epoch,
                 cid::multibase::encode(cid::multibase::Base::Base32Lower, randomness)
             ),
+            randomness_cache: RandomnessCache::new(context.randomness_cache_size),
+            write_stats: WriteStats::default(),
+            #[cfg(feature = "metrics")]
+            metrics: MachineMetrics::default(),
         })
     }
+
+    /// Like [`Machine::flush`], but writes the new blocks to the underlying blockstore from up to
+    /// `workers` threads, in batches of at most `batch_size` blocks (see
+    /// [`BufferedBlockstore::flush_parallel`]). The resulting state is the same.
+    pub fn flush_parallel(&mut self, workers: NonZeroUsize, batch_size: NonZeroUsize) -> Result<Cid>
+    where
+        B: Sync,
+    {
+        let root = self.state_tree_mut().flush()?;
+        if self.context.flush_all_blocks {
+            self.blockstore()
+                .flush_all_parallel(workers, batch_size)
+                .or_fatal()?;
+        } else {
+            self.blockstore()
+                .flush_parallel(&root, workers, batch_size)
+                .or_fatal()?;
+        }
+        Ok(root)
+    }
 }
 
 impl<B, E> Machine for DefaultMachine<B, E>
@@ -185,6 +213,35 @@ where
     fn new_limiter(&self) -> Self::Limiter {
         DefaultMemoryLimiter::for_network(&self.context().network)
     }
+
+    fn randomness_cache(&self) -> Option<&RandomnessCache> {
+        Some(&self.randomness_cache)
+    }
+
+    fn begin_message_writes(&mut self) {
+        self.blockstore().begin_generation();
+    }
+
+    fn end_message_writes(&mut self) -> Result<Option<WriteStats>> {
+        let root = self.state_tree_mut().flush()?;
+        let stats = self.blockstore().end_generation(&root).or_fatal()?;
+        self.write_stats += stats;
+        Ok(Some(stats))
+    }
+
+    fn write_stats(&self) -> Option<&WriteStats> {
+        Some(&self.write_stats)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> Option<&MachineMetrics> {
+        self.context.metrics.then_some(&self.metrics)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics_mut(&mut self) -> Option<&mut MachineMetrics> {
+        self.context.metrics.then_some(&mut self.metrics)
+    }
 }
 
 /// DagCBOR-encoded empty array. This is the default state object, so it always has to exist.
@@ -0,0 +1,308 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{Context as _, anyhow};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipld_core::ipld::Ipld;
+
+use crate::state_tree::{ActorState, StateTree};
+
+/// How deep [`diff_state`] follows differing links when comparing two actor states.
+const MAX_STATE_DIFF_DEPTH: usize = 32;
+
+/// The difference between an actor in two state trees, as reported by [`diff_state`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActorDiff {
+    /// The actor only exists in the second state tree.
+    Added(ActorID, ActorState),
+    /// The actor only exists in the first state tree.
+    Deleted(ActorID, ActorState),
+    /// The actor exists in both state trees, but differs.
+    Modified(ActorID, ModifiedActor),
+}
+
+/// The fields of an actor that differ between two state trees, as `(before, after)` pairs. Fields
+/// that didn't change are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModifiedActor {
+    pub code: Option<(Cid, Cid)>,
+    pub balance: Option<(TokenAmount, TokenAmount)>,
+    pub sequence: Option<(u64, u64)>,
+    pub delegated_address: Option<(Option<Address>, Option<Address>)>,
+    pub state: Option<StateDiff>,
+}
+
+/// The difference between two actor states.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateDiff {
+    /// The pairs of differing blocks, starting with the state roots. Each pair is linked from the
+    /// previous pair, at the only place where the previous blocks differ. The last pair is the
+    /// first one that differs in more than a single link (or that couldn't be loaded).
+    pub path: Vec<(Cid, Cid)>,
+    /// The contents of the last pair of blocks on the path, if they could be loaded and decoded.
+    pub blocks: Option<(Ipld, Ipld)>,
+}
+
+/// Compares two state trees, returning the actors that differ, ordered by ID. This is meant for
+/// debugging mismatched state roots.
+pub fn diff_state(
+    store: &impl Blockstore,
+    root_a: &Cid,
+    root_b: &Cid,
+) -> anyhow::Result<Vec<ActorDiff>> {
+    let mut actors_a = load_actors(store, root_a).context("failed to load first state tree")?;
+    let actors_b = load_actors(store, root_b).context("failed to load second state tree")?;
+
+    let mut diffs = BTreeMap::new();
+    for (id, b) in actors_b {
+        let diff = match actors_a.remove(&id) {
+            None => ActorDiff::Added(id, b),
+            Some(a) if a == b => continue,
+            Some(a) => ActorDiff::Modified(id, diff_actor(store, a, b)?),
+        };
+        diffs.insert(id, diff);
+    }
+    for (id, a) in actors_a {
+        diffs.insert(id, ActorDiff::Deleted(id, a));
+    }
+    Ok(diffs.into_values().collect())
+}
+
+fn load_actors(
+    store: &impl Blockstore,
+    root: &Cid,
+) -> anyhow::Result<BTreeMap<ActorID, ActorState>> {
+    let tree = StateTree::new_from_root(store, root)?;
+    let mut actors = BTreeMap::new();
+    tree.for_each_cacheless(|addr, actor| {
+        let id = addr
+            .id()
+            .map_err(|_| anyhow!("state tree contains a non-ID address {}", addr))?;
+        actors.insert(id, actor.clone());
+        Ok(())
+    })?;
+    Ok(actors)
+}
+
+fn diff_actor(
+    store: &impl Blockstore,
+    a: ActorState,
+    b: ActorState,
+) -> anyhow::Result<ModifiedActor> {
+    fn changed<T: PartialEq>(a: T, b: T) -> Option<(T, T)> {
+        (a != b).then_some((a, b))
+    }
+    let state = if a.state != b.state {
+        Some(diff_dag(store, a.state, b.state)?)
+    } else {
+        None
+    };
+    Ok(ModifiedActor {
+        code: changed(a.code, b.code),
+        balance: changed(a.balance, b.balance),
+        sequence: changed(a.sequence, b.sequence),
+        delegated_address: changed(a.delegated_address, b.delegated_address),
+        state,
+    })
+}
+
+/// Follows two DAGs down from their roots for as long as they differ in a single link.
+fn diff_dag(store: &impl Blockstore, a: Cid, b: Cid) -> anyhow::Result<StateDiff> {
+    let mut path = vec![(a, b)];
+    loop {
+        let (a, b) = path[path.len() - 1];
+        let blocks = match (load_block(store, &a)?, load_block(store, &b)?) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Ok(StateDiff { path, blocks: None }),
+        };
+        let mut links = Vec::new();
+        if path.len() < MAX_STATE_DIFF_DEPTH
+            && diff_links(&blocks.0, &blocks.1, &mut links)
+            && links.len() == 1
+        {
+            path.push(links[0]);
+        } else {
+            return Ok(StateDiff {
+                path,
+                blocks: Some(blocks),
+            });
+        }
+    }
+}
+
+/// Loads a DAG-CBOR block, returning `None` if it's missing or uses some other codec.
+fn load_block(store: &impl Blockstore, k: &Cid) -> anyhow::Result<Option<Ipld>> {
+    if k.codec() != DAG_CBOR {
+        return Ok(None);
+    }
+    store.get_cbor(k)
+}
+
+/// Collects the pairs of differing links between two IPLD values, returning false if the values
+/// differ in anything but their links.
+fn diff_links(a: &Ipld, b: &Ipld, out: &mut Vec<(Cid, Cid)>) -> bool {
+    match (a, b) {
+        (Ipld::Link(a), Ipld::Link(b)) => {
+            if a != b {
+                out.push((*a, *b));
+            }
+            true
+        }
+        (Ipld::List(a), Ipld::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| diff_links(a, b, out))
+        }
+        (Ipld::Map(a), Ipld::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((ka, a), (kb, b))| ka == kb && diff_links(a, b, out))
+        }
+        (a, b) => a == b,
+    }
+}
+
+impl fmt::Display for ActorDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorDiff::Added(id, actor) => write!(
+                f,
+                "actor {} added: code {}, balance {}, sequence {}",
+                id, actor.code, actor.balance, actor.sequence
+            ),
+            ActorDiff::Deleted(id, actor) => write!(
+                f,
+                "actor {} deleted: code {}, balance {}, sequence {}",
+                id, actor.code, actor.balance, actor.sequence
+            ),
+            ActorDiff::Modified(id, diff) => {
+                write!(f, "actor {} modified:", id)?;
+                if let Some((a, b)) = &diff.code {
+                    write!(f, "\n  code: {} -> {}", a, b)?;
+                }
+                if let Some((a, b)) = &diff.balance {
+                    write!(f, "\n  balance: {} -> {} ({:+})", a, b, b - a)?;
+                }
+                if let Some((a, b)) = &diff.sequence {
+                    let delta = *b as i128 - *a as i128;
+                    write!(f, "\n  sequence: {} -> {} ({:+})", a, b, delta)?;
+                }
+                if let Some((a, b)) = &diff.delegated_address {
+                    let show = |addr: &Option<Address>| {
+                        addr.map_or_else(|| "none".to_owned(), |addr| addr.to_string())
+                    };
+                    write!(f, "\n  delegated address: {} -> {}", show(a), show(b))?;
+                }
+                if let Some(state) = &diff.state {
+                    write!(f, "\n  state: {}", state)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (root_a, root_b) = &self.path[0];
+        write!(f, "{} -> {}", root_a, root_b)?;
+        if self.path.len() > 1 {
+            let (a, b) = &self.path[self.path.len() - 1];
+            write!(
+                f,
+                "\n    first differing block (depth {}): {} -> {}",
+                self.path.len() - 1,
+                a,
+                b
+            )?;
+        }
+        match &self.blocks {
+            Some((a, b)) => write!(f, "\n    before: {:?}\n    after: {:?}", a, b),
+            None => write!(f, "\n    (blocks not available)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::state::StateTreeVersion;
+    use multihash_codetable::Code;
+
+    use super::*;
+
+    fn actor(state: Cid, balance: u64, sequence: u64) -> ActorState {
+        ActorState::new(
+            Cid::default(),
+            state,
+            TokenAmount::from_atto(balance),
+            sequence,
+            None,
+        )
+    }
+
+    #[test]
+    fn diff_state_trees() {
+        let bs = MemoryBlockstore::default();
+        let child_a = bs.put_cbor(&"a", Code::Blake2b256).unwrap();
+        let child_b = bs.put_cbor(&"b", Code::Blake2b256).unwrap();
+        let state_a = bs.put_cbor(&(child_a, 1u8), Code::Blake2b256).unwrap();
+        let state_b = bs.put_cbor(&(child_b, 1u8), Code::Blake2b256).unwrap();
+
+        let mut tree = StateTree::new(&bs, StateTreeVersion::V5).unwrap();
+        tree.set_actor(100, actor(state_a, 10, 1));
+        tree.set_actor(101, actor(state_a, 20, 2));
+        tree.set_actor(102, actor(state_a, 30, 3));
+        let root_a = tree.flush().unwrap();
+
+        // Modify one actor, delete another, and leave the last alone.
+        tree.set_actor(100, actor(state_b, 5, 3));
+        tree.delete_actor(101);
+        let root_b = tree.flush().unwrap();
+
+        assert_eq!(diff_state(&bs, &root_a, &root_a).unwrap(), []);
+
+        let diffs = diff_state(&bs, &root_a, &root_b).unwrap();
+        assert_eq!(
+            diffs,
+            [
+                ActorDiff::Modified(
+                    100,
+                    ModifiedActor {
+                        balance: Some((TokenAmount::from_atto(10), TokenAmount::from_atto(5))),
+                        sequence: Some((1, 3)),
+                        state: Some(StateDiff {
+                            path: vec![(state_a, state_b), (child_a, child_b)],
+                            blocks: Some((Ipld::String("a".into()), Ipld::String("b".into()))),
+                        }),
+                        ..Default::default()
+                    }
+                ),
+                ActorDiff::Deleted(101, actor(state_a, 20, 2)),
+            ]
+        );
+        let (before, after) = (Ipld::String("a".into()), Ipld::String("b".into()));
+        assert_eq!(
+            diffs[0].to_string(),
+            format!(
+                "actor 100 modified:\
+                 \n  balance: 0.00000000000000001 -> 0.000000000000000005 (-0.000000000000000005)\
+                 \n  sequence: 1 -> 3 (+2)\
+                 \n  state: {state_a} -> {state_b}\
+                 \n    first differing block (depth 1): {child_a} -> {child_b}\
+                 \n    before: {before:?}\
+                 \n    after: {after:?}"
+            )
+        );
+
+        // The other way around, the deleted actor is added back.
+        let diffs = diff_state(&bs, &root_b, &root_a).unwrap();
+        assert_eq!(diffs[1], ActorDiff::Added(101, actor(state_a, 20, 2)));
+    }
+}
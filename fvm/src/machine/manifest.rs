@@ -25,6 +25,7 @@ pub struct Manifest {
 
     by_id: HashMap<u32, Cid>,
     by_code: HashMap<Cid, u32>,
+    by_name: HashMap<String, Cid>,
 }
 
 /// Create an "id CID" (for testing).
@@ -133,6 +134,7 @@ impl Manifest {
             ethaccount_code,
             by_id,
             by_code,
+            by_name,
         })
     }
 
@@ -146,6 +148,12 @@ impl Manifest {
         self.by_code.get(code).copied().unwrap_or(0)
     }
 
+    /// Returns the code CID for a builtin actor, given the actor's name in the manifest (e.g.,
+    /// "cron").
+    pub fn code_by_name(&self, name: &str) -> Option<&Cid> {
+        self.by_name.get(name)
+    }
+
     /// Returns true id the passed code CID is the account actor.
     pub fn is_account_actor(&self, cid: &Cid) -> bool {
         &self.account_code == cid
@@ -195,3 +203,71 @@ impl Manifest {
         &self.ethaccount_code
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use multihash_codetable::Code;
+
+    use super::*;
+
+    fn store_manifest() -> (MemoryBlockstore, Cid) {
+        let bs = MemoryBlockstore::default();
+        let root = bs
+            .put_cbor(&Manifest::DUMMY_CODES, Code::Blake2b256)
+            .unwrap();
+        (bs, root)
+    }
+
+    #[test]
+    fn load() {
+        let (bs, root) = store_manifest();
+        let manifest = Manifest::load(&bs, &root, 1).unwrap();
+
+        for (&(name, code), id) in Manifest::DUMMY_CODES.iter().zip(1u32..) {
+            assert_eq!(manifest.code_by_name(name), Some(&code));
+            assert_eq!(manifest.code_by_id(id), Some(&code));
+            assert_eq!(manifest.id_by_code(&code), id);
+        }
+        assert_eq!(manifest.code_by_name("miner"), None);
+        assert_eq!(manifest.id_by_code(&root), 0);
+
+        assert_eq!(
+            Some(manifest.get_account_code()),
+            manifest.code_by_name("account")
+        );
+        assert!(manifest.is_account_actor(manifest.get_account_code()));
+        assert_eq!(
+            Some(manifest.get_init_code()),
+            manifest.code_by_name("init")
+        );
+        assert_eq!(
+            Some(manifest.get_system_code()),
+            manifest.code_by_name("system")
+        );
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let (bs, root) = store_manifest();
+        assert!(Manifest::load(&bs, &root, 2).is_err());
+        assert!(Manifest::load(&bs, &root, 0).is_err());
+    }
+
+    #[test]
+    fn missing_actor() {
+        let codes = Manifest::DUMMY_CODES
+            .iter()
+            .filter(|(name, _)| *name != "init")
+            .copied();
+        let err = Manifest::new(codes).err().unwrap();
+        assert!(err.to_string().contains("init"), "{err}");
+    }
+
+    #[test]
+    fn missing_root() {
+        let (_, root) = store_manifest();
+        let bs = MemoryBlockstore::default();
+        assert!(Manifest::load(&bs, &root, 1).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Timing statistics for a single kind of operation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metric {
+    /// The number of timed operations.
+    pub count: u64,
+    /// The total time spent in these operations, in nanoseconds.
+    pub total_nanos: u64,
+    /// The longest time spent in a single operation, in nanoseconds.
+    pub max_nanos: u64,
+}
+
+/// Time spent executing messages, broken down by operation. Operations are named after their gas
+/// charges, e.g. `wasm_exec` for Wasm execution, `OnVerifySignature` for signature verification,
+/// `OnBlockOpen` for blockstore reads, and `OnVerifyPost` for proof verification.
+///
+/// Metrics are collected across messages until [reset](MachineMetrics::reset).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MachineMetrics {
+    operations: BTreeMap<Cow<'static, str>, Metric>,
+}
+
+impl MachineMetrics {
+    /// Records a single timed operation.
+    pub fn record(&mut self, name: Cow<'static, str>, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().try_into().unwrap_or(u64::MAX);
+        let metric = self.operations.entry(name).or_default();
+        metric.count += 1;
+        metric.total_nanos = metric.total_nanos.saturating_add(nanos);
+        metric.max_nanos = metric.max_nanos.max(nanos);
+    }
+
+    /// Adds the operations recorded in `other`.
+    pub fn merge(&mut self, other: &MachineMetrics) {
+        for (name, m) in &other.operations {
+            let metric = self.operations.entry(name.clone()).or_default();
+            metric.count = metric.count.saturating_add(m.count);
+            metric.total_nanos = metric.total_nanos.saturating_add(m.total_nanos);
+            metric.max_nanos = metric.max_nanos.max(m.max_nanos);
+        }
+    }
+
+    /// Returns the statistics for the named operation, if it has been recorded.
+    pub fn get(&self, name: &str) -> Option<&Metric> {
+        self.operations.get(name)
+    }
+
+    /// Iterates over the recorded operations, by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Metric)> {
+        self.operations.iter().map(|(k, v)| (&**k, v))
+    }
+
+    /// Discards all recorded metrics.
+    pub fn reset(&mut self) {
+        self.operations.clear();
+    }
+}
@@ -1,18 +1,26 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::ops::RangeInclusive;
+
 use cid::Cid;
 use derive_more::{Deref, DerefMut};
 use fvm_ipld_blockstore::Blockstore;
-use fvm_shared::ActorID;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use fvm_shared::conformance::SYSCALLS;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MAX_BLOCK_SIZE};
+use multihash_codetable::{Code, MultihashDigest};
 use num_traits::Zero;
 
+use crate::Kernel;
 use crate::externs::Externs;
-use crate::gas::{PriceList, price_list_by_network_version};
+use crate::gas::{Gas, PriceList, try_price_list_by_network_version};
 use crate::kernel::Result;
 use crate::state_tree::StateTree;
+use crate::syscalls::{SyscallBinder, SyscallExtension};
 
 mod default;
 
@@ -24,15 +32,52 @@ mod manifest;
 
 pub use manifest::Manifest;
 
+mod block_cache;
+mod diff;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod query;
+mod randomness;
+mod roots;
+mod signed_message;
+mod snapshot;
+
+pub use crate::blockstore::{BufferedBlockstore, WriteStats};
+pub use block_cache::{BlockCacheStats, CachingBlockstore, DEFAULT_BLOCK_CACHE_SIZE};
+pub use diff::{ActorDiff, ModifiedActor, StateDiff, diff_state};
+#[cfg(feature = "metrics")]
+pub use metrics::{MachineMetrics, Metric};
+pub use query::{StateQuery, query_state};
+pub use randomness::{DEFAULT_RANDOMNESS_CACHE_SIZE, RandomnessCache, RandomnessSource};
+
+pub use roots::{EVENTS_AMT_BITWIDTH, build_events_root, build_receipts_root};
+pub(crate) use signed_message::message_cid;
+pub use signed_message::{SignatureError, verify_signed_message};
+pub use snapshot::{
+    SnapshotInfo, SnapshotOptions, SnapshotProgress, SnapshotVerification, import_snapshot,
+};
+
 use self::limiter::MemoryLimiter;
 
 mod boxed;
 
 pub const REWARD_ACTOR_ID: ActorID = 2;
 
+/// Distinguished actor invoked at the end of every epoch.
+pub const CRON_ACTOR_ID: ActorID = 3;
+
 /// Distinguished Account actor that is the destination of all burnt funds.
 pub const BURNT_FUNDS_ACTOR_ID: ActorID = 99;
 
+/// The network versions for which machines may be built.
+#[cfg(not(feature = "nv29-dev"))]
+pub const SUPPORTED_NETWORK_VERSIONS: RangeInclusive<NetworkVersion> =
+    NetworkVersion::V21..=NetworkVersion::V28;
+/// The network versions for which machines may be built.
+#[cfg(feature = "nv29-dev")]
+pub const SUPPORTED_NETWORK_VERSIONS: RangeInclusive<NetworkVersion> =
+    NetworkVersion::V21..=NetworkVersion::V29;
+
 /// The Machine is the top-level object of the FVM.
 ///
 /// The Machine operates at a concrete network version and epoch, over an
@@ -81,6 +126,83 @@ pub trait Machine: 'static {
 
     /// Creates a new limiter to track the resources of a message execution.
     fn new_limiter(&self) -> Self::Limiter;
+
+    /// Returns the cache of the randomness returned by the externs, if this machine caches
+    /// randomness.
+    fn randomness_cache(&self) -> Option<&RandomnessCache> {
+        None
+    }
+
+    /// Starts attributing the blocks written to the machine's blockstore to a new message (see
+    /// [`Machine::end_message_writes`]). Machines that don't track writes ignore this.
+    fn begin_message_writes(&mut self) {}
+
+    /// Flushes the state-tree, and returns statistics on the blocks written by the message since
+    /// [`Machine::begin_message_writes`], adding them to the machine's totals (see
+    /// [`Machine::write_stats`]). Returns `None` if this machine doesn't track writes.
+    fn end_message_writes(&mut self) -> Result<Option<WriteStats>> {
+        Ok(None)
+    }
+
+    /// Returns the totals of the write statistics of the messages tracked so far, if this machine
+    /// tracks writes.
+    fn write_stats(&self) -> Option<&WriteStats> {
+        None
+    }
+
+    /// Returns the execution timings collected since they were last reset, if this machine
+    /// collects them (see [`MachineContext::metrics`]).
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> Option<&MachineMetrics> {
+        None
+    }
+
+    /// Returns a mutable reference to the collected execution timings (e.g., to reset them), if
+    /// this machine collects them.
+    #[cfg(feature = "metrics")]
+    fn metrics_mut(&mut self) -> Option<&mut MachineMetrics> {
+        None
+    }
+}
+
+/// The size of a wasm page, in bytes.
+const WASM_PAGE_SIZE: u64 = 64 << 10;
+
+/// The maximum size of a wasm32 linear memory, in bytes.
+const MAX_WASM32_MEMORY_BYTES: u64 = 4 << 30;
+
+/// The chain ID of Filecoin mainnet.
+const MAINNET_CHAIN_ID: u64 = 314;
+
+/// An invalid [`NetworkConfig`] (or [`MachineContext`]) setting.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid network config: `{field}` {reason}")]
+pub struct ConfigError {
+    /// The name of the invalid field.
+    pub field: &'static str,
+    /// Why the field's value is invalid.
+    pub reason: String,
+}
+
+impl ConfigError {
+    fn new(field: &'static str, reason: impl Into<String>) -> Self {
+        ConfigError {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
+fn unsupported_network_version(network_version: NetworkVersion) -> ConfigError {
+    ConfigError::new(
+        "network_version",
+        format!(
+            "network version {} is not supported (supported: {} to {})",
+            network_version,
+            SUPPORTED_NETWORK_VERSIONS.start(),
+            SUPPORTED_NETWORK_VERSIONS.end()
+        ),
+    )
 }
 
 /// Network-level settings. Except when testing locally, changing any of these likely requires a
@@ -100,6 +222,15 @@ pub struct NetworkConfig {
     /// DEFAULT: 1024
     pub max_call_depth: u32,
 
+    /// The maximum number of sends (internal calls, including failed ones) a message may make. Sends
+    /// beyond this limit fail with [`fvm_shared::error::ErrorNumber::LimitExceeded`], which the
+    /// sending actor may handle. The limit is only enforced from network version 29.
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it.
+    ///
+    /// DEFAULT: 16Ki
+    pub max_sends_per_message: u64,
+
     /// The maximum number of elements on wasm stack
     /// DEFAULT: 64Ki (512KiB of u64 elements)
     pub max_wasm_stack: u32,
@@ -121,6 +252,16 @@ pub struct NetworkConfig {
     /// DEFAULT: 1MiB
     pub max_block_size: usize,
 
+    /// The maximum total size, in bytes, of the blocks actors may create while executing a message
+    /// (across all calls, including ones that are later reverted). Creating a block beyond this
+    /// limit fails with [`fvm_shared::error::ErrorNumber::LimitExceeded`], before the block is
+    /// copied out of the actor's memory.
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it.
+    ///
+    /// DEFAULT: `None` (unlimited)
+    pub max_block_bytes_per_message: Option<u64>,
+
     /// An override for builtin-actors. If specified, this should be the CID of a builtin-actors
     /// "manifest".
     ///
@@ -139,24 +280,99 @@ pub struct NetworkConfig {
 
     /// Actor redirects for debug execution
     pub actor_redirect: Vec<(Cid, Cid)>,
+
+    /// Actor code CIDs that may not be invoked. Messages that would invoke an actor running one of
+    /// these code CIDs exit with
+    /// [`fvm_shared::error::ExitCode::SYS_DENIED_CODE`] instead.
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it. See
+    /// [`NetworkConfig::code_denylist_fingerprint`]. It may only be set from network version 29.
+    ///
+    /// DEFAULT: empty
+    pub code_denylist: Vec<Cid>,
+
+    /// Allow actors to read the state of builtin actors directly (see
+    /// [`ActorOps::get_actor_state_root`](crate::kernel::ActorOps::get_actor_state_root)),
+    /// instead of having to call them.
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it. It may only be
+    /// enabled from network version 29.
+    ///
+    /// DEFAULT: `false`
+    pub actor_state_inspection: bool,
+
+    /// The maximum gas a message may spend verifying proofs (seals, aggregate seals, replica
+    /// updates, and window PoSts), on top of its gas limit. This bounds the time it takes to
+    /// validate a block full of proof verifications. Proof verifications that would exceed it fail
+    /// with [`fvm_shared::error::ErrorNumber::LimitExceeded`], even if the message has gas left.
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it.
+    ///
+    /// DEFAULT: `None` (unlimited)
+    pub proof_gas_limit: Option<Gas>,
+
+    /// Syscall modules registered by the embedder, in addition to the built-in syscalls. See
+    /// [`NetworkConfig::with_syscall_module`].
+    ///
+    /// This is a consensus-critical network policy: all nodes must agree on it. See
+    /// [`NetworkConfig::syscall_extensions_fingerprint`].
+    ///
+    /// DEFAULT: empty
+    pub syscall_extensions: Vec<SyscallExtension>,
 }
 
 impl NetworkConfig {
-    /// Create a new network config for the given network version.
+    /// Create a new network config for the given network version, with the network version's
+    /// price list and default limits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the network version isn't supported (see [`SUPPORTED_NETWORK_VERSIONS`]). Use
+    /// [`NetworkConfig::try_new`] to handle unsupported network versions.
     pub fn new(network_version: NetworkVersion) -> Self {
-        NetworkConfig {
+        Self::try_new(network_version).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`NetworkConfig::new`], but fails if the network version isn't supported.
+    pub fn try_new(network_version: NetworkVersion) -> std::result::Result<Self, ConfigError> {
+        let price_list = try_price_list_by_network_version(network_version)
+            .ok_or_else(|| unsupported_network_version(network_version))?;
+        Ok(NetworkConfig {
             chain_id: ChainID::from(0u64),
             network_version,
             max_call_depth: 1024,
+            max_sends_per_message: 1 << 14,
             max_wasm_stack: 2048,
             max_inst_memory_bytes: 512 * (1 << 20),
             max_memory_bytes: 2 * (1 << 30),
             actor_debugging: false,
             builtin_actors_override: None,
-            price_list: price_list_by_network_version(network_version),
+            price_list,
             actor_redirect: vec![],
-            max_block_size: 1 << 20,
-        }
+            code_denylist: vec![],
+            actor_state_inspection: false,
+            max_block_size: MAX_BLOCK_SIZE as usize,
+            max_block_bytes_per_message: None,
+            proof_gas_limit: None,
+            syscall_extensions: vec![],
+        })
+    }
+
+    /// The network config used by Filecoin mainnet at the given network version: the default
+    /// limits and price list, with mainnet's chain ID.
+    pub fn mainnet(network_version: NetworkVersion) -> Self {
+        let mut nc = NetworkConfig::new(network_version);
+        nc.chain_id(ChainID::from(MAINNET_CHAIN_ID));
+        nc
+    }
+
+    /// A network config for local development networks and tests: the default limits and price
+    /// list, with actor debugging enabled. Actor debugging affects gas usage, so this config must
+    /// never be used to validate a real network's chain.
+    pub fn devnet(network_version: NetworkVersion) -> Self {
+        let mut nc = NetworkConfig::new(network_version);
+        nc.enable_actor_debugging();
+        nc
     }
 
     /// Enable actor debugging. This is a consensus-critical option (affects gas usage) so it should
@@ -179,6 +395,75 @@ impl NetworkConfig {
         self
     }
 
+    /// Deny execution of the specified actor code CIDs network-wide. This is a consensus-critical
+    /// option so it must be applied identically by all nodes on the network.
+    pub fn deny_actor_code(&mut self, code_denylist: Vec<Cid>) -> &mut Self {
+        self.code_denylist = code_denylist;
+        self
+    }
+
+    /// Allow actors to read the state of builtin actors. This is a consensus-critical option so it
+    /// must be applied identically by all nodes on the network.
+    /// [`NetworkConfig::actor_state_inspection`].
+    pub fn enable_actor_state_inspection(&mut self) -> &mut Self {
+        self.actor_state_inspection = true;
+        self
+    }
+
+    /// Returns a CID that uniquely identifies the network's code denylist, independent of the order
+    /// in which the code CIDs were specified or any duplicates. Nodes may compare fingerprints to
+    /// confirm that they're enforcing the same policy.
+    pub fn code_denylist_fingerprint(&self) -> Cid {
+        let mut codes = self.code_denylist.clone();
+        codes.sort();
+        codes.dedup();
+        let encoded = to_vec(&codes).expect("failed to encode code denylist");
+        Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&encoded))
+    }
+
+    /// Register a syscall module in the given namespace (the wasm import module actors import the
+    /// syscalls from). The binder binds the module's syscalls with
+    /// [`SyscallBinder::bind`], and is called once when each engine links syscalls for the kernel
+    /// `K`. Machines using another kernel fail to instantiate actors.
+    ///
+    /// This is a consensus-critical option so it must be applied identically by all nodes on the
+    /// network. It's meant for subnets and other networks that need their own host functions:
+    /// Filecoin mainnet doesn't allow any (see [`NetworkConfig::validate`]).
+    pub fn with_syscall_module<K: Kernel>(
+        &mut self,
+        namespace: &'static str,
+        binder: impl Fn(&mut SyscallBinder<'_, K>) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.syscall_extensions
+            .push(SyscallExtension::new(namespace, binder));
+        self
+    }
+
+    /// Returns the syscalls registered by the embedder, as sorted `namespace::name` strings.
+    /// Embedders may pin this list in their tests to catch accidental changes to their syscalls.
+    pub fn syscall_extension_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .syscall_extensions
+            .iter()
+            .flat_map(|ext| {
+                ext.names()
+                    .iter()
+                    .map(move |name| format!("{}::{}", ext.namespace(), name))
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Returns a CID that uniquely identifies the syscalls registered by the embedder (by
+    /// namespace and name), independent of the order in which they were registered. Nodes may
+    /// compare fingerprints to confirm that they're exposing the same syscalls.
+    pub fn syscall_extensions_fingerprint(&self) -> Cid {
+        let encoded =
+            to_vec(&self.syscall_extension_names()).expect("failed to encode syscall extensions");
+        Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&encoded))
+    }
+
     /// Create a ['MachineContext'] for a given epoch, timestamp, and initial state.
     pub fn for_epoch(
         &self,
@@ -192,11 +477,19 @@ impl NetworkConfig {
             epoch,
             timestamp,
             initial_state_root: initial_state,
+            block_miner: None,
             // This is just the default. The previous default of "total FIL supply" was incorrect as
             // well, so we might as well be more neutral.
             circ_supply: TokenAmount::zero(),
             tracing: false,
+            #[cfg(feature = "metrics")]
+            metrics: false,
             flush_all_blocks: false,
+            track_actor_changes: false,
+            local_code_denylist: vec![],
+            randomness_cache_size: DEFAULT_RANDOMNESS_CACHE_SIZE,
+            max_trace_events: 1 << 20,
+            max_backtrace_frames: 1 << 12,
         }
     }
 
@@ -205,6 +498,207 @@ impl NetworkConfig {
         self.chain_id = id;
         self
     }
+
+    /// Sets [`NetworkConfig::max_call_depth`].
+    pub fn set_max_call_depth(&mut self, depth: u32) -> &mut Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_sends_per_message`]. This is a consensus-critical option so it
+    /// must be applied identically by all nodes on the network.
+    pub fn set_max_sends_per_message(&mut self, sends: u64) -> &mut Self {
+        self.max_sends_per_message = sends;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_wasm_stack`].
+    pub fn set_max_wasm_stack(&mut self, elements: u32) -> &mut Self {
+        self.max_wasm_stack = elements;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_inst_memory_bytes`].
+    pub fn set_max_inst_memory_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.max_inst_memory_bytes = bytes;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_memory_bytes`].
+    pub fn set_max_memory_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.max_memory_bytes = bytes;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_block_size`].
+    pub fn set_max_block_size(&mut self, bytes: usize) -> &mut Self {
+        self.max_block_size = bytes;
+        self
+    }
+
+    /// Sets [`NetworkConfig::max_block_bytes_per_message`]. This is a consensus-critical option so
+    /// it must be applied identically by all nodes on the network.
+    pub fn set_max_block_bytes_per_message(&mut self, bytes: u64) -> &mut Self {
+        self.max_block_bytes_per_message = Some(bytes);
+        self
+    }
+
+    /// Sets [`NetworkConfig::proof_gas_limit`]. This is a consensus-critical option so it must be
+    /// applied identically by all nodes on the network.
+    pub fn set_proof_gas_limit(&mut self, limit: Gas) -> &mut Self {
+        self.proof_gas_limit = Some(limit);
+        self
+    }
+
+    /// Sets [`NetworkConfig::price_list`], overriding the price list for the network version.
+    pub fn set_price_list(&mut self, price_list: &'static PriceList) -> &mut Self {
+        self.price_list = price_list;
+        self
+    }
+
+    /// Checks that the settings are consistent, returning the first invalid one. This is checked
+    /// when constructing a [`DefaultMachine`] so that misconfigurations are reported up-front
+    /// instead of failing at invocation time.
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        if !SUPPORTED_NETWORK_VERSIONS.contains(&self.network_version) {
+            return Err(unsupported_network_version(self.network_version));
+        }
+        if self.max_call_depth == 0 {
+            return Err(ConfigError::new("max_call_depth", "must be positive"));
+        }
+        if self.max_sends_per_message == 0 {
+            return Err(ConfigError::new(
+                "max_sends_per_message",
+                "must be positive",
+            ));
+        }
+        if self.max_wasm_stack == 0 {
+            return Err(ConfigError::new("max_wasm_stack", "must be positive"));
+        }
+        if self.max_inst_memory_bytes < WASM_PAGE_SIZE {
+            return Err(ConfigError::new(
+                "max_inst_memory_bytes",
+                format!(
+                    "must be at least one wasm page ({WASM_PAGE_SIZE} bytes), got {}",
+                    self.max_inst_memory_bytes
+                ),
+            ));
+        }
+        if self.max_inst_memory_bytes % WASM_PAGE_SIZE != 0 {
+            return Err(ConfigError::new(
+                "max_inst_memory_bytes",
+                format!(
+                    "must be a multiple of the wasm page size ({WASM_PAGE_SIZE} bytes), got {}",
+                    self.max_inst_memory_bytes
+                ),
+            ));
+        }
+        if self.max_inst_memory_bytes > MAX_WASM32_MEMORY_BYTES {
+            return Err(ConfigError::new(
+                "max_inst_memory_bytes",
+                format!(
+                    "exceeds the wasm32 address space ({MAX_WASM32_MEMORY_BYTES} bytes), got {}",
+                    self.max_inst_memory_bytes
+                ),
+            ));
+        }
+        if self.max_memory_bytes == 0 {
+            return Err(ConfigError::new("max_memory_bytes", "must be positive"));
+        }
+        if self.max_block_size == 0 {
+            return Err(ConfigError::new("max_block_size", "must be positive"));
+        }
+        if u32::try_from(self.max_block_size).is_err() {
+            return Err(ConfigError::new(
+                "max_block_size",
+                format!(
+                    "must fit in a u32 (block sizes are 32-bit in syscalls), got {}",
+                    self.max_block_size
+                ),
+            ));
+        }
+        if self.max_block_bytes_per_message == Some(0) {
+            return Err(ConfigError::new(
+                "max_block_bytes_per_message",
+                "must be positive",
+            ));
+        }
+        if !self.code_denylist.is_empty() && self.network_version < NetworkVersion::V29 {
+            return Err(ConfigError::new(
+                "code_denylist",
+                "requires network version 29 or later",
+            ));
+        }
+        if self.actor_state_inspection && self.network_version < NetworkVersion::V29 {
+            return Err(ConfigError::new(
+                "actor_state_inspection",
+                "requires network version 29 or later",
+            ));
+        }
+        self.validate_syscall_extensions()?;
+        Ok(())
+    }
+
+    /// Checks that the syscall extensions are allowed on this network, and that their namespaces
+    /// and names are valid and don't collide with the built-in syscalls or each other.
+    fn validate_syscall_extensions(&self) -> std::result::Result<(), ConfigError> {
+        const FIELD: &str = "syscall_extensions";
+        if self.syscall_extensions.is_empty() {
+            return Ok(());
+        }
+        if self.chain_id == ChainID::from(MAINNET_CHAIN_ID) {
+            return Err(ConfigError::new(
+                FIELD,
+                "syscall extensions are not allowed on mainnet",
+            ));
+        }
+        let valid_ident = |s: &str| {
+            !s.is_empty()
+                && s.bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+        };
+        let mut namespaces = Vec::with_capacity(self.syscall_extensions.len());
+        for ext in &self.syscall_extensions {
+            let namespace = ext.namespace();
+            if !valid_ident(namespace) {
+                return Err(ConfigError::new(
+                    FIELD,
+                    format!("invalid namespace {namespace:?}: must be lowercase alphanumeric"),
+                ));
+            }
+            if SYSCALLS.iter().any(|info| info.module == namespace) {
+                return Err(ConfigError::new(
+                    FIELD,
+                    format!("namespace {namespace} is reserved for built-in syscalls"),
+                ));
+            }
+            if namespaces.contains(&namespace) {
+                return Err(ConfigError::new(
+                    FIELD,
+                    format!("namespace {namespace} is registered twice"),
+                ));
+            }
+            namespaces.push(namespace);
+
+            let mut names = ext.names().to_vec();
+            names.sort_unstable();
+            if let Some(name) = names.iter().find(|name| !valid_ident(name)) {
+                return Err(ConfigError::new(
+                    FIELD,
+                    format!(
+                        "invalid syscall name {namespace}::{name:?}: must be lowercase alphanumeric"
+                    ),
+                ));
+            }
+            if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(ConfigError::new(
+                    FIELD,
+                    format!("syscall {namespace}::{} is bound twice", pair[0]),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Per-epoch machine context.
@@ -220,7 +714,8 @@ pub struct MachineContext {
     /// Default: 0
     pub epoch: ChainEpoch,
 
-    /// The UNIX timestamp (in seconds) of the current tipset
+    /// The UNIX timestamp (in seconds) of the current tipset, as observed by actors. This must be
+    /// non-zero after genesis.
     ///
     /// Default: 0
     pub timestamp: u64,
@@ -233,6 +728,18 @@ pub struct MachineContext {
     /// The initial state root on which this block is based.
     pub initial_state_root: Cid,
 
+    /// The miner of the block whose messages are being applied. If set, each message's miner tip
+    /// is paid directly to this miner when the message is settled, or burnt if the miner can't be
+    /// resolved to an existing actor. Penalties are still only reported (see
+    /// [`ApplyRet::penalty`](crate::executor::ApplyRet::penalty)).
+    ///
+    /// Otherwise, tips are paid to the reward actor which, on Filecoin, pays them out to the
+    /// block's miner along with the block reward (see
+    /// [`DefaultExecutor::apply_tipset`](crate::executor::DefaultExecutor::apply_tipset)).
+    ///
+    /// DEFAULT: `None`
+    pub block_miner: Option<Address>,
+
     /// v15 and onwards: The amount of FIL that has vested from genesis actors.
     /// v14 and earlier: The amount of FIL that has vested from genesis msigs
     /// (the remainder of the circ supply must be calculated by the FVM)
@@ -244,12 +751,86 @@ pub struct MachineContext {
     /// Not consensus-critical, but has a performance impact.
     pub tracing: bool,
 
+    /// Whether or not to collect execution timings (see [`Machine::metrics`]). Not
+    /// consensus-critical, but has a performance impact.
+    ///
+    /// DEFAULT: false
+    #[cfg(feature = "metrics")]
+    pub metrics: bool,
+
     /// When true, flush() will write all blocks created during execution to the
     /// blockstore, not just those reachable from the final state root.
     pub flush_all_blocks: bool,
+
+    /// Whether or not to report the actors changed by each message in the returned result (see
+    /// [`ApplyRet::actor_changes`](crate::executor::ApplyRet::actor_changes)). Not
+    /// consensus-critical, but has a (small) performance impact.
+    ///
+    /// DEFAULT: false
+    pub track_actor_changes: bool,
+
+    /// Actor code CIDs this node refuses to invoke, in addition to the network's
+    /// [`NetworkConfig::code_denylist`]. This is intended for emergency response only: it is NOT
+    /// consensus-safe and a node with a non-empty local denylist may compute different state than
+    /// the rest of the network. See [`MachineContext::is_consensus_safe`]. It may only be set from
+    /// network version 29.
+    ///
+    /// DEFAULT: empty
+    pub local_code_denylist: Vec<Cid>,
+
+    /// The maximum number of randomness values the machine caches, across all messages (see
+    /// [`RandomnessCache`]). Not consensus-critical: 0 disables the cache.
+    ///
+    /// DEFAULT: [`DEFAULT_RANDOMNESS_CACHE_SIZE`]
+    pub randomness_cache_size: usize,
+
+    /// The maximum number of events in a message's execution trace, when tracing. Further events
+    /// are dropped, and counted by a final
+    /// [`ExecutionEvent::TraceTruncated`](crate::trace::ExecutionEvent::TraceTruncated) event. Not
+    /// consensus-critical.
+    ///
+    /// DEFAULT: 1Mi
+    pub max_trace_events: usize,
+
+    /// The maximum number of frames in a message's backtrace. Further frames are dropped, and
+    /// counted in [`Backtrace::dropped_frames`](crate::call_manager::Backtrace::dropped_frames).
+    /// Not consensus-critical.
+    ///
+    /// DEFAULT: 4Ki
+    pub max_backtrace_frames: usize,
 }
 
 impl MachineContext {
+    /// Checks that the network config is valid (see [`NetworkConfig::validate`]), and that the
+    /// tipset timestamp is set after genesis. This is checked when constructing a
+    /// [`DefaultMachine`].
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        self.network.validate()?;
+        if !self.local_code_denylist.is_empty() && self.network_version < NetworkVersion::V29 {
+            return Err(ConfigError::new(
+                "local_code_denylist",
+                "requires network version 29 or later",
+            ));
+        }
+        if self.epoch > 0 && self.timestamp == 0 {
+            return Err(ConfigError::new(
+                "timestamp",
+                format!("must be set after genesis, got 0 at epoch {}", self.epoch),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds a [`DefaultMachine`] for this context, failing if the context is invalid (including
+    /// if the network version isn't supported). This is equivalent to [`DefaultMachine::new`].
+    pub fn build<B, E>(&self, blockstore: B, externs: E) -> anyhow::Result<DefaultMachine<B, E>>
+    where
+        B: Blockstore + 'static,
+        E: Externs + 'static,
+    {
+        DefaultMachine::new(self, blockstore, externs)
+    }
+
     /// Sets [`MachineContext::base_fee`].
     pub fn set_base_fee(&mut self, amt: TokenAmount) -> &mut Self {
         self.base_fee = amt;
@@ -262,6 +843,18 @@ impl MachineContext {
         self
     }
 
+    /// Sets [`MachineContext::timestamp`].
+    pub fn set_timestamp(&mut self, timestamp: u64) -> &mut Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets [`MachineContext::block_miner`].
+    pub fn set_block_miner(&mut self, miner: Address) -> &mut Self {
+        self.block_miner = Some(miner);
+        self
+    }
+
     /// Set [`MachineContext::circ_supply`].
     pub fn set_circulating_supply(&mut self, amt: TokenAmount) -> &mut Self {
         self.circ_supply = amt;
@@ -274,9 +867,237 @@ impl MachineContext {
         self
     }
 
+    /// Enable collecting execution timings. [`MachineContext::metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn enable_metrics(&mut self) -> &mut Self {
+        self.metrics = true;
+        self
+    }
+
     /// Enable flushing all blocks. [`MachineContext::flush_all_blocks`].
     pub fn enable_flush_all_blocks(&mut self) -> &mut Self {
         self.flush_all_blocks = true;
         self
     }
+
+    /// Enable reporting the actors changed by each message.
+    /// [`MachineContext::track_actor_changes`].
+    pub fn enable_actor_change_tracking(&mut self) -> &mut Self {
+        self.track_actor_changes = true;
+        self
+    }
+
+    /// Deny execution of the specified actor code CIDs on this node only.
+    /// [`MachineContext::local_code_denylist`].
+    pub fn deny_code_locally(&mut self, code_denylist: Vec<Cid>) -> &mut Self {
+        self.local_code_denylist = code_denylist;
+        self
+    }
+
+    /// Sets [`MachineContext::randomness_cache_size`].
+    pub fn set_randomness_cache_size(&mut self, size: usize) -> &mut Self {
+        self.randomness_cache_size = size;
+        self
+    }
+
+    /// Sets [`MachineContext::max_trace_events`].
+    pub fn set_max_trace_events(&mut self, events: usize) -> &mut Self {
+        self.max_trace_events = events;
+        self
+    }
+
+    /// Sets [`MachineContext::max_backtrace_frames`].
+    pub fn set_max_backtrace_frames(&mut self, frames: usize) -> &mut Self {
+        self.max_backtrace_frames = frames;
+        self
+    }
+
+    /// Returns false if this machine has been configured with settings that may cause it to diverge
+    /// from consensus (currently, a non-empty [`MachineContext::local_code_denylist`]).
+    pub fn is_consensus_safe(&self) -> bool {
+        self.local_code_denylist.is_empty()
+    }
+
+    /// Returns true if actors with the given code CID may not be invoked, either due to the
+    /// network's code denylist or this node's local denylist.
+    pub fn is_code_denied(&self, code: &Cid) -> bool {
+        self.network.code_denylist.contains(code) || self.local_code_denylist.contains(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use fvm_shared::version::NetworkVersion;
+
+    use super::{ConfigError, MAX_BLOCK_SIZE, NetworkConfig};
+
+    fn invalid_field(nc: &NetworkConfig) -> &'static str {
+        let ConfigError { field, .. } = nc.validate().unwrap_err();
+        field
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        NetworkConfig::new(NetworkVersion::V21).validate().unwrap();
+    }
+
+    #[test]
+    fn presets() {
+        let nv = NetworkVersion::V25;
+        for (nc, chain_id, actor_debugging) in [
+            (NetworkConfig::mainnet(nv), 314, false),
+            (NetworkConfig::devnet(nv), 0, true),
+        ] {
+            nc.validate().unwrap();
+            assert_eq!(nc.network_version, nv);
+            assert_eq!(u64::from(nc.chain_id), chain_id);
+            assert_eq!(nc.actor_debugging, actor_debugging);
+            assert_eq!(nc.max_call_depth, 1024);
+            assert_eq!(nc.max_sends_per_message, 1 << 14);
+            assert_eq!(nc.max_wasm_stack, 2048);
+            assert_eq!(nc.max_inst_memory_bytes, 512 << 20);
+            assert_eq!(nc.max_memory_bytes, 2 << 30);
+            assert_eq!(nc.max_block_size, MAX_BLOCK_SIZE as usize);
+            assert_eq!(nc.max_block_bytes_per_message, None);
+            assert!(std::ptr::eq(
+                nc.price_list,
+                crate::gas::price_list_by_network_version(nv)
+            ));
+            assert_eq!(nc.builtin_actors_override, None);
+            assert!(nc.actor_redirect.is_empty());
+            assert!(nc.code_denylist.is_empty());
+            assert_eq!(nc.proof_gas_limit, None);
+        }
+    }
+
+    #[test]
+    fn unsupported_network_version() {
+        for nv in [NetworkVersion::V0, NetworkVersion::V20] {
+            let err = NetworkConfig::try_new(nv).unwrap_err();
+            assert_eq!(err.field, "network_version");
+
+            // Changing the network version afterwards is caught when validating.
+            let mut nc = NetworkConfig::new(NetworkVersion::V21);
+            nc.network_version = nv;
+            assert_eq!(invalid_field(&nc), "network_version");
+        }
+        let supported = &super::SUPPORTED_NETWORK_VERSIONS;
+        for nv in u32::from(*supported.start())..=u32::from(*supported.end()) {
+            NetworkConfig::try_new(nv.into())
+                .unwrap()
+                .validate()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "network version 20 is not supported")]
+    fn new_panics_on_unsupported_network_version() {
+        NetworkConfig::new(NetworkVersion::V20);
+    }
+
+    #[test]
+    fn zero_call_depth() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_call_depth(0);
+        assert_eq!(invalid_field(&nc), "max_call_depth");
+    }
+
+    #[test]
+    fn zero_sends_per_message() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_sends_per_message(0);
+        assert_eq!(invalid_field(&nc), "max_sends_per_message");
+    }
+
+    #[test]
+    fn zero_wasm_stack() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_wasm_stack(0);
+        assert_eq!(invalid_field(&nc), "max_wasm_stack");
+    }
+
+    #[test]
+    fn instance_memory_bounds() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+
+        nc.set_max_inst_memory_bytes(0);
+        assert_eq!(invalid_field(&nc), "max_inst_memory_bytes");
+
+        nc.set_max_inst_memory_bytes((64 << 10) + 1);
+        assert_eq!(invalid_field(&nc), "max_inst_memory_bytes");
+
+        nc.set_max_inst_memory_bytes((4 << 30) + (64 << 10));
+        assert_eq!(invalid_field(&nc), "max_inst_memory_bytes");
+
+        nc.set_max_inst_memory_bytes(4 << 30);
+        nc.validate().unwrap();
+    }
+
+    #[test]
+    fn zero_total_memory() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_memory_bytes(0);
+        let err = nc.validate().unwrap_err();
+        assert_eq!(err.field, "max_memory_bytes");
+        assert_eq!(
+            err.to_string(),
+            "invalid network config: `max_memory_bytes` must be positive"
+        );
+    }
+
+    #[test]
+    fn block_size_bounds() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_block_size(0);
+        assert_eq!(invalid_field(&nc), "max_block_size");
+
+        #[cfg(target_pointer_width = "64")]
+        {
+            nc.set_max_block_size(u32::MAX as usize + 1);
+            assert_eq!(invalid_field(&nc), "max_block_size");
+        }
+    }
+
+    #[test]
+    fn zero_block_bytes_per_message() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.set_max_block_bytes_per_message(0);
+        assert_eq!(invalid_field(&nc), "max_block_bytes_per_message");
+
+        nc.set_max_block_bytes_per_message(1);
+        nc.validate().unwrap();
+    }
+
+    #[test]
+    fn actor_state_inspection_requires_nv29() {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.enable_actor_state_inspection();
+        assert_eq!(invalid_field(&nc), "actor_state_inspection");
+
+        let mut nc = NetworkConfig::new(NetworkVersion::V29);
+        nc.enable_actor_state_inspection();
+        nc.validate().unwrap();
+    }
+
+    #[test]
+    fn timestamp_after_genesis() {
+        let nc = NetworkConfig::new(NetworkVersion::V21);
+        let root = Cid::default();
+
+        // Genesis may have a zero timestamp, later epochs may not.
+        nc.for_epoch(0, 0, root).validate().unwrap();
+        nc.for_epoch(10, 1_600_000_000, root).validate().unwrap();
+        let err = nc.for_epoch(10, 0, root).validate().unwrap_err();
+        assert_eq!(err.field, "timestamp");
+
+        // Network config errors are reported first.
+        let mut nc = nc.clone();
+        nc.set_max_block_size(0);
+        assert_eq!(
+            nc.for_epoch(10, 0, root).validate().unwrap_err().field,
+            "max_block_size"
+        );
+    }
 }
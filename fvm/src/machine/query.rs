@@ -0,0 +1,174 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::{Context as _, anyhow, bail};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use ipld_core::ipld::Ipld;
+
+use crate::state_tree::StateTree;
+
+/// A value in an actor's state, as found by [`query_state`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateQuery {
+    /// The ID of the actor.
+    pub actor: ActorID,
+    /// The block containing the value.
+    pub block: Cid,
+    /// The value.
+    pub value: Ipld,
+}
+
+/// Looks up a value in an actor's state. This is meant for debugging.
+///
+/// The path is a `/`-separated list of map keys and list indices, walked from the actor's state
+/// root. Links are followed when the path continues through them, but a link at the end of the
+/// path is returned as-is. The path is walked over the raw IPLD data, so collections such as HAMTs
+/// and AMTs aren't interpreted. An empty path returns the state root.
+pub fn query_state(
+    store: &impl Blockstore,
+    root: &Cid,
+    actor: &Address,
+    path: &str,
+) -> anyhow::Result<StateQuery> {
+    let tree = StateTree::new_from_root(store, root).context("failed to load state tree")?;
+    let id = tree
+        .lookup_id(actor)?
+        .ok_or_else(|| anyhow!("actor {} not found", actor))?;
+    let state = tree
+        .get_actor(id)?
+        .ok_or_else(|| anyhow!("actor {} not found", actor))?;
+
+    let mut block = state.state;
+    let mut value = load_block(store, &block)?;
+    let mut walked = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let at = format!("/{}", walked.join("/"));
+        if let Ipld::Link(link) = value {
+            block = link;
+            value = load_block(store, &block).with_context(|| format!("at {}", at))?;
+        }
+        value = match value {
+            Ipld::Map(mut map) => map
+                .remove(segment)
+                .ok_or_else(|| anyhow!("no key `{}` at {}", segment, at))?,
+            Ipld::List(mut list) => {
+                let index: usize = segment
+                    .parse()
+                    .map_err(|_| anyhow!("expected a list index at {}, got `{}`", at, segment))?;
+                if index >= list.len() {
+                    bail!(
+                        "index {} out of bounds at {} (length {})",
+                        index,
+                        at,
+                        list.len()
+                    );
+                }
+                list.swap_remove(index)
+            }
+            other => bail!("can't look up `{}` in {} at {}", segment, kind(&other), at),
+        };
+        walked.push(segment);
+    }
+    Ok(StateQuery {
+        actor: id,
+        block,
+        value,
+    })
+}
+
+/// Loads a DAG-CBOR block, failing if it's missing or uses some other codec.
+fn load_block(store: &impl Blockstore, k: &Cid) -> anyhow::Result<Ipld> {
+    if k.codec() != DAG_CBOR {
+        bail!("block {} isn't DAG-CBOR", k);
+    }
+    store
+        .get_cbor(k)?
+        .ok_or_else(|| anyhow!("block {} not found", k))
+}
+
+fn kind(value: &Ipld) -> &'static str {
+    match value {
+        Ipld::Null => "null",
+        Ipld::Bool(_) => "a boolean",
+        Ipld::Integer(_) => "an integer",
+        Ipld::Float(_) => "a float",
+        Ipld::String(_) => "a string",
+        Ipld::Bytes(_) => "bytes",
+        Ipld::List(_) => "a list",
+        Ipld::Map(_) => "a map",
+        Ipld::Link(_) => "a link",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::state::StateTreeVersion;
+    use multihash_codetable::Code;
+
+    use super::*;
+    use crate::state_tree::ActorState;
+
+    #[test]
+    fn query_actor_state() {
+        let bs = MemoryBlockstore::default();
+        let child = bs
+            .put_cbor(
+                &Ipld::Map(BTreeMap::from([("x".into(), Ipld::String("y".into()))])),
+                Code::Blake2b256,
+            )
+            .unwrap();
+        let state = bs
+            .put_cbor(
+                &Ipld::Map(BTreeMap::from([(
+                    "a".into(),
+                    Ipld::List(vec![Ipld::Integer(1), Ipld::Link(child)]),
+                )])),
+                Code::Blake2b256,
+            )
+            .unwrap();
+
+        let mut tree = StateTree::new(&bs, StateTreeVersion::V5).unwrap();
+        let actor = ActorState::new(Cid::default(), state, TokenAmount::default(), 0, None);
+        tree.set_actor(100, actor);
+        let root = tree.flush().unwrap();
+        let query = |path| query_state(&bs, &root, &Address::new_id(100), path);
+
+        assert_eq!(query("").unwrap().block, state);
+        assert_eq!(
+            query("a/1/x").unwrap(),
+            StateQuery {
+                actor: 100,
+                block: child,
+                value: Ipld::String("y".into()),
+            }
+        );
+        // Links at the end of the path aren't followed.
+        assert_eq!(
+            query("/a/1/").unwrap(),
+            StateQuery {
+                actor: 100,
+                block: state,
+                value: Ipld::Link(child),
+            }
+        );
+
+        let err = |path| query(path).unwrap_err().to_string();
+        assert_eq!(err("b"), "no key `b` at /");
+        assert_eq!(err("a/2"), "index 2 out of bounds at /a (length 2)");
+        assert_eq!(err("a/x"), "expected a list index at /a, got `x`");
+        assert_eq!(err("a/0/x"), "can't look up `x` in an integer at /a/0");
+        assert_eq!(
+            query_state(&bs, &root, &Address::new_id(101), "")
+                .unwrap_err()
+                .to_string(),
+            "actor f0101 not found"
+        );
+    }
+}
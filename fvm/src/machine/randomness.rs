@@ -0,0 +1,178 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::randomness::RANDOMNESS_LENGTH;
+
+/// The default maximum number of entries in a [`RandomnessCache`].
+pub const DEFAULT_RANDOMNESS_CACHE_SIZE: usize = 1024;
+
+/// Where a randomness value comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RandomnessSource {
+    /// The ticket chain ([`Rand::get_chain_randomness`](crate::externs::Rand::get_chain_randomness)).
+    Chain,
+    /// The beacon ([`Rand::get_beacon_randomness`](crate::externs::Rand::get_beacon_randomness)).
+    Beacon,
+}
+
+/// A bounded cache of the randomness returned by the externs, shared by all the messages (including
+/// cron) executed by a machine.
+///
+/// The randomness for an epoch is fixed by the machine's chain, so caching it changes nothing but
+/// the number of calls to the externs: gas is charged the same way on hits and misses. Domain
+/// separation tags and entropy are mixed in by actors, so they aren't part of the key.
+///
+/// Once full, the oldest entries are evicted first.
+#[derive(Debug)]
+pub struct RandomnessCache {
+    capacity: usize,
+    inner: RefCell<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<(RandomnessSource, ChainEpoch), [u8; RANDOMNESS_LENGTH]>,
+    order: VecDeque<(RandomnessSource, ChainEpoch)>,
+}
+
+impl RandomnessCache {
+    /// Creates a cache holding at most `capacity` entries. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Default::default(),
+        }
+    }
+
+    /// Returns the cached randomness for the given source and epoch, calling `fetch` (and caching
+    /// its result) on a miss. Errors aren't cached.
+    pub fn get_or_fetch(
+        &self,
+        source: RandomnessSource,
+        epoch: ChainEpoch,
+        fetch: impl FnOnce() -> anyhow::Result<[u8; RANDOMNESS_LENGTH]>,
+    ) -> anyhow::Result<[u8; RANDOMNESS_LENGTH]> {
+        let key = (source, epoch);
+        if let Some(randomness) = self.inner.borrow().entries.get(&key) {
+            return Ok(*randomness);
+        }
+
+        // Don't hold the borrow while calling out to the externs.
+        let randomness = fetch()?;
+        if self.capacity == 0 {
+            return Ok(randomness);
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        if inner.entries.insert(key, randomness).is_none() {
+            inner.order.push_back(key);
+            while inner.order.len() > self.capacity {
+                let oldest = inner.order.pop_front().expect("cache order is non-empty");
+                inner.entries.remove(&oldest);
+            }
+        }
+        Ok(randomness)
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn fetch<'a>(
+        calls: &'a Cell<usize>,
+        value: u8,
+    ) -> impl FnOnce() -> anyhow::Result<[u8; RANDOMNESS_LENGTH]> + 'a {
+        move || {
+            calls.set(calls.get() + 1);
+            Ok([value; RANDOMNESS_LENGTH])
+        }
+    }
+
+    #[test]
+    fn caches_by_source_and_epoch() {
+        let cache = RandomnessCache::new(10);
+        let calls = Cell::new(0);
+
+        for _ in 0..3 {
+            let randomness = cache
+                .get_or_fetch(RandomnessSource::Chain, 1, fetch(&calls, 1))
+                .unwrap();
+            assert_eq!(randomness, [1; RANDOMNESS_LENGTH]);
+        }
+        assert_eq!(calls.get(), 1);
+
+        let randomness = cache
+            .get_or_fetch(RandomnessSource::Beacon, 1, fetch(&calls, 2))
+            .unwrap();
+        assert_eq!(randomness, [2; RANDOMNESS_LENGTH]);
+        let randomness = cache
+            .get_or_fetch(RandomnessSource::Chain, 2, fetch(&calls, 3))
+            .unwrap();
+        assert_eq!(randomness, [3; RANDOMNESS_LENGTH]);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let cache = RandomnessCache::new(10);
+        cache
+            .get_or_fetch(RandomnessSource::Chain, 1, || Err(anyhow::anyhow!("nope")))
+            .unwrap_err();
+        assert!(cache.is_empty());
+
+        let calls = Cell::new(0);
+        cache
+            .get_or_fetch(RandomnessSource::Chain, 1, fetch(&calls, 1))
+            .unwrap();
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn evicts_oldest_entries() {
+        let cache = RandomnessCache::new(2);
+        let calls = Cell::new(0);
+        for epoch in 0..3 {
+            cache
+                .get_or_fetch(RandomnessSource::Chain, epoch, fetch(&calls, 0))
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+
+        // Epoch 0 was evicted, epoch 2 wasn't.
+        cache
+            .get_or_fetch(RandomnessSource::Chain, 2, fetch(&calls, 0))
+            .unwrap();
+        assert_eq!(calls.get(), 3);
+        cache
+            .get_or_fetch(RandomnessSource::Chain, 0, fetch(&calls, 0))
+            .unwrap();
+        assert_eq!(calls.get(), 4);
+
+        // A zero capacity disables caching.
+        let cache = RandomnessCache::new(0);
+        for _ in 0..2 {
+            cache
+                .get_or_fetch(RandomnessSource::Chain, 0, fetch(&calls, 0))
+                .unwrap();
+        }
+        assert_eq!(calls.get(), 6);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use anyhow::Context as _;
+use cid::Cid;
+use fvm_ipld_amt::{Amt, Amtv0};
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::event::StampedEvent;
+use fvm_shared::receipt::Receipt;
+
+/// The bit width of the AMT holding a message's events.
+pub const EVENTS_AMT_BITWIDTH: u32 = 5;
+
+/// Builds the AMT of message receipts referenced by the block header, returning its root.
+///
+/// For historical reasons, receipts are stored in a legacy (v0) AMT with the default bit width.
+/// The root of an empty receipt list is the root of an empty AMT.
+pub fn build_receipts_root(store: &impl Blockstore, receipts: &[Receipt]) -> anyhow::Result<Cid> {
+    Amtv0::new_from_iter(store, receipts).context("failed to construct receipts AMT")
+}
+
+/// Builds the AMT of events emitted by a single message, returning its root. Returns `None` if
+/// there are no events, in which case the receipt's `events_root` must be `None` as well.
+pub fn build_events_root(
+    store: &impl Blockstore,
+    events: &[StampedEvent],
+) -> anyhow::Result<Option<Cid>> {
+    if events.is_empty() {
+        return Ok(None);
+    }
+    Amt::new_from_iter_with_bit_width(store, EVENTS_AMT_BITWIDTH, events)
+        .map(Some)
+        .context("failed to construct events AMT")
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_encoding::RawBytes;
+    use fvm_shared::IPLD_RAW;
+    use fvm_shared::error::ExitCode;
+    use fvm_shared::event::{Entry, Flags};
+
+    use super::*;
+
+    fn events() -> Vec<StampedEvent> {
+        vec![
+            StampedEvent::new(
+                1000,
+                vec![
+                    Entry {
+                        flags: Flags::FLAG_INDEXED_ALL,
+                        key: "type".into(),
+                        codec: IPLD_RAW,
+                        value: b"transfer".to_vec(),
+                    },
+                    Entry {
+                        flags: Flags::FLAG_INDEXED_VALUE,
+                        key: "amount".into(),
+                        codec: IPLD_RAW,
+                        value: vec![1, 0],
+                    },
+                ]
+                .into(),
+            ),
+            StampedEvent::new(1001, vec![].into()),
+        ]
+    }
+
+    #[test]
+    fn empty() {
+        let bs = MemoryBlockstore::default();
+        assert_eq!(build_events_root(&bs, &[]).unwrap(), None);
+        assert_eq!(
+            build_receipts_root(&bs, &[]).unwrap().to_string(),
+            "bafy2bzacedswlcz5ddgqnyo3sak3jmhmkxashisnlpq6ujgyhe4mlobzpnhs6"
+        );
+    }
+
+    #[test]
+    fn golden() {
+        let bs = MemoryBlockstore::default();
+        let events_root = build_events_root(&bs, &events()).unwrap().unwrap();
+        assert_eq!(
+            events_root.to_string(),
+            "bafy2bzacecnbhomvto4yizsdps5fxrl5tbk5gx5n6tpxctqkkxzxk4i6veupw"
+        );
+
+        let receipts = [
+            Receipt {
+                exit_code: ExitCode::OK,
+                return_data: RawBytes::default(),
+                gas_used: 1234,
+                events_root: None,
+            },
+            Receipt {
+                exit_code: ExitCode::USR_ILLEGAL_ARGUMENT,
+                return_data: RawBytes::new(vec![1, 2, 3]),
+                gas_used: 5678,
+                events_root: Some(events_root),
+            },
+            Receipt {
+                exit_code: ExitCode::OK,
+                return_data: RawBytes::new(vec![0x82, 0x01, 0x02]),
+                gas_used: 0,
+                events_root: None,
+            },
+        ];
+        assert_eq!(
+            build_receipts_root(&bs, &receipts).unwrap().to_string(),
+            "bafy2bzacebu34tosreu2nn5rh6chz4sdh23jazb63phz55unvqkp5gtaxs57w"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature;
+use fvm_shared::message::{Message, SignedMessage};
+use multihash_codetable::{Code, MultihashDigest};
+
+use super::Manifest;
+use crate::account_actor;
+use crate::state_tree::StateTree;
+
+/// Why [`verify_signed_message`] rejected a message.
+#[derive(thiserror::Error, Debug)]
+pub enum SignatureError {
+    /// The sender doesn't exist.
+    #[error("sender {0} not found")]
+    UnknownSender(Address),
+    /// The sender isn't an account actor, so it has no key to sign with.
+    #[error("sender {0} is not an account actor")]
+    NotAnAccount(Address),
+    /// The signature doesn't match the message and the sender's key.
+    #[error("invalid signature from {0}: {1}")]
+    InvalidSignature(Address, String),
+    /// The state tree couldn't be read.
+    #[error("fatal error: {0}")]
+    Fatal(anyhow::Error),
+}
+
+/// Verifies a signed message's signature against the key address of its sender, which must be an
+/// account actor in the given state tree, with the same signature verification as actors get from
+/// the kernel.
+///
+/// Both secp256k1 and BLS signatures sign the bytes of the unsigned message's CID. Secp256k1
+/// signatures are over the blake2b-256 hash of these bytes.
+pub fn verify_signed_message<B: Blockstore>(
+    state_tree: &StateTree<B>,
+    builtin_actors: &Manifest,
+    msg: &SignedMessage,
+) -> Result<(), SignatureError> {
+    let from = msg.message.from;
+    let fatal = |e| SignatureError::Fatal(anyhow::Error::from(e));
+
+    let actor = match state_tree.lookup_id(&from).map_err(fatal)? {
+        Some(id) => state_tree.get_actor(id).map_err(fatal)?,
+        None => None,
+    }
+    .ok_or(SignatureError::UnknownSender(from))?;
+    let key_address = account_actor::key_address(state_tree.store(), builtin_actors, &actor)
+        .map_err(SignatureError::Fatal)?
+        .ok_or(SignatureError::NotAnAccount(from))?;
+
+    let cid = message_cid(&msg.message).map_err(SignatureError::Fatal)?;
+    signature::verify(
+        msg.signature.signature_type(),
+        msg.signature.bytes(),
+        &cid.to_bytes(),
+        &key_address,
+    )
+    .map_err(|e| SignatureError::InvalidSignature(from, e))
+}
+
+/// Returns the CID of an unsigned message.
+pub(crate) fn message_cid(msg: &Message) -> anyhow::Result<Cid> {
+    Ok(Cid::new_v1(
+        DAG_CBOR,
+        Code::Blake2b256.digest(&to_vec(msg)?),
+    ))
+}
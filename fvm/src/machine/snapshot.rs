@@ -0,0 +1,479 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io;
+
+use anyhow::{Context as _, anyhow, bail};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_car::CarReader;
+use fvm_ipld_encoding::{DAG_CBOR, from_slice};
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::state::StateRoot;
+use ipld_core::ipld::Ipld;
+
+/// The number of blocks written to the store at once (and between progress reports).
+const SNAPSHOT_BATCH_SIZE: usize = 1000;
+
+/// The number of fields in a block header.
+const BLOCK_HEADER_FIELDS: usize = 16;
+/// The index of the `Height` field in a block header.
+const BLOCK_HEADER_HEIGHT: usize = 7;
+/// The index of the `ParentStateRoot` field in a block header.
+const BLOCK_HEADER_PARENT_STATE_ROOT: usize = 8;
+
+/// Which of the blocks in a snapshot [`import_snapshot`] checks against their CIDs.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SnapshotVerification {
+    /// Trust the snapshot.
+    None,
+    /// Verify one in every `n` blocks, starting with the first (every block if `n` is 0 or 1).
+    Sampled(u64),
+    /// Verify every block.
+    #[default]
+    Full,
+}
+
+impl SnapshotVerification {
+    fn verifies(&self, index: u64) -> bool {
+        match *self {
+            SnapshotVerification::None => false,
+            SnapshotVerification::Sampled(n) => index % n.max(1) == 0,
+            SnapshotVerification::Full => true,
+        }
+    }
+}
+
+/// How far [`import_snapshot`] has got, as reported to [`SnapshotOptions::progress`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotProgress {
+    /// The number of blocks read so far, including the skipped ones.
+    pub blocks: u64,
+    /// The number of bytes read from the snapshot so far.
+    pub bytes: u64,
+}
+
+/// Options for [`import_snapshot`].
+#[derive(Default)]
+pub struct SnapshotOptions<'a> {
+    /// Which blocks to check against their CIDs. Defaults to all of them.
+    pub verification: SnapshotVerification,
+    /// Skip (without verifying) the blocks already in the store, e.g., to resume an interrupted
+    /// import. The snapshot is still read in full.
+    pub skip_existing: bool,
+    /// Called periodically while the snapshot is read, and once at the end.
+    pub progress: Option<Box<dyn FnMut(&SnapshotProgress) + 'a>>,
+}
+
+/// What [`import_snapshot`] found in a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    /// The roots listed in the snapshot's header.
+    pub roots: Vec<Cid>,
+    /// The state roots found among (or referenced by block headers among) the roots, deduplicated
+    /// and in order.
+    pub state_roots: Vec<Cid>,
+    /// The epoch of the block headers among the roots, if any. The state roots referenced by the
+    /// headers are the states at the start of this epoch.
+    pub epoch: Option<ChainEpoch>,
+    /// The number of blocks in the snapshot.
+    pub blocks: u64,
+    /// The number of blocks skipped because they were already in the store.
+    pub skipped_blocks: u64,
+    /// The number of blocks checked against their CIDs.
+    pub verified_blocks: u64,
+    /// The size of the snapshot, in bytes.
+    pub bytes: u64,
+}
+
+/// Counts the bytes read from a reader.
+struct CountingReader<R> {
+    inner: R,
+    bytes: u64,
+}
+
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+/// Imports a chain snapshot (a CAR file, such as the ones exported by Lotus) into the store, and
+/// identifies the state roots to build machines on.
+///
+/// The snapshot's roots may be block headers (e.g., the tipset a Lotus snapshot was taken at), in
+/// which case their parent state roots (and epoch) are returned, or state roots. Other roots are
+/// ignored, but the import fails if there are no state roots at all, or if a state root is missing
+/// from the snapshot (and the store).
+///
+/// A machine can then be built with a [`MachineContext`](super::MachineContext) from
+/// [`NetworkConfig::for_epoch`](super::NetworkConfig::for_epoch), given one of the state roots.
+pub fn import_snapshot(
+    reader: impl io::Read,
+    store: &impl Blockstore,
+    mut options: SnapshotOptions<'_>,
+) -> anyhow::Result<SnapshotInfo> {
+    let mut car = CarReader::new_unchecked(CountingReader {
+        inner: reader,
+        bytes: 0,
+    })
+    .context("failed to read snapshot header")?;
+
+    let mut info = SnapshotInfo {
+        roots: car.header.roots.clone(),
+        state_roots: Vec::new(),
+        epoch: None,
+        blocks: 0,
+        skipped_blocks: 0,
+        verified_blocks: 0,
+        bytes: 0,
+    };
+
+    let mut batch = Vec::with_capacity(SNAPSHOT_BATCH_SIZE);
+    while let Some(block) = car.next() {
+        let block =
+            block.with_context(|| format!("failed to read snapshot block {}", info.blocks))?;
+        let index = info.blocks;
+        info.blocks += 1;
+
+        if options.skip_existing && store.has(&block.cid)? {
+            info.skipped_blocks += 1;
+            continue;
+        }
+        if options.verification.verifies(index) {
+            block
+                .validate()
+                .with_context(|| format!("snapshot block {index} is corrupted"))?;
+            info.verified_blocks += 1;
+        }
+
+        batch.push((block.cid, block.data));
+        if batch.len() >= SNAPSHOT_BATCH_SIZE {
+            store
+                .put_many_keyed(batch.drain(..))
+                .context("failed to write snapshot blocks")?;
+            if let Some(progress) = &mut options.progress {
+                progress(&SnapshotProgress {
+                    blocks: info.blocks,
+                    bytes: car.reader.bytes,
+                });
+            }
+        }
+    }
+    store
+        .put_many_keyed(batch)
+        .context("failed to write snapshot blocks")?;
+    info.bytes = car.reader.bytes;
+    if let Some(progress) = &mut options.progress {
+        progress(&SnapshotProgress {
+            blocks: info.blocks,
+            bytes: info.bytes,
+        });
+    }
+
+    for root in &info.roots {
+        let (state_root, epoch) = match load_root(store, root)? {
+            SnapshotRoot::StateRoot => (*root, None),
+            SnapshotRoot::BlockHeader { state_root, height } => {
+                let parent = load_root(store, &state_root)
+                    .with_context(|| format!("failed to load the parent state of {root}"))?;
+                if !matches!(parent, SnapshotRoot::StateRoot) {
+                    bail!("the parent state {state_root} of {root} isn't a state root");
+                }
+                (state_root, Some(height))
+            }
+            SnapshotRoot::Other => continue,
+        };
+
+        match (info.epoch, epoch) {
+            (Some(a), Some(b)) if a != b => {
+                bail!("snapshot block headers are at different epochs ({a} and {b})")
+            }
+            (None, Some(_)) => info.epoch = epoch,
+            _ => {}
+        }
+        if !info.state_roots.contains(&state_root) {
+            info.state_roots.push(state_root);
+        }
+    }
+    if info.state_roots.is_empty() {
+        bail!("no state roots among the snapshot's roots");
+    }
+
+    Ok(info)
+}
+
+/// The kinds of snapshot roots.
+enum SnapshotRoot {
+    StateRoot,
+    BlockHeader { state_root: Cid, height: ChainEpoch },
+    Other,
+}
+
+/// Loads a snapshot root, working out what kind of root it is.
+fn load_root(store: &impl Blockstore, root: &Cid) -> anyhow::Result<SnapshotRoot> {
+    let data = store
+        .get(root)?
+        .ok_or_else(|| anyhow!("snapshot root {root} is missing"))?;
+    if root.codec() != DAG_CBOR {
+        return Ok(SnapshotRoot::Other);
+    }
+    if from_slice::<StateRoot>(&data).is_ok() {
+        return Ok(SnapshotRoot::StateRoot);
+    }
+    if let Ok(Ipld::List(fields)) = from_slice::<Ipld>(&data)
+        && fields.len() == BLOCK_HEADER_FIELDS
+        && let Ipld::Integer(height) = &fields[BLOCK_HEADER_HEIGHT]
+        && let Ipld::Link(state_root) = &fields[BLOCK_HEADER_PARENT_STATE_ROOT]
+    {
+        let height = (*height)
+            .try_into()
+            .context("invalid block header height")?;
+        return Ok(SnapshotRoot::BlockHeader {
+            state_root: *state_root,
+            height,
+        });
+    }
+    Ok(SnapshotRoot::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use fvm_ipld_car::{Block, CarHeader, CarWriter};
+    use fvm_ipld_encoding::{CborStore, IPLD_RAW};
+    use fvm_shared::state::StateTreeVersion;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+    use crate::state_tree::{ActorState, StateTree};
+
+    const HEIGHT: ChainEpoch = 1234;
+    const ACTOR_ID: u64 = 1000;
+
+    /// Collects the blocks put into it.
+    #[derive(Default)]
+    struct Collector(RefCell<Vec<(Cid, Vec<u8>)>>);
+
+    impl Blockstore for Collector {
+        fn get(&self, _: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.0.borrow_mut().push((*k, block.into()));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        state_root: Cid,
+        header: Cid,
+        /// The header, then the rest of the state, then the state root.
+        blocks: Vec<(Cid, Vec<u8>)>,
+    }
+
+    /// A snapshot of a state tree with a single actor, at a (stripped down) block header.
+    fn fixture() -> Fixture {
+        let mut state_tree =
+            StateTree::new(MemoryBlockstore::default(), StateTreeVersion::V5).unwrap();
+        let code = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"code"));
+        state_tree.set_actor(ACTOR_ID, ActorState::new_empty(code, None));
+        let state_root = state_tree.flush().unwrap();
+        let store = state_tree.into_store();
+
+        let mut fields = vec![Ipld::Null; BLOCK_HEADER_FIELDS];
+        fields[BLOCK_HEADER_HEIGHT] = Ipld::Integer(HEIGHT.into());
+        fields[BLOCK_HEADER_PARENT_STATE_ROOT] = Ipld::Link(state_root);
+        let header = store
+            .put_cbor(&Ipld::List(fields), Code::Blake2b256)
+            .unwrap();
+
+        let collector = Collector::default();
+        store.copy_to(&collector).unwrap();
+        let mut blocks = collector.0.into_inner();
+        blocks.sort_by_key(|(cid, _)| (*cid != header, *cid == state_root, *cid));
+        Fixture {
+            state_root,
+            header,
+            blocks,
+        }
+    }
+
+    fn write_car(roots: Vec<Cid>, blocks: &[(Cid, Vec<u8>)]) -> Vec<u8> {
+        let mut car = Vec::new();
+        let mut writer = CarWriter::new(CarHeader::from(roots), &mut car).unwrap();
+        for block in blocks {
+            writer.write(Block::from(block.clone())).unwrap();
+        }
+        writer.flush().unwrap();
+        car
+    }
+
+    fn assert_state_loaded(store: &MemoryBlockstore, state_root: &Cid) {
+        let state_tree = StateTree::new_from_root(store, state_root).unwrap();
+        assert!(state_tree.get_actor(ACTOR_ID).unwrap().is_some());
+    }
+
+    #[test]
+    fn imports_block_header_snapshots() {
+        let fixture = fixture();
+        let car = write_car(vec![fixture.header], &fixture.blocks);
+        let num_blocks = fixture.blocks.len() as u64;
+
+        let store = MemoryBlockstore::default();
+        let mut reports = Vec::new();
+        let info = import_snapshot(
+            &*car,
+            &store,
+            SnapshotOptions {
+                progress: Some(Box::new(|progress| reports.push(*progress))),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            info,
+            SnapshotInfo {
+                roots: vec![fixture.header],
+                state_roots: vec![fixture.state_root],
+                epoch: Some(HEIGHT),
+                blocks: num_blocks,
+                skipped_blocks: 0,
+                verified_blocks: num_blocks,
+                bytes: car.len() as u64,
+            }
+        );
+        assert_eq!(
+            reports,
+            [SnapshotProgress {
+                blocks: num_blocks,
+                bytes: car.len() as u64,
+            }]
+        );
+        for (cid, data) in &fixture.blocks {
+            assert_eq!(store.get(cid).unwrap().as_ref(), Some(data));
+        }
+        assert_state_loaded(&store, &fixture.state_root);
+    }
+
+    #[test]
+    fn imports_state_root_snapshots() {
+        let fixture = fixture();
+        let car = write_car(vec![fixture.state_root], &fixture.blocks[1..]);
+
+        let store = MemoryBlockstore::default();
+        let info = import_snapshot(&*car, &store, Default::default()).unwrap();
+        assert_eq!(info.state_roots, [fixture.state_root]);
+        assert_eq!(info.epoch, None);
+        assert_state_loaded(&store, &fixture.state_root);
+    }
+
+    #[test]
+    fn resumes_imports() {
+        let fixture = fixture();
+        let car = write_car(vec![fixture.header], &fixture.blocks);
+        let num_blocks = fixture.blocks.len() as u64;
+
+        // An interrupted import only loaded some of the blocks.
+        let store = MemoryBlockstore::default();
+        let loaded = fixture.blocks.len() / 2;
+        store
+            .put_many_keyed(fixture.blocks[..loaded].iter().cloned())
+            .unwrap();
+
+        let info = import_snapshot(
+            &*car,
+            &store,
+            SnapshotOptions {
+                skip_existing: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(info.blocks, num_blocks);
+        assert_eq!(info.skipped_blocks, loaded as u64);
+        assert_eq!(info.verified_blocks, num_blocks - loaded as u64);
+        assert_state_loaded(&store, &fixture.state_root);
+    }
+
+    #[test]
+    fn verifies_blocks() {
+        let fixture = fixture();
+        // Neither the header nor the state root.
+        let mut blocks = fixture.blocks.clone();
+        blocks[1].1.push(0);
+        let car = write_car(vec![fixture.header], &blocks);
+        let num_blocks = blocks.len() as u64;
+
+        let import = |verification| {
+            import_snapshot(
+                &*car,
+                &MemoryBlockstore::default(),
+                SnapshotOptions {
+                    verification,
+                    ..Default::default()
+                },
+            )
+        };
+
+        for verification in [SnapshotVerification::Full, SnapshotVerification::Sampled(1)] {
+            let err = import(verification).unwrap_err();
+            assert!(
+                err.to_string().contains("snapshot block 1 is corrupted"),
+                "{err:?}"
+            );
+        }
+
+        // Sampling may miss the corrupted block.
+        let info = import(SnapshotVerification::Sampled(2)).unwrap();
+        assert_eq!(info.verified_blocks, num_blocks.div_ceil(2));
+        let info = import(SnapshotVerification::None).unwrap();
+        assert_eq!(info.verified_blocks, 0);
+    }
+
+    #[test]
+    fn requires_state_roots() {
+        let fixture = fixture();
+        let raw = (
+            Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"raw")),
+            b"raw".to_vec(),
+        );
+        let mut blocks = fixture.blocks.clone();
+        blocks.push(raw.clone());
+
+        // Roots that aren't state roots or block headers are ignored...
+        let car = write_car(vec![raw.0, fixture.header], &blocks);
+        let info =
+            import_snapshot(&*car, &MemoryBlockstore::default(), Default::default()).unwrap();
+        assert_eq!(info.state_roots, [fixture.state_root]);
+
+        // ... but there must be at least one.
+        let car = write_car(vec![raw.0], &blocks);
+        let err =
+            import_snapshot(&*car, &MemoryBlockstore::default(), Default::default()).unwrap_err();
+        assert!(err.to_string().contains("no state roots"), "{err:?}");
+
+        // And the roots (and the states they reference) must be present.
+        let car = write_car(vec![fixture.header], &blocks[1..]);
+        let err =
+            import_snapshot(&*car, &MemoryBlockstore::default(), Default::default()).unwrap_err();
+        assert!(err.to_string().contains("is missing"), "{err:?}");
+        let state_root = blocks
+            .iter()
+            .position(|(cid, _)| *cid == fixture.state_root)
+            .unwrap();
+        blocks.remove(state_root);
+        let car = write_car(vec![fixture.header], &blocks);
+        let err =
+            import_snapshot(&*car, &MemoryBlockstore::default(), Default::default()).unwrap_err();
+        assert!(
+            format!("{err:#}").contains(&format!("{} is missing", fixture.state_root)),
+            "{err:?}"
+        );
+    }
+}
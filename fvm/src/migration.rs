@@ -0,0 +1,419 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Deterministic migration of the state tree across network upgrades.
+//!
+//! A network upgrade may change the code CIDs of builtin actors and, occasionally, the layout of
+//! their state. A [`Migration`] maps the old builtin actor code CIDs to the new ones and applies a
+//! registered [`StateMigration`] to the state of every actor whose (old) code has one, producing a
+//! new state root.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context as _, anyhow};
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_shared::ActorID;
+use fvm_shared::clock::ChainEpoch;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::machine::Manifest;
+use crate::state_tree::{ActorState, StateTree};
+
+/// The default number of actors re-resolved from the new state tree after a migration.
+pub const DEFAULT_VERIFICATION_SAMPLE_SIZE: usize = 100;
+
+/// Migrates the state of a single actor type.
+///
+/// Migrations run in parallel, so implementations must be deterministic and must not depend on
+/// the order in which actors are migrated.
+pub trait StateMigration<BS>: Send + Sync {
+    /// Migrates an actor's state, writing any new blocks to the store and returning the new state
+    /// root.
+    fn migrate_state(&self, store: &BS, old_head: Cid, epoch: ChainEpoch) -> anyhow::Result<Cid>;
+}
+
+impl<BS, F> StateMigration<BS> for F
+where
+    F: Fn(&BS, Cid, ChainEpoch) -> anyhow::Result<Cid> + Send + Sync,
+{
+    fn migrate_state(&self, store: &BS, old_head: Cid, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        self(store, old_head, epoch)
+    }
+}
+
+/// Progress of a running migration, reported after each actor is migrated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// The number of actors migrated so far.
+    pub migrated: usize,
+    /// The total number of actors in the state tree.
+    pub total: usize,
+}
+
+/// A state tree migration: a mapping from old to new actor code CIDs, along with the state
+/// migrations to apply to actors by (old) code CID.
+pub struct Migration<BS> {
+    code_map: HashMap<Cid, Cid>,
+    migrations: HashMap<Cid, Box<dyn StateMigration<BS>>>,
+    verification_sample_size: usize,
+}
+
+impl<BS> Default for Migration<BS> {
+    fn default() -> Self {
+        Self {
+            code_map: HashMap::new(),
+            migrations: HashMap::new(),
+            verification_sample_size: DEFAULT_VERIFICATION_SAMPLE_SIZE,
+        }
+    }
+}
+
+impl<BS> Migration<BS>
+where
+    BS: Blockstore + Sync,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rewrites the code CID `old` to `new`.
+    pub fn map_code(mut self, old: Cid, new: Cid) -> Self {
+        self.code_map.insert(old, new);
+        self
+    }
+
+    /// Rewrites the code CIDs of all builtin actors in the `old` manifest to the code CIDs of the
+    /// same builtin actors in the `new` manifest.
+    ///
+    /// Builtin actor types are identified by their (consensus-visible) builtin actor type IDs, which
+    /// are stable across manifest versions. Every builtin actor in the old manifest must exist in
+    /// the new one.
+    pub fn map_manifests(mut self, old: &Manifest, new: &Manifest) -> anyhow::Result<Self> {
+        for old_code in old.builtin_actor_codes() {
+            let id = old.id_by_code(old_code);
+            let new_code = new
+                .code_by_id(id)
+                .with_context(|| format!("builtin actor type {id} missing from new manifest"))?;
+            self.code_map.insert(*old_code, *new_code);
+        }
+        Ok(self)
+    }
+
+    /// Registers a state migration for actors with the specified (old) code CID, replacing any
+    /// previously registered migration for that code.
+    pub fn add_migration(
+        mut self,
+        old_code: Cid,
+        migration: impl StateMigration<BS> + 'static,
+    ) -> Self {
+        self.migrations.insert(old_code, Box::new(migration));
+        self
+    }
+
+    /// Sets the number of actors to re-resolve from the new state tree once the migration
+    /// completes. Zero disables verification.
+    pub fn verification_sample_size(mut self, size: usize) -> Self {
+        self.verification_sample_size = size;
+        self
+    }
+
+    /// Migrates the state tree rooted at `state_root`, returning the new state root.
+    ///
+    /// Actors are migrated in parallel and `progress` is called (from any thread) after each actor
+    /// is migrated. Actors with no registered state migration keep their state, and actors with
+    /// unmapped code CIDs (e.g., user-deployed actors) keep their code.
+    pub fn run<F>(
+        &self,
+        store: &BS,
+        state_root: &Cid,
+        epoch: ChainEpoch,
+        progress: F,
+    ) -> anyhow::Result<Cid>
+    where
+        F: Fn(MigrationProgress) + Sync,
+    {
+        let mut state_tree = StateTree::new_from_root(store, state_root)?;
+
+        let mut actors = Vec::new();
+        state_tree.for_each(|addr, actor| {
+            let id = addr
+                .id()
+                .with_context(|| format!("non-id address {addr} in the state tree"))?;
+            actors.push((id, actor.clone()));
+            Ok(())
+        })?;
+
+        let total = actors.len();
+        let migrated = AtomicUsize::new(0);
+        let actors = actors
+            .par_iter()
+            .map(|(id, actor)| -> anyhow::Result<(ActorID, ActorState)> {
+                let actor = self
+                    .migrate_actor(store, actor, epoch)
+                    .with_context(|| format!("failed to migrate actor {id}"))?;
+                progress(MigrationProgress {
+                    migrated: migrated.fetch_add(1, Ordering::Relaxed) + 1,
+                    total,
+                });
+                Ok((*id, actor))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        for (id, actor) in &actors {
+            state_tree.set_actor(*id, actor.clone());
+        }
+        let new_root = state_tree.flush()?;
+
+        self.verify(store, &new_root, &actors)?;
+        Ok(new_root)
+    }
+
+    /// Migrates a single actor's code and state.
+    fn migrate_actor(
+        &self,
+        store: &BS,
+        actor: &ActorState,
+        epoch: ChainEpoch,
+    ) -> anyhow::Result<ActorState> {
+        let mut actor = actor.clone();
+        if let Some(migration) = self.migrations.get(&actor.code) {
+            actor.state = migration.migrate_state(store, actor.state, epoch)?;
+        }
+        if let Some(new_code) = self.code_map.get(&actor.code) {
+            actor.code = *new_code;
+        }
+        Ok(actor)
+    }
+
+    /// Re-resolves an evenly spaced sample of the migrated actors from the new state tree, checking
+    /// that they match the migration's output.
+    fn verify(
+        &self,
+        store: &BS,
+        new_root: &Cid,
+        actors: &[(ActorID, ActorState)],
+    ) -> anyhow::Result<()> {
+        if self.verification_sample_size == 0 || actors.is_empty() {
+            return Ok(());
+        }
+
+        let state_tree = StateTree::new_from_root(store, new_root)?;
+        let step = (actors.len() / self.verification_sample_size).max(1);
+        for (id, expected) in actors
+            .iter()
+            .step_by(step)
+            .take(self.verification_sample_size)
+        {
+            match state_tree.get_actor(*id)? {
+                Some(actor) if actor == *expected => {}
+                Some(_) => return Err(anyhow!("actor {id} differs after migration")),
+                None => return Err(anyhow!("actor {id} missing after migration")),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use fvm_ipld_encoding::CborStore;
+    use fvm_ipld_encoding::tuple::*;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::state::StateTreeVersion;
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+
+    /// A thread-safe in-memory blockstore (the `MemoryBlockstore` isn't `Sync`).
+    #[derive(Default)]
+    struct SyncBlockstore(Mutex<HashMap<Cid, Vec<u8>>>);
+
+    impl Blockstore for SyncBlockstore {
+        fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.lock().unwrap().get(k).cloned())
+        }
+
+        fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+            self.0.lock().unwrap().insert(*k, block.into());
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize_tuple, Deserialize_tuple)]
+    struct AccountStateV1 {
+        address: Address,
+    }
+
+    /// The account state with a new field.
+    #[derive(Serialize_tuple, Deserialize_tuple)]
+    struct AccountStateV2 {
+        address: Address,
+        nonce_hint: u64,
+    }
+
+    const UPGRADE_EPOCH: ChainEpoch = 1000;
+    const ACCOUNTS: ActorID = 200;
+    const USER_ACTOR: ActorID = 5000;
+
+    fn user_code() -> Cid {
+        Cid::new_v1(fvm_shared::IPLD_RAW, Code::Blake2b256.digest(b"user"))
+    }
+
+    fn migrate_account(
+        store: &SyncBlockstore,
+        old_head: Cid,
+        _epoch: ChainEpoch,
+    ) -> anyhow::Result<Cid> {
+        let old: AccountStateV1 = store
+            .get_cbor(&old_head)?
+            .context("missing account state")?;
+        let new = AccountStateV2 {
+            address: old.address,
+            nonce_hint: 0,
+        };
+        store.put_cbor(&new, Code::Blake2b256)
+    }
+
+    /// Builds a state tree with accounts (using the `account_code`) and a user actor, writing each
+    /// account's state with `account_state`.
+    fn build_state<S: fvm_ipld_encoding::ser::Serialize>(
+        store: &SyncBlockstore,
+        account_code: Cid,
+        account_state: impl Fn(Address) -> S,
+    ) -> Cid {
+        let mut state_tree = StateTree::new(store, StateTreeVersion::V5).unwrap();
+        for id in 100..100 + ACCOUNTS {
+            let state = store
+                .put_cbor(&account_state(Address::new_id(id)), Code::Blake2b256)
+                .unwrap();
+            state_tree.set_actor(
+                id,
+                ActorState::new(account_code, state, TokenAmount::from_atto(id), id, None),
+            );
+        }
+        let user_state = store.put_cbor(&[0u8; 0], Code::Blake2b256).unwrap();
+        state_tree.set_actor(
+            USER_ACTOR,
+            ActorState::new(user_code(), user_state, TokenAmount::default(), 1, None),
+        );
+        state_tree.flush().unwrap()
+    }
+
+    fn manifests() -> (Manifest, Manifest) {
+        let old = Manifest::dummy();
+        let new = Manifest::new(
+            Manifest::DUMMY_CODES
+                .iter()
+                .map(|(name, code)| (*name, Cid::new_v1(code.codec() + 1, *code.hash()))),
+        )
+        .unwrap();
+        (old, new)
+    }
+
+    fn account_migration(old: &Manifest, new: &Manifest) -> Migration<SyncBlockstore> {
+        Migration::new()
+            .map_manifests(old, new)
+            .unwrap()
+            .add_migration(*old.get_account_code(), migrate_account)
+    }
+
+    #[test]
+    fn migrate_account_state() {
+        let (old, new) = manifests();
+        let store = SyncBlockstore::default();
+        let root = build_state(&store, *old.get_account_code(), |address| AccountStateV1 {
+            address,
+        });
+
+        let calls = AtomicUsize::new(0);
+        let new_root = account_migration(&old, &new)
+            .run(&store, &root, UPGRADE_EPOCH, |p| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                assert_eq!(p.total, ACCOUNTS as usize + 1);
+                assert!(p.migrated >= 1 && p.migrated <= p.total);
+            })
+            .unwrap();
+        assert_eq!(calls.into_inner(), ACCOUNTS as usize + 1);
+
+        // The result matches a state tree built directly with the new code and state.
+        let expected = build_state(&store, *new.get_account_code(), |address| AccountStateV2 {
+            address,
+            nonce_hint: 0,
+        });
+        assert_eq!(new_root, expected);
+
+        // The user actor is untouched.
+        let state_tree = StateTree::new_from_root(&store, &new_root).unwrap();
+        let user = state_tree.get_actor(USER_ACTOR).unwrap().unwrap();
+        assert_eq!(user.code, user_code());
+        assert_eq!(user.sequence, 1);
+
+        // And migrating again from the same root is deterministic.
+        let again = account_migration(&old, &new)
+            .run(&store, &root, UPGRADE_EPOCH, |_| {})
+            .unwrap();
+        assert_eq!(again, new_root);
+    }
+
+    #[test]
+    fn code_only_migration() {
+        let (old, new) = manifests();
+        let store = SyncBlockstore::default();
+        let root = build_state(&store, *old.get_account_code(), |address| AccountStateV1 {
+            address,
+        });
+
+        let new_root = Migration::new()
+            .map_manifests(&old, &new)
+            .unwrap()
+            .run(&store, &root, UPGRADE_EPOCH, |_| {})
+            .unwrap();
+        let expected = build_state(&store, *new.get_account_code(), |address| AccountStateV1 {
+            address,
+        });
+        assert_eq!(new_root, expected);
+    }
+
+    #[test]
+    fn failed_state_migration() {
+        let (old, new) = manifests();
+        let store = SyncBlockstore::default();
+        let root = build_state(&store, *old.get_account_code(), |address| AccountStateV1 {
+            address,
+        });
+
+        let err = Migration::new()
+            .map_manifests(&old, &new)
+            .unwrap()
+            .add_migration(
+                *old.get_account_code(),
+                |_: &SyncBlockstore, _: Cid, _: ChainEpoch| -> anyhow::Result<Cid> {
+                    Err(anyhow!("boom"))
+                },
+            )
+            .run(&store, &root, UPGRADE_EPOCH, |_| {})
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("boom"));
+    }
+
+    #[test]
+    fn missing_builtin_actor() {
+        let old = Manifest::dummy();
+        let new = Manifest::new(
+            Manifest::DUMMY_CODES
+                .iter()
+                .copied()
+                .filter(|(name, _)| *name != "cron"),
+        )
+        .unwrap();
+        assert!(
+            Migration::<SyncBlockstore>::new()
+                .map_manifests(&old, &new)
+                .is_err()
+        );
+    }
+}
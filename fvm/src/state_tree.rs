@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 use anyhow::{Context as _, anyhow};
 use cid::Cid;
@@ -34,6 +35,32 @@ pub struct StateTree<S> {
     /// Snapshot layers. Each layer contains points in the actor/resolve cache histories to which
     /// said caches will be reverted on revert.
     layers: Vec<StateSnapLayer>,
+    /// The original states of the actors written since change tracking began, if enabled. See
+    /// [`StateTree::begin_tracking_changes`].
+    tracked_changes: Option<BTreeMap<ActorID, OriginalActor>>,
+}
+
+/// The actors that changed between a call to [`StateTree::begin_tracking_changes`] and the
+/// following call to [`StateTree::take_changes`]. Changes that were reverted, or that left an actor
+/// exactly as it was, are not included.
+///
+/// Each list is sorted, and no actor appears in more than one list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActorChanges {
+    /// Actors whose state (code, state root, nonce, balance, or delegated address) changed.
+    pub modified: Vec<ActorID>,
+    /// Actors that were created.
+    pub created: Vec<ActorID>,
+    /// Actors that were deleted.
+    pub deleted: Vec<ActorID>,
+}
+
+/// The state of an actor before it was first written while tracking changes.
+enum OriginalActor {
+    /// The actor wasn't cached, so its original state is whatever is in the HAMT.
+    Stored,
+    /// The cached actor state, or None if the actor didn't exist.
+    Cached(Option<ActorState>),
 }
 
 /// An entry in the actor cache.
@@ -90,6 +117,7 @@ where
             actor_cache: Default::default(),
             resolve_cache: Default::default(),
             layers: Vec::new(),
+            tracked_changes: None,
         })
     }
 
@@ -139,6 +167,7 @@ where
                     actor_cache: Default::default(),
                     resolve_cache: Default::default(),
                     layers: Vec::new(),
+                    tracked_changes: None,
                 })
             }
         }
@@ -165,15 +194,9 @@ where
             .borrow_mut()
             .get_or_try_insert_with(id, || {
                 // It's not cached/dirty, so we look it up and cache it.
-                let key = Address::new_id(id).to_bytes();
                 Ok(ActorCacheEntry {
                     dirty: false,
-                    actor: self
-                        .hamt
-                        .get(&key)
-                        .with_context(|| format!("failed to lookup actor {}", id))
-                        .or_fatal()?
-                        .cloned(),
+                    actor: self.get_stored_actor(id)?,
                 })
             })
             .map(|ActorCacheEntry { actor, .. }| actor.clone())
@@ -181,6 +204,7 @@ where
 
     /// Set actor state with an actor ID.
     pub fn set_actor(&mut self, id: ActorID, actor: ActorState) {
+        self.record_original(id);
         self.actor_cache.borrow_mut().insert(
             id,
             ActorCacheEntry {
@@ -215,6 +239,7 @@ where
     /// Delete actor identified by the supplied ID.
     pub fn delete_actor(&mut self, id: ActorID) {
         // Record that we've deleted the actor.
+        self.record_original(id);
         self.actor_cache.borrow_mut().insert(
             id,
             ActorCacheEntry {
@@ -258,7 +283,10 @@ where
         Ok(true)
     }
 
-    /// Register a new address through the init actor.
+    /// Register a new address through the init actor, allocating the next sequential ID.
+    ///
+    /// This is the only way new IDs are allocated, so IDs are assigned in the order in which
+    /// addresses are registered during execution.
     pub fn register_new_address(&mut self, addr: &Address) -> Result<ActorID> {
         let (mut state, mut actor) = InitActorState::load(self)?;
 
@@ -312,6 +340,57 @@ where
         !self.layers.is_empty()
     }
 
+    /// Start tracking which actors change, discarding any changes tracked so far. The changes can
+    /// be retrieved with [`StateTree::take_changes`].
+    pub fn begin_tracking_changes(&mut self) {
+        self.tracked_changes = Some(BTreeMap::new());
+    }
+
+    /// Stop tracking changes, returning the actors that changed since the last call to
+    /// [`StateTree::begin_tracking_changes`] (or None if changes aren't being tracked).
+    pub fn take_changes(&mut self) -> Result<Option<ActorChanges>> {
+        let Some(originals) = self.tracked_changes.take() else {
+            return Ok(None);
+        };
+        let mut changes = ActorChanges::default();
+        for (id, original) in originals {
+            let before = match original {
+                OriginalActor::Cached(actor) => actor,
+                OriginalActor::Stored => self.get_stored_actor(id)?,
+            };
+            match (before, self.get_actor(id)?) {
+                (None, Some(_)) => changes.created.push(id),
+                (Some(_), None) => changes.deleted.push(id),
+                (Some(before), Some(after)) if before != after => changes.modified.push(id),
+                _ => {}
+            }
+        }
+        Ok(Some(changes))
+    }
+
+    /// Records the state of the actor before it's first written, if we're tracking changes.
+    fn record_original(&mut self, id: ActorID) {
+        if let Some(originals) = &mut self.tracked_changes {
+            originals
+                .entry(id)
+                .or_insert_with(|| match self.actor_cache.get_mut().get(&id) {
+                    Some(entry) => OriginalActor::Cached(entry.actor.clone()),
+                    None => OriginalActor::Stored,
+                });
+        }
+    }
+
+    /// Looks up an actor in the HAMT, bypassing the cache.
+    fn get_stored_actor(&self, id: ActorID) -> Result<Option<ActorState>> {
+        let key = Address::new_id(id).to_bytes();
+        Ok(self
+            .hamt
+            .get(&key)
+            .with_context(|| format!("failed to lookup actor {}", id))
+            .or_fatal()?
+            .cloned())
+    }
+
     /// Flush state tree and return Cid root.
     pub fn flush(&mut self) -> Result<Cid> {
         if self.in_transaction() {
@@ -319,6 +398,16 @@ where
                 "cannot flush while inside of a transaction",
             )));
         }
+        // We're about to update the HAMT, so we need to look up any tracked original states we'd
+        // otherwise read from it later.
+        if let Some(mut originals) = self.tracked_changes.take() {
+            for (&id, original) in originals.iter_mut() {
+                if let OriginalActor::Stored = original {
+                    *original = OriginalActor::Cached(self.get_stored_actor(id)?);
+                }
+            }
+            self.tracked_changes = Some(originals);
+        }
         for (&id, entry) in self.actor_cache.get_mut().iter_mut() {
             if !entry.dirty {
                 continue;
@@ -388,3 +477,345 @@ where
         Ok(())
     }
 }
+
+/// A read-only view of the actors in a flushed state tree, for node APIs (listing actors, looking
+/// up actors by address, etc.).
+///
+/// Unlike a [`StateTree`], a view has no actor or address caches and can't be written to. It reads
+/// the state as of the given root only, so it never observes (or affects) the unflushed state of a
+/// machine executing messages on top of that root.
+pub struct StateView<S> {
+    hamt: Hamt<S, ActorState>,
+}
+
+impl<S> StateView<S>
+where
+    S: Blockstore,
+{
+    /// Loads the state tree with the given root.
+    pub fn load(store: S, root: &Cid) -> Result<Self> {
+        let tree = StateTree::new_from_root(store, root)?;
+        Ok(Self { hamt: tree.hamt })
+    }
+
+    pub fn store(&self) -> &S {
+        self.hamt.store()
+    }
+
+    pub fn into_store(self) -> S {
+        self.hamt.into_store()
+    }
+
+    /// Resolves an address to an actor ID through the init actor. ID addresses are returned as-is,
+    /// whether or not the actor exists.
+    pub fn lookup_id(&self, addr: &Address) -> Result<Option<ActorID>> {
+        if let &Payload::ID(id) = addr.payload() {
+            return Ok(Some(id));
+        }
+        let init_actor = self
+            .get_actor_id(crate::init_actor::INIT_ACTOR_ID)?
+            .context("init actor not found")
+            .or_fatal()?;
+        let state: InitActorState = self
+            .store()
+            .get_cbor(&init_actor.state)
+            .or_fatal()?
+            .context("init actor state not found")
+            .or_fatal()?;
+        state.resolve_address(self.store(), addr)
+    }
+
+    /// Returns the state of the actor with the given address, resolving it first if it isn't an
+    /// ID address.
+    pub fn get_actor(&self, addr: &Address) -> Result<Option<ActorState>> {
+        match self.lookup_id(addr)? {
+            Some(id) => self.get_actor_id(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the state of the actor with the given ID.
+    pub fn get_actor_id(&self, id: ActorID) -> Result<Option<ActorState>> {
+        Ok(self
+            .hamt
+            .get(&Address::new_id(id).to_bytes())
+            .with_context(|| format!("failed to lookup actor {}", id))
+            .or_fatal()?
+            .cloned())
+    }
+
+    /// Calls `f` with each actor, streaming them from the state tree without loading it all into
+    /// memory. Iteration stops at the first error.
+    ///
+    /// Actors are visited in the order in which they're stored in the HAMT (i.e., ordered by the
+    /// hashes of their ID addresses, not by ID). This order only depends on the state root, so it's
+    /// the same on every node and for every traversal.
+    pub fn for_each_actor<F>(&self, mut f: F) -> anyhow::Result<()>
+    where
+        F: FnMut(ActorID, &ActorState) -> anyhow::Result<()>,
+    {
+        self.hamt.for_each_cacheless(|k, v| {
+            let addr = Address::from_bytes(&k.0)?;
+            let id = addr
+                .id()
+                .with_context(|| format!("non-ID address {} in the state tree", addr))?;
+            f(id, v)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use cid::Cid;
+    use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+    use fvm_shared::econ::TokenAmount;
+    use quickcheck::{Arbitrary, Gen};
+    use quickcheck_macros::quickcheck;
+
+    use super::*;
+
+    /// The number of distinct actor IDs touched by the randomized workloads. This is kept small so
+    /// that operations frequently hit the same actors.
+    const NUM_ACTORS: u64 = 8;
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        /// Sets the actor's balance (creating it if necessary).
+        Set(ActorID, u64),
+        Delete(ActorID),
+        Begin,
+        End {
+            revert: bool,
+        },
+        Flush,
+    }
+
+    impl Arbitrary for Op {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let id = u64::arbitrary(g) % NUM_ACTORS;
+            match u8::arbitrary(g) % 6 {
+                // Use a few distinct balances so that some writes don't change anything.
+                0 | 1 => Op::Set(id, u64::arbitrary(g) % 3),
+                2 => Op::Delete(id),
+                3 => Op::Begin,
+                4 => Op::End {
+                    revert: bool::arbitrary(g),
+                },
+                _ => Op::Flush,
+            }
+        }
+    }
+
+    fn actor(balance: u64) -> ActorState {
+        let mut actor = ActorState::new_empty(Cid::default(), None);
+        actor.balance = TokenAmount::from_atto(balance);
+        actor
+    }
+
+    fn load_actors(bs: &impl Blockstore, root: &Cid) -> BTreeMap<ActorID, ActorState> {
+        let mut actors = BTreeMap::new();
+        StateTree::new_from_root(bs, root)
+            .unwrap()
+            .for_each(|addr, actor| {
+                actors.insert(addr.id().unwrap(), actor.clone());
+                Ok(())
+            })
+            .unwrap();
+        actors
+    }
+
+    /// Computes the changes between two state roots.
+    fn diff_roots(bs: &impl Blockstore, before: &Cid, after: &Cid) -> ActorChanges {
+        let before = load_actors(bs, before);
+        let after = load_actors(bs, after);
+        let mut changes = ActorChanges::default();
+        for id in 0..NUM_ACTORS {
+            match (before.get(&id), after.get(&id)) {
+                (None, Some(_)) => changes.created.push(id),
+                (Some(_), None) => changes.deleted.push(id),
+                (Some(before), Some(after)) if before != after => changes.modified.push(id),
+                _ => {}
+            }
+        }
+        changes
+    }
+
+    #[quickcheck]
+    fn tracked_changes_match_state_diff(ops: Vec<Op>) -> bool {
+        let mut st = StateTree::new(MemoryBlockstore::default(), StateTreeVersion::V5).unwrap();
+        for id in 0..NUM_ACTORS / 2 {
+            st.set_actor(id, actor(0));
+        }
+        let before = st.flush().unwrap();
+
+        st.begin_tracking_changes();
+        let mut depth = 0;
+        for op in ops {
+            match op {
+                Op::Set(id, balance) => st.set_actor(id, actor(balance)),
+                Op::Delete(id) => st.delete_actor(id),
+                Op::Begin => {
+                    st.begin_transaction();
+                    depth += 1;
+                }
+                Op::End { revert } if depth > 0 => {
+                    st.end_transaction(revert).unwrap();
+                    depth -= 1;
+                }
+                Op::Flush if depth == 0 => {
+                    st.flush().unwrap();
+                }
+                _ => {}
+            }
+        }
+        for _ in 0..depth {
+            st.end_transaction(true).unwrap();
+        }
+        let changes = st.take_changes().unwrap().unwrap();
+        let after = st.flush().unwrap();
+
+        changes == diff_roots(st.store(), &before, &after)
+    }
+
+    #[test]
+    fn reverted_changes() {
+        let mut st = StateTree::new(MemoryBlockstore::default(), StateTreeVersion::V5).unwrap();
+        st.set_actor(1, actor(0));
+        st.set_actor(2, actor(0));
+        st.flush().unwrap();
+
+        // Not tracking by default.
+        st.set_actor(1, actor(1));
+        assert_eq!(st.take_changes().unwrap(), None);
+
+        st.begin_tracking_changes();
+        st.set_actor(1, actor(2));
+        st.set_actor(2, actor(0)); // no-op
+        st.set_actor(3, actor(0));
+        st.begin_transaction();
+        st.delete_actor(2);
+        st.set_actor(4, actor(0));
+        st.end_transaction(true).unwrap();
+
+        assert_eq!(
+            st.take_changes().unwrap(),
+            Some(ActorChanges {
+                modified: vec![1],
+                created: vec![3],
+                deleted: vec![],
+            })
+        );
+        assert_eq!(st.take_changes().unwrap(), None);
+    }
+
+    /// The number of actors in the trees used to test [`StateView`].
+    const VIEW_ACTORS: u64 = 3000;
+
+    /// Builds a state tree with an init actor and [`VIEW_ACTORS`] actors, each registered under
+    /// an actor address, in the given order. Returns the tree and the registered addresses by ID.
+    fn view_tree(
+        order: impl Iterator<Item = u64>,
+    ) -> (StateTree<MemoryBlockstore>, BTreeMap<ActorID, Address>) {
+        let mut st = StateTree::new(MemoryBlockstore::default(), StateTreeVersion::V5).unwrap();
+        let init_state = st
+            .store()
+            .put_cbor(
+                &InitActorState::new_test(st.store()),
+                multihash_codetable::Code::Blake2b256,
+            )
+            .unwrap();
+        let mut init_actor = ActorState::new_empty(Cid::default(), None);
+        init_actor.state = init_state;
+        st.set_actor(crate::init_actor::INIT_ACTOR_ID, init_actor);
+        let mut addrs = BTreeMap::new();
+        for i in order {
+            let addr = Address::new_actor(&i.to_be_bytes());
+            let id = st.register_new_address(&addr).unwrap();
+            st.set_actor(id, actor(i));
+            addrs.insert(id, addr);
+        }
+        (st, addrs)
+    }
+
+    fn actor_ids(view: &StateView<impl Blockstore>) -> Vec<ActorID> {
+        let mut ids = Vec::new();
+        view.for_each_actor(|id, _| {
+            ids.push(id);
+            Ok(())
+        })
+        .unwrap();
+        ids
+    }
+
+    #[test]
+    fn view_lists_actors() {
+        let (mut st, addrs) = view_tree(0..VIEW_ACTORS);
+        let root = st.flush().unwrap();
+        let view = StateView::load(st.store(), &root).unwrap();
+
+        let ids = actor_ids(&view);
+        let mut sorted = ids.clone();
+        sorted.sort();
+        let mut expected: Vec<_> = addrs.keys().copied().collect();
+        expected.insert(0, crate::init_actor::INIT_ACTOR_ID);
+        assert_eq!(sorted, expected);
+
+        // The order only depends on the state, not on how it was built or on the traversal.
+        assert_eq!(actor_ids(&view), ids);
+        let (mut reversed, _) = view_tree((0..VIEW_ACTORS).rev());
+        let reversed_root = reversed.flush().unwrap();
+        let reversed = StateView::load(reversed.store(), &reversed_root).unwrap();
+        assert_eq!(actor_ids(&reversed), ids);
+
+        // Errors stop the iteration.
+        let mut visited = 0;
+        assert!(
+            view.for_each_actor(|_, _| {
+                visited += 1;
+                anyhow::bail!("stop")
+            })
+            .is_err()
+        );
+        assert_eq!(visited, 1);
+    }
+
+    #[test]
+    fn view_gets_actors_by_address() {
+        let (mut st, addrs) = view_tree(0..VIEW_ACTORS);
+        let root = st.flush().unwrap();
+        let view = StateView::load(st.store(), &root).unwrap();
+
+        for (&id, addr) in &addrs {
+            assert_eq!(view.lookup_id(addr).unwrap(), Some(id));
+            let expected = st.get_actor(id).unwrap();
+            assert!(expected.is_some());
+            assert_eq!(view.get_actor(addr).unwrap(), expected);
+            assert_eq!(view.get_actor(&Address::new_id(id)).unwrap(), expected);
+        }
+
+        let unknown = Address::new_actor(b"unknown");
+        assert_eq!(view.lookup_id(&unknown).unwrap(), None);
+        assert_eq!(view.get_actor(&unknown).unwrap(), None);
+        let missing = Address::new_id(u64::MAX);
+        assert_eq!(view.lookup_id(&missing).unwrap(), Some(u64::MAX));
+        assert_eq!(view.get_actor(&missing).unwrap(), None);
+    }
+
+    #[test]
+    fn view_ignores_unflushed_state() {
+        let (mut st, addrs) = view_tree(0..2);
+        let root = st.flush().unwrap();
+        let (&id, _) = addrs.first_key_value().unwrap();
+        st.set_actor(id, actor(100));
+        let new_addr = Address::new_actor(b"new");
+        st.register_new_address(&new_addr).unwrap();
+
+        let view = StateView::load(st.store(), &root).unwrap();
+        assert_eq!(view.get_actor_id(id).unwrap(), Some(actor(0)));
+        assert_eq!(view.lookup_id(&new_addr).unwrap(), None);
+        assert_eq!(actor_ids(&view).len(), 3);
+    }
+}
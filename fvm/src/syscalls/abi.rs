@@ -0,0 +1,121 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::ops::RangeInclusive;
+
+use fvm_shared::sys::{DEFAULT_SYSCALL_ABI_VERSION, SYSCALL_ABI_SECTION};
+
+/// The syscall ABI versions actors may target.
+///
+/// - Version 1: the original ABI.
+/// - Version 2: `crypto::hash` fails with `BufferTooSmall` instead of truncating the digest, and
+///   `self::set_root`, `actor::create_actor`, and `actor::upgrade_actor` take the CID's length
+///   after its offset, rejecting CIDs that aren't allowed as state roots or code.
+pub const SUPPORTED_SYSCALL_ABI_VERSIONS: RangeInclusive<u32> = 1..=2;
+
+/// Returns the syscall ABI version declared by the passed (valid) Wasm module in its
+/// [`SYSCALL_ABI_SECTION`] custom section, or [`DEFAULT_SYSCALL_ABI_VERSION`] if it doesn't
+/// declare one.
+///
+/// This must be called on the original module: instrumentation doesn't preserve custom sections.
+pub(crate) fn declared_abi_version(wasm: &[u8]) -> Result<u32, &'static str> {
+    // Skip the magic number and the Wasm version.
+    let mut rest = wasm.get(8..).ok_or("truncated module")?;
+    let mut declared = None;
+    while let Some((&id, tail)) = rest.split_first() {
+        let (size, tail) = read_u32(tail).ok_or("malformed section")?;
+        let (section, tail) = tail
+            .split_at_checked(size as usize)
+            .ok_or("truncated section")?;
+        rest = tail;
+
+        // Only custom sections (id 0) may declare the ABI version.
+        if id != 0 {
+            continue;
+        }
+        let (name_len, section) = read_u32(section).ok_or("malformed custom section")?;
+        let (name, payload) = section
+            .split_at_checked(name_len as usize)
+            .ok_or("malformed custom section")?;
+        if name != SYSCALL_ABI_SECTION.as_bytes() {
+            continue;
+        }
+        if declared.is_some() {
+            return Err("syscall ABI version declared more than once");
+        }
+        match read_u32(payload) {
+            Some((version, [])) => declared = Some(version),
+            _ => return Err("malformed syscall ABI version"),
+        }
+    }
+    Ok(declared.unwrap_or(DEFAULT_SYSCALL_ABI_VERSION))
+}
+
+/// Reads an unsigned LEB128-encoded `u32`, returning it and the remaining bytes.
+fn read_u32(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let mut value = 0u64;
+    for (i, &b) in bytes.iter().enumerate().take(5) {
+        value |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((u32::try_from(value).ok()?, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn custom_section(name: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut section = vec![0, (1 + name.len() + payload.len()) as u8, name.len() as u8];
+        section.extend_from_slice(name);
+        section.extend_from_slice(payload);
+        section
+    }
+
+    fn module(sections: &[Vec<u8>]) -> Vec<u8> {
+        let mut wasm = HEADER.to_vec();
+        sections.iter().for_each(|s| wasm.extend_from_slice(s));
+        wasm
+    }
+
+    #[test]
+    fn declared_versions() {
+        let abi = |payload: &[u8]| custom_section(SYSCALL_ABI_SECTION.as_bytes(), payload);
+        // A type section with no types, then an unrelated custom section.
+        let other = [vec![1, 1, 0], custom_section(b"name", &[0, 0])];
+
+        assert_eq!(declared_abi_version(&module(&other)), Ok(1));
+        assert_eq!(
+            declared_abi_version(&module(&[other[0].clone(), abi(&[2]), other[1].clone()])),
+            Ok(2)
+        );
+        assert_eq!(
+            declared_abi_version(&module(&[abi(&[0x80, 0x01])])),
+            Ok(128)
+        );
+        assert_eq!(
+            declared_abi_version(&module(&[abi(&[0xff, 0xff, 0xff, 0xff, 0x0f])])),
+            Ok(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn malformed_declarations() {
+        let abi = |payload: &[u8]| custom_section(SYSCALL_ABI_SECTION.as_bytes(), payload);
+        for sections in [
+            vec![abi(&[])],
+            vec![abi(&[2, 0])],
+            vec![abi(&[0x80])],
+            vec![abi(&[0xff, 0xff, 0xff, 0xff, 0x1f])],
+            vec![abi(&[2]), abi(&[2])],
+        ] {
+            assert!(
+                declared_abi_version(&module(&sections)).is_err(),
+                "{sections:?}"
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use anyhow::{Context as _, anyhow};
+use cid::Cid;
 use fvm_shared::{ActorID, sys};
 
 use super::Context;
@@ -55,6 +56,19 @@ pub fn get_actor_code_cid(
     context.memory.write_cid(&typ, obuf_off, obuf_len)
 }
 
+pub fn get_actor_state_root(
+    context: Context<'_, impl ActorOps>,
+    actor_id: u64,
+    obuf_off: u32, // Cid
+    obuf_len: u32,
+) -> Result<u32> {
+    context.memory.check_bounds(obuf_off, obuf_len)?;
+
+    let root = context.kernel.get_actor_state_root(actor_id)?;
+
+    context.memory.write_cid(&root, obuf_off, obuf_len)
+}
+
 /// Generates a new actor address, and writes it into the supplied output buffer.
 ///
 /// The output buffer must be at least 21 bytes long, which is the length of a class 2 address
@@ -101,6 +115,42 @@ pub fn create_actor(
     delegated_addr_len: u32,
 ) -> Result<()> {
     let typ = context.memory.read_cid(typ_off)?;
+    create_actor_inner(
+        context,
+        actor_id,
+        typ,
+        delegated_addr_off,
+        delegated_addr_len,
+    )
+}
+
+/// Like [`create_actor`], but takes the code CID's length and only accepts allowed CIDs (see
+/// [`Memory::read_checked_cid`](super::Memory::read_checked_cid)) (syscall ABI version 2).
+pub fn create_actor_v2(
+    context: Context<'_, impl ActorOps>,
+    actor_id: u64, // ID
+    typ_off: u32,  // Cid
+    typ_len: u32,
+    delegated_addr_off: u32,
+    delegated_addr_len: u32,
+) -> Result<()> {
+    let typ = context.memory.read_checked_cid(typ_off, typ_len)?;
+    create_actor_inner(
+        context,
+        actor_id,
+        typ,
+        delegated_addr_off,
+        delegated_addr_len,
+    )
+}
+
+fn create_actor_inner(
+    context: Context<'_, impl ActorOps>,
+    actor_id: u64,
+    typ: Cid,
+    delegated_addr_off: u32,
+    delegated_addr_len: u32,
+) -> Result<()> {
     let addr = (delegated_addr_len > 0)
         .then(|| {
             context
@@ -117,11 +167,34 @@ pub fn upgrade_actor(
     new_code_cid_off: u32,
     params_id: u32,
 ) -> ControlFlow<sys::out::send::Send> {
-    let cid = match context.memory.read_cid(new_code_cid_off) {
-        Ok(cid) => cid,
-        Err(err) => return err.into(),
-    };
+    match context.memory.read_cid(new_code_cid_off) {
+        Ok(cid) => upgrade_actor_inner(context, cid, params_id),
+        Err(err) => err.into(),
+    }
+}
 
+/// Like [`upgrade_actor`], but takes the code CID's length and only accepts allowed CIDs (see
+/// [`Memory::read_checked_cid`](super::Memory::read_checked_cid)) (syscall ABI version 2).
+pub fn upgrade_actor_v2(
+    context: Context<'_, impl UpgradeOps + Kernel>,
+    new_code_cid_off: u32,
+    new_code_cid_len: u32,
+    params_id: u32,
+) -> ControlFlow<sys::out::send::Send> {
+    match context
+        .memory
+        .read_checked_cid(new_code_cid_off, new_code_cid_len)
+    {
+        Ok(cid) => upgrade_actor_inner(context, cid, params_id),
+        Err(err) => err.into(),
+    }
+}
+
+fn upgrade_actor_inner(
+    context: Context<'_, impl UpgradeOps + Kernel>,
+    cid: Cid,
+    params_id: u32,
+) -> ControlFlow<sys::out::send::Send> {
     match context.kernel.upgrade_actor(cid, params_id) {
         Ok(CallResult {
             block_id,
@@ -3,10 +3,11 @@
 use std::io::Cursor;
 use std::ops::{Deref, DerefMut};
 
-use cid::Cid;
-use fvm_shared::MAX_CID_LEN;
-use fvm_shared::address::Address;
+use cid::{Cid, Version};
+use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW};
+use fvm_shared::address::{Address, MAX_ADDRESS_LEN};
 use fvm_shared::error::ErrorNumber;
+use fvm_shared::{IDENTITY_HASH, MAX_CID_LEN};
 
 use crate::kernel::{ClassifyResult, Context as _, Result};
 use crate::syscall_error;
@@ -14,6 +15,8 @@ use crate::syscall_error;
 #[cfg(doc)]
 use crate::Kernel;
 
+const BLAKE2B_256: u64 = 0xb220;
+
 /// The syscall context. Allows syscalls to access the [`Kernel`] and the actor's memory.
 pub struct Context<'a, K> {
     /// The running actor's [`Kernel`].
@@ -25,6 +28,10 @@ pub struct Context<'a, K> {
 /// Represents a Wasm memory. All methods are inexpensive and time-bounded, regardless of the
 /// inputs. It's usually not necessary to explicitly account for the gas costs of calling these
 /// methods a small (< 5) constant number of times while handling a syscall.
+///
+/// Slicing memory doesn't copy it. Syscalls that copy data between actor memory and the host
+/// must charge for the copy as part of the operation's price (e.g., `OnBlockRead` charges per
+/// byte copied into the actor's buffer).
 #[repr(transparent)]
 pub struct Memory([u8]);
 
@@ -132,6 +139,45 @@ impl Memory {
         .context("failed to parse cid")
     }
 
+    /// Read a state root or code CID from actor memory. CIDs are passed as an (offset, length) pair,
+    /// and must be exactly `len` bytes long.
+    ///
+    /// If the CID is longer than [`MAX_CID_LEN`], truncated, followed by trailing bytes, or out of
+    /// bounds, this method returns an [`ErrorNumber::IllegalArgument`] error. If it's well-formed
+    /// but isn't a CIDv1 with the DAG-CBOR or raw codec and either a 32-byte blake2b-256 or an
+    /// identity multihash, it returns an [`ErrorNumber::IllegalCid`] error.
+    pub fn read_checked_cid(&self, offset: u32, len: u32) -> Result<Cid> {
+        if len as usize > MAX_CID_LEN {
+            return Err(syscall_error!(IllegalArgument; "cid length {len} exceeds the maximum of {MAX_CID_LEN} bytes").into());
+        }
+        let mut bytes = self.try_slice(offset, len)?;
+        let k = Cid::read_bytes(&mut bytes)
+            .or_error(ErrorNumber::IllegalArgument)
+            .context("failed to parse cid")?;
+        if !bytes.is_empty() {
+            return Err(
+                syscall_error!(IllegalArgument; "cid has {} trailing bytes", bytes.len()).into(),
+            );
+        }
+
+        if k.version() != Version::V1 {
+            return Err(syscall_error!(IllegalCid; "cid {k} is not a CIDv1").into());
+        }
+        if !matches!(k.codec(), DAG_CBOR | IPLD_RAW) {
+            return Err(
+                syscall_error!(IllegalCid; "cid {k} has forbidden codec {:#x}", k.codec()).into(),
+            );
+        }
+        // Identity digests are bounded by the CID length limit.
+        match (k.hash().code(), k.hash().size()) {
+            (BLAKE2B_256, 32) | (IDENTITY_HASH, _) => Ok(k),
+            (code, size) => Err(syscall_error!(
+                IllegalCid; "cid {k} has forbidden multihash (code: {code:#x}, len: {size})"
+            )
+            .into()),
+        }
+    }
+
     /// Write a CID to actor memory at the given offset.
     ///
     /// If the CID's length exceeds the specified length, this method returns an
@@ -152,17 +198,29 @@ impl Memory {
         Ok(len as u32)
     }
 
-    /// Read a Filecoin address from actor memory.
+    /// Read a Filecoin address from actor memory. Addresses are passed as a (offset, length) pair
+    /// so variable-length addresses (e.g., f4 addresses with long sub-addresses) are never
+    /// truncated.
     ///
-    /// On failure, this method returns an [`ErrorNumber::IllegalArgument`] error.
+    /// On failure (including addresses longer than [`MAX_ADDRESS_LEN`] and f4 addresses with
+    /// invalid sub-addresses), this method returns an [`ErrorNumber::IllegalArgument`] error.
     pub fn read_address(&self, offset: u32, len: u32) -> Result<Address> {
+        if len as usize > MAX_ADDRESS_LEN {
+            return Err(syscall_error!(IllegalArgument; "address length {len} exceeds the maximum of {MAX_ADDRESS_LEN} bytes").into());
+        }
         let bytes = self.try_slice(offset, len)?;
-        Address::from_bytes(bytes).or_error(ErrorNumber::IllegalArgument)
+        Address::from_bytes(bytes)
+            .or_error(ErrorNumber::IllegalArgument)
+            .context("failed to parse address")
     }
 }
 
 #[cfg(test)]
 mod test {
+    use cid::multihash::Multihash;
+    use fvm_shared::address::{DelegatedAddress, MAX_SUBADDRESS_LEN};
+    use quickcheck_macros::quickcheck;
+
     use super::*;
 
     const RAW: u64 = 0x55;
@@ -222,6 +280,106 @@ mod test {
         expect_syscall_err!(IllegalArgument, mem.read_cid(200));
     }
 
+    /// Writes the CID to memory, then reads it back with `read_checked_cid`.
+    fn read_checked_cid(k: &[u8]) -> Result<Cid> {
+        let mut bytes = k.to_vec();
+        Memory::new(&mut bytes).read_checked_cid(0, k.len() as u32)
+    }
+
+    #[test]
+    fn test_read_checked_cid() {
+        let blake2b = Cid::new_v1(DAG_CBOR, Multihash::wrap(BLAKE2B_256, &[1; 32]).unwrap());
+        assert_eq!(read_checked_cid(&blake2b.to_bytes()).unwrap(), blake2b);
+
+        // The largest identity CID.
+        let identity = Cid::new_v1(RAW, Multihash::wrap(IDENTITY_HASH, &[1; 64]).unwrap());
+        assert_eq!(read_checked_cid(&identity.to_bytes()).unwrap(), identity);
+    }
+
+    #[test]
+    fn test_read_checked_cid_length() {
+        let k = Cid::new_v1(RAW, Multihash::wrap(BLAKE2B_256, &[1; 32]).unwrap()).to_bytes();
+        expect_syscall_err!(IllegalArgument, read_checked_cid(&k[..k.len() - 1]));
+        expect_syscall_err!(IllegalArgument, read_checked_cid(&[&k[..], &[0]].concat()));
+        // The CID must fit in the specified length, even if there's memory after it.
+        let mut bytes = [&k[..], &[0]].concat();
+        let mem = Memory::new(&mut bytes);
+        expect_syscall_err!(IllegalArgument, mem.read_checked_cid(0, k.len() as u32 - 1));
+        expect_syscall_err!(IllegalArgument, mem.read_checked_cid(0, 200));
+    }
+
+    #[test]
+    fn test_read_checked_cid_forbidden() {
+        // A CIDv0 (i.e., a bare sha2-256 multihash).
+        let v0 = Cid::new_v0(Multihash::wrap(SHA2_256, HASH).unwrap()).unwrap();
+        expect_syscall_err!(IllegalCid, read_checked_cid(&v0.to_bytes()));
+
+        // A sha1 multihash.
+        let sha1 = Cid::new_v1(RAW, Multihash::wrap(0x11, &HASH[..20]).unwrap());
+        expect_syscall_err!(IllegalCid, read_checked_cid(&sha1.to_bytes()));
+
+        // A truncated blake2b-256 multihash.
+        let truncated = Cid::new_v1(RAW, Multihash::wrap(BLAKE2B_256, &[1; 20]).unwrap());
+        expect_syscall_err!(IllegalCid, read_checked_cid(&truncated.to_bytes()));
+
+        // Another codec.
+        let cbor = Cid::new_v1(0x51, Multihash::wrap(BLAKE2B_256, &[1; 32]).unwrap());
+        expect_syscall_err!(IllegalCid, read_checked_cid(&cbor.to_bytes()));
+
+        // An identity CID over 200 bytes doesn't fit in a CID, let alone `MAX_CID_LEN`.
+        let mut identity = vec![0x01, RAW as u8, IDENTITY_HASH as u8, 0xc9, 0x01];
+        identity.extend([1; 201]);
+        expect_syscall_err!(IllegalArgument, read_checked_cid(&identity));
+    }
+
+    #[quickcheck]
+    fn prop_read_delegated_address(addr: DelegatedAddress) -> bool {
+        let addr = Address::new_delegated(addr.namespace(), addr.subaddress()).unwrap();
+        let mut bytes = addr.to_bytes();
+        let len = bytes.len() as u32;
+        Memory::new(&mut bytes).read_address(0, len).unwrap() == addr
+    }
+
+    #[test]
+    fn test_read_address_max_len() {
+        let addr = Address::new_delegated(u64::MAX, &[0xff; MAX_SUBADDRESS_LEN]).unwrap();
+        let mut bytes = addr.to_bytes();
+        assert_eq!(bytes.len(), MAX_ADDRESS_LEN);
+        let mem = Memory::new(&mut bytes);
+        assert_eq!(mem.read_address(0, MAX_ADDRESS_LEN as u32).unwrap(), addr);
+    }
+
+    #[test]
+    fn test_read_address_too_long() {
+        // A sub-address that's one byte too long.
+        let mut bytes = Address::new_delegated(10, &[0xff; MAX_SUBADDRESS_LEN])
+            .unwrap()
+            .to_bytes();
+        bytes.push(0xff);
+        let len = bytes.len() as u32;
+        let mem = Memory::new(&mut bytes);
+        expect_syscall_err!(IllegalArgument, mem.read_address(0, len));
+
+        // An address longer than any valid address.
+        let mut bytes = [0u8; MAX_ADDRESS_LEN + 1];
+        let mem = Memory::new(&mut bytes);
+        expect_syscall_err!(
+            IllegalArgument,
+            mem.read_address(0, MAX_ADDRESS_LEN as u32 + 1)
+        );
+    }
+
+    #[test]
+    fn test_read_address_truncated() {
+        let mut bytes = Address::new_delegated(10, b"foobar").unwrap().to_bytes();
+        let len = bytes.len() as u32;
+        let mem = Memory::new(&mut bytes);
+        // Dropping the last byte is still a valid (shorter) address, but claiming more bytes than
+        // we have is out of bounds.
+        assert!(mem.read_address(0, len - 1).is_ok());
+        expect_syscall_err!(IllegalArgument, mem.read_address(0, len + 1));
+    }
+
     #[test]
     fn test_read_slice_out_of_bounds() {
         let mem = Memory::new(&mut []);
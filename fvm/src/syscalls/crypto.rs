@@ -46,6 +46,81 @@ pub fn verify_signature(
         .map(|v| if v { 0 } else { -1 })
 }
 
+#[cfg(feature = "verify-signature")]
+/// Verifies that a signature is valid for an address and the domain-separated plaintext
+/// `tag || plaintext`.
+///
+/// The return i32 indicates the status code of the verification:
+///  - 0: verification ok.
+///  - -1: verification failed.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_signature_tagged(
+    context: Context<'_, impl CryptoOps>,
+    sig_type: u32,
+    sig_off: u32,
+    sig_len: u32,
+    addr_off: u32,
+    addr_len: u32,
+    tag_off: u32,
+    tag_len: u32,
+    plaintext_off: u32,
+    plaintext_len: u32,
+) -> Result<i32> {
+    use anyhow::Context as _;
+    use fvm_shared::crypto::signature::SignatureType;
+    use num_traits::FromPrimitive;
+
+    let sig_type = SignatureType::from_u32(sig_type)
+        .with_context(|| format!("unknown signature type {}", sig_type))
+        .or_illegal_argument()?;
+    let sig_bytes = context.memory.try_slice(sig_off, sig_len)?;
+    let addr = context.memory.read_address(addr_off, addr_len)?;
+    let tag = context.memory.try_slice(tag_off, tag_len)?;
+    let plaintext = context.memory.try_slice(plaintext_off, plaintext_len)?;
+
+    context
+        .kernel
+        .verify_signature_tagged(sig_type, sig_bytes, &addr, tag, plaintext)
+        .map(|v| if v { 0 } else { -1 })
+}
+
+#[cfg(feature = "verify-signature")]
+/// Verifies that a secp256k1 signature is valid for an address and a 32-byte blake2b-256 message
+/// digest.
+///
+/// The return i32 indicates the status code of the verification:
+///  - 0: verification ok.
+///  - -1: verification failed.
+pub fn verify_signature_hashed(
+    context: Context<'_, impl CryptoOps>,
+    sig_type: u32,
+    sig_off: u32,
+    sig_len: u32,
+    addr_off: u32,
+    addr_len: u32,
+    digest_off: u32,
+) -> Result<i32> {
+    use anyhow::Context as _;
+    use fvm_shared::crypto::signature::SignatureType;
+    use num_traits::FromPrimitive;
+
+    let sig_type = SignatureType::from_u32(sig_type)
+        .with_context(|| format!("unknown signature type {}", sig_type))
+        .or_illegal_argument()?;
+    let sig_bytes = context.memory.try_slice(sig_off, sig_len)?;
+    let addr = context.memory.read_address(addr_off, addr_len)?;
+    let digest = context
+        .memory
+        .try_slice(digest_off, SECP_SIG_MESSAGE_HASH_SIZE as u32)?
+        .try_into()
+        .or_illegal_argument()?;
+
+    context
+        .kernel
+        .verify_signature_hashed(sig_type, sig_bytes, &addr, digest)
+        .map(|v| if v { 0 } else { -1 })
+}
+
 /// Verifies that a bls aggregate signature is valid for a list of public keys and plaintexts.
 ///
 /// The return i32 indicates the status code of the verification:
@@ -141,3 +216,35 @@ pub fn hash(
     digest_out[..length].copy_from_slice(&digest.digest()[..length]);
     Ok(length as u32)
 }
+
+/// Like [`hash`], but fails with `BufferTooSmall` instead of truncating the digest
+/// when it doesn't fit in the provided buffer (syscall ABI version 2).
+pub fn hash_v2(
+    context: Context<'_, impl CryptoOps>,
+    hash_code: u64,
+    data_off: u32, // input
+    data_len: u32,
+    digest_off: u32, // output
+    digest_len: u32,
+) -> Result<u32> {
+    context.memory.check_bounds(digest_off, digest_len)?;
+
+    let digest = {
+        let data = context.memory.try_slice(data_off, data_len)?;
+        context.kernel.hash(hash_code, data)?
+    };
+
+    let digest = digest.digest();
+    if digest.len() > digest_len as usize {
+        return Err(syscall_error!(
+            BufferTooSmall;
+            "digest buffer too small: {digest_len} < {}", digest.len()
+        )
+        .into());
+    }
+    context
+        .memory
+        .try_slice_mut(digest_off, digest.len() as u32)?
+        .copy_from_slice(digest);
+    Ok(digest.len() as u32)
+}
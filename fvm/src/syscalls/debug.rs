@@ -1,17 +1,23 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use crate::kernel::{ClassifyResult, DebugOps, Result};
+use crate::kernel::{ClassifyResult, DebugOps, ExecutionError, Result};
 use crate::syscalls::context::Context;
 
+// `log` and `store_artifact` never fail, so they behave (and cost) the same whether or not actor
+// debugging is enabled. Malformed arguments are reported on the node instead.
+
 pub fn log(context: Context<'_, impl DebugOps>, msg_off: u32, msg_len: u32) -> Result<()> {
     // No-op if disabled.
     if !context.kernel.debug_enabled() {
         return Ok(());
     }
 
-    let msg = context.memory.try_slice(msg_off, msg_len)?;
-    let msg = String::from_utf8(msg.to_owned()).or_illegal_argument()?;
-    context.kernel.log(msg);
+    match context.memory.try_slice(msg_off, msg_len) {
+        Ok(msg) => context
+            .kernel
+            .log(String::from_utf8_lossy(msg).into_owned()),
+        Err(e) => log::warn!("ignoring actor debug log: {}", e),
+    }
     Ok(())
 }
 
@@ -35,12 +41,19 @@ pub fn store_artifact(
         return Ok(());
     }
 
-    let data = context.memory.try_slice(data_off, data_len)?;
-    let name = context.memory.try_slice(name_off, name_len)?;
-    let name =
-        std::str::from_utf8(name).or_error(fvm_shared::error::ErrorNumber::IllegalArgument)?;
-
-    context.kernel.store_artifact(name, data)?;
-
-    Ok(())
+    let res = context
+        .memory
+        .try_slice(data_off, data_len)
+        .and_then(|data| {
+            let name = context.memory.try_slice(name_off, name_len)?;
+            let name = std::str::from_utf8(name).or_illegal_argument()?;
+            context.kernel.store_artifact(name, data)
+        });
+    match res {
+        Err(ExecutionError::Syscall(e)) => {
+            log::warn!("ignoring actor debug artifact: {}", e);
+            Ok(())
+        }
+        res => res,
+    }
 }
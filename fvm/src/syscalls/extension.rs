@@ -0,0 +1,153 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Syscall extensions: additional syscall modules registered by the embedder (see
+//! [`NetworkConfig::with_syscall_module`](crate::machine::NetworkConfig::with_syscall_module)).
+use std::any::Any;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+
+use super::{Linker, Syscall};
+use crate::Kernel;
+
+/// Binds the syscalls of an extension module. Passed to the binder registered with
+/// [`NetworkConfig::with_syscall_module`](crate::machine::NetworkConfig::with_syscall_module).
+pub struct SyscallBinder<'a, K> {
+    namespace: &'static str,
+    /// The linker to bind to, or `None` when we're only recording the module's syscall names.
+    linker: Option<&'a mut Linker<K>>,
+    names: Vec<&'static str>,
+    /// The first error encountered while linking, if any.
+    error: Option<anyhow::Error>,
+}
+
+impl<K: Kernel> SyscallBinder<'_, K> {
+    /// The namespace (wasm import module) the syscalls are bound in.
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+
+    /// Bind a syscall, which actors can then import as `name` from the extension's namespace.
+    /// Syscalls follow the same conventions as the built-in ones (see [`Syscall`]).
+    ///
+    /// Syscalls should constrain the kernel to the kernel operation traits they need (e.g.,
+    /// `Context<'_, impl CryptoOps>`) rather than a specific kernel implementation, so that they
+    /// only use the kernel's public operations.
+    pub fn bind<Args, Ret>(
+        &mut self,
+        name: &'static str,
+        syscall: impl Syscall<K, Args, Ret>,
+    ) -> &mut Self {
+        self.names.push(name);
+        if let Some(linker) = &mut self.linker
+            && self.error.is_none()
+            && let Err(e) = linker.link_syscall(self.namespace, name, syscall)
+        {
+            self.error = Some(e);
+        }
+        self
+    }
+}
+
+type Binder<K> = dyn Fn(&mut SyscallBinder<'_, K>) + Send + Sync;
+
+/// A syscall module registered by the embedder, for a specific kernel type.
+///
+/// Two extensions are equal if they're the same registration. Extensions hash by their namespace
+/// and syscall names.
+#[derive(Clone)]
+pub struct SyscallExtension {
+    namespace: &'static str,
+    names: Vec<&'static str>,
+    /// A `Box<Binder<K>>`.
+    binder: Arc<dyn Any + Send + Sync>,
+}
+
+impl SyscallExtension {
+    /// Create an extension binding syscalls in the given namespace with the given binder.
+    pub fn new<K: Kernel>(
+        namespace: &'static str,
+        binder: impl Fn(&mut SyscallBinder<'_, K>) + Send + Sync + 'static,
+    ) -> Self {
+        // Record the syscall names up-front so that they can be validated and fingerprinted.
+        let mut dry_run = SyscallBinder {
+            namespace,
+            linker: None,
+            names: Vec::new(),
+            error: None,
+        };
+        binder(&mut dry_run);
+        let binder: Box<Binder<K>> = Box::new(binder);
+        SyscallExtension {
+            namespace,
+            names: dry_run.names,
+            binder: Arc::new(binder),
+        }
+    }
+
+    /// The namespace (wasm import module) of the extension's syscalls.
+    pub fn namespace(&self) -> &'static str {
+        self.namespace
+    }
+
+    /// The names of the extension's syscalls, in the order they're bound.
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    /// Link the extension's syscalls. Fails if the extension was registered for another kernel.
+    pub(crate) fn link<K: Kernel>(&self, linker: &mut Linker<K>) -> anyhow::Result<()> {
+        let binder = self
+            .binder
+            .downcast_ref::<Box<Binder<K>>>()
+            .ok_or_else(|| {
+                anyhow!(
+                    "syscall extension {} was registered for a different kernel than {}",
+                    self.namespace,
+                    std::any::type_name::<K>()
+                )
+            })?;
+        let mut binder_ctx = SyscallBinder {
+            namespace: self.namespace,
+            linker: Some(linker),
+            names: Vec::new(),
+            error: None,
+        };
+        binder(&mut binder_ctx);
+        match binder_ctx.error {
+            Some(e) => Err(e.context(format!(
+                "failed to link syscall extension {}",
+                self.namespace
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for SyscallExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyscallExtension")
+            .field("namespace", &self.namespace)
+            .field("names", &self.names)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for SyscallExtension {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.binder, &other.binder)
+            && self.namespace == other.namespace
+            && self.names == other.names
+    }
+}
+
+impl Eq for SyscallExtension {}
+
+impl Hash for SyscallExtension {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.namespace.hash(state);
+        self.names.hash(state);
+    }
+}
@@ -4,7 +4,6 @@ use std::panic;
 
 use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_shared::error::ErrorNumber;
-use fvm_shared::sector::WindowPoStVerifyInfo;
 
 use super::Context;
 use super::context::Memory;
@@ -15,11 +14,13 @@ use anyhow::Context as _;
 use anyhow::anyhow;
 use fvm_ipld_encoding::from_slice;
 use fvm_shared::piece::PieceInfo;
-use fvm_shared::sector::{
-    AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
-};
+use fvm_shared::sector::{RegisteredSealProof, ReplicaUpdateInfo, StoragePower};
 use fvm_shared::sys;
 
+use self::lenient::{
+    AggregateSealVerifyProofAndInfosParams, SealVerifyInfoParams, WindowPoStVerifyInfoParams,
+};
+
 /// Private extension trait for reading CBOR. This operation is not safe to call on untrusted
 /// (user-controlled) memory.
 trait ReadCbor {
@@ -75,6 +76,69 @@ pub fn compute_unsealed_sector_cid(
     context.memory.write_cid(&cid, cid_off, cid_len)
 }
 
+/// Computes the unsealed sector CIDs (CommDs) of a batch of sectors, encoded as a CBOR array of
+/// `(proof_type, [PieceInfo])` tuples.
+///
+/// Writes one [`sys::out::crypto::ComputeUnsealedSectorCid`] per entry (in order) to the buffer at
+/// `results_off`. Entries that fail record their error number instead of failing the batch.
+pub fn compute_unsealed_sector_cids(
+    context: Context<'_, impl FilecoinKernel>,
+    batch_off: u32, // [(RegisteredSealProof, [PieceInfo])]
+    batch_len: u32,
+    results_off: u32,
+) -> Result<()> {
+    use sys::out::crypto::ComputeUnsealedSectorCid;
+    const RESULT_SIZE: usize = std::mem::size_of::<ComputeUnsealedSectorCid>();
+
+    // Check/read all arguments.
+    let batch = context
+        .memory
+        .read_cbor::<Vec<(RegisteredSealProof, Vec<PieceInfo>)>>(batch_off, batch_len)?;
+    let results_len = batch
+        .len()
+        .checked_mul(RESULT_SIZE)
+        .and_then(|len| u32::try_from(len).ok())
+        .ok_or_else(
+            || syscall_error!(IllegalArgument; "batch too large: {} entries", batch.len()),
+        )?;
+    let output = context.memory.try_slice_mut(results_off, results_len)?;
+
+    // Compute
+    let results = context.kernel.compute_unsealed_sector_cids(&batch)?;
+
+    // Sanity check that we got the correct number of results.
+    if results.len() != batch.len() {
+        return Err(anyhow!(
+            "expected one result per input: {} != {}",
+            batch.len(),
+            results.len()
+        ))
+        .or_fatal();
+    }
+
+    // Return. The output is an array of packed `ComputeUnsealedSectorCid` structs: a little-endian
+    // error number, a little-endian CID length, then the (zero-padded) CID itself.
+    for (out, res) in output.chunks_exact_mut(RESULT_SIZE).zip(results) {
+        out.fill(0);
+        match res {
+            Ok(cid) => {
+                let cid = cid.to_bytes();
+                if cid.len() > out.len() - 8 {
+                    return Err(anyhow!(
+                        "unsealed sector CID too large: {} bytes",
+                        cid.len()
+                    ))
+                    .or_fatal();
+                }
+                out[4..8].copy_from_slice(&(cid.len() as u32).to_le_bytes());
+                out[8..8 + cid.len()].copy_from_slice(&cid);
+            }
+            Err(code) => out[..4].copy_from_slice(&(code as u32).to_le_bytes()),
+        }
+    }
+    Ok(())
+}
+
 /// Verifies a window proof of spacetime.
 ///
 /// The return i32 indicates the status code of the verification:
@@ -85,13 +149,60 @@ pub fn verify_post(
     info_off: u32, // WindowPoStVerifyInfo,
     info_len: u32,
 ) -> Result<i32> {
-    let info = context
+    let (info, valid) = context
         .memory
-        .read_cbor::<WindowPoStVerifyInfo>(info_off, info_len)?;
-    context
-        .kernel
-        .verify_post(&info)
-        .map(|v| if v { 0 } else { -1 })
+        .read_cbor::<WindowPoStVerifyInfoParams>(info_off, info_len)?
+        .into_checked();
+    let ok = context.kernel.verify_post(&info)?;
+    if !valid {
+        return Err(lenient::invalid_randomness());
+    }
+    Ok(if ok { 0 } else { -1 })
+}
+
+/// Verifies a batch of window proofs of spacetime encoded as a CBOR array of
+/// `WindowPoStVerifyInfo`.
+///
+/// When successful, this method writes a packed bitfield of results into the buffer at
+/// `result_off` (`ceil(L / 8)` bytes for `L` proofs): bit `i % 8` of byte `i / 8` (least significant
+/// bit first) is set if the `i`th proof is valid. Invalid or malformed proofs don't fail the batch.
+pub fn verify_post_batch(
+    context: Context<'_, impl FilecoinKernel>,
+    batch_off: u32, // [WindowPoStVerifyInfo]
+    batch_len: u32,
+    result_off: u32,
+) -> Result<()> {
+    // Check and decode params.
+    let (batch, valid): (Vec<_>, Vec<_>) = context
+        .memory
+        .read_cbor::<Vec<WindowPoStVerifyInfoParams>>(batch_off, batch_len)?
+        .into_iter()
+        .map(WindowPoStVerifyInfoParams::into_checked)
+        .unzip();
+    let output = context
+        .memory
+        .try_slice_mut(result_off, batch.len().div_ceil(8) as u32)?;
+
+    // Execute.
+    let mut result = context.kernel.verify_post_batch(&batch)?;
+
+    // Sanity check that we got the correct number of results.
+    if result.len() != batch.len() {
+        return Err(anyhow!(
+            "expected one result per input: {} != {}",
+            batch.len(),
+            result.len()
+        ))
+        .or_fatal();
+    }
+
+    // Return.
+    lenient::fail_invalid(&mut result, &valid);
+    output.fill(0);
+    for (i, _) in result.iter().enumerate().filter(|(_, ok)| **ok) {
+        output[i / 8] |= 1 << (i % 8);
+    }
+    Ok(())
 }
 
 /// Verifies that two block headers provide proof of a consensus fault:
@@ -140,6 +251,56 @@ pub fn verify_consensus_fault(
     }
 }
 
+/// Verifies a consensus fault like [`verify_consensus_fault`] and, if the headers prove a fault,
+/// returns the at-fault miner's power at the fault epoch along with the fault.
+pub fn verify_consensus_fault_report(
+    context: Context<'_, impl FilecoinKernel>,
+    h1_off: u32,
+    h1_len: u32,
+    h2_off: u32,
+    h2_len: u32,
+    extra_off: u32,
+    extra_len: u32,
+) -> Result<sys::out::crypto::VerifyConsensusFaultReport> {
+    let h1 = context.memory.try_slice(h1_off, h1_len)?;
+    let h2 = context.memory.try_slice(h2_off, h2_len)?;
+    let extra = context.memory.try_slice(extra_off, extra_len)?;
+
+    let Some(report) = context
+        .kernel
+        .verify_consensus_fault_report(h1, h2, extra)?
+    else {
+        // No consensus fault.
+        return Ok(sys::out::crypto::VerifyConsensusFaultReport {
+            epoch: 0,
+            target: 0,
+            fault: 0,
+            raw_byte_power: [0; 2],
+            quality_adj_power: [0; 2],
+        });
+    };
+    Ok(sys::out::crypto::VerifyConsensusFaultReport {
+        fault: report.fault.fault_type as u32,
+        epoch: report.fault.epoch,
+        target: report
+            .fault
+            .target
+            .id()
+            .context("kernel returned non-id target address")
+            .or_fatal()?,
+        raw_byte_power: power_words(&report.power.raw_byte_power)?,
+        quality_adj_power: power_words(&report.power.quality_adj_power)?,
+    })
+}
+
+/// Splits a storage power into the low and high 64 bits of a u128.
+fn power_words(power: &StoragePower) -> Result<[u64; 2]> {
+    let power = u128::try_from(power)
+        .context("externs returned a negative power or one that doesn't fit in a u128")
+        .or_fatal()?;
+    Ok([power as u64, (power >> u64::BITS) as u64])
+}
+
 /// The return i32 indicates the status code of the verification:
 ///  - 0: verification ok.
 ///  - -1: verification failed.
@@ -148,13 +309,15 @@ pub fn verify_aggregate_seals(
     agg_off: u32, // AggregateSealVerifyProofAndInfos
     agg_len: u32,
 ) -> Result<i32> {
-    let info = context
+    let (info, valid) = context
         .memory
-        .read_cbor::<AggregateSealVerifyProofAndInfos>(agg_off, agg_len)?;
-    context
-        .kernel
-        .verify_aggregate_seals(&info)
-        .map(|v| if v { 0 } else { -1 })
+        .read_cbor::<AggregateSealVerifyProofAndInfosParams>(agg_off, agg_len)?
+        .into_checked();
+    let ok = context.kernel.verify_aggregate_seals(&info)?;
+    if !valid {
+        return Err(lenient::invalid_randomness());
+    }
+    Ok(if ok { 0 } else { -1 })
 }
 
 /// The return i32 indicates the status code of the verification:
@@ -185,15 +348,18 @@ pub fn batch_verify_seals(
     result_off: u32,
 ) -> Result<()> {
     // Check and decode params.
-    let batch = context
+    let (batch, valid): (Vec<_>, Vec<_>) = context
         .memory
-        .read_cbor::<Vec<SealVerifyInfo>>(batch_off, batch_len)?;
+        .read_cbor::<Vec<SealVerifyInfoParams>>(batch_off, batch_len)?
+        .into_iter()
+        .map(SealVerifyInfoParams::into_checked)
+        .unzip();
     let output = context
         .memory
         .try_slice_mut(result_off, batch.len() as u32)?;
 
     // Execute.
-    let result = context.kernel.batch_verify_seals(&batch)?;
+    let mut result = context.kernel.batch_verify_seals(&batch)?;
 
     // Sanity check that we got the correct number of results.
     if result.len() != batch.len() {
@@ -206,6 +372,7 @@ pub fn batch_verify_seals(
     }
 
     // Return.
+    lenient::fail_invalid(&mut result, &valid);
     unsafe {
         output.copy_from_slice(&*(&*result as *const [bool] as *const [u8]));
     }
@@ -223,3 +390,195 @@ pub fn total_fil_circ_supply(
         .context("circulating supply exceeds u128 limit")
         .or_fatal()
 }
+
+/// Proof verification parameters that accept randomness of any length.
+///
+/// [`Randomness`] used to be a byte vector, so randomness of the wrong length wasn't rejected when
+/// decoding, but failed the verification it was passed to: the whole verification (after charging
+/// for it) with `IllegalArgument`, or only its entry in a batch. These types preserve that. They
+/// substitute zeros for invalid randomness, so that the kernel charges for the verification as
+/// usual, and report it so that the syscall can fail the verification afterwards.
+mod lenient {
+    use cid::Cid;
+    use fvm_ipld_encoding::tuple::*;
+    use fvm_ipld_encoding::{BytesDe, strict_bytes};
+    use fvm_shared::ActorID;
+    use fvm_shared::deal::DealID;
+    use fvm_shared::randomness::Randomness;
+    use fvm_shared::sector::{
+        AggregateSealVerifyInfo, AggregateSealVerifyProofAndInfos, PoStProof,
+        RegisteredAggregateProof, RegisteredSealProof, SealVerifyInfo, SectorID, SectorInfo,
+        SectorNumber, WindowPoStVerifyInfo,
+    };
+
+    use crate::kernel::ExecutionError;
+    use crate::syscall_error;
+
+    /// Converts the bytes to randomness, or to zeros (clearing `valid`) if they're the wrong
+    /// length.
+    fn randomness(bytes: BytesDe, valid: &mut bool) -> Randomness {
+        Randomness::try_from(bytes.0.as_slice()).unwrap_or_else(|_| {
+            *valid = false;
+            Randomness::default()
+        })
+    }
+
+    /// The error for a verification given randomness of the wrong length.
+    pub fn invalid_randomness() -> ExecutionError {
+        syscall_error!(IllegalArgument; "randomness must be 32 bytes").into()
+    }
+
+    /// Fails the batch entries with invalid randomness.
+    pub fn fail_invalid(result: &mut [bool], valid: &[bool]) {
+        for (ok, valid) in result.iter_mut().zip(valid) {
+            *ok &= valid;
+        }
+    }
+
+    /// A [`SealVerifyInfo`].
+    #[derive(Deserialize_tuple)]
+    #[serde_tuple(optional_trailing)]
+    pub struct SealVerifyInfoParams {
+        registered_proof: RegisteredSealProof,
+        sector_id: SectorID,
+        deal_ids: Vec<DealID>,
+        randomness: BytesDe,
+        interactive_randomness: BytesDe,
+        #[serde(with = "strict_bytes")]
+        proof: Vec<u8>,
+        sealed_cid: Cid,
+        unsealed_cid: Cid,
+    }
+
+    impl SealVerifyInfoParams {
+        /// Returns the info, and whether its randomness is valid.
+        pub fn into_checked(self) -> (SealVerifyInfo, bool) {
+            let mut valid = true;
+            let info = SealVerifyInfo {
+                registered_proof: self.registered_proof,
+                sector_id: self.sector_id,
+                deal_ids: self.deal_ids,
+                randomness: randomness(self.randomness, &mut valid),
+                interactive_randomness: randomness(self.interactive_randomness, &mut valid),
+                proof: self.proof,
+                sealed_cid: self.sealed_cid,
+                unsealed_cid: self.unsealed_cid,
+            };
+            (info, valid)
+        }
+    }
+
+    /// A [`WindowPoStVerifyInfo`].
+    #[derive(Deserialize_tuple)]
+    #[serde_tuple(optional_trailing)]
+    pub struct WindowPoStVerifyInfoParams {
+        randomness: BytesDe,
+        proofs: Vec<PoStProof>,
+        challenged_sectors: Vec<SectorInfo>,
+        prover: ActorID,
+    }
+
+    impl WindowPoStVerifyInfoParams {
+        /// Returns the info, and whether its randomness is valid.
+        pub fn into_checked(self) -> (WindowPoStVerifyInfo, bool) {
+            let mut valid = true;
+            let info = WindowPoStVerifyInfo {
+                randomness: randomness(self.randomness, &mut valid),
+                proofs: self.proofs,
+                challenged_sectors: self.challenged_sectors,
+                prover: self.prover,
+            };
+            (info, valid)
+        }
+    }
+
+    /// An [`AggregateSealVerifyInfo`].
+    #[derive(Deserialize_tuple)]
+    #[serde_tuple(optional_trailing)]
+    pub struct AggregateSealVerifyInfoParams {
+        sector_number: SectorNumber,
+        randomness: BytesDe,
+        interactive_randomness: BytesDe,
+        sealed_cid: Cid,
+        unsealed_cid: Cid,
+    }
+
+    /// An [`AggregateSealVerifyProofAndInfos`].
+    #[derive(Deserialize_tuple)]
+    #[serde_tuple(optional_trailing)]
+    pub struct AggregateSealVerifyProofAndInfosParams {
+        miner: ActorID,
+        seal_proof: RegisteredSealProof,
+        aggregate_proof: RegisteredAggregateProof,
+        #[serde(with = "strict_bytes")]
+        proof: Vec<u8>,
+        infos: Vec<AggregateSealVerifyInfoParams>,
+    }
+
+    impl AggregateSealVerifyProofAndInfosParams {
+        /// Returns the aggregate, and whether the randomness of all its infos is valid.
+        pub fn into_checked(self) -> (AggregateSealVerifyProofAndInfos, bool) {
+            let mut valid = true;
+            let infos = self
+                .infos
+                .into_iter()
+                .map(|info| AggregateSealVerifyInfo {
+                    sector_number: info.sector_number,
+                    randomness: randomness(info.randomness, &mut valid),
+                    interactive_randomness: randomness(info.interactive_randomness, &mut valid),
+                    sealed_cid: info.sealed_cid,
+                    unsealed_cid: info.unsealed_cid,
+                })
+                .collect();
+            let aggregate = AggregateSealVerifyProofAndInfos {
+                miner: self.miner,
+                seal_proof: self.seal_proof,
+                aggregate_proof: self.aggregate_proof,
+                proof: self.proof,
+                infos,
+            };
+            (aggregate, valid)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use fvm_ipld_encoding::{BytesSer, from_slice, to_vec};
+        use fvm_shared::sector::RegisteredPoStProof;
+
+        use super::*;
+
+        fn window_post_info(randomness: &[u8]) -> (WindowPoStVerifyInfo, bool) {
+            let proofs = vec![PoStProof {
+                post_proof: RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+                proof_bytes: vec![1, 2, 3],
+            }];
+            let encoded =
+                to_vec(&(BytesSer(randomness), proofs, Vec::<SectorInfo>::new(), 1000)).unwrap();
+            from_slice::<WindowPoStVerifyInfoParams>(&encoded)
+                .unwrap()
+                .into_checked()
+        }
+
+        #[test]
+        fn accepts_randomness_of_any_length() {
+            let (info, valid) = window_post_info(&[7; 32]);
+            assert!(valid);
+            assert_eq!(info.randomness, Randomness([7; 32]));
+            assert_eq!(info.prover, 1000);
+
+            for len in [0, 31, 33] {
+                let (info, valid) = window_post_info(&vec![7; len]);
+                assert!(!valid, "{len}");
+                assert_eq!(info.randomness, Randomness::default());
+            }
+        }
+
+        #[test]
+        fn fails_invalid_entries() {
+            let mut result = [true, true, false, false];
+            fail_invalid(&mut result, &[true, false, true, false]);
+            assert_eq!(result, [true, false, false, false]);
+        }
+    }
+}
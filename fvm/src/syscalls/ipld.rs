@@ -69,3 +69,31 @@ pub fn block_stat(
             size: stat.size,
         })
 }
+
+pub fn block_stat_links(
+    context: Context<'_, impl IpldBlockOps>,
+    id: u32,
+) -> Result<sys::out::ipld::IpldStatLinks> {
+    context
+        .kernel
+        .block_stat_links(id)
+        .map(|(stat, links)| sys::out::ipld::IpldStatLinks {
+            codec: stat.codec,
+            size: stat.size,
+            links,
+        })
+}
+
+pub fn block_stat_by_cid(
+    context: Context<'_, impl IpldBlockOps>,
+    cid: u32,
+) -> Result<sys::out::ipld::IpldStat> {
+    let cid = context.memory.read_cid(cid)?;
+    context
+        .kernel
+        .block_stat_by_cid(&cid)
+        .map(|stat| sys::out::ipld::IpldStat {
+            codec: stat.codec,
+            size: stat.size,
+        })
+}
@@ -4,6 +4,7 @@ use std::mem;
 
 use fvm_shared::error::ErrorNumber;
 use fvm_shared::sys::SyscallSafe;
+use fvm_shared::version::NetworkVersion;
 use wasmtime::{Caller, WasmTy};
 
 use super::context::Memory;
@@ -13,9 +14,28 @@ use crate::call_manager::backtrace;
 use crate::kernel::{self, ExecutionError, Kernel, SyscallError};
 
 /// A "linker" for exposing syscalls to wasm modules.
-pub struct Linker<K>(pub(crate) wasmtime::Linker<InvocationData<K>>);
+///
+/// Each linker binds the syscalls of a single syscall ABI version (see [`Linker::abi_version`])
+/// and network version (see [`Linker::network_version`]).
+pub struct Linker<K>(
+    pub(crate) wasmtime::Linker<InvocationData<K>>,
+    pub(crate) u32,
+    pub(crate) NetworkVersion,
+);
 
 impl<K> Linker<K> {
+    /// The syscall ABI version targeted by the actors this linker links. Syscalls whose
+    /// signature or semantics differ between versions must be bound accordingly.
+    pub fn abi_version(&self) -> u32 {
+        self.1
+    }
+
+    /// The network version of the machine running the actors this linker links. New syscalls
+    /// must only be bound from the network version that introduces them.
+    pub fn network_version(&self) -> NetworkVersion {
+        self.2
+    }
+
     /// Link a syscall.
     ///
     /// # Example
@@ -245,3 +265,4 @@ impl_syscall!(A B C D E);
 impl_syscall!(A B C D E F);
 impl_syscall!(A B C D E F G);
 impl_syscall!(A B C D E F G H);
+impl_syscall!(A B C D E F G H I);
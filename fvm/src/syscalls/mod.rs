@@ -1,6 +1,7 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use anyhow::{Context as _, anyhow};
+use fvm_shared::version::NetworkVersion;
 use num_traits::Zero;
 use wasmtime::{AsContext, AsContextMut, ExternType, Global, Module, Val};
 
@@ -17,11 +18,13 @@ use crate::{DefaultKernel, Kernel};
 
 pub(crate) mod error;
 
+mod abi;
 mod actor;
 mod context;
 mod crypto;
 mod debug;
 mod event;
+mod extension;
 mod filecoin;
 mod gas;
 mod ipld;
@@ -32,8 +35,11 @@ mod send;
 mod sself;
 mod vm;
 
+pub use abi::SUPPORTED_SYSCALL_ABI_VERSIONS;
+pub(crate) use abi::declared_abi_version;
 pub use context::{Context, Memory};
 pub use error::Abort;
+pub use extension::{SyscallBinder, SyscallExtension};
 pub use linker::{ControlFlow, Linker};
 
 pub use linker::{IntoControlFlow, Syscall};
@@ -278,10 +284,18 @@ where
         linker.link_syscall("ipld", "block_create", ipld::block_create)?;
         linker.link_syscall("ipld", "block_read", ipld::block_read)?;
         linker.link_syscall("ipld", "block_stat", ipld::block_stat)?;
+        linker.link_syscall("ipld", "block_stat_links", ipld::block_stat_links)?;
+        if linker.network_version() >= NetworkVersion::V29 {
+            linker.link_syscall("ipld", "block_stat_by_cid", ipld::block_stat_by_cid)?;
+        }
         linker.link_syscall("ipld", "block_link", ipld::block_link)?;
 
         linker.link_syscall("self", "root", sself::root)?;
-        linker.link_syscall("self", "set_root", sself::set_root)?;
+        if linker.abi_version() >= 2 {
+            linker.link_syscall("self", "set_root", sself::set_root_v2)?;
+        } else {
+            linker.link_syscall("self", "set_root", sself::set_root)?;
+        }
         linker.link_syscall("self", "current_balance", sself::current_balance)?;
         linker.link_syscall("self", "self_destruct", sself::self_destruct)?;
 
@@ -293,11 +307,19 @@ where
         )?;
         linker.link_syscall("actor", "get_actor_code_cid", actor::get_actor_code_cid)?;
         linker.link_syscall("actor", "next_actor_address", actor::next_actor_address)?;
-        linker.link_syscall("actor", "create_actor", actor::create_actor)?;
+        if linker.abi_version() >= 2 {
+            linker.link_syscall("actor", "create_actor", actor::create_actor_v2)?;
+        } else {
+            linker.link_syscall("actor", "create_actor", actor::create_actor)?;
+        }
         if cfg!(feature = "upgrade-actor") {
             // We disable/enable with the feature, but we always compile this code to ensure we don't
             // accidentally break it.
-            linker.link_syscall("actor", "upgrade_actor", actor::upgrade_actor)?;
+            if linker.abi_version() >= 2 {
+                linker.link_syscall("actor", "upgrade_actor", actor::upgrade_actor_v2)?;
+            } else {
+                linker.link_syscall("actor", "upgrade_actor", actor::upgrade_actor)?;
+            }
         }
         linker.link_syscall(
             "actor",
@@ -310,6 +332,9 @@ where
             actor::get_code_cid_for_type,
         )?;
         linker.link_syscall("actor", "balance_of", actor::balance_of)?;
+        if linker.network_version() >= NetworkVersion::V29 {
+            linker.link_syscall("actor", "get_actor_state_root", actor::get_actor_state_root)?;
+        }
 
         // Only wire this syscall when M2 native is enabled.
         if cfg!(feature = "m2-native") {
@@ -317,6 +342,19 @@ where
         }
         #[cfg(feature = "verify-signature")]
         linker.link_syscall("crypto", "verify_signature", crypto::verify_signature)?;
+        #[cfg(feature = "verify-signature")]
+        if linker.network_version() >= NetworkVersion::V29 {
+            linker.link_syscall(
+                "crypto",
+                "verify_signature_tagged",
+                crypto::verify_signature_tagged,
+            )?;
+            linker.link_syscall(
+                "crypto",
+                "verify_signature_hashed",
+                crypto::verify_signature_hashed,
+            )?;
+        }
         linker.link_syscall(
             "crypto",
             "verify_bls_aggregate",
@@ -327,7 +365,11 @@ where
             "recover_secp_public_key",
             crypto::recover_secp_public_key,
         )?;
-        linker.link_syscall("crypto", "hash", crypto::hash)?;
+        if linker.abi_version() >= 2 {
+            linker.link_syscall("crypto", "hash", crypto::hash_v2)?;
+        } else {
+            linker.link_syscall("crypto", "hash", crypto::hash)?;
+        }
 
         linker.link_syscall("event", "emit_event", event::emit_event)?;
 
@@ -379,12 +421,25 @@ where
             "compute_unsealed_sector_cid",
             filecoin::compute_unsealed_sector_cid,
         )?;
+        linker.link_syscall(
+            "crypto",
+            "compute_unsealed_sector_cids",
+            filecoin::compute_unsealed_sector_cids,
+        )?;
         linker.link_syscall("crypto", "verify_post", filecoin::verify_post)?;
+        linker.link_syscall("crypto", "verify_post_batch", filecoin::verify_post_batch)?;
         linker.link_syscall(
             "crypto",
             "verify_consensus_fault",
             filecoin::verify_consensus_fault,
         )?;
+        if linker.network_version() >= NetworkVersion::V29 {
+            linker.link_syscall(
+                "crypto",
+                "verify_consensus_fault_report",
+                filecoin::verify_consensus_fault_report,
+            )?;
+        }
         linker.link_syscall(
             "crypto",
             "verify_aggregate_seals",
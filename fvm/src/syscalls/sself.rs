@@ -25,6 +25,14 @@ pub fn set_root(context: Context<'_, impl SelfOps>, cid_off: u32) -> Result<()>
     Ok(())
 }
 
+/// Like [`set_root`], but takes the CID's length and only accepts allowed CIDs (see
+/// [`Memory::read_checked_cid`](super::Memory::read_checked_cid)) (syscall ABI version 2).
+pub fn set_root_v2(context: Context<'_, impl SelfOps>, cid_off: u32, cid_len: u32) -> Result<()> {
+    let cid = context.memory.read_checked_cid(cid_off, cid_len)?;
+    context.kernel.set_root(cid)?;
+    Ok(())
+}
+
 pub fn current_balance(context: Context<'_, impl SelfOps>) -> Result<sys::TokenAmount> {
     let balance = context.kernel.current_balance()?;
     balance
@@ -1,11 +1,14 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::conformance;
 use fvm_shared::error::ExitCode;
 use fvm_shared::sys::out::vm::MessageContext;
+use fvm_shared::version::NetworkVersion;
 
 use super::Context;
 use super::error::Abort;
-use crate::kernel::MessageOps;
+use crate::kernel::{ExecutionError, Kernel, MessageOps};
+use crate::machine::Machine;
 
 /// The maximum message length included in the backtrace. Given 1024 levels, this gives us a total
 /// maximum of around 1MiB for debugging.
@@ -13,14 +16,15 @@ const MAX_MESSAGE_LEN: usize = 1024;
 
 // NOTE: this won't clobber the last syscall error because it directly returns a "trap".
 pub fn exit(
-    context: Context<'_, impl Sized>, // "impl Sized" means "any struct"
+    context: Context<'_, impl Kernel>,
     code: u32,
     blk: u32,
     message_off: u32,
     message_len: u32,
 ) -> Abort {
     let code = ExitCode::new(code);
-    if !code.is_success() && code.is_system_error() {
+    let nv = context.kernel.machine().context().network_version;
+    if !conformance::is_actor_exit_allowed(nv, code) {
         return Abort::Exit(
             ExitCode::SYS_ILLEGAL_EXIT_CODE,
             format!("actor aborted with code {}", code),
@@ -28,32 +32,121 @@ pub fn exit(
         );
     }
 
-    let message = if message_len == 0 {
-        "actor aborted".to_owned()
-    } else {
-        match context.memory.try_slice(message_off, message_len) {
-            Ok(bytes) => {
-                if bytes.len() > MAX_MESSAGE_LEN {
-                    let prefix = &bytes[..(MAX_MESSAGE_LEN / 2)];
-                    let suffix = &bytes[bytes.len() - (MAX_MESSAGE_LEN / 2)..];
-                    format!(
-                        "{} ... (skipped {} bytes) ... {}",
-                        String::from_utf8_lossy(prefix),
-                        bytes.len() - MAX_MESSAGE_LEN,
-                        String::from_utf8_lossy(suffix)
-                    )
-                } else {
-                    String::from_utf8_lossy(bytes).into_owned()
-                }
-            }
-            Err(e) => format!("failed to extract error message: {e}"),
+    if message_len == 0 {
+        return Abort::Exit(code, "actor aborted".to_owned(), blk);
+    }
+
+    // Charge for reading the message before we read it (we only read up to `MAX_MESSAGE_LEN`
+    // bytes). If we can't afford it, abort anyways but drop the message. We don't return
+    // `Abort::OutOfGas` here as we'd lose the actor's exit code.
+    let charge = context
+        .kernel
+        .machine()
+        .context()
+        .price_list
+        .on_abort((message_len as usize).min(MAX_MESSAGE_LEN));
+    let timer = match context.kernel.charge_gas(&charge.name, charge.total()) {
+        Ok(timer) => timer,
+        Err(ExecutionError::OutOfGas) => {
+            return Abort::Exit(
+                code,
+                "actor aborted (message dropped: out of gas)".to_owned(),
+                blk,
+            );
         }
+        Err(e) => return Abort::from_error_as_fatal(e),
+    };
+
+    let message = match context.memory.try_slice(message_off, message_len) {
+        Ok(bytes) => truncate_message(bytes),
+        Err(e) => format!("failed to extract error message: {e}"),
     };
+    timer.stop();
     Abort::Exit(code, message, blk)
 }
 
+/// Decodes an abort message (replacing invalid UTF-8), keeping only the first and last
+/// `MAX_MESSAGE_LEN / 2` bytes of messages longer than `MAX_MESSAGE_LEN` bytes.
+fn truncate_message(bytes: &[u8]) -> String {
+    if bytes.len() <= MAX_MESSAGE_LEN {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    let prefix = &bytes[..(MAX_MESSAGE_LEN / 2)];
+    let suffix = &bytes[bytes.len() - (MAX_MESSAGE_LEN / 2)..];
+    format!(
+        "{} ... (skipped {} bytes) ... {}",
+        String::from_utf8_lossy(prefix),
+        bytes.len() - MAX_MESSAGE_LEN,
+        String::from_utf8_lossy(suffix)
+    )
+}
+
 pub fn message_context(
     context: Context<'_, impl MessageOps>,
 ) -> crate::kernel::Result<MessageContext> {
     context.kernel.msg_context()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that actors may (or may not) exit with each of the specified codes.
+    fn assert_bands(nv: NetworkVersion, allowed: &[u32], denied: &[u32]) {
+        for &code in allowed {
+            assert!(
+                conformance::is_actor_exit_allowed(nv, ExitCode::new(code)),
+                "{code} should be allowed in nv{nv}"
+            );
+        }
+        for &code in denied {
+            assert!(
+                !conformance::is_actor_exit_allowed(nv, ExitCode::new(code)),
+                "{code} should be denied in nv{nv}"
+            );
+        }
+    }
+
+    #[test]
+    fn exit_code_bands() {
+        for nv in 21..=28 {
+            assert_bands(
+                NetworkVersion::new(nv),
+                &[0, 16, 17, 0x7fff_ffff, 0x8000_0000, u32::MAX],
+                &[1, 15],
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_long_messages() {
+        let short = "a".repeat(MAX_MESSAGE_LEN);
+        assert_eq!(truncate_message(short.as_bytes()), short);
+
+        let long = format!(
+            "{}{}{}",
+            "a".repeat(MAX_MESSAGE_LEN / 2),
+            "b".repeat(100),
+            "c".repeat(MAX_MESSAGE_LEN / 2)
+        );
+        assert_eq!(
+            truncate_message(long.as_bytes()),
+            format!(
+                "{} ... (skipped 100 bytes) ... {}",
+                "a".repeat(MAX_MESSAGE_LEN / 2),
+                "c".repeat(MAX_MESSAGE_LEN / 2)
+            )
+        );
+
+        // Characters split by the truncation (and any other invalid UTF-8) are replaced.
+        let mut split = vec![b'a'; MAX_MESSAGE_LEN / 2 - 1];
+        split.extend("é".repeat(MAX_MESSAGE_LEN).as_bytes());
+        let truncated = truncate_message(&split);
+        assert!(truncated.starts_with(&format!(
+            "{}\u{FFFD} ... ",
+            "a".repeat(MAX_MESSAGE_LEN / 2 - 1)
+        )));
+        assert!(truncated.len() < 2 * MAX_MESSAGE_LEN);
+        assert_eq!(truncate_message(b"bad \xff"), "bad \u{FFFD}");
+    }
+}
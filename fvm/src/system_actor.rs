@@ -13,6 +13,7 @@ use crate::state_tree::{ActorState, StateTree};
 pub const SYSTEM_ACTOR_ID: ActorID = 0;
 
 #[derive(Default, Deserialize_tuple, Serialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct State {
     // builtin actor registry: Vec<(String, Cid)>
     pub builtin_actors: Cid,
@@ -0,0 +1,1132 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Versioned, self-describing serialization of message execution results for long-term storage.
+//!
+//! An [`ArchivedApplyRet`] captures the result of applying a message (receipt, gas outputs, failure
+//! info, events, the actors created and deleted, and the execution trace, including gas charges)
+//! in a stable format. The optional, node-local [`ApplyRet::actor_changes`] and
+//! [`ApplyRet::write_stats`] aren't archived: they're only reported when enabled on the machine
+//! and, in the case of write statistics, depend on the node's blockstore.
+//!
+//! The format is as follows:
+//!
+//! - Every record is an array whose first element is the schema version ([`ARCHIVE_VERSION`]),
+//!   followed by the record's fields in a fixed order.
+//! - Decoding is forward-tolerant: trailing fields appended by future schema versions are ignored,
+//!   as are trace events of unknown kinds.
+//!
+//! Records are encoded as CBOR (and, with the `json` feature, JSON). Many records can be streamed
+//! to/from a single archive with [`write_archive`] and [`read_archive`], where each record is
+//! prefixed with its length as a big-endian `u32`.
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::time::Duration;
+
+use cid::Cid;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::{BytesDe, BytesSer, RawBytes};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::event::{ActorEvent, Entry, Flags, StampedEvent};
+use fvm_shared::receipt::Receipt;
+use fvm_shared::state::ActorState;
+use num_traits::FromPrimitive;
+use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{ExecutionEvent, ExecutionTrace, IpldOperation};
+use crate::executor::ApplyRet;
+use crate::gas::{Gas, GasCharge, GasDuration};
+use crate::kernel::SyscallError;
+
+/// The current archive schema version. Records are always written with this version.
+///
+/// - Version 1 records lack the miner tip recipient, proof gas, and created and deleted actors.
+/// - Version 2 appends them.
+pub const ARCHIVE_VERSION: u64 = 2;
+
+/// The maximum size of a single record in an archive. This guards against allocating huge buffers
+/// when reading corrupted archives.
+pub const MAX_RECORD_SIZE: usize = 1 << 30;
+
+/// An error encountered while reading or writing an archive.
+#[derive(thiserror::Error, Debug)]
+pub enum ArchiveError {
+    #[error("archive i/o error: {0}")]
+    Io(#[from] io::Error),
+    #[error("failed to encode or decode archive record: {0}")]
+    Encoding(#[from] fvm_ipld_encoding::Error),
+    #[error("archive record of {0} bytes exceeds the maximum record size")]
+    RecordTooLarge(usize),
+}
+
+/// The archived form of an [`ApplyRet`].
+#[derive(Clone, Debug)]
+pub struct ArchivedApplyRet {
+    /// The schema version of the record this was decoded from.
+    pub version: u64,
+    /// Message receipt for the transaction.
+    pub msg_receipt: Receipt,
+    /// Gas penalty from transaction, if any.
+    pub penalty: TokenAmount,
+    /// Tip given to miner from message.
+    pub miner_tip: TokenAmount,
+    pub base_fee_burn: TokenAmount,
+    pub over_estimation_burn: TokenAmount,
+    pub refund: TokenAmount,
+    pub gas_refund: u64,
+    pub gas_burned: u64,
+    /// The rendered failure information (e.g., the backtrace), if any.
+    pub failure_info: Option<String>,
+    /// The execution trace, including all gas charges.
+    pub exec_trace: ExecutionTrace,
+    /// Events generated while applying the message.
+    pub events: Vec<StampedEvent>,
+    /// The IPLD codec of the return data, if any.
+    pub return_codec: Option<u64>,
+    /// The actor credited with the miner tip, if a tip was paid. `None` in version 1 records.
+    pub miner_tip_recipient: Option<ActorID>,
+    /// Gas used verifying proofs, included in the receipt's `gas_used`. 0 in version 1 records.
+    pub proof_gas_used: u64,
+    /// The actors created by the message, with the addresses they were created for. Empty in
+    /// version 1 records.
+    pub created_actors: Vec<(ActorID, Address)>,
+    /// The actors deleted by the message. Empty in version 1 records.
+    pub deleted_actors: Vec<ActorID>,
+}
+
+impl From<&ApplyRet> for ArchivedApplyRet {
+    fn from(ret: &ApplyRet) -> Self {
+        ArchivedApplyRet {
+            version: ARCHIVE_VERSION,
+            msg_receipt: ret.msg_receipt.clone(),
+            penalty: ret.penalty.clone(),
+            miner_tip: ret.miner_tip.clone(),
+            base_fee_burn: ret.base_fee_burn.clone(),
+            over_estimation_burn: ret.over_estimation_burn.clone(),
+            refund: ret.refund.clone(),
+            gas_refund: ret.gas_refund,
+            gas_burned: ret.gas_burned,
+            failure_info: ret.failure_info.as_ref().map(|f| f.to_string()),
+            exec_trace: ret.exec_trace.clone(),
+            events: ret.events.clone(),
+            return_codec: ret.return_codec,
+            miner_tip_recipient: ret.miner_tip_recipient,
+            proof_gas_used: ret.proof_gas_used,
+            created_actors: ret.created_actors.clone(),
+            deleted_actors: ret.deleted_actors.clone(),
+        }
+    }
+}
+
+impl ArchivedApplyRet {
+    /// Encodes this record as CBOR.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, ArchiveError> {
+        Ok(fvm_ipld_encoding::to_vec(self)?)
+    }
+
+    /// Decodes a CBOR-encoded record.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, ArchiveError> {
+        Ok(fvm_ipld_encoding::from_slice(bytes)?)
+    }
+
+    /// Encodes this record as JSON.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Decodes a JSON-encoded record.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Writes the records to the writer as a length-prefixed stream of CBOR-encoded records.
+pub fn write_archive<'a, W, I>(mut writer: W, records: I) -> Result<(), ArchiveError>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ArchivedApplyRet>,
+{
+    for record in records {
+        let encoded = record.to_cbor()?;
+        let len: u32 = encoded
+            .len()
+            .try_into()
+            .ok()
+            .filter(|&l| l as usize <= MAX_RECORD_SIZE)
+            .ok_or(ArchiveError::RecordTooLarge(encoded.len()))?;
+        writer.write_all(&len.to_be_bytes())?;
+        writer.write_all(&encoded)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a stream of records written by [`write_archive`], one record at a time.
+pub fn read_archive<R: Read>(reader: R) -> ArchiveReader<R> {
+    ArchiveReader {
+        reader,
+        done: false,
+    }
+}
+
+/// An iterator over the records in an archive. See [`read_archive`].
+pub struct ArchiveReader<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Reads the next record, returning `None` at the end of the archive.
+    fn read_record(&mut self) -> Result<Option<ArchivedApplyRet>, ArchiveError> {
+        let mut len = [0u8; 4];
+        // Distinguish between a clean end of the archive and a truncated length prefix.
+        let mut read = 0;
+        while read < len.len() {
+            match self.reader.read(&mut len[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_RECORD_SIZE {
+            return Err(ArchiveError::RecordTooLarge(len));
+        }
+        let mut buf = vec![0u8; len];
+        self.reader.read_exact(&mut buf)?;
+        ArchivedApplyRet::from_cbor(&buf).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = Result<ArchivedApplyRet, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_record().transpose();
+        // Stop at the end of the archive, or at the first error as we can't resynchronize.
+        if !matches!(res, Some(Ok(_))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+impl Serialize for ArchivedApplyRet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(17))?;
+        seq.serialize_element(&ARCHIVE_VERSION)?;
+        seq.serialize_element(&Ser(&self.msg_receipt))?;
+        seq.serialize_element(&Ser(&self.penalty))?;
+        seq.serialize_element(&Ser(&self.miner_tip))?;
+        seq.serialize_element(&Ser(&self.base_fee_burn))?;
+        seq.serialize_element(&Ser(&self.over_estimation_burn))?;
+        seq.serialize_element(&Ser(&self.refund))?;
+        seq.serialize_element(&self.gas_refund)?;
+        seq.serialize_element(&self.gas_burned)?;
+        seq.serialize_element(&self.failure_info)?;
+        seq.serialize_element(&Ser(&self.exec_trace))?;
+        seq.serialize_element(&Ser(&self.events))?;
+        seq.serialize_element(&self.return_codec)?;
+        seq.serialize_element(&self.miner_tip_recipient)?;
+        seq.serialize_element(&self.proof_gas_used)?;
+        seq.serialize_element(&Ser(&self.created_actors))?;
+        seq.serialize_element(&self.deleted_actors)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ArchivedApplyRet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RecordVisitor;
+
+        impl<'de> Visitor<'de> for RecordVisitor {
+            type Value = ArchivedApplyRet;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an archived apply-ret record")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Fields::new(&mut seq);
+                let version: u64 = fields.next()?;
+                if version == 0 {
+                    return Err(de::Error::custom("invalid archive schema version 0"));
+                }
+                let mut record = ArchivedApplyRet {
+                    version,
+                    msg_receipt: fields.next_repr()?,
+                    penalty: fields.next_repr()?,
+                    miner_tip: fields.next_repr()?,
+                    base_fee_burn: fields.next_repr()?,
+                    over_estimation_burn: fields.next_repr()?,
+                    refund: fields.next_repr()?,
+                    gas_refund: fields.next()?,
+                    gas_burned: fields.next()?,
+                    failure_info: fields.next()?,
+                    exec_trace: fields.next_repr()?,
+                    events: fields.next_repr()?,
+                    return_codec: fields.next()?,
+                    miner_tip_recipient: None,
+                    proof_gas_used: 0,
+                    created_actors: Vec::new(),
+                    deleted_actors: Vec::new(),
+                };
+                if version >= 2 {
+                    record.miner_tip_recipient = fields.next()?;
+                    record.proof_gas_used = fields.next()?;
+                    record.created_actors = fields.next_repr()?;
+                    record.deleted_actors = fields.next()?;
+                }
+                fields.skip_rest()?;
+                Ok(record)
+            }
+        }
+
+        deserializer.deserialize_seq(RecordVisitor)
+    }
+}
+
+/// Reads positional fields from a sequence, ignoring any unknown trailing fields.
+struct Fields<'a, A> {
+    seq: &'a mut A,
+    index: usize,
+}
+
+impl<'a, 'de, A: SeqAccess<'de>> Fields<'a, A> {
+    fn new(seq: &'a mut A) -> Self {
+        Fields { seq, index: 0 }
+    }
+
+    fn next<T: Deserialize<'de>>(&mut self) -> Result<T, A::Error> {
+        let value = self
+            .seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(self.index, &"more fields"))?;
+        self.index += 1;
+        Ok(value)
+    }
+
+    fn next_repr<T: Repr>(&mut self) -> Result<T, A::Error> {
+        self.next::<De<T>>().map(|v| v.0)
+    }
+
+    fn skip_rest(&mut self) -> Result<(), A::Error> {
+        while self.seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(())
+    }
+}
+
+/// Types archived as a sequence of positional fields. Like records and trace events, they ignore
+/// unknown trailing fields, so that future schema versions can append fields to them.
+trait Fielded: Sized {
+    const EXPECTING: &'static str;
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error>;
+}
+
+/// Deserializes a [`Fielded`] type, skipping any unknown trailing fields.
+fn deserialize_fielded<'de, D: Deserializer<'de>, T: Fielded>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    struct FieldedVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Fielded> Visitor<'de> for FieldedVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(T::EXPECTING)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut fields = Fields::new(&mut seq);
+            let value = T::read(&mut fields)?;
+            fields.skip_rest()?;
+            Ok(value)
+        }
+    }
+
+    deserializer.deserialize_seq(FieldedVisitor(PhantomData))
+}
+
+/// Types with an archive representation. The binary (CBOR) representation of on-chain types
+/// matches their canonical encoding, while the human-readable (JSON) representation renders
+/// amounts and CIDs as strings.
+trait Repr: Sized {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Serializes a value with its archive representation.
+struct Ser<'a, T>(&'a T);
+
+impl<T: Repr> Serialize for Ser<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.ser(serializer)
+    }
+}
+
+/// Deserializes a value from its archive representation.
+struct De<T>(T);
+
+impl<'de, T: Repr> Deserialize<'de> for De<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::de(deserializer).map(De)
+    }
+}
+
+impl<T: Repr> Repr for Option<T> {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(v) => serializer.serialize_some(&Ser(v)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<De<T>>::deserialize(deserializer)?.map(|v| v.0))
+    }
+}
+
+impl<T: Repr> Repr for Vec<T> {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(Ser))
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<De<T>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|v| v.0)
+            .collect())
+    }
+}
+
+impl Repr for Vec<u8> {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            self.as_slice().serialize(serializer)
+        } else {
+            BytesSer(self).serialize(serializer)
+        }
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Vec::<u8>::deserialize(deserializer)
+        } else {
+            BytesDe::deserialize(deserializer).map(|b| b.0)
+        }
+    }
+}
+
+impl Repr for TokenAmount {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self.atto())
+        } else {
+            self.serialize(serializer)
+        }
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let atto = String::deserialize(deserializer)?;
+            BigInt::from_str(&atto)
+                .map(TokenAmount::from_atto)
+                .map_err(de::Error::custom)
+        } else {
+            TokenAmount::deserialize(deserializer)
+        }
+    }
+}
+
+impl Repr for Address {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Addresses are stored as bytes (not strings) so they don't depend on the network prefix.
+        self.to_bytes().ser(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Address::from_bytes(&Vec::<u8>::de(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl Repr for Cid {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            self.serialize(serializer)
+        }
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let cid = String::deserialize(deserializer)?;
+            Cid::try_from(cid.as_str()).map_err(de::Error::custom)
+        } else {
+            Cid::deserialize(deserializer)
+        }
+    }
+}
+
+impl Repr for Receipt {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (
+            self.exit_code.value(),
+            Ser(&self.return_data.to_vec()),
+            self.gas_used,
+            Ser(&self.events_root),
+        )
+            .serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for Receipt {
+    const EXPECTING: &'static str = "an archived receipt";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok(Receipt {
+            exit_code: ExitCode::new(fields.next()?),
+            return_data: RawBytes::new(fields.next_repr()?),
+            gas_used: fields.next()?,
+            events_root: fields.next_repr()?,
+        })
+    }
+}
+
+impl Repr for ActorState {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (
+            Ser(&self.code),
+            Ser(&self.state),
+            self.sequence,
+            Ser(&self.balance),
+            Ser(&self.delegated_address),
+        )
+            .serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for ActorState {
+    const EXPECTING: &'static str = "an archived actor state";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok(ActorState {
+            code: fields.next_repr()?,
+            state: fields.next_repr()?,
+            sequence: fields.next()?,
+            balance: fields.next_repr()?,
+            delegated_address: fields.next_repr()?,
+        })
+    }
+}
+
+impl Repr for Entry {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.flags.bits(), &self.key, self.codec, Ser(&self.value)).serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for Entry {
+    const EXPECTING: &'static str = "an archived event entry";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok(Entry {
+            flags: Flags::from_bits_retain(fields.next()?),
+            key: fields.next()?,
+            codec: fields.next()?,
+            value: fields.next_repr()?,
+        })
+    }
+}
+
+impl Repr for StampedEvent {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.emitter, Ser(&self.event.entries)).serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for StampedEvent {
+    const EXPECTING: &'static str = "an archived event";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok(StampedEvent {
+            emitter: fields.next()?,
+            event: ActorEvent {
+                entries: fields.next_repr()?,
+            },
+        })
+    }
+}
+
+impl Repr for (ActorID, Address) {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.0, Ser(&self.1)).serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for (ActorID, Address) {
+    const EXPECTING: &'static str = "an archived created actor";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok((fields.next()?, fields.next_repr()?))
+    }
+}
+
+impl Repr for IpldBlock {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.codec, Ser(&self.data)).serialize(serializer)
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fielded(deserializer)
+    }
+}
+
+impl Fielded for IpldBlock {
+    const EXPECTING: &'static str = "an archived IPLD block";
+
+    fn read<'de, A: SeqAccess<'de>>(fields: &mut Fields<'_, A>) -> Result<Self, A::Error> {
+        Ok(IpldBlock {
+            codec: fields.next()?,
+            data: fields.next_repr()?,
+        })
+    }
+}
+
+// Trace event kinds. These values are part of the archive schema and must never be reused.
+const EVENT_GAS_CHARGE: u64 = 0;
+const EVENT_CALL: u64 = 1;
+const EVENT_CALL_RETURN: u64 = 2;
+const EVENT_CALL_ERROR: u64 = 3;
+const EVENT_INVOKE_ACTOR: u64 = 4;
+const EVENT_LOG: u64 = 5;
+const EVENT_IPLD: u64 = 6;
+const EVENT_CODE_DENIED: u64 = 7;
+const EVENT_SET_ROOT: u64 = 8;
+const EVENT_CREATE_ACTOR: u64 = 9;
+const EVENT_NETWORK_CONTEXT: u64 = 10;
+const EVENT_TRACE_TRUNCATED: u64 = 11;
+
+impl Repr for ExecutionTrace {
+    fn ser<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(EventSer))
+    }
+
+    fn de<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Skip events of unknown kinds.
+        Ok(Vec::<MaybeEvent>::deserialize(deserializer)?
+            .into_iter()
+            .filter_map(|v| v.0)
+            .collect())
+    }
+}
+
+/// Serializes a trace event as `[kind, fields...]`.
+struct EventSer<'a>(&'a ExecutionEvent);
+
+impl Serialize for EventSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            ExecutionEvent::GasCharge(charge) => (
+                EVENT_GAS_CHARGE,
+                &*charge.name,
+                charge.compute_gas.as_milligas(),
+                charge.other_gas.as_milligas(),
+                charge
+                    .elapsed
+                    .get()
+                    .map(|d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX)),
+            )
+                .serialize(serializer),
+            ExecutionEvent::Call {
+                from,
+                to,
+                method,
+                params,
+                value,
+                gas_limit,
+                read_only,
+            } => (
+                EVENT_CALL,
+                from,
+                Ser(to),
+                method,
+                Ser(params),
+                Ser(value),
+                gas_limit,
+                read_only,
+            )
+                .serialize(serializer),
+            ExecutionEvent::CallReturn(exit_code, ret) => {
+                (EVENT_CALL_RETURN, exit_code.value(), Ser(ret)).serialize(serializer)
+            }
+            ExecutionEvent::CallError(SyscallError(message, errno)) => {
+                (EVENT_CALL_ERROR, message, *errno as u32).serialize(serializer)
+            }
+            ExecutionEvent::InvokeActor { id, state } => {
+                (EVENT_INVOKE_ACTOR, id, Ser(state)).serialize(serializer)
+            }
+            ExecutionEvent::Log(message) => (EVENT_LOG, message).serialize(serializer),
+            ExecutionEvent::Ipld { op, cid, size } => {
+                let op: u64 = match op {
+                    IpldOperation::Get => 0,
+                    IpldOperation::Put => 1,
+                };
+                (EVENT_IPLD, op, Ser(cid), *size as u64).serialize(serializer)
+            }
+            ExecutionEvent::CodeDenied { id, code } => {
+                (EVENT_CODE_DENIED, id, Ser(code)).serialize(serializer)
+            }
+            ExecutionEvent::SetRoot { id, root, reverted } => {
+                (EVENT_SET_ROOT, id, Ser(root), reverted).serialize(serializer)
+            }
+            ExecutionEvent::CreateActor {
+                id,
+                code,
+                delegated_address,
+                actors_created,
+            } => (
+                EVENT_CREATE_ACTOR,
+                id,
+                Ser(code),
+                Ser(delegated_address),
+                actors_created,
+            )
+                .serialize(serializer),
+            ExecutionEvent::NetworkContext { epoch, timestamp } => {
+                (EVENT_NETWORK_CONTEXT, epoch, timestamp).serialize(serializer)
+            }
+            ExecutionEvent::TraceTruncated { dropped } => {
+                (EVENT_TRACE_TRUNCATED, dropped).serialize(serializer)
+            }
+        }
+    }
+}
+
+/// Deserializes a trace event, or `None` if the event is of an unknown kind.
+struct MaybeEvent(Option<ExecutionEvent>);
+
+impl<'de> Deserialize<'de> for MaybeEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EventVisitor;
+
+        impl<'de> Visitor<'de> for EventVisitor {
+            type Value = MaybeEvent;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an archived trace event")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Fields::new(&mut seq);
+                let kind: u64 = fields.next()?;
+                let event = match kind {
+                    EVENT_GAS_CHARGE => {
+                        let name: String = fields.next()?;
+                        let compute_gas = Gas::from_milligas(fields.next()?);
+                        let other_gas = Gas::from_milligas(fields.next()?);
+                        let elapsed: Option<u64> = fields.next()?;
+                        Some(ExecutionEvent::GasCharge(GasCharge {
+                            name: Cow::Owned(name),
+                            compute_gas,
+                            other_gas,
+                            proof: false,
+                            elapsed: elapsed
+                                .map(|nanos| GasDuration::from(Duration::from_nanos(nanos)))
+                                .unwrap_or_default(),
+                        }))
+                    }
+                    EVENT_CALL => Some(ExecutionEvent::Call {
+                        from: fields.next()?,
+                        to: fields.next_repr()?,
+                        method: fields.next()?,
+                        params: fields.next_repr()?,
+                        value: fields.next_repr()?,
+                        gas_limit: fields.next()?,
+                        read_only: fields.next()?,
+                    }),
+                    EVENT_CALL_RETURN => {
+                        let exit_code = ExitCode::new(fields.next()?);
+                        Some(ExecutionEvent::CallReturn(exit_code, fields.next_repr()?))
+                    }
+                    EVENT_CALL_ERROR => {
+                        let message: String = fields.next()?;
+                        let errno: u32 = fields.next()?;
+                        let errno = ErrorNumber::from_u32(errno).ok_or_else(|| {
+                            de::Error::custom(format!("unknown error number {errno}"))
+                        })?;
+                        Some(ExecutionEvent::CallError(SyscallError(message, errno)))
+                    }
+                    EVENT_INVOKE_ACTOR => Some(ExecutionEvent::InvokeActor {
+                        id: fields.next()?,
+                        state: fields.next_repr()?,
+                    }),
+                    EVENT_LOG => Some(ExecutionEvent::Log(fields.next()?)),
+                    EVENT_IPLD => {
+                        let op = match fields.next::<u64>()? {
+                            0 => IpldOperation::Get,
+                            1 => IpldOperation::Put,
+                            op => {
+                                return Err(de::Error::custom(format!(
+                                    "unknown ipld operation {op}"
+                                )));
+                            }
+                        };
+                        let cid = fields.next_repr()?;
+                        let size: u64 = fields.next()?;
+                        Some(ExecutionEvent::Ipld {
+                            op,
+                            cid,
+                            size: size.try_into().map_err(de::Error::custom)?,
+                        })
+                    }
+                    EVENT_CODE_DENIED => Some(ExecutionEvent::CodeDenied {
+                        id: fields.next()?,
+                        code: fields.next_repr()?,
+                    }),
+                    EVENT_SET_ROOT => Some(ExecutionEvent::SetRoot {
+                        id: fields.next()?,
+                        root: fields.next_repr()?,
+                        reverted: fields.next()?,
+                    }),
+                    EVENT_CREATE_ACTOR => Some(ExecutionEvent::CreateActor {
+                        id: fields.next()?,
+                        code: fields.next_repr()?,
+                        delegated_address: fields.next_repr()?,
+                        actors_created: fields.next()?,
+                    }),
+                    EVENT_NETWORK_CONTEXT => Some(ExecutionEvent::NetworkContext {
+                        epoch: fields.next()?,
+                        timestamp: fields.next()?,
+                    }),
+                    EVENT_TRACE_TRUNCATED => Some(ExecutionEvent::TraceTruncated {
+                        dropped: fields.next()?,
+                    }),
+                    _ => None,
+                };
+                fields.skip_rest()?;
+                Ok(MaybeEvent(event))
+            }
+        }
+
+        deserializer.deserialize_seq(EventVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW};
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+
+    /// A record exercising every field and trace event kind.
+    fn sample_record() -> ArchivedApplyRet {
+        let code = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"code"));
+        let state = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"state"));
+        ArchivedApplyRet {
+            version: ARCHIVE_VERSION,
+            msg_receipt: Receipt {
+                exit_code: ExitCode::OK,
+                return_data: RawBytes::new(vec![1, 2, 3]),
+                gas_used: 1234,
+                events_root: Some(state),
+            },
+            penalty: TokenAmount::from_atto(0),
+            miner_tip: TokenAmount::from_atto(100),
+            base_fee_burn: TokenAmount::from_whole(200),
+            over_estimation_burn: TokenAmount::from_atto(0),
+            refund: TokenAmount::from_atto(300),
+            gas_refund: 10,
+            gas_burned: 20,
+            failure_info: Some("message failed with backtrace:".into()),
+            exec_trace: vec![
+                ExecutionEvent::NetworkContext {
+                    epoch: 100,
+                    timestamp: 1_700_000_000,
+                },
+                ExecutionEvent::GasCharge(GasCharge {
+                    name: "OnMethodInvocation".into(),
+                    compute_gas: Gas::from_milligas(75_000),
+                    other_gas: Gas::from_milligas(1),
+                    proof: false,
+                    elapsed: GasDuration::from(Duration::from_nanos(42)),
+                }),
+                ExecutionEvent::Call {
+                    from: 100,
+                    to: Address::new_id(1000),
+                    method: 2,
+                    params: Some(IpldBlock {
+                        codec: IPLD_RAW,
+                        data: b"params".to_vec(),
+                    }),
+                    value: TokenAmount::from_atto(5),
+                    gas_limit: 1000,
+                    read_only: false,
+                },
+                ExecutionEvent::InvokeActor {
+                    id: 1000,
+                    state: ActorState::new(
+                        code,
+                        state,
+                        TokenAmount::from_atto(5),
+                        0,
+                        Some(Address::new_delegated(10, b"foo").unwrap()),
+                    ),
+                },
+                ExecutionEvent::Ipld {
+                    op: IpldOperation::Put,
+                    cid: state,
+                    size: 64,
+                },
+                ExecutionEvent::Log("hello".into()),
+                ExecutionEvent::CallError(SyscallError("boom".into(), ErrorNumber::NotFound)),
+                ExecutionEvent::CodeDenied { id: 1001, code },
+                ExecutionEvent::CreateActor {
+                    id: 1002,
+                    code,
+                    delegated_address: Some(Address::new_delegated(10, b"bar").unwrap()),
+                    actors_created: 3,
+                },
+                ExecutionEvent::SetRoot {
+                    id: 1000,
+                    root: state,
+                    reverted: true,
+                },
+                ExecutionEvent::CallReturn(ExitCode::new(16), None),
+                ExecutionEvent::TraceTruncated { dropped: 7 },
+            ],
+            events: vec![StampedEvent::new(
+                1000,
+                ActorEvent {
+                    entries: vec![Entry {
+                        flags: Flags::FLAG_INDEXED_ALL,
+                        key: "foo".into(),
+                        codec: IPLD_RAW,
+                        value: b"bar".to_vec(),
+                    }],
+                },
+            )],
+            return_codec: Some(IPLD_RAW),
+            miner_tip_recipient: Some(1000),
+            proof_gas_used: 5,
+            created_actors: vec![(1002, Address::new_delegated(10, b"bar").unwrap())],
+            deleted_actors: vec![1001],
+        }
+    }
+
+    fn assert_records_eq(a: &ArchivedApplyRet, b: &ArchivedApplyRet) {
+        // Neither trace events nor gas charges implement a useful equality, so we compare the
+        // debug representation (which includes everything we archive).
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        let record = sample_record();
+        let decoded = ArchivedApplyRet::from_cbor(&record.to_cbor().unwrap()).unwrap();
+        assert_records_eq(&record, &decoded);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let record = sample_record();
+        let decoded = ArchivedApplyRet::from_json(&record.to_json().unwrap()).unwrap();
+        assert_records_eq(&record, &decoded);
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let records = vec![sample_record(), sample_record()];
+        let mut archive = Vec::new();
+        write_archive(&mut archive, &records).unwrap();
+
+        let decoded: Vec<_> = read_archive(archive.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(decoded.len(), records.len());
+        for (a, b) in records.iter().zip(&decoded) {
+            assert_records_eq(a, b);
+        }
+
+        // Truncated archives are reported as errors.
+        let mut truncated = read_archive(&archive[..archive.len() - 1]);
+        assert!(truncated.next().unwrap().is_ok());
+        assert!(matches!(truncated.next(), Some(Err(ArchiveError::Io(_)))));
+        assert!(truncated.next().is_none());
+    }
+
+    /// The golden version 1 encoding, which must still be decoded.
+    #[test]
+    fn golden_v1() {
+        let golden = include_bytes!("testdata/apply_ret_v1.cbor");
+        let record = ArchivedApplyRet::from_cbor(golden).unwrap();
+        assert_eq!(record.version, 1);
+        assert_eq!(record.msg_receipt.exit_code, ExitCode::new(16));
+        assert_eq!(record.msg_receipt.return_data, RawBytes::new(vec![1, 2]));
+        assert_eq!(record.msg_receipt.gas_used, 1234);
+        assert_eq!(record.miner_tip, TokenAmount::from_atto(100));
+        assert_eq!(record.refund, TokenAmount::from_atto(300));
+        assert_eq!(record.gas_refund, 10);
+        assert_eq!(record.gas_burned, 20);
+        assert_eq!(record.failure_info.as_deref(), Some("failed"));
+        assert_eq!(record.return_codec, Some(IPLD_RAW));
+        assert!(record.events.is_empty());
+        assert_eq!(record.exec_trace.len(), 4);
+        assert!(matches!(
+            &record.exec_trace[0],
+            ExecutionEvent::GasCharge(GasCharge { name, compute_gas, .. })
+                if name == "OnMethodInvocation" && compute_gas.as_milligas() == 75_000
+        ));
+        assert!(matches!(
+            &record.exec_trace[1],
+            ExecutionEvent::Call { from: 100, to, method: 2, params: None, gas_limit: 1000, read_only: false, .. }
+                if *to == Address::new_id(1000)
+        ));
+        assert!(matches!(
+            &record.exec_trace[2],
+            ExecutionEvent::CallError(SyscallError(msg, ErrorNumber::NotFound)) if msg == "boom"
+        ));
+        assert!(matches!(
+            &record.exec_trace[3],
+            ExecutionEvent::CallReturn(code, None) if *code == ExitCode::new(16)
+        ));
+        // Fields added in version 2 are left empty.
+        assert_eq!(record.miner_tip_recipient, None);
+        assert_eq!(record.proof_gas_used, 0);
+        assert!(record.created_actors.is_empty());
+        assert!(record.deleted_actors.is_empty());
+    }
+
+    /// The golden version 2 encoding: the version 1 fixture with the fields added in version 2. If
+    /// this test fails, the archive schema has changed: bump [`ARCHIVE_VERSION`] and keep decoding
+    /// older records instead of updating this fixture.
+    #[test]
+    fn golden_v2() {
+        let golden = include_bytes!("testdata/apply_ret_v2.cbor");
+        let record = ArchivedApplyRet::from_cbor(golden).unwrap();
+        assert_eq!(record.version, 2);
+        assert_eq!(record.msg_receipt.gas_used, 1234);
+        assert_eq!(record.return_codec, Some(IPLD_RAW));
+        assert_eq!(record.exec_trace.len(), 4);
+        assert_eq!(record.miner_tip_recipient, Some(1000));
+        assert_eq!(record.proof_gas_used, 5);
+        assert_eq!(record.created_actors, [(1002, Address::new_id(1002))]);
+        assert_eq!(record.deleted_actors, [1001]);
+
+        // Re-encoding the decoded record must reproduce the fixture exactly.
+        assert_eq!(record.to_cbor().unwrap(), golden);
+    }
+
+    /// A hand-constructed record from a hypothetical future schema version with an extra trailing
+    /// record field, an extra trailing trace event field, and an unknown trace event kind.
+    #[test]
+    fn decode_future_version() {
+        let future = include_bytes!("testdata/apply_ret_future.cbor");
+        let record = ArchivedApplyRet::from_cbor(future).unwrap();
+        assert_eq!(record.version, 3);
+        assert_eq!(record.msg_receipt.gas_used, 1234);
+        assert_eq!(record.return_codec, Some(IPLD_RAW));
+        assert_eq!(record.deleted_actors, [1001]);
+        // The unknown event is skipped, and the known event with an extra field is decoded.
+        assert_eq!(record.exec_trace.len(), 1);
+        assert!(matches!(
+            &record.exec_trace[0],
+            ExecutionEvent::Log(msg) if msg == "hello"
+        ));
+    }
+
+    /// Types nested in records (receipts, actor states, events, blocks, and created actors) also
+    /// ignore trailing fields appended by future schema versions.
+    #[test]
+    fn decode_future_nested_fields() {
+        use ipld_core::ipld::Ipld;
+
+        fn field(ipld: &mut Ipld, i: usize) -> &mut Ipld {
+            let Ipld::List(fields) = ipld else {
+                panic!("expected a list, got {ipld:?}")
+            };
+            &mut fields[i]
+        }
+        fn extend(ipld: &mut Ipld) {
+            let Ipld::List(fields) = ipld else {
+                panic!("expected a list, got {ipld:?}")
+            };
+            fields.push(Ipld::String("future".into()));
+        }
+
+        let record = sample_record();
+        let mut ipld: Ipld = fvm_ipld_encoding::from_slice(&record.to_cbor().unwrap()).unwrap();
+
+        // The receipt.
+        extend(field(&mut ipld, 1));
+        // The call parameters and the invoked actor's state.
+        let Ipld::List(trace) = field(&mut ipld, 10) else {
+            panic!("expected a trace")
+        };
+        for event in trace {
+            let kind = field(event, 0).clone();
+            match kind {
+                Ipld::Integer(1) => extend(field(event, 4)),
+                Ipld::Integer(4) => extend(field(event, 2)),
+                _ => {}
+            }
+        }
+        // The events and their entries.
+        let Ipld::List(events) = field(&mut ipld, 11) else {
+            panic!("expected events")
+        };
+        for event in events {
+            let Ipld::List(entries) = field(event, 1) else {
+                panic!("expected entries")
+            };
+            entries.iter_mut().for_each(extend);
+            extend(event);
+        }
+        // The created actors.
+        let Ipld::List(created) = field(&mut ipld, 15) else {
+            panic!("expected created actors")
+        };
+        created.iter_mut().for_each(extend);
+
+        let future = fvm_ipld_encoding::to_vec(&ipld).unwrap();
+        let decoded = ArchivedApplyRet::from_cbor(&future).unwrap();
+        assert_records_eq(&record, &decoded);
+    }
+}
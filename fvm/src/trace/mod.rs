@@ -1,8 +1,11 @@
 use cid::Cid;
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::BTreeMap;
+
 use fvm_ipld_encoding::ipld_block::IpldBlock;
 use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
 use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ExitCode;
 use fvm_shared::state::ActorState;
@@ -11,6 +14,8 @@ use fvm_shared::{ActorID, MethodNum};
 use crate::gas::GasCharge;
 use crate::kernel::SyscallError;
 
+pub mod archive;
+
 /// Execution Trace, only for informational and debugging purposes.
 pub type ExecutionTrace = Vec<ExecutionEvent>;
 
@@ -27,6 +32,13 @@ pub enum IpldOperation {
 #[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum ExecutionEvent {
+    /// The tipset context observed by actors while executing the message. Emitted once, at the
+    /// start of the trace.
+    NetworkContext {
+        epoch: ChainEpoch,
+        /// The tipset timestamp, in seconds since the Unix epoch.
+        timestamp: u64,
+    },
     GasCharge(GasCharge),
     /// Emitted on each send call regardless whether we actually end up invoking the
     /// actor or not (e.g. if we don't have enough gas or if the actor does not exist)
@@ -46,10 +58,79 @@ pub enum ExecutionEvent {
         id: ActorID,
         state: ActorState,
     },
+    /// Emitted when an actor isn't invoked because its code CID is denied by the network or the
+    /// local node.
+    CodeDenied {
+        id: ActorID,
+        code: Cid,
+    },
     Log(String),
+    /// Emitted every time an actor is created, when tracing. Actors created by a call that later
+    /// fails are still counted.
+    CreateActor {
+        id: ActorID,
+        code: Cid,
+        delegated_address: Option<Address>,
+        /// The number of actors created earlier in the message. This is mixed into the robust (f2)
+        /// address assigned to the actor by the init actor (see
+        /// [`CallManager::next_actor_address`](crate::call_manager::CallManager::next_actor_address)).
+        actors_created: u64,
+    },
     Ipld {
         op: IpldOperation,
         cid: Cid,
         size: usize,
     },
+    /// Emitted every time an actor sets its state root, when tracing. If the call that set the
+    /// root (or one of its callers) later fails, the event is kept but marked as reverted.
+    SetRoot {
+        id: ActorID,
+        root: Cid,
+        reverted: bool,
+    },
+    /// The last event of a trace that reached the configured limit (see
+    /// [`MachineContext::max_trace_events`](crate::machine::MachineContext::max_trace_events)),
+    /// counting the events dropped from the end of the trace.
+    TraceTruncated {
+        dropped: u64,
+    },
+}
+
+/// A state root set by an actor, as recorded in an execution trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootUpdate {
+    /// The new state root.
+    pub root: Cid,
+    /// The position of the [`ExecutionEvent::SetRoot`] event in the trace.
+    pub index: usize,
+    /// The positions of the [`ExecutionEvent::Call`] events of the calls that were active when
+    /// the root was set, outermost first.
+    pub call_path: Vec<usize>,
+    /// Whether the root was reverted.
+    pub reverted: bool,
+}
+
+/// Reconstructs the history of state roots set by each actor from an execution trace, in the
+/// order in which they were set.
+pub fn root_history(trace: &[ExecutionEvent]) -> BTreeMap<ActorID, Vec<RootUpdate>> {
+    let mut history: BTreeMap<ActorID, Vec<RootUpdate>> = BTreeMap::new();
+    let mut call_path = Vec::new();
+    for (index, event) in trace.iter().enumerate() {
+        match event {
+            ExecutionEvent::Call { .. } => call_path.push(index),
+            ExecutionEvent::CallReturn(..) | ExecutionEvent::CallError(_) => {
+                call_path.pop();
+            }
+            ExecutionEvent::SetRoot { id, root, reverted } => {
+                history.entry(*id).or_default().push(RootUpdate {
+                    root: *root,
+                    index,
+                    call_path: call_path.clone(),
+                    reverted: *reverted,
+                })
+            }
+            _ => {}
+        }
+    }
+    history
 }
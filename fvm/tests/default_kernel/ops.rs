@@ -9,7 +9,8 @@ mod ipld {
     use fvm::machine::Machine;
     use fvm::trace::IpldOperation;
     use fvm_ipld_blockstore::Blockstore;
-    use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW};
+    use fvm_ipld_encoding::{CBOR, DAG_CBOR, IPLD_RAW};
+    use fvm_shared::version::NetworkVersion;
     use multihash_codetable::MultihashDigest;
     use pretty_assertions::{assert_eq, assert_ne};
 
@@ -120,6 +121,90 @@ mod ipld {
         Ok(())
     }
 
+    #[test]
+    fn create_identical_blocks_share_buffer() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        // E.g., an actor sending the same 100KiB params to many receivers.
+        let block = vec![0xab; 100 << 10];
+        let mut ids = Vec::new();
+        for _ in 0..1000 {
+            ids.push(kern.block_create(IPLD_RAW, &block)?);
+        }
+
+        // Every block gets its own handle, but there's only one copy of the data.
+        assert_eq!(ids, (1..=1000).collect::<Vec<_>>());
+        assert_eq!(kern.blocks.buffered_bytes(), block.len());
+        let first = kern.blocks.get(ids[0])?.clone();
+        for &id in &ids {
+            let b = kern.blocks.get(id)?;
+            assert!(b.shares_buffer(&first));
+            assert_eq!(b.data(), &block[..]);
+        }
+
+        // Blocks with different contents (or codecs) get their own buffers.
+        let other = vec![0xcd; 100 << 10];
+        let other_id = kern.block_create(IPLD_RAW, &other)?;
+        let cbor_id = kern.block_create(DAG_CBOR, &[0x80])?;
+        let raw_id = kern.block_create(IPLD_RAW, &[0x80])?;
+        assert_eq!(kern.blocks.buffered_bytes(), block.len() + other.len() + 2);
+        assert!(!kern.blocks.get(other_id)?.shares_buffer(&first));
+        assert!(
+            !kern
+                .blocks
+                .get(cbor_id)?
+                .shares_buffer(kern.blocks.get(raw_id)?)
+        );
+        assert_eq!(kern.block_stat(cbor_id)?.codec, DAG_CBOR);
+        assert_eq!(kern.block_stat(raw_id)?.codec, IPLD_RAW);
+
+        // Each handle can be read (and dropped) independently.
+        let mut buf = vec![0u8; block.len()];
+        assert_eq!(kern.block_read(ids[500], 0, &mut buf)?, 0);
+        assert_eq!(buf, block);
+        drop(first);
+        let (_, blocks) = kern.into_inner();
+        assert_eq!(blocks.get(ids[999])?.data(), &block[..]);
+        assert_eq!(blocks.buffered_bytes(), block.len() + other.len() + 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_identical_blocks_same_gas() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        let (mut kern1, _) = build_inspecting_test()?;
+
+        // Deduplication is invisible to gas: creating the same block repeatedly costs exactly as
+        // much as creating distinct blocks of the same size.
+        let block = vec![0xab; 100 << 10];
+        let expected = kern
+            .call_manager
+            .machine
+            .context()
+            .price_list
+            .on_block_create(block.len(), 0)
+            .total();
+        for i in 0..10u8 {
+            let before = kern.call_manager.gas_tracker.gas_used();
+            kern.block_create(IPLD_RAW, &block)?;
+            assert_eq!(kern.call_manager.gas_tracker.gas_used() - before, expected);
+
+            let mut distinct = block.clone();
+            distinct[0] = i;
+            let before = kern1.call_manager.gas_tracker.gas_used();
+            kern1.block_create(IPLD_RAW, &distinct)?;
+            assert_eq!(kern1.call_manager.gas_tracker.gas_used() - before, expected);
+        }
+        assert_eq!(
+            kern.call_manager.gas_tracker.gas_used(),
+            kern1.call_manager.gas_tracker.gas_used()
+        );
+        assert!(kern.blocks.buffered_bytes() < kern1.blocks.buffered_bytes());
+
+        Ok(())
+    }
+
     #[test]
     fn create_unexpected() -> anyhow::Result<()> {
         let (mut kern, _) = build_inspecting_test()?;
@@ -133,6 +218,80 @@ mod ipld {
         Ok(())
     }
 
+    #[test]
+    fn create_size_limit() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        kern.call_manager
+            .machine
+            .ctx
+            .network
+            .set_max_block_size(1024);
+
+        kern.block_create(IPLD_RAW, &[0; 1024])?;
+        expect_syscall_err!(LimitExceeded, kern.block_create(IPLD_RAW, &[0; 1025]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_bytes_per_message_limit() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        kern.call_manager
+            .machine
+            .ctx
+            .network
+            .set_max_block_bytes_per_message(1000);
+
+        kern.block_create(IPLD_RAW, &[0; 600])?;
+        // Blocks that fail to be created don't count.
+        expect_syscall_err!(IllegalCodec, kern.block_create(0xFF, &[0; 600]));
+        // Up to the limit...
+        kern.block_create(IPLD_RAW, &[0; 400])?;
+        assert_eq!(kern.call_manager.block_bytes, 1000);
+        // ...but not beyond it, even by a single byte.
+        expect_syscall_err!(LimitExceeded, kern.block_create(IPLD_RAW, &[0; 1]));
+        assert_eq!(kern.call_manager.block_bytes, 1000);
+        // Empty blocks are still allowed.
+        kern.block_create(IPLD_RAW, &[])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_validates_dag_cbor() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let float = [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+        let unknown_tag = [0xc1, 0x01];
+        let undefined = [0x82, 0x01, 0xf7];
+        let truncated = [0x82, 0x01];
+        let bad_cid = [0xd8, 42, 0x42, 0x00, 0x01];
+
+        // Before nv29, DAG-CBOR blocks are only checked while scanning them for links.
+        kern.block_create(DAG_CBOR, &float)?;
+        kern.block_create(DAG_CBOR, &unknown_tag)?;
+        kern.block_create(DAG_CBOR, &undefined)?;
+        expect_syscall_err!(Serialization, kern.block_create(DAG_CBOR, &truncated));
+        expect_syscall_err!(Serialization, kern.block_create(DAG_CBOR, &bad_cid));
+
+        kern.call_manager.machine.ctx.network.network_version = NetworkVersion::V29;
+        for block in [
+            &float[..],
+            &unknown_tag[..],
+            &undefined[..],
+            &truncated[..],
+            &bad_cid[..],
+        ] {
+            expect_syscall_err!(Serialization, kern.block_create(DAG_CBOR, block));
+            // CBOR and raw blocks are opaque.
+            kern.block_create(CBOR, block)?;
+            kern.block_create(IPLD_RAW, block)?;
+        }
+        kern.block_create(DAG_CBOR, &[0x83, 0x01, 0xf5, 0xf6])?;
+
+        Ok(())
+    }
+
     #[test]
     fn link() -> anyhow::Result<()> {
         let (mut kern, _) = build_inspecting_test()?;
@@ -425,6 +584,80 @@ mod ipld {
         Ok(())
     }
 
+    #[test]
+    fn read_charges_per_copied_byte() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let block = vec![0xab; 1 << 20];
+        let id = kern.block_create(IPLD_RAW, &block)?;
+
+        let price_list = kern.call_manager.machine.context().price_list;
+        let read_price = price_list.on_block_read(block.len()).total();
+        assert!(read_price > price_list.on_block_read(block.len() / 2).total());
+
+        // Every read of the whole block pays for copying the whole block into the buffer.
+        let mut buf = vec![0u8; block.len()];
+        let before = kern.call_manager.gas_tracker.gas_used();
+        kern.block_read(id, 0, &mut buf)?;
+        let once = kern.call_manager.gas_tracker.gas_used() - before;
+        kern.block_read(id, 0, &mut buf)?;
+        let twice = kern.call_manager.gas_tracker.gas_used() - before;
+        assert_eq!(once, read_price);
+        assert_eq!(twice, read_price + read_price);
+
+        // Only the bytes actually copied are charged, not the size of the block or the buffer.
+        let before = kern.call_manager.gas_tracker.gas_used();
+        kern.block_read(id, block.len() as u32 - 10, &mut buf)?;
+        assert_eq!(
+            kern.call_manager.gas_tracker.gas_used() - before,
+            price_list.on_block_read(10).total()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_at_offsets() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let block = "hello world!".as_bytes();
+        let id = kern.block_create(IPLD_RAW, block)?;
+        let price_list = kern.call_manager.machine.context().price_list;
+
+        // (offset, expected bytes copied into a 5 byte buffer)
+        for (offset, copied) in [
+            (0, 5),   // start
+            (6, 5),   // middle
+            (10, 2),  // straddling the end: truncated
+            (12, 0),  // end
+            (100, 0), // past the end: not an error
+        ] {
+            let mut buf = [0u8; 5];
+            let before = kern.call_manager.gas_tracker.gas_used();
+            let remaining = kern.block_read(id, offset, &mut buf)?;
+
+            assert_eq!(
+                remaining,
+                block.len() as i32 - (offset as i32 + buf.len() as i32),
+                "offset {offset}"
+            );
+            let start = (offset as usize).min(block.len());
+            assert_eq!(
+                &buf[..copied],
+                &block[start..start + copied],
+                "offset {offset}"
+            );
+            assert!(buf[copied..].iter().all(|&b| b == 0), "offset {offset}");
+            assert_eq!(
+                kern.call_manager.gas_tracker.gas_used() - before,
+                price_list.on_block_read(copied).total(),
+                "offset {offset}"
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn stat() -> anyhow::Result<()> {
         let (mut kern, _) = build_inspecting_test()?;
@@ -477,6 +710,99 @@ mod ipld {
 
         Ok(())
     }
+
+    #[test]
+    fn stat_links() -> anyhow::Result<()> {
+        let (mut kern, test_data) = build_inspecting_test()?;
+
+        let child_id = kern.block_create(IPLD_RAW, "foo".as_bytes())?;
+        let child = kern.block_link(child_id, Code::Blake2b256.into(), 32)?;
+        let parent = fvm_ipld_encoding::to_vec(&[child, child])?;
+        let parent_id = kern.block_create(DAG_CBOR, &parent)?;
+
+        // reset gas calls
+        test_data.borrow_mut().charge_gas_calls = 0;
+        let gas_before = kern.gas_available();
+
+        let (stat, links) = kern.block_stat_links(parent_id)?;
+        assert_eq!(stat.codec, DAG_CBOR);
+        assert_eq!(stat.size, parent.len() as u32);
+        assert_eq!(links, 2);
+
+        // The links were scanned when the block was created, so this costs the same as a stat.
+        assert_eq!(
+            test_data.borrow().charge_gas_calls,
+            1,
+            "charge_gas should be called exactly once in block_stat_links"
+        );
+        assert_eq!(
+            gas_before - kern.gas_available(),
+            kern.machine().context().price_list.on_block_stat().total(),
+        );
+
+        // Only DAG-CBOR blocks have links.
+        let (stat, links) = kern.block_stat_links(child_id)?;
+        assert_eq!(stat.codec, IPLD_RAW);
+        assert_eq!(links, 0);
+        let raw_id = kern.block_create(IPLD_RAW, &parent)?;
+        assert_eq!(kern.block_stat_links(raw_id)?.1, 0);
+
+        expect_syscall_err!(InvalidHandle, kern.block_stat_links(0));
+        expect_syscall_err!(InvalidHandle, kern.block_stat_links(0xFF));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stat_by_cid() -> anyhow::Result<()> {
+        let (mut kern, test_data) = build_inspecting_test()?;
+
+        let block = "foo".as_bytes();
+
+        let id = kern.block_create(IPLD_RAW, block)?;
+        let cid = kern.block_link(id, Code::Blake2b256.into(), 32)?;
+
+        // reset gas calls
+        test_data.borrow_mut().charge_gas_calls = 0;
+        let gas_before = kern.gas_available();
+
+        let stat = kern.block_stat_by_cid(&cid)?;
+
+        assert_eq!(stat.codec, IPLD_RAW);
+        assert_eq!(stat.size, 3);
+
+        // assert gas
+        assert_eq!(
+            test_data.borrow().charge_gas_calls,
+            1,
+            "charge_gas should be called exactly once in block_stat_by_cid"
+        );
+        assert_eq!(
+            gas_before - kern.gas_available(),
+            kern.machine()
+                .context()
+                .price_list
+                .on_block_stat_by_cid()
+                .total(),
+            "gas use of 'stat'ing a block by CID does not match price list"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stat_by_cid_unreachable() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        // Put a block directly into the blockstore, bypassing the kernel. It must not be visible as
+        // it isn't in the reachable set.
+        let block = "foo".as_bytes();
+        let cid = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(block));
+        kern.machine().blockstore().put_keyed(&cid, block)?;
+
+        expect_syscall_err!(NotFound, kern.block_stat_by_cid(&cid));
+
+        Ok(())
+    }
 }
 
 mod gas {
@@ -573,3 +899,363 @@ mod gas {
         Ok(())
     }
 }
+
+#[cfg(feature = "verify-signature")]
+mod crypto {
+    use fvm::gas::{Gas, GasTracker, price_list_by_network_version};
+    use fvm::kernel::CryptoOps;
+    use fvm_shared::address::Address;
+    use fvm_shared::crypto::signature::{
+        BLS_PUB_LEN, BLS_SIG_LEN, SECP_PUB_LEN, SECP_SIG_LEN, SignatureType,
+    };
+    use fvm_shared::version::NetworkVersion;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const HASH: &str = "18c547e4f7b0f325ad1e56f57e26c745b09a3e503d86e00e5255ff7f715d3d1c";
+    const R: &str = "73b1693892219d736caba55bdb67216e485557ea6b6af75f37096c9aa6a5a75f";
+    const HIGH_S: &str = "eeb940b1d03b21e36b0e47e79769f095fe2ab855bd91e3a38756b7d75a9c4549";
+    /// `n - HIGH_S`, where `n` is the order of the secp256k1 curve.
+    const LOW_S: &str = "1146bf4e2fc4de1c94f1b81868960f68bc842490f1b6bc98387ba6b57599fbf8";
+
+    fn hex32(s: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        out
+    }
+
+    fn signature(s: &str, rec_id: u8) -> [u8; SECP_SIG_LEN] {
+        let mut sig = [0u8; SECP_SIG_LEN];
+        sig[..32].copy_from_slice(&hex32(R));
+        sig[32..64].copy_from_slice(&hex32(s));
+        sig[64] = rec_id;
+        sig
+    }
+
+    #[test]
+    fn high_s_signatures() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+
+        let hash = hex32(HASH);
+        let high_s = signature(HIGH_S, 1);
+        // The malleable dual: negate s and flip the recovery ID.
+        let low_s = signature(LOW_S, 0);
+
+        // Raw recovery accepts both signatures, recovering the same key.
+        let key = kern.recover_secp_public_key(&hash, &high_s)?;
+        assert_eq!(kern.recover_secp_public_key(&hash, &low_s)?, key);
+        let signer = Address::new_secp256k1(&key)?;
+
+        // Before nv29, verification accepts both.
+        for sig in [&high_s, &low_s] {
+            assert!(kern.verify_signature_hashed(SignatureType::Secp256k1, sig, &signer, &hash)?);
+        }
+
+        // From nv29, verification rejects high-s signatures, but recovery doesn't.
+        kern.call_manager.machine.ctx.network.network_version = NetworkVersion::V29;
+        assert!(!kern.verify_signature_hashed(
+            SignatureType::Secp256k1,
+            &high_s,
+            &signer,
+            &hash
+        )?);
+        assert!(kern.verify_signature_hashed(SignatureType::Secp256k1, &low_s, &signer, &hash)?);
+        assert_eq!(kern.recover_secp_public_key(&hash, &high_s)?, key);
+
+        Ok(())
+    }
+
+    #[test]
+    fn id_address_signer() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        // Signatures are only verified against key addresses: the kernel doesn't resolve ID
+        // addresses to keys.
+        let sig = signature(LOW_S, 0);
+        let signer = Address::new_id(1000);
+        expect_syscall_err!(
+            IllegalArgument,
+            kern.verify_signature(SignatureType::Secp256k1, &sig, &signer, b"plaintext")
+        );
+        expect_syscall_err!(
+            IllegalArgument,
+            kern.verify_signature_hashed(SignatureType::Secp256k1, &sig, &signer, &hex32(HASH))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn hashed_rejects_bls() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        // BLS signatures are over the message itself, so they can't be verified against a digest,
+        // whatever the signer.
+        let sig = [0u8; BLS_SIG_LEN];
+        let bls_signer = Address::new_bls(&[1; BLS_PUB_LEN])?;
+        let secp_signer = Address::new_secp256k1(&[4; SECP_PUB_LEN])?;
+        for signer in [bls_signer, secp_signer] {
+            expect_syscall_err!(
+                IllegalArgument,
+                kern.verify_signature_hashed(SignatureType::BLS, &sig, &signer, &hex32(HASH))
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn charges_before_verifying() -> anyhow::Result<()> {
+        let plaintext = b"plaintext";
+        let hash = hex32(HASH);
+        let sig = signature(LOW_S, 0);
+        let price_list = price_list_by_network_version(STUB_NETWORK_VER);
+        let charge = price_list
+            .on_verify_signature(SignatureType::Secp256k1, plaintext.len())
+            .total();
+        let hashed_charge = price_list
+            .on_verify_signature(SignatureType::Secp256k1, 0)
+            .total();
+
+        // The whole cost (hashing and verification) is charged before looking at the signer or
+        // doing any work, so even invalid signers run out of gas (using up the gas limit).
+        let key_signer = Address::new_secp256k1(&[4; SECP_PUB_LEN])?;
+        for signer in [key_signer, Address::new_id(1000)] {
+            let limit = charge - Gas::new(1);
+            let (kern, _) = build_inspecting_gas_test(GasTracker::new(limit, Gas::zero(), false))?;
+            expect_out_of_gas!(kern.verify_signature(
+                SignatureType::Secp256k1,
+                &sig,
+                &signer,
+                plaintext
+            ));
+            assert_eq!(kern.call_manager.gas_tracker.gas_used(), limit);
+
+            let limit = hashed_charge - Gas::new(1);
+            let (kern, _) = build_inspecting_gas_test(GasTracker::new(limit, Gas::zero(), false))?;
+            expect_out_of_gas!(kern.verify_signature_hashed(
+                SignatureType::Secp256k1,
+                &sig,
+                &signer,
+                &hash
+            ));
+            assert_eq!(kern.call_manager.gas_tracker.gas_used(), limit);
+        }
+
+        // With exactly enough gas, the signature is verified (and doesn't match the signer).
+        let gas_tracker = GasTracker::new(charge, Gas::zero(), false);
+        let (kern, _) = build_inspecting_gas_test(gas_tracker)?;
+        assert!(!kern.verify_signature(SignatureType::Secp256k1, &sig, &key_signer, plaintext)?);
+        assert_eq!(kern.call_manager.gas_tracker.gas_used(), charge);
+
+        Ok(())
+    }
+}
+
+mod bls {
+    use fvm::call_manager::CallManager;
+    use fvm::gas::Gas;
+    use fvm::kernel::CryptoOps;
+    use fvm_shared::crypto::signature::{BLS_PUB_LEN, BLS_SIG_LEN};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const PUB_KEYS: [[u8; BLS_PUB_LEN]; 3] = [
+        [
+            173, 154, 145, 188, 114, 85, 101, 250, 129, 225, 3, 205, 128, 61, 161, 185, 210, 18,
+            147, 84, 160, 15, 233, 114, 178, 113, 115, 142, 4, 221, 81, 215, 188, 151, 11, 87, 4,
+            110, 23, 219, 125, 143, 122, 176, 207, 123, 66, 146,
+        ],
+        [
+            166, 188, 253, 186, 140, 16, 193, 46, 218, 161, 3, 28, 70, 112, 192, 253, 195, 179,
+            167, 181, 197, 130, 19, 216, 51, 188, 86, 179, 88, 40, 161, 215, 116, 189, 157, 29, 27,
+            61, 144, 111, 195, 221, 100, 87, 107, 239, 25, 189,
+        ],
+        [
+            167, 241, 45, 72, 153, 172, 192, 10, 118, 144, 223, 120, 38, 106, 140, 48, 14, 57, 104,
+            0, 67, 174, 148, 177, 204, 138, 35, 201, 92, 108, 208, 60, 109, 226, 9, 169, 2, 168,
+            27, 73, 138, 221, 77, 74, 103, 186, 117, 225,
+        ],
+    ];
+
+    /// The aggregate signature of the bytes `0..30`, split into three plaintexts of 10 bytes, one
+    /// for each of `PUB_KEYS`.
+    const SIG: [u8; BLS_SIG_LEN] = [
+        164, 39, 224, 212, 184, 193, 176, 129, 10, 127, 96, 36, 101, 63, 133, 5, 223, 148, 253, 34,
+        139, 109, 244, 229, 242, 247, 83, 84, 6, 96, 9, 163, 87, 252, 234, 52, 105, 48, 87, 38,
+        154, 48, 150, 34, 165, 53, 42, 108, 7, 106, 225, 93, 147, 11, 156, 109, 108, 226, 27, 126,
+        213, 199, 148, 3, 77, 102, 248, 239, 41, 108, 177, 159, 14, 50, 153, 49, 47, 22, 250, 113,
+        252, 170, 223, 150, 51, 97, 180, 19, 226, 171, 246, 197, 50, 92, 47, 182,
+    ];
+
+    /// A valid G2 point, but not the signature of the plaintexts.
+    const WRONG_SIG: [u8; BLS_SIG_LEN] = [
+        146, 72, 239, 152, 88, 59, 69, 25, 119, 24, 54, 37, 105, 220, 134, 131, 46, 186, 98, 35,
+        46, 160, 88, 225, 195, 50, 135, 39, 24, 178, 11, 241, 46, 166, 214, 198, 67, 200, 61, 183,
+        51, 108, 69, 115, 184, 150, 124, 32, 21, 192, 204, 174, 253, 151, 49, 111, 246, 60, 52,
+        147, 90, 133, 90, 53, 9, 9, 78, 187, 127, 26, 207, 47, 240, 248, 109, 45, 104, 83, 99, 45,
+        35, 78, 18, 219, 13, 50, 145, 26, 23, 6, 103, 32, 248, 188, 235, 111,
+    ];
+
+    const PLAINTEXTS_LEN: usize = 30;
+
+    /// Verifies `sig` against `PUB_KEYS`, returning the result and the gas charged.
+    fn verify(kern: &TestingKernel, sig: &[u8; BLS_SIG_LEN]) -> anyhow::Result<(bool, Gas)> {
+        let plaintexts: Vec<u8> = (0..PLAINTEXTS_LEN as u8).collect();
+        let before = kern.call_manager.gas_tracker.gas_used();
+        let valid = kern.verify_bls_aggregate(sig, &PUB_KEYS, &plaintexts, &[10; 3])?;
+        Ok((valid, kern.call_manager.gas_tracker.gas_used() - before))
+    }
+
+    /// The charge for verifying against `PUB_KEYS`, `new_keys` of which weren't validated yet.
+    fn charge(kern: &TestingKernel, new_keys: usize) -> Gas {
+        kern.call_manager
+            .price_list()
+            .on_verify_aggregate_signature(PUB_KEYS.len(), new_keys, PLAINTEXTS_LEN)
+            .total()
+    }
+
+    #[test]
+    fn caches_keys_across_syscalls() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+
+        assert_eq!(verify(&kern, &SIG)?, (true, charge(&kern, 3)));
+        assert_eq!(kern.call_manager.bls_pub_keys.len(), 3);
+
+        // Later verifications in the same message don't validate the keys again, whether or not
+        // the signature is valid.
+        assert_eq!(verify(&kern, &WRONG_SIG)?, (false, charge(&kern, 0)));
+        assert_eq!(verify(&kern, &SIG)?, (true, charge(&kern, 0)));
+        assert_eq!(kern.call_manager.bls_pub_keys.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn not_carried_over_between_messages() -> anyhow::Result<()> {
+        // Each message gets a new call manager, starting with an empty cache.
+        for _ in 0..2 {
+            let (kern, _) = build_inspecting_test()?;
+            assert!(kern.call_manager.bls_pub_keys.is_empty());
+            assert_eq!(verify(&kern, &SIG)?, (true, charge(&kern, 3)));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_keys_are_charged_but_not_cached() -> anyhow::Result<()> {
+        let (kern, _) = build_inspecting_test()?;
+        let plaintexts: Vec<u8> = (0..PLAINTEXTS_LEN as u8).collect();
+        let pub_keys = [PUB_KEYS[0], [0; BLS_PUB_LEN], PUB_KEYS[2]];
+
+        let before = kern.call_manager.gas_tracker.gas_used();
+        assert!(!kern.verify_bls_aggregate(&SIG, &pub_keys, &plaintexts, &[10; 3])?);
+        assert_eq!(
+            kern.call_manager.gas_tracker.gas_used() - before,
+            charge(&kern, 3)
+        );
+
+        // Validation stops at the first invalid key.
+        assert_eq!(kern.call_manager.bls_pub_keys.len(), 1);
+        assert_eq!(verify(&kern, &SIG)?, (true, charge(&kern, 2)));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "nv29-dev")]
+    #[test]
+    fn cache_hits_are_cheaper() -> anyhow::Result<()> {
+        use fvm::gas::price_list_by_network_version;
+        use fvm_shared::version::NetworkVersion;
+
+        let (mut kern, _) = build_inspecting_test()?;
+        kern.call_manager.machine.ctx.price_list =
+            price_list_by_network_version(NetworkVersion::V29);
+
+        let (_, cold) = verify(&kern, &SIG)?;
+        let (_, warm) = verify(&kern, &SIG)?;
+        assert!(warm < cold);
+        assert_eq!(cold, charge(&kern, 3));
+        assert_eq!(warm, charge(&kern, 0));
+
+        Ok(())
+    }
+}
+
+mod proofs {
+    use fvm::gas::{Gas, GasTracker};
+    use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel};
+    use fvm::machine::Machine;
+    use fvm_shared::EMPTY_ARR_CID;
+    use fvm_shared::sector::{RegisteredUpdateProof, ReplicaUpdateInfo};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn proof_gas_limit() -> anyhow::Result<()> {
+        // The CIDs aren't commitments, so verification fails right after charging for it.
+        let replica = ReplicaUpdateInfo {
+            update_proof_type: RegisteredUpdateProof::StackedDRG32GiBV1,
+            old_sealed_cid: EMPTY_ARR_CID,
+            new_sealed_cid: EMPTY_ARR_CID,
+            new_unsealed_cid: EMPTY_ARR_CID,
+            proof: vec![],
+        };
+
+        let (mut kern, _) = build_inspecting_test()?;
+        let charge = kern
+            .call_manager
+            .machine
+            .context()
+            .price_list
+            .on_verify_replica_update(&replica)
+            .total();
+        kern.call_manager.gas_tracker =
+            GasTracker::new(Gas::new(10_000_000_000), Gas::zero(), false)
+                .with_proof_gas_limit(Some(charge * 2u32));
+        let kern = DefaultFilecoinKernel(kern);
+        let gas_tracker = &kern.0.call_manager.gas_tracker;
+
+        for _ in 0..2 {
+            expect_syscall_err!(IllegalArgument, kern.verify_replica_update(&replica));
+        }
+        assert_eq!(gas_tracker.proof_gas_used(), charge * 2u32);
+        assert_eq!(gas_tracker.gas_used(), charge * 2u32);
+
+        // The third verification would exceed the proof gas limit, so it fails up-front, without
+        // charging any gas, even though there's plenty left.
+        expect_syscall_err!(LimitExceeded, kern.verify_replica_update(&replica));
+        assert_eq!(gas_tracker.proof_gas_used(), charge * 2u32);
+        assert_eq!(gas_tracker.gas_used(), charge * 2u32);
+        assert!(gas_tracker.gas_available() > charge);
+
+        Ok(())
+    }
+}
+
+mod randomness {
+    use fvm::kernel::RandomnessOps;
+
+    use super::*;
+
+    #[test]
+    fn rejects_future_epochs() -> anyhow::Result<()> {
+        let (mut kern, _) = build_inspecting_test()?;
+        kern.call_manager.machine.ctx.epoch = 10;
+
+        // The externs would panic if called: these epochs are rejected before reaching them, as
+        // illegal arguments rather than fatal errors.
+        for epoch in [11, i64::MAX] {
+            expect_syscall_err!(IllegalArgument, kern.get_randomness_from_tickets(epoch));
+            expect_syscall_err!(IllegalArgument, kern.get_randomness_from_beacon(epoch));
+        }
+
+        Ok(())
+    }
+}
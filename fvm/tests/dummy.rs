@@ -10,6 +10,7 @@ use fvm::call_manager::{Backtrace, CallManager, Entrypoint, FinishRet, Invocatio
 use fvm::engine::Engine;
 use fvm::externs::{Chain, Consensus, Externs, Rand};
 use fvm::gas::{Gas, GasCharge, GasTimer, GasTracker};
+use fvm::kernel::BlsPubKeyCache;
 use fvm::machine::limiter::MemoryLimiter;
 use fvm::machine::{Machine, MachineContext, Manifest, NetworkConfig};
 use fvm::state_tree::StateTree;
@@ -59,6 +60,14 @@ impl Consensus for DummyExterns {
         // consensus is always valid for tests :)
         anyhow::Result::Ok((None, 0))
     }
+
+    fn get_miner_power(
+        &self,
+        _miner: fvm_shared::ActorID,
+        _epoch: fvm_shared::clock::ChainEpoch,
+    ) -> anyhow::Result<fvm_shared::consensus::MinerPower> {
+        Ok(Default::default())
+    }
 }
 
 impl Chain for DummyExterns {
@@ -193,6 +202,8 @@ pub struct DummyCallManager {
     pub nonce: u64,
     pub test_data: Rc<RefCell<TestData>>,
     pub ipld_traces: Vec<(IpldOperation, Cid, usize)>,
+    pub bls_pub_keys: BlsPubKeyCache,
+    pub block_bytes: u64,
     limits: DummyLimiter,
 }
 
@@ -209,9 +220,11 @@ impl DummyCallManager {
             charge_gas_calls: 0,
         }));
         let cell_ref = rc.clone();
+        let machine = DummyMachine::new_stub().unwrap();
+        let bls_pub_keys = bls_pub_keys(&machine);
         (
             Self {
-                machine: DummyMachine::new_stub().unwrap(),
+                machine,
                 gas_tracker: GasTracker::new(BLOCK_GAS_LIMIT, Gas::new(0), false),
                 origin: 0,
                 nonce: 0,
@@ -219,6 +232,8 @@ impl DummyCallManager {
                 limits: DummyLimiter::default(),
                 gas_premium: TokenAmount::zero(),
                 ipld_traces: vec![],
+                bls_pub_keys,
+                block_bytes: 0,
             },
             cell_ref,
         )
@@ -229,9 +244,11 @@ impl DummyCallManager {
             charge_gas_calls: 0,
         }));
         let cell_ref = rc.clone();
+        let machine = DummyMachine::new_stub().unwrap();
+        let bls_pub_keys = bls_pub_keys(&machine);
         (
             Self {
-                machine: DummyMachine::new_stub().unwrap(),
+                machine,
                 gas_tracker,
                 origin: 0,
                 nonce: 0,
@@ -239,12 +256,18 @@ impl DummyCallManager {
                 limits: DummyLimiter::default(),
                 gas_premium: TokenAmount::zero(),
                 ipld_traces: vec![],
+                bls_pub_keys,
+                block_bytes: 0,
             },
             cell_ref,
         )
     }
 }
 
+fn bls_pub_keys(machine: &DummyMachine) -> BlsPubKeyCache {
+    BlsPubKeyCache::new(machine.context().price_list.bls_pub_key_cache_size())
+}
+
 impl CallManager for DummyCallManager {
     type Machine = DummyMachine;
 
@@ -263,6 +286,7 @@ impl CallManager for DummyCallManager {
             charge_gas_calls: 0,
         }));
         let limits = machine.new_limiter();
+        let bls_pub_keys = bls_pub_keys(&machine);
         Self {
             machine,
             gas_tracker: GasTracker::new(BLOCK_GAS_LIMIT, Gas::new(0), false),
@@ -272,6 +296,8 @@ impl CallManager for DummyCallManager {
             test_data: rc,
             limits,
             ipld_traces: vec![],
+            bls_pub_keys,
+            block_bytes: 0,
         }
     }
 
@@ -301,13 +327,13 @@ impl CallManager for DummyCallManager {
         (
             Ok(FinishRet {
                 gas_used: 0,
-                backtrace: Backtrace {
-                    frames: Vec::new(),
-                    cause: None,
-                },
+                proof_gas_used: 0,
+                backtrace: Backtrace::default(),
                 exec_trace: Vec::new(),
                 events: Vec::new(),
                 events_root: None,
+                created_actors: Vec::new(),
+                deleted_actors: Vec::new(),
             }),
             self.machine,
         )
@@ -367,6 +393,10 @@ impl CallManager for DummyCallManager {
         todo!()
     }
 
+    fn bls_pub_keys(&self) -> &BlsPubKeyCache {
+        &self.bls_pub_keys
+    }
+
     fn limiter_mut(&mut self) -> &mut <Self::Machine as Machine>::Limiter {
         &mut self.limits
     }
@@ -413,4 +443,17 @@ impl CallManager for DummyCallManager {
     fn trace_ipld(&mut self, op: IpldOperation, cid: Cid, size: usize) {
         self.ipld_traces.push((op, cid, size));
     }
+
+    fn trace_set_root(&mut self, _id: ActorID, _root: Cid) {}
+
+    fn record_block_create(&mut self, size: usize) -> kernel::Result<()> {
+        let block_bytes = self.block_bytes + size as u64;
+        if let Some(max) = self.machine.context().max_block_bytes_per_message
+            && block_bytes > max
+        {
+            return Err(fvm::syscall_error!(LimitExceeded; "too many block bytes").into());
+        }
+        self.block_bytes = block_bytes;
+        Ok(())
+    }
 }
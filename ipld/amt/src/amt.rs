@@ -97,11 +97,11 @@
 
 use anyhow::anyhow;
 use cid::Cid;
-use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::CborStore;
+use fvm_ipld_blockstore::{Block, Blockstore};
 use fvm_ipld_encoding::de::DeserializeOwned;
 use fvm_ipld_encoding::ser::Serialize;
 use fvm_ipld_encoding::serde::Deserialize;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR, to_vec};
 use itertools::sorted;
 use multihash_codetable::Code;
 
@@ -418,12 +418,33 @@ where
         if let Some(cid) = self.flushed_cid {
             return Ok(cid);
         }
-        self.root.node.flush(&self.block_store)?;
-        let cid = self.block_store.put_cbor(&self.root, Code::Blake2b256)?;
+        let mut blocks = Vec::new();
+        let cid = match self.write_flushed(&mut blocks) {
+            Ok(cid) => cid,
+            Err(e) => {
+                // Nothing may link to blocks that weren't written.
+                self.root
+                    .node
+                    .unflush(&blocks.into_iter().map(|(cid, _)| cid).collect());
+                return Err(e);
+            }
+        };
         self.flushed_cid = Some(cid);
         Ok(cid)
     }
 
+    /// Flushes the root, writing the modified nodes (children before parents) and the root in a
+    /// single batch. The flushed nodes are collected in `blocks`, even on failure.
+    fn write_flushed(&mut self, blocks: &mut Vec<(Cid, Vec<u8>)>) -> Result<Cid, Error> {
+        self.root.node.flush(blocks)?;
+        let root = Block::new(DAG_CBOR, to_vec(&self.root)?);
+        let cid = root.cid(Code::Blake2b256);
+        blocks.push((cid, root.data));
+        self.block_store
+            .put_many_keyed(blocks.iter().map(|(cid, data)| (*cid, data)))?;
+        Ok(cid)
+    }
+
     /// Iterates over each value in the Amt and runs a function on the values.
     ///
     /// The index in the amt is a `u64` and the value is the generic parameter `V` as defined
@@ -2,12 +2,13 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
+use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
 
 use anyhow::anyhow;
 use cid::Cid;
-use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{BytesSer, CborStore, strict_bytes};
+use fvm_ipld_blockstore::{Block, Blockstore};
+use fvm_ipld_encoding::{BytesSer, CborStore, DAG_CBOR, strict_bytes, to_vec};
 use multihash_codetable::Code;
 use once_cell::unsync::OnceCell;
 use serde::de::{self, DeserializeOwned};
@@ -180,17 +181,20 @@ where
         }
     }
 
-    /// Flushes cache for node, replacing any cached values with a Cid variant
-    pub(super) fn flush<DB: Blockstore>(&mut self, bs: &DB) -> Result<(), Error> {
+    /// Flushes cache for node, replacing any cached values with a Cid variant, and collects the
+    /// blocks of the flushed nodes (children before parents) to be written in a single batch.
+    pub(super) fn flush(&mut self, blocks: &mut Vec<(Cid, Vec<u8>)>) -> Result<(), Error> {
         if let Node::Link { links } = self {
             for link in links.iter_mut().flatten() {
                 // links should only be flushed if the bitmap is set.
                 if let Link::Dirty(n) = link {
                     // flush sub node to clear caches
-                    n.flush(bs)?;
+                    n.flush(blocks)?;
 
-                    // Puts node in blockstore and and retrieves it's CID
-                    let cid = bs.put_cbor(n, Code::Blake2b256)?;
+                    // Encodes the node and computes it's CID
+                    let block = Block::new(DAG_CBOR, to_vec(n)?);
+                    let cid = block.cid(Code::Blake2b256);
+                    blocks.push((cid, block.data));
 
                     // Replace the data with some arbitrary node to move without requiring clone
                     let existing = std::mem::replace(n, Box::new(Node::empty()));
@@ -205,6 +209,22 @@ where
         Ok(())
     }
 
+    /// Undoes a [`flush`](Self::flush) whose blocks weren't written, turning the links to the
+    /// given (unwritten) blocks back into dirty nodes, so they're written by the next flush.
+    pub(super) fn unflush(&mut self, unwritten: &HashSet<Cid>) {
+        if let Node::Link { links } = self {
+            for link in links.iter_mut().flatten() {
+                if let Link::Cid { cid, cache } = link
+                    && unwritten.contains(cid)
+                    && let Some(mut node) = cache.take()
+                {
+                    node.unflush(unwritten);
+                    *link = Link::Dirty(node);
+                }
+            }
+        }
+    }
+
     /// Returns true if there is only a link in the first index of the values.
     /// This node can be collapsed into the parent node.
     pub(super) fn can_collapse(&self) -> bool {
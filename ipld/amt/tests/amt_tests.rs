@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use fvm_ipld_amt::{Amt, Amtv0, Error, MAX_INDEX};
-use fvm_ipld_blockstore::tracking::{BSStats, TrackingBlockstore};
+use fvm_ipld_blockstore::tracking::{BSStats, BatchCountingBlockstore, TrackingBlockstore};
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::BytesDe;
 use fvm_ipld_encoding::de::DeserializeOwned;
@@ -706,3 +706,43 @@ fn new_from_iter() {
     let expected: Vec<_> = data.into_iter().enumerate().collect();
     assert_eq!(expected, restored);
 }
+
+#[test]
+fn flush_writes_one_batch() {
+    let store = BatchCountingBlockstore::<MemoryBlockstore>::default();
+    let mut a = Amt::new(&store);
+    for i in 0..1000 {
+        a.set(i, tbytes(b"value")).unwrap();
+    }
+    let cid = a.flush().unwrap();
+    assert_eq!((store.puts.get(), store.batches.get()), (0, 1));
+
+    // Only the modified nodes are written, again in one batch.
+    a.set(1000, tbytes(b"value")).unwrap();
+    let cid2 = a.flush().unwrap();
+    assert_ne!(cid, cid2);
+    assert_eq!((store.puts.get(), store.batches.get()), (0, 2));
+
+    let reloaded: Amt<BytesDe, _> = Amt::load(&cid2, &store).unwrap();
+    assert_eq!(reloaded.count(), 1001);
+    assert_get(&reloaded, 1000, &tbytes(b"value"));
+}
+
+#[test]
+fn flush_retries_failed_batch() {
+    let store = BatchCountingBlockstore::<MemoryBlockstore>::default();
+    let mut a = Amt::new(&store);
+    for i in 0..1000 {
+        a.set(i, tbytes(b"value")).unwrap();
+    }
+    store.failing_batches.set(1);
+    a.flush().unwrap_err();
+
+    // The failed flush didn't leave links to unwritten nodes behind.
+    let cid = a.flush().unwrap();
+    let reloaded: Amt<BytesDe, _> = Amt::load(&cid, &store).unwrap();
+    assert_eq!(reloaded.count(), 1000);
+    for i in 0..1000 {
+        assert_get(&reloaded, i, &tbytes(b"value"));
+    }
+}
@@ -1,5 +1,6 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use std::io;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -34,6 +35,15 @@ pub trait Blockstore {
         Ok(self.get(k)?.is_some())
     }
 
+    /// Returns the size of the specified block in bytes, or `None` if the blockstore doesn't have
+    /// the block.
+    ///
+    /// By default, this defers to get. Blockstores that can look up the size without loading the
+    /// block should override this method.
+    fn get_size(&self, k: &Cid) -> Result<Option<usize>> {
+        Ok(self.get(k)?.map(|b| b.len()))
+    }
+
     /// Puts the block into the blockstore, computing the hash with the specified multicodec.
     ///
     /// By default, this defers to put.
@@ -82,6 +92,26 @@ pub trait Blockstore {
         }
         Ok(())
     }
+
+    /// Returns a writer for streaming a block into the blockstore, or `None` if the blockstore
+    /// can only put blocks as contiguous slices.
+    ///
+    /// By default, this returns `None`. Blockstores that can persist a block incrementally (and
+    /// key it once it has been fully written) should override this method.
+    fn block_writer(&self) -> Option<Box<dyn BlockWriter + '_>> {
+        None
+    }
+}
+
+/// A writer for a block whose CID is only known once the block has been fully written. See
+/// [`Blockstore::block_writer`].
+///
+/// Dropping the writer without committing it discards the written data.
+pub trait BlockWriter: io::Write {
+    /// Stores the written data as the block with the specified CID.
+    ///
+    /// The caller is responsible for computing the CID from the written data.
+    fn commit(self: Box<Self>, k: &Cid) -> Result<()>;
 }
 
 pub trait Buffered: Blockstore {
@@ -105,6 +135,10 @@ macro_rules! impl_blockstore {
                     (**self).has(k)
                 }
 
+                fn get_size(&self, k: &Cid) -> Result<Option<usize>> {
+                    (**self).get_size(k)
+                }
+
                 fn put<D>(&self, mh_code: multihash_codetable::Code, block: &Block<D>) -> Result<Cid>
                 where
                     Self: Sized,
@@ -130,6 +164,10 @@ macro_rules! impl_blockstore {
                 {
                     (**self).put_many_keyed(blocks)
                 }
+
+                fn block_writer(&self) -> Option<Box<dyn BlockWriter + '_>> {
+                    (**self).block_writer()
+                }
             }
         )+
     }
@@ -2,11 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io;
 
 use anyhow::Result;
 use cid::Cid;
 
-use super::Blockstore;
+use super::{BlockWriter, Blockstore};
 
 #[derive(Debug, Default, Clone)]
 pub struct MemoryBlockstore {
@@ -33,8 +34,43 @@ impl Blockstore for MemoryBlockstore {
         Ok(self.blocks.borrow().get(k).cloned())
     }
 
+    fn get_size(&self, k: &Cid) -> Result<Option<usize>> {
+        Ok(self.blocks.borrow().get(k).map(Vec::len))
+    }
+
     fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
         self.blocks.borrow_mut().insert(*k, block.into());
         Ok(())
     }
+
+    fn block_writer(&self) -> Option<Box<dyn BlockWriter + '_>> {
+        Some(Box::new(MemoryBlockWriter {
+            store: self,
+            data: Vec::new(),
+        }))
+    }
+}
+
+/// Writes a block directly into the buffer it'll be stored in, instead of copying it on put.
+struct MemoryBlockWriter<'a> {
+    store: &'a MemoryBlockstore,
+    data: Vec<u8>,
+}
+
+impl io::Write for MemoryBlockWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl BlockWriter for MemoryBlockWriter<'_> {
+    fn commit(self: Box<Self>, k: &Cid) -> Result<()> {
+        self.store.blocks.borrow_mut().insert(*k, self.data);
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 //#![cfg(feature = "tracking")]
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use anyhow::Result;
 use cid::Cid;
@@ -62,6 +62,11 @@ where
         self.base.has(cid)
     }
 
+    fn get_size(&self, cid: &Cid) -> Result<Option<usize>> {
+        self.stats.borrow_mut().r += 1;
+        self.base.get_size(cid)
+    }
+
     fn put<D>(&self, code: Code, block: &Block<D>) -> Result<Cid>
     where
         D: AsRef<[u8]>,
@@ -109,6 +114,66 @@ where
     }
 }
 
+/// Wrapper around `Blockstore` counting the blocks written one by one and the batches of blocks,
+/// and failing batched writes on demand, for verifying how blocks are written.
+/// This struct should only be used for testing.
+#[derive(Debug, Default)]
+pub struct BatchCountingBlockstore<BS> {
+    base: BS,
+    /// Number of blocks written one by one.
+    pub puts: Cell<usize>,
+    /// Number of batches written.
+    pub batches: Cell<usize>,
+    /// Number of upcoming batches to fail, without writing any of their blocks.
+    pub failing_batches: Cell<usize>,
+}
+
+impl<BS> BatchCountingBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    pub fn new(base: BS) -> Self {
+        Self {
+            base,
+            puts: Default::default(),
+            batches: Default::default(),
+            failing_batches: Default::default(),
+        }
+    }
+}
+
+impl<BS> Blockstore for BatchCountingBlockstore<BS>
+where
+    BS: Blockstore,
+{
+    fn get(&self, k: &Cid) -> Result<Option<Vec<u8>>> {
+        self.base.get(k)
+    }
+
+    fn has(&self, k: &Cid) -> Result<bool> {
+        self.base.has(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> Result<()> {
+        self.puts.set(self.puts.get() + 1);
+        self.base.put_keyed(k, block)
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        if let Some(failing) = self.failing_batches.get().checked_sub(1) {
+            self.failing_batches.set(failing);
+            return Err(anyhow::anyhow!("failed to write batch"));
+        }
+        self.batches.set(self.batches.get() + 1);
+        self.base.put_many_keyed(blocks)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
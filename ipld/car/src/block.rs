@@ -25,7 +25,8 @@ impl From<(Cid, Vec<u8>)> for Block {
 }
 
 impl Block {
-    pub(crate) fn validate(&self) -> Result<(), Error> {
+    /// Checks that the block's data matches its CID.
+    pub fn validate(&self) -> Result<(), Error> {
         match self.cid.hash().code() {
             0x0 => {
                 if self.cid.hash().digest() != self.data {
@@ -0,0 +1,122 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Derive macros for `fvm_ipld_encoding`. Use them through `fvm_ipld_encoding::tuple`, not
+//! directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Error, Fields, parse_macro_input, parse_quote,
+};
+
+/// Derives `Deserialize` for a struct encoded as an array of its fields (see `Serialize_tuple`).
+///
+/// Decoding fails unless the array has exactly one element per field, except that trailing fields
+/// marked `#[serde(default)]` may be missing. Structs that must accept (and ignore) elements added
+/// by future versions can opt out of the length check with `#[serde_tuple(optional_trailing)]`.
+///
+/// Field and container `#[serde(...)]` attributes are supported as by `serde_tuple`.
+#[proc_macro_derive(Deserialize_tuple, attributes(serde, serde_tuple))]
+pub fn derive_deserialize_tuple(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_deserialize_tuple(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Returns true if the struct opts into `#[serde_tuple(optional_trailing)]`.
+fn optional_trailing(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut optional_trailing = false;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde_tuple"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("optional_trailing") {
+                optional_trailing = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown serde_tuple attribute"))
+            }
+        })?;
+    }
+    Ok(optional_trailing)
+}
+
+fn is_serde_attr(attr: &&Attribute) -> bool {
+    attr.path().is_ident("serde")
+}
+
+// The struct is decoded through a copy of itself deriving `serde_tuple::Deserialize_tuple`, which
+// handles the serde attributes. Unless the struct opts out, that copy is decoded from a deserializer
+// that rejects any elements it leaves behind.
+fn expand_deserialize_tuple(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let optional_trailing = optional_trailing(&input.attrs)?;
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Deserialize_tuple can only be derived for structs with named fields",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let shadow = format_ident!("__DeserializeTuple{}", ident);
+    let container_attrs = input.attrs.iter().filter(is_serde_attr);
+    let names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let shadow_fields = fields.iter().map(|field| {
+        let attrs = field.attrs.iter().filter(is_serde_attr);
+        let name = &field.ident;
+        let ty = &field.ty;
+        quote!(#(#attrs)* #name: #ty)
+    });
+
+    let generics = &input.generics;
+    let where_clause = &generics.where_clause;
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let mut impl_generics = generics.clone();
+    impl_generics.params.insert(0, parse_quote!('de));
+    impl_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(
+            #shadow #ty_generics: __fvm_ipld_encoding_tuple::serde::Deserialize<'de>
+        ));
+    let (impl_generics, _, impl_where_clause) = impl_generics.split_for_impl();
+
+    let deserializer = if optional_trailing {
+        quote!(deserializer)
+    } else {
+        quote!(__fvm_ipld_encoding_tuple::StrictTuple(deserializer))
+    };
+
+    Ok(quote! {
+        const _: () = {
+            #[derive(serde_tuple::Deserialize_tuple)]
+            #(#container_attrs)*
+            struct #shadow #generics #where_clause {
+                #(#shadow_fields),*
+            }
+
+            #[automatically_derived]
+            impl #impl_generics __fvm_ipld_encoding_tuple::serde::Deserialize<'de>
+                for #ident #ty_generics #impl_where_clause
+            {
+                fn deserialize<__D>(deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+                where
+                    __D: __fvm_ipld_encoding_tuple::serde::Deserializer<'de>,
+                {
+                    let #shadow { #(#names),* } =
+                        __fvm_ipld_encoding_tuple::serde::Deserialize::deserialize(#deserializer)?;
+                    ::core::result::Result::Ok(Self { #(#names),* })
+                }
+            }
+        };
+    })
+}
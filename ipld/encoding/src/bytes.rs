@@ -8,8 +8,9 @@
 ///    part).
 /// 2. Can decode to/from byte arrays.
 pub mod strict_bytes {
-    use std::borrow::Cow;
-    use std::fmt;
+    use alloc::borrow::Cow;
+    use alloc::vec::Vec;
+    use core::fmt;
 
     use serde::de::{Error, Visitor};
     pub use serde::{Deserializer, Serializer};
@@ -166,6 +167,99 @@ pub mod strict_bytes {
     {
         Deserialize::deserialize(deserializer)
     }
+
+    /// Like [`strict_bytes`](self), but for a list of byte strings (e.g., `Vec<Vec<u8>>` or
+    /// `Vec<[u8; N]>`). Each element is encoded as "bytes", and decoding rejects elements that
+    /// aren't "bytes".
+    ///
+    /// ```rust
+    /// use fvm_ipld_encoding::strict_bytes;
+    /// use fvm_ipld_encoding::tuple::*;
+    ///
+    /// #[derive(Serialize_tuple, Deserialize_tuple)]
+    /// struct Params {
+    ///     #[serde(with = "strict_bytes::vec")]
+    ///     multiaddrs: Vec<Vec<u8>>,
+    /// }
+    /// ```
+    pub mod vec {
+        use alloc::vec::Vec;
+        use core::fmt;
+        use core::marker::PhantomData;
+
+        use serde::de::{SeqAccess, Visitor};
+        use serde::{Deserializer, Serializer};
+
+        use super::Deserialize;
+
+        /// Wrapper for serializing a single element.
+        struct ElemSer<'a>(&'a [u8]);
+
+        impl serde::Serialize for ElemSer<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+
+        /// Wrapper for deserializing a single element.
+        struct ElemDe<T>(T);
+
+        impl<'de, T> serde::Deserialize<'de> for ElemDe<T>
+        where
+            T: Deserialize<'de>,
+        {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Deserialize::deserialize(deserializer).map(ElemDe)
+            }
+        }
+
+        pub fn serialize<T, S>(items: &[T], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: AsRef<[u8]>,
+            S: Serializer,
+        {
+            serializer.collect_seq(items.iter().map(|item| ElemSer(item.as_ref())))
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            struct SeqVisitor<T>(PhantomData<T>);
+
+            impl<'de, T> Visitor<'de> for SeqVisitor<T>
+            where
+                T: Deserialize<'de>,
+            {
+                type Value = Vec<T>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("a list of byte arrays")
+                }
+
+                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: SeqAccess<'de>,
+                {
+                    // Don't trust the size hint too far, it comes from the input.
+                    let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1024));
+                    while let Some(ElemDe(item)) = seq.next_element()? {
+                        items.push(item);
+                    }
+                    Ok(items)
+                }
+            }
+
+            deserializer.deserialize_seq(SeqVisitor(PhantomData))
+        }
+    }
 }
 
 pub use strict_bytes::ByteBuf as BytesDe;
@@ -220,4 +314,72 @@ mod test {
         let serialized = to_vec(&[1u8, 2, 3, 4]).unwrap();
         from_slice::<BytesDe>(&serialized).expect_err("can't decode list into bytes");
     }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[serde(transparent)]
+    struct Nested(#[serde(with = "strict_bytes::vec")] Vec<Vec<u8>>);
+
+    #[test]
+    fn nested_round_trip() {
+        let input = Nested(vec![vec![1, 2, 3], vec![], vec![4]]);
+        let serialized = to_vec(&input).unwrap();
+        assert_eq!(serialized, [0x83, 0x43, 1, 2, 3, 0x40, 0x41, 4]);
+        assert_eq!(from_slice::<Nested>(&serialized).unwrap(), input);
+
+        // The same encoding as a manually wrapped list.
+        let wrapped: Vec<BytesDe> = input.0.iter().cloned().map(BytesDe).collect();
+        assert_eq!(to_vec(&wrapped).unwrap(), serialized);
+    }
+
+    #[test]
+    fn nested_fixed_size() {
+        #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+        #[serde(transparent)]
+        struct Fixed(#[serde(with = "strict_bytes::vec")] Vec<[u8; 2]>);
+
+        let input = Fixed(vec![[1, 2], [3, 4]]);
+        let serialized = to_vec(&input).unwrap();
+        assert_eq!(serialized, [0x82, 0x42, 1, 2, 0x42, 3, 4]);
+        assert_eq!(from_slice::<Fixed>(&serialized).unwrap(), input);
+
+        from_slice::<Fixed>(&[0x81, 0x43, 1, 2, 3]).expect_err("wrong length");
+    }
+
+    #[test]
+    fn nested_go_fixture() {
+        // A `[][]byte` of two multiaddrs (`/ip4/1.2.3.4/tcp/1234` and `/dns4/a.b`), as encoded by
+        // go's cbor-gen.
+        let fixture = [
+            0x82, 0x48, 0x04, 0x01, 0x02, 0x03, 0x04, 0x06, 0x04, 0xd2, 0x45, 0x36, 0x03, 0x61,
+            0x2e, 0x62,
+        ];
+        let decoded: Nested = from_slice(&fixture).unwrap();
+        assert_eq!(
+            decoded.0,
+            [
+                vec![0x04, 0x01, 0x02, 0x03, 0x04, 0x06, 0x04, 0xd2],
+                b"\x36\x03a.b".to_vec()
+            ]
+        );
+        assert_eq!(to_vec(&decoded).unwrap(), fixture);
+
+        // An empty list (a nil slice in go is also encoded as an empty list).
+        assert_eq!(from_slice::<Nested>(&[0x80]).unwrap(), Nested(vec![]));
+    }
+
+    #[test]
+    fn nested_rejects_non_bytes() {
+        // A list of lists of integers.
+        let serialized = to_vec(&vec![vec![1u8, 2, 3]]).unwrap();
+        assert_eq!(serialized, [0x81, 0x83, 1, 2, 3]);
+        from_slice::<Nested>(&serialized).expect_err("can't decode list of lists into bytes");
+
+        // A list of strings.
+        let serialized = to_vec(&vec!["abc"]).unwrap();
+        from_slice::<Nested>(&serialized).expect_err("can't decode list of strings into bytes");
+
+        // Bytes, instead of a list of bytes.
+        let serialized = to_vec(&BytesSer(&[1, 2, 3])).unwrap();
+        from_slice::<Nested>(&serialized).expect_err("can't decode bytes into a list");
+    }
 }
@@ -2,9 +2,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::fmt::{Debug, Formatter};
-use std::ops::Deref;
-use std::rc::Rc;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+use core::ops::Deref;
 
 use serde::{Deserialize, Serialize};
 
@@ -92,7 +93,7 @@ impl RawBytes {
 }
 
 impl Debug for RawBytes {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "RawBytes {{ ")?;
         for byte in &self.bytes {
             write!(f, "{:02x}", byte)?;
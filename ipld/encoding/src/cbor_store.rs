@@ -5,6 +5,7 @@ use cid::Cid;
 use fvm_ipld_blockstore::{Block, Blockstore};
 use serde::{de, ser};
 
+use crate::streaming::{HashingWriter, hasher};
 use crate::{CBOR, DAG_CBOR};
 
 /// Wrapper for database to handle inserting and retrieving ipld data with Cids
@@ -40,6 +41,28 @@ pub trait CborStore: Blockstore + Sized {
             },
         )
     }
+
+    /// Put an object in the block store and return the Cid identifier, like
+    /// [`put_cbor`](Self::put_cbor), but without encoding the whole object in memory first.
+    ///
+    /// The object is encoded straight into the block store's
+    /// [`block_writer`](fvm_ipld_blockstore::Blockstore::block_writer) in bounded chunks, computing
+    /// the hash along the way. This falls back on [`put_cbor`](Self::put_cbor) if the block store
+    /// can't stream blocks, or if the hash function can't be computed incrementally.
+    fn put_cbor_streaming<S>(&self, obj: &S, code: multihash_codetable::Code) -> anyhow::Result<Cid>
+    where
+        S: ser::Serialize,
+    {
+        let Some((hasher, writer)) = hasher(code).zip(self.block_writer()) else {
+            return self.put_cbor(obj, code);
+        };
+        let mut writer = HashingWriter::new(code, hasher, writer);
+        crate::to_writer(&mut writer, obj)?;
+        let (mh, writer) = writer.finish()?;
+        let k = Cid::new_v1(DAG_CBOR, mh);
+        writer.commit(&k)?;
+        Ok(k)
+    }
 }
 
 impl<T: Blockstore> CborStore for T {}
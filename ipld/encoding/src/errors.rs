@@ -2,7 +2,10 @@
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{fmt, io};
+use alloc::string::{String, ToString};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
 
 use cid::Error as CidError;
 use serde_ipld_dagcbor::{DecodeError, EncodeError};
@@ -46,6 +49,7 @@ impl From<CidError> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
         Self::other(err)
@@ -62,6 +66,7 @@ pub enum CodecProtocol {
     Unsupported,
     Cbor,
     Raw,
+    Json,
 }
 
 impl fmt::Display for CodecProtocol {
@@ -70,6 +75,7 @@ impl fmt::Display for CodecProtocol {
             CodecProtocol::Unsupported => write!(f, "Unsupported"),
             CodecProtocol::Cbor => write!(f, "Cbor"),
             CodecProtocol::Raw => write!(f, "Raw"),
+            CodecProtocol::Json => write!(f, "Json"),
         }
     }
 }
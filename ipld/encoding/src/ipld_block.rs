@@ -1,4 +1,6 @@
-use std::fmt::{Debug, Formatter};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
 
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
@@ -14,10 +16,10 @@ pub struct IpldBlock {
 }
 
 impl Debug for IpldBlock {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         struct HexFmtHelper<'a>(&'a [u8]);
         impl Debug for HexFmtHelper<'_> {
-            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
                 write!(f, "[")?;
                 for byte in self.0 {
                     write!(f, "{:02x}", byte)?;
@@ -80,6 +82,167 @@ impl IpldBlock {
     }
 }
 
+#[cfg(feature = "json")]
+mod json {
+    use alloc::collections::BTreeMap;
+    use alloc::string::{String, ToString};
+
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD_NO_PAD as BASE64;
+    use cid::Cid;
+    use ipld_core::ipld::Ipld;
+    use serde_json::{Map, Number, Value};
+
+    use super::IpldBlock;
+    use crate::{CodecProtocol, DAG_CBOR, Error, IPLD_RAW};
+
+    /// The key reserved by DAG-JSON for CIDs and byte strings.
+    const RESERVED_KEY: &str = "/";
+
+    fn json_error(description: impl ToString) -> Error {
+        Error {
+            description: description.to_string(),
+            protocol: CodecProtocol::Json,
+        }
+    }
+
+    impl IpldBlock {
+        /// Converts the block to JSON, for debugging and RPC. This follows the DAG-JSON
+        /// conventions for values JSON can't represent natively:
+        ///
+        /// - Byte strings become `{"/": {"bytes": "<base64, unpadded>"}}`.
+        /// - CIDs become `{"/": "<cid>"}` (e.g., `{"/": "bafy..."}`).
+        /// - Integers outside the range of 64-bit integers become decimal strings. This is lossy:
+        ///   [`IpldBlock::from_json`] will read them back as strings.
+        ///
+        /// Big integers (e.g., token amounts and storage power) are encoded as byte strings (a sign
+        /// byte followed by the big-endian magnitude), and IPLD doesn't say which byte strings hold
+        /// them, so they're converted to base64 like any other bytes, not to decimal strings. They
+        /// round-trip through [`IpldBlock::from_json`]; to show them as numbers, deserialize the
+        /// block into its Rust type first.
+        ///
+        /// Raw blocks are converted to byte strings. Fails if the block can't be decoded, or
+        /// contains values JSON can't represent (non-finite floats, or maps with a single `"/"`
+        /// key, which DAG-JSON reserves).
+        pub fn to_json(&self) -> Result<Value, Error> {
+            ipld_to_json(self.deserialize()?)
+        }
+
+        /// Encodes JSON as a block with the given codec, reversing [`IpldBlock::to_json`].
+        ///
+        /// Fails if the JSON can't be represented with the codec: floats are rejected when
+        /// encoding DAG-CBOR (which the FVM doesn't allow floats in), and raw blocks must be byte
+        /// strings.
+        pub fn from_json(codec: u64, value: &Value) -> Result<Self, Error> {
+            let ipld = json_to_ipld(value, codec != DAG_CBOR)?;
+            if codec == IPLD_RAW && !matches!(ipld, Ipld::Bytes(_)) {
+                return Err(json_error("raw blocks must be byte strings"));
+            }
+            IpldBlock::serialize(codec, &ipld)
+        }
+    }
+
+    fn ipld_to_json(ipld: Ipld) -> Result<Value, Error> {
+        Ok(match ipld {
+            Ipld::Null => Value::Null,
+            Ipld::Bool(b) => Value::Bool(b),
+            Ipld::Integer(i) => {
+                if let Ok(u) = u64::try_from(i) {
+                    Value::Number(u.into())
+                } else if let Ok(i) = i64::try_from(i) {
+                    Value::Number(i.into())
+                } else {
+                    Value::String(i.to_string())
+                }
+            }
+            Ipld::Float(f) => Value::Number(
+                Number::from_f64(f).ok_or_else(|| json_error(format!("non-finite float {f}")))?,
+            ),
+            Ipld::String(s) => Value::String(s),
+            Ipld::Bytes(bytes) => reserved(Value::Object(Map::from_iter([(
+                "bytes".into(),
+                Value::String(BASE64.encode(bytes)),
+            )]))),
+            Ipld::List(list) => Value::Array(
+                list.into_iter()
+                    .map(ipld_to_json)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ipld::Map(map) => {
+                if map.len() == 1 && map.contains_key(RESERVED_KEY) {
+                    return Err(json_error(
+                        "maps with a single \"/\" key can't be represented in JSON",
+                    ));
+                }
+                Value::Object(
+                    map.into_iter()
+                        .map(|(k, v)| Ok((k, ipld_to_json(v)?)))
+                        .collect::<Result<_, Error>>()?,
+                )
+            }
+            Ipld::Link(cid) => reserved(Value::String(cid.to_string())),
+        })
+    }
+
+    fn reserved(value: Value) -> Value {
+        Value::Object(Map::from_iter([(RESERVED_KEY.into(), value)]))
+    }
+
+    fn json_to_ipld(value: &Value, allow_floats: bool) -> Result<Ipld, Error> {
+        Ok(match value {
+            Value::Null => Ipld::Null,
+            Value::Bool(b) => Ipld::Bool(*b),
+            Value::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    Ipld::Integer(u.into())
+                } else if let Some(i) = n.as_i64() {
+                    Ipld::Integer(i.into())
+                } else if allow_floats && let Some(f) = n.as_f64() {
+                    Ipld::Float(f)
+                } else {
+                    return Err(json_error(format!(
+                        "{n} can't be encoded: DAG-CBOR blocks may not contain floats"
+                    )));
+                }
+            }
+            Value::String(s) => Ipld::String(s.clone()),
+            Value::Array(values) => Ipld::List(
+                values
+                    .iter()
+                    .map(|v| json_to_ipld(v, allow_floats))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => match map.get(RESERVED_KEY) {
+                Some(reserved) if map.len() == 1 => reserved_to_ipld(reserved)?,
+                _ => Ipld::Map(
+                    map.iter()
+                        .map(|(k, v)| Ok((k.clone(), json_to_ipld(v, allow_floats)?)))
+                        .collect::<Result<BTreeMap<String, Ipld>, Error>>()?,
+                ),
+            },
+        })
+    }
+
+    /// Converts the value of a `{"/": ...}` object: a CID or a byte string.
+    fn reserved_to_ipld(value: &Value) -> Result<Ipld, Error> {
+        match value {
+            Value::String(cid) => Cid::try_from(cid.as_str())
+                .map(Ipld::Link)
+                .map_err(|e| json_error(format!("invalid CID {cid}: {e}"))),
+            Value::Object(map) if map.len() == 1 => match map.get("bytes") {
+                Some(Value::String(bytes)) => BASE64
+                    .decode(bytes)
+                    .map(Ipld::Bytes)
+                    .map_err(|e| json_error(format!("invalid base64 bytes: {e}"))),
+                _ => Err(json_error("expected {\"/\": {\"bytes\": \"<base64>\"}}")),
+            },
+            _ => Err(json_error(
+                "expected a CID or bytes under the reserved \"/\" key",
+            )),
+        }
+    }
+}
+
 impl From<RawBytes> for Option<IpldBlock> {
     fn from(other: RawBytes) -> Self {
         (!other.is_empty()).then(|| IpldBlock {
@@ -136,4 +299,125 @@ mod test {
             )
         );
     }
+
+    #[cfg(feature = "json")]
+    mod json {
+        use cid::Cid;
+        use multihash_codetable::{Code, MultihashDigest};
+        use serde::{Deserialize, Serialize};
+        use serde_json::json;
+
+        use crate::{BytesSer, CBOR, DAG_CBOR, IPLD_RAW, IpldBlock, RawBytes};
+
+        /// The power actor's `ExecParams` (a code CID and opaque constructor parameters), encoded
+        /// as a tuple.
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct ExecParams(Cid, RawBytes);
+
+        fn code_cid() -> Cid {
+            Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"fil/test/miner"))
+        }
+
+        #[test]
+        fn exec_params_round_trip() {
+            let params = ExecParams(code_cid(), RawBytes::new(vec![0x82, 0x01, 0x02]));
+            let block = IpldBlock::serialize_dag_cbor(&params).unwrap().unwrap();
+
+            let value = block.to_json().unwrap();
+            assert_eq!(
+                value,
+                json!([
+                    {"/": code_cid().to_string()},
+                    {"/": {"bytes": "ggEC"}},
+                ])
+            );
+            assert!(value[0]["/"].as_str().unwrap().starts_with("bafk"));
+
+            let decoded = IpldBlock::from_json(DAG_CBOR, &value).unwrap();
+            assert_eq!(decoded, block);
+            assert_eq!(decoded.deserialize::<ExecParams>().unwrap(), params);
+        }
+
+        #[test]
+        fn integers() {
+            #[derive(Serialize, Deserialize, Debug, PartialEq)]
+            struct Ints(u64, i64, i64, i128);
+
+            let ints = Ints(u64::MAX, i64::MIN, -1, -(1 << 64));
+            let block = IpldBlock::serialize(CBOR, &ints).unwrap();
+            let value = block.to_json().unwrap();
+            // Integers JSON numbers can't hold are converted to strings.
+            assert_eq!(
+                value,
+                json!([u64::MAX, i64::MIN, -1, "-18446744073709551616"])
+            );
+
+            let value = json!([u64::MAX, i64::MIN, -1, 0]);
+            let ints = IpldBlock::from_json(CBOR, &value)
+                .unwrap()
+                .deserialize::<Ints>()
+                .unwrap();
+            assert_eq!(ints, Ints(u64::MAX, i64::MIN, -1, 0));
+        }
+
+        #[test]
+        fn bigints() {
+            // A big integer of 256, as encoded by `fvm_shared::bigint`: a positive sign byte and
+            // the big-endian magnitude. It's indistinguishable from other bytes, so stays base64.
+            let block = IpldBlock::serialize_dag_cbor(&BytesSer(&[0x00, 0x01, 0x00]))
+                .unwrap()
+                .unwrap();
+            let value = block.to_json().unwrap();
+            assert_eq!(value, json!({"/": {"bytes": "AAEA"}}));
+            assert_eq!(IpldBlock::from_json(DAG_CBOR, &value).unwrap(), block);
+        }
+
+        #[test]
+        fn raw() {
+            let block = IpldBlock {
+                codec: IPLD_RAW,
+                data: b"raw data".to_vec(),
+            };
+            let value = block.to_json().unwrap();
+            assert_eq!(value, json!({"/": {"bytes": "cmF3IGRhdGE"}}));
+            assert_eq!(IpldBlock::from_json(IPLD_RAW, &value).unwrap(), block);
+
+            assert!(IpldBlock::from_json(IPLD_RAW, &json!("raw data")).is_err());
+        }
+
+        #[test]
+        fn floats() {
+            let value = json!({"a": [1, 2.5]});
+            assert!(IpldBlock::from_json(DAG_CBOR, &value).is_err());
+
+            // Plain CBOR can carry floats.
+            let block = IpldBlock::from_json(CBOR, &value).unwrap();
+            assert_eq!(block.to_json().unwrap(), value);
+        }
+
+        #[test]
+        fn invalid_reserved() {
+            for value in [
+                json!({"/": "not a cid"}),
+                json!({"/": {"bytes": "!!"}}),
+                json!({"/": {"other": "ggEC"}}),
+                json!({"/": 1}),
+            ] {
+                assert!(IpldBlock::from_json(DAG_CBOR, &value).is_err(), "{value}");
+            }
+
+            // Other maps containing the reserved key are plain maps.
+            let value = json!({"/": "x", "y": 1});
+            let block = IpldBlock::from_json(DAG_CBOR, &value).unwrap();
+            assert_eq!(block.to_json().unwrap(), value);
+
+            // Maps with a single "/" key can't be converted to JSON.
+            let block = IpldBlock::serialize(
+                DAG_CBOR,
+                &std::collections::BTreeMap::from([("/", BytesSer(b"x"))]),
+            )
+            .unwrap();
+            assert!(block.to_json().is_err());
+        }
+    }
 }
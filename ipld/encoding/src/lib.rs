@@ -1,22 +1,35 @@
 // Copyright 2021-2023 Protocol Labs
 // Copyright 2019-2022 ChainSafe Systems
 // SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 mod bytes;
 mod cbor;
+#[cfg(feature = "std")]
 mod cbor_store;
 mod errors;
 pub mod ipld_block;
+mod link;
 mod raw;
+#[cfg(feature = "std")]
+mod streaming;
+mod validate;
 mod vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io;
 
 pub use serde::{self, de, ser};
 
 pub use self::bytes::*;
 pub use self::cbor::*;
+#[cfg(feature = "std")]
 pub use self::cbor_store::CborStore;
 pub use self::errors::*;
+pub use self::link::Link;
+pub use self::validate::validate_dag_cbor;
 pub use self::vec::*;
 
 /// CBOR should be used to pass CBOR data when internal links don't need to be
@@ -30,15 +43,16 @@ pub const IPLD_RAW: u64 = 0x55;
 
 pub type Multihash = cid::multihash::Multihash<64>;
 
-/// Re-export of `serde_tuple`. If you import this, you must make sure to import
-/// `tuple::serde_tuple` or the derive macros won't work properly. You should generally import as:
+/// Derive macros for encoding structs as arrays of their fields: `Serialize_tuple` (a re-export of
+/// `serde_tuple`'s) and `Deserialize_tuple`, which rejects arrays with too many or too few elements
+/// (see [`Deserialize_tuple`](tuple::Deserialize_tuple)). If you import these, you must also import
+/// the rest of this module or the derive macros won't work properly. You should generally import
+/// as:
 ///
 /// ```rust
 /// use fvm_ipld_encoding::tuple::*;
 /// ```
-pub mod tuple {
-    pub use serde_tuple::{self, Deserialize_tuple, Serialize_tuple};
-}
+pub mod tuple;
 
 /// Re-export of `serde_repr`. If you import this, you must make sure to import `repr::serde_repr`
 /// or the derive macros won't work properly. You should generally import as:
@@ -59,6 +73,7 @@ where
 }
 
 /// Decode a value from CBOR from the given reader.
+#[cfg(feature = "std")]
 pub fn from_reader<T, R>(reader: R) -> Result<T, Error>
 where
     T: de::DeserializeOwned,
@@ -76,6 +91,7 @@ where
 }
 
 /// Encode a value as CBOR to the given writer.
+#[cfg(feature = "std")]
 pub fn to_writer<W, T>(mut writer: W, value: &T) -> Result<(), Error>
 where
     W: io::Write,
@@ -83,3 +99,18 @@ where
 {
     serde_ipld_dagcbor::to_writer(&mut writer, value).map_err(Into::into)
 }
+
+/// Returns the CID of a value's DAG-CBOR encoding, hashed with blake2b-256. This is the CID under
+/// which [`CborStore::put_cbor`](crate::CborStore::put_cbor) stores the value with
+/// `Code::Blake2b256`.
+pub fn cid_of<T>(value: &T) -> Result<cid::Cid, Error>
+where
+    T: ser::Serialize + ?Sized,
+{
+    use multihash_codetable::{Code, MultihashDigest};
+
+    Ok(cid::Cid::new_v1(
+        DAG_CBOR,
+        Code::Blake2b256.digest(&to_vec(value)?),
+    ))
+}
@@ -0,0 +1,242 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
+use anyhow::anyhow;
+use cid::Cid;
+#[cfg(feature = "std")]
+use fvm_ipld_blockstore::Blockstore;
+#[cfg(feature = "std")]
+use multihash_codetable::Code;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "std")]
+use serde::{de, ser};
+
+#[cfg(feature = "std")]
+use crate::{CborStore, DAG_CBOR};
+
+/// A typed link: the CID of a DAG-CBOR encoded `T`. A `Link<T>` (de)serializes as a bare CID, so it
+/// can replace a `Cid` field without changing the encoding.
+///
+/// Links never cache the loaded value: every [`load`](Link::load) reads from the blockstore.
+pub struct Link<T> {
+    cid: Cid,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Link<T> {
+    /// Wraps a CID, without checking that it refers to a `T`.
+    pub fn new(cid: Cid) -> Self {
+        Self {
+            cid,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the linked CID.
+    pub fn cid(&self) -> &Cid {
+        &self.cid
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Link<T>
+where
+    T: de::DeserializeOwned,
+{
+    /// Loads and decodes the linked value. Fails if the link isn't a DAG-CBOR CID, if the block is
+    /// missing, or if it doesn't decode as a `T`.
+    pub fn load(&self, bs: &impl Blockstore) -> anyhow::Result<T> {
+        if self.cid.codec() != DAG_CBOR {
+            return Err(anyhow!(
+                "link {} has codec {:#x}, expected DAG-CBOR",
+                self.cid,
+                self.cid.codec()
+            ));
+        }
+        bs.get_cbor(&self.cid)?
+            .ok_or_else(|| anyhow!("linked block {} not found", self.cid))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Link<T>
+where
+    T: ser::Serialize,
+{
+    /// Encodes and stores a value, returning a link to it.
+    pub fn store(bs: &impl Blockstore, value: &T) -> anyhow::Result<Self> {
+        bs.put_cbor(value, Code::Blake2b256).map(Self::new)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Link<T>
+where
+    T: ser::Serialize + de::DeserializeOwned,
+{
+    /// Loads the linked value, applies `f` to it, then stores the modified value and updates this
+    /// link to point to it. The link is left unchanged if any step fails.
+    pub fn modify<R>(
+        &mut self,
+        bs: &impl Blockstore,
+        f: impl FnOnce(&mut T) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut value = self.load(bs)?;
+        let ret = f(&mut value)?;
+        *self = Self::store(bs, &value)?;
+        Ok(ret)
+    }
+}
+
+impl<T> From<Link<T>> for Cid {
+    fn from(link: Link<T>) -> Self {
+        link.cid
+    }
+}
+
+impl<T> Clone for Link<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Link<T> {}
+
+impl<T> PartialEq for Link<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cid == other.cid
+    }
+}
+
+impl<T> Eq for Link<T> {}
+
+impl<T> Hash for Link<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.cid, state)
+    }
+}
+
+impl<T> fmt::Debug for Link<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Link").field(&self.cid).finish()
+    }
+}
+
+impl<T> fmt::Display for Link<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.cid, f)
+    }
+}
+
+impl<T> Serialize for Link<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.cid.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Link<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Cid::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fvm_ipld_blockstore::MemoryBlockstore;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{IPLD_RAW, from_slice, to_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+    struct Claims(BTreeMap<String, u64>);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct State {
+        claims: Link<Claims>,
+        cron_queue: Link<Vec<u64>>,
+    }
+
+    #[test]
+    fn store_load() {
+        let bs = MemoryBlockstore::default();
+        let claims = Claims([("a".into(), 10), ("b".into(), 20)].into());
+        let link = Link::store(&bs, &claims).unwrap();
+        assert_eq!(link.cid().codec(), DAG_CBOR);
+        assert_eq!(link.load(&bs).unwrap(), claims);
+
+        // Loading never caches: a missing block is always an error.
+        let empty = MemoryBlockstore::default();
+        link.load(&empty).expect_err("block is missing");
+    }
+
+    #[test]
+    fn encodes_as_cid() {
+        let bs = MemoryBlockstore::default();
+        let state = State {
+            claims: Link::store(&bs, &Claims::default()).unwrap(),
+            cron_queue: Link::store(&bs, &vec![1u64, 2, 3]).unwrap(),
+        };
+        let encoded = to_vec(&state).unwrap();
+        let cids: BTreeMap<String, Cid> = from_slice(&encoded).unwrap();
+        assert_eq!(cids["claims"], *state.claims.cid());
+        assert_eq!(cids["cron_queue"], *state.cron_queue.cid());
+        assert_eq!(from_slice::<State>(&encoded).unwrap(), state);
+    }
+
+    #[test]
+    fn rejects_non_dag_cbor() {
+        let bs = MemoryBlockstore::default();
+        let stored = Link::<Vec<u64>>::store(&bs, &vec![1]).unwrap();
+        let raw: Link<Vec<u64>> = Link::new(Cid::new_v1(IPLD_RAW, *stored.cid().hash()));
+        raw.load(&bs).expect_err("not DAG-CBOR");
+    }
+
+    #[test]
+    fn wrong_type() {
+        let bs = MemoryBlockstore::default();
+        let queue = Link::store(&bs, &vec![1u64, 2, 3]).unwrap();
+        let claims: Link<Claims> = Link::new(*queue.cid());
+        claims.load(&bs).expect_err("a list is not a map");
+    }
+
+    #[test]
+    fn modify() {
+        let bs = MemoryBlockstore::default();
+        let mut link = Link::store(&bs, &vec![1u64, 2]).unwrap();
+        let original = link;
+
+        let len = link
+            .modify(&bs, |queue| {
+                queue.push(3);
+                Ok(queue.len())
+            })
+            .unwrap();
+        assert_eq!(len, 3);
+        assert_ne!(link, original);
+        assert_eq!(link.load(&bs).unwrap(), [1, 2, 3]);
+        assert_eq!(original.load(&bs).unwrap(), [1, 2]);
+
+        // A failed modification leaves the link untouched.
+        let before = link;
+        link.modify(&bs, |queue| {
+            queue.clear();
+            Err::<(), _>(anyhow!("abort"))
+        })
+        .expect_err("modification failed");
+        assert_eq!(link, before);
+        assert_eq!(link.load(&bs).unwrap(), [1, 2, 3]);
+    }
+}
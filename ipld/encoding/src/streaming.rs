@@ -0,0 +1,81 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io;
+
+use fvm_ipld_blockstore::BlockWriter;
+use multihash_codetable::Code;
+use multihash_derive::Hasher;
+
+use crate::Multihash;
+
+/// The size of the chunks written through to the hasher and the blockstore.
+const CHUNK_SIZE: usize = 64 << 10;
+
+/// Returns an incremental hasher for the specified multihash code, if supported.
+pub(crate) fn hasher(code: Code) -> Option<Box<dyn Hasher>> {
+    match code {
+        Code::Blake2b256 => Some(Box::<multihash_codetable::Blake2b256>::default()),
+        Code::Blake2b512 => Some(Box::<multihash_codetable::Blake2b512>::default()),
+        // Other codes may be enabled by features of dependent crates.
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Writes encoded data through to a block writer in bounded chunks, hashing it along the way.
+pub(crate) struct HashingWriter<'a> {
+    code: Code,
+    hasher: Box<dyn Hasher>,
+    inner: Box<dyn BlockWriter + 'a>,
+    buf: Vec<u8>,
+}
+
+impl<'a> HashingWriter<'a> {
+    pub fn new(code: Code, hasher: Box<dyn Hasher>, inner: Box<dyn BlockWriter + 'a>) -> Self {
+        Self {
+            code,
+            hasher,
+            inner,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    /// Writes out any buffered data, returning the multihash of everything written along with the
+    /// underlying block writer.
+    pub fn finish(mut self) -> io::Result<(Multihash, Box<dyn BlockWriter + 'a>)> {
+        self.write_chunk()?;
+        let mh =
+            Multihash::wrap(self.code.into(), self.hasher.finalize()).map_err(io::Error::other)?;
+        Ok((mh, self.inner))
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.hasher.update(&self.buf);
+            self.inner.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+impl io::Write for HashingWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > CHUNK_SIZE {
+            self.write_chunk()?;
+        }
+        if data.len() >= CHUNK_SIZE {
+            // Too large to buffer, write it straight through.
+            self.hasher.update(data);
+            self.inner.write_all(data)?;
+        } else {
+            self.buf.extend_from_slice(data);
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_chunk()?;
+        self.inner.flush()
+    }
+}
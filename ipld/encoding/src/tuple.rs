@@ -0,0 +1,192 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+pub use fvm_ipld_encoding_derive::Deserialize_tuple;
+pub use serde_tuple::{self, Serialize_tuple};
+
+/// Support code for the `Deserialize_tuple` derive macro.
+#[doc(hidden)]
+pub mod __fvm_ipld_encoding_tuple {
+    use core::fmt;
+
+    pub use serde;
+    use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, SeqAccess, Visitor};
+
+    /// A deserializer that fails if a sequence (e.g., a tuple struct) has more elements than the
+    /// value decoded from it consumes, instead of silently ignoring them.
+    ///
+    /// Only the outermost value is checked: nested tuple structs check their own length.
+    pub struct StrictTuple<D>(pub D);
+
+    /// Wraps a visitor, checking that it consumes the whole sequence.
+    struct StrictVisitor<V>(V);
+
+    impl<'de, V> Visitor<'de> for StrictVisitor<V>
+    where
+        V: Visitor<'de>,
+    {
+        type Value = V::Value;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            self.0.expecting(formatter)
+        }
+
+        fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut seq = CountingSeq { seq, count: 0 };
+            let value = self.0.visit_seq(&mut seq)?;
+            let expected = seq.count;
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            if seq.count > expected {
+                return Err(de::Error::invalid_length(
+                    seq.count,
+                    &ExpectedLength(expected),
+                ));
+            }
+            Ok(value)
+        }
+    }
+
+    /// Counts the elements taken from a sequence.
+    struct CountingSeq<A> {
+        seq: A,
+        count: usize,
+    }
+
+    impl<'de, A> SeqAccess<'de> for CountingSeq<A>
+    where
+        A: SeqAccess<'de>,
+    {
+        type Error = A::Error;
+
+        fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+        where
+            T: DeserializeSeed<'de>,
+        {
+            let element = self.seq.next_element_seed(seed)?;
+            if element.is_some() {
+                self.count += 1;
+            }
+            Ok(element)
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            self.seq.size_hint()
+        }
+    }
+
+    struct ExpectedLength(usize);
+
+    impl de::Expected for ExpectedLength {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "an array of {} elements", self.0)
+        }
+    }
+
+    macro_rules! forward {
+        ($($method:ident)*) => {
+            $(
+                fn $method<V>(self, visitor: V) -> Result<V::Value, D::Error>
+                where
+                    V: Visitor<'de>,
+                {
+                    self.0.$method(visitor)
+                }
+            )*
+        };
+    }
+
+    impl<'de, D> Deserializer<'de> for StrictTuple<D>
+    where
+        D: Deserializer<'de>,
+    {
+        type Error = D::Error;
+
+        forward! {
+            deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+            deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+            deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+            deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+            deserialize_option deserialize_unit deserialize_map
+            deserialize_identifier deserialize_ignored_any
+        }
+
+        fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_seq(StrictVisitor(visitor))
+        }
+
+        fn deserialize_unit_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_unit_struct(name, visitor)
+        }
+
+        fn deserialize_newtype_struct<V>(
+            self,
+            name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_newtype_struct(name, visitor)
+        }
+
+        fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_tuple(len, StrictVisitor(visitor))
+        }
+
+        fn deserialize_tuple_struct<V>(
+            self,
+            name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0
+                .deserialize_tuple_struct(name, len, StrictVisitor(visitor))
+        }
+
+        fn deserialize_struct<V>(
+            self,
+            name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_struct(name, fields, visitor)
+        }
+
+        fn deserialize_enum<V>(
+            self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.0.deserialize_enum(name, variants, visitor)
+        }
+
+        fn is_human_readable(&self) -> bool {
+            self.0.is_human_readable()
+        }
+    }
+}
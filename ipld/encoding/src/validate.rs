@@ -0,0 +1,246 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use alloc::format;
+use alloc::string::String;
+
+use cid::Cid;
+
+use crate::errors::{CodecProtocol, Error};
+
+/// The CBOR tag of CIDs in DAG-CBOR.
+const CID_TAG: u64 = 42;
+
+/// Checks that `bytes` hold exactly one well-formed DAG-CBOR object, without decoding it. That is:
+///
+/// - Only definite-length items.
+/// - No floats, and no simple values other than `false`, `true`, and `null`.
+/// - No tags other than 42, which must wrap a byte string holding a `0x00` byte followed by a
+///   valid CID.
+/// - Text strings are valid UTF-8.
+/// - No trailing bytes.
+///
+/// This is a structural check: it doesn't check that integers and lengths are minimally encoded,
+/// nor that map keys are unique, sorted strings.
+pub fn validate_dag_cbor(bytes: &[u8]) -> Result<(), Error> {
+    let buf = &mut &bytes[..];
+    // The number of items left to read. This can't exceed the number of bytes left, plus
+    // `u64::MAX`, so we check for overflows.
+    let mut remaining: u64 = 1;
+    while remaining > 0 {
+        remaining -= 1;
+        let (major, info, arg) = read_header(buf)?;
+        match major {
+            // Unsigned and negative integers.
+            0 | 1 => {}
+            // Byte strings.
+            2 => {
+                take(buf, arg)?;
+            }
+            // Text strings.
+            3 => {
+                core::str::from_utf8(take(buf, arg)?)
+                    .map_err(|e| error(format!("invalid utf8 text string: {e}")))?;
+            }
+            // Arrays.
+            4 => {
+                remaining = remaining
+                    .checked_add(arg)
+                    .ok_or_else(|| error("cbor item count overflow"))?;
+            }
+            // Maps.
+            5 => {
+                remaining = arg
+                    .checked_mul(2)
+                    .and_then(|n| n.checked_add(remaining))
+                    .ok_or_else(|| error("cbor item count overflow"))?;
+            }
+            // Tags.
+            6 => {
+                if arg != CID_TAG {
+                    return Err(error(format!("unsupported cbor tag {arg}")));
+                }
+                validate_cid(buf)?;
+            }
+            // Simple values and floats.
+            7 => match info {
+                // false, true, null
+                20..=22 => {}
+                25..=27 => return Err(error("floats are not allowed in dag-cbor")),
+                _ => return Err(error(format!("unsupported cbor simple value {arg}"))),
+            },
+            8.. => unreachable!("major types are 3 bits"),
+        }
+    }
+    if !buf.is_empty() {
+        return Err(error(format!("{} trailing bytes in cbor", buf.len())));
+    }
+    Ok(())
+}
+
+/// Validates a CID (following its tag): a byte string holding a `0x00` byte then the CID itself.
+fn validate_cid(buf: &mut &[u8]) -> Result<(), Error> {
+    let (major, _, len) = read_header(buf)?;
+    if major != 2 {
+        return Err(error("expected a cbor byte string for a cid"));
+    }
+    let mut cid_buf = match take(buf, len)? {
+        [0, cid_buf @ ..] => cid_buf,
+        _ => return Err(error("dag-cbor cid does not start with a 0x00 byte")),
+    };
+    Cid::read_bytes(&mut cid_buf)?;
+    if !cid_buf.is_empty() {
+        return Err(error(format!("cid has {} trailing bytes", cid_buf.len())));
+    }
+    Ok(())
+}
+
+/// Reads a CBOR item header, returning its major type, additional information, and argument (for
+/// simple values and floats, the argument isn't meaningful).
+fn read_header(buf: &mut &[u8]) -> Result<(u8, u8, u64), Error> {
+    let first = take(buf, 1)?[0];
+    let (major, info) = (first >> 5, first & 0x1f);
+    let len = match info {
+        ..=23 => return Ok((major, info, info.into())),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        31 => return Err(error("indefinite-length items are not allowed in dag-cbor")),
+        _ => return Err(error("invalid cbor header")),
+    };
+    let mut arg = [0u8; 8];
+    arg[8 - len..].copy_from_slice(take(buf, len as u64)?);
+    Ok((major, info, u64::from_be_bytes(arg)))
+}
+
+/// Splits `len` bytes off the front of the buffer.
+fn take<'a>(buf: &mut &'a [u8], len: u64) -> Result<&'a [u8], Error> {
+    if len > buf.len() as u64 {
+        return Err(error("unexpected end of cbor stream"));
+    }
+    let (taken, rest) = buf.split_at(len as usize);
+    *buf = rest;
+    Ok(taken)
+}
+
+fn error(description: impl Into<String>) -> Error {
+    Error {
+        description: description.into(),
+        protocol: CodecProtocol::Cbor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use multihash_codetable::{Code, MultihashDigest};
+    use serde::Serialize;
+
+    use super::validate_dag_cbor;
+    use crate::{BytesSer, DAG_CBOR, to_vec};
+
+    #[derive(Serialize)]
+    struct Fixture {
+        name: String,
+        values: Vec<i64>,
+        link: Cid,
+        nested: Option<(bool, Vec<u8>)>,
+    }
+
+    fn fixture() -> Vec<u8> {
+        to_vec(&Fixture {
+            name: "fixture".into(),
+            values: vec![0, -1, 1 << 40],
+            link: Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"link")),
+            nested: Some((true, vec![1, 2, 3])),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        validate_dag_cbor(&fixture()).unwrap();
+        validate_dag_cbor(&to_vec(&()).unwrap()).unwrap();
+        validate_dag_cbor(&to_vec(&BytesSer(&[0; 100])).unwrap()).unwrap();
+        validate_dag_cbor(&to_vec(&vec![vec![(); 3]; 3]).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn truncated() {
+        let data = fixture();
+        validate_dag_cbor(&[]).unwrap_err();
+        for len in 0..data.len() {
+            validate_dag_cbor(&data[..len]).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut data = fixture();
+        data.push(0);
+        validate_dag_cbor(&data).unwrap_err();
+    }
+
+    #[test]
+    fn floats() {
+        // 1.5 as a half, single, and double-precision float.
+        validate_dag_cbor(&[0xf9, 0x3e, 0x00]).unwrap_err();
+        validate_dag_cbor(&[0xfa, 0x3f, 0xc0, 0x00, 0x00]).unwrap_err();
+        let float = to_vec(&1.5f64).unwrap();
+        assert_eq!(float[0], 0xfb);
+        validate_dag_cbor(&float).unwrap_err();
+        // Nested in a list.
+        validate_dag_cbor(&[0x82, 0x01, 0xf9, 0x3e, 0x00]).unwrap_err();
+    }
+
+    #[test]
+    fn simple_values() {
+        // false, true, null
+        for value in [0xf4, 0xf5, 0xf6] {
+            validate_dag_cbor(&[value]).unwrap();
+        }
+        // undefined, and a one-byte simple value.
+        validate_dag_cbor(&[0xf7]).unwrap_err();
+        validate_dag_cbor(&[0xf8, 0x20]).unwrap_err();
+    }
+
+    #[test]
+    fn bad_cids() {
+        let cid = Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(b"link")).to_bytes();
+        let tagged = |tag: u8, payload: &[u8]| {
+            let mut data = vec![0xd8, tag];
+            data.extend(to_vec(&BytesSer(payload)).unwrap());
+            data
+        };
+        let prefixed = |cid: &[u8]| [&[0u8][..], cid].concat();
+
+        validate_dag_cbor(&tagged(42, &prefixed(&cid))).unwrap();
+        // Another tag.
+        validate_dag_cbor(&tagged(43, &prefixed(&cid))).unwrap_err();
+        // No 0x00 prefix.
+        validate_dag_cbor(&tagged(42, &cid)).unwrap_err();
+        // Not a CID.
+        validate_dag_cbor(&tagged(42, &prefixed(&cid[..cid.len() - 1]))).unwrap_err();
+        validate_dag_cbor(&tagged(42, &prefixed(&[&cid[..], &[0u8][..]].concat()))).unwrap_err();
+        // Not a byte string.
+        validate_dag_cbor(&[0xd8, 42, 0x01]).unwrap_err();
+    }
+
+    #[test]
+    fn invalid_text() {
+        validate_dag_cbor(&[0x62, 0xc3, 0x28]).unwrap_err();
+    }
+
+    #[test]
+    fn indefinite_length() {
+        // An indefinite-length list holding 1.
+        validate_dag_cbor(&[0x9f, 0x01, 0xff]).unwrap_err();
+    }
+
+    #[test]
+    fn oversized_lengths() {
+        validate_dag_cbor(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap_err();
+        validate_dag_cbor(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap_err();
+        validate_dag_cbor(&[0xbb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap_err();
+    }
+}
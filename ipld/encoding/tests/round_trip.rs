@@ -0,0 +1,203 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::BTreeMap;
+
+use cid::Cid;
+use fvm_ipld_encoding::{DAG_CBOR, IPLD_RAW, from_slice, to_vec};
+use ipld_core::ipld::Ipld;
+use multihash_codetable::{Code, MultihashDigest};
+use quickcheck::{Arbitrary, Gen};
+use quickcheck_macros::quickcheck;
+
+/// The maximum nesting depth of generated lists and maps.
+const MAX_DEPTH: usize = 3;
+
+/// The maximum number of elements in a generated list or map.
+const MAX_LEN: usize = 4;
+
+/// Integers at the boundaries of the CBOR integer encodings and the Rust integer types.
+const BOUNDARY_INTEGERS: &[i128] = &[
+    0,
+    1,
+    23,
+    24,
+    255,
+    256,
+    65535,
+    65536,
+    u32::MAX as i128,
+    u32::MAX as i128 + 1,
+    i64::MAX as i128,
+    i64::MAX as i128 + 1,
+    u64::MAX as i128,
+    -1,
+    -24,
+    -25,
+    -256,
+    -257,
+    i64::MIN as i128,
+    i64::MIN as i128 - 1,
+    -(u64::MAX as i128) - 1,
+];
+
+/// An arbitrary IPLD value that is representable in DAG-CBOR: integers fit in the CBOR range,
+/// floats are finite, and nesting is bounded.
+#[derive(Clone, Debug)]
+struct ArbIpld(Ipld);
+
+impl Arbitrary for ArbIpld {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self(arbitrary_ipld(g, MAX_DEPTH))
+    }
+}
+
+fn arbitrary_ipld(g: &mut Gen, depth: usize) -> Ipld {
+    let kinds = if depth == 0 { 7 } else { 9 };
+    match u8::arbitrary(g) % kinds {
+        0 => Ipld::Null,
+        1 => Ipld::Bool(bool::arbitrary(g)),
+        2 => Ipld::Integer(arbitrary_integer(g)),
+        3 => {
+            let f = f64::arbitrary(g);
+            Ipld::Float(if f.is_finite() { f } else { 0.0 })
+        }
+        4 => Ipld::String(String::arbitrary(g)),
+        5 => Ipld::Bytes(Vec::arbitrary(g)),
+        6 => Ipld::Link(arbitrary_cid(g)),
+        7 => Ipld::List(
+            (0..usize::arbitrary(g) % (MAX_LEN + 1))
+                .map(|_| arbitrary_ipld(g, depth - 1))
+                .collect(),
+        ),
+        _ => Ipld::Map(
+            (0..usize::arbitrary(g) % (MAX_LEN + 1))
+                .map(|_| (String::arbitrary(g), arbitrary_ipld(g, depth - 1)))
+                .collect(),
+        ),
+    }
+}
+
+fn arbitrary_integer(g: &mut Gen) -> i128 {
+    match u8::arbitrary(g) % 3 {
+        0 => *g.choose(BOUNDARY_INTEGERS).unwrap(),
+        1 => i64::arbitrary(g).into(),
+        _ => u64::arbitrary(g).into(),
+    }
+}
+
+fn arbitrary_cid(g: &mut Gen) -> Cid {
+    let codec = *g.choose(&[DAG_CBOR, IPLD_RAW]).unwrap();
+    let data: Vec<u8> = Arbitrary::arbitrary(g);
+    Cid::new_v1(codec, Code::Blake2b256.digest(&data))
+}
+
+/// Decoding an encoded value yields the original value.
+#[quickcheck]
+fn prop_round_trip(value: ArbIpld) -> bool {
+    let bytes = to_vec(&value.0).unwrap();
+    from_slice::<Ipld>(&bytes).unwrap() == value.0
+}
+
+/// Re-encoding a decoded value reproduces the original (canonical) bytes.
+#[quickcheck]
+fn prop_canonical_bytes(value: ArbIpld) -> bool {
+    let bytes = to_vec(&value.0).unwrap();
+    let decoded: Ipld = from_slice(&bytes).unwrap();
+    to_vec(&decoded).unwrap() == bytes
+}
+
+#[test]
+fn boundary_integers() {
+    for &i in BOUNDARY_INTEGERS {
+        let bytes = to_vec(&Ipld::Integer(i)).unwrap();
+        assert_eq!(from_slice::<Ipld>(&bytes).unwrap(), Ipld::Integer(i));
+    }
+}
+
+#[test]
+fn u64_max() {
+    let bytes = to_vec(&u64::MAX).unwrap();
+    assert_eq!(
+        bytes,
+        [0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(from_slice::<u64>(&bytes).unwrap(), u64::MAX);
+    assert_eq!(to_vec(&Ipld::Integer(u64::MAX.into())).unwrap(), bytes);
+
+    // Doesn't fit in an i64.
+    assert!(from_slice::<i64>(&bytes).is_err());
+
+    // One past the end isn't representable in CBOR.
+    assert!(to_vec(&Ipld::Integer(u64::MAX as i128 + 1)).is_err());
+}
+
+#[test]
+fn negative_integers_beyond_i64() {
+    // CBOR encodes negative integers as -1 - n, so the smallest is -2^64.
+    let min = -(u64::MAX as i128) - 1;
+    let bytes = to_vec(&Ipld::Integer(min)).unwrap();
+    assert_eq!(
+        bytes,
+        [0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(from_slice::<Ipld>(&bytes).unwrap(), Ipld::Integer(min));
+
+    // Doesn't fit in an i64.
+    assert!(from_slice::<i64>(&bytes).is_err());
+
+    // One past the end isn't representable in CBOR.
+    assert!(to_vec(&Ipld::Integer(min - 1)).is_err());
+
+    // Just past i64::MIN still round-trips.
+    let bytes = to_vec(&Ipld::Integer(i64::MIN as i128 - 1)).unwrap();
+    assert_eq!(
+        bytes,
+        [0x3b, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        from_slice::<Ipld>(&bytes).unwrap(),
+        Ipld::Integer(i64::MIN as i128 - 1)
+    );
+}
+
+#[test]
+fn floats() {
+    // DAG-CBOR only permits finite floats, always encoded as 64-bit.
+    assert!(to_vec(&f64::NAN).is_err());
+    assert!(to_vec(&f64::INFINITY).is_err());
+    assert!(to_vec(&f64::NEG_INFINITY).is_err());
+    assert!(to_vec(&Ipld::Float(f64::NAN)).is_err());
+
+    let bytes = to_vec(&1.5f64).unwrap();
+    assert_eq!(
+        bytes,
+        [0xfb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(to_vec(&1.5f32).unwrap(), bytes);
+    assert_eq!(from_slice::<Ipld>(&bytes).unwrap(), Ipld::Float(1.5));
+
+    // Half and single precision floats are not canonical, so they're rejected.
+    assert!(from_slice::<Ipld>(&[0xf9, 0x3e, 0x00]).is_err());
+    assert!(from_slice::<Ipld>(&[0xfa, 0x3f, 0xc0, 0x00, 0x00]).is_err());
+}
+
+#[test]
+fn empty_map_vs_empty_array() {
+    let map = to_vec(&Ipld::Map(BTreeMap::new())).unwrap();
+    let list = to_vec(&Ipld::List(Vec::new())).unwrap();
+    assert_eq!(map, [0xa0]);
+    assert_eq!(list, [0x80]);
+    assert_eq!(to_vec(&BTreeMap::<String, u64>::new()).unwrap(), map);
+    assert_eq!(to_vec(&Vec::<u64>::new()).unwrap(), list);
+
+    assert_eq!(
+        from_slice::<Ipld>(&map).unwrap(),
+        Ipld::Map(BTreeMap::new())
+    );
+    assert_eq!(from_slice::<Ipld>(&list).unwrap(), Ipld::List(Vec::new()));
+
+    // Neither decodes as the other.
+    assert!(from_slice::<Vec<u64>>(&map).is_err());
+    assert!(from_slice::<BTreeMap<String, u64>>(&list).is_err());
+}
@@ -0,0 +1,209 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::io;
+
+use cid::Cid;
+use fvm_ipld_blockstore::{Block, BlockWriter, Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::{BytesSer, CborStore, DAG_CBOR, to_vec};
+use multihash_codetable::Code;
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+/// Tracks the bytes allocated by the current thread, so tests running in parallel don't interfere
+/// with each other's measurements.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(delta: isize) {
+    // Ignore allocations made while the thread is being torn down.
+    let _ = ALLOCATED.try_with(|allocated| {
+        let current = allocated.get() + delta;
+        allocated.set(current);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(current)));
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        record(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs the function, returning its result and the peak number of bytes it had allocated at once.
+fn peak_allocation<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let baseline = ALLOCATED.with(Cell::get);
+    PEAK.with(|peak| peak.set(baseline));
+    let res = f();
+    let peak = PEAK.with(Cell::get);
+    (res, (peak - baseline) as usize)
+}
+
+/// A large list of byte strings, generated while it's serialized rather than held in memory.
+struct Synthetic {
+    items: usize,
+}
+
+const ITEM_SIZE: usize = 1 << 10;
+
+impl Serialize for Synthetic {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.items))?;
+        let mut item = [0u8; ITEM_SIZE];
+        for i in 0..self.items {
+            item.fill(i as u8);
+            seq.serialize_element(&BytesSer(&item))?;
+        }
+        seq.end()
+    }
+}
+
+/// 50MiB of synthetic data.
+const LARGE: Synthetic = Synthetic { items: 50 << 10 };
+
+/// A blockstore that streams blocks to nowhere, keeping track of their CIDs and sizes.
+#[derive(Default)]
+struct SinkBlockstore {
+    blocks: RefCell<Vec<(Cid, usize)>>,
+}
+
+struct SinkWriter<'a> {
+    store: &'a SinkBlockstore,
+    written: usize,
+}
+
+impl Blockstore for SinkBlockstore {
+    fn get(&self, _: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.blocks.borrow_mut().push((*k, block.len()));
+        Ok(())
+    }
+
+    fn block_writer(&self) -> Option<Box<dyn BlockWriter + '_>> {
+        Some(Box::new(SinkWriter {
+            store: self,
+            written: 0,
+        }))
+    }
+}
+
+impl io::Write for SinkWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl BlockWriter for SinkWriter<'_> {
+    fn commit(self: Box<Self>, k: &Cid) -> anyhow::Result<()> {
+        self.store.blocks.borrow_mut().push((*k, self.written));
+        Ok(())
+    }
+}
+
+/// A blockstore that can only put contiguous blocks.
+#[derive(Default)]
+struct SliceBlockstore(MemoryBlockstore);
+
+impl Blockstore for SliceBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        self.0.get(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.0.put_keyed(k, block)
+    }
+}
+
+#[test]
+fn streaming_memory_is_bounded() {
+    let store = SinkBlockstore::default();
+    let (cid, peak) = peak_allocation(|| store.put_cbor_streaming(&LARGE, Code::Blake2b256));
+    let cid = cid.unwrap();
+    assert!(peak < 1 << 20, "allocated {peak} bytes at once");
+
+    let blocks = store.blocks.borrow();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].0, cid);
+    assert!(blocks[0].1 > LARGE.items * ITEM_SIZE);
+
+    // Whereas the buffered path holds the whole block in memory.
+    let (_, peak) = peak_allocation(|| store.put_cbor(&LARGE, Code::Blake2b256).unwrap());
+    assert!(
+        peak > LARGE.items * ITEM_SIZE,
+        "allocated {peak} bytes at once"
+    );
+}
+
+#[test]
+fn streaming_matches_buffered() {
+    let expected = Block::new(DAG_CBOR, to_vec(&LARGE).unwrap()).cid(Code::Blake2b256);
+    let cid = SinkBlockstore::default()
+        .put_cbor_streaming(&LARGE, Code::Blake2b256)
+        .unwrap();
+    assert_eq!(cid, expected);
+
+    type Value = (u64, String, Vec<u8>, [Option<i32>; 2]);
+    for code in [Code::Blake2b256, Code::Blake2b512] {
+        let value: Value = (1, "two".into(), vec![3; 100_000], [Some(4), None]);
+        let streamed = MemoryBlockstore::default();
+        let streamed_cid = streamed.put_cbor_streaming(&value, code).unwrap();
+        let buffered = MemoryBlockstore::default();
+        let buffered_cid = buffered.put_cbor(&value, code).unwrap();
+        assert_eq!(streamed_cid, buffered_cid);
+        assert_eq!(
+            streamed.get(&streamed_cid).unwrap(),
+            buffered.get(&buffered_cid).unwrap()
+        );
+        assert_eq!(
+            streamed.get_cbor::<Value>(&streamed_cid).unwrap(),
+            Some(value)
+        );
+    }
+}
+
+#[test]
+fn streaming_falls_back_to_buffered() {
+    let value = vec!["a".repeat(1000); 100];
+    let store = SliceBlockstore::default();
+    let cid = store.put_cbor_streaming(&value, Code::Blake2b256).unwrap();
+    assert_eq!(
+        cid,
+        MemoryBlockstore::default()
+            .put_cbor(&value, Code::Blake2b256)
+            .unwrap()
+    );
+    assert_eq!(store.get_cbor::<Vec<String>>(&cid).unwrap(), Some(value));
+}
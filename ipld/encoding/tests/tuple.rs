@@ -0,0 +1,113 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Tuple-encoded structs must be decoded from arrays with exactly one element per field, unless
+//! they opt into optional trailing fields.
+
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{BytesSer, from_slice, strict_bytes, to_vec};
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq)]
+struct Pair {
+    a: u64,
+    #[serde(with = "strict_bytes")]
+    b: Vec<u8>,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq)]
+struct Outer {
+    pair: Pair,
+    n: u64,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq)]
+struct Generic<T> {
+    value: T,
+    n: u64,
+}
+
+#[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq)]
+#[serde_tuple(optional_trailing)]
+struct Versioned {
+    a: u64,
+    #[serde(default)]
+    b: Option<u64>,
+}
+
+fn pair() -> Pair {
+    Pair {
+        a: 1,
+        b: b"ab".to_vec(),
+    }
+}
+
+fn assert_invalid_length<T: std::fmt::Debug>(res: Result<T, fvm_ipld_encoding::Error>) {
+    let err = res.unwrap_err();
+    assert!(err.to_string().contains("invalid length"), "{err}");
+}
+
+#[test]
+fn exact_length() {
+    let encoded = to_vec(&pair()).unwrap();
+    assert_eq!(encoded, to_vec(&(1u64, BytesSer(b"ab"))).unwrap());
+    assert_eq!(from_slice::<Pair>(&encoded).unwrap(), pair());
+
+    let outer = Outer { pair: pair(), n: 3 };
+    assert_eq!(
+        from_slice::<Outer>(&to_vec(&outer).unwrap()).unwrap(),
+        outer
+    );
+
+    let generic = Generic {
+        value: pair(),
+        n: 3,
+    };
+    assert_eq!(
+        from_slice::<Generic<Pair>>(&to_vec(&generic).unwrap()).unwrap(),
+        generic
+    );
+}
+
+#[test]
+fn too_short() {
+    assert_invalid_length(from_slice::<Pair>(&to_vec(&(1u64,)).unwrap()));
+    assert_invalid_length(from_slice::<Pair>(&to_vec(&Vec::<u64>::new()).unwrap()));
+}
+
+#[test]
+fn too_long() {
+    let long = to_vec(&(1u64, BytesSer(b"ab"), 3u64)).unwrap();
+    assert_invalid_length(from_slice::<Pair>(&long));
+    let long = to_vec(&(1u64, BytesSer(b"ab"), 3u64, 4u64)).unwrap();
+    assert_invalid_length(from_slice::<Pair>(&long));
+
+    // Nested structs are checked too.
+    let nested = to_vec(&((1u64, BytesSer(b"ab"), 3u64), 4u64)).unwrap();
+    assert_invalid_length(from_slice::<Outer>(&nested));
+    assert_invalid_length(from_slice::<Generic<Pair>>(&nested));
+    let outer = to_vec(&((1u64, BytesSer(b"ab")), 4u64, 5u64)).unwrap();
+    assert_invalid_length(from_slice::<Outer>(&outer));
+}
+
+#[test]
+fn optional_trailing() {
+    let exact = Versioned { a: 1, b: Some(2) };
+    assert_eq!(
+        from_slice::<Versioned>(&to_vec(&exact).unwrap()).unwrap(),
+        exact
+    );
+
+    // Extra elements are ignored, and missing default fields are filled in.
+    let long = to_vec(&(1u64, 2u64, 3u64)).unwrap();
+    assert_eq!(from_slice::<Versioned>(&long).unwrap(), exact);
+    let short = to_vec(&(1u64,)).unwrap();
+    assert_eq!(
+        from_slice::<Versioned>(&short).unwrap(),
+        Versioned { a: 1, b: None }
+    );
+
+    // Fields without a default are still required.
+    assert_invalid_length(from_slice::<Versioned>(
+        &to_vec(&Vec::<u64>::new()).unwrap(),
+    ));
+}
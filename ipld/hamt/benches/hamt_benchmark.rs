@@ -3,14 +3,20 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::hint::black_box;
+use std::time::{Duration, Instant};
 
-use criterion::{Criterion, criterion_group, criterion_main};
+use cid::Cid;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::tuple::*;
 use fvm_ipld_hamt::Hamt;
 
 const BIT_WIDTH: u32 = 5;
 const ITEM_COUNT: u8 = 40;
+const FLUSH_ITEM_COUNT: u64 = 1000;
+
+/// The simulated fixed cost of each blockstore call (e.g., a syscall made by an actor).
+const CALL_COST: Duration = Duration::from_micros(1);
 
 // Struct to simulate a reasonable amount of data per value into the amt
 #[derive(Clone, Serialize_tuple, Deserialize_tuple, PartialEq)]
@@ -110,6 +116,75 @@ fn for_each_cacheless(c: &mut Criterion) {
     });
 }
 
+/// A blockstore paying [`CALL_COST`] per call. Unless `batched`, batches are written one block at
+/// a time, as HAMTs used to be flushed.
+struct CallCostBlockstore {
+    base: MemoryBlockstore,
+    batched: bool,
+}
+
+impl CallCostBlockstore {
+    fn call(&self) {
+        let start = Instant::now();
+        while start.elapsed() < CALL_COST {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+impl Blockstore for CallCostBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        self.call();
+        self.base.get(k)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.call();
+        self.base.put_keyed(k, block)
+    }
+
+    fn put_many_keyed<D, I>(&self, blocks: I) -> anyhow::Result<()>
+    where
+        Self: Sized,
+        D: AsRef<[u8]>,
+        I: IntoIterator<Item = (Cid, D)>,
+    {
+        if !self.batched {
+            for (k, block) in blocks {
+                self.put_keyed(&k, block.as_ref())?;
+            }
+            return Ok(());
+        }
+        self.call();
+        self.base.put_many_keyed(blocks)
+    }
+}
+
+fn flush(c: &mut Criterion) {
+    for (batched, writes) in [(false, "one write per node"), (true, "one batch")] {
+        c.bench_function(
+            &format!("HAMT flush of {FLUSH_ITEM_COUNT} entries ({writes})"),
+            |b| {
+                b.iter_batched(
+                    || {
+                        let db = CallCostBlockstore {
+                            base: MemoryBlockstore::default(),
+                            batched,
+                        };
+                        let mut a = Hamt::<_, u64>::new_with_bit_width(db, BIT_WIDTH);
+                        for i in 0..FLUSH_ITEM_COUNT {
+                            a.set(i.to_be_bytes().to_vec().into(), i).unwrap();
+                        }
+                        a
+                    },
+                    |mut a| black_box(a.flush().unwrap()),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+}
+
 fn setup_hamt<BS: Blockstore>(db: &BS) -> Hamt<&BS, BenchData> {
     let mut a = Hamt::<_, _>::new_with_bit_width(db, BIT_WIDTH);
     for i in 0..ITEM_COUNT {
@@ -124,6 +199,7 @@ criterion_group!(
     insert_load_flush,
     delete,
     for_each,
-    for_each_cacheless
+    for_each_cacheless,
+    flush
 );
 criterion_main!(benches);
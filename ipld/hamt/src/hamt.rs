@@ -7,8 +7,8 @@ use std::marker::PhantomData;
 
 use cid::Cid;
 use forest_hash_utils::BytesKey;
-use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::CborStore;
+use fvm_ipld_blockstore::{Block, Blockstore};
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
 use multihash_codetable::Code;
 use serde::de::DeserializeOwned;
 use serde::{Serialize, Serializer};
@@ -325,12 +325,32 @@ where
         if let Some(cid) = self.flushed_cid {
             return Ok(cid);
         }
-        self.root.flush(self.store.borrow())?;
-        let cid = self.store.put_cbor(&self.root, Code::Blake2b256)?;
+        let mut blocks = Vec::new();
+        let cid = match self.write_flushed(&mut blocks) {
+            Ok(cid) => cid,
+            Err(e) => {
+                // Nothing may link to blocks that weren't written.
+                self.root
+                    .unflush(&blocks.into_iter().map(|(cid, _)| cid).collect());
+                return Err(e);
+            }
+        };
         self.flushed_cid = Some(cid);
         Ok(cid)
     }
 
+    /// Flushes the root, writing the modified nodes (children before parents) and the root in a
+    /// single batch. The flushed nodes are collected in `blocks`, even on failure.
+    fn write_flushed(&mut self, blocks: &mut Vec<(Cid, Vec<u8>)>) -> Result<Cid, Error> {
+        self.root.flush(blocks)?;
+        let root = Block::new(DAG_CBOR, to_vec(&self.root)?);
+        let cid = root.cid(Code::Blake2b256);
+        blocks.push((cid, root.data));
+        self.store
+            .put_many_keyed(blocks.iter().map(|(cid, data)| (*cid, data)))?;
+        Ok(cid)
+    }
+
     /// Returns true if the HAMT has no entries
     pub fn is_empty(&self) -> bool {
         self.root.is_empty()
@@ -3,12 +3,13 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use std::borrow::Borrow;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
 use cid::Cid;
-use fvm_ipld_blockstore::Blockstore;
-use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_ipld_blockstore::{Block, Blockstore};
+use fvm_ipld_encoding::{CborStore, DAG_CBOR, to_vec};
 use multihash_codetable::Code;
 use once_cell::unsync::OnceCell;
 use serde::de::DeserializeOwned;
@@ -535,14 +536,18 @@ where
         }
     }
 
-    pub fn flush<S: Blockstore>(&mut self, store: &S) -> Result<(), Error> {
+    /// Encodes the dirty sub-nodes, replacing them with links, and collects their blocks (children
+    /// before parents) to be written in a single batch.
+    pub fn flush(&mut self, blocks: &mut Vec<(Cid, Vec<u8>)>) -> Result<(), Error> {
         for pointer in &mut self.pointers {
             if let Pointer::Dirty(node) = pointer {
                 // Flush cached sub node to clear it's cache
-                node.flush(store)?;
+                node.flush(blocks)?;
 
-                // Put node in blockstore and retrieve Cid
-                let cid = store.put_cbor(node, Code::Blake2b256)?;
+                // Encode the node and compute its Cid
+                let block = Block::new(DAG_CBOR, to_vec(node)?);
+                let cid = block.cid(Code::Blake2b256);
+                blocks.push((cid, block.data));
 
                 // Can keep the flushed node in link cache
                 let cache = OnceCell::from(std::mem::take(node));
@@ -555,6 +560,20 @@ where
         Ok(())
     }
 
+    /// Undoes a [`flush`](Self::flush) whose blocks weren't written, turning the links to the
+    /// given (unwritten) blocks back into dirty sub-nodes, so they're written by the next flush.
+    pub fn unflush(&mut self, unwritten: &HashSet<Cid>) {
+        for pointer in &mut self.pointers {
+            if let Pointer::Link { cid, cache } = pointer
+                && unwritten.contains(cid)
+                && let Some(mut node) = cache.take()
+            {
+                node.unflush(unwritten);
+                *pointer = Pointer::Dirty(node);
+            }
+        }
+    }
+
     fn rm_child(&mut self, i: usize, idx: u8) -> Pointer<K, V, H, Ver> {
         self.bitfield.clear_bit(idx);
         self.pointers.remove(i)
@@ -6,7 +6,7 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 
 use cid::Cid;
-use fvm_ipld_blockstore::tracking::{BSStats, TrackingBlockstore};
+use fvm_ipld_blockstore::tracking::{BSStats, BatchCountingBlockstore, TrackingBlockstore};
 use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
 use fvm_ipld_encoding::CborStore;
 use fvm_ipld_encoding::de::DeserializeOwned;
@@ -1310,6 +1310,46 @@ fn prop_cid_ops_reduced<const N: u32>(factory: HamtFactory, ops: LimitedKeyOps<N
     cid1 == cid2
 }
 
+#[test]
+fn flush_writes_one_batch() {
+    let store = BatchCountingBlockstore::<MemoryBlockstore>::default();
+    let mut hamt: Hamt<_, u64> = Hamt::new(&store);
+    for i in 0..1000 {
+        hamt.set(tstring(i), i).unwrap();
+    }
+    let cid = hamt.flush().unwrap();
+    assert_eq!((store.puts.get(), store.batches.get()), (0, 1));
+
+    // Only the modified nodes are written, again in one batch.
+    hamt.set(tstring(1000), 1000).unwrap();
+    let cid2 = hamt.flush().unwrap();
+    assert_ne!(cid, cid2);
+    assert_eq!((store.puts.get(), store.batches.get()), (0, 2));
+
+    let reloaded: Hamt<_, u64> = Hamt::load(&cid2, &store).unwrap();
+    for i in 0..=1000 {
+        assert_eq!(reloaded.get(&tstring(i)).unwrap(), Some(&i));
+    }
+}
+
+#[test]
+fn flush_retries_failed_batch() {
+    let store = BatchCountingBlockstore::<MemoryBlockstore>::default();
+    let mut hamt: Hamt<_, u64> = Hamt::new(&store);
+    for i in 0..1000 {
+        hamt.set(tstring(i), i).unwrap();
+    }
+    store.failing_batches.set(1);
+    hamt.flush().unwrap_err();
+
+    // The failed flush didn't leave links to unwritten nodes behind.
+    let cid = hamt.flush().unwrap();
+    let reloaded: Hamt<_, u64> = Hamt::load(&cid, &store).unwrap();
+    for i in 0..1000 {
+        assert_eq!(reloaded.get(&tstring(i)).unwrap(), Some(&i));
+    }
+}
+
 fn tstring(v: impl Display) -> BytesKey {
     BytesKey(v.to_string().into_bytes())
 }
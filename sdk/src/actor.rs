@@ -102,7 +102,36 @@ pub fn create_actor(
             .as_deref()
             .map(|v| (v.as_ptr(), v.len()))
             .unwrap_or((ptr::null(), 0));
-        sys::actor::create_actor(actor_id, cid.as_ptr(), addr_off, addr_len as u32)
+        create_actor_raw(actor_id, &cid, addr_off, addr_len as u32)
+    }
+}
+
+/// Calls `actor::create_actor` with the signature of the targeted syscall ABI version.
+#[cfg(not(feature = "syscall-abi-v2"))]
+unsafe fn create_actor_raw(
+    actor_id: ActorID,
+    code_cid: &[u8],
+    addr_off: *const u8,
+    addr_len: u32,
+) -> SyscallResult<()> {
+    unsafe { sys::actor::create_actor(actor_id, code_cid.as_ptr(), addr_off, addr_len) }
+}
+
+#[cfg(feature = "syscall-abi-v2")]
+unsafe fn create_actor_raw(
+    actor_id: ActorID,
+    code_cid: &[u8],
+    addr_off: *const u8,
+    addr_len: u32,
+) -> SyscallResult<()> {
+    unsafe {
+        sys::actor::v2::create_actor(
+            actor_id,
+            code_cid.as_ptr(),
+            code_cid.len() as u32,
+            addr_off,
+            addr_len,
+        )
     }
 }
 
@@ -121,11 +150,30 @@ pub fn upgrade_actor(
             None => NO_DATA_BLOCK_ID,
         };
 
-        let send = sys::actor::upgrade_actor(cid.as_ptr(), params_id)?;
+        let send = upgrade_actor_raw(&cid, params_id)?;
         build_response(send)
     }
 }
 
+/// Calls `actor::upgrade_actor` with the signature of the targeted syscall ABI version.
+#[cfg(all(feature = "upgrade-actor", not(feature = "syscall-abi-v2")))]
+unsafe fn upgrade_actor_raw(
+    new_code_cid: &[u8],
+    params_id: u32,
+) -> SyscallResult<sys::actor::Send> {
+    unsafe { sys::actor::upgrade_actor(new_code_cid.as_ptr(), params_id) }
+}
+
+#[cfg(all(feature = "upgrade-actor", feature = "syscall-abi-v2"))]
+unsafe fn upgrade_actor_raw(
+    new_code_cid: &[u8],
+    params_id: u32,
+) -> SyscallResult<sys::actor::Send> {
+    unsafe {
+        sys::actor::v2::upgrade_actor(new_code_cid.as_ptr(), new_code_cid.len() as u32, params_id)
+    }
+}
+
 /// Installs or ensures an actor code CID is valid and loaded.
 /// Note: this is a privileged syscall, restricted to the init actor.
 #[cfg(feature = "m2-native")]
@@ -168,3 +216,17 @@ pub fn balance_of(actor_id: ActorID) -> Option<TokenAmount> {
         }
     }
 }
+
+/// Gets the state root of a builtin actor, so its state can be read (but not modified) with the
+/// [`ipld`](crate::ipld) functions. Fails with [`ErrorNumber::Forbidden`] if the network doesn't
+/// allow actor state inspection or the actor isn't a builtin actor, and with
+/// [`ErrorNumber::NotFound`] if the actor doesn't exist.
+///
+/// Only available from network version 29.
+pub fn state_root_of(actor_id: ActorID) -> SyscallResult<Cid> {
+    let mut buf = [0u8; MAX_CID_LEN];
+    unsafe {
+        let len = sys::actor::get_actor_state_root(actor_id, buf.as_mut_ptr(), MAX_CID_LEN as u32)?;
+        Ok(Cid::read_bytes(&buf[..len as usize]).expect("runtime returned an invalid CID"))
+    }
+}
@@ -4,7 +4,7 @@ use cid::Cid;
 use fvm_ipld_encoding::to_vec;
 use fvm_shared::MAX_CID_LEN;
 use fvm_shared::address::Address;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultReport, MinerPower};
 use fvm_shared::crypto::{
     hash::SupportedHashes,
     signature::{
@@ -15,7 +15,7 @@ use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
-    WindowPoStVerifyInfo,
+    StoragePower, WindowPoStVerifyInfo,
 };
 use num_traits::FromPrimitive;
 
@@ -99,6 +99,113 @@ pub fn verify_signature(
     }
 }
 
+#[cfg(feature = "verify-signature")]
+/// Verifies that a signature is valid for an address and the domain-separated plaintext
+/// `tag || plaintext`, without building the concatenated plaintext in actor memory.
+///
+/// NOTE: This only supports f1 and f3 addresses.
+pub fn verify_signature_tagged(
+    signature: &Signature,
+    signer: &Address,
+    tag: &[u8],
+    plaintext: &[u8],
+) -> SyscallResult<bool> {
+    let sig_type = signature.signature_type();
+    let sig_bytes = signature.bytes();
+    let signer = signer.to_bytes();
+    unsafe {
+        sys::crypto::verify_signature_tagged(
+            sig_type as u32,
+            sig_bytes.as_ptr(),
+            sig_bytes.len() as u32,
+            signer.as_ptr(),
+            signer.len() as u32,
+            tag.as_ptr(),
+            tag.len() as u32,
+            plaintext.as_ptr(),
+            plaintext.len() as u32,
+        )
+        .map(status_code_to_bool)
+    }
+}
+
+#[cfg(not(feature = "verify-signature"))]
+/// Verifies that a signature is valid for an address and the domain-separated plaintext
+/// `tag || plaintext`.
+///
+/// NOTE: This only supports f1 and f3 addresses.
+pub fn verify_signature_tagged(
+    signature: &Signature,
+    signer: &Address,
+    tag: &[u8],
+    plaintext: &[u8],
+) -> SyscallResult<bool> {
+    verify_signature(signature, signer, &[tag, plaintext].concat())
+}
+
+#[cfg(feature = "verify-signature")]
+/// Verifies that a secp256k1 signature is valid for an address and the blake2b-256 `digest` of the
+/// signed data.
+///
+/// BLS signatures are verified over the message itself, not its digest, so this function returns
+/// [`ErrorNumber::IllegalArgument`] for BLS signatures and f3 signers.
+pub fn verify_signature_hashed(
+    signature: &Signature,
+    signer: &Address,
+    digest: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+) -> SyscallResult<bool> {
+    let sig_type = signature.signature_type();
+    let sig_bytes = signature.bytes();
+    let signer = signer.to_bytes();
+    unsafe {
+        sys::crypto::verify_signature_hashed(
+            sig_type as u32,
+            sig_bytes.as_ptr(),
+            sig_bytes.len() as u32,
+            signer.as_ptr(),
+            signer.len() as u32,
+            digest.as_ptr(),
+        )
+        .map(status_code_to_bool)
+    }
+}
+
+#[cfg(not(feature = "verify-signature"))]
+/// Verifies that a secp256k1 signature is valid for an address and the blake2b-256 `digest` of the
+/// signed data.
+///
+/// BLS signatures are verified over the message itself, not its digest, so this function returns
+/// [`ErrorNumber::IllegalArgument`] for BLS signatures and f3 signers.
+pub fn verify_signature_hashed(
+    signature: &Signature,
+    signer: &Address,
+    digest: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+) -> SyscallResult<bool> {
+    use fvm_shared::{address::Protocol, crypto::signature::SignatureType};
+
+    if signature.signature_type() != SignatureType::Secp256k1
+        || signer.protocol() != Protocol::Secp256k1
+    {
+        return Err(ErrorNumber::IllegalArgument);
+    }
+
+    let sig: &[u8; SECP_SIG_LEN] = signature
+        .bytes()
+        .try_into()
+        .map_err(|_| ErrorNumber::IllegalArgument)?;
+
+    let pub_key = recover_secp_public_key(digest, sig)?;
+    let addr_recovered = Address::new_secp256k1(&pub_key)
+        .expect("recovered secp256k1 public key should always be a valid secp256k1 address");
+
+    Ok(signer == &addr_recovered)
+}
+
+/// Verifies a BLS aggregate signature over `plaintexts`, where the i'th plaintext was signed by the
+/// i'th public key in `pub_keys`.
+///
+/// Returns `Ok(false)` if the signature is invalid, and [`ErrorNumber::IllegalArgument`] if the
+/// number of public keys and plaintexts differ.
 pub fn verify_bls_aggregate(
     sig: &[u8; BLS_SIG_LEN],
     pub_keys: &[[u8; BLS_PUB_LEN]],
@@ -150,7 +257,7 @@ pub fn hash_blake2b(data: &[u8]) -> [u8; 32] {
     const BLAKE2B_256: u64 = 0xb220;
     // This can only fail if we manage to pass in corrupted memory.
     let mut ret = [0u8; 32];
-    unsafe {
+    let written = unsafe {
         sys::crypto::hash(
             BLAKE2B_256,
             data.as_ptr(),
@@ -160,6 +267,7 @@ pub fn hash_blake2b(data: &[u8]) -> [u8; 32] {
         )
     }
     .expect("failed to compute blake2b hash");
+    assert_eq!(written, 32, "expected a 32 byte blake2b digest");
     ret
 }
 
@@ -201,6 +309,22 @@ pub fn hash_into(hasher: SupportedHashes, data: &[u8], digest: &mut [u8]) -> usi
 }
 
 /// Computes an unsealed sector CID (CommD) from its constituent piece CIDs (CommPs) and sizes.
+///
+/// Any space left in the sector after the supplied pieces is filled with zero pieces.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::compute_unsealed_sector_cid;
+/// use fvm_shared::piece::{PaddedPieceSize, PieceInfo};
+/// use fvm_shared::sector::RegisteredSealProof;
+///
+/// let pieces = [PieceInfo {
+///     size: PaddedPieceSize(1024),
+///     cid: piece_cid,
+/// }];
+/// let comm_d = compute_unsealed_sector_cid(RegisteredSealProof::StackedDRG2KiBV1P1, &pieces)?;
+/// ```
 pub fn compute_unsealed_sector_cid(
     proof_type: RegisteredSealProof,
     pieces: &[PieceInfo],
@@ -226,12 +350,112 @@ pub fn compute_unsealed_sector_cid(
     }
 }
 
+/// Computes the unsealed sector CIDs (CommDs) of a batch of sectors in a single syscall, given each
+/// sector's seal proof type and pieces.
+///
+/// Returns one result per entry (in order). Entries are validated individually: an invalid entry
+/// yields the error [`compute_unsealed_sector_cid`] would have returned for it, without failing the
+/// rest of the batch.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::compute_unsealed_sector_cids;
+/// use fvm_shared::sector::RegisteredSealProof;
+///
+/// let results = compute_unsealed_sector_cids(&[
+///     (RegisteredSealProof::StackedDRG32GiBV1P1, &sector1_pieces),
+///     (RegisteredSealProof::StackedDRG32GiBV1P1, &sector2_pieces),
+/// ])?;
+/// ```
+pub fn compute_unsealed_sector_cids(
+    batch: &[(RegisteredSealProof, &[PieceInfo])],
+) -> SyscallResult<Vec<Result<Cid, ErrorNumber>>> {
+    let encoded = to_vec(batch).expect("failed to marshal unsealed sector CID batch");
+
+    let mut results = vec![sys::crypto::ComputeUnsealedSectorCid::default(); batch.len()];
+    unsafe {
+        sys::crypto::compute_unsealed_sector_cids(
+            encoded.as_ptr(),
+            encoded.len() as u32,
+            results.as_mut_ptr(),
+        )?;
+    }
+    Ok(results
+        .into_iter()
+        .map(|res| {
+            let (error, len) = (res.error, res.cid_len as usize);
+            if error != 0 {
+                return Err(ErrorNumber::from_u32(error).unwrap_or_else(|| {
+                    panic!("runtime returned an invalid error number: {error}")
+                }));
+            }
+            let cid = res.cid;
+            assert!(len <= cid.len(), "CID too large: {} > {}", len, cid.len());
+            Ok(Cid::read_bytes(&cid[..len]).expect("runtime returned an invalid CID"))
+        })
+        .collect())
+}
+
 /// Verifies a window proof of spacetime.
+///
+/// Returns `Ok(false)` if the proof is invalid, and [`ErrorNumber::IllegalArgument`] if the
+/// verification info is malformed (e.g., mixes proof types).
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::verify_post;
+/// use fvm_shared::sector::WindowPoStVerifyInfo;
+///
+/// let info = WindowPoStVerifyInfo {
+///     randomness,
+///     proofs,
+///     challenged_sectors,
+///     prover: fvm_sdk::message::receiver(),
+/// };
+/// if !verify_post(&info)? {
+///     fvm_sdk::vm::abort(USR_ILLEGAL_ARGUMENT, Some("invalid window post"));
+/// }
+/// ```
 pub fn verify_post(info: &WindowPoStVerifyInfo) -> SyscallResult<bool> {
     let info = to_vec(info).expect("failed to marshal PoSt verification input");
     unsafe { sys::crypto::verify_post(info.as_ptr(), info.len() as u32).map(status_code_to_bool) }
 }
 
+/// Verifies a batch of window proofs of spacetime in a single syscall, returning one result per
+/// proof (in order).
+///
+/// Each proof is charged for as if verified with [`verify_post`], but the proofs are verified in
+/// parallel. Unlike [`verify_post`], malformed verification info yields `false` instead of failing
+/// the whole batch.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::verify_post_batch;
+///
+/// let results = verify_post_batch(&infos)?;
+/// for (info, valid) in infos.iter().zip(results) {
+///     // Fault the partitions with invalid proofs.
+/// }
+/// ```
+pub fn verify_post_batch(batch: &[WindowPoStVerifyInfo]) -> SyscallResult<Vec<bool>> {
+    let encoded = to_vec(batch).expect("failed to marshal batch PoSt verification input");
+
+    let mut result = vec![0u8; batch.len().div_ceil(8)];
+    unsafe {
+        sys::crypto::verify_post_batch(
+            encoded.as_ptr(),
+            encoded.len() as u32,
+            result.as_mut_ptr(),
+        )?;
+    }
+    Ok((0..batch.len())
+        .map(|i| result[i / 8] & (1 << (i % 8)) != 0)
+        .collect())
+}
+
 /// Verifies that two block headers provide proof of a consensus fault:
 /// - both headers mined by the same actor
 /// - headers are different
@@ -242,7 +466,17 @@ pub fn verify_post(info: &WindowPoStVerifyInfo) -> SyscallResult<bool> {
 /// The parameters are all serialized block headers. The third "extra" parameter is consulted only for
 /// the "parent grinding fault", in which case it must be the sibling of h1 (same parent tipset) and one of the
 /// blocks in the parent of h2 (i.e. h2's grandparent).
-/// Returns None and an error if the headers don't prove a fault.
+/// Returns `Ok(None)` if the headers don't prove a fault.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::verify_consensus_fault;
+///
+/// if let Some(fault) = verify_consensus_fault(&header1, &header2, &[])? {
+///     // Slash `fault.target` for the fault committed at `fault.epoch`.
+/// }
+/// ```
 pub fn verify_consensus_fault(
     h1: &[u8],
     h2: &[u8],
@@ -267,13 +501,71 @@ pub fn verify_consensus_fault(
     }
     let fault_type =
         FromPrimitive::from_u32(fault).expect("received an invalid fault type from the runtime");
-    Ok(Some(ConsensusFault {
+    Ok(Some(ConsensusFault::new(target, epoch, fault_type)))
+}
+
+/// Verifies that two block headers provide proof of a consensus fault, like
+/// [`verify_consensus_fault`]. If they do, also returns the at-fault miner's power at the fault
+/// epoch, which actors need to process the fault report but can't look up themselves.
+///
+/// Only available from network version 29.
+///
+/// Returns `Ok(None)` if the headers don't prove a fault.
+pub fn verify_consensus_fault_report(
+    h1: &[u8],
+    h2: &[u8],
+    extra: &[u8],
+) -> SyscallResult<Option<ConsensusFaultReport>> {
+    let fvm_shared::sys::out::crypto::VerifyConsensusFaultReport {
+        fault,
         epoch,
-        target: Address::new_id(target),
-        fault_type,
+        target,
+        raw_byte_power,
+        quality_adj_power,
+    } = unsafe {
+        sys::crypto::verify_consensus_fault_report(
+            h1.as_ptr(),
+            h1.len() as u32,
+            h2.as_ptr(),
+            h2.len() as u32,
+            extra.as_ptr(),
+            extra.len() as u32,
+        )?
+    };
+    if fault == 0 {
+        return Ok(None);
+    }
+    let fault_type =
+        FromPrimitive::from_u32(fault).expect("received an invalid fault type from the runtime");
+    let power = |[lo, hi]: [u64; 2]| StoragePower::from(((hi as u128) << u64::BITS) | lo as u128);
+    Ok(Some(ConsensusFaultReport {
+        fault: ConsensusFault::new(target, epoch, fault_type),
+        power: MinerPower {
+            raw_byte_power: power(raw_byte_power),
+            quality_adj_power: power(quality_adj_power),
+        },
     }))
 }
 
+/// Verifies an aggregated batch of prove-commits.
+///
+/// Returns `Ok(false)` if the aggregate proof is invalid.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::verify_aggregate_seals;
+/// use fvm_shared::sector::{AggregateSealVerifyProofAndInfos, RegisteredAggregateProof};
+///
+/// let info = AggregateSealVerifyProofAndInfos {
+///     miner: fvm_sdk::message::receiver(),
+///     seal_proof,
+///     aggregate_proof: RegisteredAggregateProof::SnarkPackV2,
+///     proof: aggregate_proof_bytes,
+///     infos,
+/// };
+/// let valid = verify_aggregate_seals(&info)?;
+/// ```
 pub fn verify_aggregate_seals(info: &AggregateSealVerifyProofAndInfos) -> SyscallResult<bool> {
     let info = to_vec(info).expect("failed to marshal aggregate seal verification input");
     unsafe {
@@ -282,6 +574,24 @@ pub fn verify_aggregate_seals(info: &AggregateSealVerifyProofAndInfos) -> Syscal
     }
 }
 
+/// Verifies a replica update (an upgrade from a CC sector to a sector with deals).
+///
+/// Returns `Ok(false)` if the update proof is invalid.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::verify_replica_update;
+/// use fvm_shared::sector::ReplicaUpdateInfo;
+///
+/// let valid = verify_replica_update(&ReplicaUpdateInfo {
+///     update_proof_type,
+///     old_sealed_cid,
+///     new_sealed_cid,
+///     new_unsealed_cid,
+///     proof,
+/// })?;
+/// ```
 pub fn verify_replica_update(info: &ReplicaUpdateInfo) -> SyscallResult<bool> {
     let info = to_vec(info).expect("failed to marshal replica update verification input");
     unsafe {
@@ -290,17 +600,42 @@ pub fn verify_replica_update(info: &ReplicaUpdateInfo) -> SyscallResult<bool> {
     }
 }
 
+/// Verifies a batch of sector seal proofs, returning one result per seal (in order).
+///
+/// This is a privileged operation intended for the power actor during cron.
+///
+/// # Example
+///
+/// ```ignore
+/// use fvm_sdk::crypto::batch_verify_seals;
+///
+/// let results = batch_verify_seals(&seals)?;
+/// for (seal, valid) in seals.iter().zip(results) {
+///     // Only activate sectors with valid proofs.
+/// }
+/// ```
 pub fn batch_verify_seals(batch: &[SealVerifyInfo]) -> SyscallResult<Vec<bool>> {
     let encoded = to_vec(batch).expect("failed to marshal batch seal verification input");
 
-    Ok(unsafe {
-        let mut result: Vec<bool> = Vec::with_capacity(batch.len());
+    // The runtime writes one byte per seal (1 for valid, 0 for invalid). We read into a byte buffer
+    // instead of a `Vec<bool>` so that an unexpected value can't produce an invalid `bool`.
+    let mut result = vec![0u8; batch.len()];
+    unsafe {
         sys::crypto::batch_verify_seals(
             encoded.as_ptr(),
             encoded.len() as u32,
-            result.as_mut_ptr() as *mut u8,
+            result.as_mut_ptr(),
         )?;
-        result.set_len(batch.len());
-        result
-    })
+    }
+    Ok(result
+        .into_iter()
+        .map(|r| match r {
+            0 => false,
+            1 => true,
+            _ => panic!(
+                "runtime returned an invalid seal verification result: {}",
+                r
+            ),
+        })
+        .collect())
 }
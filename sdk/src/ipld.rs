@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use cid::Cid;
 use cid::multihash::Multihash;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ser::Serialize;
+use fvm_ipld_encoding::{CBOR, DAG_CBOR, IPLD_RAW};
 use fvm_shared::MAX_CID_LEN;
+use fvm_shared::crypto::hash::SupportedHashes;
 use fvm_shared::error::ErrorNumber;
 
 use crate::{SyscallResult, sys};
@@ -10,9 +14,71 @@ use crate::{SyscallResult, sys};
 /// The unit/void object.
 pub const UNIT: u32 = sys::ipld::UNIT;
 
+/// The maximum size of a block, in bytes, under the default network limits. Creating a larger
+/// block fails with [`ErrorNumber::LimitExceeded`], so actors storing more data than this must split
+/// it across multiple blocks.
+pub const MAX_BLOCK_SIZE: u32 = fvm_shared::MAX_BLOCK_SIZE;
+
+/// The multihash code used by the typed helpers ([`put_cbor`] and [`put_raw`]): blake2b-256.
+pub const DEFAULT_HASH_CODE: u64 = SupportedHashes::Blake2b256 as u64;
+
+/// Encodes a value as DAG-CBOR and stores it, hashed with blake2b-256. Like [`put_with_hash`],
+/// the block is only persisted if it's linked into the actor's state-tree.
+pub fn put_cbor<T: Serialize + ?Sized>(value: &T) -> SyscallResult<Cid> {
+    let data = fvm_ipld_encoding::to_vec(value).map_err(|_| ErrorNumber::Serialization)?;
+    put_with_hash(DEFAULT_HASH_CODE, DAG_CBOR, &data)
+}
+
+/// Stores raw bytes, hashed with blake2b-256. Like [`put_with_hash`], the block is only persisted
+/// if it's linked into the actor's state-tree.
+pub fn put_raw(data: &[u8]) -> SyscallResult<Cid> {
+    put_with_hash(DEFAULT_HASH_CODE, IPLD_RAW, data)
+}
+
+/// Gets a block (see [`get`]) and decodes it from CBOR. Returns `None` if the block isn't in the
+/// reachable set.
+///
+/// Fails with [`ErrorNumber::IllegalCodec`] if the CID's codec isn't CBOR or DAG-CBOR, and with
+/// [`ErrorNumber::Serialization`] if the block can't be decoded.
+pub fn get_cbor<T: DeserializeOwned>(cid: &Cid) -> SyscallResult<Option<T>> {
+    if !matches!(cid.codec(), CBOR | DAG_CBOR) {
+        return Err(ErrorNumber::IllegalCodec);
+    }
+    let data = match get(cid) {
+        Ok(data) => data,
+        Err(ErrorNumber::NotFound) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    fvm_ipld_encoding::from_slice(&data)
+        .map(Some)
+        .map_err(|_| ErrorNumber::Serialization)
+}
+
+/// Store a block, hashed with the specified multihash function. The block will only be persisted
+/// in the state-tree if the CID is "linked in" to the actor's state-tree before the end of the
+/// current invocation.
+///
+/// The digest size is that of the hash function (or the size of the data, for identity hashes).
+/// Fails with [`ErrorNumber::IllegalCid`] if the hash function isn't supported.
+pub fn put_with_hash(mh_code: u64, codec: u64, data: &[u8]) -> SyscallResult<Cid> {
+    let mh_size = if mh_code == fvm_shared::IDENTITY_HASH {
+        data.len() as u32
+    } else {
+        SupportedHashes::try_from(mh_code)
+            .map_err(|_| ErrorNumber::IllegalCid)?
+            .digest_size()
+    };
+    put_sized(mh_code, mh_size, codec, data)
+}
+
 /// Store a block. The block will only be persisted in the state-tree if the CID is "linked in" to
 /// the actor's state-tree before the end of the current invocation.
+#[deprecated = "the digest size is implied by the hash function: use `put_with_hash`, `put_cbor`, or `put_raw`"]
 pub fn put(mh_code: u64, mh_size: u32, codec: u64, data: &[u8]) -> SyscallResult<Cid> {
+    put_sized(mh_code, mh_size, codec, data)
+}
+
+fn put_sized(mh_code: u64, mh_size: u32, codec: u64, data: &[u8]) -> SyscallResult<Cid> {
     // Short-circuit for identity hashes. There's nothing to "put" as the block will be embedded in
     // the CID directly.
     if mh_code == fvm_shared::IDENTITY_HASH {
@@ -60,14 +126,44 @@ pub fn get(cid: &Cid) -> SyscallResult<Vec<u8>> {
             .expect("CID encoding should not fail");
         let fvm_shared::sys::out::ipld::IpldOpen { id, size, .. } =
             sys::ipld::block_open(cid_buf.as_mut_ptr())?;
-        let mut block = Vec::with_capacity(size as usize);
-        let remaining = sys::ipld::block_read(id, 0, block.as_mut_ptr(), size)?;
-        assert_eq!(remaining, 0, "expected to read the block exactly");
-        block.set_len(size as usize);
-        Ok(block)
+        get_block_exact(id, size)
     }
 }
 
+/// Returns the size of a block in bytes, without loading it. Like [`get`], this only works on
+/// blocks in the reachable set, returning `None` for any other block (even if it exists in the
+/// state-tree).
+///
+/// Only available from network version 29.
+pub fn get_size(cid: &Cid) -> SyscallResult<Option<u32>> {
+    // Short-circuit for identity hashes. The block is inlined in the hash itself.
+    if cid.hash().code() == fvm_shared::IDENTITY_HASH {
+        return Ok(Some(cid.hash().digest().len() as u32));
+    }
+
+    let mut cid_buf = [0u8; MAX_CID_LEN];
+    cid.write_bytes(&mut cid_buf[..])
+        .expect("CID encoding should not fail");
+    match unsafe { sys::ipld::block_stat_by_cid(cid_buf.as_ptr()) } {
+        Ok(stat) => Ok(Some(stat.size)),
+        Err(ErrorNumber::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks if a block is in the reachable set (see [`get`]), without loading it.
+///
+/// Only available from network version 29.
+pub fn has(cid: &Cid) -> SyscallResult<bool> {
+    get_size(cid).map(|size| size.is_some())
+}
+
+/// Returns the codec and size of an open block, along with the number of (reachable) blocks it
+/// links to. This lets actors walk a DAG without parsing blocks themselves.
+pub fn stat(id: fvm_shared::sys::BlockId) -> SyscallResult<sys::ipld::IpldStatLinks> {
+    unsafe { sys::ipld::block_stat_links(id) }
+}
+
 /// Gets the data of the block referenced by BlockId. If the caller knows the size, this function
 /// will read the block in a single syscall. Otherwise, any block over 1KiB will take two syscalls.
 pub fn get_block(id: fvm_shared::sys::BlockId, size_hint: Option<u32>) -> SyscallResult<Vec<u8>> {
@@ -75,30 +171,68 @@ pub fn get_block(id: fvm_shared::sys::BlockId, size_hint: Option<u32>) -> Syscal
     if id == UNIT {
         return Ok(Vec::new());
     }
+    read_block(id, size_hint.unwrap_or(1024))
+}
 
-    let mut buf = Vec::with_capacity(size_hint.unwrap_or(1024) as usize);
-    unsafe {
-        let mut remaining = sys::ipld::block_read(id, 0, buf.as_mut_ptr(), buf.capacity() as u32)?;
-        if remaining > 0 {
-            buf.set_len(buf.capacity());
-            buf.reserve_exact(remaining as usize);
-            remaining = sys::ipld::block_read(
+/// Gets the data of the block referenced by BlockId, which the caller knows (e.g., from
+/// [`sys::ipld::block_stat`]) to be exactly `size` bytes. Returns
+/// [`ErrorNumber::IllegalOperation`] if the block turns out to be some other size.
+pub(crate) fn get_block_exact(id: fvm_shared::sys::BlockId, size: u32) -> SyscallResult<Vec<u8>> {
+    let block = get_block(id, Some(size))?;
+    if block.len() != size as usize {
+        return Err(ErrorNumber::IllegalOperation);
+    }
+    Ok(block)
+}
+
+/// Reads the block referenced by BlockId into `buf`, starting at `offset` in the block, and returns
+/// the number of bytes copied. This lets actors process large blocks in fixed-size windows without
+/// loading them whole.
+///
+/// Reads straddling the end of the block are truncated, and reads starting at or past the end of
+/// the block copy nothing (returning 0). Gas is only charged for the bytes copied.
+///
+/// Fails with [`ErrorNumber::IllegalArgument`] if `offset + buf.len()` exceeds `i32::MAX`.
+pub fn read_at(id: fvm_shared::sys::BlockId, offset: u32, buf: &mut [u8]) -> SyscallResult<u32> {
+    // The "empty" block has no data to read.
+    if id == UNIT {
+        return Ok(0);
+    }
+    let len = u32::try_from(buf.len()).map_err(|_| ErrorNumber::IllegalArgument)?;
+    let remaining = unsafe { sys::ipld::block_read(id, offset, buf.as_mut_ptr(), len)? };
+    // The return value is relative to the end of the buffer: if it's negative, the block ends
+    // that many bytes before it (possibly before the start of the buffer).
+    Ok((len as i64 + remaining.min(0) as i64).max(0) as u32)
+}
+
+/// Reads a block into a buffer with the given initial capacity, growing it until the whole block
+/// has been read.
+fn read_block(id: fvm_shared::sys::BlockId, capacity: u32) -> SyscallResult<Vec<u8>> {
+    let mut buf = Vec::<u8>::with_capacity(capacity as usize);
+    loop {
+        let offset = buf.len();
+        let remaining = unsafe {
+            sys::ipld::block_read(
                 id,
-                buf.len() as u32,
-                buf.as_mut_ptr_range().end,
-                (buf.capacity() - buf.len()) as u32,
-            )?;
-            assert!(remaining <= 0, "should have read whole block");
+                offset as u32,
+                buf.as_mut_ptr().add(offset),
+                (buf.capacity() - offset) as u32,
+            )?
+        };
+        // The return value is relative to the end of the buffer's capacity: if it's positive,
+        // we filled the buffer and there's more to read. Otherwise, the block ends within it.
+        let end = buf.capacity() as i64 + remaining.min(0) as i64;
+        if end < offset as i64 {
+            // The block is shorter than what we've already read.
+            return Err(ErrorNumber::IllegalOperation);
+        }
+        // SAFETY: the runtime initialized every byte from `offset` to `end`.
+        unsafe { buf.set_len(end as usize) };
+        if remaining <= 0 {
+            return Ok(buf);
         }
-        let size = (buf.capacity() as i64) + (remaining as i64);
-        assert!(size >= 0, "size can't be negative");
-        assert!(
-            size <= buf.capacity() as i64,
-            "size shouldn't exceed capacity"
-        );
-        buf.set_len(size as usize);
+        buf.reserve_exact(remaining as usize);
     }
-    Ok(buf)
 }
 
 /// Writes the supplied block and returns the BlockId.
@@ -15,10 +15,42 @@ pub mod sself;
 pub mod sys;
 pub mod vm;
 
+/// Method numbers derived from method names (FRC-0042).
+pub use fvm_shared::{method, method_hash};
+
 /// BlockID representing nil parameters or return data.
 pub const NO_DATA_BLOCK_ID: u32 = 0;
 
-// TODO: provide a custom panic handler?
+/// The syscall ABI version targeted by the SDK's syscall bindings: 2 with the `syscall-abi-v2`
+/// feature, 1 otherwise. Actors declare it with [`declare_syscall_abi`].
+pub const SYSCALL_ABI_VERSION: u32 = if cfg!(feature = "syscall-abi-v2") {
+    2
+} else {
+    1
+};
+
+// The declaration below is a single LEB128 byte.
+const _: () = assert!(SYSCALL_ABI_VERSION < 0x80);
+
+/// Declares the syscall ABI version targeted by the SDK ([`SYSCALL_ABI_VERSION`]) in the actor's
+/// Wasm module, by emitting a [`fvm_shared::sys::SYSCALL_ABI_SECTION`] custom section. Invoke it
+/// once, in the actor's crate:
+///
+/// ```ignore
+/// fvm_sdk::declare_syscall_abi!();
+/// ```
+///
+/// Actors built with the `syscall-abi-v2` feature must declare it, or they'll fail to link. The FVM
+/// only reads declarations from nv29; before, all actors target version 1.
+#[macro_export]
+macro_rules! declare_syscall_abi {
+    () => {
+        #[cfg(target_arch = "wasm32")]
+        #[used]
+        #[unsafe(link_section = "fvm_syscall_abi")]
+        static __FVM_SYSCALL_ABI: [u8; 1] = [$crate::SYSCALL_ABI_VERSION as u8];
+    };
+}
 
 #[inline]
 pub(crate) fn status_code_to_bool(code: i32) -> bool {
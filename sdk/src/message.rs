@@ -16,7 +16,8 @@ lazy_static::lazy_static! {
     };
 }
 
-/// Returns the nonce from the (explicit) message.
+/// Returns the nonce from the (explicit) message. This is the same for every call made while
+/// executing the message.
 #[inline(always)]
 pub fn nonce() -> u64 {
     MESSAGE_CONTEXT.nonce
@@ -28,7 +29,8 @@ pub fn caller() -> ActorID {
     MESSAGE_CONTEXT.caller
 }
 
-/// Returns the ID address of the origin
+/// Returns the ID address of the origin (the account that signed the top-level message). This is
+/// the same for every call made while executing the message.
 #[inline(always)]
 pub fn origin() -> ActorID {
     MESSAGE_CONTEXT.origin
@@ -52,12 +54,18 @@ pub fn value_received() -> TokenAmount {
     MESSAGE_CONTEXT.value_received.into()
 }
 
-/// Returns the execution gas premium
+/// Returns the effective gas premium of the message being executed, capped such that the base fee
+/// plus the premium doesn't exceed the message's fee cap.
+#[inline(always)]
 pub fn gas_premium() -> TokenAmount {
     MESSAGE_CONTEXT.gas_premium.into()
 }
 
 /// Returns the message parameters as an [`Option<IpldBlock>`].
+///
+/// The parameters are read in a single syscall, into a buffer allocated at their exact size. To
+/// process large parameters without loading them whole, read them in windows with
+/// [`crate::ipld::read_at`] instead.
 pub fn params_raw(id: BlockId) -> SyscallResult<Option<IpldBlock>> {
     if id == NO_DATA_BLOCK_ID {
         return Ok(None);
@@ -66,7 +74,7 @@ pub fn params_raw(id: BlockId) -> SyscallResult<Option<IpldBlock>> {
         let fvm_shared::sys::out::ipld::IpldStat { codec, size } = sys::ipld::block_stat(id)?;
         Ok(Some(IpldBlock {
             codec,
-            data: crate::ipld::get_block(id, Some(size))?,
+            data: crate::ipld::get_block_exact(id, size)?,
         }))
     }
 }
@@ -6,7 +6,7 @@ use fvm_shared::econ::TokenAmount;
 use fvm_shared::error::ErrorNumber;
 
 use crate::error::{ActorDeleteError, StateReadError, StateUpdateError};
-use crate::sys;
+use crate::{SyscallResult, sys};
 
 /// Get the IPLD root CID. Fails if the actor doesn't have state (before the first call to
 /// `set_root` and after actor deletion).
@@ -28,18 +28,30 @@ pub fn root() -> Result<Cid, StateReadError> {
 ///
 /// - The new root is not in the actor's "reachable" set.
 /// - Fails if the actor has been deleted.
+/// - With the `syscall-abi-v2` feature, the new root isn't a CIDv1 with the DAG-CBOR or raw codec,
+///   and a 32-byte blake2b-256 or an identity multihash.
 pub fn set_root(cid: &Cid) -> Result<(), StateUpdateError> {
     let mut buf = [0u8; MAX_CID_LEN];
-    cid.write_bytes(&mut buf[..])
+    let len = cid
+        .write_bytes(&mut buf[..])
         .expect("CID encoding should not fail");
 
-    unsafe {
-        sys::sself::set_root(buf.as_ptr()).map_err(|e| match e {
-            ErrorNumber::IllegalOperation => StateUpdateError::ActorDeleted,
-            ErrorNumber::ReadOnly => StateUpdateError::ReadOnly,
-            e => panic!("unexpected error from `self::set_root` syscall: {}", e),
-        })
-    }
+    unsafe { set_root_raw(&buf[..len]) }.map_err(|e| match e {
+        ErrorNumber::IllegalOperation => StateUpdateError::ActorDeleted,
+        ErrorNumber::ReadOnly => StateUpdateError::ReadOnly,
+        e => panic!("unexpected error from `self::set_root` syscall: {}", e),
+    })
+}
+
+/// Calls `self::set_root` with the signature of the targeted syscall ABI version.
+#[cfg(not(feature = "syscall-abi-v2"))]
+unsafe fn set_root_raw(cid: &[u8]) -> SyscallResult<()> {
+    unsafe { sys::sself::set_root(cid.as_ptr()) }
+}
+
+#[cfg(feature = "syscall-abi-v2")]
+unsafe fn set_root_raw(cid: &[u8]) -> SyscallResult<()> {
+    unsafe { sys::sself::v2::set_root(cid.as_ptr(), cid.len() as u32) }
 }
 
 /// Gets the current balance for the calling actor.
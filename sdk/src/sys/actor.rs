@@ -121,6 +121,7 @@ super::fvm_syscalls! {
 
     /// Creates a new actor in the state-tree with the specified actor ID, recording the specified
     /// "delegated" address in the actor root if non-empty, and returning a new stable address.
+    /// Actors targeting syscall ABI version 2 or later must use [`v2::create_actor`] instead.
     ///
     /// **Privileged:** May only be called by the init actor.
     #[doc(hidden)]
@@ -137,7 +138,9 @@ super::fvm_syscalls! {
     ///
     /// # Parameters
     ///
-    /// - `new_code_cid_off` is the offset (in wasm memory) of the code CID to upgrade _to_.
+    /// - `new_code_cid_off` is the offset (in wasm memory) of the code CID to upgrade _to_. Actors
+    ///   targeting syscall ABI version 2 or later (see [`fvm_shared::sys::SYSCALL_ABI_SECTION`])
+    ///   must use [`v2::upgrade_actor`] instead.
     /// - `params` is the IPLD block handle passed to the new code's `upgrade` wasm endpoint.
     ///
     /// # Returns
@@ -160,6 +163,7 @@ super::fvm_syscalls! {
     /// | [`LimitExceeded`]     | recursion limit reached.                                        |
     /// | [`IllegalArgument`]   | invalid code cid buffer.                                        |
     /// | [`Forbidden`]         | the actor is not allowed to upgrade (e.g., due to re-entrency). |
+    /// | [`Forbidden`]         | the new code is builtin actor code.                             |
     /// | [`ReadOnly`]          | the actor is executing in read-only mode.                       |
     #[cfg(feature = "upgrade-actor")]
     pub fn upgrade_actor(
@@ -186,4 +190,87 @@ super::fvm_syscalls! {
     pub fn balance_of(
         actor_id: u64
     )  -> Result<super::TokenAmount>;
+
+    /// Gets the state root of a builtin actor, making it reachable so that the actor's state can be
+    /// read (but not modified) with the `ipld` syscalls.
+    ///
+    /// Only available from network version 29.
+    ///
+    /// # Arguments
+    ///
+    /// - `actor_id` is the ID of the target actor.
+    /// - `obuf_off` and `obuf_len` specify the location and length of a byte buffer into which the
+    ///   FVM will write the actor's state root.
+    ///
+    /// # Returns
+    ///
+    /// The length of the CID.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                    |
+    /// |---------------------|-----------------------------------------------------------|
+    /// | [`NotFound`]        | the target actor does not exist                           |
+    /// | [`Forbidden`]       | the network doesn't allow actor state inspection          |
+    /// | [`Forbidden`]       | the target actor isn't a builtin actor                    |
+    /// | [`BufferTooSmall`]  | if the output buffer isn't large enough to fit the CID    |
+    /// | [`IllegalArgument`] | if the output buffer isn't valid, in memory, etc.         |
+    pub fn get_actor_state_root(
+        actor_id: u64,
+        obuf_off: *mut u8,
+        obuf_len: u32,
+    ) -> Result<u32>;
+}
+
+/// The syscalls of this module whose signatures changed in syscall ABI version 2. Only actors
+/// targeting that version (see [`fvm_shared::sys::SYSCALL_ABI_SECTION`]) may import them.
+///
+/// These take the code CID's length after its offset, and fail with `IllegalCid` unless the CID is
+/// a CIDv1 with the DAG-CBOR or raw codec, and a 32-byte blake2b-256 or an identity multihash.
+#[allow(clashing_extern_declarations)]
+pub mod v2 {
+    #[doc(inline)]
+    pub use fvm_shared::sys::out::send::*;
+
+    // for documentation links
+    #[cfg(doc)]
+    use crate::sys::ErrorNumber::*;
+
+    crate::sys::fvm_syscalls! {
+        module = "actor";
+
+        /// Like [`super::create_actor`], but takes the code CID's length.
+        ///
+        /// **Privileged:** May only be called by the init actor.
+        #[doc(hidden)]
+        pub fn create_actor(
+            actor_id: u64,
+            typ_off: *const u8,
+            typ_len: u32,
+            delegated_addr_off: *const u8,
+            delegated_addr_len: u32,
+        ) -> Result<()>;
+
+        /// Like [`super::upgrade_actor`], but takes the code CID's length.
+        ///
+        /// # Errors
+        ///
+        /// | Error                 | Reason                                                          |
+        /// |-----------------------|-----------------------------------------------------------------|
+        /// | [`NotFound`]          | no code with the specified CID has been deployed.               |
+        /// | [`IllegalOperation`]  | the actor has been deleted.                                     |
+        /// | [`InvalidHandle`]     | parameters block not found.                                     |
+        /// | [`LimitExceeded`]     | recursion limit reached.                                        |
+        /// | [`IllegalArgument`]   | invalid code cid buffer.                                        |
+        /// | [`IllegalCid`]        | the code CID isn't allowed.                                     |
+        /// | [`Forbidden`]         | the actor is not allowed to upgrade (e.g., due to re-entrency). |
+        /// | [`Forbidden`]         | the new code is builtin actor code.                             |
+        /// | [`ReadOnly`]          | the actor is executing in read-only mode.                       |
+        #[cfg(feature = "upgrade-actor")]
+        pub fn upgrade_actor(
+            new_code_cid_off: *const u8,
+            new_code_cid_len: u32,
+            params: u32,
+        ) -> Result<Send>;
+    }
 }
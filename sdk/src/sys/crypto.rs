@@ -39,6 +39,68 @@ super::fvm_syscalls! {
         plaintext_len: u32,
     ) -> Result<i32>;
 
+    /// Verifies that a signature is valid for an f1 or f3 address and the domain-separated
+    /// plaintext `tag || plaintext`. The tag and plaintext are concatenated (and hashed, for
+    /// secp256k1 signatures) by the FVM.
+    ///
+    /// Only available from network version 29.
+    ///
+    /// Returns 0 on success, or -1 if the signature fails to validate.
+    ///
+    /// # Arguments
+    ///
+    /// - `sig_off` and `sig_len` specify location and length of the signature.
+    /// - `addr_off` and `addr_len` specify location and length of expected signer's address.
+    /// - `tag_off` and `tag_len` specify location and length of the domain separation tag.
+    /// - `plaintext_off` and `plaintext_len` specify location and length of the signed data.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                    |
+    /// |---------------------|-----------------------------------------------------------|
+    /// | [`IllegalArgument`] | signature, address, tag, or plaintext buffers are invalid |
+    #[cfg(feature = "verify-signature")]
+    pub fn verify_signature_tagged(
+        sig_type: u32,
+        sig_off: *const u8,
+        sig_len: u32,
+        addr_off: *const u8,
+        addr_len: u32,
+        tag_off: *const u8,
+        tag_len: u32,
+        plaintext_off: *const u8,
+        plaintext_len: u32,
+    ) -> Result<i32>;
+
+    /// Verifies that a secp256k1 signature is valid for an f1 address and a blake2b-256 digest
+    /// of the signed data.
+    ///
+    /// Only available from network version 29.
+    ///
+    /// Returns 0 on success, or -1 if the signature fails to validate.
+    ///
+    /// # Arguments
+    ///
+    /// - `sig_off` and `sig_len` specify location and length of the signature.
+    /// - `addr_off` and `addr_len` specify location and length of expected signer's address.
+    /// - `digest_off` specifies the location of the 32-byte message digest.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                                      |
+    /// |---------------------|-------------------------------------------------------------|
+    /// | [`IllegalArgument`] | signature, address, or digest buffers are invalid           |
+    /// | [`IllegalArgument`] | the signature or the address is BLS (BLS signs the message) |
+    #[cfg(feature = "verify-signature")]
+    pub fn verify_signature_hashed(
+        sig_type: u32,
+        sig_off: *const u8,
+        sig_len: u32,
+        addr_off: *const u8,
+        addr_len: u32,
+        digest_off: *const u8,
+    ) -> Result<i32>;
+
     /// Verifies that a BLS aggregate signature is valid for a list of signers' BLS public keys and
     /// and the digest of each signer's plaintext.
     ///
@@ -99,7 +161,9 @@ super::fvm_syscalls! {
 
 
     /// Hashes input data using the specified hash function. The digest is written to the passed
-    /// digest buffer and truncated to `digest_len`.
+    /// digest buffer and truncated to `digest_len`. Actors targeting syscall ABI version 2 or
+    /// later (see [`fvm_shared::sys::SYSCALL_ABI_SECTION`]) get `BufferTooSmall` instead of a
+    /// truncated digest.
     ///
     /// Returns the length of the digest written to the digest buffer.
     ///
@@ -112,9 +176,10 @@ super::fvm_syscalls! {
     ///
     /// # Errors
     ///
-    /// | Error               | Reason                                          |
-    /// |---------------------|-------------------------------------------------|
-    /// | [`IllegalArgument`] | the input buffer does not point to valid memory |
+    /// | Error               | Reason                                                  |
+    /// |---------------------|---------------------------------------------------------|
+    /// | [`IllegalArgument`] | the input buffer does not point to valid memory         |
+    /// | [`BufferTooSmall`]  | the digest doesn't fit in the buffer (ABI version 2+)   |
     pub fn hash(
         hash_code: u64,
         data_off: *const u8,
@@ -151,6 +216,28 @@ super::fvm_syscalls! {
         cid_len: u32,
     ) -> Result<u32>;
 
+    /// Computes the unsealed sector CIDs (CommDs) of a batch of sectors.
+    ///
+    /// # Arguments
+    ///
+    /// - `batch_off` and `batch_len` specify the location and length of a cbor-encoded list of
+    ///   `(proof_type, [PieceInfo])` tuples, where each `PieceInfo` is in tuple representation.
+    /// - `results_off` specifies the location of a buffer of `L` [`ComputeUnsealedSectorCid`]
+    ///   structs, where `L` is the number of entries in the batch. For each entry (in input
+    ///   order), the runtime writes either the computed CID or, if the computation failed, the
+    ///   error number it failed with.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                   |
+    /// |---------------------|--------------------------|
+    /// | [`IllegalArgument`] | an argument is malformed |
+    pub fn compute_unsealed_sector_cids(
+        batch_off: *const u8,
+        batch_len: u32,
+        results_off: *mut ComputeUnsealedSectorCid,
+    ) -> Result<()>;
+
     /// Verifies a window proof of spacetime.
     ///
     /// Returns 0 to indicate that the proof was valid, -1 otherwise.
@@ -167,6 +254,25 @@ super::fvm_syscalls! {
     /// | [`IllegalArgument`] | an argument is malformed |
     pub fn verify_post(info_off: *const u8, info_len: u32) -> Result<i32>;
 
+    /// Verifies a batch of window proofs of spacetime.
+    ///
+    /// # Arguments
+    ///
+    /// - `batch_off` and `batch_len` specify the location and length of a cbor-encoded list of
+    ///   [`WindowPoStVerifyInfo`][fvm_shared::sector::WindowPoStVerifyInfo] in tuple
+    ///   representation.
+    /// - `result_off` specifies the location of a `ceil(L / 8)` byte buffer, where `L` is the number
+    ///   of proofs in the batch. The runtime writes a packed bitfield of results into this buffer:
+    ///   bit `i % 8` (least significant bit first) of byte `i / 8` is set if the `i`th proof is
+    ///   valid, and cleared otherwise (including if the proof is malformed).
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                   |
+    /// |---------------------|--------------------------|
+    /// | [`IllegalArgument`] | an argument is malformed |
+    pub fn verify_post_batch(batch_off: *const u8, batch_len: u32, result_off: *mut u8) -> Result<()>;
+
     /// Verifies that two block headers provide proof of a consensus fault.
     ///
     /// Returns a 0 status if a consensus fault was recognized, along with the
@@ -195,6 +301,36 @@ super::fvm_syscalls! {
         extra_len: u32,
     ) -> Result<VerifyConsensusFault>;
 
+    /// Verifies that two block headers provide proof of a consensus fault like
+    /// [`verify_consensus_fault`] and, if so, also returns the at-fault miner's power at the fault
+    /// epoch.
+    ///
+    /// Only available from network version 29.
+    ///
+    /// If there's no fault, the fault type (and all other fields) of the result are 0.
+    ///
+    /// # Arguments
+    ///
+    /// - `h1_off`/`h1_len` and `h2_off`/`h2_len` specify the location and length of the block
+    ///   headers that allegedly represent a consensus fault.
+    /// - `extra_off` and `extra_len` specifies the "extra data" passed in the
+    ///   `ReportConsensusFault` message.
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                |
+    /// |---------------------|---------------------------------------|
+    /// | [`LimitExceeded`]   | exceeded lookback limit finding block |
+    /// | [`IllegalArgument`] | an argument is malformed              |
+    pub fn verify_consensus_fault_report(
+        h1_off: *const u8,
+        h1_len: u32,
+        h2_off: *const u8,
+        h2_len: u32,
+        extra_off: *const u8,
+        extra_len: u32,
+    ) -> Result<VerifyConsensusFaultReport>;
+
     /// Verifies an aggregated batch of sector seal proofs.
     ///
     /// Returns 0 to indicate that the proof was valid, -1 otherwise.
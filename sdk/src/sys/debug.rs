@@ -9,9 +9,10 @@ super::fvm_syscalls! {
     /// yes, a negative return value means no.
     pub fn enabled() -> Result<i32>;
 
-    /// Logs a message on the node.
+    /// Logs a message on the node. Never fails: messages that aren't valid UTF-8 are logged
+    /// lossily, and out-of-bounds messages are ignored.
     pub fn log(message: *const u8, message_len: u32) -> Result<()>;
 
-    /// Save data as a debug artifact on the node.
+    /// Save data as a debug artifact on the node. Never fails: invalid artifacts are ignored.
     pub fn store_artifact(name_off: *const u8, name_len: u32, data_off: *const u8, data_len: u32) -> Result<()>;
 }
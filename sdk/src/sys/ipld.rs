@@ -91,6 +91,39 @@ super::fvm_syscalls! {
     /// | [`InvalidHandle`] | if the handle isn't known. |
     pub fn block_stat(id: u32) -> Result<IpldStat>;
 
+    /// Returns the codec and size of the specified block, along with the number of reachable
+    /// blocks it links to. Only DAG-CBOR blocks have links: the link count of any other block is
+    /// always 0.
+    ///
+    /// Links to inline (identity-hashed) blocks aren't counted, but links _within_ inline blocks
+    /// are. Links to blocks that aren't part of the state (e.g., sector CIDs) aren't counted.
+    ///
+    /// # Errors
+    ///
+    /// | Error             | Reason                     |
+    /// |-------------------|----------------------------|
+    /// | [`InvalidHandle`] | if the handle isn't known. |
+    pub fn block_stat_links(id: u32) -> Result<IpldStatLinks>;
+
+    /// Returns the codec and size of the specified block from the "reachable" set, without loading
+    /// the block.
+    ///
+    /// Unlike [`block_open`], this doesn't add the block's children to the reachable set.
+    ///
+    /// Only available from network version 29.
+    ///
+    /// # Arguments
+    ///
+    /// - `cid` the location of the input CID (in wasm memory).
+    ///
+    /// # Errors
+    ///
+    /// | Error               | Reason                                      |
+    /// |---------------------|---------------------------------------------|
+    /// | [`NotFound`]        | the target block isn't in the reachable set |
+    /// | [`IllegalArgument`] | there's something wrong with the CID        |
+    pub fn block_stat_by_cid(cid: *const u8) -> Result<IpldStat>;
+
     /// Computes the given block's CID, writing the resulting CID into `cid`.
     ///
     /// The returned CID is added to the reachable set.
@@ -24,7 +24,7 @@ super::fvm_syscalls! {
     /// - `method` is the method number to invoke.
     /// - `params` is the IPLD block handle of the method parameters.
     /// - `value_hi` are the "high" bits of the token value to send (little-endian) in attoFIL.
-    /// - `value_lo` are the "high" bits of the token value to send (little-endian) in attoFIL.
+    /// - `value_lo` are the "low" bits of the token value to send (little-endian) in attoFIL.
     /// - `gas_limit` is the gas this send is allowed to use. Zero means "all available gas".
     /// - `send_flags` are additional send flags.
     ///
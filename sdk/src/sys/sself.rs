@@ -33,6 +33,9 @@ super::fvm_syscalls! {
     ///
     /// - `cid` is the location in memory of the new state-root CID.
     ///
+    /// Actors targeting syscall ABI version 2 or later (see
+    /// [`fvm_shared::sys::SYSCALL_ABI_SECTION`]) must use [`v2::set_root`] instead.
+    ///
     /// # Errors
     ///
     /// | Error                | Reason                                         |
@@ -65,3 +68,36 @@ super::fvm_syscalls! {
     /// | [`ReadOnly`]          | the actor is executing in read-only mode  |
     pub fn self_destruct(burn_funds: bool) -> Result<()>;
 }
+
+/// The syscalls of this module whose signatures changed in syscall ABI version 2. Only actors
+/// targeting that version (see [`fvm_shared::sys::SYSCALL_ABI_SECTION`]) may import them.
+#[allow(clashing_extern_declarations)]
+pub mod v2 {
+    // for documentation links
+    #[cfg(doc)]
+    use crate::sys::ErrorNumber::*;
+
+    crate::sys::fvm_syscalls! {
+        module = "self";
+
+        /// Sets the root CID for the calling actor. The new root must be in the reachable set.
+        ///
+        /// # Arguments
+        ///
+        /// - `cid` and `cid_len` specify the location and length of the new state-root CID.
+        ///
+        /// # Errors
+        ///
+        /// | Error                | Reason                                                  |
+        /// |----------------------|---------------------------------------------------------|
+        /// | [`IllegalOperation`] | actor has been deleted                                  |
+        /// | [`ReadOnly`]         | the actor is executing in read-only mode                |
+        /// | [`NotFound`]         | specified root CID is not in the reachable set          |
+        /// | [`IllegalArgument`]  | the CID buffer isn't valid, in memory, etc.             |
+        /// | [`IllegalCid`]       | the CID isn't allowed as a state root (see below)       |
+        ///
+        /// State roots must be CIDv1s with the DAG-CBOR or raw codec, and a 32-byte blake2b-256
+        /// or an identity multihash.
+        pub fn set_root(cid: *const u8, cid_len: u32) -> Result<()>;
+    }
+}
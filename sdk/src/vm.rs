@@ -29,6 +29,113 @@ pub fn abort(code: u32, message: Option<&str>) -> ! {
     }
 }
 
+/// The maximum length (in bytes) of a message formatted by [`abort_fmt`]. Longer messages are
+/// truncated. The FVM truncates abort messages longer than this anyways.
+pub const MAX_ABORT_MESSAGE_LEN: usize = 1024;
+
+/// Abort execution with a message formatted from a static template and integer arguments, without
+/// using `core::fmt` (which, along with an allocator, is otherwise linked into the actor just to
+/// format error messages).
+///
+/// The template may contain the following placeholders, each of which consumes the next argument:
+///
+/// - `%d`: the argument, in decimal.
+/// - `%u`: the argument reinterpreted as a `u64`, in decimal (e.g., for sector numbers above
+///   `i64::MAX`).
+///
+/// `%%` is replaced with `%`. Placeholders without a matching argument, and any other use of `%`,
+/// are left as-is. Unused arguments are ignored. Messages longer than [`MAX_ABORT_MESSAGE_LEN`]
+/// bytes are truncated.
+///
+/// ```ignore
+/// sdk::vm::abort_fmt(code, "bad sector %u at epoch %d", &[sector as i64, epoch]);
+/// ```
+///
+/// The exit code must be non-zero (see [`abort`]).
+pub fn abort_fmt(code: u32, template: &str, args: &[i64]) -> ! {
+    let mut message = MessageBuf::new();
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some((literal, spec)) = rest.split_once('%') {
+        message.push_str(literal);
+        rest = if let Some(tail) = spec.strip_prefix('d') {
+            match args.next() {
+                Some(&arg) => message.push_i64(arg),
+                None => message.push_str("%d"),
+            }
+            tail
+        } else if let Some(tail) = spec.strip_prefix('u') {
+            match args.next() {
+                Some(&arg) => message.push_u64(arg as u64),
+                None => message.push_str("%u"),
+            }
+            tail
+        } else if let Some(tail) = spec.strip_prefix('%') {
+            message.push_str("%");
+            tail
+        } else {
+            message.push_str("%");
+            spec
+        };
+    }
+    message.push_str(rest);
+    abort(code, Some(message.as_str()))
+}
+
+/// A fixed-size, stack-allocated buffer for abort messages.
+struct MessageBuf {
+    buf: [u8; MAX_ABORT_MESSAGE_LEN],
+    len: usize,
+}
+
+impl MessageBuf {
+    fn new() -> Self {
+        Self {
+            buf: [0; MAX_ABORT_MESSAGE_LEN],
+            len: 0,
+        }
+    }
+
+    /// Appends as much of the string as fits, without splitting characters.
+    fn push_str(&mut self, s: &str) {
+        let mut n = s.len().min(self.buf.len() - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+    }
+
+    fn push_u64(&mut self, mut value: u64) {
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        // Digits are ASCII, so we can truncate them anywhere.
+        let n = (digits.len() - start).min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&digits[start..start + n]);
+        self.len += n;
+    }
+
+    fn push_i64(&mut self, value: i64) {
+        if value < 0 {
+            self.push_str("-");
+        }
+        self.push_u64(value.unsigned_abs())
+    }
+
+    fn as_str(&self) -> &str {
+        // SAFETY: we only ever append whole UTF-8 characters.
+        unsafe { std::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+}
+
 /// Exit from current message execution, with the specified code and an optional message and data.
 pub fn exit(code: u32, data: Option<IpldBlock>, message: Option<&str>) -> ! {
     unsafe {
@@ -53,10 +160,16 @@ pub fn exit(code: u32, data: Option<IpldBlock>, message: Option<&str>) -> ! {
 ///
 /// NOTE: This will incure a small cost on failure (to format an error message).
 pub fn set_panic_handler() {
-    std::panic::set_hook(Box::new(|info| {
-        abort(
-            ExitCode::USR_ASSERTION_FAILED.value(),
-            Some(&format!("{}", info)),
-        )
+    set_panic_handler_with_exit_code(ExitCode::USR_ASSERTION_FAILED)
+}
+
+/// Like [`set_panic_handler`], but aborts with the specified exit code instead. The abort message
+/// includes the panic message and the location (file and line) of the panic.
+///
+/// The exit code must be a user exit code (or `USR_ASSERTION_FAILED` will be used if it's zero),
+/// otherwise the FVM will report `SYS_ILLEGAL_EXIT_CODE` and drop the message.
+pub fn set_panic_handler_with_exit_code(code: ExitCode) {
+    std::panic::set_hook(Box::new(move |info| {
+        abort(code.value(), Some(&format!("{}", info)))
     }));
 }
@@ -0,0 +1,79 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Dumps the FVM conformance tables (see [`fvm_shared::conformance`]) to stdout as JSON.
+
+use fvm_shared::conformance::{ERROR_NUMBERS, EXIT_CODE_BANDS, EXIT_CODES, SYSCALLS};
+use serde_json::json;
+
+fn main() {
+    let error_numbers: Vec<_> = ERROR_NUMBERS
+        .iter()
+        .map(|info| {
+            json!({
+                "number": info.number as u32,
+                "name": info.name,
+                "message": info.number.to_string(),
+                "description": info.description,
+            })
+        })
+        .collect();
+
+    let exit_codes: Vec<_> = EXIT_CODES
+        .iter()
+        .map(|info| {
+            json!({
+                "code": info.code.value(),
+                "name": info.name,
+                "description": info.description,
+            })
+        })
+        .collect();
+
+    let exit_code_bands: Vec<_> = EXIT_CODE_BANDS
+        .iter()
+        .map(|entry| {
+            let bands: Vec<_> = entry
+                .bands
+                .iter()
+                .map(|band| {
+                    json!({
+                        "first": band.first,
+                        "last": band.last,
+                        "name": band.name,
+                        "description": band.description,
+                        "actor_exit_allowed": band.actor_exit_allowed,
+                    })
+                })
+                .collect();
+            json!({
+                "since_network_version": u32::from(entry.since),
+                "bands": bands,
+            })
+        })
+        .collect();
+
+    let syscalls: Vec<_> = SYSCALLS
+        .iter()
+        .map(|info| {
+            let errors: Vec<_> = info.errors.iter().map(|&e| e as u32).collect();
+            json!({
+                "module": info.module,
+                "name": info.name,
+                "abi_versions": {
+                    "first": info.abi_versions.first,
+                    "last": info.abi_versions.last,
+                },
+                "errors": errors,
+            })
+        })
+        .collect();
+
+    let tables = json!({
+        "error_numbers": error_numbers,
+        "exit_codes": exit_codes,
+        "exit_code_bands": exit_code_bands,
+        "syscalls": syscalls,
+    });
+    println!("{}", serde_json::to_string_pretty(&tables).unwrap());
+}
@@ -0,0 +1,825 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Machine-readable tables describing how the FVM reports failures: the syscall error numbers,
+//! the exit codes, the exit code bands, and the error numbers each syscall may return.
+//!
+//! These tables are intended as a source of truth for other FVM implementations and external test
+//! suites. The `conformance_tables` example dumps them as JSON:
+//!
+//! ```text
+//! cargo run -p fvm_shared --example conformance_tables
+//! ```
+
+use num_traits::FromPrimitive;
+
+use crate::error::{ErrorNumber, ExitCode};
+use crate::version::NetworkVersion;
+
+/// A syscall error number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorNumberInfo {
+    /// The error number.
+    pub number: ErrorNumber,
+    /// The name of the [`ErrorNumber`] variant.
+    pub name: &'static str,
+    /// What the error means.
+    pub description: &'static str,
+}
+
+/// A named exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitCodeInfo {
+    /// The exit code.
+    pub code: ExitCode,
+    /// The name of the [`ExitCode`] constant.
+    pub name: &'static str,
+    /// What the exit code means.
+    pub description: &'static str,
+}
+
+/// A contiguous, inclusive range of exit codes with a common meaning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitCodeBand {
+    /// The first exit code in the band.
+    pub first: u32,
+    /// The last exit code in the band.
+    pub last: u32,
+    /// A short name for the band.
+    pub name: &'static str,
+    /// Who may use exit codes in this band, and what they mean.
+    pub description: &'static str,
+    /// Whether actors may exit with codes in this band.
+    pub actor_exit_allowed: bool,
+}
+
+impl ExitCodeBand {
+    /// Returns true if the exit code falls within this band.
+    pub fn contains(&self, code: ExitCode) -> bool {
+        (self.first..=self.last).contains(&code.value())
+    }
+}
+
+/// The exit code bands that apply from a given network version until the next entry in
+/// [`EXIT_CODE_BANDS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkExitCodeBands {
+    /// The first network version to which these bands apply.
+    pub since: NetworkVersion,
+    /// The bands, in order, covering every possible exit code.
+    pub bands: &'static [ExitCodeBand],
+}
+
+/// An inclusive range of syscall ABI versions (see [`crate::sys::SYSCALL_ABI_SECTION`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbiVersions {
+    /// The first version in the range.
+    pub first: u32,
+    /// The last version in the range.
+    pub last: u32,
+}
+
+impl AbiVersions {
+    /// All ABI versions.
+    pub const ALL: AbiVersions = AbiVersions::since(1);
+
+    /// The versions from `first` on.
+    pub const fn since(first: u32) -> Self {
+        AbiVersions {
+            first,
+            last: u32::MAX,
+        }
+    }
+
+    /// Returns true if the range contains the given version.
+    pub fn contains(&self, version: u32) -> bool {
+        self.first <= version && version <= self.last
+    }
+}
+
+/// A syscall, and the error numbers it may return. Syscalls whose signature or errors depend on
+/// the caller's syscall ABI version have an entry per range of versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallInfo {
+    /// The wasm module the syscall is imported from.
+    pub module: &'static str,
+    /// The name of the syscall within its module.
+    pub name: &'static str,
+    /// The syscall ABI versions this entry applies to.
+    pub abi_versions: AbiVersions,
+    /// The error numbers the syscall may return (in addition to aborting the actor).
+    pub errors: &'static [ErrorNumber],
+}
+
+macro_rules! error_number {
+    ($name:ident, $description:literal) => {
+        ErrorNumberInfo {
+            number: ErrorNumber::$name,
+            name: stringify!($name),
+            description: $description,
+        }
+    };
+}
+
+macro_rules! exit_code {
+    ($name:ident, $description:literal) => {
+        ExitCodeInfo {
+            code: ExitCode::$name,
+            name: stringify!($name),
+            description: $description,
+        }
+    };
+}
+
+/// All syscall error numbers, in numeric order.
+pub const ERROR_NUMBERS: &[ErrorNumberInfo] = &[
+    error_number!(IllegalArgument, "A syscall parameter was invalid."),
+    error_number!(
+        IllegalOperation,
+        "The actor is not in the correct state to perform the requested operation."
+    ),
+    error_number!(
+        LimitExceeded,
+        "This syscall would exceed some system limit (memory, lookback, call depth, etc.)."
+    ),
+    error_number!(
+        AssertionFailed,
+        "A system-level assertion has failed. Non-system actors should never receive this error number."
+    ),
+    error_number!(
+        InsufficientFunds,
+        "There were insufficient funds to complete the requested operation."
+    ),
+    error_number!(NotFound, "A resource was not found."),
+    error_number!(
+        InvalidHandle,
+        "The specified IPLD block handle was invalid."
+    ),
+    error_number!(
+        IllegalCid,
+        "The requested CID shape (multihash codec, multihash length) isn't supported."
+    ),
+    error_number!(IllegalCodec, "The requested IPLD codec isn't supported."),
+    error_number!(
+        Serialization,
+        "The IPLD block did not match the specified IPLD codec."
+    ),
+    error_number!(Forbidden, "The operation is forbidden."),
+    error_number!(BufferTooSmall, "The passed buffer is too small."),
+    error_number!(ReadOnly, "The actor is executing in a read-only context."),
+];
+
+/// All named exit codes, in numeric order.
+pub const EXIT_CODES: &[ExitCodeInfo] = &[
+    exit_code!(OK, "The code indicating successful execution."),
+    exit_code!(SYS_SENDER_INVALID, "The message sender doesn't exist."),
+    exit_code!(
+        SYS_SENDER_STATE_INVALID,
+        "The message sender was not in a valid state to send this message: either the nonce didn't match, or the sender couldn't cover the message gas."
+    ),
+    exit_code!(
+        SYS_ILLEGAL_INSTRUCTION,
+        "The message receiver trapped (panicked)."
+    ),
+    exit_code!(
+        SYS_INVALID_RECEIVER,
+        "The message receiver either doesn't exist and can't be automatically created or it doesn't implement the required entrypoint."
+    ),
+    exit_code!(
+        SYS_INSUFFICIENT_FUNDS,
+        "The message sender didn't have the requisite funds."
+    ),
+    exit_code!(
+        SYS_OUT_OF_GAS,
+        "Message execution (including subcalls) used more gas than the specified limit."
+    ),
+    exit_code!(
+        SYS_ILLEGAL_EXIT_CODE,
+        "The message receiver aborted with a reserved exit code."
+    ),
+    exit_code!(SYS_ASSERTION_FAILED, "An internal VM assertion failed."),
+    exit_code!(
+        SYS_MISSING_RETURN,
+        "The actor returned a block handle that doesn't exist."
+    ),
+    exit_code!(
+        SYS_DENIED_CODE,
+        "The message receiver's code CID has been denied by the network or the node executing the message, so the actor was not invoked."
+    ),
+    exit_code!(
+        SYS_UNSUPPORTED_ABI,
+        "The message receiver targets an unsupported syscall ABI version (or declares it incorrectly), so the actor could not be instantiated."
+    ),
+    exit_code!(USR_ILLEGAL_ARGUMENT, "The method parameters are invalid."),
+    exit_code!(USR_NOT_FOUND, "The requested resource does not exist."),
+    exit_code!(USR_FORBIDDEN, "The requested operation is forbidden."),
+    exit_code!(
+        USR_INSUFFICIENT_FUNDS,
+        "The actor has insufficient funds to perform the requested operation."
+    ),
+    exit_code!(USR_ILLEGAL_STATE, "The actor's internal state is invalid."),
+    exit_code!(
+        USR_SERIALIZATION,
+        "There was a de/serialization failure within actor code."
+    ),
+    exit_code!(
+        USR_UNHANDLED_MESSAGE,
+        "The message cannot be handled (usually indicates an unhandled method number)."
+    ),
+    exit_code!(
+        USR_UNSPECIFIED,
+        "The actor failed with an unspecified error."
+    ),
+    exit_code!(
+        USR_ASSERTION_FAILED,
+        "The actor failed a user-level assertion."
+    ),
+    exit_code!(
+        USR_READ_ONLY,
+        "The requested operation cannot be performed in \"read-only\" mode."
+    ),
+    exit_code!(
+        USR_NOT_PAYABLE,
+        "The method cannot handle a transfer of value."
+    ),
+];
+
+/// The exit code bands, by the network version from which they apply, in order.
+pub const EXIT_CODE_BANDS: &[NetworkExitCodeBands] = &[NetworkExitCodeBands {
+    since: NetworkVersion::V16,
+    bands: &[
+        ExitCodeBand {
+            first: 0,
+            last: 0,
+            name: "success",
+            description: "Successful execution.",
+            actor_exit_allowed: true,
+        },
+        ExitCodeBand {
+            first: 1,
+            last: ExitCode::FIRST_USER_EXIT_CODE - 1,
+            name: "system",
+            description: "Reserved for the VM. Actors that abort with these exit codes exit with SYS_ILLEGAL_EXIT_CODE instead.",
+            actor_exit_allowed: false,
+        },
+        ExitCodeBand {
+            first: ExitCode::FIRST_USER_EXIT_CODE,
+            last: ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE - 1,
+            name: "standard",
+            description: "Standard exit codes according to the built-in actors' calling convention. Unnamed exit codes in this band are reserved.",
+            actor_exit_allowed: true,
+        },
+        ExitCodeBand {
+            first: ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE,
+            last: u32::MAX,
+            name: "actor-specific",
+            description: "Exit codes with actor-specific meanings.",
+            actor_exit_allowed: true,
+        },
+    ],
+}];
+
+/// All syscalls, by module, and the error numbers they may return, as documented on the syscall
+/// bindings in the SDK.
+pub const SYSCALLS: &[SyscallInfo] = {
+    use ErrorNumber::*;
+    const V1: AbiVersions = AbiVersions { first: 1, last: 1 };
+    &[
+        // actor
+        SyscallInfo {
+            module: "actor",
+            name: "resolve_address",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "lookup_delegated_address",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound, BufferTooSmall, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "get_actor_code_cid",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound, BufferTooSmall, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "get_builtin_actor_type",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "get_code_cid_for_type",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "next_actor_address",
+            abi_versions: AbiVersions::ALL,
+            errors: &[BufferTooSmall, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "create_actor",
+            abi_versions: V1,
+            errors: &[Forbidden, ReadOnly, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "create_actor",
+            abi_versions: AbiVersions::since(2),
+            errors: &[Forbidden, ReadOnly, IllegalArgument, IllegalCid],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "upgrade_actor",
+            abi_versions: V1,
+            errors: &[
+                NotFound,
+                IllegalOperation,
+                InvalidHandle,
+                LimitExceeded,
+                IllegalArgument,
+                Forbidden,
+                ReadOnly,
+            ],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "upgrade_actor",
+            abi_versions: AbiVersions::since(2),
+            errors: &[
+                NotFound,
+                IllegalOperation,
+                InvalidHandle,
+                LimitExceeded,
+                IllegalArgument,
+                IllegalCid,
+                Forbidden,
+                ReadOnly,
+            ],
+        },
+        SyscallInfo {
+            module: "actor",
+            name: "balance_of",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound],
+        },
+        // crypto
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_signature",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_signature_tagged",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_signature_hashed",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_bls_aggregate",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "recover_secp_public_key",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "hash",
+            abi_versions: V1,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "hash",
+            abi_versions: AbiVersions::since(2),
+            errors: &[IllegalArgument, BufferTooSmall],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "compute_unsealed_sector_cid",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument, BufferTooSmall],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "compute_unsealed_sector_cids",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_post",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_post_batch",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_consensus_fault",
+            abi_versions: AbiVersions::ALL,
+            errors: &[LimitExceeded, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_consensus_fault_report",
+            abi_versions: AbiVersions::ALL,
+            errors: &[LimitExceeded, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_aggregate_seals",
+            abi_versions: AbiVersions::ALL,
+            errors: &[LimitExceeded, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "verify_replica_update",
+            abi_versions: AbiVersions::ALL,
+            errors: &[LimitExceeded, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "crypto",
+            name: "batch_verify_seals",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        // debug
+        SyscallInfo {
+            module: "debug",
+            name: "enabled",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+        SyscallInfo {
+            module: "debug",
+            name: "log",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "debug",
+            name: "store_artifact",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        // event
+        SyscallInfo {
+            module: "event",
+            name: "emit_event",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument, ReadOnly],
+        },
+        // gas
+        SyscallInfo {
+            module: "gas",
+            name: "charge",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "gas",
+            name: "available",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+        // ipld
+        SyscallInfo {
+            module: "ipld",
+            name: "block_open",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_create",
+            abi_versions: AbiVersions::ALL,
+            errors: &[
+                LimitExceeded,
+                NotFound,
+                IllegalCodec,
+                Serialization,
+                IllegalArgument,
+            ],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_read",
+            abi_versions: AbiVersions::ALL,
+            errors: &[InvalidHandle, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_stat",
+            abi_versions: AbiVersions::ALL,
+            errors: &[InvalidHandle],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_stat_links",
+            abi_versions: AbiVersions::ALL,
+            errors: &[InvalidHandle],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_stat_by_cid",
+            abi_versions: AbiVersions::ALL,
+            errors: &[NotFound, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "ipld",
+            name: "block_link",
+            abi_versions: AbiVersions::ALL,
+            errors: &[InvalidHandle, IllegalCid, BufferTooSmall, IllegalArgument],
+        },
+        // network
+        SyscallInfo {
+            module: "network",
+            name: "context",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+        SyscallInfo {
+            module: "network",
+            name: "tipset_cid",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument, LimitExceeded],
+        },
+        // rand
+        SyscallInfo {
+            module: "rand",
+            name: "get_chain_randomness",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        SyscallInfo {
+            module: "rand",
+            name: "get_beacon_randomness",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalArgument],
+        },
+        // self
+        SyscallInfo {
+            module: "self",
+            name: "root",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalOperation, IllegalArgument, BufferTooSmall],
+        },
+        SyscallInfo {
+            module: "self",
+            name: "set_root",
+            abi_versions: V1,
+            errors: &[IllegalOperation, ReadOnly, NotFound, IllegalArgument],
+        },
+        SyscallInfo {
+            module: "self",
+            name: "set_root",
+            abi_versions: AbiVersions::since(2),
+            errors: &[
+                IllegalOperation,
+                ReadOnly,
+                NotFound,
+                IllegalArgument,
+                IllegalCid,
+            ],
+        },
+        SyscallInfo {
+            module: "self",
+            name: "current_balance",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+        SyscallInfo {
+            module: "self",
+            name: "self_destruct",
+            abi_versions: AbiVersions::ALL,
+            errors: &[IllegalOperation, ReadOnly],
+        },
+        // send
+        SyscallInfo {
+            module: "send",
+            name: "send",
+            abi_versions: AbiVersions::ALL,
+            errors: &[
+                NotFound,
+                InsufficientFunds,
+                InvalidHandle,
+                LimitExceeded,
+                IllegalArgument,
+                ReadOnly,
+            ],
+        },
+        // vm
+        SyscallInfo {
+            module: "vm",
+            name: "exit",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+        SyscallInfo {
+            module: "vm",
+            name: "message_context",
+            abi_versions: AbiVersions::ALL,
+            errors: &[],
+        },
+    ]
+};
+
+/// Returns the table entry for the given error number.
+pub fn error_number_info(number: ErrorNumber) -> &'static ErrorNumberInfo {
+    ERROR_NUMBERS
+        .iter()
+        .find(|info| info.number == number)
+        .expect("every error number is in the table")
+}
+
+/// Looks up an error number by its numeric value.
+pub fn error_number_by_value(value: u32) -> Option<&'static ErrorNumberInfo> {
+    ErrorNumber::from_u32(value).map(error_number_info)
+}
+
+/// Returns the table entry for the given exit code, if it's a named exit code.
+pub fn exit_code_info(code: ExitCode) -> Option<&'static ExitCodeInfo> {
+    EXIT_CODES.iter().find(|info| info.code == code)
+}
+
+/// Returns the exit code bands that apply at the given network version, or `None` if the network
+/// version predates the FVM.
+pub fn exit_code_bands(nv: NetworkVersion) -> Option<&'static [ExitCodeBand]> {
+    EXIT_CODE_BANDS
+        .iter()
+        .rev()
+        .find(|entry| entry.since <= nv)
+        .map(|entry| entry.bands)
+}
+
+/// Returns the band the exit code falls into at the given network version.
+pub fn exit_code_band(nv: NetworkVersion, code: ExitCode) -> Option<&'static ExitCodeBand> {
+    exit_code_bands(nv)?.iter().find(|band| band.contains(code))
+}
+
+/// Returns true if actors may exit with the given code at the given network version.
+pub fn is_actor_exit_allowed(nv: NetworkVersion, code: ExitCode) -> bool {
+    exit_code_band(nv, code).is_some_and(|band| band.actor_exit_allowed)
+}
+
+/// Returns the table entry for the given syscall, as linked for actors targeting the given syscall
+/// ABI version.
+pub fn syscall_info(module: &str, name: &str, abi_version: u32) -> Option<&'static SyscallInfo> {
+    SYSCALLS.iter().find(|info| {
+        info.module == module && info.name == name && info.abi_versions.contains(abi_version)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// Returns the name of an error number variant. This match must be exhaustive, so adding a
+    /// variant without updating this test (and the table) fails to compile.
+    fn variant_name(number: ErrorNumber) -> &'static str {
+        use ErrorNumber::*;
+        match number {
+            IllegalArgument => "IllegalArgument",
+            IllegalOperation => "IllegalOperation",
+            LimitExceeded => "LimitExceeded",
+            AssertionFailed => "AssertionFailed",
+            InsufficientFunds => "InsufficientFunds",
+            NotFound => "NotFound",
+            InvalidHandle => "InvalidHandle",
+            IllegalCid => "IllegalCid",
+            IllegalCodec => "IllegalCodec",
+            Serialization => "Serialization",
+            Forbidden => "Forbidden",
+            BufferTooSmall => "BufferTooSmall",
+            ReadOnly => "ReadOnly",
+        }
+    }
+
+    #[test]
+    fn every_error_number_appears_once() {
+        let numbers: Vec<_> = (0..=u8::MAX as u32)
+            .filter_map(ErrorNumber::from_u32)
+            .collect();
+        assert_eq!(numbers.len(), ERROR_NUMBERS.len());
+        for number in numbers {
+            let entries: Vec<_> = ERROR_NUMBERS
+                .iter()
+                .filter(|info| info.number == number)
+                .collect();
+            assert_eq!(entries.len(), 1, "{number:?}");
+            assert_eq!(entries[0].name, variant_name(number));
+            assert_eq!(entries[0].name, format!("{number:?}"));
+            assert_eq!(error_number_by_value(number as u32), Some(entries[0]));
+        }
+        assert_eq!(error_number_by_value(0), None);
+    }
+
+    #[test]
+    fn tables_are_sorted_and_unique() {
+        assert!(
+            ERROR_NUMBERS
+                .windows(2)
+                .all(|w| (w[0].number as u32) < (w[1].number as u32))
+        );
+        assert!(
+            EXIT_CODES
+                .windows(2)
+                .all(|w| w[0].code.value() < w[1].code.value())
+        );
+        assert!(EXIT_CODE_BANDS.windows(2).all(|w| w[0].since < w[1].since));
+
+        for info in SYSCALLS {
+            assert!(
+                info.abi_versions.first <= info.abi_versions.last,
+                "{info:?}"
+            );
+            // Entries for the same syscall don't overlap.
+            assert!(
+                !SYSCALLS.iter().any(|other| other != info
+                    && (other.module, other.name) == (info.module, info.name)
+                    && other.abi_versions.first <= info.abi_versions.last
+                    && info.abi_versions.first <= other.abi_versions.last),
+                "{info:?}"
+            );
+            let errors: HashSet<_> = info.errors.iter().map(|&e| e as u32).collect();
+            assert_eq!(errors.len(), info.errors.len(), "{info:?}");
+        }
+    }
+
+    #[test]
+    fn exit_code_bands_cover_all_codes() {
+        for entry in EXIT_CODE_BANDS {
+            let bands = entry.bands;
+            assert_eq!(bands.first().unwrap().first, 0);
+            assert_eq!(bands.last().unwrap().last, u32::MAX);
+            assert!(bands.iter().all(|band| band.first <= band.last));
+            assert!(bands.windows(2).all(|w| w[0].last + 1 == w[1].first));
+        }
+    }
+
+    #[test]
+    fn exit_codes_match_bands() {
+        let nv = NetworkVersion::V21;
+        for info in EXIT_CODES {
+            let band = exit_code_band(nv, info.code).unwrap();
+            let expected = if info.code.is_success() {
+                "success"
+            } else if info.code.is_system_error() {
+                assert!(info.name.starts_with("SYS_"), "{}", info.name);
+                "system"
+            } else {
+                assert!(info.code.is_standard(), "{}", info.name);
+                assert!(info.name.starts_with("USR_"), "{}", info.name);
+                "standard"
+            };
+            assert_eq!(band.name, expected, "{}", info.name);
+            assert_eq!(exit_code_info(info.code), Some(info));
+        }
+        assert_eq!(exit_code_info(ExitCode::new(3)), None);
+        assert_eq!(
+            exit_code_band(nv, ExitCode::new(1000)).unwrap().name,
+            "actor-specific"
+        );
+        assert_eq!(exit_code_bands(NetworkVersion::V15), None);
+    }
+
+    #[test]
+    fn syscall_lookup() {
+        let info = syscall_info("ipld", "block_stat", 1).unwrap();
+        assert_eq!(info.errors, &[ErrorNumber::InvalidHandle]);
+        assert_eq!(syscall_info("ipld", "block_stat", 2), Some(info));
+        assert_eq!(syscall_info("ipld", "block_close", 1), None);
+
+        // Only ABI version 2 reports digests that don't fit.
+        let v1 = syscall_info("crypto", "hash", 1).unwrap();
+        let v2 = syscall_info("crypto", "hash", 2).unwrap();
+        assert!(!v1.errors.contains(&ErrorNumber::BufferTooSmall));
+        assert!(v2.errors.contains(&ErrorNumber::BufferTooSmall));
+        assert_eq!(syscall_info("crypto", "hash", u32::MAX), Some(v2));
+    }
+}
@@ -1,11 +1,16 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
+use fvm_ipld_encoding::repr::*;
+use fvm_ipld_encoding::tuple::*;
 use num_derive::FromPrimitive;
 
-use super::{Address, ChainEpoch};
+use super::{ActorID, Address, ChainEpoch};
+use crate::sector::StoragePower;
 
 /// Result of checking two headers for a consensus fault.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct ConsensusFault {
     /// Address of the miner at fault (always an ID address).
     pub target: Address,
@@ -15,11 +20,190 @@ pub struct ConsensusFault {
     pub fault_type: ConsensusFaultType,
 }
 
+impl ConsensusFault {
+    /// Creates a new consensus fault committed by the specified miner.
+    pub fn new(target: ActorID, epoch: ChainEpoch, fault_type: ConsensusFaultType) -> Self {
+        Self {
+            target: Address::new_id(target),
+            epoch,
+            fault_type,
+        }
+    }
+}
+
+/// A miner's power at some epoch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinerPower {
+    /// The miner's raw byte power.
+    pub raw_byte_power: StoragePower,
+    /// The miner's quality-adjusted power.
+    pub quality_adj_power: StoragePower,
+}
+
+/// A consensus fault, along with the at-fault miner's power at the fault epoch. Actors need the
+/// miner's power at the fault epoch to process a fault report, but the FVM doesn't have historical
+/// chain state, so the node looks it up when the fault is verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsensusFaultReport {
+    /// The fault.
+    pub fault: ConsensusFault,
+    /// The at-fault miner's power at the fault epoch (zero if the miner had no power).
+    pub power: MinerPower,
+}
+
 /// Consensus fault types in VM.
-#[derive(FromPrimitive, Clone, Copy, Debug)]
+#[derive(FromPrimitive, Clone, Copy, Debug, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum ConsensusFaultType {
     DoubleForkMining = 1,
     ParentGrinding = 2,
     TimeOffsetMining = 3,
 }
+
+/// The parts of a block header needed to check for a consensus fault.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FaultBlockHeader {
+    /// The CID of the block header.
+    pub cid: Cid,
+    /// The miner that produced the block.
+    pub miner: Address,
+    /// The epoch at which the block was produced.
+    pub epoch: ChainEpoch,
+    /// The block's parents (the CIDs of the blocks in its parent tipset).
+    pub parents: Vec<Cid>,
+}
+
+/// Checks whether two block headers (and, for parent grinding, an optional third "witness" header)
+/// constitute a consensus fault, returning the type of the fault if so. The checks are applied in
+/// the following order, returning the first match:
+///
+/// 1. [`DoubleForkMining`](ConsensusFaultType::DoubleForkMining): both blocks were produced at the
+///    same epoch.
+/// 2. [`TimeOffsetMining`](ConsensusFaultType::TimeOffsetMining): both blocks have the same
+///    parents, but were produced at different epochs.
+/// 3. [`ParentGrinding`](ConsensusFaultType::ParentGrinding): `extra` is a sibling of `h1` (same
+///    parents and epoch), and `h2` includes `extra` but not `h1` in its parents.
+///
+/// Two identical blocks, or blocks produced by different miners, are never a fault.
+///
+/// This only covers the relationship between the headers. The caller must still verify the
+/// headers' signatures and that they're part of the chain.
+pub fn fault_applies(
+    h1: &FaultBlockHeader,
+    h2: &FaultBlockHeader,
+    extra: Option<&FaultBlockHeader>,
+) -> Option<ConsensusFaultType> {
+    if h1.cid == h2.cid || h1.miner != h2.miner {
+        return None;
+    }
+    if h1.epoch == h2.epoch {
+        return Some(ConsensusFaultType::DoubleForkMining);
+    }
+    if h1.parents == h2.parents {
+        return Some(ConsensusFaultType::TimeOffsetMining);
+    }
+    if let Some(h3) = extra
+        && h1.parents == h3.parents
+        && h1.epoch == h3.epoch
+        && h2.parents.contains(&h3.cid)
+        && !h2.parents.contains(&h1.cid)
+    {
+        return Some(ConsensusFaultType::ParentGrinding);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_ipld_encoding::{DAG_CBOR, from_slice, to_vec};
+    use multihash_codetable::{Code, MultihashDigest};
+
+    use super::*;
+
+    fn header(name: &[u8], epoch: ChainEpoch, parents: &[Cid]) -> FaultBlockHeader {
+        FaultBlockHeader {
+            cid: Cid::new_v1(DAG_CBOR, Code::Sha2_256.digest(name)),
+            miner: Address::new_id(1000),
+            epoch,
+            parents: parents.to_vec(),
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        let fault = ConsensusFault::new(1000, 10, ConsensusFaultType::ParentGrinding);
+        // [h'00e807', 10, 2]
+        let fixture = [0x83, 0x43, 0x00, 0xe8, 0x07, 0x0a, 0x02];
+        assert_eq!(to_vec(&fault).unwrap(), fixture);
+        assert_eq!(from_slice::<ConsensusFault>(&fixture).unwrap(), fault);
+
+        for (ty, n) in [
+            (ConsensusFaultType::DoubleForkMining, 1u8),
+            (ConsensusFaultType::ParentGrinding, 2),
+            (ConsensusFaultType::TimeOffsetMining, 3),
+        ] {
+            assert_eq!(to_vec(&ty).unwrap(), to_vec(&n).unwrap());
+            assert_eq!(from_slice::<ConsensusFaultType>(&[n]).unwrap(), ty);
+        }
+        from_slice::<ConsensusFaultType>(&[0]).expect_err("0 is not a fault type");
+        from_slice::<ConsensusFaultType>(&[4]).expect_err("4 is not a fault type");
+    }
+
+    #[test]
+    fn double_fork_mining() {
+        let parent = header(b"parent", 9, &[]);
+        let h1 = header(b"h1", 10, &[parent.cid]);
+        let h2 = header(b"h2", 10, &[]);
+        assert_eq!(
+            fault_applies(&h1, &h2, None),
+            Some(ConsensusFaultType::DoubleForkMining)
+        );
+    }
+
+    #[test]
+    fn time_offset_mining() {
+        let parent = header(b"parent", 9, &[]);
+        let h1 = header(b"h1", 10, &[parent.cid]);
+        let h2 = header(b"h2", 11, &[parent.cid]);
+        assert_eq!(
+            fault_applies(&h1, &h2, None),
+            Some(ConsensusFaultType::TimeOffsetMining)
+        );
+    }
+
+    #[test]
+    fn parent_grinding() {
+        let parent = header(b"parent", 9, &[]);
+        let h1 = header(b"h1", 10, &[parent.cid]);
+        let h3 = header(b"h3", 10, &[parent.cid]);
+        // h2 builds on h3, omitting the miner's own block h1.
+        let h2 = header(b"h2", 11, &[h3.cid]);
+        assert_eq!(
+            fault_applies(&h1, &h2, Some(&h3)),
+            Some(ConsensusFaultType::ParentGrinding)
+        );
+
+        // Not a fault without the witness, or if h2 includes h1.
+        assert_eq!(fault_applies(&h1, &h2, None), None);
+        let h2 = header(b"h2", 11, &[h1.cid, h3.cid]);
+        assert_eq!(fault_applies(&h1, &h2, Some(&h3)), None);
+    }
+
+    #[test]
+    fn no_fault() {
+        let parent = header(b"parent", 9, &[]);
+        let h1 = header(b"h1", 10, &[parent.cid]);
+
+        // The same block twice.
+        assert_eq!(fault_applies(&h1, &h1.clone(), None), None);
+
+        // Different miners.
+        let mut h2 = header(b"h2", 10, &[parent.cid]);
+        h2.miner = Address::new_id(1001);
+        assert_eq!(fault_applies(&h1, &h2, None), None);
+
+        // A valid child.
+        let h2 = header(b"h2", 11, &[h1.cid]);
+        assert_eq!(fault_applies(&h1, &h2, None), None);
+    }
+}
@@ -15,3 +15,32 @@ impl From<SupportedHashes> for u64 {
         value as Self
     }
 }
+
+impl TryFrom<u64> for SupportedHashes {
+    type Error = u64;
+
+    /// Returns the hash function with the given multihash code, or the code if it isn't supported.
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        [
+            Self::Sha2_256,
+            Self::Blake2b256,
+            Self::Blake2b512,
+            Self::Keccak256,
+            Self::Ripemd160,
+        ]
+        .into_iter()
+        .find(|hash| *hash as u64 == code)
+        .ok_or(code)
+    }
+}
+
+impl SupportedHashes {
+    /// The size of the digests produced by this hash function, in bytes.
+    pub const fn digest_size(self) -> u32 {
+        match self {
+            Self::Sha2_256 | Self::Blake2b256 | Self::Keccak256 => 32,
+            Self::Blake2b512 => 64,
+            Self::Ripemd160 => 20,
+        }
+    }
+}
@@ -126,6 +126,52 @@ impl quickcheck::Arbitrary for Signature {
     }
 }
 
+/// Crypto error
+#[derive(Debug, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Failed to produce a signature
+    #[error("Failed to sign data {0}")]
+    SigningError(String),
+    /// Unable to perform ecrecover with the given params
+    #[error("Could not recover public key from signature: {0}")]
+    InvalidRecovery(String),
+    /// Provided public key is not understood
+    #[error("Invalid generated pub key to create address: {0}")]
+    InvalidPubKey(#[from] AddressError),
+    /// The secp256k1 signature's `s` value is in the upper half of the curve order
+    #[error("Malleable signature: s is greater than n/2")]
+    MalleableSignature,
+}
+
+impl From<Box<dyn error::Error>> for Error {
+    fn from(err: Box<dyn error::Error>) -> Error {
+        // Pass error encountered in signer trait as module error type
+        Error::SigningError(err.to_string())
+    }
+}
+
+impl From<EncodingError> for Error {
+    fn from(err: EncodingError) -> Error {
+        // Pass error encountered in signer trait as module error type
+        Error::SigningError(err.to_string())
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl From<k256::ecdsa::Error> for Error {
+    fn from(err: k256::ecdsa::Error) -> Error {
+        Error::SigningError(format!("Invalid signature: {}", err))
+    }
+}
+
+#[cfg(feature = "blst")]
+impl From<bls_signatures::Error> for Error {
+    fn from(err: bls_signatures::Error) -> Error {
+        Error::SigningError(format!("Invalid signature: {}", err))
+    }
+}
+
 #[cfg(feature = "crypto")]
 impl Signature {
     /// Checks if a signature is valid given data and address.
@@ -149,9 +195,8 @@ pub fn verify(
 
 #[cfg(feature = "crypto")]
 pub mod ops {
-    use bls_signatures::{
-        PublicKey as BlsPubKey, Serialize, Signature as BlsSignature, verify_messages,
-    };
+    pub use bls_signatures::PublicKey as BlsPubKey;
+    use bls_signatures::{Serialize, Signature as BlsSignature, verify_messages};
     use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey};
 
     use super::{Error, SECP_PUB_LEN, SECP_SIG_LEN, SECP_SIG_MESSAGE_HASH_SIZE};
@@ -185,12 +230,35 @@ pub mod ops {
         }
     }
 
+    /// Deserializes a compressed BLS public key, checking that it's a valid G1 curve point in the
+    /// correct subgroup. Returns `String` error if it isn't.
+    pub fn validate_bls_pub_key(pub_key: &[u8; super::BLS_PUB_LEN]) -> Result<BlsPubKey, String> {
+        BlsPubKey::from_bytes(pub_key.as_slice())
+            .map_err(|_| "bls public key bytes are invalid G1 curve point".to_string())
+    }
+
     /// Verifies an aggregated BLS signature. Returns `Ok(false)` if signature verification fails
     /// and `String` error if arguments are invalid.
     pub fn verify_bls_aggregate(
         aggregate_sig: &[u8; super::BLS_SIG_LEN],
         pub_keys: &[[u8; super::BLS_PUB_LEN]],
         plaintexts: &[&[u8]],
+    ) -> Result<bool, String> {
+        // Deserialize each public key's bytes into a curve point.
+        let pub_keys = pub_keys
+            .iter()
+            .map(validate_bls_pub_key)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        verify_bls_aggregate_validated(aggregate_sig, &pub_keys, plaintexts)
+    }
+
+    /// Like [`verify_bls_aggregate`], but with public keys that have already been validated (see
+    /// [`validate_bls_pub_key`]).
+    pub fn verify_bls_aggregate_validated(
+        aggregate_sig: &[u8; super::BLS_SIG_LEN],
+        pub_keys: &[BlsPubKey],
+        plaintexts: &[&[u8]],
     ) -> Result<bool, String> {
         // If the number of public keys and data does not match, return false;
         let (num_pub_keys, num_plaintexts) = (pub_keys.len(), plaintexts.len());
@@ -207,14 +275,7 @@ pub mod ops {
         let sig = BlsSignature::from_bytes(aggregate_sig)
             .map_err(|_| "bls aggregate signature bytes are invalid G2 curve point".to_string())?;
 
-        // Deserialize each public key's bytes into a curve point.
-        let pub_keys = pub_keys
-            .iter()
-            .map(|pub_key| BlsPubKey::from_bytes(pub_key.as_slice()))
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|_| "bls public key bytes are invalid G2 curve point".to_string())?;
-
-        Ok(bls_signatures::verify_messages(&sig, plaintexts, &pub_keys))
+        Ok(verify_messages(&sig, plaintexts, pub_keys))
     }
 
     /// Returns `String` error if a secp256k1 signature is invalid.
@@ -230,13 +291,6 @@ pub mod ops {
             ));
         }
 
-        if signature.len() != SECP_SIG_LEN {
-            return Err(format!(
-                "Invalid Secp256k1 signature length. Was {}, must be 65",
-                signature.len()
-            ));
-        }
-
         // blake2b 256 hash
         let hash = blake2b_simd::Params::new()
             .hash_length(32)
@@ -244,11 +298,41 @@ pub mod ops {
             .update(data)
             .finalize();
 
+        verify_secp256k1_sig_hashed(
+            signature,
+            hash.as_bytes().try_into().expect("fixed array size"),
+            addr,
+        )
+    }
+
+    /// Returns `String` error if a secp256k1 signature over the given blake2b-256 message digest
+    /// is invalid.
+    ///
+    /// Unlike [`verify_secp256k1_sig`], this function does not hash its input. There is no BLS
+    /// equivalent because BLS signatures are always verified over the message itself.
+    pub fn verify_secp256k1_sig_hashed(
+        signature: &[u8],
+        hash: &[u8; SECP_SIG_MESSAGE_HASH_SIZE],
+        addr: &Address,
+    ) -> Result<(), String> {
+        if addr.protocol() != Protocol::Secp256k1 {
+            return Err(format!(
+                "cannot validate a secp256k1 signature against a {} address",
+                addr.protocol()
+            ));
+        }
+
+        if signature.len() != SECP_SIG_LEN {
+            return Err(format!(
+                "Invalid Secp256k1 signature length. Was {}, must be 65",
+                signature.len()
+            ));
+        }
+
         // Ecrecover with hash and signature
         let mut sig = [0u8; SECP_SIG_LEN];
         sig[..].copy_from_slice(signature);
-        let rec_addr = ecrecover(hash.as_bytes().try_into().expect("fixed array size"), &sig)
-            .map_err(|e| e.to_string())?;
+        let rec_addr = ecrecover(hash, &sig).map_err(|e| e.to_string())?;
 
         // check address against recovered address
         if &rec_addr == addr {
@@ -267,8 +351,7 @@ pub mod ops {
         let mut rec_byte = signature[64];
 
         // Create signature from the first 64 bytes
-        let mut signature = EcdsaSignature::from_slice(&signature[..64])
-            .map_err(|e| Error::SigningError(format!("Invalid signature: {}", e)))?;
+        let mut signature = EcdsaSignature::from_slice(&signature[..64])?;
 
         // Normalize the signature & recovery byte (required for Ethereum compatibility).
         if let Some(normalized) = signature.normalize_s() {
@@ -291,18 +374,35 @@ pub mod ops {
     }
 
     /// Return Address for a message given it's signing bytes hash and signature.
+    ///
+    /// Like [`recover_secp_public_key`], this accepts malleable (high-s) signatures. Use
+    /// [`check_low_s`] to reject them.
     pub fn ecrecover(hash: &[u8; 32], signature: &[u8; SECP_SIG_LEN]) -> Result<Address, Error> {
         // recover public key from a message hash and secp signature.
         let key = recover_secp_public_key(hash, signature)?;
         let addr = Address::new_secp256k1(&key)?;
         Ok(addr)
     }
+
+    /// Returns [`Error::MalleableSignature`] if the `s` value of a secp256k1 signature is greater
+    /// than half the curve order.
+    ///
+    /// Negating `s` (and flipping the recovery ID) turns any valid signature into a second, distinct
+    /// signature of the same message by the same key. Only accepting low-s signatures makes
+    /// signatures unique.
+    pub fn check_low_s(signature: &[u8; SECP_SIG_LEN]) -> Result<(), Error> {
+        let signature = EcdsaSignature::from_slice(&signature[..64])?;
+        if signature.normalize_s().is_some() {
+            return Err(Error::MalleableSignature);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "crypto"))]
 mod tests {
     use bls_signatures::{PrivateKey, Serialize, Signature as BlsSignature};
-    use k256::ecdsa::SigningKey;
+    use k256::ecdsa::{Signature as EcdsaSignature, SigningKey};
     use multihash_codetable::Code;
     use multihash_codetable::MultihashDigest;
     use rand::{Rng, SeedableRng};
@@ -311,7 +411,7 @@ mod tests {
     use super::ops::recover_secp_public_key;
     use super::*;
     use crate::Address;
-    use crate::crypto::signature::ops::{ecrecover, verify_bls_aggregate};
+    use crate::crypto::signature::ops::{check_low_s, ecrecover, verify_bls_aggregate};
 
     #[test]
     fn bls_agg_verify() {
@@ -408,6 +508,46 @@ mod tests {
         let recovered = recover_secp_public_key(&hash, &sig).unwrap();
         let hashed = Code::Keccak256.digest(&recovered[1..]);
         assert_eq!(expected, &hashed.digest()[12..]);
+
+        // This vector has a high s.
+        assert_eq!(check_low_s(&sig), Err(Error::MalleableSignature));
+    }
+
+    #[test]
+    fn secp_high_s() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(10);
+
+        let signing_key = SigningKey::random(rng);
+        let encoded_point = signing_key.verifying_key().to_encoded_point(false);
+        let secp_addr = Address::new_secp256k1(encoded_point.as_bytes()).unwrap();
+
+        let hash = [7u8; 32];
+        let (signature, recovery_id) = signing_key
+            .sign_prehash_recoverable(&hash)
+            .expect("signing should not fail");
+        let mut low_s = [0u8; 65];
+        low_s[..64].copy_from_slice(&signature.to_bytes());
+        low_s[64] = recovery_id.to_byte();
+
+        // Construct the malleable dual by negating s and flipping the recovery ID.
+        let dual =
+            EcdsaSignature::from_scalars(signature.r().to_bytes(), (-*signature.s()).to_bytes())
+                .unwrap();
+        assert_ne!(dual, signature);
+        let mut high_s = [0u8; 65];
+        high_s[..64].copy_from_slice(&dual.to_bytes());
+        high_s[64] = recovery_id.to_byte() ^ 1;
+
+        check_low_s(&low_s).unwrap();
+        assert_eq!(check_low_s(&high_s), Err(Error::MalleableSignature));
+
+        // Raw recovery accepts both.
+        assert_eq!(ecrecover(&hash, &low_s).unwrap(), secp_addr);
+        assert_eq!(ecrecover(&hash, &high_s).unwrap(), secp_addr);
+        assert_eq!(
+            recover_secp_public_key(&hash, &high_s).unwrap(),
+            encoded_point.as_bytes()
+        );
     }
 
     #[test]
@@ -443,32 +583,69 @@ mod tests {
 
         assert_eq!(ecrecover(&hash, &sig_bytes).unwrap(), secp_addr);
     }
-}
 
-/// Crypto error
-#[derive(Debug, PartialEq, Eq, Error)]
-pub enum Error {
-    /// Failed to produce a signature
-    #[error("Failed to sign data {0}")]
-    SigningError(String),
-    /// Unable to perform ecrecover with the given params
-    #[error("Could not recover public key from signature: {0}")]
-    InvalidRecovery(String),
-    /// Provided public key is not understood
-    #[error("Invalid generated pub key to create address: {0}")]
-    InvalidPubKey(#[from] AddressError),
-}
+    #[test]
+    fn secp_verify_hashed() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(9);
 
-impl From<Box<dyn error::Error>> for Error {
-    fn from(err: Box<dyn error::Error>) -> Error {
-        // Pass error encountered in signer trait as module error type
-        Error::SigningError(err.to_string())
+        let signing_key = SigningKey::random(rng);
+        let encoded_point = signing_key.verifying_key().to_encoded_point(false);
+        let secp_addr = Address::new_secp256k1(encoded_point.as_bytes()).unwrap();
+
+        let plaintext = b"tag:plaintext";
+        let hash: [u8; 32] = blake2b_simd::Params::new()
+            .hash_length(32)
+            .to_state()
+            .update(plaintext)
+            .finalize()
+            .as_bytes()
+            .try_into()
+            .expect("fixed array size");
+
+        let (signature, recovery_id) = signing_key
+            .sign_prehash_recoverable(&hash)
+            .expect("signing should not fail");
+        let mut sig_bytes = [0u8; 65];
+        sig_bytes[..64].copy_from_slice(&signature.to_bytes());
+        sig_bytes[64] = recovery_id.to_byte();
+
+        // Verifying the digest and verifying the plaintext must agree.
+        ops::verify_secp256k1_sig_hashed(&sig_bytes, &hash, &secp_addr).unwrap();
+        ops::verify_secp256k1_sig(&sig_bytes, plaintext, &secp_addr).unwrap();
+
+        // A different digest must not verify.
+        let mut bad_hash = hash;
+        bad_hash[0] ^= 1;
+        ops::verify_secp256k1_sig_hashed(&sig_bytes, &bad_hash, &secp_addr).unwrap_err();
     }
-}
 
-impl From<EncodingError> for Error {
-    fn from(err: EncodingError) -> Error {
-        // Pass error encountered in signer trait as module error type
-        Error::SigningError(err.to_string())
+    #[test]
+    fn error_conversions() {
+        let err = Error::from(AddressError::InvalidLength);
+        assert_eq!(err, Error::InvalidPubKey(AddressError::InvalidLength));
+        assert_eq!(
+            err.to_string(),
+            "Invalid generated pub key to create address: Invalid address length"
+        );
+
+        let err = Error::from(Box::<dyn error::Error>::from("boom"));
+        assert_eq!(err, Error::SigningError("boom".into()));
+        assert_eq!(err.to_string(), "Failed to sign data boom");
+
+        let encoding_err = fvm_ipld_encoding::from_slice::<u64>(&[]).unwrap_err();
+        let message = encoding_err.to_string();
+        assert_eq!(Error::from(encoding_err), Error::SigningError(message));
+
+        // A zero `r` is invalid.
+        let secp_err = EcdsaSignature::from_slice(&[0; 64]).unwrap_err();
+        let message = format!("Invalid signature: {}", secp_err);
+        let err = Error::from(secp_err);
+        assert_eq!(err, Error::SigningError(message));
+        // Signature parsing errors are reported with the same message.
+        assert_eq!(check_low_s(&[0; SECP_SIG_LEN]), Err(err));
+
+        let bls_err = BlsSignature::from_bytes(&[0; 3]).unwrap_err();
+        let message = format!("Invalid signature: {}", bls_err);
+        assert_eq!(Error::from(bls_err), Error::SigningError(message));
     }
 }
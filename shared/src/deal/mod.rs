@@ -1,3 +1,86 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-pub type DealID = u64;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// The largest valid deal ID.
+pub const MAX_DEAL_ID: u64 = i64::MAX as u64;
+
+/// The ID of a storage deal, assigned by the market actor.
+///
+/// Valid deal IDs are at most [`MAX_DEAL_ID`]. New deal IDs should be constructed with
+/// [`DealID::new`], which enforces this. However, deal IDs are decoded without checking the range,
+/// so that existing state always decodes: inputs must be checked with [`DealID::validate`] where
+/// they enter the system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DealID(u64);
+
+/// The error returned when a deal ID exceeds [`MAX_DEAL_ID`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("deal ID {0} exceeds the maximum of {MAX_DEAL_ID}")]
+pub struct InvalidDealID(pub u64);
+
+impl DealID {
+    /// The largest valid deal ID.
+    pub const MAX: DealID = DealID(MAX_DEAL_ID);
+
+    /// Returns the deal ID, or an error if it exceeds [`MAX_DEAL_ID`].
+    pub const fn new(id: u64) -> Result<Self, InvalidDealID> {
+        if id > MAX_DEAL_ID {
+            return Err(InvalidDealID(id));
+        }
+        Ok(Self(id))
+    }
+
+    /// Returns the deal ID as a u64.
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the deal ID if it's valid (e.g., after decoding it), or an error if it exceeds
+    /// [`MAX_DEAL_ID`].
+    pub const fn validate(self) -> Result<Self, InvalidDealID> {
+        Self::new(self.0)
+    }
+}
+
+impl TryFrom<u64> for DealID {
+    type Error = InvalidDealID;
+
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<DealID> for u64 {
+    fn from(id: DealID) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for DealID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Serialize for DealID {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DealID {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Out-of-range deal IDs are accepted here, see `validate`.
+        u64::deserialize(deserializer).map(Self)
+    }
+}
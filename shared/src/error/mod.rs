@@ -33,6 +33,18 @@ impl ExitCode {
     pub fn is_system_error(self) -> bool {
         self.value < (Self::FIRST_USER_EXIT_CODE)
     }
+
+    /// Returns true if the exit code is one actors may exit with (unless reserved by the current
+    /// network version): either a standard exit code, or an actor-specific one.
+    pub fn is_user(self) -> bool {
+        self.value >= Self::FIRST_USER_EXIT_CODE
+    }
+
+    /// Returns true if the exit code is in the range of standard exit codes, defined by the
+    /// built-in actors' calling convention. Unnamed codes in this range are reserved.
+    pub fn is_standard(self) -> bool {
+        self.is_user() && self.value < Self::FIRST_ACTOR_SPECIFIC_EXIT_CODE
+    }
 }
 
 impl From<u32> for ExitCode {
@@ -78,8 +90,12 @@ impl ExitCode {
     pub const SYS_ASSERTION_FAILED: ExitCode = ExitCode::new(10);
     /// The actor returned a block handle that doesn't exist
     pub const SYS_MISSING_RETURN: ExitCode = ExitCode::new(11);
-    // pub const SYS_RESERVED_12: ExitCode = ExitCode::new(12);
-    // pub const SYS_RESERVED_13: ExitCode = ExitCode::new(13);
+    /// The message receiver's code CID has been denied by the network or the node executing the
+    /// message, so the actor was not invoked.
+    pub const SYS_DENIED_CODE: ExitCode = ExitCode::new(12);
+    /// The message receiver targets a syscall ABI version this FVM doesn't support, or declares
+    /// its syscall ABI version incorrectly, so the actor could not be instantiated.
+    pub const SYS_UNSUPPORTED_ABI: ExitCode = ExitCode::new(13);
     // pub const SYS_RESERVED_14: ExitCode = ExitCode::new(14);
     // pub const SYS_RESERVED_15: ExitCode = ExitCode::new(15);
 
@@ -114,6 +130,9 @@ impl ExitCode {
     // pub const RESERVED_29: ExitCode = ExitCode::new(29);
     // pub const RESERVED_30: ExitCode = ExitCode::new(30);
     // pub const RESERVED_31: ExitCode = ExitCode::new(31);
+
+    /// The lowest exit code with an actor-specific meaning.
+    pub const FIRST_ACTOR_SPECIFIC_EXIT_CODE: u32 = 32;
 }
 
 /// When a syscall fails, it returns an `ErrorNumber` to indicate why. The syscalls themselves
@@ -9,6 +9,7 @@ use crate::ActorID;
 /// Event with extra information stamped by the FVM. This is the structure that gets committed
 /// on-chain via the receipt.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+#[serde_tuple(optional_trailing)]
 pub struct StampedEvent {
     /// Carries the ID of the actor that emitted this event.
     pub emitter: ActorID,
@@ -24,6 +25,7 @@ impl StampedEvent {
 
 /// An event as originally emitted by the actor.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+#[serde_tuple(optional_trailing)]
 #[serde(transparent)]
 pub struct ActorEvent {
     pub entries: Vec<Entry>,
@@ -49,6 +51,7 @@ bitflags! {
 
 /// A key value entry inside an Event.
 #[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+#[serde_tuple(optional_trailing)]
 pub struct Entry {
     /// A bitmap conveying metadata or hints about this entry.
     pub flags: Flags,
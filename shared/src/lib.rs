@@ -13,6 +13,7 @@ pub mod bigint;
 pub mod chainid;
 pub mod clock;
 pub mod commcid;
+pub mod conformance;
 pub mod consensus;
 pub mod crypto;
 pub mod deal;
@@ -20,6 +21,7 @@ pub mod econ;
 pub mod error;
 pub mod event;
 pub mod message;
+pub mod method;
 pub mod piece;
 pub mod randomness;
 pub mod receipt;
@@ -45,6 +47,9 @@ pub const IDENTITY_HASH: u64 = 0x0;
 /// The maximum supported CID size.
 pub const MAX_CID_LEN: usize = 100;
 
+/// The maximum size of a block, in bytes, under the default network limits.
+pub const MAX_BLOCK_SIZE: u32 = 1 << 20;
+
 /// Default bit width for the hamt in the filecoin protocol.
 pub const HAMT_BIT_WIDTH: u32 = 5;
 
@@ -6,9 +6,11 @@ use anyhow::anyhow;
 use fvm_ipld_encoding::RawBytes;
 use fvm_ipld_encoding::de::{Deserialize, Deserializer};
 use fvm_ipld_encoding::ser::{Serialize, Serializer};
+use fvm_ipld_encoding::tuple::*;
 
 use crate::MethodNum;
 use crate::address::Address;
+use crate::crypto::signature::Signature;
 use crate::econ::TokenAmount;
 
 /// Default Unsigned VM message type which includes all data needed for a state transition
@@ -94,6 +96,15 @@ impl<'de> Deserialize<'de> for Message {
     }
 }
 
+/// A message signed by its sender's key. Both secp256k1 and BLS signatures sign the bytes of the
+/// unsigned message's CID (DAG-CBOR, blake2b-256).
+#[derive(PartialEq, Clone, Debug, Hash, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
+pub struct SignedMessage {
+    pub message: Message,
+    pub signature: Signature,
+}
+
 #[cfg(feature = "arb")]
 impl quickcheck::Arbitrary for Message {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
@@ -0,0 +1,338 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Method numbers derived from method names, following
+//! [FRC-0042](https://github.com/filecoin-project/FIPs/blob/master/FRCs/frc-0042.md).
+//!
+//! A method's number is the first big-endian 32-bit word of `blake2b-512("1|" + name)` that's at
+//! least [`FIRST_EXPORTED_METHOD_NUMBER`], keeping numbers below it (including the reserved send
+//! and constructor numbers) free. The `Constructor` method is always [`METHOD_CONSTRUCTOR`].
+//!
+//! Everything here can be evaluated at compile time, e.g., to dispatch on method numbers:
+//!
+//! ```
+//! use fvm_shared::method::{Method, MethodResolver};
+//! use fvm_shared::{MethodNum, method_hash};
+//!
+//! struct Transfer;
+//!
+//! impl Method for Transfer {
+//!     const NAME: &'static str = "Transfer";
+//! }
+//!
+//! const BURN: MethodNum = method_hash!("Burn");
+//! const METHODS: MethodResolver<3> = MethodResolver::new(["Constructor", "Transfer", "Burn"]);
+//!
+//! fn dispatch(method: MethodNum) -> &'static str {
+//!     match method {
+//!         Transfer::NUMBER => "transfer",
+//!         BURN => "burn",
+//!         _ => METHODS.name(method).unwrap_or("unhandled"),
+//!     }
+//! }
+//!
+//! assert_eq!(dispatch(80475954), "transfer");
+//! assert_eq!(dispatch(1), "Constructor");
+//! assert_eq!(METHODS.number("Burn"), Some(1434719642));
+//! ```
+//!
+//! Two methods of a resolver with the same number (e.g., the same name twice) fail to compile:
+//!
+//! ```compile_fail
+//! use fvm_shared::method::MethodResolver;
+//!
+//! const METHODS: MethodResolver<2> = MethodResolver::new(["Transfer", "Transfer"]);
+//! ```
+use thiserror::Error;
+
+use crate::{METHOD_CONSTRUCTOR, MethodNum};
+
+/// The smallest number of a method numbered by name, other than the constructor.
+pub const FIRST_EXPORTED_METHOD_NUMBER: MethodNum = 1 << 24;
+
+/// The name of the constructor, numbered [`METHOD_CONSTRUCTOR`].
+pub const CONSTRUCTOR_METHOD_NAME: &str = "Constructor";
+
+/// The prefix hashed before method names.
+const NAME_PREFIX: &[u8] = b"1|";
+
+/// Returns the number of the method with the given name, as a constant:
+/// `method_hash!("Transfer")`. Invalid names fail to compile.
+#[macro_export]
+macro_rules! method_hash {
+    ($name:expr) => {
+        const { $crate::method::method_number($name) }
+    };
+}
+
+/// The error returned when a method can't be numbered by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum MethodNameError {
+    #[error("method names may not be empty")]
+    Empty,
+    #[error("method names must start with an uppercase letter, followed by letters, digits and _")]
+    IllegalName,
+    #[error("no word of the method name's hash is a valid method number")]
+    IndeterminableNumber,
+}
+
+/// Returns the number of the method with the given name, or an error if the name isn't valid.
+pub const fn try_method_number(name: &str) -> Result<MethodNum, MethodNameError> {
+    let name = name.as_bytes();
+    if name.is_empty() {
+        return Err(MethodNameError::Empty);
+    }
+    if !name[0].is_ascii_uppercase() {
+        return Err(MethodNameError::IllegalName);
+    }
+    let mut i = 1;
+    while i < name.len() {
+        if !name[i].is_ascii_alphanumeric() && name[i] != b'_' {
+            return Err(MethodNameError::IllegalName);
+        }
+        i += 1;
+    }
+    if bytes_eq(name, CONSTRUCTOR_METHOD_NAME.as_bytes()) {
+        return Ok(METHOD_CONSTRUCTOR);
+    }
+
+    let digest = blake2b_512(NAME_PREFIX, name);
+    let mut i = 0;
+    while i < digest.len() {
+        let word =
+            u32::from_be_bytes([digest[i], digest[i + 1], digest[i + 2], digest[i + 3]]) as u64;
+        if word >= FIRST_EXPORTED_METHOD_NUMBER {
+            return Ok(word);
+        }
+        i += 4;
+    }
+    Err(MethodNameError::IndeterminableNumber)
+}
+
+/// Returns the number of the method with the given name.
+///
+/// # Panics
+///
+/// Panics if the name isn't valid (at compile time, when evaluated as a constant).
+pub const fn method_number(name: &str) -> MethodNum {
+    match try_method_number(name) {
+        Ok(number) => number,
+        Err(MethodNameError::Empty) => panic!("method names may not be empty"),
+        Err(MethodNameError::IllegalName) => panic!("illegal method name"),
+        Err(MethodNameError::IndeterminableNumber) => panic!("method name has no valid number"),
+    }
+}
+
+/// A method numbered by name.
+pub trait Method {
+    /// The method's name.
+    const NAME: &'static str;
+    /// The method's number. Invalid names fail to compile when this is used.
+    const NUMBER: MethodNum = method_number(Self::NAME);
+}
+
+/// The methods exported by an actor, numbered by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodResolver<const N: usize> {
+    methods: [(&'static str, MethodNum); N],
+}
+
+impl<const N: usize> MethodResolver<N> {
+    /// Numbers the given methods.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name isn't valid, or if two methods have the same number (at compile time, when
+    /// evaluated as a constant).
+    pub const fn new(names: [&'static str; N]) -> Self {
+        let mut methods = [("", 0); N];
+        let mut i = 0;
+        while i < N {
+            let number = method_number(names[i]);
+            let mut j = 0;
+            while j < i {
+                if methods[j].1 == number {
+                    panic!("two methods have the same number");
+                }
+                j += 1;
+            }
+            methods[i] = (names[i], number);
+            i += 1;
+        }
+        Self { methods }
+    }
+
+    /// Returns the number of the method with the given name, if it's one of these methods.
+    pub const fn number(&self, name: &str) -> Option<MethodNum> {
+        let mut i = 0;
+        while i < N {
+            if bytes_eq(self.methods[i].0.as_bytes(), name.as_bytes()) {
+                return Some(self.methods[i].1);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Returns the name of the method with the given number, if it's one of these methods.
+    pub const fn name(&self, number: MethodNum) -> Option<&'static str> {
+        let mut i = 0;
+        while i < N {
+            if self.methods[i].1 == number {
+                return Some(self.methods[i].0);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Returns the methods' names and numbers, in the order they were given.
+    pub const fn methods(&self) -> &[(&'static str, MethodNum); N] {
+        &self.methods
+    }
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+const BLAKE2B_BLOCK_LEN: usize = 128;
+
+/// Computes the (unkeyed) blake2b-512 digest of `prefix` followed by `data`, at compile time.
+const fn blake2b_512(prefix: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut h = BLAKE2B_IV;
+    // Parameter block: 64 byte digest, no key, fanout and depth 1.
+    h[0] ^= 0x0101_0040;
+
+    let len = prefix.len() + data.len();
+    let mut offset = 0;
+    loop {
+        let mut block = [0u8; BLAKE2B_BLOCK_LEN];
+        let mut i = 0;
+        while i < BLAKE2B_BLOCK_LEN && offset + i < len {
+            let pos = offset + i;
+            block[i] = if pos < prefix.len() {
+                prefix[pos]
+            } else {
+                data[pos - prefix.len()]
+            };
+            i += 1;
+        }
+        offset += i;
+        let last = offset == len;
+        blake2b_compress(&mut h, &block, offset as u128, last);
+        if last {
+            break;
+        }
+    }
+
+    let mut out = [0u8; 64];
+    let mut i = 0;
+    while i < 8 {
+        let word = h[i].to_le_bytes();
+        let mut j = 0;
+        while j < 8 {
+            out[i * 8 + j] = word[j];
+            j += 1;
+        }
+        i += 1;
+    }
+    out
+}
+
+const fn blake2b_compress(
+    h: &mut [u64; 8],
+    block: &[u8; BLAKE2B_BLOCK_LEN],
+    counter: u128,
+    last: bool,
+) {
+    let mut m = [0u64; 16];
+    let mut i = 0;
+    while i < 16 {
+        let mut word = [0u8; 8];
+        let mut j = 0;
+        while j < 8 {
+            word[j] = block[i * 8 + j];
+            j += 1;
+        }
+        m[i] = u64::from_le_bytes(word);
+        i += 1;
+    }
+
+    let mut v = [0u64; 16];
+    let mut i = 0;
+    while i < 8 {
+        v[i] = h[i];
+        v[i + 8] = BLAKE2B_IV[i];
+        i += 1;
+    }
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    let mut round = 0;
+    while round < 12 {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        blake2b_g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        blake2b_g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        blake2b_g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        blake2b_g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        blake2b_g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        blake2b_g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        blake2b_g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        round += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        h[i] ^= v[i] ^ v[i + 8];
+        i += 1;
+    }
+}
+
+const fn blake2b_g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
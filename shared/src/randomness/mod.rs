@@ -3,16 +3,64 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 
 use fvm_ipld_encoding::{BytesDe, BytesSer};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use num_derive::FromPrimitive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use thiserror::Error;
 
-// TODO: turn this back into a 32byte array once we no longer need go compat. It's a vec so that the
-// errors match.
-/// String of random bytes usually generated from a randomness beacon or from tickets on chain.
-#[derive(PartialEq, Eq, Default, Clone, Debug)]
-pub struct Randomness(pub Vec<u8>);
+use crate::clock::ChainEpoch;
 
 pub const RANDOMNESS_LENGTH: usize = 32;
 
+/// String of random bytes usually generated from a randomness beacon or from tickets on chain.
+///
+/// Encoded as a CBOR byte string of exactly [`RANDOMNESS_LENGTH`] bytes. Byte strings of any other
+/// length are rejected when decoding.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Hash)]
+pub struct Randomness(pub [u8; RANDOMNESS_LENGTH]);
+
+/// The error returned when converting bytes of the wrong length to [`Randomness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("randomness must be {RANDOMNESS_LENGTH} bytes, got {0}")]
+pub struct InvalidRandomnessLength(pub usize);
+
+impl Randomness {
+    /// Converts a vector of bytes to randomness, or fails if it isn't [`RANDOMNESS_LENGTH`] bytes
+    /// long.
+    #[deprecated = "Randomness wraps a fixed-size array. Use `Randomness::from` or `Randomness::try_from`."]
+    pub fn from_vec(bytes: Vec<u8>) -> Result<Self, InvalidRandomnessLength> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl From<[u8; RANDOMNESS_LENGTH]> for Randomness {
+    fn from(bytes: [u8; RANDOMNESS_LENGTH]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Randomness {
+    type Error = InvalidRandomnessLength;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        bytes
+            .try_into()
+            .map(Self)
+            .map_err(|_| InvalidRandomnessLength(bytes.len()))
+    }
+}
+
+impl From<Randomness> for [u8; RANDOMNESS_LENGTH] {
+    fn from(randomness: Randomness) -> Self {
+        randomness.0
+    }
+}
+
+impl AsRef<[u8]> for Randomness {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl Serialize for Randomness {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -28,6 +76,52 @@ impl<'de> Deserialize<'de> for Randomness {
         D: Deserializer<'de>,
     {
         let bytes = BytesDe::deserialize(deserializer)?;
-        Ok(Self(bytes.0))
+        Self::try_from(bytes.0.as_slice())
+            .map_err(|_| de::Error::invalid_length(bytes.0.len(), &"32 bytes of randomness"))
     }
 }
+
+/// Domain separation tags for randomness, mixed into the randomness drawn with [`draw_randomness`]
+/// so that different consumers of the same randomness get independent values.
+///
+/// The values are part of consensus and match the Go implementation (`go-state-types`).
+#[derive(FromPrimitive, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(i64)]
+pub enum DomainSeparationTag {
+    TicketProduction = 1,
+    ElectionProofProduction = 2,
+    WinningPoStChallengeSeed = 3,
+    WindowedPoStChallengeSeed = 4,
+    SealRandomness = 5,
+    InteractiveSealChallengeSeed = 6,
+    WindowedPoStDeadlineAssignment = 7,
+    MarketDealCronSeed = 8,
+    PoStChainCommit = 9,
+    EvmPrevRandao = 10,
+}
+
+/// Draws randomness for the given domain separation tag, epoch, and entropy from a randomness base,
+/// as returned by the chain and beacon randomness syscalls (a digest of the ticket or beacon
+/// entry, not the raw VRF proof).
+///
+/// This is the blake2b-256 hash of the tag (big-endian i64), the randomness base, the epoch
+/// (big-endian i64), and the entropy, in that order, matching Lotus' `DrawRandomnessFromDigest`.
+pub fn draw_randomness(
+    rand_base: &[u8; RANDOMNESS_LENGTH],
+    tag: DomainSeparationTag,
+    round: ChainEpoch,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    let mut hasher = blake2b_simd::Params::new()
+        .hash_length(RANDOMNESS_LENGTH)
+        .to_state();
+    hasher.update(&(tag as i64).to_be_bytes());
+    hasher.update(rand_base);
+    hasher.update(&round.to_be_bytes());
+    hasher.update(entropy);
+    hasher
+        .finalize()
+        .as_bytes()
+        .try_into()
+        .expect("blake2b-256 hashes are 32 bytes")
+}
@@ -10,6 +10,7 @@ use crate::error::ExitCode;
 
 /// Result of a state transition from a message
 #[derive(Serialize_tuple, Deserialize_tuple, Debug, PartialEq, Eq, Clone)]
+#[serde_tuple(optional_trailing)]
 pub struct Receipt {
     pub exit_code: ExitCode,
     pub return_data: RawBytes,
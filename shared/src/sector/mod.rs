@@ -12,14 +12,93 @@ use fvm_ipld_encoding::repr::*;
 use fvm_ipld_encoding::tuple::*;
 use num_bigint::BigInt;
 use num_derive::FromPrimitive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
 
 pub use self::post::*;
 pub use self::registered_proof::*;
 pub use self::seal::*;
 use crate::ActorID;
 
+/// The largest valid sector number.
+pub const MAX_SECTOR_NUMBER: u64 = i64::MAX as u64;
+
 /// SectorNumber is a numeric identifier for a sector. It is usually relative to a miner.
-pub type SectorNumber = u64;
+///
+/// Valid sector numbers are at most [`MAX_SECTOR_NUMBER`]. New sector numbers should be constructed
+/// with [`SectorNumber::new`], which enforces this. However, sector numbers are decoded without
+/// checking the range, so that existing state always decodes: inputs must be checked with
+/// [`SectorNumber::validate`] where they enter the system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SectorNumber(u64);
+
+/// The error returned when a sector number exceeds [`MAX_SECTOR_NUMBER`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("sector number {0} exceeds the maximum of {MAX_SECTOR_NUMBER}")]
+pub struct InvalidSectorNumber(pub u64);
+
+impl SectorNumber {
+    /// The largest valid sector number.
+    pub const MAX: SectorNumber = SectorNumber(MAX_SECTOR_NUMBER);
+
+    /// Returns the sector number, or an error if it exceeds [`MAX_SECTOR_NUMBER`].
+    pub const fn new(number: u64) -> Result<Self, InvalidSectorNumber> {
+        if number > MAX_SECTOR_NUMBER {
+            return Err(InvalidSectorNumber(number));
+        }
+        Ok(Self(number))
+    }
+
+    /// Returns the sector number as a u64.
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the sector number if it's valid (e.g., after decoding it), or an error if it exceeds
+    /// [`MAX_SECTOR_NUMBER`].
+    pub const fn validate(self) -> Result<Self, InvalidSectorNumber> {
+        Self::new(self.0)
+    }
+}
+
+impl TryFrom<u64> for SectorNumber {
+    type Error = InvalidSectorNumber;
+
+    fn try_from(number: u64) -> Result<Self, Self::Error> {
+        Self::new(number)
+    }
+}
+
+impl From<SectorNumber> for u64 {
+    fn from(number: SectorNumber) -> Self {
+        number.0
+    }
+}
+
+impl fmt::Display for SectorNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Serialize for SectorNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SectorNumber {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Out-of-range sector numbers are accepted here, see `validate`.
+        u64::deserialize(deserializer).map(Self)
+    }
+}
 
 /// Unit of storage power (measured in bytes)
 pub type StoragePower = BigInt;
@@ -43,6 +122,7 @@ impl fmt::Display for SectorSize {
 
 /// Sector ID which contains the sector number and the actor ID for the miner.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct SectorID {
     pub miner: ActorID,
     pub number: SectorNumber,
@@ -14,6 +14,7 @@ pub type PoStRandomness = Randomness;
 
 /// Information about a sector necessary for PoSt verification
 #[derive(Debug, PartialEq, Clone, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct SectorInfo {
     /// Used when sealing - needs to be mapped to PoSt registered proof when used to verify a PoSt
     pub proof: RegisteredSealProof,
@@ -23,6 +24,7 @@ pub struct SectorInfo {
 
 /// Proof of spacetime data stored on chain.
 #[derive(Debug, PartialEq, Clone, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct PoStProof {
     pub post_proof: RegisteredPoStProof,
     #[serde(with = "strict_bytes")]
@@ -56,6 +58,7 @@ impl quickcheck::Arbitrary for PoStProof {
 /// Information needed to verify a Winning PoSt attached to a block header.
 /// Note: this is not used within the state machine, but by the consensus/election mechanisms.
 #[derive(Debug, PartialEq, Default, Clone, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct WinningPoStVerifyInfo {
     pub randomness: PoStRandomness,
     pub proofs: Vec<PoStProof>,
@@ -66,6 +69,7 @@ pub struct WinningPoStVerifyInfo {
 
 /// Information needed to verify a Window PoSt submitted directly to a miner actor.
 #[derive(Debug, PartialEq, Default, Clone, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct WindowPoStVerifyInfo {
     pub randomness: PoStRandomness,
     pub proofs: Vec<PoStProof>,
@@ -75,6 +79,7 @@ pub struct WindowPoStVerifyInfo {
 
 /// Information submitted by a miner to provide a Window PoSt.
 #[derive(Debug, PartialEq, Default, Clone, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct OnChainWindowPoStVerifyInfo {
     pub proofs: Vec<PoStProof>,
 }
@@ -21,6 +21,7 @@ pub type InteractiveSealRandomness = Randomness;
 
 /// Information needed to verify a seal proof.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct SealVerifyInfo {
     pub registered_proof: RegisteredSealProof,
     pub sector_id: SectorID,
@@ -38,6 +39,7 @@ pub struct SealVerifyInfo {
 /// state tree but will be verified in sm.CommitSector. See SealCommitment for
 /// data stored on the state tree for each sector.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct SealVerifyParams {
     pub sealed_cid: Cid,
     pub interactive_epoch: ChainEpoch,
@@ -51,6 +53,7 @@ pub struct SealVerifyParams {
 
 /// Information needed to verify an aggregated seal proof.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct AggregateSealVerifyInfo {
     pub sector_number: SectorNumber,
     pub randomness: SealRandomness,
@@ -61,6 +64,7 @@ pub struct AggregateSealVerifyInfo {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct AggregateSealVerifyProofAndInfos {
     pub miner: ActorID,
     pub seal_proof: RegisteredSealProof,
@@ -72,6 +76,7 @@ pub struct AggregateSealVerifyProofAndInfos {
 
 /// Information needed to verify a replica update
 #[derive(Clone, Debug, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct ReplicaUpdateInfo {
     pub update_proof_type: RegisteredUpdateProof,
     pub old_sealed_cid: Cid,
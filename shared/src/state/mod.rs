@@ -35,6 +35,7 @@ pub enum StateTreeVersion {
 /// State root information. Contains information about the version of the state tree,
 /// the root of the tree, and a link to the information about the tree.
 #[derive(Deserialize_tuple, Serialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct StateRoot {
     /// State tree version
     pub version: StateTreeVersion,
@@ -53,6 +54,7 @@ pub struct StateInfo0([(); 0]);
 
 /// State of all actor implementations.
 #[derive(PartialEq, Eq, Clone, Debug, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct ActorState {
     /// Link to code for the actor.
     pub code: Cid,
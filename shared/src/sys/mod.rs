@@ -11,6 +11,13 @@ pub mod out;
 pub type BlockId = u32;
 pub type Codec = u64;
 
+/// The name of the Wasm custom section in which an actor declares the syscall ABI version it
+/// targets. The section's payload is the version, a single LEB128-encoded `u32`.
+pub const SYSCALL_ABI_SECTION: &str = "fvm_syscall_abi";
+
+/// The syscall ABI version targeted by actors that don't declare one.
+pub const DEFAULT_SYSCALL_ABI_VERSION: u32 = 1;
+
 /// The token amount type used in syscalls. It can represent any token amount (in atto-FIL) from 0
 /// to `2^128-1` attoFIL. Or 0 to about 340 exaFIL.
 ///
@@ -23,29 +30,40 @@ pub struct TokenAmount {
     pub hi: u64,
 }
 
+impl From<u128> for TokenAmount {
+    fn from(v: u128) -> Self {
+        Self {
+            hi: (v >> u64::BITS) as u64,
+            lo: v as u64,
+        }
+    }
+}
+
+impl From<TokenAmount> for u128 {
+    fn from(v: TokenAmount) -> Self {
+        ((v.hi as u128) << u64::BITS) | (v.lo as u128)
+    }
+}
+
 impl From<TokenAmount> for crate::econ::TokenAmount {
     fn from(v: TokenAmount) -> Self {
-        crate::econ::TokenAmount::from_atto(((v.hi as u128) << 64) | (v.lo as u128))
+        crate::econ::TokenAmount::from_atto(u128::from(v))
     }
 }
 
+/// Fails if the amount is negative or doesn't fit in 128 bits.
 impl TryFrom<crate::econ::TokenAmount> for TokenAmount {
     type Error = TryFromBigIntError<()>;
     fn try_from(v: crate::econ::TokenAmount) -> Result<Self, Self::Error> {
-        v.atto().try_into().map(|v: u128| Self {
-            hi: (v >> u64::BITS) as u64,
-            lo: v as u64,
-        })
+        (&v).try_into()
     }
 }
 
+/// Fails if the amount is negative or doesn't fit in 128 bits.
 impl<'a> TryFrom<&'a crate::econ::TokenAmount> for TokenAmount {
     type Error = TryFromBigIntError<()>;
     fn try_from(v: &'a crate::econ::TokenAmount) -> Result<Self, Self::Error> {
-        v.atto().try_into().map(|v: u128| Self {
-            hi: (v >> u64::BITS) as u64,
-            lo: v as u64,
-        })
+        v.atto().try_into().map(|v: u128| v.into())
     }
 }
 
@@ -105,8 +123,10 @@ assert_syscall_safe! {
     TokenAmount,
     out::ipld::IpldOpen,
     out::ipld::IpldStat,
+    out::ipld::IpldStatLinks,
     out::send::Send,
     out::crypto::VerifyConsensusFault,
+    out::crypto::VerifyConsensusFaultReport,
     out::network::NetworkContext,
     out::vm::MessageContext,
 }
@@ -31,6 +31,14 @@ pub mod ipld {
         pub codec: u64,
         pub size: u32,
     }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct IpldStatLinks {
+        pub codec: u64,
+        pub size: u32,
+        pub links: u32,
+    }
 }
 
 pub mod send {
@@ -47,7 +55,7 @@ pub mod send {
 }
 
 pub mod crypto {
-    use crate::{ActorID, ChainEpoch};
+    use crate::{ActorID, ChainEpoch, MAX_CID_LEN};
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     #[repr(packed, C)]
@@ -56,6 +64,42 @@ pub mod crypto {
         pub target: ActorID,
         pub fault: u32,
     }
+
+    /// A consensus fault, along with the at-fault miner's power at the fault epoch.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct VerifyConsensusFaultReport {
+        pub epoch: ChainEpoch,
+        pub target: ActorID,
+        /// The fault type, or 0 if there's no fault (in which case the other fields are 0).
+        pub fault: u32,
+        /// The raw byte power, as the low and high 64 bits of a u128.
+        pub raw_byte_power: [u64; 2],
+        /// The quality-adjusted power, as the low and high 64 bits of a u128.
+        pub quality_adj_power: [u64; 2],
+    }
+
+    /// The result of computing a single unsealed sector CID in a batch.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    #[repr(packed, C)]
+    pub struct ComputeUnsealedSectorCid {
+        /// 0 on success, otherwise the error number of the failed computation.
+        pub error: u32,
+        /// The length of the CID written to `cid` (0 on failure).
+        pub cid_len: u32,
+        /// The computed CID, in its binary representation, padded with zeros.
+        pub cid: [u8; MAX_CID_LEN],
+    }
+
+    impl Default for ComputeUnsealedSectorCid {
+        fn default() -> Self {
+            Self {
+                error: 0,
+                cid_len: 0,
+                cid: [0; MAX_CID_LEN],
+            }
+        }
+    }
 }
 
 pub mod vm {
@@ -86,7 +130,8 @@ pub mod vm {
     pub struct MessageContext {
         /// The current call's origin actor ID.
         pub origin: ActorID,
-        /// The nonce from the explicit message.
+        /// The nonce of the explicit (top-level) message sent by the origin. This is the same for
+        /// every call made while executing the message.
         pub nonce: u64,
         /// The caller's actor ID.
         pub caller: ActorID,
@@ -4,6 +4,7 @@ use cid::Cid;
 use fvm_ipld_encoding::tuple::*;
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize_tuple, Deserialize_tuple)]
+#[serde_tuple(optional_trailing)]
 pub struct UpgradeInfo {
     // the old code cid we are upgrading from
     pub old_code_cid: Cid,
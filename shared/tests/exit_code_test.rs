@@ -0,0 +1,65 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The exit codes are part of consensus (they're recorded in receipts), so their values and the
+//! partition of the exit code space must never change.
+
+use fvm_shared::conformance::EXIT_CODES;
+use fvm_shared::error::ExitCode;
+
+/// The value of each named exit code, in order. New codes may be added, but values must never
+/// change.
+const NAMED_EXIT_CODE_VALUES: &[u32] = &[
+    0, 1, 2, 4, 5, 6, 7, 9, 10, 11, 12, 13, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+];
+
+#[test]
+fn named_exit_code_values() {
+    let values: Vec<u32> = EXIT_CODES.iter().map(|info| info.code.value()).collect();
+    assert_eq!(values, NAMED_EXIT_CODE_VALUES);
+
+    for info in EXIT_CODES {
+        let (code, name) = (info.code, info.name);
+        assert_eq!(ExitCode::from(code.value()), code, "{name}");
+        if name.starts_with("SYS_") {
+            assert!(code.is_system_error() && !code.is_success(), "{name}");
+        } else if name.starts_with("USR_") {
+            assert!(code.is_standard(), "{name}");
+        } else {
+            assert_eq!((code, name), (ExitCode::OK, "OK"));
+        }
+    }
+    assert!(EXIT_CODES.iter().any(
+        |info| (info.code, info.name) == (ExitCode::SYS_UNSUPPORTED_ABI, "SYS_UNSUPPORTED_ABI")
+    ));
+}
+
+#[test]
+fn exit_code_partition() {
+    let classify = |value: u32| {
+        let code = ExitCode::new(value);
+        (
+            code.is_success(),
+            code.is_system_error(),
+            code.is_user(),
+            code.is_standard(),
+        )
+    };
+    // Success is also in the range reserved for the VM.
+    assert_eq!(classify(0), (true, true, false, false));
+    for value in [1, ExitCode::FIRST_USER_EXIT_CODE - 1] {
+        assert_eq!(classify(value), (false, true, false, false), "{value}");
+    }
+    for value in [
+        ExitCode::FIRST_USER_EXIT_CODE,
+        ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE - 1,
+    ] {
+        assert_eq!(classify(value), (false, false, true, true), "{value}");
+    }
+    for value in [ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE, u32::MAX] {
+        assert_eq!(classify(value), (false, false, true, false), "{value}");
+    }
+
+    assert_eq!(ExitCode::FIRST_USER_EXIT_CODE, 16);
+    assert_eq!(ExitCode::FIRST_ACTOR_SPECIFIC_EXIT_CODE, 32);
+}
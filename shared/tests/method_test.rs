@@ -0,0 +1,110 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! FRC-0042 method numbers, checked against the numbers used by the builtin actors (and computed
+//! by the Go implementation of the same convention).
+
+use fvm_shared::method::{
+    FIRST_EXPORTED_METHOD_NUMBER, Method, MethodNameError, MethodResolver, method_number,
+    try_method_number,
+};
+use fvm_shared::{METHOD_CONSTRUCTOR, MethodNum, method_hash};
+
+const REFERENCE: &[(&str, MethodNum)] = &[
+    ("Constructor", 1),
+    ("AuthenticateMessage", 2643134072),
+    ("Receive", 3726118371),
+    ("InvokeEVM", 3844450837),
+    ("Name", 48890204),
+    ("Symbol", 2061153854),
+    ("TotalSupply", 114981429),
+    ("BalanceOf", 2266030508),
+    ("Transfer", 80475954),
+    ("Mint", 116935346),
+    ("Burn", 1434719642),
+    ("AddBalance", 822473126),
+    ("PublishStorageDeals", 2236929350),
+];
+
+#[test]
+fn reference_numbers() {
+    for &(name, number) in REFERENCE {
+        assert_eq!(method_number(name), number, "{name}");
+    }
+}
+
+#[test]
+fn constant_numbers() {
+    struct Receive;
+    impl Method for Receive {
+        const NAME: &'static str = "Receive";
+    }
+
+    const INVOKE_EVM: MethodNum = method_hash!("InvokeEVM");
+    assert_eq!(INVOKE_EVM, 3844450837);
+    assert_eq!(Receive::NUMBER, 3726118371);
+    assert_eq!(method_hash!("Constructor"), METHOD_CONSTRUCTOR);
+}
+
+#[test]
+fn numbers_in_range() {
+    // Long names span several blake2b blocks.
+    let long = "A".repeat(300);
+    for name in ["A", "Z9", "With_Underscore", &long] {
+        assert!(
+            method_number(name) >= FIRST_EXPORTED_METHOD_NUMBER,
+            "{name}"
+        );
+        assert!(method_number(name) <= u32::MAX as MethodNum, "{name}");
+    }
+    assert_eq!(method_number(&long), 468410153);
+}
+
+#[test]
+fn invalid_names() {
+    assert_eq!(try_method_number(""), Err(MethodNameError::Empty));
+    for name in [
+        "transfer",
+        "_Transfer",
+        "1Transfer",
+        "Trans fer",
+        "Trans-fer",
+        "Tränsfer",
+    ] {
+        assert_eq!(
+            try_method_number(name),
+            Err(MethodNameError::IllegalName),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "illegal method name")]
+fn invalid_name_panics() {
+    method_number("transfer");
+}
+
+#[test]
+fn resolver() {
+    const METHODS: MethodResolver<3> = MethodResolver::new(["Constructor", "Mint", "Burn"]);
+    assert_eq!(METHODS.number("Mint"), Some(116935346));
+    assert_eq!(METHODS.number("Transfer"), None);
+    assert_eq!(METHODS.name(1), Some("Constructor"));
+    assert_eq!(METHODS.name(1434719642), Some("Burn"));
+    assert_eq!(METHODS.name(2), None);
+    assert_eq!(
+        METHODS.methods(),
+        &[
+            ("Constructor", 1),
+            ("Mint", 116935346),
+            ("Burn", 1434719642)
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "two methods have the same number")]
+fn resolver_collision_panics() {
+    MethodResolver::new(["Mint", "Burn", "Mint"]);
+}
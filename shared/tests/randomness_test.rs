@@ -0,0 +1,240 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Golden tests for drawing randomness. The tag values and the hashing scheme are part of
+//! consensus: if one of these tests fails, actors would draw different randomness than other
+//! implementations.
+
+use fvm_ipld_encoding::{from_slice, to_vec};
+use fvm_shared::randomness::{
+    DomainSeparationTag, InvalidRandomnessLength, RANDOMNESS_LENGTH, Randomness, draw_randomness,
+};
+use fvm_shared::sector::WindowPoStVerifyInfo;
+use num_traits::FromPrimitive;
+
+/// The bytes `0..32`.
+const BASE_1: [u8; RANDOMNESS_LENGTH] = {
+    let mut base = [0u8; RANDOMNESS_LENGTH];
+    let mut i = 0;
+    while i < RANDOMNESS_LENGTH {
+        base[i] = i as u8;
+        i += 1;
+    }
+    base
+};
+
+/// A VRF output, as found in a block ticket.
+const TICKET: &[u8] = b"ticket";
+
+/// A beacon entry's data: the bytes `0..96`, the size of a drand signature.
+const BEACON_ENTRY: [u8; 96] = {
+    let mut entry = [0u8; 96];
+    let mut i = 0;
+    while i < entry.len() {
+        entry[i] = i as u8;
+        i += 1;
+    }
+    entry
+};
+
+/// A transcription of Lotus' `DrawRandomnessFromBase` (`chain/rand/rand.go`), which digests a
+/// ticket's or beacon entry's VRF output into the randomness base, then draws randomness from it
+/// like `DrawRandomnessFromDigest`. Kept step by step, with the Go code it follows, so that it can
+/// be compared against Lotus by reading.
+fn lotus_draw_randomness_from_base(
+    rbase: &[u8],
+    pers: DomainSeparationTag,
+    round: i64,
+    entropy: &[u8],
+) -> [u8; RANDOMNESS_LENGTH] {
+    let blake2b_256 = || blake2b_simd::Params::new().hash_length(32).to_state();
+    // h := blake2b.New256()
+    let mut h = blake2b_256();
+    // binary.Write(h, binary.BigEndian, int64(pers))
+    h.update(&(pers as i64).to_be_bytes());
+    // VRFDigest := blake2b.Sum256(rbase)
+    let vrf_digest = blake2b_256().update(rbase).finalize();
+    // h.Write(VRFDigest[:])
+    h.update(vrf_digest.as_bytes());
+    // binary.Write(h, binary.BigEndian, round)
+    h.update(&round.to_be_bytes());
+    // h.Write(entropy)
+    h.update(entropy);
+    // return h.Sum(nil), nil
+    h.finalize().as_bytes().try_into().unwrap()
+}
+
+/// The randomness base the FVM receives for a VRF output: its blake2b-256 digest.
+fn rand_base(vrf_output: &[u8]) -> [u8; RANDOMNESS_LENGTH] {
+    blake2b_simd::Params::new()
+        .hash_length(32)
+        .hash(vrf_output)
+        .as_bytes()
+        .try_into()
+        .unwrap()
+}
+
+fn assert_golden(
+    base: &[u8; RANDOMNESS_LENGTH],
+    tag: DomainSeparationTag,
+    round: i64,
+    entropy: &[u8],
+    expected_hex: &str,
+) {
+    assert_eq!(
+        hex::encode(draw_randomness(base, tag, round, entropy)),
+        expected_hex
+    );
+}
+
+/// Checks randomness drawn from a VRF output, both against the expected value and against the
+/// transcription of Lotus' implementation.
+fn assert_lotus_golden(
+    vrf_output: &[u8],
+    tag: DomainSeparationTag,
+    round: i64,
+    entropy: &[u8],
+    expected_hex: &str,
+) {
+    assert_golden(&rand_base(vrf_output), tag, round, entropy, expected_hex);
+    assert_eq!(
+        hex::encode(lotus_draw_randomness_from_base(
+            vrf_output, tag, round, entropy
+        )),
+        expected_hex
+    );
+}
+
+#[test]
+fn tag_values() {
+    use DomainSeparationTag::*;
+    let tags = [
+        TicketProduction,
+        ElectionProofProduction,
+        WinningPoStChallengeSeed,
+        WindowedPoStChallengeSeed,
+        SealRandomness,
+        InteractiveSealChallengeSeed,
+        WindowedPoStDeadlineAssignment,
+        MarketDealCronSeed,
+        PoStChainCommit,
+        EvmPrevRandao,
+    ];
+    for (tag, value) in tags.into_iter().zip(1i64..) {
+        assert_eq!(tag as i64, value);
+        assert_eq!(DomainSeparationTag::from_i64(value), Some(tag));
+    }
+    assert_eq!(DomainSeparationTag::from_i64(0), None);
+    assert_eq!(DomainSeparationTag::from_i64(11), None);
+}
+
+/// The expected values were computed independently (with Python's `hashlib.blake2b`), following
+/// Lotus' `DrawRandomnessFromBase`.
+#[test]
+fn golden() {
+    assert_eq!(
+        hex::encode(rand_base(TICKET)),
+        "353d54e471c61d1b96485f4035d1c297c24506e9904282856d6bf56b09042f0f"
+    );
+
+    assert_lotus_golden(
+        TICKET,
+        DomainSeparationTag::ElectionProofProduction,
+        0,
+        &[],
+        "7f5c06551500b1128ad0e00c4512ffe756505efbb2d73411300513956b120530",
+    );
+    assert_lotus_golden(
+        TICKET,
+        DomainSeparationTag::WindowedPoStChallengeSeed,
+        -1,
+        b"entropy",
+        "44fd7e70658a46f9a8a5eb2e866460912d0386650e4ec65dd4eb00d089eb1506",
+    );
+    assert_lotus_golden(
+        &BEACON_ENTRY,
+        DomainSeparationTag::SealRandomness,
+        1000,
+        &[0x00, 0xe8, 0x07],
+        "cee52c2f9da1604f2c6c22bd35409f98f59b15d920202a3da6d0a26ede453f3f",
+    );
+    assert_lotus_golden(
+        &BEACON_ENTRY,
+        DomainSeparationTag::EvmPrevRandao,
+        1 << 62,
+        &[0; 100],
+        "736d9172fed49eb6070755b18eca137c7f5a207c0f95a9e16590f636ed987551",
+    );
+}
+
+#[test]
+fn entropy_changes_randomness() {
+    let tag = DomainSeparationTag::WindowedPoStDeadlineAssignment;
+    assert_golden(
+        &BASE_1,
+        tag,
+        1,
+        b"a",
+        "38ac8ccc026af905564246ac54db3a58b5e320be7e58c22eadbc578c8a4687ed",
+    );
+    assert_golden(
+        &BASE_1,
+        tag,
+        1,
+        b"b",
+        "5ce06ea364daa9c2e82026395f5fa63fe27671a689787beeac6f0c0affe97746",
+    );
+}
+
+/// `BASE_1`, encoded as a CBOR byte string by the previous (`Vec<u8>`) `Randomness`.
+const BASE_1_CBOR: &str = "5820000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+#[test]
+fn randomness_encoding_compat() {
+    let encoded = hex::decode(BASE_1_CBOR).unwrap();
+    let randomness: Randomness = from_slice(&encoded).unwrap();
+    assert_eq!(randomness, Randomness(BASE_1));
+    assert_eq!(to_vec(&randomness).unwrap(), encoded);
+
+    // A `WindowPoStVerifyInfo` with no proofs or sectors, for prover 1000.
+    let encoded = hex::decode(format!("84{BASE_1_CBOR}80801903e8")).unwrap();
+    let info: WindowPoStVerifyInfo = from_slice(&encoded).unwrap();
+    assert_eq!(
+        info,
+        WindowPoStVerifyInfo {
+            randomness: Randomness(BASE_1),
+            proofs: vec![],
+            challenged_sectors: vec![],
+            prover: 1000,
+        }
+    );
+    assert_eq!(to_vec(&info).unwrap(), encoded);
+}
+
+#[test]
+fn randomness_rejects_other_lengths() {
+    // The previous encoding accepted byte strings of any length.
+    for encoded in [
+        "40".to_owned(),
+        format!("581f{}", &BASE_1_CBOR[4..66]),
+        format!("5821{}ff", &BASE_1_CBOR[4..]),
+    ] {
+        let encoded = hex::decode(&encoded).unwrap();
+        from_slice::<Randomness>(&encoded).unwrap_err();
+    }
+}
+
+#[test]
+fn randomness_conversions() {
+    assert_eq!(Randomness::from(BASE_1), Randomness(BASE_1));
+    assert_eq!(<[u8; RANDOMNESS_LENGTH]>::from(Randomness(BASE_1)), BASE_1);
+    assert_eq!(Randomness::try_from(&BASE_1[..]), Ok(Randomness(BASE_1)));
+    assert_eq!(
+        Randomness::try_from(&BASE_1[..31]),
+        Err(InvalidRandomnessLength(31))
+    );
+    assert_eq!(
+        Randomness::try_from(&[0; 33][..]),
+        Err(InvalidRandomnessLength(33))
+    );
+}
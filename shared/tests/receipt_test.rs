@@ -0,0 +1,87 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Golden tests for the on-chain receipt encoding. Receipts are tuple-encoded as
+//! `[exit_code, return_data, gas_used, events_root]`, and the receipts AMT root depends on this
+//! exact layout. If one of these tests fails, the encoding changed in a consensus-breaking way.
+
+use cid::Cid;
+use fvm_ipld_encoding::{DAG_CBOR, RawBytes, from_slice, to_vec};
+use fvm_shared::error::ExitCode;
+use fvm_shared::receipt::Receipt;
+use multihash_codetable::{Code, MultihashDigest};
+
+fn assert_golden(receipt: Receipt, expected_hex: &str) {
+    let encoded = to_vec(&receipt).unwrap();
+    assert_eq!(hex::encode(&encoded), expected_hex);
+    let decoded: Receipt = from_slice(&hex::decode(expected_hex).unwrap()).unwrap();
+    assert_eq!(decoded, receipt);
+}
+
+#[test]
+fn success_receipt() {
+    assert_golden(
+        Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::new(vec![0x82, 0x01, 0x02]),
+            gas_used: 1_234_567,
+            events_root: None,
+        },
+        "8400438201021a0012d687f6",
+    );
+}
+
+#[test]
+fn failed_receipt_with_return_data() {
+    assert_golden(
+        Receipt {
+            exit_code: ExitCode::USR_ILLEGAL_ARGUMENT,
+            return_data: RawBytes::new(b"oops".to_vec()),
+            gas_used: 100_000,
+            events_root: None,
+        },
+        "8410446f6f70731a000186a0f6",
+    );
+}
+
+#[test]
+fn zero_gas_implicit_receipt() {
+    assert_golden(
+        Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::default(),
+            gas_used: 0,
+            events_root: None,
+        },
+        "84004000f6",
+    );
+}
+
+#[test]
+fn receipt_with_events_root() {
+    assert_golden(
+        Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::default(),
+            gas_used: 1000,
+            events_root: Some(Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&[]))),
+        },
+        "8400401903e8d82a5827000171a0e402200e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+    );
+}
+
+#[test]
+fn receipt_ignores_trailing_elements() {
+    // Receipts are decoded as leniently as before strict tuple decoding: extra elements are
+    // ignored.
+    let decoded: Receipt = from_slice(&hex::decode("8500438201021a0012d687f6f6").unwrap()).unwrap();
+    assert_eq!(
+        decoded,
+        Receipt {
+            exit_code: ExitCode::OK,
+            return_data: RawBytes::new(vec![0x82, 0x01, 0x02]),
+            gas_used: 1_234_567,
+            events_root: None,
+        }
+    );
+}
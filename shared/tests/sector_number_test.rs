@@ -0,0 +1,63 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Sector numbers and deal IDs are range-checked when constructed, but decode permissively so that
+//! existing state always decodes.
+
+use fvm_ipld_encoding::{from_slice, to_vec};
+use fvm_shared::deal::{DealID, InvalidDealID, MAX_DEAL_ID};
+use fvm_shared::sector::{InvalidSectorNumber, MAX_SECTOR_NUMBER, SectorNumber};
+
+#[test]
+fn sector_number_range() {
+    assert_eq!(SectorNumber::new(0).unwrap().value(), 0);
+    assert_eq!(
+        SectorNumber::new(MAX_SECTOR_NUMBER).unwrap(),
+        SectorNumber::MAX
+    );
+    assert_eq!(
+        SectorNumber::new(1 << 63),
+        Err(InvalidSectorNumber(1 << 63))
+    );
+    assert_eq!(
+        SectorNumber::try_from(u64::MAX),
+        Err(InvalidSectorNumber(u64::MAX))
+    );
+}
+
+#[test]
+fn deal_id_range() {
+    assert_eq!(DealID::new(0).unwrap().value(), 0);
+    assert_eq!(DealID::new(MAX_DEAL_ID).unwrap(), DealID::MAX);
+    assert_eq!(DealID::new(1 << 63), Err(InvalidDealID(1 << 63)));
+    assert_eq!(DealID::try_from(u64::MAX), Err(InvalidDealID(u64::MAX)));
+}
+
+#[test]
+fn encoded_as_integers() {
+    let number = SectorNumber::new(1234).unwrap();
+    assert_eq!(to_vec(&number).unwrap(), to_vec(&1234u64).unwrap());
+    assert_eq!(
+        from_slice::<SectorNumber>(&to_vec(&number).unwrap()).unwrap(),
+        number
+    );
+
+    let id = DealID::new(1234).unwrap();
+    assert_eq!(to_vec(&id).unwrap(), to_vec(&1234u64).unwrap());
+    assert_eq!(from_slice::<DealID>(&to_vec(&id).unwrap()).unwrap(), id);
+}
+
+#[test]
+fn out_of_range_values_decode() {
+    let encoded = to_vec(&(1u64 << 63)).unwrap();
+
+    let number: SectorNumber = from_slice(&encoded).unwrap();
+    assert_eq!(u64::from(number), 1 << 63);
+    assert_eq!(number.validate(), Err(InvalidSectorNumber(1 << 63)));
+    assert_eq!(to_vec(&number).unwrap(), encoded);
+
+    let id: DealID = from_slice(&encoded).unwrap();
+    assert_eq!(u64::from(id), 1 << 63);
+    assert_eq!(id.validate(), Err(InvalidDealID(1 << 63)));
+    assert_eq!(to_vec(&id).unwrap(), encoded);
+}
@@ -0,0 +1,27 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_shared::crypto::hash::SupportedHashes;
+use multihash_codetable::{Code, MultihashDigest};
+
+#[test]
+fn digest_sizes() {
+    for hash in [
+        SupportedHashes::Sha2_256,
+        SupportedHashes::Blake2b256,
+        SupportedHashes::Blake2b512,
+        SupportedHashes::Keccak256,
+        SupportedHashes::Ripemd160,
+    ] {
+        let code = hash as u64;
+        assert_eq!(SupportedHashes::try_from(code), Ok(hash));
+
+        let digest = Code::try_from(code).unwrap().digest(b"data");
+        assert_eq!(hash.digest_size(), u32::from(digest.size()), "{hash:?}");
+    }
+}
+
+#[test]
+fn unsupported_codes() {
+    assert_eq!(SupportedHashes::try_from(0), Err(0));
+    assert_eq!(SupportedHashes::try_from(0xb260), Err(0xb260));
+}
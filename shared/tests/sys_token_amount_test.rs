@@ -0,0 +1,49 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::sys;
+use quickcheck_macros::quickcheck;
+
+#[quickcheck]
+fn prop_sys_token_amount_roundtrip(atto: u128) -> bool {
+    let amount = TokenAmount::from_atto(atto);
+    let repr = sys::TokenAmount::try_from(&amount).unwrap();
+    u128::from(repr) == atto && TokenAmount::from(repr) == amount
+}
+
+#[quickcheck]
+fn prop_sys_token_amount_parts(hi: u64, lo: u64) -> bool {
+    let repr = sys::TokenAmount { hi, lo };
+    let amount = TokenAmount::from(repr);
+    *amount.atto() == (BigInt::from(hi) << 64) + lo
+        && sys::TokenAmount::try_from(amount).ok() == Some(repr)
+}
+
+#[test]
+fn sys_token_amount_bounds() {
+    for atto in [0, 1, u64::MAX as u128, u64::MAX as u128 + 1, u128::MAX] {
+        let repr = sys::TokenAmount::try_from(TokenAmount::from_atto(atto)).unwrap();
+        assert_eq!(u128::from(repr), atto);
+    }
+
+    let max = sys::TokenAmount::from(u128::MAX);
+    assert_eq!((max.hi, max.lo), (u64::MAX, u64::MAX));
+
+    // Amounts that don't fit in 128 bits are rejected rather than truncated.
+    let too_big = TokenAmount::from_atto(BigInt::from(u128::MAX) + 1);
+    assert!(sys::TokenAmount::try_from(&too_big).is_err());
+    assert!(sys::TokenAmount::try_from(too_big).is_err());
+}
+
+#[test]
+fn sys_token_amount_rejects_negative() {
+    for atto in [-1i64, i64::MIN] {
+        let amount = TokenAmount::from_atto(atto);
+        assert!(sys::TokenAmount::try_from(&amount).is_err());
+        assert!(sys::TokenAmount::try_from(amount).is_err());
+    }
+    let amount = -TokenAmount::from_atto(u128::MAX);
+    assert!(sys::TokenAmount::try_from(amount).is_err());
+}
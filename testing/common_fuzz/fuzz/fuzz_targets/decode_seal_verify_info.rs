@@ -0,0 +1,9 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![no_main]
+
+use common_fuzz::tuple::check_decode;
+use fvm_shared::sector::SealVerifyInfo;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| check_decode::<SealVerifyInfo>(data));
@@ -0,0 +1,45 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::fmt::Debug;
+
+use fvm_ipld_encoding as encoding;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Decodes `data` as a tuple-encoded `T` that opts into `#[serde_tuple(optional_trailing)]`.
+/// Anything that decodes must re-encode stably, and must decode to the same value once an element
+/// is appended to its array.
+pub fn check_decode<T>(data: &[u8])
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let Ok(value) = encoding::from_slice::<T>(data) else {
+        return;
+    };
+    let out = encoding::to_vec(&value).expect("decoded value must be possible to encode");
+    let value2 = encoding::from_slice::<T>(&out).expect("everything that encodes must decode");
+    assert_eq!(value, value2, "decoding must round-trip");
+    let out2 = encoding::to_vec(&value2).expect("decoded value must be possible to encode2");
+    assert_eq!(out, out2, "repeated encodings must be stable");
+
+    if let Some(long) = with_extra_element(&out) {
+        assert_eq!(
+            encoding::from_slice::<T>(&long).ok(),
+            Some(value),
+            "trailing elements must be ignored"
+        );
+    }
+}
+
+/// Appends a null to an encoded array, if its length fits in the header byte.
+fn with_extra_element(encoded: &[u8]) -> Option<Vec<u8>> {
+    let (&header, rest) = encoded.split_first()?;
+    if !(0x80..0x97).contains(&header) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(encoded.len() + 1);
+    out.push(header + 1);
+    out.extend_from_slice(rest);
+    out.push(0xf6);
+    Some(out)
+}
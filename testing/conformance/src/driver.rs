@@ -199,6 +199,17 @@ pub enum VariantResult {
     Failed { reason: anyhow::Error, id: String },
 }
 
+impl VariantResult {
+    /// The ID of the variant.
+    pub fn id(&self) -> &str {
+        match self {
+            VariantResult::Ok { id }
+            | VariantResult::Skipped { id, .. }
+            | VariantResult::Failed { id, .. } => id,
+        }
+    }
+}
+
 pub fn run_variant(
     bs: MemoryBlockstore,
     v: &MessageVector,
@@ -231,7 +242,7 @@ pub fn run_variant(
     // Preload the actors. We don't usually preload actors when testing, so we're going to do
     // this explicitly.
     engine
-        .acquire()
+        .acquire()?
         .preload_all(
             machine.blockstore(),
             machine.builtin_actors().builtin_actor_codes(),
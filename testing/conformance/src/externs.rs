@@ -1,8 +1,9 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
 use fvm::externs::{Chain, Consensus, Externs, Rand};
+use fvm_shared::ActorID;
 use fvm_shared::clock::ChainEpoch;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, MinerPower};
 
 use crate::rand::ReplayingRand;
 use crate::vector::Randomness;
@@ -43,6 +44,13 @@ impl Consensus for TestExterns {
     ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
         todo!()
     }
+
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower> {
+        // Test vectors don't record miner power.
+        Err(anyhow::anyhow!(
+            "miner power of f0{miner} at epoch {epoch} not available in test vectors"
+        ))
+    }
 }
 
 impl Chain for TestExterns {
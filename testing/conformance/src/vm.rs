@@ -7,11 +7,14 @@ use fvm::kernel::filecoin::{DefaultFilecoinKernel, FilecoinKernel};
 use fvm::call_manager::{CallManager, DefaultCallManager};
 use fvm::gas::price_list_by_network_version;
 use fvm::machine::limiter::MemoryLimiter;
-use fvm::machine::{DefaultMachine, Machine, MachineContext, Manifest, NetworkConfig};
+use fvm::machine::{
+    DefaultMachine, Machine, MachineContext, Manifest, NetworkConfig, RandomnessCache, WriteStats,
+};
 use fvm::state_tree::StateTree;
 use fvm::syscalls::Linker;
 use fvm_ipld_blockstore::MemoryBlockstore;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultReport};
+use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::sector::{
     AggregateSealVerifyProofAndInfos, RegisteredSealProof, ReplicaUpdateInfo, SealVerifyInfo,
@@ -77,7 +80,7 @@ impl TestMachine<Box<DefaultMachine<MemoryBlockstore, TestExterns>>> {
 
         let externs = TestExterns::new(&v.randomness);
 
-        let mut nc = NetworkConfig::new(network_version);
+        let mut nc = NetworkConfig::try_new(network_version)?;
         let mut mc = nc.for_epoch(epoch, (epoch * 30) as u64, state_root);
         // Allow overriding prices to some other network version.
         if let Some(nv) = price_network_version {
@@ -141,6 +144,22 @@ where
         self.machine.machine_id()
     }
 
+    fn randomness_cache(&self) -> Option<&RandomnessCache> {
+        self.machine.randomness_cache()
+    }
+
+    fn begin_message_writes(&mut self) {
+        self.machine.begin_message_writes()
+    }
+
+    fn end_message_writes(&mut self) -> Result<Option<WriteStats>> {
+        self.machine.end_message_writes()
+    }
+
+    fn write_stats(&self) -> Option<&WriteStats> {
+        self.machine.write_stats()
+    }
+
     fn new_limiter(&self) -> Self::Limiter {
         TestLimiter {
             inner: self.machine.new_limiter(),
@@ -241,10 +260,24 @@ impl FilecoinKernel for TestKernel {
         self.0.compute_unsealed_sector_cid(proof_type, pieces)
     }
 
+    fn compute_unsealed_sector_cids(
+        &self,
+        batch: &[(RegisteredSealProof, Vec<PieceInfo>)],
+    ) -> Result<Vec<std::result::Result<Cid, ErrorNumber>>> {
+        self.0.compute_unsealed_sector_cids(batch)
+    }
+
     fn verify_post(&self, verify_info: &fvm_shared::sector::WindowPoStVerifyInfo) -> Result<bool> {
         self.0.verify_post(verify_info)
     }
 
+    fn verify_post_batch(
+        &self,
+        verify_infos: &[fvm_shared::sector::WindowPoStVerifyInfo],
+    ) -> Result<Vec<bool>> {
+        self.0.verify_post_batch(verify_infos)
+    }
+
     // NOT forwarded
     fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
         Ok(vec![true; vis.len()])
@@ -264,6 +297,17 @@ impl FilecoinKernel for TestKernel {
         Ok(None)
     }
 
+    // NOT forwarded (like verify_consensus_fault, never finds a fault)
+    fn verify_consensus_fault_report(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFaultReport>> {
+        self.verify_consensus_fault(h1, h2, extra)?;
+        Ok(None)
+    }
+
     // NOT forwarded
     fn verify_aggregate_seals(&self, agg: &AggregateSealVerifyProofAndInfos) -> Result<bool> {
         let charge = self.price_list().on_verify_aggregate_seals(agg);
@@ -15,7 +15,7 @@ use futures::{StreamExt, stream};
 use fvm::engine::MultiEngine;
 use fvm_conformance_tests::driver::*;
 use fvm_conformance_tests::report;
-use fvm_conformance_tests::tracing::{TestTraceExporter, TestTraceExporterRef};
+use fvm_conformance_tests::tracing::{TestTraceExporter, TestTraceExporterRef, TestTraceFun};
 use fvm_conformance_tests::vector::{MessageVector, Selector};
 use fvm_conformance_tests::vm::{TestStatsGlobal, TestStatsRef};
 use itertools::Itertools;
@@ -249,6 +249,11 @@ fn run_vector(
                     let variant_id = variant.id.clone();
                     let name = format!("{} | {}", path.display(), variant_id);
 
+                    let trace = tracer
+                        .as_ref()
+                        .clone()
+                        .map(|t| t.export_fun(path.clone(), variant_id.clone()));
+                    let traced = trace.is_some();
                     run_variant(
                         bs.clone(),
                         &v,
@@ -256,11 +261,32 @@ fn run_vector(
                         &ENGINES,
                         true,
                         stats.as_ref().clone(),
-                        tracer
-                            .as_ref()
-                            .clone()
-                            .map(|t| t.export_fun(path.clone(), variant_id.clone())),
+                        trace,
                     )
+                    .and_then(|res| match res {
+                        // Tracing must not affect execution, so we check the postconditions again
+                        // with tracing enabled (discarding the traces).
+                        VariantResult::Ok { .. } if !traced => {
+                            let discard: TestTraceFun = Box::new(|_| Ok(()));
+                            run_variant(
+                                bs.clone(),
+                                &v,
+                                variant,
+                                &ENGINES,
+                                true,
+                                None,
+                                Some(discard),
+                            )
+                            .map(|res| match res {
+                                VariantResult::Failed { id, reason } => VariantResult::Failed {
+                                    id,
+                                    reason: reason.context("with tracing enabled"),
+                                },
+                                res => res,
+                            })
+                        }
+                        res => Ok(res),
+                    })
                     .with_context(|| format!("failed to run {name}"))
                     .unwrap_or_else(|e| VariantResult::Failed {
                         id: variant_id,
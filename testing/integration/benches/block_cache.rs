@@ -0,0 +1,144 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::hint::black_box;
+use std::rc::Rc;
+
+use cid::Cid;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use fvm::DefaultKernel;
+use fvm::call_manager::DefaultCallManager;
+use fvm::engine::EnginePool;
+use fvm::executor::{ApplyKind, DefaultExecutor, Executor};
+use fvm::machine::{
+    BlockCacheStats, CachingBlockstore, DEFAULT_BLOCK_CACHE_SIZE, DefaultMachine, NetworkConfig,
+};
+use fvm_integration_tests::bundle;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_blockstore::tracking::TrackingBlockstore;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+/// The number of transfers applied per run.
+const TRANSFERS: usize = 1000;
+/// The number of transfers per machine (i.e., per epoch).
+const TRANSFERS_PER_EPOCH: usize = 10;
+const ACCOUNTS: usize = 10;
+const NETWORK_VERSION: NetworkVersion = NetworkVersion::V21;
+
+type Store = Rc<CachingBlockstore<TrackingBlockstore<MemoryBlockstore>>>;
+type BenchExecutor =
+    DefaultExecutor<DefaultKernel<DefaultCallManager<DefaultMachine<Store, DummyExterns>>>>;
+
+/// A genesis state with some accounts, in a blockstore behind a block cache of the given size.
+struct Chain {
+    store: Store,
+    network: NetworkConfig,
+    engine: EnginePool,
+    root: Cid,
+    accounts: [Account; ACCOUNTS],
+}
+
+fn setup(cache_size: usize) -> Chain {
+    let store = Rc::new(CachingBlockstore::new(
+        TrackingBlockstore::new(MemoryBlockstore::default()),
+        cache_size,
+    ));
+    let bundle_cid = bundle::import_bundle(&store, actors::BUNDLE_CAR).unwrap();
+    let mut tester = Tester::<Store, DummyExterns>::new(
+        NETWORK_VERSION,
+        StateTreeVersion::V5,
+        bundle_cid,
+        store.clone(),
+    )
+    .unwrap();
+    let accounts = tester.create_accounts().unwrap();
+    let root = tester.state_tree.as_mut().unwrap().flush().unwrap();
+
+    let mut network = NetworkConfig::new(NETWORK_VERSION);
+    network.override_actors(bundle_cid);
+    let engine = EnginePool::new((&network).into()).unwrap();
+
+    // Start cold, as if the genesis state had been written by another process.
+    store.clear();
+    Chain {
+        store,
+        network,
+        engine,
+        root,
+        accounts,
+    }
+}
+
+/// Applies [`TRANSFERS`] transfers between the accounts, creating a new machine every
+/// [`TRANSFERS_PER_EPOCH`] transfers. Returns the final state root.
+fn apply_transfers(chain: &Chain) -> Cid {
+    let mut root = chain.root;
+    let mut sequences = [0; ACCOUNTS];
+    for epoch in 0..TRANSFERS / TRANSFERS_PER_EPOCH {
+        let mut mc = chain
+            .network
+            .for_epoch(epoch as i64, epoch as u64 * 30, root);
+        mc.set_base_fee(TokenAmount::from_atto(100));
+        let machine = DefaultMachine::new(&mc, chain.store.clone(), DummyExterns).unwrap();
+        let mut executor = BenchExecutor::new(chain.engine.clone(), machine).unwrap();
+        for i in 0..TRANSFERS_PER_EPOCH {
+            let from = (epoch * TRANSFERS_PER_EPOCH + i) % ACCOUNTS;
+            let to = (from + 1) % ACCOUNTS;
+            let message = Message {
+                from: chain.accounts[from].1,
+                to: chain.accounts[to].1,
+                sequence: sequences[from],
+                value: TokenAmount::from_atto(1),
+                gas_limit: 10_000_000,
+                ..Message::default()
+            };
+            sequences[from] += 1;
+            let ret = executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap();
+            assert!(
+                ret.msg_receipt.exit_code.is_success(),
+                "{:?}",
+                ret.failure_info
+            );
+        }
+        root = executor.flush().unwrap();
+    }
+    root
+}
+
+/// Measures applying transfers over many epochs with and without a block cache in front of the
+/// "node" blockstore, after reporting the number of reads reaching the node blockstore.
+fn bench_block_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("1000 transfers");
+    group.sample_size(10);
+    for (name, cache_size) in [("cached", DEFAULT_BLOCK_CACHE_SIZE), ("uncached", 0)] {
+        let chain = setup(cache_size);
+        let reads_before = chain.store.inner().stats.borrow().r;
+        let stats_before = chain.store.stats();
+        apply_transfers(&chain);
+        let BlockCacheStats { hits, misses } = chain.store.stats();
+        println!(
+            "{name}: {} reads from the underlying blockstore ({} cache hits, {} misses)",
+            chain.store.inner().stats.borrow().r - reads_before,
+            hits - stats_before.hits,
+            misses - stats_before.misses,
+        );
+
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || setup(cache_size),
+                |chain| black_box(apply_transfers(&chain)),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_block_cache);
+criterion_main!(benches);
@@ -0,0 +1,83 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use cid::Cid;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use fvm::machine::BufferedBlockstore;
+use fvm_ipld_blockstore::{Blockstore, Buffered};
+use fvm_ipld_encoding::CborStore;
+use multihash_codetable::Code;
+
+/// The number of leaves of the flushed tree.
+const LEAVES: u32 = 1024;
+/// The number of links per inner node of the flushed tree.
+const FANOUT: usize = 8;
+/// The latency of a single write to the base store.
+const WRITE_LATENCY: Duration = Duration::from_micros(100);
+
+/// A thread-safe in-memory blockstore that waits before each write, as a disk or remote store
+/// would.
+#[derive(Default)]
+struct SlowBlockstore {
+    blocks: Mutex<HashMap<Cid, Vec<u8>>>,
+}
+
+impl Blockstore for SlowBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.blocks.lock().unwrap().get(k).cloned())
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        std::thread::sleep(WRITE_LATENCY);
+        self.blocks.lock().unwrap().insert(*k, block.to_vec());
+        Ok(())
+    }
+}
+
+/// Buffers a tree of blocks over a slow base store, returning the buffered store and the root.
+fn setup() -> (BufferedBlockstore<SlowBlockstore>, Cid) {
+    let store = BufferedBlockstore::new(SlowBlockstore::default());
+    let mut level: Vec<Cid> = (0..LEAVES)
+        .map(|i| store.put_cbor(&i, Code::Blake2b256).unwrap())
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(FANOUT)
+            .map(|links| store.put_cbor(&links, Code::Blake2b256).unwrap())
+            .collect();
+    }
+    let root = level[0];
+    (store, root)
+}
+
+/// Measures flushing the tree serially, and from an increasing number of workers.
+fn bench_buffered_flush(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buffered flush");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.iter_batched(
+            setup,
+            |(store, root)| store.flush(&root).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    let batch_size = NonZeroUsize::new(16).unwrap();
+    for workers in [1, 4, 16] {
+        let workers = NonZeroUsize::new(workers).unwrap();
+        group.bench_function(format!("{workers} workers"), |b| {
+            b.iter_batched(
+                setup,
+                |(store, root)| store.flush_parallel(&root, workers, batch_size).unwrap(),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_buffered_flush);
+criterion_main!(benches);
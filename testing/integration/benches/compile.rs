@@ -21,13 +21,14 @@ fn bench_compile(c: &mut Criterion) {
         let (manifest_version, manifest_cid): (u32, Cid) =
             blockstore.get_cbor(&bundle_cid).unwrap().unwrap();
         let manifest = Manifest::load(&blockstore, &manifest_cid, manifest_version).unwrap();
-        let nc = NetworkConfig::new(NetworkVersion::V21);
+        let nc = NetworkConfig::mainnet(NetworkVersion::V21);
         b.iter_batched(
             || EnginePool::new((&nc).into()).unwrap(),
             |engine| {
                 black_box(
                     engine
                         .acquire()
+                        .unwrap()
                         .preload_all(&blockstore, manifest.builtin_actor_codes())
                         .unwrap(),
                 );
@@ -0,0 +1,93 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use fvm::engine::DEFAULT_MAX_POOLED_INSTANCES;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::bundle;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor, BasicTester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::HELLO_WORLD_ACTOR_BINARY;
+
+const ACTOR_ID: u64 = 10000;
+
+/// Sets up an executor with an engine pooling at most `max_pooled_instances` instances (0 disables
+/// pooling).
+fn setup(max_pooled_instances: u32) -> (Account, BasicExecutor) {
+    let blockstore = MemoryBlockstore::default();
+    let bundle_cid = bundle::import_bundle(&blockstore, actors::BUNDLE_CAR).unwrap();
+    let mut tester = BasicTester::new(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        bundle_cid,
+        blockstore,
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            HELLO_WORLD_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::default(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_engine_config(
+            DummyExterns,
+            |_| (),
+            |_| (),
+            |ec| ec.max_pooled_instances = max_pooled_instances,
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+/// Measures the throughput of small messages, dominated by instantiating the (immediately
+/// aborting) actor, with and without instance pooling.
+fn bench_instance_pooling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small message");
+    for (name, max_pooled_instances) in [("pooled", DEFAULT_MAX_POOLED_INSTANCES), ("on-demand", 0)]
+    {
+        group.bench_function(name, |b| {
+            let (sender, mut executor) = setup(max_pooled_instances);
+            let mut sequence = 0;
+            b.iter(|| {
+                let message = Message {
+                    from: sender.1,
+                    to: Address::new_id(ACTOR_ID),
+                    sequence,
+                    gas_limit: 1_000_000_000,
+                    method_num: 1,
+                    ..Message::default()
+                };
+                sequence += 1;
+                black_box(
+                    executor
+                        .execute_message(message, ApplyKind::Explicit, 100)
+                        .unwrap(),
+                )
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::from_secs(10));
+    targets = bench_instance_pooling
+}
+
+criterion_main!(benches);
@@ -11,7 +11,8 @@ use fvm::kernel::{
 };
 use fvm::syscalls::Linker;
 use fvm_shared::clock::ChainEpoch;
-use fvm_shared::consensus::ConsensusFault;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultReport};
+use fvm_shared::error::ErrorNumber;
 use fvm_shared::piece::PieceInfo;
 use fvm_shared::randomness::RANDOMNESS_LENGTH;
 use fvm_shared::sector::{
@@ -127,27 +128,45 @@ where
         self.0.compute_unsealed_sector_cid(proof_type, pieces)
     }
 
+    fn compute_unsealed_sector_cids(
+        &self,
+        batch: &[(RegisteredSealProof, Vec<PieceInfo>)],
+    ) -> Result<Vec<std::result::Result<Cid, ErrorNumber>>> {
+        self.0.compute_unsealed_sector_cids(batch)
+    }
+
     fn verify_post(&self, verify_info: &fvm_shared::sector::WindowPoStVerifyInfo) -> Result<bool> {
         self.0.verify_post(verify_info)
     }
 
+    fn verify_post_batch(
+        &self,
+        verify_infos: &[fvm_shared::sector::WindowPoStVerifyInfo],
+    ) -> Result<Vec<bool>> {
+        self.0.verify_post_batch(verify_infos)
+    }
+
     // NOT forwarded
     fn batch_verify_seals(&self, vis: &[SealVerifyInfo]) -> Result<Vec<bool>> {
         Ok(vec![true; vis.len()])
     }
 
-    // NOT forwarded
     fn verify_consensus_fault(
         &self,
         h1: &[u8],
         h2: &[u8],
         extra: &[u8],
     ) -> Result<Option<ConsensusFault>> {
-        let charge = self
-            .price_list()
-            .on_verify_consensus_fault(h1.len(), h2.len(), extra.len());
-        let _ = self.charge_gas(&charge.name, charge.total())?;
-        Ok(None)
+        self.0.verify_consensus_fault(h1, h2, extra)
+    }
+
+    fn verify_consensus_fault_report(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> Result<Option<ConsensusFaultReport>> {
+        self.0.verify_consensus_fault_report(h1, h2, extra)
     }
 
     // NOT forwarded
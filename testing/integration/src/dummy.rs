@@ -3,8 +3,13 @@
 use cid::Cid;
 use cid::multihash::Multihash;
 use fvm::externs::{Chain, Consensus, Externs, Rand};
-use fvm_ipld_encoding::DAG_CBOR;
-use fvm_shared::IDENTITY_HASH;
+use fvm_ipld_encoding::{DAG_CBOR, from_slice};
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, FaultBlockHeader, MinerPower, fault_applies};
+use fvm_shared::sector::StoragePower;
+use fvm_shared::{ActorID, IDENTITY_HASH};
+use multihash_codetable::{Code, MultihashDigest};
 use rand::distributions::Alphanumeric;
 use rand::{Rng, thread_rng};
 pub struct DummyExterns;
@@ -39,14 +44,54 @@ impl Rand for DummyExterns {
     }
 }
 
+/// Decodes a block header in the simplified format understood by [`DummyExterns`]: a DAG-CBOR
+/// tuple of the miner's address, the epoch, and the parent CIDs.
+fn decode_header(header: &[u8]) -> Option<FaultBlockHeader> {
+    let (miner, epoch, parents): (Address, ChainEpoch, Vec<Cid>) = from_slice(header).ok()?;
+    Some(FaultBlockHeader {
+        cid: Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(header)),
+        miner,
+        epoch,
+        parents,
+    })
+}
+
+/// Detects consensus faults between block headers in the simplified format described in
+/// [`decode_header`], skipping signature and chain checks. Headers in any other format are never
+/// at fault.
 impl Consensus for DummyExterns {
     fn verify_consensus_fault(
         &self,
-        _h1: &[u8],
-        _h2: &[u8],
-        _extra: &[u8],
-    ) -> anyhow::Result<(Option<fvm_shared::consensus::ConsensusFault>, i64)> {
-        Ok((None, 0))
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        let (Some(h1), Some(h2)) = (decode_header(h1), decode_header(h2)) else {
+            return Ok((None, 0));
+        };
+        let extra = if extra.is_empty() {
+            None
+        } else {
+            decode_header(extra)
+        };
+        let fault = fault_applies(&h1, &h2, extra.as_ref()).and_then(|fault_type| {
+            Some(ConsensusFault::new(
+                h1.miner.id().ok()?,
+                h1.epoch.max(h2.epoch),
+                fault_type,
+            ))
+        });
+        Ok((fault, 0))
+    }
+
+    /// Returns a power derived from the miner and epoch: the raw byte power is `miner * 2^64 +
+    /// epoch` (so that it doesn't fit in 64 bits), and the quality-adjusted power is ten times that.
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower> {
+        let raw_byte_power = StoragePower::from(((miner as u128) << 64) | epoch as u64 as u128);
+        Ok(MinerPower {
+            quality_adj_power: &raw_byte_power * 10,
+            raw_byte_power,
+        })
     }
 }
 
@@ -0,0 +1,82 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bls_signatures::Serialize as _;
+use fvm_shared::address::Address;
+use k256::SecretKey;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use multihash_codetable::{Code, MultihashDigest};
+
+/// A seeded generator of secp256k1 and BLS keys for reproducible test fixtures.
+///
+/// Keys are derived from `(seed, index)` by hashing alone, so the same seed and index always yield
+/// the same key (and address), independent of the platform, the order in which keys are generated,
+/// and the version of any RNG crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterministicKeyGen {
+    seed: u64,
+}
+
+impl DeterministicKeyGen {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the generator's seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Derives 32 bytes of key material as
+    /// `blake2b-256("fvm-test-keygen/" || domain || "/" || seed || index || attempt)`, with all
+    /// integers encoded as big-endian.
+    fn derive(&self, domain: &[u8], index: u64, attempt: u32) -> [u8; 32] {
+        let mut input = Vec::with_capacity(64);
+        input.extend_from_slice(b"fvm-test-keygen/");
+        input.extend_from_slice(domain);
+        input.push(b'/');
+        input.extend_from_slice(&self.seed.to_be_bytes());
+        input.extend_from_slice(&index.to_be_bytes());
+        input.extend_from_slice(&attempt.to_be_bytes());
+        Code::Blake2b256
+            .digest(&input)
+            .digest()
+            .try_into()
+            .expect("blake2b-256 digests are 32 bytes")
+    }
+
+    /// Returns the secp256k1 private key at the given index.
+    pub fn secp256k1_key(&self, index: u64) -> SecretKey {
+        // Re-derive in the (astronomically unlikely) case that the bytes aren't a valid scalar.
+        (0..)
+            .find_map(|attempt| {
+                SecretKey::from_slice(&self.derive(b"secp256k1", index, attempt)).ok()
+            })
+            .expect("failed to derive a secp256k1 key")
+    }
+
+    /// Returns the f1 address of the secp256k1 key at the given index.
+    pub fn secp256k1_address(&self, index: u64) -> Address {
+        secp256k1_address(&self.secp256k1_key(index))
+    }
+
+    /// Returns the BLS private key at the given index.
+    pub fn bls_key(&self, index: u64) -> bls_signatures::PrivateKey {
+        bls_signatures::PrivateKey::new(self.derive(b"bls", index, 0))
+    }
+
+    /// Returns the f3 address of the BLS key at the given index.
+    pub fn bls_address(&self, index: u64) -> Address {
+        bls_address(&self.bls_key(index))
+    }
+}
+
+/// Returns the f1 address of a secp256k1 private key.
+pub fn secp256k1_address(key: &SecretKey) -> Address {
+    Address::new_secp256k1(key.public_key().to_encoded_point(false).as_bytes())
+        .expect("uncompressed secp256k1 public keys are always valid")
+}
+
+/// Returns the f3 address of a BLS private key.
+pub fn bls_address(key: &bls_signatures::PrivateKey) -> Address {
+    Address::new_bls(&key.public_key().as_bytes()).expect("BLS public keys are always valid")
+}
@@ -3,21 +3,21 @@
 use anyhow::{Context, Result, anyhow};
 use cid::Cid;
 use fvm::call_manager::DefaultCallManager;
-use fvm::engine::EnginePool;
-use fvm::executor::DefaultExecutor;
+use fvm::engine::{EngineConfig, EnginePool};
+use fvm::executor::{ApplyKind, ApplyRet, DefaultExecutor, Executor};
 use fvm::externs::Externs;
 use fvm::machine::{DefaultMachine, Machine, MachineContext, NetworkConfig};
 use fvm::state_tree::{ActorState, StateTree};
 use fvm::{init_actor, system_actor};
 use fvm_ipld_blockstore::{Block, Blockstore, MemoryBlockstore};
-use fvm_ipld_encoding::{CborStore, ser};
+use fvm_ipld_encoding::{CborStore, RawBytes, ser, to_vec};
 use fvm_shared::address::{Address, Protocol};
 use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
 use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
-use fvm_shared::{ActorID, IPLD_RAW};
+use fvm_shared::{ActorID, IPLD_RAW, MethodNum};
 use k256::SecretKey;
-use k256::elliptic_curve::sec1::ToEncodedPoint;
 use lazy_static::lazy_static;
 use multihash_codetable::Code;
 
@@ -27,9 +27,16 @@ use crate::builtin::{
 use crate::custom_kernel::DefaultCustomKernel;
 use crate::dummy::DummyExterns;
 use crate::error::Error::{FailedToFlushTree, NoManifestInformation};
+use crate::keygen::{self, DeterministicKeyGen};
 
 const DEFAULT_BASE_FEE: u64 = 100;
 
+/// The gas limit of the messages executed by [`Tester::execute_message`].
+pub const DEFAULT_GAS_LIMIT: u64 = 1_000_000_000;
+
+/// Seed used to generate BLS accounts when the tester wasn't configured with a seed.
+const DEFAULT_KEYGEN_SEED: u64 = 8;
+
 lazy_static! {
     pub static ref INITIAL_ACCOUNT_BALANCE: TokenAmount = TokenAmount::from_atto(10000);
 }
@@ -73,6 +80,11 @@ pub struct Tester<B: Blockstore + 'static, E: Externs + 'static> {
 
     // ready if the machine has been instantiated
     pub ready: bool,
+
+    // Deterministic key generator used to create accounts, if seeded
+    keygen: Option<DeterministicKeyGen>,
+    // Index of the next key to generate with the key generator
+    next_key_index: u64,
 }
 
 impl<B, E> Tester<B, E>
@@ -117,11 +129,33 @@ where
             placeholder_code_cid,
             options: None,
             ready: false,
+            keygen: None,
+            next_key_index: 0,
         })
     }
 
+    /// Seeds the tester's key generator. Accounts created afterwards are derived from the seed and
+    /// their creation order alone, so a test seeded with the same value produces identical keys,
+    /// addresses, genesis state, and message CIDs on every run.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.keygen = Some(DeterministicKeyGen::new(seed));
+        self.next_key_index = 0;
+        self
+    }
+
+    /// Returns the next key index, advancing the key generator.
+    fn next_key_index(&mut self) -> u64 {
+        let index = self.next_key_index;
+        self.next_key_index += 1;
+        index
+    }
+
     /// Creates new accounts in the testing context
     /// Inserts the specified number of accounts in the state tree, all with 1000 FIL，returning their IDs and Addresses.
+    ///
+    /// If the tester was seeded with [`Tester::with_seed`], each call creates new accounts with
+    /// keys from the tester's key generator. Otherwise, every call creates the same sequence of
+    /// keys.
     pub fn create_accounts<const N: usize>(&mut self) -> Result<[Account; N]> {
         use rand::SeedableRng;
 
@@ -129,12 +163,32 @@ where
 
         let mut ret: [Account; N] = [(0, Address::default()); N];
         for account in ret.iter_mut().take(N) {
-            let priv_key = SecretKey::random(rng);
+            let priv_key = match self.keygen {
+                Some(keygen) => keygen.secp256k1_key(self.next_key_index()),
+                None => SecretKey::random(rng),
+            };
             *account = self.make_secp256k1_account(priv_key, INITIAL_ACCOUNT_BALANCE.clone())?;
         }
         Ok(ret)
     }
 
+    /// Creates new BLS accounts in the testing context, with keys from the tester's key generator
+    /// (or a default seed if the tester wasn't seeded).
+    pub fn create_bls_accounts<const N: usize>(&mut self) -> Result<[Account; N]> {
+        // Don't store the default key generator: that would make later secp256k1 accounts
+        // deterministic too.
+        let keygen = self
+            .keygen
+            .unwrap_or_else(|| DeterministicKeyGen::new(DEFAULT_KEYGEN_SEED));
+
+        let mut ret: [Account; N] = [(0, Address::default()); N];
+        for account in ret.iter_mut() {
+            let priv_key = keygen.bls_key(self.next_key_index());
+            *account = self.make_bls_account(&priv_key, INITIAL_ACCOUNT_BALANCE.clone())?;
+        }
+        Ok(ret)
+    }
+
     pub fn create_account(&mut self) -> Result<Account> {
         let accounts: [Account; 1] = self.create_accounts()?;
         Ok(accounts[0])
@@ -266,6 +320,23 @@ where
     where
         F: FnOnce(&mut NetworkConfig),
         G: FnOnce(&mut MachineContext),
+    {
+        self.instantiate_machine_with_engine_config(externs, configure_nc, configure_mc, |_| ())
+    }
+
+    /// Like [`Tester::instantiate_machine_with_config`], but the `configure_ec` function also
+    /// allows the caller to adjust the `EngineConfig` (derived from the `NetworkConfig`).
+    pub fn instantiate_machine_with_engine_config<F, G, H>(
+        &mut self,
+        externs: E,
+        configure_nc: F,
+        configure_mc: G,
+        configure_ec: H,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut NetworkConfig),
+        G: FnOnce(&mut MachineContext),
+        H: FnOnce(&mut EngineConfig),
     {
         // Take the state tree and leave None behind.
         let mut state_tree = self.state_tree.take().unwrap();
@@ -279,9 +350,8 @@ where
         // Consume the state tree and take the blockstore.
         let blockstore = state_tree.into_store();
 
-        let mut nc = NetworkConfig::new(self.nv);
+        let mut nc = NetworkConfig::devnet(self.nv);
         nc.override_actors(self.builtin_actors);
-        nc.enable_actor_debugging();
 
         // Custom configuration.
         configure_nc(&mut nc);
@@ -293,8 +363,12 @@ where
         // Custom configuration.
         configure_mc(&mut mc);
 
-        let engine = EnginePool::new((&mc.network.clone()).into())?;
-        engine.acquire().preload_all(&blockstore, &self.code_cids)?;
+        let mut ec: EngineConfig = (&mc.network).into();
+        configure_ec(&mut ec);
+        let engine = EnginePool::new(ec)?;
+        engine
+            .acquire()?
+            .preload_all(&blockstore, &self.code_cids)?;
 
         let machine = DefaultMachine::new(&mc, blockstore, externs)?;
 
@@ -317,15 +391,63 @@ where
         }
     }
 
+    /// Executes an explicit message from `from` to `to`, with the sender's next sequence number and
+    /// a gas limit of [`DEFAULT_GAS_LIMIT`]. Fails if the machine hasn't been instantiated, or if
+    /// the sender doesn't exist.
+    pub fn execute_message(
+        &mut self,
+        from: Address,
+        to: Address,
+        method_num: MethodNum,
+        params: RawBytes,
+        value: TokenAmount,
+    ) -> Result<ApplyRet> {
+        let executor = self
+            .executor
+            .as_mut()
+            .context("the machine hasn't been instantiated")?;
+        let sender = executor
+            .state_tree()
+            .get_actor_by_address(&from)?
+            .with_context(|| format!("sender {from} not found"))?;
+        let message = Message {
+            from,
+            to,
+            sequence: sender.sequence,
+            value,
+            method_num,
+            params,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            ..Message::default()
+        };
+        let raw_length = to_vec(&message)?.len();
+        executor.execute_message(message, ApplyKind::Explicit, raw_length)
+    }
+
     /// Put account with specified private key and balance
     pub fn make_secp256k1_account(
         &mut self,
         priv_key: SecretKey,
         init_balance: TokenAmount,
     ) -> Result<Account> {
-        let pub_key = priv_key.public_key();
-        let pub_key_addr = Address::new_secp256k1(pub_key.to_encoded_point(false).as_bytes())?;
+        self.make_account(keygen::secp256k1_address(&priv_key), init_balance)
+    }
+
+    /// Put BLS account with specified private key and balance
+    pub fn make_bls_account(
+        &mut self,
+        priv_key: &bls_signatures::PrivateKey,
+        init_balance: TokenAmount,
+    ) -> Result<Account> {
+        self.make_account(keygen::bls_address(priv_key), init_balance)
+    }
 
+    /// Put account with specified public key address and balance
+    fn make_account(
+        &mut self,
+        pub_key_addr: Address,
+        init_balance: TokenAmount,
+    ) -> Result<Account> {
         let state_tree = self
             .state_tree
             .as_mut()
@@ -84,10 +84,7 @@ pub enum EVMMethod {
     GetBytecodeHash = 4,
     GetStorageAt = 5,
     InvokeContractDelegate = 6,
-    // it is very unfortunate but the hasher creates a circular dependency, so we use the raw
-    // number.
-    //InvokeContract = frc42_dispatch::method_hash!("InvokeEVM"),
-    InvokeContract = 3844450837,
+    InvokeContract = fvm_shared::method_hash!("InvokeEVM"),
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
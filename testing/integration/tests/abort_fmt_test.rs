@@ -0,0 +1,92 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::{ABORT_FMT_ACTOR_BINARY, ABORT_FORMAT_ACTOR_BINARY};
+use num_traits::Zero;
+
+mod bundles;
+
+const EXIT_CODE: ExitCode = ExitCode::new(0x42);
+
+/// Invokes the given actor's methods in order, returning the abort messages.
+fn abort_messages(wasm_bin: &[u8], methods: &[u64]) -> Vec<String> {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    methods
+        .iter()
+        .enumerate()
+        .map(|(seq, &method_num)| {
+            let message = Message {
+                from: sender.1,
+                to: actor_address,
+                gas_limit: 1_000_000_000,
+                method_num,
+                sequence: seq as u64,
+                ..Message::default()
+            };
+            let res = executor
+                .execute_message(message, ApplyKind::Explicit, 100)
+                .unwrap();
+            assert_eq!(res.msg_receipt.exit_code, EXIT_CODE);
+            res.failure_info.expect("expected failure info").to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn abort_fmt_formats_message() {
+    let messages = abort_messages(ABORT_FMT_ACTOR_BINARY, &[1, 2, 3]);
+    assert!(
+        messages[0].contains("bad sector 42 at epoch -1"),
+        "{}",
+        messages[0]
+    );
+    // `%u` reinterprets negative arguments, unknown and unmatched placeholders are kept as-is.
+    assert!(
+        messages[1].contains("100% -2 18446744073709551614 %x %d"),
+        "{}",
+        messages[1]
+    );
+    assert!(
+        messages[2].contains("unrecognized method 3"),
+        "{}",
+        messages[2]
+    );
+
+    // Same messages as with `format!`.
+    let formatted = abort_messages(ABORT_FORMAT_ACTOR_BINARY, &[1, 3]);
+    assert_eq!(formatted, [messages[0].clone(), messages[2].clone()]);
+}
+
+#[test]
+fn abort_fmt_is_smaller_than_format() {
+    assert!(
+        ABORT_FMT_ACTOR_BINARY.len() < ABORT_FORMAT_ACTOR_BINARY.len(),
+        "abort_fmt: {} bytes, format!: {} bytes",
+        ABORT_FMT_ACTOR_BINARY.len(),
+        ABORT_FORMAT_ACTOR_BINARY.len()
+    );
+}
@@ -0,0 +1,121 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(feature = "nv29-dev")]
+
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::gas::{Gas, price_list_by_network_version};
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use num_traits::Zero;
+
+mod bundles;
+
+// Aborts are only charged from nv29 onwards.
+const NV: NetworkVersion = NetworkVersion::V29;
+const EXIT_CODE: ExitCode = ExitCode::new(42);
+const MESSAGE: &str = "something went wrong";
+
+/// An actor that aborts with `EXIT_CODE` and `MESSAGE`.
+fn abort_wat() -> String {
+    format!(
+        r#"(module
+             (import "vm" "exit" (func $exit (param i32 i32 i32 i32) (result i32)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "{MESSAGE}")
+             (func (export "invoke") (param $x i32) (result i32)
+               (drop (call $exit (i32.const {code}) (i32.const 0) (i32.const 0) (i32.const {len})))
+               (i32.const 0)))"#,
+        code = EXIT_CODE.value(),
+        len = MESSAGE.len(),
+    )
+}
+
+/// Invokes the aborting actor (in a fresh machine) with the given gas limit.
+fn abort_with_gas_limit(gas_limit: u64) -> ApplyRet {
+    let mut tester = new_tester(NV, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(abort_wat()).unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit,
+        method_num: 1,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn abort_charges(res: &ApplyRet) -> Vec<Gas> {
+    res.exec_trace
+        .iter()
+        .filter_map(|event| match event {
+            ExecutionEvent::GasCharge(charge) if charge.name == "OnAbort" => Some(charge.total()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn failure_message(res: &ApplyRet) -> String {
+    res.failure_info
+        .as_ref()
+        .expect("expected the message to fail")
+        .to_string()
+}
+
+#[test]
+fn abort_charges_message_read() {
+    let res = abort_with_gas_limit(10_000_000);
+    assert_eq!(res.msg_receipt.exit_code, EXIT_CODE);
+    assert!(failure_message(&res).contains(MESSAGE));
+
+    // The message read is reported as its own charge.
+    let expected = price_list_by_network_version(NV).on_abort(MESSAGE.len());
+    assert!(!expected.total().is_zero());
+    assert_eq!(abort_charges(&res), [expected.total()]);
+}
+
+#[test]
+fn abort_with_exact_gas() {
+    let gas_used = abort_with_gas_limit(10_000_000).msg_receipt.gas_used;
+
+    let res = abort_with_gas_limit(gas_used);
+    assert_eq!(res.msg_receipt.exit_code, EXIT_CODE);
+    assert_eq!(res.msg_receipt.gas_used, gas_used);
+    assert!(failure_message(&res).contains(MESSAGE));
+}
+
+#[test]
+fn abort_one_unit_short() {
+    let gas_used = abort_with_gas_limit(10_000_000).msg_receipt.gas_used;
+
+    // We can't afford the message read, so the message is dropped. But the exit code is preserved.
+    let res = abort_with_gas_limit(gas_used - 1);
+    assert_eq!(res.msg_receipt.exit_code, EXIT_CODE);
+    assert_eq!(res.msg_receipt.gas_used, gas_used - 1);
+    let failure = failure_message(&res);
+    assert!(!failure.contains(MESSAGE), "{failure}");
+    assert!(failure.contains("message dropped: out of gas"), "{failure}");
+
+    // The failed charge still shows up in the trace.
+    assert_eq!(abort_charges(&res).len(), 1);
+}
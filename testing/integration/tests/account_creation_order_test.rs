@@ -0,0 +1,156 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::init_actor;
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{RawBytes, from_slice, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::SEND_ACTOR_BINARY;
+
+mod bundles;
+
+const SEND_ACTOR_ID: ActorID = 10000;
+
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct SendParams {
+    dest: Address,
+    method: MethodNum,
+    value: TokenAmount,
+}
+
+fn setup() -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap()
+    .with_seed(1);
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            SEND_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(SEND_ACTOR_ID),
+            TokenAmount::from_atto(100),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+/// Returns the next ID the init actor will allocate.
+fn next_id(executor: &BasicExecutor) -> ActorID {
+    init_actor::State::load(executor.state_tree())
+        .unwrap()
+        .0
+        .next_id
+}
+
+/// Sends 1 atto to each destination in order, from within a single message, asserting that all
+/// sends succeed.
+fn send_to_all(sender: &Account, executor: &mut BasicExecutor, sequence: u64, dests: &[Address]) {
+    let params: Vec<_> = dests
+        .iter()
+        .map(|dest| SendParams {
+            dest: *dest,
+            method: 0,
+            value: TokenAmount::from_atto(1),
+        })
+        .collect();
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(SEND_ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num: 3,
+        sequence,
+        params: RawBytes::new(to_vec(&params).unwrap()),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let exit_codes: Vec<ExitCode> = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(exit_codes, vec![ExitCode::OK; dests.len()]);
+}
+
+/// Asserts that each address was assigned the given ID, and that an account actor was created for
+/// it with the transferred funds.
+fn assert_ids(executor: &BasicExecutor, dests: &[Address], first_id: ActorID) {
+    for (id, dest) in (first_id..).zip(dests) {
+        assert_eq!(
+            executor.state_tree().lookup_id(dest).unwrap(),
+            Some(id),
+            "{dest}"
+        );
+        let actor = executor.state_tree().get_actor(id).unwrap().unwrap();
+        assert_eq!(actor.balance, TokenAmount::from_atto(1));
+    }
+}
+
+#[test]
+fn auto_created_ids_follow_execution_order() {
+    let keygen = DeterministicKeyGen::new(1000);
+    let dests = [
+        keygen.secp256k1_address(0),
+        keygen.bls_address(1),
+        keygen.secp256k1_address(2),
+    ];
+
+    let (sender, mut executor) = setup();
+    let first_id = next_id(&executor);
+    send_to_all(&sender, &mut executor, 0, &dests);
+
+    // The first send gets the lowest ID, and so on, with nothing else allocating IDs in between.
+    assert_ids(&executor, &dests, first_id);
+    assert_eq!(next_id(&executor), first_id + dests.len() as ActorID);
+}
+
+#[test]
+fn auto_created_ids_depend_only_on_order() {
+    let keygen = DeterministicKeyGen::new(1000);
+    let mut dests = [
+        keygen.secp256k1_address(0),
+        keygen.bls_address(1),
+        keygen.secp256k1_address(2),
+    ];
+    dests.reverse();
+
+    // Sending to the same addresses in reverse order assigns the IDs in reverse.
+    let (sender, mut executor) = setup();
+    let first_id = next_id(&executor);
+    send_to_all(&sender, &mut executor, 0, &dests);
+    assert_ids(&executor, &dests, first_id);
+
+    // Sending to existing accounts (and a new one) in a later message only allocates for the new
+    // account.
+    let new = keygen.secp256k1_address(3);
+    send_to_all(&sender, &mut executor, 1, &[dests[0], new, dests[2]]);
+    assert_eq!(
+        executor.state_tree().lookup_id(&new).unwrap(),
+        Some(first_id + 3)
+    );
+    assert_eq!(next_id(&executor), first_id + 4);
+}
@@ -0,0 +1,86 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+/// The account actor's `PubkeyAddress` method.
+const PUBKEY_ADDRESS_METHOD: u64 = 2;
+
+#[test]
+fn resolve_to_key_address() {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    // Create an account implicitly, by sending funds to a new key address.
+    let key_address = Address::new_secp256k1(&[7; 65]).unwrap();
+    let message = Message {
+        from: sender.1,
+        to: key_address,
+        gas_limit: 1000000000,
+        value: TokenAmount::from_atto(100),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+
+    let id = executor
+        .state_tree()
+        .lookup_id(&key_address)
+        .unwrap()
+        .expect("account wasn't created");
+
+    // Ask the account actor for its key.
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(id),
+        gas_limit: 1000000000,
+        method_num: PUBKEY_ADDRESS_METHOD,
+        sequence: 1,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    let from_method: Address = res.msg_receipt.return_data.deserialize().unwrap();
+
+    // Both ways agree.
+    assert_eq!(from_method, key_address);
+    assert_eq!(executor.resolve_to_key_address(id).unwrap(), key_address);
+    assert_eq!(executor.resolve_to_key_address(sender.0).unwrap(), sender.1);
+
+    // Only account actors have keys.
+    executor
+        .resolve_to_key_address(0)
+        .expect_err("the system actor isn't an account");
+    executor
+        .resolve_to_key_address(id + 1000)
+        .expect_err("the actor doesn't exist");
+}
@@ -0,0 +1,133 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::collections::{BTreeMap, BTreeSet};
+
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm::state_tree::{ActorChanges, ActorState, StateTree};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+fn setup(track_actor_changes: bool) -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| mc.track_actor_changes = track_actor_changes,
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn send(
+    sender: &Account,
+    executor: &mut BasicExecutor,
+    sequence: u64,
+    to: Address,
+    value: u64,
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to,
+        gas_limit: 1000000000,
+        sequence,
+        value: TokenAmount::from_atto(value),
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn load_actors(executor: &BasicExecutor, root: &Cid) -> BTreeMap<ActorID, ActorState> {
+    let mut actors = BTreeMap::new();
+    StateTree::new_from_root(executor.blockstore(), root)
+        .unwrap()
+        .for_each(|addr, actor| {
+            actors.insert(addr.id().unwrap(), actor.clone());
+            Ok(())
+        })
+        .unwrap();
+    actors
+}
+
+/// Computes the changes between two state roots.
+fn diff_roots(executor: &BasicExecutor, before: &Cid, after: &Cid) -> ActorChanges {
+    let before = load_actors(executor, before);
+    let after = load_actors(executor, after);
+    let ids: BTreeSet<_> = before.keys().chain(after.keys()).copied().collect();
+    let mut changes = ActorChanges::default();
+    for id in ids {
+        match (before.get(&id), after.get(&id)) {
+            (None, Some(_)) => changes.created.push(id),
+            (Some(_), None) => changes.deleted.push(id),
+            (Some(before), Some(after)) if before != after => changes.modified.push(id),
+            _ => {}
+        }
+    }
+    changes
+}
+
+#[test]
+fn actor_changes_match_state_diff() {
+    let (sender, mut executor) = setup(true);
+
+    // Send to an f4 to create a placeholder.
+    let receiver = Address::new_delegated(10, b"foobar").unwrap();
+    // And to an unknown ID address, which fails and reverts the transfer.
+    let missing = Address::new_id(1234);
+
+    for (sequence, (to, exit_code)) in [
+        (receiver, ExitCode::OK),
+        (receiver, ExitCode::OK),
+        (missing, ExitCode::SYS_INVALID_RECEIVER),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let before = executor.flush().unwrap();
+        let ret = send(&sender, &mut executor, sequence as u64, to, 1);
+        assert_eq!(ret.msg_receipt.exit_code, exit_code);
+        let after = executor.flush().unwrap();
+
+        let changes = ret.actor_changes.expect("expected actor changes");
+        assert_eq!(changes, diff_roots(&executor, &before, &after));
+
+        // The sender always pays for gas.
+        assert!(changes.modified.contains(&sender.0));
+        let receiver_id = executor.state_tree().lookup_id(&receiver).unwrap().unwrap();
+        match sequence {
+            0 => assert_eq!(changes.created, [receiver_id]),
+            1 => assert!(changes.created.is_empty() && changes.modified.contains(&receiver_id)),
+            _ => assert!(changes.created.is_empty() && !changes.modified.contains(&1234)),
+        }
+    }
+}
+
+#[test]
+fn actor_changes_disabled() {
+    let (sender, mut executor) = setup(false);
+    let ret = send(&sender, &mut executor, 0, Address::new_id(1234), 0);
+    assert_eq!(ret.actor_changes, None);
+}
@@ -0,0 +1,105 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Robust addresses are derived from the number of actors created so far in the message, which
+//! isn't rolled back when a call fails.
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::machine::Machine;
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Account;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::to_vec;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::CREATE_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+/// This actor is allowed to create actors directly (see the default kernel).
+const CREATE_ACTOR_ID: ActorID = 98;
+
+const NONCE: u64 = 7;
+
+/// The robust address of the actor created after `actors_created` others in the message.
+fn robust_address(origin: &Address, actors_created: u64) -> Address {
+    let mut b = to_vec(origin).unwrap();
+    b.extend_from_slice(&NONCE.to_be_bytes());
+    b.extend_from_slice(&actors_created.to_be_bytes());
+    Address::new_actor(&b)
+}
+
+#[test]
+fn aborted_creations_are_counted() {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender]: [Account; 1] = tester.create_accounts().unwrap();
+    tester.set_account_sequence(sender.0, NONCE).unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            CREATE_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(CREATE_ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(CREATE_ACTOR_ID),
+        gas_limit: 1_000_000_000,
+        method_num: 3,
+        sequence: NONCE,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+
+    // Each creation, including the aborted one, changed the next actor address.
+    let addresses: Vec<_> = res
+        .msg_receipt
+        .return_data
+        .chunks(21)
+        .map(|b| Address::from_bytes(b).unwrap())
+        .collect();
+    let expected: Vec<_> = (0..3).map(|i| robust_address(&sender.1, i)).collect();
+    assert_eq!(addresses, expected);
+
+    // The aborted creation is traced, and counted.
+    let created: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|event| match event {
+            ExecutionEvent::CreateActor {
+                id, actors_created, ..
+            } => Some((*id, *actors_created)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(created, [(2000, 0), (2001, 1), (2002, 2)]);
+
+    let state_tree = executor.state_tree();
+    assert!(state_tree.get_actor(2000).unwrap().is_some());
+    assert!(state_tree.get_actor(2001).unwrap().is_none());
+    assert!(state_tree.get_actor(2002).unwrap().is_some());
+}
@@ -0,0 +1,187 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Messages report the actors they created and deleted, excluding changes made by reverted calls.
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::ActorID;
+use fvm_shared::address::{Address, SECP_PUB_LEN};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::{CREATE_ACTOR_BINARY, SSELF_ACTOR_BINARY};
+use num_traits::Zero;
+
+mod bundles;
+
+/// This actor is allowed to create actors directly (see the default kernel).
+const CREATE_ACTOR_ID: ActorID = 98;
+const SSELF_ACTOR_ID: ActorID = 10000;
+
+fn setup() -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            CREATE_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(CREATE_ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .set_actor_from_bin(
+            SSELF_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(SSELF_ACTOR_ID),
+            TokenAmount::from_nano(1_000_000),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn send(
+    executor: &mut BasicExecutor,
+    sender: &Account,
+    sequence: u64,
+    to: Address,
+    method_num: u64,
+    value: TokenAmount,
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to,
+        gas_limit: 1_000_000_000,
+        method_num,
+        sequence,
+        value,
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn aborted_creations_are_excluded() {
+    let (sender, mut executor) = setup();
+
+    // Method 3 creates actors 2000 and 2002, and 2001 in a call that aborts. It returns the next
+    // actor address before each creation.
+    let res = send(
+        &mut executor,
+        &sender,
+        0,
+        Address::new_id(CREATE_ACTOR_ID),
+        3,
+        TokenAmount::zero(),
+    );
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    let addresses: Vec<_> = res
+        .msg_receipt
+        .return_data
+        .chunks(21)
+        .map(|b| Address::from_bytes(b).unwrap())
+        .collect();
+
+    assert_eq!(
+        res.created_actors,
+        [(2000, addresses[0]), (2002, addresses[2])]
+    );
+    assert!(res.deleted_actors.is_empty());
+}
+
+#[test]
+fn implicit_account_creation() {
+    let (sender, mut executor) = setup();
+
+    let new_account = Address::new_secp256k1(&[1; SECP_PUB_LEN]).unwrap();
+    let res = send(
+        &mut executor,
+        &sender,
+        0,
+        new_account,
+        0,
+        TokenAmount::from_atto(1),
+    );
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+
+    let id = executor
+        .state_tree()
+        .lookup_id(&new_account)
+        .unwrap()
+        .expect("account wasn't created");
+    assert_eq!(res.created_actors, [(id, new_account)]);
+    assert!(res.deleted_actors.is_empty());
+
+    // Sending to the account again doesn't create anything.
+    let res = send(
+        &mut executor,
+        &sender,
+        1,
+        new_account,
+        0,
+        TokenAmount::from_atto(1),
+    );
+    assert!(res.msg_receipt.exit_code.is_success());
+    assert!(res.created_actors.is_empty());
+}
+
+#[test]
+fn self_destruct() {
+    let (sender, mut executor) = setup();
+
+    // Method 2 self-destructs, then fails: nothing was deleted.
+    let res = send(
+        &mut executor,
+        &sender,
+        0,
+        Address::new_id(SSELF_ACTOR_ID),
+        2,
+        TokenAmount::zero(),
+    );
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::new(ExitCode::FIRST_USER_EXIT_CODE)
+    );
+    assert!(res.created_actors.is_empty());
+    assert!(res.deleted_actors.is_empty());
+
+    // Method 1 self-destructs, and succeeds.
+    let res = send(
+        &mut executor,
+        &sender,
+        1,
+        Address::new_id(SSELF_ACTOR_ID),
+        1,
+        TokenAmount::zero(),
+    );
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    assert!(res.created_actors.is_empty());
+    assert_eq!(res.deleted_actors, [SSELF_ACTOR_ID]);
+}
@@ -0,0 +1,235 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(feature = "async")]
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bundles::*;
+use cid::Cid;
+use futures::executor::block_on;
+use futures::lock::Mutex;
+use fvm::executor::{ApplyKind, ApplyRet, AsyncExecutor, AsyncExecutorError, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::MethodNum;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+/// Messages with this method make the executor panic.
+const PANIC_METHOD: MethodNum = 1001;
+/// Messages with this method make the executor fail with a fatal error.
+const FATAL_METHOD: MethodNum = 1002;
+
+/// Wraps the executor to inject failures, and signals when it's dropped.
+struct TestExecutor {
+    inner: BasicExecutor,
+    dropped: mpsc::Sender<()>,
+}
+
+impl Executor for TestExecutor {
+    type Kernel = <BasicExecutor as Executor>::Kernel;
+
+    fn execute_message(
+        &mut self,
+        msg: Message,
+        apply_kind: ApplyKind,
+        raw_length: usize,
+    ) -> anyhow::Result<ApplyRet> {
+        match msg.method_num {
+            PANIC_METHOD => panic!("boom"),
+            FATAL_METHOD => Err(anyhow!("fatal")),
+            _ => self.inner.execute_message(msg, apply_kind, raw_length),
+        }
+    }
+
+    fn flush(&mut self) -> anyhow::Result<Cid> {
+        self.inner.flush()
+    }
+}
+
+impl Drop for TestExecutor {
+    fn drop(&mut self) {
+        let _ = self.dropped.send(());
+    }
+}
+
+/// Creates an executor (on the executor's thread) with N accounts, returning the accounts, the
+/// executor, and a channel signalled when the underlying executor is dropped.
+async fn setup<const N: usize>() -> ([Account; N], AsyncExecutor, mpsc::Receiver<()>) {
+    let (accounts_tx, accounts_rx) = mpsc::channel();
+    let (dropped_tx, dropped_rx) = mpsc::channel();
+    let executor = AsyncExecutor::new(4, move || {
+        let mut tester = new_tester(
+            NetworkVersion::V21,
+            StateTreeVersion::V5,
+            MemoryBlockstore::default(),
+        )?;
+        let accounts: [Account; N] = tester.create_accounts()?;
+        tester.instantiate_machine(DummyExterns)?;
+        accounts_tx.send(accounts)?;
+        Ok(TestExecutor {
+            inner: tester.executor.unwrap(),
+            dropped: dropped_tx,
+        })
+    })
+    .await
+    .unwrap();
+    (accounts_rx.recv().unwrap(), executor, dropped_rx)
+}
+
+/// A transfer of 1 atto.
+fn transfer(from: &Account, to: &Account, sequence: u64) -> Message {
+    Message {
+        from: from.1,
+        to: to.1,
+        sequence,
+        value: TokenAmount::from_atto(1),
+        gas_limit: 10_000_000,
+        ..Message::default()
+    }
+}
+
+#[test]
+fn concurrent_threads() {
+    let (accounts, executor, _) = block_on(setup::<5>());
+    let [senders @ .., receiver] = accounts;
+    let executor = Arc::new(executor);
+
+    // Each thread sends its own messages, concurrently with the others.
+    let threads: Vec<_> = senders
+        .into_iter()
+        .map(|sender| {
+            let executor = executor.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    for sequence in 0..5 {
+                        let ret = executor
+                            .execute_message(
+                                transfer(&sender, &receiver, sequence),
+                                ApplyKind::Explicit,
+                                100,
+                            )
+                            .await
+                            .unwrap();
+                        assert_eq!(
+                            ret.msg_receipt.exit_code,
+                            ExitCode::OK,
+                            "{:?}",
+                            ret.failure_info
+                        );
+                    }
+                })
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // A flush after all the messages is deterministic.
+    let root = block_on(executor.flush()).unwrap();
+    assert_eq!(block_on(executor.flush()).unwrap(), root);
+}
+
+#[test]
+fn submission_order() {
+    let ([sender, receiver], executor, _) = block_on(setup::<2>());
+    let executor = Arc::new(executor);
+
+    // Threads take turns submitting messages from the same sender, but wait for the results
+    // concurrently. A message applied out of submission order would fail the nonce check.
+    let next_sequence = Arc::new(Mutex::new(0));
+    let threads: Vec<_> = (0..5)
+        .map(|_| {
+            let executor = executor.clone();
+            let next_sequence = next_sequence.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    for _ in 0..4 {
+                        let pending = {
+                            let mut sequence = next_sequence.lock().await;
+                            let pending = executor
+                                .submit_message(
+                                    transfer(&sender, &receiver, *sequence),
+                                    ApplyKind::Explicit,
+                                    100,
+                                )
+                                .await
+                                .unwrap();
+                            *sequence += 1;
+                            pending
+                        };
+                        let ret = pending.await.unwrap();
+                        assert_eq!(
+                            ret.msg_receipt.exit_code,
+                            ExitCode::OK,
+                            "{:?}",
+                            ret.failure_info
+                        );
+                    }
+                })
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(*block_on(next_sequence.lock()), 20);
+}
+
+#[test]
+fn fatal_errors_and_panics() {
+    let ([sender, receiver], executor, dropped) = block_on(setup::<2>());
+    let message = |sequence, method_num| Message {
+        method_num,
+        ..transfer(&sender, &receiver, sequence)
+    };
+
+    // Fatal errors are returned, and the executor keeps going.
+    let err =
+        block_on(executor.execute_message(message(0, FATAL_METHOD), ApplyKind::Explicit, 100))
+            .unwrap_err();
+    assert!(matches!(err, AsyncExecutorError::Fatal(_)), "{err}");
+    let ret = block_on(executor.execute_message(message(0, 0), ApplyKind::Explicit, 100)).unwrap();
+    assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK);
+
+    // Panics are caught, and shut the executor down.
+    let err =
+        block_on(executor.execute_message(message(1, PANIC_METHOD), ApplyKind::Explicit, 100))
+            .unwrap_err();
+    assert!(
+        matches!(&err, AsyncExecutorError::Panicked(msg) if msg == "boom"),
+        "{err}"
+    );
+    dropped.recv_timeout(Duration::from_secs(10)).unwrap();
+    let err =
+        block_on(executor.execute_message(message(1, 0), ApplyKind::Explicit, 100)).unwrap_err();
+    assert!(matches!(err, AsyncExecutorError::ShutDown), "{err}");
+    let err = block_on(executor.flush()).unwrap_err();
+    assert!(matches!(err, AsyncExecutorError::ShutDown), "{err}");
+}
+
+#[test]
+fn shutdown_on_drop() {
+    let ([sender, receiver], executor, dropped) = block_on(setup::<2>());
+    let pending = block_on(executor.submit_message(
+        transfer(&sender, &receiver, 0),
+        ApplyKind::Explicit,
+        100,
+    ))
+    .unwrap();
+
+    // Dropping the executor lets the queued message finish before shutting down.
+    drop(executor);
+    let ret = block_on(pending).unwrap();
+    assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK);
+    dropped.recv_timeout(Duration::from_secs(10)).unwrap();
+}
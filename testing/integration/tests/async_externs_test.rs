@@ -0,0 +1,188 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Externs can be serviced asynchronously (here, by futures polled on a service thread) through
+//! `SyncExterns`, and extern failures abort the message as fatal errors.
+use std::thread;
+
+use anyhow::anyhow;
+use bundles::*;
+use futures::StreamExt;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::externs::{Externs, ExternsRequest, Reply, SyncExterns};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{BytesSer, RawBytes};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::RANDOMNESS_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+const RANDOMNESS_ACTOR_ID: ActorID = 10000;
+const EPOCH: ChainEpoch = 100;
+const GAS_LIMIT: u64 = 1_000_000_000;
+
+/// How the externs service answers randomness requests.
+#[derive(Clone, Copy)]
+enum Behavior {
+    Answer,
+    Fail,
+    Drop,
+}
+
+fn randomness(beacon: bool, round: ChainEpoch) -> [u8; 32] {
+    Code::Blake2b256
+        .digest(format!("{beacon}-{round}").as_bytes())
+        .digest()[..32]
+        .try_into()
+        .unwrap()
+}
+
+fn answer(behavior: Behavior, beacon: bool, round: ChainEpoch, reply: Reply<[u8; 32]>) {
+    match behavior {
+        Behavior::Answer => reply.send(Ok(randomness(beacon, round))),
+        Behavior::Fail => reply.send(Err(anyhow!("randomness unavailable"))),
+        Behavior::Drop => drop(reply),
+    }
+}
+
+/// Spawns an externs service on its own thread, answering requests concurrently (like a node
+/// querying its chain store), and returns externs that call it. The service stops once the externs
+/// are dropped.
+fn spawn_service(
+    behavior: Behavior,
+) -> SyncExterns<impl Fn(ExternsRequest) -> anyhow::Result<()> + 'static> {
+    let (tx, rx) = mpsc::unbounded::<ExternsRequest>();
+    thread::spawn(move || {
+        block_on(rx.for_each_concurrent(None, |request| async move {
+            match request {
+                ExternsRequest::ChainRandomness { round, reply } => {
+                    answer(behavior, false, round, reply)
+                }
+                ExternsRequest::BeaconRandomness { round, reply } => {
+                    answer(behavior, true, round, reply)
+                }
+                other => other.serve(&DummyExterns),
+            }
+        }))
+    });
+    SyncExterns::new(move |request| {
+        tx.unbounded_send(request)
+            .map_err(|_| anyhow!("externs service stopped"))
+    })
+}
+
+fn setup<E: Externs + 'static>(externs: E) -> (Account, IntegrationExecutor<MemoryBlockstore, E>) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            RANDOMNESS_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(RANDOMNESS_ACTOR_ID),
+            TokenAmount::default(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            externs,
+            |_| (),
+            |mc| {
+                mc.set_epoch(EPOCH).set_timestamp(EPOCH as u64 * 30);
+            },
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn draw<E: Externs + 'static>(
+    executor: &mut IntegrationExecutor<MemoryBlockstore, E>,
+    sender: &Account,
+    sequence: u64,
+    beacon: bool,
+    entropy: &[u8],
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(RANDOMNESS_ACTOR_ID),
+        sequence,
+        gas_limit: GAS_LIMIT,
+        method_num: 2,
+        params: RawBytes::serialize((beacon, EPOCH - 1, BytesSer(entropy))).unwrap(),
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn serviced_asynchronously() {
+    let (sender, mut executor) = setup(spawn_service(Behavior::Answer));
+
+    for (sequence, beacon) in [(0, false), (1, true)] {
+        let res = draw(&mut executor, &sender, sequence, beacon, b"entropy");
+        assert_eq!(
+            res.msg_receipt.exit_code,
+            ExitCode::OK,
+            "{:?}",
+            res.failure_info
+        );
+
+        let mut data = randomness(beacon, EPOCH - 1).to_vec();
+        data.extend_from_slice(b"entropy");
+        assert_eq!(
+            res.msg_receipt.return_data.bytes(),
+            Code::Blake2b256.digest(&data).digest()
+        );
+    }
+}
+
+#[test]
+fn failures_are_fatal() {
+    for (behavior, error) in [
+        (Behavior::Fail, "randomness unavailable"),
+        (Behavior::Drop, "externs request dropped without a reply"),
+    ] {
+        let (sender, mut executor) = setup(spawn_service(behavior));
+        let res = draw(&mut executor, &sender, 0, false, b"entropy");
+
+        // The actor can't observe the failure: the message is aborted, consuming all its gas.
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+        assert_eq!(res.msg_receipt.gas_used, GAS_LIMIT);
+        let failure = res.failure_info.expect("expected a backtrace").to_string();
+        assert!(failure.contains("[FATAL]"), "{failure}");
+        assert!(failure.contains(error), "{failure}");
+    }
+
+    // Requests that can't be sent fail the same way.
+    let (sender, mut executor) = setup(SyncExterns::new(|_| {
+        Err(anyhow!("externs service stopped"))
+    }));
+    let res = draw(&mut executor, &sender, 0, true, b"entropy");
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+    let failure = res.failure_info.expect("expected a backtrace").to_string();
+    assert!(
+        failure.contains("failed to send externs request"),
+        "{failure}"
+    );
+}
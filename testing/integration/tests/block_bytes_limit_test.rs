@@ -0,0 +1,115 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! `NetworkConfig::max_block_bytes_per_message` caps the total size of the blocks created while
+//! executing a message, across the whole call stack and including reverted calls.
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{IPLD_RAW, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::STATE_ROOT_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+const ACTOR_ID: ActorID = 10000;
+
+/// Calls the state root actor with the given per-message limit.
+fn call(max_block_bytes: u64, method_num: MethodNum) -> ApplyRet {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            STATE_ROOT_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.set_max_block_bytes_per_message(max_block_bytes);
+            },
+            |_| (),
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn raw_cid(data: &[u8]) -> Cid {
+    Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(data))
+}
+
+/// Checks that the message succeeds with a limit of exactly `total` bytes, and fails when creating
+/// its last block with a limit of one byte less.
+fn assert_limit(method_num: MethodNum, total: usize) {
+    let res = call(total as u64, method_num);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    let res = call(total as u64 - 1, method_num);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::USR_ASSERTION_FAILED);
+    match &res.failure_info {
+        Some(ApplyFailure::MessageBacktrace(bt)) => {
+            assert!(bt.to_string().contains("LimitExceeded"), "{bt}")
+        }
+        other => panic!("expected a backtrace, got {other:?}"),
+    }
+}
+
+#[test]
+fn counts_blocks_in_a_call() {
+    // Method 2 writes three raw blocks, and returns the last one's CID.
+    let total = b"first".len()
+        + b"second".len()
+        + b"third".len()
+        + to_vec(&raw_cid(b"third")).unwrap().len();
+    assert_limit(2, total);
+}
+
+#[test]
+fn counts_blocks_across_calls() {
+    // Method 6 writes a block, calls method 5 (which writes a block and returns two CIDs), calls
+    // method 7 (which writes a block and aborts), and returns method 5's CID.
+    let nested = raw_cid(b"nested");
+    let total = b"outer".len()
+        + b"nested".len()
+        + to_vec(&(raw_cid(b"outer"), nested)).unwrap().len()
+        + b"reverted".len()
+        + to_vec(&nested).unwrap().len();
+    assert_limit(6, total);
+}
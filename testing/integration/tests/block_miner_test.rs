@@ -0,0 +1,171 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::{BURNT_FUNDS_ACTOR_ID, Machine, REWARD_ACTOR_ID};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::RECORDER_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+const GAS_LIMIT: u64 = 10_000_000;
+const BASE_FEE: u64 = 100;
+
+/// Sets up a funded sender, a receiver and a miner, and a reward actor (which just records calls).
+/// The block miner is chosen once the accounts are created.
+fn setup(
+    block_miner: impl FnOnce(&[Account; 3]) -> Option<Address>,
+) -> ([Account; 3], BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 3] = tester.create_accounts().unwrap();
+    tester
+        .state_tree
+        .as_mut()
+        .unwrap()
+        .mutate_actor(accounts[0].0, |actor| {
+            actor.balance = TokenAmount::from_whole(1);
+            Ok(())
+        })
+        .unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            RECORDER_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(REWARD_ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    let block_miner = block_miner(&accounts);
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                if let Some(miner) = block_miner {
+                    mc.set_block_miner(miner);
+                }
+            },
+        )
+        .unwrap();
+    (accounts, tester.executor.unwrap())
+}
+
+fn balance(executor: &BasicExecutor, id: ActorID) -> TokenAmount {
+    executor
+        .state_tree()
+        .get_actor(id)
+        .unwrap()
+        .unwrap()
+        .balance
+}
+
+/// Transfers 1 atto from the sender to the receiver, with a non-zero tip.
+fn transfer(
+    executor: &mut BasicExecutor,
+    [sender, receiver, _]: &[Account; 3],
+    apply_kind: ApplyKind,
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: receiver.1,
+        value: TokenAmount::from_atto(1),
+        gas_limit: GAS_LIMIT,
+        gas_fee_cap: TokenAmount::from_atto(BASE_FEE * 2),
+        gas_premium: TokenAmount::from_atto(10),
+        ..Message::default()
+    };
+    let ret = executor.execute_message(message, apply_kind, 100).unwrap();
+    assert_eq!(
+        ret.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        ret.failure_info
+    );
+    ret
+}
+
+/// Applies a transfer, returning the miner's ID, the result, and the balance changes of the reward
+/// actor, burnt funds actor and miner.
+fn settle(
+    block_miner: impl FnOnce(&[Account; 3]) -> Option<Address>,
+    apply_kind: ApplyKind,
+) -> (ActorID, ApplyRet, [TokenAmount; 3]) {
+    let (accounts, mut executor) = setup(block_miner);
+    let ids = [REWARD_ACTOR_ID, BURNT_FUNDS_ACTOR_ID, accounts[2].0];
+    let before = ids.map(|id| balance(&executor, id));
+    let ret = transfer(&mut executor, &accounts, apply_kind);
+    let changes = std::array::from_fn(|i| balance(&executor, ids[i]) - &before[i]);
+    (accounts[2].0, ret, changes)
+}
+
+#[test]
+fn tip_paid_to_reward_actor_by_default() {
+    let (_, ret, [reward, burnt, miner]) = settle(|_| None, ApplyKind::Explicit);
+    assert!(ret.miner_tip.is_positive());
+    assert_eq!(ret.miner_tip_recipient, Some(REWARD_ACTOR_ID));
+    assert_eq!(reward, ret.miner_tip);
+    assert_eq!(burnt, &ret.base_fee_burn + &ret.over_estimation_burn);
+    assert!(miner.is_zero());
+}
+
+#[test]
+fn tip_paid_to_block_miner() {
+    let (miner_id, ret, [reward, burnt, miner]) =
+        settle(|accounts| Some(accounts[2].1), ApplyKind::Explicit);
+    assert!(ret.miner_tip.is_positive());
+    assert_eq!(ret.miner_tip_recipient, Some(miner_id));
+    assert!(ret.base_fee_burn.is_positive());
+    assert!(reward.is_zero());
+    assert_eq!(burnt, &ret.base_fee_burn + &ret.over_estimation_burn);
+    assert_eq!(miner, ret.miner_tip);
+}
+
+#[test]
+fn tip_burnt_if_block_miner_unresolvable() {
+    let missing = [
+        Address::new_id(9999),
+        DeterministicKeyGen::new(1).secp256k1_address(0),
+    ];
+    for block_miner in missing {
+        let (_, ret, [reward, burnt, miner]) = settle(|_| Some(block_miner), ApplyKind::Explicit);
+        assert!(ret.miner_tip.is_positive());
+        assert_eq!(ret.miner_tip_recipient, Some(BURNT_FUNDS_ACTOR_ID));
+        assert!(reward.is_zero());
+        assert_eq!(
+            burnt,
+            &ret.base_fee_burn + &ret.over_estimation_burn + &ret.miner_tip
+        );
+        assert!(miner.is_zero());
+    }
+}
+
+#[test]
+fn implicit_message_not_settled() {
+    let (_, ret, changes) = settle(|accounts| Some(accounts[2].1), ApplyKind::Implicit);
+    assert!(ret.miner_tip.is_zero());
+    assert!(ret.base_fee_burn.is_zero());
+    assert!(ret.over_estimation_burn.is_zero());
+    assert!(ret.refund.is_zero());
+    assert_eq!(ret.miner_tip_recipient, None);
+    assert!(changes.iter().all(TokenAmount::is_zero), "{changes:?}");
+}
@@ -0,0 +1,59 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(feature = "nv29-dev")]
+
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::BLOCK_STAT_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+/// `ipld::block_stat_by_cid` is only linked from nv29.
+#[test]
+fn block_stat_by_cid() {
+    let mut tester = new_tester(
+        NetworkVersion::V29,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            BLOCK_STAT_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 1,
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+}
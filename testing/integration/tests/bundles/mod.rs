@@ -12,10 +12,17 @@ use fvm_shared::version::NetworkVersion;
 use lazy_static::lazy_static;
 
 lazy_static! {
-    static ref BUNDLES: BTreeMap<NetworkVersion, &'static [u8]> =
-        [(NetworkVersion::V21, actors::BUNDLE_CAR),]
-            .into_iter()
-            .collect();
+    static ref BUNDLES: BTreeMap<NetworkVersion, &'static [u8]> = {
+        #[allow(unused_mut)]
+        let mut bundles: BTreeMap<NetworkVersion, &'static [u8]> =
+            [(NetworkVersion::V21, actors::BUNDLE_CAR),]
+                .into_iter()
+                .collect();
+        // There's no nv29 bundle yet, so we test nv29 with the latest bundle.
+        #[cfg(feature = "nv29-dev")]
+        bundles.insert(NetworkVersion::V29, actors::BUNDLE_CAR);
+        bundles
+    };
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,308 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use cid::Cid;
+#[cfg(feature = "nv29-dev")]
+use fvm::executor::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+#[cfg(feature = "nv29-dev")]
+use fvm::machine::Machine;
+use fvm::machine::NetworkConfig;
+#[cfg(feature = "nv29-dev")]
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+#[cfg(feature = "nv29-dev")]
+use fvm_integration_tests::tester::BasicExecutor;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::IPLD_RAW;
+#[cfg(feature = "nv29-dev")]
+use fvm_ipld_encoding::tuple::*;
+#[cfg(feature = "nv29-dev")]
+use fvm_ipld_encoding::{RawBytes, from_slice, to_vec};
+#[cfg(feature = "nv29-dev")]
+use fvm_shared::MethodNum;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+#[cfg(feature = "nv29-dev")]
+use fvm_shared::error::ExitCode;
+#[cfg(feature = "nv29-dev")]
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::{HELLO_WORLD_ACTOR_BINARY, SEND_ACTOR_BINARY};
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+const SEND_ACTOR_ID: u64 = 10000;
+const HELLO_ACTOR_ID: u64 = 10001;
+
+#[cfg(feature = "nv29-dev")]
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct SendParams {
+    dest: Address,
+    method: MethodNum,
+    value: TokenAmount,
+}
+
+enum DenySource {
+    Network,
+    Local,
+}
+
+/// Deploys the send actor and the hello world actor at the given network version. Returns the
+/// tester, a sender account, and the hello world actor's code.
+fn deploy(nv: NetworkVersion) -> (Tester<MemoryBlockstore, DummyExterns>, Account, Cid) {
+    let mut tester = new_tester(nv, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            SEND_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(SEND_ACTOR_ID),
+            TokenAmount::from_atto(100),
+        )
+        .unwrap();
+    let hello_code = tester
+        .set_actor_from_bin(
+            HELLO_WORLD_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(HELLO_ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    (tester, sender, hello_code)
+}
+
+/// Instantiates the tester's machine, denying the specified code via the specified source.
+fn instantiate(
+    tester: &mut Tester<MemoryBlockstore, DummyExterns>,
+    source: DenySource,
+    denied_code: Cid,
+) -> anyhow::Result<()> {
+    tester.instantiate_machine_with_config(
+        DummyExterns,
+        |nc| {
+            if let DenySource::Network = source {
+                nc.deny_actor_code(vec![denied_code]);
+            }
+        },
+        |mc| {
+            if let DenySource::Local = source {
+                mc.deny_code_locally(vec![denied_code]);
+            }
+        },
+    )
+}
+
+/// Deploys the send actor and the hello world actor at nv29, denying the hello world actor's code
+/// via the specified source.
+#[cfg(feature = "nv29-dev")]
+fn setup(source: DenySource) -> (Account, Cid, BasicExecutor) {
+    let (mut tester, sender, denied_code) = deploy(NetworkVersion::V29);
+    instantiate(&mut tester, source, denied_code).unwrap();
+    (sender, denied_code, tester.executor.unwrap())
+}
+
+#[cfg(feature = "nv29-dev")]
+fn assert_denied(res: &ApplyRet, denied_code: Cid) {
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_DENIED_CODE);
+
+    match &res.failure_info {
+        Some(ApplyFailure::MessageBacktrace(bt)) => {
+            let frame = bt.frames.last().expect("expected a backtrace frame");
+            assert_eq!(frame.source, HELLO_ACTOR_ID);
+            assert_eq!(frame.code, ExitCode::SYS_DENIED_CODE);
+        }
+        other => panic!("expected a message backtrace, got {:?}", other),
+    }
+
+    // The invocation is charged before the code is denied.
+    let denied_at = res
+        .exec_trace
+        .iter()
+        .position(|evt| matches!(
+            evt,
+            ExecutionEvent::CodeDenied { id, code } if *id == HELLO_ACTOR_ID && *code == denied_code
+        ))
+        .expect("expected a code denied event in the trace");
+    assert!(
+        matches!(
+            &res.exec_trace[denied_at - 1],
+            ExecutionEvent::GasCharge(charge) if charge.name == "OnMethodInvocation"
+        ),
+        "expected the invocation to be charged before the code is denied"
+    );
+}
+
+#[cfg(feature = "nv29-dev")]
+fn invoke_denied_actor(sender: &Account, executor: &mut BasicExecutor) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(HELLO_ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num: 1,
+        sequence: 0,
+        value: TokenAmount::from_atto(10),
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn code_denylist_requires_nv29() {
+    for source in [DenySource::Network, DenySource::Local] {
+        let (mut tester, _, denied_code) = deploy(NetworkVersion::V21);
+        instantiate(&mut tester, source, denied_code).unwrap_err();
+    }
+}
+
+#[cfg(feature = "nv29-dev")]
+#[test]
+fn network_code_denylist() {
+    let (sender, denied_code, mut executor) = setup(DenySource::Network);
+
+    // The network policy is consensus-relevant, so the machine remains consensus-safe.
+    assert!(executor.context().is_consensus_safe());
+
+    let res = invoke_denied_actor(&sender, &mut executor);
+    assert_denied(&res, denied_code);
+
+    // The value transfer must have been reverted.
+    let actor = executor
+        .state_tree()
+        .get_actor(HELLO_ACTOR_ID)
+        .unwrap()
+        .unwrap();
+    assert!(actor.balance.is_zero());
+}
+
+#[cfg(feature = "nv29-dev")]
+#[test]
+fn local_code_denylist() {
+    let (sender, denied_code, mut executor) = setup(DenySource::Local);
+
+    // A node-local denylist may diverge from the rest of the network.
+    assert!(!executor.context().is_consensus_safe());
+    assert!(executor.context().code_denylist.is_empty());
+
+    let res = invoke_denied_actor(&sender, &mut executor);
+    assert_denied(&res, denied_code);
+}
+
+#[cfg(feature = "nv29-dev")]
+#[test]
+fn denied_account_code() {
+    let (mut tester, sender, _) = deploy(NetworkVersion::V29);
+    let account_code = tester
+        .state_tree
+        .as_ref()
+        .unwrap()
+        .get_actor(sender.0)
+        .unwrap()
+        .unwrap()
+        .code;
+    instantiate(&mut tester, DenySource::Network, account_code).unwrap();
+    let mut executor = tester.executor.unwrap();
+
+    // Sending to a new secp256k1 address would create an account actor.
+    let dest = Address::new_secp256k1(&[1; 65]).unwrap();
+    let message = Message {
+        from: sender.1,
+        to: dest,
+        gas_limit: 1000000000,
+        sequence: 0,
+        value: TokenAmount::from_atto(10),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(!res.msg_receipt.exit_code.is_success());
+
+    // The account actor must not have been created.
+    assert_eq!(executor.state_tree().lookup_id(&dest).unwrap(), None);
+}
+
+#[cfg(feature = "nv29-dev")]
+#[test]
+fn nested_send_to_denied_code() {
+    let (sender, denied_code, mut executor) = setup(DenySource::Network);
+
+    let params = SendParams {
+        dest: Address::new_id(HELLO_ACTOR_ID),
+        method: 1,
+        value: TokenAmount::from_atto(10),
+    };
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(SEND_ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num: 2,
+        sequence: 0,
+        params: RawBytes::new(to_vec(&params).unwrap()),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    // The caller observes the denial as the exit code of its send and carries on.
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let inner_exit: ExitCode = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(inner_exit, ExitCode::SYS_DENIED_CODE);
+
+    assert!(res.exec_trace.iter().any(|evt| matches!(
+        evt,
+        ExecutionEvent::CodeDenied { id, code } if *id == HELLO_ACTOR_ID && *code == denied_code
+    )));
+
+    // The inner value transfer was rolled back, leaving the caller's balance untouched.
+    let caller = executor
+        .state_tree()
+        .get_actor(SEND_ACTOR_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(caller.balance, TokenAmount::from_atto(100));
+    let callee = executor
+        .state_tree()
+        .get_actor(HELLO_ACTOR_ID)
+        .unwrap()
+        .unwrap();
+    assert!(callee.balance.is_zero());
+}
+
+#[test]
+fn code_denylist_fingerprint() {
+    let a = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"a"));
+    let b = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"b"));
+
+    let mut nc1 = NetworkConfig::new(NetworkVersion::V21);
+    nc1.deny_actor_code(vec![a, b]);
+    let mut nc2 = NetworkConfig::new(NetworkVersion::V21);
+    nc2.deny_actor_code(vec![b, a, b]);
+    let mut nc3 = NetworkConfig::new(NetworkVersion::V21);
+    nc3.deny_actor_code(vec![a]);
+
+    // Order and duplicates don't affect the fingerprint, but the contents do.
+    assert_eq!(
+        nc1.code_denylist_fingerprint(),
+        nc2.code_denylist_fingerprint()
+    );
+    assert_ne!(
+        nc1.code_denylist_fingerprint(),
+        nc3.code_denylist_fingerprint()
+    );
+}
@@ -0,0 +1,99 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use cid::Cid;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicTester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_shared::address::Protocol;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+/// Pinned addresses for `(seed, index)` pairs. If these change, every fixture derived from seeded
+/// keys changes with them.
+const GOLDEN_SECP256K1_ADDRESSES: &[(u64, u64, &str)] = &[
+    (42, 0, "01224b1fb4699be20759f86a719e15b37ec59d065d"),
+    (42, 1, "01d50ecc15a8d53777346858621c0fb4822c2f7273"),
+    (7, 0, "0187334e4013bb3cf95af862214dde1125531f5886"),
+];
+
+fn seeded_tester(seed: u64) -> BasicTester {
+    new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap()
+    .with_seed(seed)
+}
+
+fn message_cid(from: &Account, to: &Account) -> Cid {
+    let message = Message {
+        from: from.1,
+        to: to.1,
+        gas_limit: 1000000000,
+        value: TokenAmount::from_atto(10),
+        ..Message::default()
+    };
+    Cid::new_v1(
+        DAG_CBOR,
+        Code::Blake2b256.digest(&to_vec(&message).unwrap()),
+    )
+}
+
+#[test]
+fn golden_secp256k1_addresses() {
+    for &(seed, index, expected) in GOLDEN_SECP256K1_ADDRESSES {
+        let addr = DeterministicKeyGen::new(seed).secp256k1_address(index);
+        assert_eq!(
+            hex::encode(addr.to_bytes()),
+            expected,
+            "seed {seed}, index {index}"
+        );
+    }
+}
+
+#[test]
+fn bls_keys_are_deterministic() {
+    let keygen = DeterministicKeyGen::new(42);
+
+    let addr = keygen.bls_address(0);
+    assert_eq!(addr.protocol(), Protocol::BLS);
+    assert_eq!(addr, DeterministicKeyGen::new(42).bls_address(0));
+    assert_ne!(addr, keygen.bls_address(1));
+    assert_ne!(addr, DeterministicKeyGen::new(43).bls_address(0));
+}
+
+#[test]
+fn seeded_accounts_use_keygen() {
+    let keygen = DeterministicKeyGen::new(42);
+    let mut tester = seeded_tester(42);
+
+    // Successive calls create new accounts, continuing through the key generator's indices.
+    let [a, b] = tester.create_accounts().unwrap();
+    let [c] = tester.create_bls_accounts().unwrap();
+    assert_eq!(a.1, keygen.secp256k1_address(0));
+    assert_eq!(b.1, keygen.secp256k1_address(1));
+    assert_eq!(c.1, keygen.bls_address(2));
+    assert_eq!(c.0, b.0 + 1);
+}
+
+#[test]
+fn seeded_testers_are_reproducible() {
+    let run = |seed| {
+        let mut tester = seeded_tester(seed);
+        let [a, b] = tester.create_accounts().unwrap();
+        let [c] = tester.create_bls_accounts().unwrap();
+        let root = tester.state_tree.as_mut().unwrap().flush().unwrap();
+        (root, message_cid(&a, &b), message_cid(&b, &c))
+    };
+
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(7));
+}
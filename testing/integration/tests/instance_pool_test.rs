@@ -0,0 +1,101 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Engines whose instance pool can't hold a full call stack allocate instances on demand instead.
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor, ThreadedExecutor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Account;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::STACK_OVERFLOW_ACTOR_BINARY;
+
+mod bundles;
+
+/// The stack overflow actor calls itself with increasing method numbers until method 1025, then
+/// aborts with 0x42 from method 1026. Starting at this method makes for a call stack of this many
+/// instances (plus one).
+const FIRST_METHOD: u64 = 1000;
+const CALL_DEPTH: u32 = (1026 - FIRST_METHOD) as u32;
+
+/// The network's maximum call depth. Engines only pool instances if the pool can hold this many,
+/// so it's lowered from the default (which would disable pooling for any pool smaller than 1024
+/// instances), but is still deep enough for the call stack above.
+const MAX_CALL_DEPTH: u32 = 32;
+
+/// Sends a message to the stack overflow actor with an engine pooling at most `max_pooled_instances`
+/// instances.
+fn deep_call(max_pooled_instances: u32) -> ApplyRet {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender]: [Account; 1] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            STACK_OVERFLOW_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::default(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_engine_config(
+            DummyExterns,
+            |nc| {
+                nc.set_max_call_depth(MAX_CALL_DEPTH);
+            },
+            |_| (),
+            |ec| ec.max_pooled_instances = max_pooled_instances,
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 10_000_000_000,
+        method_num: FIRST_METHOD,
+        ..Message::default()
+    };
+    // Each call recurses deeply in wasm, so we need a large native stack.
+    ThreadedExecutor(tester.executor.unwrap())
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn call_stack_deeper_than_the_pool() {
+    assert!(CALL_DEPTH < MAX_CALL_DEPTH);
+
+    // The pool holds a full call stack, so instances are pooled.
+    let pooled = deep_call(MAX_CALL_DEPTH);
+    assert_eq!(
+        pooled.msg_receipt.exit_code.value(),
+        0x80000042,
+        "{:?}",
+        pooled.failure_info
+    );
+
+    // With a pool smaller than the call stack, instances are allocated on demand instead, and the
+    // message runs exactly the same, rather than failing when the pool runs out.
+    let on_demand = deep_call(CALL_DEPTH / 2);
+    assert_eq!(on_demand.msg_receipt, pooled.msg_receipt);
+
+    // Likewise with a pool that could hold this call stack, but not a full one.
+    let on_demand = deep_call(MAX_CALL_DEPTH - 1);
+    assert_eq!(on_demand.msg_receipt, pooled.msg_receipt);
+
+    // And with pooling disabled.
+    let on_demand = deep_call(0);
+    assert_eq!(on_demand.msg_receipt, pooled.msg_receipt);
+}
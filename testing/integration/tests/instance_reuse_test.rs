@@ -0,0 +1,119 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+/// An actor that asserts that all of its instance state (globals, memory, tables, and passive
+/// segments) is in its initial state, then mutates all of it. If any state leaked from a previous
+/// invocation, the actor traps.
+const STATEFUL_ACTOR: &str = r#"
+(module
+  (type $ret_i32 (func (result i32)))
+  (memory (export "memory") 1)
+  (global $counter (mut i32) (i32.const 0))
+  (table 2 funcref)
+  (elem (i32.const 0) $one $two)
+  (elem $passive_elem func $one)
+  (data (i32.const 0) "\2a")
+  (data $passive_data "\07")
+  (func $one (result i32) (i32.const 1))
+  (func $two (result i32) (i32.const 2))
+  (func (export "invoke") (param $x i32) (result i32)
+    ;; Globals start at their initial values.
+    (if (i32.ne (global.get $counter) (i32.const 0)) (then unreachable))
+    ;; Memory is zeroed except for the active data segment.
+    (if (i32.ne (i32.load8_u (i32.const 0)) (i32.const 42)) (then unreachable))
+    (if (i32.ne (i32.load (i32.const 16)) (i32.const 0)) (then unreachable))
+    ;; Tables contain the active element segment.
+    (if (i32.ne (call_indirect (type $ret_i32) (i32.const 1)) (i32.const 2)) (then unreachable))
+    ;; Passive segments haven't been dropped (these trap if they have).
+    (memory.init $passive_data (i32.const 32) (i32.const 0) (i32.const 1))
+    (if (i32.ne (i32.load8_u (i32.const 32)) (i32.const 7)) (then unreachable))
+    (table.init $passive_elem (i32.const 1) (i32.const 0) (i32.const 1))
+    (if (i32.ne (call_indirect (type $ret_i32) (i32.const 1)) (i32.const 1)) (then unreachable))
+
+    ;; Now mutate everything.
+    (global.set $counter (i32.const 1))
+    (i32.store8 (i32.const 0) (i32.const 0))
+    (i32.store (i32.const 16) (i32.const 0xdeadbeef))
+    (table.copy (i32.const 0) (i32.const 1) (i32.const 1))
+    (data.drop $passive_data)
+    (elem.drop $passive_elem)
+    (i32.const 0)))
+"#;
+
+const ACTOR_ID: u64 = 10000;
+
+fn setup() -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(STATEFUL_ACTOR).unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            &wasm_bin,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::from_atto(0),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn invoke(sender: &Account, executor: &mut BasicExecutor, sequence: u64) -> Receipt {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num: 1,
+        sequence,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "invocation {sequence} failed: {:?}",
+        res.failure_info
+    );
+    res.msg_receipt
+}
+
+#[test]
+fn instance_state_does_not_leak() {
+    // Execute in a fresh executor, where no pooled instance slot has been used before.
+    let (sender, mut executor) = setup();
+    let fresh = invoke(&sender, &mut executor, 0);
+
+    // Then re-invoke the same actor repeatedly, recycling pooled instance slots. Every invocation
+    // must observe pristine instance state and behave exactly like the fresh one.
+    for sequence in 1..5 {
+        let reused = invoke(&sender, &mut executor, sequence);
+        assert_eq!(reused, fresh, "invocation {sequence} diverged");
+    }
+
+    // And an independent executor behaves identically.
+    let (sender, mut executor) = setup();
+    assert_eq!(invoke(&sender, &mut executor, 0), fresh);
+}
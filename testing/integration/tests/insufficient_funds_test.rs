@@ -0,0 +1,213 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Internal sends that can't be completed: the actor gets a syscall error it can handle, and
+//! nothing changes.
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm::init_actor;
+use fvm::machine::Machine;
+use fvm::state_tree::ActorState;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{RawBytes, from_slice, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, METHOD_SEND, MethodNum};
+use fvm_test_actors::wasm_bin::SEND_ACTOR_BINARY;
+use num_traits::FromPrimitive;
+
+mod bundles;
+
+const SEND_ACTOR_ID: ActorID = 10000;
+const ACTOR_BALANCE: u64 = 1000;
+const EAM_ACTOR_ID: ActorID = 10;
+
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct SendParams {
+    dest: Address,
+    method: MethodNum,
+    value: TokenAmount,
+}
+
+fn setup() -> ([Account; 2], BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            SEND_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(SEND_ACTOR_ID),
+            TokenAmount::from_atto(ACTOR_BALANCE),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (accounts, tester.executor.unwrap())
+}
+
+fn actor(executor: &BasicExecutor, id: ActorID) -> ActorState {
+    executor.state_tree().get_actor(id).unwrap().unwrap()
+}
+
+/// Returns the next ID the init actor will allocate.
+fn next_id(executor: &BasicExecutor) -> ActorID {
+    init_actor::State::load(executor.state_tree())
+        .unwrap()
+        .0
+        .next_id
+}
+
+/// Has the send actor make each (destination, value) send in order, from within a single message,
+/// returning the error number of each send (if any).
+fn send_all(
+    executor: &mut BasicExecutor,
+    sender: &Account,
+    sends: &[(Address, u64)],
+) -> Vec<Option<ErrorNumber>> {
+    let params: Vec<_> = sends
+        .iter()
+        .map(|(dest, value)| SendParams {
+            dest: *dest,
+            method: METHOD_SEND,
+            value: TokenAmount::from_atto(*value),
+        })
+        .collect();
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(SEND_ACTOR_ID),
+        gas_limit: 1_000_000_000,
+        method_num: 4,
+        sequence: actor(executor, sender.0).sequence,
+        params: RawBytes::new(to_vec(&params).unwrap()),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    // Failed sends don't trap the calling actor.
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let errors: Vec<u32> = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    errors
+        .into_iter()
+        .map(|errno| (errno != 0).then(|| ErrorNumber::from_u32(errno).unwrap()))
+        .collect()
+}
+
+#[test]
+fn insufficient_funds_is_recoverable() {
+    let ([sender, receiver], mut executor) = setup();
+    let new_account = DeterministicKeyGen::new(1).secp256k1_address(0);
+    let send_actor = Address::new_id(SEND_ACTOR_ID);
+
+    let actor_before = actor(&executor, SEND_ACTOR_ID);
+    let receiver_before = actor(&executor, receiver.0);
+    let next_id_before = next_id(&executor);
+
+    let errors = send_all(
+        &mut executor,
+        &sender,
+        &[
+            // The account would be created, but the transfer fails and the creation is reverted.
+            (new_account, ACTOR_BALANCE + 1),
+            // Self-transfers must be affordable, even though they don't move any funds.
+            (send_actor, ACTOR_BALANCE + 1),
+            (send_actor, ACTOR_BALANCE),
+            (receiver.1, ACTOR_BALANCE + 1),
+            // The actor carries on after handling the errors.
+            (receiver.1, 1),
+        ],
+    );
+    assert_eq!(
+        errors,
+        [
+            Some(ErrorNumber::InsufficientFunds),
+            Some(ErrorNumber::InsufficientFunds),
+            None,
+            Some(ErrorNumber::InsufficientFunds),
+            None,
+        ]
+    );
+
+    // Only the last transfer went through.
+    let actor_after = actor(&executor, SEND_ACTOR_ID);
+    assert_eq!(
+        actor_after.balance,
+        TokenAmount::from_atto(ACTOR_BALANCE - 1)
+    );
+    assert_eq!(actor_after.sequence, actor_before.sequence);
+    assert_eq!(actor_after.state, actor_before.state);
+    assert_eq!(
+        actor(&executor, receiver.0).balance,
+        &receiver_before.balance + TokenAmount::from_atto(1)
+    );
+    assert_eq!(executor.state_tree().lookup_id(&new_account).unwrap(), None);
+    assert_eq!(next_id(&executor), next_id_before);
+}
+
+#[test]
+fn zero_value_sends_to_missing_actors() {
+    let ([sender, _], mut executor) = setup();
+    let new_account = DeterministicKeyGen::new(1).secp256k1_address(0);
+    let new_placeholder = Address::new_delegated(EAM_ACTOR_ID, &[1; 20]).unwrap();
+    let missing = [
+        Address::new_id(SEND_ACTOR_ID + 1),
+        Address::new_actor(b"missing"),
+        Address::new_delegated(1234, &[1; 20]).unwrap(),
+    ];
+
+    // Actors are never created at ID, actor, or non-EAM delegated addresses.
+    let next_id_before = next_id(&executor);
+    let sends: Vec<_> = missing.iter().map(|dest| (*dest, 0)).collect();
+    let errors = send_all(&mut executor, &sender, &sends);
+    assert_eq!(errors, [Some(ErrorNumber::NotFound); 3]);
+    assert_eq!(
+        executor.state_tree().get_actor(SEND_ACTOR_ID + 1).unwrap(),
+        None
+    );
+    for dest in &missing[1..] {
+        assert_eq!(
+            executor.state_tree().lookup_id(dest).unwrap(),
+            None,
+            "{dest}"
+        );
+    }
+    assert_eq!(next_id(&executor), next_id_before);
+
+    // Whereas sends to key addresses create accounts, and sends to EAM addresses create
+    // placeholders, even without any value.
+    let errors = send_all(
+        &mut executor,
+        &sender,
+        &[(new_account, 0), (new_placeholder, 0)],
+    );
+    assert_eq!(errors, [None, None]);
+    for (id, dest) in (next_id_before..).zip([new_account, new_placeholder]) {
+        assert_eq!(
+            executor.state_tree().lookup_id(&dest).unwrap(),
+            Some(id),
+            "{dest}"
+        );
+        assert!(actor(&executor, id).balance.is_zero());
+    }
+}
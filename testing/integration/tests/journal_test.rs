@@ -0,0 +1,159 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Journaled execution, resumed after stopping partway through a batch, must end in the same state
+//! as uninterrupted execution.
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ChainMessage, Executor, Journal};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::SECP_PUB_LEN;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+const MESSAGES: usize = 6;
+
+fn setup() -> ([Account; 2], BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts = tester.create_accounts().unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (accounts, tester.executor.unwrap())
+}
+
+/// Transfers from the first account, alternating between the second account and new accounts.
+fn messages([sender, receiver]: &[Account; 2]) -> Vec<ChainMessage> {
+    (0..MESSAGES)
+        .map(|i| {
+            let to = if i % 2 == 0 {
+                receiver.1
+            } else {
+                Address::new_secp256k1(&[i as u8; SECP_PUB_LEN]).unwrap()
+            };
+            ChainMessage {
+                message: Message {
+                    from: sender.1,
+                    to,
+                    gas_limit: 1_000_000_000,
+                    sequence: i as u64,
+                    value: TokenAmount::from_atto(10),
+                    ..Message::default()
+                },
+                raw_length: 100,
+            }
+        })
+        .collect()
+}
+
+/// Applies the messages without a journal, returning the receipts and the final state root.
+fn apply_uninterrupted(messages: &[ChainMessage]) -> (Vec<Receipt>, Cid) {
+    let (_, mut executor) = setup();
+    let receipts = messages
+        .iter()
+        .map(|msg| {
+            executor
+                .execute_message(msg.message.clone(), ApplyKind::Explicit, msg.raw_length)
+                .unwrap()
+                .msg_receipt
+        })
+        .collect();
+    (receipts, executor.flush().unwrap())
+}
+
+/// Applies the first `count` messages with a journal, returning the journal's bytes.
+fn apply_until(messages: &[ChainMessage], count: usize) -> Vec<u8> {
+    let (_, mut executor) = setup();
+    let mut journal = Journal::new(Vec::new());
+    let rets = executor
+        .apply_journaled(&mut journal, &messages[..count])
+        .unwrap();
+    assert_eq!(rets.len(), count);
+    assert_eq!(journal.next_index(), count);
+    journal.into_writer()
+}
+
+/// Resumes from the journal on a fresh executor and applies the remaining messages.
+fn resume(messages: &[ChainMessage], data: &[u8], resumed: usize) -> (Vec<Receipt>, Cid) {
+    let (_, mut executor) = setup();
+    let mut journal = executor
+        .resume_from_journal(data, messages, Vec::new())
+        .unwrap();
+    assert_eq!(journal.next_index(), resumed);
+    let rets = executor.apply_journaled(&mut journal, messages).unwrap();
+    assert_eq!(rets.len(), messages.len() - resumed);
+    (journal.receipts().to_vec(), executor.flush().unwrap())
+}
+
+#[test]
+fn resume_matches_uninterrupted() {
+    let (accounts, _) = setup();
+    let messages = messages(&accounts);
+    let (expected_receipts, expected_root) = apply_uninterrupted(&messages);
+    for receipt in &expected_receipts {
+        assert_eq!(receipt.exit_code, ExitCode::OK);
+    }
+
+    for stop in 0..=MESSAGES {
+        let data = apply_until(&messages, stop);
+        let (receipts, root) = resume(&messages, &data, stop);
+        assert_eq!(receipts, expected_receipts, "stopped after {stop} messages");
+        assert_eq!(root, expected_root, "stopped after {stop} messages");
+    }
+}
+
+#[test]
+fn truncated_journal() {
+    let (accounts, _) = setup();
+    let messages = messages(&accounts);
+    let (expected_receipts, expected_root) = apply_uninterrupted(&messages);
+
+    // The last record was only partially written.
+    let mut data = apply_until(&messages, MESSAGES);
+    data.pop();
+    let (receipts, root) = resume(&messages, &data, MESSAGES - 1);
+    assert_eq!(receipts, expected_receipts);
+    assert_eq!(root, expected_root);
+
+    // Only part of the first record's length was written.
+    let (receipts, root) = resume(&messages, &data[..4], 0);
+    assert_eq!(receipts, expected_receipts);
+    assert_eq!(root, expected_root);
+}
+
+#[test]
+fn mismatched_journal() {
+    let (accounts, _) = setup();
+    let messages = messages(&accounts);
+    let data = apply_until(&messages, 3);
+
+    // Different messages.
+    let mut other = messages.clone();
+    other[1].message.value = TokenAmount::from_atto(11);
+    let (_, mut executor) = setup();
+    assert!(
+        executor
+            .resume_from_journal(&data[..], &other, Vec::new())
+            .is_err()
+    );
+
+    // Fewer messages than records.
+    let (_, mut executor) = setup();
+    assert!(
+        executor
+            .resume_from_journal(&data[..], &messages[..2], Vec::new())
+            .is_err()
+    );
+}
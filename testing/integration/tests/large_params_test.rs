@@ -0,0 +1,189 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Messages with parameters up to the maximum block size are delivered whole to the receiving
+//! actor, which reads them through the SDK into a memory that has to grow to hold them.
+use bundles::*;
+use fvm::call_manager::backtrace::Cause;
+use fvm::executor::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+use fvm::gas::Gas;
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::PARAMS_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+const MIB: usize = 1 << 20;
+
+/// Sends `size` bytes of parameters to the params actor, with the given maximum block size.
+/// Returns the result, and the parameters.
+fn send_params(nv: NetworkVersion, max_block_size: usize, size: usize) -> (ApplyRet, Vec<u8>) {
+    let mut tester = new_tester(nv, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            PARAMS_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.set_max_block_size(max_block_size);
+            },
+            |_| (),
+        )
+        .unwrap();
+
+    let params: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 10_000_000_000,
+        method_num: 2,
+        params: RawBytes::new(params.clone()),
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, raw_length(size))
+        .unwrap();
+    (res, params)
+}
+
+/// The chain length of a message with `size` bytes of parameters.
+fn raw_length(size: usize) -> usize {
+    size + 100
+}
+
+/// Returns the totals of the gas charges with the given name, in order.
+fn charges(res: &ApplyRet, name: &str) -> Vec<Gas> {
+    res.exec_trace
+        .iter()
+        .filter_map(|event| match event {
+            ExecutionEvent::GasCharge(charge) if charge.name == name => Some(charge.total()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks that the actor received the parameters whole, and that they were charged for once on
+/// inclusion and once when read, with the given charges.
+///
+/// From nv21, including a message costs `38863 + 475000 + 36 * 1300` gas plus 1300 gas per byte of
+/// the message, and reading a block costs 0.4 gas per byte.
+fn assert_delivered(res: &ApplyRet, params: &[u8], on_chain_message: Gas, on_block_read: Gas) {
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    let mut expected = (params.len() as u64).to_be_bytes().to_vec();
+    expected.extend_from_slice(Code::Blake2b256.digest(params).digest());
+    assert_eq!(res.msg_receipt.return_data.bytes(), expected);
+
+    assert_eq!(charges(res, "OnChainMessage"), [on_chain_message]);
+    assert_eq!(charges(res, "OnBlockRead"), [on_block_read]);
+}
+
+#[test]
+fn one_mib_params() {
+    let nv = NetworkVersion::V21;
+    let (res, params) = send_params(nv, MIB, MIB);
+    assert_delivered(
+        &res,
+        &params,
+        Gas::new(1_363_839_463),
+        Gas::from_milligas(419_430_400),
+    );
+
+    // The gas used is deterministic.
+    let (again, _) = send_params(nv, MIB, MIB);
+    assert_eq!(again.msg_receipt.gas_used, res.msg_receipt.gas_used);
+}
+
+#[test]
+fn params_at_configured_max() {
+    let nv = NetworkVersion::V21;
+    let (res, params) = send_params(nv, 4 * MIB, 4 * MIB);
+    assert_delivered(
+        &res,
+        &params,
+        Gas::new(5_453_285_863),
+        Gas::from_milligas(1_677_721_600),
+    );
+
+    // Larger parameters cost more to include, read and (in this actor) hash.
+    let (small, _) = send_params(nv, 4 * MIB, MIB);
+    assert!(small.msg_receipt.gas_used < res.msg_receipt.gas_used);
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn params_over_max_rejected_at_send() {
+    let nv = NetworkVersion::V29;
+
+    // At the limit, the parameters are delivered.
+    let (res, params) = send_params(nv, MIB, MIB);
+    assert_delivered(
+        &res,
+        &params,
+        Gas::new(1_363_839_463),
+        Gas::from_milligas(419_430_400),
+    );
+
+    // Just over, the send fails before the actor is invoked, only charging for inclusion.
+    let (res, _) = send_params(nv, MIB, MIB + 1);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+    assert!(
+        !res.exec_trace
+            .iter()
+            .any(|event| matches!(event, ExecutionEvent::InvokeActor { .. })),
+        "the actor shouldn't be invoked"
+    );
+    assert!(charges(&res, "OnBlockRead").is_empty());
+    assert_eq!(res.msg_receipt.gas_used, 1_363_840_763);
+    match &res.failure_info {
+        Some(ApplyFailure::MessageBacktrace(bt)) => assert!(
+            matches!(
+                &bt.cause,
+                Some(Cause::Syscall {
+                    error: ErrorNumber::LimitExceeded,
+                    ..
+                })
+            ),
+            "{bt}"
+        ),
+        other => panic!("expected a backtrace, got {other:?}"),
+    }
+}
+
+#[test]
+fn params_over_max_delivered_before_nv29() {
+    let nv = NetworkVersion::V21;
+    let (res, params) = send_params(nv, MIB, MIB + 1);
+    assert_delivered(
+        &res,
+        &params,
+        Gas::new(1_363_840_763),
+        Gas::from_milligas(419_430_800),
+    );
+}
@@ -21,9 +21,10 @@ use fvm_shared::state::StateTreeVersion;
 use fvm_shared::version::NetworkVersion;
 use fvm_test_actors::wasm_bin::{
     ADDRESS_ACTOR_BINARY, CREATE_ACTOR_BINARY, CUSTOM_SYSCALL_ACTOR_BINARY, EXIT_DATA_ACTOR_BINARY,
-    HELLO_WORLD_ACTOR_BINARY, IPLD_ACTOR_BINARY, OOM_ACTOR_BINARY, READONLY_ACTOR_BINARY,
-    SSELF_ACTOR_BINARY, STACK_OVERFLOW_ACTOR_BINARY, SYSCALL_ACTOR_BINARY,
-    SYSCALL_ACTOR_BINARY_FIP0079, UPGRADE_ACTOR_BINARY, UPGRADE_RECEIVE_ACTOR_BINARY,
+    HELLO_WORLD_ACTOR_BINARY, IPLD_ACTOR_BINARY, OOM_ACTOR_BINARY, PANIC_ACTOR_BINARY,
+    PROOFS_ACTOR_BINARY, READONLY_ACTOR_BINARY, SSELF_ACTOR_BINARY, STACK_OVERFLOW_ACTOR_BINARY,
+    SYSCALL_ACTOR_BINARY, SYSCALL_ACTOR_BINARY_FIP0079, UPGRADE_ACTOR_BINARY,
+    UPGRADE_RECEIVE_ACTOR_BINARY,
 };
 use num_traits::Zero;
 
@@ -212,6 +213,61 @@ fn syscalls_inner(wasm_bin: &[u8]) {
     }
 }
 
+#[test]
+fn proofs() {
+    // Instantiate tester
+    let mut tester = new_tester(
+        NV_FOR_TEST,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Set actor state
+    let actor_state = State::default();
+    let state_cid = tester.set_state(&actor_state).unwrap();
+
+    // Set actor
+    let actor_address = Address::new_id(10000);
+
+    tester
+        .set_actor_from_bin(
+            PROOFS_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    // Instantiate machine
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // Send message
+    let message = Message {
+        from: sender[0].1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 1,
+        ..Message::default()
+    };
+
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+
+    if !res.msg_receipt.exit_code.is_success() {
+        if let Some(info) = res.failure_info {
+            panic!("{}", info)
+        } else {
+            panic!("non-zero exit code {}", res.msg_receipt.exit_code)
+        }
+    }
+}
+
 #[test]
 fn sself() {
     // Instantiate tester
@@ -480,6 +536,69 @@ fn exit_data() {
     }
 }
 
+#[test]
+fn panic_handler() {
+    // Instantiate tester
+    let mut tester = new_tester(
+        NV_FOR_TEST,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+    // Set actor state
+    let actor_state = State::default();
+    let state_cid = tester.set_state(&actor_state).unwrap();
+
+    // Set actor
+    let actor_address = Address::new_id(10000);
+
+    tester
+        .set_actor_from_bin(
+            PANIC_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    // Instantiate machine
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let executor = tester.executor.as_mut().unwrap();
+
+    // Method 1 panics with the default panic handler, method 2 with a custom exit code.
+    for (seq, (method_num, exit_code)) in [
+        (1, ExitCode::USR_ASSERTION_FAILED),
+        (2, ExitCode::new(0x42)),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let message = Message {
+            from: sender[0].1,
+            to: actor_address,
+            gas_limit: 1000000000,
+            method_num,
+            sequence: seq as u64,
+            ..Message::default()
+        };
+
+        let res = executor
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+
+        assert_eq!(res.msg_receipt.exit_code, exit_code);
+
+        // The abort message includes the panic message and its location.
+        let info = res.failure_info.expect("expected failure info").to_string();
+        assert!(info.contains("index out of bounds"), "{info}");
+        assert!(info.contains("src/actor.rs:"), "{info}");
+    }
+}
+
 #[test]
 fn native_stack_overflow() {
     // Instantiate tester
@@ -656,6 +775,38 @@ fn out_of_stack() {
     );
 }
 
+/// Returns an actor that exits with the specified code.
+fn exit_with_code_wat(code: u32) -> String {
+    format!(
+        r#"(module
+             (import "vm" "exit" (func $exit (param i32 i32 i32 i32) (result i32)))
+             (memory (export "memory") 1)
+             (func (export "invoke") (param $x i32) (result i32)
+               (drop (call $exit (i32.const {code}) (i32.const 0) (i32.const 0) (i32.const 0)))
+               (i32.const 0)))"#,
+        code = code as i32,
+    )
+}
+
+#[test]
+fn exit_code_bands() {
+    // System exit codes are rejected.
+    for code in [1, ExitCode::FIRST_USER_EXIT_CODE - 1] {
+        test_exitcode(&exit_with_code_wat(code), ExitCode::SYS_ILLEGAL_EXIT_CODE);
+    }
+    // User exit codes are passed through, including the boundaries.
+    for code in [
+        ExitCode::FIRST_USER_EXIT_CODE,
+        0x7fff_ffff,
+        0x8000_0000,
+        u32::MAX,
+    ] {
+        test_exitcode(&exit_with_code_wat(code), ExitCode::new(code));
+    }
+    // Exiting with 0 is a successful early return.
+    test_exitcode(&exit_with_code_wat(0), ExitCode::OK);
+}
+
 #[test]
 fn no_memory() {
     // Make sure we can construct a module with 0 memory pages.
@@ -809,6 +960,94 @@ fn backtraces() {
     println!("panic backtrace: {}", res.failure_info.unwrap());
 }
 
+#[test]
+fn fatal_error_block_registry_snapshot() {
+    // Creates a few raw blocks, then opens the actor's state root (which fails to load).
+    const WAT: &str = r#"
+    (module
+      (import "ipld" "block_create" (func $block_create (param i32 i64 i32 i32) (result i32)))
+      (import "ipld" "block_open" (func $block_open (param i32 i32) (result i32)))
+      (import "self" "root" (func $root (param i32 i32 i32) (result i32)))
+      (memory (export "memory") 1)
+      (data (i32.const 0) "first")
+      (data (i32.const 16) "second block")
+      (data (i32.const 32) "third!")
+      (func (export "invoke") (param $x i32) (result i32)
+        (drop (call $block_create (i32.const 256) (i64.const 0x55) (i32.const 0) (i32.const 5)))
+        (drop (call $block_create (i32.const 256) (i64.const 0x55) (i32.const 16) (i32.const 12)))
+        (drop (call $block_create (i32.const 256) (i64.const 0x55) (i32.const 32) (i32.const 6)))
+        (drop (call $root (i32.const 256) (i32.const 512) (i32.const 100)))
+        (drop (call $block_open (i32.const 256) (i32.const 512)))
+        unreachable))
+    "#;
+
+    let run = |actor_debugging: bool| {
+        let blockstore = Rc::new(FailingBlockstore::default());
+        let mut tester = new_tester(NV_FOR_TEST, StateTreeVersion::V5, blockstore.clone()).unwrap();
+        let sender: [Account; 1] = tester.create_accounts().unwrap();
+
+        let state_cid = tester.set_state(&State { count: 42 }).unwrap();
+        blockstore.add_fail(state_cid);
+
+        let actor_address = Address::new_id(10000);
+        tester
+            .set_actor_from_bin(
+                &wat::parse_str(WAT).unwrap(),
+                state_cid,
+                actor_address,
+                TokenAmount::zero(),
+            )
+            .unwrap();
+        tester
+            .instantiate_machine_with_config(
+                DummyExterns,
+                |nc| nc.actor_debugging = actor_debugging,
+                |_| (),
+            )
+            .unwrap();
+
+        let message = Message {
+            from: sender[0].1,
+            to: actor_address,
+            gas_limit: 10_000_000,
+            method_num: 1,
+            ..Message::default()
+        };
+        let res = tester
+            .executor
+            .as_mut()
+            .unwrap()
+            .execute_message(message, ApplyKind::Explicit, 100)
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_ASSERTION_FAILED);
+        res.failure_info.unwrap().to_string()
+    };
+
+    // The snapshot lists the invocation and the metadata of every block in its registry.
+    let failure = run(false);
+    assert!(
+        failure.contains(
+            "::invoke(1) at call depth 1 (block registry: 3 blocks, 1 reachable CIDs; \
+             #1: created, codec 0x55, 5 bytes, 0 links; \
+             #2: created, codec 0x55, 12 bytes, 0 links; \
+             #3: created, codec 0x55, 6 bytes, 0 links)"
+        ),
+        "{failure}"
+    );
+    assert!(failure.contains("an error was triggered"), "{failure}");
+
+    // With actor debugging enabled, it also includes a preview of each block.
+    let failure = run(true);
+    assert!(
+        failure.contains(
+            "#1: created, codec 0x55, 5 bytes, 0 links, data 6669727374; \
+             #2: created, codec 0x55, 12 bytes, 0 links, data 7365636f6e6420626c6f636b; \
+             #3: created, codec 0x55, 6 bytes, 0 links, data 746869726421)"
+        ),
+        "{failure}"
+    );
+}
+
 #[test]
 fn test_allocate_max() {
     // Test the not-OOM condition: just enough memory to not run out of memory.
@@ -1236,6 +1475,7 @@ fn upgrade_actor_test() {
     let receiver = Address::new_id(10000);
     let receiver2 = Address::new_id(10001);
     let receiver3 = Address::new_id(10002);
+    let delegated_address = Address::new_delegated(10, b"upgrade").unwrap();
 
     // inline function to reset the tester framework so we can have clean slate between test cases
     let init_tester = || -> (Tester<MemoryBlockstore, DummyExterns>, [Account; 1]) {
@@ -1281,6 +1521,17 @@ fn upgrade_actor_test() {
             )
             .unwrap();
 
+        // give the main actor a delegated address, which must survive upgrades
+        tester
+            .state_tree
+            .as_mut()
+            .unwrap()
+            .mutate_actor(receiver.id().unwrap(), |actor| {
+                actor.delegated_address = Some(delegated_address);
+                Ok(())
+            })
+            .unwrap();
+
         tester.instantiate_machine(DummyExterns).unwrap();
 
         (tester, sender)
@@ -1328,6 +1579,12 @@ fn upgrade_actor_test() {
                 return_data: None,
                 expected_cid: None,
             },
+            // test that upgrading to builtin actor code fails with Forbidden
+            Case {
+                method_num: 6,
+                return_data: None,
+                expected_cid: Some(calc_cid_func(UPGRADE_ACTOR_BINARY)),
+            },
         ]
     };
 
@@ -1363,13 +1620,13 @@ fn upgrade_actor_test() {
 
         // if this test case should have changed the code cid, check that it did
         if let Some(expected_cid) = case.expected_cid {
-            let code = executor
+            let actor = executor
                 .state_tree()
                 .get_actor(receiver.id().unwrap())
                 .unwrap()
-                .unwrap()
-                .code;
-            assert_eq!(code, expected_cid);
+                .unwrap();
+            assert_eq!(actor.code, expected_cid);
+            assert_eq!(actor.delegated_address, Some(delegated_address));
         }
     }
 }
@@ -0,0 +1,139 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{ApplyKind, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::from_slice;
+use fvm_ipld_encoding::tuple::*;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::MESSAGE_CONTEXT_ACTOR_BINARY;
+
+mod bundles;
+
+const ACTOR_ID: ActorID = 10000;
+const SENDER_SEQUENCE: u64 = 7;
+
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct MessageInfo {
+    origin: ActorID,
+    nonce: u64,
+    gas_premium: TokenAmount,
+}
+
+fn setup() -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+    tester
+        .set_account_sequence(sender.0, SENDER_SEQUENCE)
+        .unwrap();
+
+    // Give the sender enough funds to pay for gas at a non-zero fee cap.
+    let state_tree = tester.state_tree.as_mut().unwrap();
+    let mut actor = state_tree.get_actor(sender.0).unwrap().unwrap();
+    actor.balance = TokenAmount::from_whole(1000);
+    state_tree.set_actor(sender.0, actor);
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            MESSAGE_CONTEXT_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::from_atto(0),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn call(
+    sender: &Account,
+    executor: &mut BasicExecutor,
+    method_num: u64,
+    sequence: u64,
+    gas_premium: u64,
+) -> Vec<u8> {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num,
+        sequence,
+        gas_fee_cap: TokenAmount::from_atto(150),
+        gas_premium: TokenAmount::from_atto(gas_premium),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    res.msg_receipt.return_data.to_vec()
+}
+
+#[test]
+fn message_context() {
+    let (sender, mut executor) = setup();
+
+    let info: MessageInfo =
+        from_slice(&call(&sender, &mut executor, 2, SENDER_SEQUENCE, 10)).unwrap();
+    assert_eq!(
+        info,
+        MessageInfo {
+            origin: sender.0,
+            nonce: SENDER_SEQUENCE,
+            gas_premium: TokenAmount::from_atto(10),
+        }
+    );
+}
+
+#[test]
+fn message_context_capped_premium() {
+    let (sender, mut executor) = setup();
+
+    // The base fee is 100, so only 50 of the 80 premium fits under the fee cap of 150.
+    let info: MessageInfo =
+        from_slice(&call(&sender, &mut executor, 2, SENDER_SEQUENCE, 80)).unwrap();
+    assert_eq!(info.gas_premium, TokenAmount::from_atto(50));
+}
+
+#[test]
+fn message_context_constant_across_calls() {
+    let (sender, mut executor) = setup();
+
+    let (outer, inner): (MessageInfo, MessageInfo) =
+        from_slice(&call(&sender, &mut executor, 3, SENDER_SEQUENCE, 10)).unwrap();
+    assert_eq!(outer.origin, sender.0);
+    assert_eq!(outer.nonce, SENDER_SEQUENCE);
+    assert_eq!(outer, inner);
+}
+
+#[test]
+fn message_context_nonce_across_messages() {
+    let (sender, mut executor) = setup();
+
+    // The nested call (at call depth 2) observes the nonce of each top-level message.
+    for sequence in [SENDER_SEQUENCE, SENDER_SEQUENCE + 1] {
+        let (outer, inner): (MessageInfo, MessageInfo) =
+            from_slice(&call(&sender, &mut executor, 3, sequence, 10)).unwrap();
+        assert_eq!(outer.nonce, sequence);
+        assert_eq!(inner.nonce, sequence);
+    }
+}
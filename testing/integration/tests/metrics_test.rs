@@ -0,0 +1,105 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(feature = "metrics")]
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::{Machine, MachineMetrics};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::IPLD_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+/// Runs the IPLD actor once on a fresh machine, returning the result, the resulting state root,
+/// and the collected metrics, if enabled.
+fn run_ipld_actor(metrics: bool) -> (ApplyRet, Cid, Option<MachineMetrics>) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            IPLD_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.metrics = metrics;
+            },
+        )
+        .unwrap();
+    let executor = tester.executor.as_mut().unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 1,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+
+    let root = Executor::flush(executor).unwrap();
+    let collected = executor.metrics().cloned();
+    assert_eq!(collected.is_some(), metrics);
+
+    if let Some(m) = executor.metrics_mut() {
+        m.reset();
+        assert_eq!(executor.metrics().unwrap().iter().count(), 0);
+    }
+
+    (res, root, collected)
+}
+
+#[test]
+fn collects_metrics() {
+    let (_, _, metrics) = run_ipld_actor(true);
+    let metrics = metrics.expect("metrics are enabled");
+
+    let wasm = metrics
+        .get("wasm_exec")
+        .expect("no wasm execution recorded");
+    assert!(wasm.count > 0);
+    assert!(wasm.max_nanos <= wasm.total_nanos);
+
+    let open = metrics.get("OnBlockOpen").expect("no block reads recorded");
+    assert!(open.count > 0);
+}
+
+#[test]
+fn metrics_are_deterministic() {
+    let (res_a, root_a, _) = run_ipld_actor(true);
+    let (res_b, root_b, metrics) = run_ipld_actor(false);
+    assert!(metrics.is_none());
+
+    // Collecting timings must not change anything consensus-critical.
+    assert_eq!(res_a.msg_receipt, res_b.msg_receipt);
+    assert_eq!(res_a.gas_burned, res_b.gas_burned);
+    assert_eq!(root_a, root_b);
+}
@@ -0,0 +1,140 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Messages whose gas limit is zero, or too small to cover the message inclusion cost.
+use bundles::*;
+use fvm::executor::{ApplyFailure, ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm::state_tree::ActorState;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::ActorID;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+
+mod bundles;
+
+const BASE_FEE: u64 = 100;
+const RAW_LENGTH: usize = 100;
+
+fn setup() -> ([Account; 2], BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 2] = tester.create_accounts().unwrap();
+    tester
+        .state_tree
+        .as_mut()
+        .unwrap()
+        .mutate_actor(accounts[0].0, |actor| {
+            actor.balance = TokenAmount::from_whole(1);
+            Ok(())
+        })
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (accounts, tester.executor.unwrap())
+}
+
+fn actor(executor: &BasicExecutor, id: ActorID) -> ActorState {
+    executor.state_tree().get_actor(id).unwrap().unwrap()
+}
+
+/// Sends 1 atto with the specified gas limit, paying exactly the base fee for gas.
+fn transfer(
+    executor: &mut BasicExecutor,
+    [sender, receiver]: &[Account; 2],
+    gas_limit: u64,
+) -> ApplyRet {
+    let sequence = actor(executor, sender.0).sequence;
+    let message = Message {
+        from: sender.1,
+        to: receiver.1,
+        sequence,
+        value: TokenAmount::from_atto(1),
+        gas_limit,
+        gas_fee_cap: TokenAmount::from_atto(BASE_FEE),
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, RAW_LENGTH)
+        .unwrap()
+}
+
+#[test]
+fn gas_limit_sweep() {
+    let (accounts, mut executor) = setup();
+    let [sender, receiver] = &accounts;
+    let inclusion_cost = executor
+        .context()
+        .price_list
+        .on_chain_message(RAW_LENGTH)
+        .total()
+        .round_up();
+
+    for gas_limit in 0..=inclusion_cost + 10 {
+        let sender_before = actor(&executor, sender.0);
+        let receiver_before = actor(&executor, receiver.0);
+
+        let res = transfer(&mut executor, &accounts, gas_limit);
+
+        // Either way, the message runs out of gas and the receiver gets nothing.
+        assert_eq!(
+            res.msg_receipt.exit_code,
+            ExitCode::SYS_OUT_OF_GAS,
+            "gas limit {gas_limit}"
+        );
+        assert_eq!(actor(&executor, receiver.0), receiver_before);
+        assert!(!res.refund.is_negative(), "gas limit {gas_limit}");
+
+        let sender_after = actor(&executor, sender.0);
+        if gas_limit < inclusion_cost {
+            // The message can't pay for its own inclusion, so it's rejected without touching the
+            // sender. The miner pays for including it.
+            assert!(
+                matches!(res.failure_info, Some(ApplyFailure::PreValidation(_))),
+                "gas limit {gas_limit}: {:?}",
+                res.failure_info
+            );
+            assert_eq!(res.msg_receipt.gas_used, 0);
+            assert_eq!(
+                res.penalty,
+                TokenAmount::from_atto(BASE_FEE * inclusion_cost)
+            );
+            assert!(res.base_fee_burn.is_zero());
+            assert!(res.refund.is_zero());
+            assert_eq!(sender_after, sender_before, "gas limit {gas_limit}");
+        } else {
+            // From the inclusion cost on, the message is executed and runs out of gas while
+            // transferring the value. The nonce is bumped and the sender pays for all the gas.
+            assert!(
+                !matches!(res.failure_info, Some(ApplyFailure::PreValidation(_))),
+                "gas limit {gas_limit}: {:?}",
+                res.failure_info
+            );
+            assert_eq!(res.msg_receipt.gas_used, gas_limit);
+            assert!(res.penalty.is_zero());
+            assert_eq!(
+                res.base_fee_burn,
+                TokenAmount::from_atto(BASE_FEE * gas_limit)
+            );
+            assert!(res.over_estimation_burn.is_zero());
+            assert!(res.refund.is_zero());
+            assert_eq!(sender_after.sequence, sender_before.sequence + 1);
+            assert_eq!(
+                sender_after.balance,
+                &sender_before.balance - &res.base_fee_burn
+            );
+        }
+    }
+
+    // Every message at or above the inclusion cost was executed.
+    assert_eq!(actor(&executor, sender.0).sequence, 11);
+}
@@ -0,0 +1,369 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Differential tests checking that observability is free: tracing (and metrics, when built with
+//! the `metrics` feature) must not change receipts, fees, or state. Each workload is run with
+//! tracing and metrics enabled and disabled, and the results are compared.
+//!
+//! Actor debugging is different: actors can query it (`debug::enabled`), so it's a network
+//! parameter and may change the gas used by actors that branch on it (e.g., through
+//! `fvm_sdk::initialize`). Workloads are compared with debugging fixed, except for an actor that
+//! never queries it, where the debug syscalls must behave identically either way.
+use std::fs;
+
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicExecutor, ExecutionOptions};
+use fvm_integration_tests::testkit;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{RawBytes, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, METHOD_SEND, MethodNum};
+use fvm_test_actors::wasm_bin::{
+    EXIT_DATA_ACTOR_BINARY, SEND_ACTOR_BINARY, STATE_ROOT_ACTOR_BINARY,
+};
+
+mod bundles;
+
+const STATE_ROOT_ACTOR_ID: ActorID = 10000;
+const SEND_ACTOR_ID: ActorID = 10001;
+const EXIT_DATA_ACTOR_ID: ActorID = 10002;
+const DEBUG_ACTOR_ID: ActorID = 10003;
+
+const CONTRACT_PATH: &str = "../../tools/contracts/gas-stress/recursive.bin";
+
+#[derive(Clone, Copy, Debug)]
+struct Observability {
+    tracing: bool,
+    debugging: bool,
+}
+
+/// Everything about a message's execution that ends up on chain, or in the fees paid.
+#[derive(Debug, PartialEq)]
+struct MessageOutcome {
+    /// The CBOR-encoded receipt.
+    receipt: Vec<u8>,
+    base_fee_burn: TokenAmount,
+    over_estimation_burn: TokenAmount,
+    miner_tip: TokenAmount,
+    penalty: TokenAmount,
+    refund: TokenAmount,
+    gas_refund: u64,
+    gas_burned: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    messages: Vec<MessageOutcome>,
+    state_root: Cid,
+}
+
+/// Records the outcome of a message, checking that the trace was (or wasn't) recorded.
+fn record(obs: Observability, messages: &mut Vec<MessageOutcome>, ret: ApplyRet) -> ApplyRet {
+    let traced = ret
+        .exec_trace
+        .iter()
+        .any(|e| matches!(e, ExecutionEvent::GasCharge(_)));
+    assert_eq!(traced, obs.tracing, "{obs:?}");
+    messages.push(MessageOutcome {
+        receipt: to_vec(&ret.msg_receipt).unwrap(),
+        base_fee_burn: ret.base_fee_burn.clone(),
+        over_estimation_burn: ret.over_estimation_burn.clone(),
+        miner_tip: ret.miner_tip.clone(),
+        penalty: ret.penalty.clone(),
+        refund: ret.refund.clone(),
+        gas_refund: ret.gas_refund,
+        gas_burned: ret.gas_burned,
+    });
+    ret
+}
+
+/// Runs the workload under all combinations of tracing, with debugging fixed, asserting the
+/// outcomes are identical.
+fn assert_tracing_is_free(workload: impl Fn(Observability) -> Outcome) {
+    for debugging in [false, true] {
+        let untraced = workload(Observability {
+            tracing: false,
+            debugging,
+        });
+        let traced = workload(Observability {
+            tracing: true,
+            debugging,
+        });
+        assert_eq!(untraced, traced, "debugging: {debugging}");
+    }
+}
+
+fn new_executor(obs: Observability, actors: &[(&[u8], ActorID, u64)]) -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    for (wasm_bin, id, balance) in actors {
+        tester
+            .set_actor_from_bin(
+                wasm_bin,
+                state_cid,
+                Address::new_id(*id),
+                TokenAmount::from_atto(*balance),
+            )
+            .unwrap();
+    }
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| nc.actor_debugging = obs.debugging,
+            |mc| {
+                mc.tracing = obs.tracing;
+                #[cfg(feature = "metrics")]
+                {
+                    mc.metrics = obs.tracing;
+                }
+            },
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn apply(
+    executor: &mut BasicExecutor,
+    sender: &Account,
+    to: ActorID,
+    method_num: MethodNum,
+    params: RawBytes,
+    gas_limit: u64,
+) -> ApplyRet {
+    let sequence = executor
+        .state_tree()
+        .get_actor(sender.0)
+        .unwrap()
+        .unwrap()
+        .sequence;
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(to),
+        sequence,
+        method_num,
+        params,
+        value: TokenAmount::from_atto(100),
+        gas_limit,
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+/// Commits and aborts state transactions, sends (creating an account), exits with return data, and
+/// runs out of gas.
+fn test_actors_workload(obs: Observability) -> Outcome {
+    let (sender, mut executor) = new_executor(
+        obs,
+        &[
+            (STATE_ROOT_ACTOR_BINARY, STATE_ROOT_ACTOR_ID, 0),
+            (SEND_ACTOR_BINARY, SEND_ACTOR_ID, 1000),
+            (EXIT_DATA_ACTOR_BINARY, EXIT_DATA_ACTOR_ID, 0),
+        ],
+    );
+    let new_account = DeterministicKeyGen::new(1).secp256k1_address(0);
+    let sends = to_vec(&[
+        (
+            Address::new_id(STATE_ROOT_ACTOR_ID),
+            2,
+            TokenAmount::from_atto(10),
+        ),
+        (new_account, METHOD_SEND, TokenAmount::from_atto(1)),
+        (
+            Address::new_id(EXIT_DATA_ACTOR_ID),
+            3,
+            TokenAmount::from_atto(0),
+        ),
+    ])
+    .unwrap();
+
+    let mut messages = Vec::new();
+    let mut run = |to, method, params, gas_limit| {
+        record(
+            obs,
+            &mut messages,
+            apply(&mut executor, &sender, to, method, params, gas_limit),
+        )
+    };
+    let gas_limit = 1_000_000_000;
+    let receipt = run(STATE_ROOT_ACTOR_ID, 2, RawBytes::default(), gas_limit).msg_receipt;
+    assert_eq!(receipt.exit_code, ExitCode::OK);
+    assert_eq!(
+        run(STATE_ROOT_ACTOR_ID, 3, RawBytes::default(), gas_limit)
+            .msg_receipt
+            .exit_code,
+        ExitCode::new(42)
+    );
+    assert_eq!(
+        run(SEND_ACTOR_ID, 3, RawBytes::new(sends), gas_limit)
+            .msg_receipt
+            .exit_code,
+        ExitCode::OK
+    );
+    assert_eq!(
+        run(EXIT_DATA_ACTOR_ID, 3, RawBytes::default(), gas_limit)
+            .msg_receipt
+            .exit_code,
+        ExitCode::new(0x42)
+    );
+    assert_eq!(
+        run(
+            STATE_ROOT_ACTOR_ID,
+            2,
+            RawBytes::default(),
+            receipt.gas_used / 2
+        )
+        .msg_receipt
+        .exit_code,
+        ExitCode::SYS_OUT_OF_GAS
+    );
+
+    Outcome {
+        messages,
+        state_root: executor.flush().unwrap(),
+    }
+}
+
+#[test]
+fn test_actors_unaffected_by_tracing() {
+    assert_tracing_is_free(test_actors_workload);
+}
+
+/// Deploys the gas-stress contract and invokes it at a range of gas limits, many of which run out
+/// of gas at different points (see `gasfuzz`).
+fn fevm_workload(obs: Observability) -> Outcome {
+    let mut tester = new_basic_tester(ExecutionOptions {
+        debug: obs.debugging,
+        trace: obs.tracing,
+        events: false,
+    })
+    .unwrap();
+    let mut account = tester.create_basic_account().unwrap();
+    let contract = hex::decode(fs::read_to_string(CONTRACT_PATH).unwrap()).unwrap();
+
+    let mut messages = Vec::new();
+    let create_res = record(
+        obs,
+        &mut messages,
+        testkit::fevm::create_contract(&mut tester, &mut account, &contract).unwrap(),
+    );
+    assert!(create_res.msg_receipt.exit_code.is_success());
+    let create_return: testkit::fevm::CreateReturn =
+        create_res.msg_receipt.return_data.deserialize().unwrap();
+    let actor = Address::new_id(create_return.actor_id);
+
+    for gas in (2_000_000..=12_000_000)
+        .step_by(500_000)
+        .chain([testkit::fevm::DEFAULT_GAS])
+    {
+        record(
+            obs,
+            &mut messages,
+            testkit::fevm::invoke_contract(&mut tester, &mut account, actor, &[], gas).unwrap(),
+        );
+    }
+
+    Outcome {
+        messages,
+        state_root: tester.with_executor(|e| e.flush()).unwrap(),
+    }
+}
+
+#[test]
+fn fevm_unaffected_by_tracing() {
+    assert_tracing_is_free(fevm_workload);
+}
+
+/// An actor that logs and stores artifacts, with both valid and malformed arguments, without
+/// querying whether debugging is enabled. It traps if any of the syscalls fail.
+const DEBUG_ACTOR: &str = r#"
+(module
+  (import "debug" "log" (func $log (param i32 i32) (result i32)))
+  (import "debug" "store_artifact" (func $store_artifact (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "hello")
+  (data (i32.const 16) "\ff\fe")
+  (data (i32.const 32) "../artifact")
+  (func (export "invoke") (param $x i32) (result i32)
+    (local $errs i32)
+    ;; A valid message, one that isn't UTF-8, and one that's out of bounds.
+    (local.set $errs (call $log (i32.const 0) (i32.const 5)))
+    (local.set $errs (i32.or (local.get $errs) (call $log (i32.const 16) (i32.const 2))))
+    (local.set $errs (i32.or (local.get $errs) (call $log (i32.const 65530) (i32.const 100))))
+    ;; A valid artifact, one with an invalid name, and one that's out of bounds.
+    (local.set $errs (i32.or (local.get $errs)
+      (call $store_artifact (i32.const 35) (i32.const 8) (i32.const 0) (i32.const 5))))
+    (local.set $errs (i32.or (local.get $errs)
+      (call $store_artifact (i32.const 32) (i32.const 11) (i32.const 0) (i32.const 5))))
+    (local.set $errs (i32.or (local.get $errs)
+      (call $store_artifact (i32.const 32) (i32.const 11) (i32.const 65530) (i32.const 100))))
+    (if (local.get $errs) (then unreachable))
+    (i32.const 0)))
+"#;
+
+fn debug_actor_workload(obs: Observability) -> (Outcome, Vec<String>) {
+    let wasm_bin = wat::parse_str(DEBUG_ACTOR).unwrap();
+    let (sender, mut executor) = new_executor(obs, &[(wasm_bin.as_slice(), DEBUG_ACTOR_ID, 0)]);
+    let ret = apply(
+        &mut executor,
+        &sender,
+        DEBUG_ACTOR_ID,
+        1,
+        RawBytes::default(),
+        10_000_000,
+    );
+    assert_eq!(ret.msg_receipt.exit_code, ExitCode::OK, "{obs:?}");
+    let logs = ret
+        .exec_trace
+        .iter()
+        .filter_map(|e| match e {
+            ExecutionEvent::Log(msg) => Some(msg.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mut messages = Vec::new();
+    record(obs, &mut messages, ret);
+    let outcome = Outcome {
+        messages,
+        state_root: executor.flush().unwrap(),
+    };
+    (outcome, logs)
+}
+
+#[test]
+fn debug_syscalls_are_free() {
+    let (expected, logs) = debug_actor_workload(Observability {
+        tracing: false,
+        debugging: false,
+    });
+    assert!(logs.is_empty());
+    for (tracing, debugging) in [(true, false), (false, true), (true, true)] {
+        let obs = Observability { tracing, debugging };
+        let (outcome, logs) = debug_actor_workload(obs);
+        assert_eq!(outcome, expected, "{obs:?}");
+        // Malformed messages are logged lossily, or dropped.
+        let expected_logs: &[&str] = if debugging {
+            &["hello", "\u{fffd}\u{fffd}"]
+        } else {
+            &[]
+        };
+        assert_eq!(logs, expected_logs, "{obs:?}");
+    }
+}
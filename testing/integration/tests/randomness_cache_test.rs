@@ -0,0 +1,243 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! The machine caches randomness from the externs across messages, without changing gas.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::externs::{Chain, Consensus, Externs, Rand};
+use fvm::machine::Machine;
+use fvm::system_actor::SYSTEM_ACTOR_ID;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, IntegrationExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{BytesSer, RawBytes};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, MinerPower};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::RANDOMNESS_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+const RANDOMNESS_ACTOR_ID: ActorID = 10000;
+const EPOCH: ChainEpoch = 100;
+
+/// Deterministic randomness, counting the calls for each (beacon, epoch).
+#[derive(Clone, Default)]
+struct CountingExterns {
+    calls: Arc<Mutex<HashMap<(bool, ChainEpoch), usize>>>,
+}
+
+impl CountingExterns {
+    fn randomness(&self, beacon: bool, round: ChainEpoch) -> [u8; 32] {
+        *self
+            .calls
+            .lock()
+            .unwrap()
+            .entry((beacon, round))
+            .or_default() += 1;
+        Code::Blake2b256
+            .digest(format!("{beacon}-{round}").as_bytes())
+            .digest()[..32]
+            .try_into()
+            .unwrap()
+    }
+
+    fn calls(&self, beacon: bool, round: ChainEpoch) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .get(&(beacon, round))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Externs for CountingExterns {}
+
+impl Rand for CountingExterns {
+    fn get_chain_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        Ok(self.randomness(false, round))
+    }
+
+    fn get_beacon_randomness(&self, round: ChainEpoch) -> anyhow::Result<[u8; 32]> {
+        Ok(self.randomness(true, round))
+    }
+}
+
+impl Consensus for CountingExterns {
+    fn verify_consensus_fault(
+        &self,
+        h1: &[u8],
+        h2: &[u8],
+        extra: &[u8],
+    ) -> anyhow::Result<(Option<ConsensusFault>, i64)> {
+        DummyExterns.verify_consensus_fault(h1, h2, extra)
+    }
+
+    fn get_miner_power(&self, miner: ActorID, epoch: ChainEpoch) -> anyhow::Result<MinerPower> {
+        DummyExterns.get_miner_power(miner, epoch)
+    }
+}
+
+impl Chain for CountingExterns {
+    fn get_tipset_cid(&self, epoch: ChainEpoch) -> anyhow::Result<Cid> {
+        DummyExterns.get_tipset_cid(epoch)
+    }
+}
+
+type CountingExecutor = IntegrationExecutor<MemoryBlockstore, CountingExterns>;
+
+fn setup(externs: CountingExterns, cache_size: Option<usize>) -> (Account, CountingExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            RANDOMNESS_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(RANDOMNESS_ACTOR_ID),
+            TokenAmount::default(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            externs,
+            |_| (),
+            |mc| {
+                mc.set_epoch(EPOCH).set_timestamp(EPOCH as u64 * 30);
+                if let Some(size) = cache_size {
+                    mc.set_randomness_cache_size(size);
+                }
+            },
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+/// Draws randomness through the actor, with an explicit message from the sender or, without a
+/// sender, an implicit message from the system actor (like cron).
+fn draw(
+    executor: &mut CountingExecutor,
+    sender: Option<&Account>,
+    beacon: bool,
+    epoch: ChainEpoch,
+    entropy: &[u8],
+) -> ([u8; 32], ApplyRet) {
+    let (from, apply_kind) = match sender {
+        Some(sender) => (*sender, ApplyKind::Explicit),
+        None => (
+            (SYSTEM_ACTOR_ID, Address::new_id(SYSTEM_ACTOR_ID)),
+            ApplyKind::Implicit,
+        ),
+    };
+    let sequence = executor
+        .state_tree()
+        .get_actor(from.0)
+        .unwrap()
+        .unwrap()
+        .sequence;
+    let message = Message {
+        from: from.1,
+        to: Address::new_id(RANDOMNESS_ACTOR_ID),
+        sequence,
+        gas_limit: 1_000_000_000,
+        method_num: 2,
+        params: RawBytes::serialize((beacon, epoch, BytesSer(entropy))).unwrap(),
+        ..Message::default()
+    };
+    let res = executor.execute_message(message, apply_kind, 100).unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let randomness = res.msg_receipt.return_data.bytes().try_into().unwrap();
+    (randomness, res)
+}
+
+#[test]
+fn cached_across_messages() {
+    let externs = CountingExterns::default();
+    let (sender, mut executor) = setup(externs.clone(), None);
+
+    // Three messages (the last one implicit, like cron) draw the same randomness, but only the
+    // first hits the externs. Gas is charged the same way on hits and misses.
+    let (first, first_ret) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+    let (second, second_ret) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+    let (third, _) = draw(&mut executor, None, false, EPOCH - 1, b"a");
+    assert_eq!(second, first);
+    assert_eq!(third, first);
+    assert_eq!(
+        second_ret.msg_receipt.gas_used,
+        first_ret.msg_receipt.gas_used
+    );
+    assert_eq!(externs.calls(false, EPOCH - 1), 1);
+
+    // Entropy is mixed in by the actor, so it's applied on top of the cached randomness.
+    let (other_entropy, _) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"b");
+    assert_ne!(other_entropy, first);
+    assert_eq!(externs.calls(false, EPOCH - 1), 1);
+
+    // Other epochs, and the beacon, are cached separately.
+    let (other_epoch, _) = draw(&mut executor, Some(&sender), false, EPOCH - 2, b"a");
+    let (beacon, _) = draw(&mut executor, Some(&sender), true, EPOCH - 1, b"a");
+    assert_ne!(other_epoch, first);
+    assert_ne!(beacon, first);
+    for _ in 0..2 {
+        draw(&mut executor, Some(&sender), false, EPOCH - 2, b"a");
+        draw(&mut executor, Some(&sender), true, EPOCH - 1, b"a");
+    }
+    assert_eq!(externs.calls(false, EPOCH - 2), 1);
+    assert_eq!(externs.calls(true, EPOCH - 1), 1);
+}
+
+#[test]
+fn not_shared_across_machines() {
+    let externs = CountingExterns::default();
+    let (first, first_ret) = {
+        let (sender, mut executor) = setup(externs.clone(), None);
+        draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+        draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a")
+    };
+    assert_eq!(externs.calls(false, EPOCH - 1), 1);
+
+    // A new machine starts with an empty cache.
+    let (sender, mut executor) = setup(externs.clone(), None);
+    let (randomness, ret) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+    assert_eq!(randomness, first);
+    assert_eq!(ret.msg_receipt.gas_used, first_ret.msg_receipt.gas_used);
+    assert_eq!(externs.calls(false, EPOCH - 1), 2);
+}
+
+#[test]
+fn disabled_cache() {
+    let externs = CountingExterns::default();
+    let (sender, mut executor) = setup(externs.clone(), Some(0));
+    let (first, first_ret) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+    let (second, second_ret) = draw(&mut executor, Some(&sender), false, EPOCH - 1, b"a");
+    assert_eq!(first, second);
+    assert_eq!(
+        first_ret.msg_receipt.gas_used,
+        second_ret.msg_receipt.gas_used
+    );
+    assert_eq!(externs.calls(false, EPOCH - 1), 2);
+}
@@ -0,0 +1,267 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Messages (and internal sends) where the sender is also the receiver.
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::Machine;
+use fvm::state_tree::ActorState;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::tuple::*;
+use fvm_ipld_encoding::{IPLD_RAW, RawBytes, from_slice};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, METHOD_SEND, MethodNum};
+use fvm_test_actors::wasm_bin::{SEND_ACTOR_BINARY, STATE_ROOT_ACTOR_BINARY};
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+const SEND_ACTOR_ID: ActorID = 10000;
+const STATE_ROOT_ACTOR_ID: ActorID = 10001;
+
+const SENDER_BALANCE: u64 = 1_000_000_000_000_000_000;
+const ACTOR_BALANCE: u64 = 1000;
+const GAS_LIMIT: u64 = 10_000_000;
+
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct SendParams {
+    dest: Address,
+    method: MethodNum,
+    value: TokenAmount,
+}
+
+fn setup() -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+    // Make sure the sender can pay for gas at a non-zero fee cap.
+    tester
+        .state_tree
+        .as_mut()
+        .unwrap()
+        .mutate_actor(sender.0, |actor| {
+            actor.balance = TokenAmount::from_atto(SENDER_BALANCE);
+            Ok(())
+        })
+        .unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    for (bin, id) in [
+        (SEND_ACTOR_BINARY, SEND_ACTOR_ID),
+        (STATE_ROOT_ACTOR_BINARY, STATE_ROOT_ACTOR_ID),
+    ] {
+        tester
+            .set_actor_from_bin(
+                bin,
+                state_cid,
+                Address::new_id(id),
+                TokenAmount::from_atto(ACTOR_BALANCE),
+            )
+            .unwrap();
+    }
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn actor(executor: &BasicExecutor, id: ActorID) -> ActorState {
+    executor.state_tree().get_actor(id).unwrap().unwrap()
+}
+
+/// A message paying for gas at a fee cap above the base fee. There's no premium, as the tester has
+/// no reward actor to receive tips.
+fn message(from: Address, to: Address, sequence: u64, value: u64) -> Message {
+    Message {
+        from,
+        to,
+        sequence,
+        value: TokenAmount::from_atto(value),
+        gas_limit: GAS_LIMIT,
+        gas_fee_cap: TokenAmount::from_atto(200),
+        ..Message::default()
+    }
+}
+
+/// Returns the amount actually paid for gas (i.e., the gas held minus the refund).
+fn gas_paid(res: &ApplyRet) -> TokenAmount {
+    &res.base_fee_burn + &res.over_estimation_burn + &res.miner_tip
+}
+
+#[test]
+fn value_self_send() {
+    let (sender, mut executor) = setup();
+
+    // Self-sends by ID address and by key address are no-op transfers, but otherwise behave like
+    // any other message: the nonce is bumped, and the sender pays for gas.
+    let to_addrs = [Address::new_id(sender.0), sender.1];
+    for (sequence, to) in (0..).zip(to_addrs) {
+        let before = actor(&executor, sender.0);
+        let res = executor
+            .execute_message(
+                message(sender.1, to, sequence, 100),
+                ApplyKind::Explicit,
+                100,
+            )
+            .unwrap();
+        assert_eq!(res.msg_receipt.exit_code, ExitCode::OK, "{to}");
+
+        let after = actor(&executor, sender.0);
+        assert_eq!(after.sequence, before.sequence + 1);
+        assert!(gas_paid(&res).is_positive());
+        assert_eq!(after.balance, &before.balance - gas_paid(&res));
+        assert_eq!(
+            &gas_paid(&res) + &res.refund,
+            TokenAmount::from_atto(200) * GAS_LIMIT
+        );
+        assert_eq!(after.state, before.state);
+    }
+
+    // The sender must still be able to afford the value once gas is held, even though the value
+    // would be sent back to itself. The message fails, but the nonce is bumped and gas is paid.
+    let before = actor(&executor, sender.0);
+    let mut msg = message(sender.1, sender.1, 2, 0);
+    msg.value = before.balance.clone();
+    let res = executor
+        .execute_message(msg, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_INSUFFICIENT_FUNDS);
+    let after = actor(&executor, sender.0);
+    assert_eq!(after.sequence, before.sequence + 1);
+    assert_eq!(after.balance, &before.balance - gas_paid(&res));
+}
+
+#[test]
+fn state_mutating_self_send() {
+    let (_, mut executor) = setup();
+
+    // Only accounts can send explicit messages, so an actor can only invoke one of its own
+    // methods at the top level through an implicit message.
+    let before = actor(&executor, STATE_ROOT_ACTOR_ID);
+    let mut msg = message(
+        Address::new_id(STATE_ROOT_ACTOR_ID),
+        Address::new_id(STATE_ROOT_ACTOR_ID),
+        before.sequence,
+        ACTOR_BALANCE,
+    );
+    msg.method_num = 2;
+    let res = executor
+        .execute_message(msg, ApplyKind::Implicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    // The state is updated, and nothing else: implicit messages neither bump the nonce nor pay
+    // for gas, and the value is sent back to the actor.
+    let root: Cid = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(
+        root,
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"third"))
+    );
+    let after = actor(&executor, STATE_ROOT_ACTOR_ID);
+    assert_eq!(after.state, root);
+    assert_eq!(after.sequence, before.sequence);
+    assert_eq!(after.balance, before.balance);
+}
+
+#[test]
+fn nested_self_send() {
+    let (sender, mut executor) = setup();
+    let self_send = |value| {
+        RawBytes::serialize(SendParams {
+            dest: Address::new_id(SEND_ACTOR_ID),
+            method: METHOD_SEND,
+            value: TokenAmount::from_atto(value),
+        })
+        .unwrap()
+    };
+
+    // An actor sends its whole balance to itself while handling a message from an account.
+    let before = actor(&executor, sender.0);
+    let mut msg = message(sender.1, Address::new_id(SEND_ACTOR_ID), 0, 10);
+    msg.method_num = 2;
+    msg.params = self_send(ACTOR_BALANCE + 10);
+    let res = executor
+        .execute_message(msg, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let exit_code: ExitCode = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(exit_code, ExitCode::OK);
+
+    let after = actor(&executor, sender.0);
+    assert_eq!(after.sequence, before.sequence + 1);
+    assert_eq!(
+        after.balance,
+        &before.balance - gas_paid(&res) - TokenAmount::from_atto(10)
+    );
+    let send_actor = actor(&executor, SEND_ACTOR_ID);
+    assert_eq!(
+        send_actor.balance,
+        TokenAmount::from_atto(ACTOR_BALANCE + 10)
+    );
+
+    // And the same, from within a message the actor sent to itself.
+    let before = actor(&executor, SEND_ACTOR_ID);
+    let mut msg = message(
+        Address::new_id(SEND_ACTOR_ID),
+        Address::new_id(SEND_ACTOR_ID),
+        before.sequence,
+        ACTOR_BALANCE,
+    );
+    msg.method_num = 2;
+    msg.params = self_send(ACTOR_BALANCE + 10);
+    let res = executor
+        .execute_message(msg, ApplyKind::Implicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let exit_code: ExitCode = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(exit_code, ExitCode::OK);
+    let after = actor(&executor, SEND_ACTOR_ID);
+    assert_eq!(after.sequence, before.sequence);
+    assert_eq!(after.balance, before.balance);
+}
+
+#[test]
+fn self_send_without_gas_funds() {
+    let (sender, mut executor) = setup();
+
+    // The sender can't cover the gas limit at this fee cap, so the message is rejected before
+    // execution, leaving the sender untouched.
+    let before = actor(&executor, sender.0);
+    let mut msg = message(sender.1, sender.1, 0, 0);
+    msg.gas_fee_cap = TokenAmount::from_atto(SENDER_BALANCE / GAS_LIMIT + 1);
+    let res = executor
+        .execute_message(msg, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::SYS_SENDER_STATE_INVALID
+    );
+    assert_eq!(res.msg_receipt.gas_used, 0);
+    assert_eq!(actor(&executor, sender.0), before);
+}
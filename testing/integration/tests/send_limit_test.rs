@@ -0,0 +1,154 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! From nv29, a message may only make a bounded number of sends. Its trace is bounded when
+//! tracing.
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::{MachineContext, NetworkConfig};
+#[cfg(feature = "nv29-dev")]
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+#[cfg(feature = "nv29-dev")]
+use fvm_ipld_encoding::from_slice;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::FANOUT_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+/// Invokes the fan-out actor, which sends to its caller until it hits the send limit, under the
+/// given network version and configuration.
+fn invoke_fan_out(
+    nv: NetworkVersion,
+    configure_nc: impl FnOnce(&mut NetworkConfig),
+    configure_mc: impl FnOnce(&mut MachineContext),
+) -> ApplyRet {
+    let mut tester = new_tester(nv, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            FANOUT_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(DummyExterns, configure_nc, configure_mc)
+        .unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+/// Invokes the fan-out actor at nv29, where sends are limited.
+#[cfg(feature = "nv29-dev")]
+fn fan_out(
+    configure_nc: impl FnOnce(&mut NetworkConfig),
+    configure_mc: impl FnOnce(&mut MachineContext),
+) -> ApplyRet {
+    let res = invoke_fan_out(NetworkVersion::V29, configure_nc, configure_mc);
+
+    // The actor handles the failed sends.
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    res
+}
+
+/// Returns the number of sends the fan-out actor made.
+#[cfg(feature = "nv29-dev")]
+fn sends(res: &ApplyRet) -> u64 {
+    from_slice(res.msg_receipt.return_data.bytes()).unwrap()
+}
+
+#[test]
+fn sends_not_limited_before_nv29() {
+    // The actor never hits the limit, so it sends until it runs out of gas.
+    let res = invoke_fan_out(
+        NetworkVersion::V21,
+        |nc| {
+            nc.set_max_sends_per_message(10);
+        },
+        |_| (),
+    );
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_OUT_OF_GAS);
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn sends_are_limited() {
+    let res = fan_out(
+        |nc| {
+            nc.set_max_sends_per_message(10);
+        },
+        |_| (),
+    );
+    assert_eq!(sends(&res), 10);
+
+    let res = fan_out(
+        |nc| {
+            nc.set_max_sends_per_message(1);
+        },
+        |_| (),
+    );
+    assert_eq!(sends(&res), 1);
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn trace_is_truncated() {
+    let configure_nc = |nc: &mut NetworkConfig| {
+        nc.set_max_sends_per_message(10);
+    };
+
+    let full = fan_out(configure_nc, |mc| {
+        mc.enable_tracing();
+    });
+    assert!(
+        !full
+            .exec_trace
+            .iter()
+            .any(|event| matches!(event, ExecutionEvent::TraceTruncated { .. }))
+    );
+
+    const MAX_EVENTS: usize = 20;
+    assert!(full.exec_trace.len() > MAX_EVENTS);
+    let truncated = fan_out(configure_nc, |mc| {
+        mc.enable_tracing().set_max_trace_events(MAX_EVENTS);
+    });
+
+    // The trace keeps its first events, then counts the rest.
+    assert_eq!(truncated.exec_trace.len(), MAX_EVENTS + 1);
+    let expected = (full.exec_trace.len() - MAX_EVENTS) as u64;
+    assert!(
+        matches!(
+            truncated.exec_trace[MAX_EVENTS],
+            ExecutionEvent::TraceTruncated { dropped } if dropped == expected
+        ),
+        "{:?}",
+        truncated.exec_trace[MAX_EVENTS]
+    );
+}
@@ -0,0 +1,142 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Signed messages are verified against the key of their sending account actor.
+use bls_signatures::Serialize as _;
+use bundles::*;
+use cid::Cid;
+use fvm::machine::{Machine, SignatureError, verify_signed_message};
+use fvm::system_actor::SYSTEM_ACTOR_ID;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::keygen::DeterministicKeyGen;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_shared::address::Address;
+use fvm_shared::crypto::signature::{SECP_SIG_LEN, Signature};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::{Message, SignedMessage};
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+const SEED: u64 = 7;
+
+/// Returns a secp256k1 account and a BLS account, created with the first two keys of
+/// `DeterministicKeyGen::new(SEED)`.
+fn setup() -> (Account, Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap()
+    .with_seed(SEED);
+
+    let [secp] = tester.create_accounts().unwrap();
+    let [bls] = tester.create_bls_accounts().unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (secp, bls, tester.executor.unwrap())
+}
+
+fn message(from: Address) -> Message {
+    Message {
+        from,
+        to: Address::new_id(SYSTEM_ACTOR_ID),
+        value: TokenAmount::from_atto(100),
+        gas_limit: 1_000_000,
+        ..Message::default()
+    }
+}
+
+fn message_cid_bytes(message: &Message) -> Vec<u8> {
+    Cid::new_v1(DAG_CBOR, Code::Blake2b256.digest(&to_vec(message).unwrap())).to_bytes()
+}
+
+/// Signs the message with the secp256k1 key at the given index.
+fn sign_secp(index: u64, message: Message) -> SignedMessage {
+    let sk = k256::ecdsa::SigningKey::from(DeterministicKeyGen::new(SEED).secp256k1_key(index));
+    let hash = Code::Blake2b256.digest(&message_cid_bytes(&message));
+    let (sig, recovery_id) = sk.sign_prehash_recoverable(hash.digest()).unwrap();
+
+    let mut signature = [0u8; SECP_SIG_LEN];
+    signature[..64].copy_from_slice(&sig.to_bytes());
+    signature[64] = recovery_id.to_byte();
+    SignedMessage {
+        message,
+        signature: Signature::new_secp256k1(signature.to_vec()),
+    }
+}
+
+/// Signs the message with the BLS key at the given index.
+fn sign_bls(index: u64, message: Message) -> SignedMessage {
+    let sk = DeterministicKeyGen::new(SEED).bls_key(index);
+    let signature = sk.sign(message_cid_bytes(&message));
+    SignedMessage {
+        message,
+        signature: Signature::new_bls(signature.as_bytes()),
+    }
+}
+
+fn verify(executor: &BasicExecutor, msg: &SignedMessage) -> Result<(), SignatureError> {
+    verify_signed_message(executor.state_tree(), executor.builtin_actors(), msg)
+}
+
+#[test]
+fn valid_signatures() {
+    let (secp, bls, executor) = setup();
+
+    // Senders may be given by ID or by key address.
+    verify(&executor, &sign_secp(0, message(secp.1))).unwrap();
+    verify(&executor, &sign_secp(0, message(Address::new_id(secp.0)))).unwrap();
+    verify(&executor, &sign_bls(1, message(bls.1))).unwrap();
+    verify(&executor, &sign_bls(1, message(Address::new_id(bls.0)))).unwrap();
+}
+
+#[test]
+fn invalid_signatures() {
+    let (secp, bls, executor) = setup();
+
+    // Tampered messages.
+    for mut msg in [sign_secp(0, message(secp.1)), sign_bls(1, message(bls.1))] {
+        msg.message.value = TokenAmount::from_atto(1000);
+        assert!(matches!(
+            verify(&executor, &msg),
+            Err(SignatureError::InvalidSignature(..))
+        ));
+    }
+
+    // Signed with the wrong key, of the right or the wrong type.
+    for msg in [
+        sign_secp(2, message(secp.1)),
+        sign_bls(0, message(secp.1)),
+        sign_bls(2, message(bls.1)),
+        sign_secp(1, message(bls.1)),
+    ] {
+        assert!(matches!(
+            verify(&executor, &msg),
+            Err(SignatureError::InvalidSignature(..))
+        ));
+    }
+}
+
+#[test]
+fn invalid_senders() {
+    let (_, _, executor) = setup();
+
+    // Nobody has this key.
+    let unknown = DeterministicKeyGen::new(SEED).secp256k1_address(2);
+    assert!(matches!(
+        verify(&executor, &sign_secp(2, message(unknown))),
+        Err(SignatureError::UnknownSender(addr)) if addr == unknown
+    ));
+
+    // The system actor has no key.
+    let system = Address::new_id(SYSTEM_ACTOR_ID);
+    assert!(matches!(
+        verify(&executor, &sign_secp(0, message(system))),
+        Err(SignatureError::NotAnAccount(addr)) if addr == system
+    ));
+}
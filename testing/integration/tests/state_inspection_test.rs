@@ -0,0 +1,109 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#![cfg(feature = "nv29-dev")]
+use bundles::*;
+use fvm::account_actor;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::STATE_INSPECTION_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+const ACTOR_ID: ActorID = 10000;
+
+/// The actor method reading another actor's state.
+const READ_STATE: MethodNum = 2;
+/// The actor method asserting that reading another actor's state is forbidden.
+const EXPECT_FORBIDDEN: MethodNum = 3;
+
+fn setup(enable_inspection: bool) -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V29,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            STATE_INSPECTION_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                if enable_inspection {
+                    nc.enable_actor_state_inspection();
+                }
+            },
+            |_| (),
+        )
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+fn inspect(
+    sender: &Account,
+    executor: &mut BasicExecutor,
+    method_num: MethodNum,
+    target: ActorID,
+) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num,
+        params: RawBytes::serialize(target).unwrap(),
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    res
+}
+
+#[test]
+fn read_account_state() {
+    let (sender, mut executor) = setup(true);
+
+    // The actor reads the sender's (account actor) state without calling it.
+    let res = inspect(&sender, &mut executor, READ_STATE, sender.0);
+    let state: account_actor::State = res.msg_receipt.return_data.deserialize().unwrap();
+    assert_eq!(state.address, sender.1);
+}
+
+#[test]
+fn inspection_forbidden() {
+    // Only builtin actors may be inspected.
+    let (sender, mut executor) = setup(true);
+    inspect(&sender, &mut executor, EXPECT_FORBIDDEN, ACTOR_ID);
+
+    // And only if the network allows it.
+    let (sender, mut executor) = setup(false);
+    inspect(&sender, &mut executor, EXPECT_FORBIDDEN, sender.0);
+}
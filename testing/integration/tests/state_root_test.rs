@@ -0,0 +1,190 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use cid::Cid;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::gas::price_list_by_network_version;
+use fvm::machine::Machine;
+use fvm::trace::{ExecutionEvent, root_history};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{IPLD_RAW, from_slice};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::STATE_ROOT_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+const NV: NetworkVersion = NetworkVersion::V21;
+const ACTOR_ID: ActorID = 10000;
+
+/// Sets up the state root actor, returning the sender, the executor, and the actor's initial
+/// state root.
+fn setup() -> (Account, BasicExecutor, Cid) {
+    let mut tester = new_tester(NV, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            STATE_ROOT_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (sender, tester.executor.unwrap(), state_cid)
+}
+
+fn call(sender: &Account, executor: &mut BasicExecutor, method_num: MethodNum) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1000000000,
+        method_num,
+        ..Message::default()
+    };
+    executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn state_root(executor: &BasicExecutor) -> Cid {
+    executor
+        .state_tree()
+        .get_actor(ACTOR_ID)
+        .unwrap()
+        .unwrap()
+        .state
+}
+
+fn raw_cid(data: &[u8]) -> Cid {
+    Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(data))
+}
+
+#[test]
+fn last_set_root_wins() {
+    let (sender, mut executor, _) = setup();
+
+    let res = call(&sender, &mut executor, 2);
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    let root: Cid = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(root, raw_cid(b"third"));
+    assert_eq!(state_root(&executor), root);
+
+    // Every set_root (including the rejected one) pays the same flat fee, with nothing deferred:
+    // storage is charged when the blocks are written.
+    let set_root_charges: Vec<_> = res
+        .exec_trace
+        .iter()
+        .filter_map(|event| match event {
+            ExecutionEvent::GasCharge(charge) if charge.name == "OnActorSetRoot" => Some(charge),
+            _ => None,
+        })
+        .collect();
+    let expected = price_list_by_network_version(NV).on_set_root();
+    assert_eq!(set_root_charges.len(), 4);
+    for charge in set_root_charges {
+        assert_eq!(charge.compute_gas, expected.compute_gas);
+        assert!(charge.other_gas.is_zero());
+    }
+    let link_charges = res
+        .exec_trace
+        .iter()
+        .filter(|event| {
+            matches!(event, ExecutionEvent::GasCharge(charge)
+                if charge.name == "OnBlockLink" && !charge.other_gas.is_zero())
+        })
+        .count();
+    assert_eq!(link_charges, 3);
+}
+
+#[test]
+fn abort_reverts_set_root() {
+    let (sender, mut executor, initial_root) = setup();
+
+    let res = call(&sender, &mut executor, 3);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(42));
+    assert_eq!(state_root(&executor), initial_root);
+}
+
+#[test]
+fn nested_call_observes_latest_root() {
+    let (sender, mut executor, _) = setup();
+
+    // The nested call observes the outer call's root, and the outer call observes the nested
+    // call's root after it returns.
+    let res = call(&sender, &mut executor, 4);
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    let root: Cid = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(root, raw_cid(b"nested"));
+    assert_eq!(state_root(&executor), root);
+}
+
+#[test]
+fn root_history_breadcrumbs() {
+    let (sender, mut executor, _) = setup();
+
+    // Sets "outer", then "nested" in a successful nested call, then "reverted" in an aborted one.
+    let res = call(&sender, &mut executor, 6);
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    assert_eq!(state_root(&executor), raw_cid(b"nested"));
+
+    // The message, and the two nested calls.
+    let calls: Vec<_> = res
+        .exec_trace
+        .iter()
+        .enumerate()
+        .filter_map(|(i, event)| match event {
+            ExecutionEvent::Call { method, .. } => Some((i, *method)),
+            _ => None,
+        })
+        .collect();
+    let [(top, 6), (first, 5), (second, 7)] = calls[..] else {
+        panic!("unexpected calls: {calls:?}");
+    };
+
+    let history = root_history(&res.exec_trace);
+    assert_eq!(history.keys().collect::<Vec<_>>(), [&ACTOR_ID]);
+    let breadcrumbs: Vec<_> = history[&ACTOR_ID]
+        .iter()
+        .map(|update| {
+            assert!(matches!(
+                &res.exec_trace[update.index],
+                ExecutionEvent::SetRoot { id: ACTOR_ID, root, .. } if *root == update.root
+            ));
+            (update.root, update.call_path.clone(), update.reverted)
+        })
+        .collect();
+    assert_eq!(
+        breadcrumbs,
+        [
+            (raw_cid(b"outer"), vec![top], false),
+            (raw_cid(b"nested"), vec![top, first], false),
+            (raw_cid(b"reverted"), vec![top, second], true),
+        ]
+    );
+}
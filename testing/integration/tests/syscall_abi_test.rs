@@ -0,0 +1,282 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! From nv29, actors are linked against the syscalls of the ABI version they declare. Before, the
+//! declaration is ignored and all actors target version 1.
+use bundles::*;
+use cid::Cid;
+use cid::multihash::Multihash;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::IPLD_RAW;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::sys::SYSCALL_ABI_SECTION;
+use fvm_shared::version::NetworkVersion;
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+/// The actor exits with `ERROR_BASE + errno` if hashing fails...
+const ERROR_BASE: u32 = 1000;
+/// ...and with `LENGTH_BASE + len` (the digest length) if it succeeds.
+const LENGTH_BASE: u32 = 2000;
+
+/// An actor that hashes some data with blake2b-256 into a `digest_len` byte buffer.
+fn hash_wat(digest_len: u32) -> String {
+    format!(
+        r#"(module
+             (import "vm" "exit" (func $exit (param i32 i32 i32 i32) (result i32)))
+             (import "crypto" "hash"
+               (func $hash (param i32 i64 i32 i32 i32 i32) (result i32)))
+             (memory (export "memory") 1)
+             (data (i32.const 16) "some data")
+             (func (export "invoke") (param $x i32) (result i32)
+               (local $errno i32)
+               (local.set $errno
+                 (call $hash (i32.const 0) (i64.const {code}) (i32.const 16) (i32.const 9)
+                   (i32.const 64) (i32.const {digest_len})))
+               (if (local.get $errno)
+                 (then (drop (call $exit (i32.add (i32.const {ERROR_BASE}) (local.get $errno))
+                         (i32.const 0) (i32.const 0) (i32.const 0)))))
+               (drop (call $exit (i32.add (i32.const {LENGTH_BASE}) (i32.load (i32.const 0)))
+                 (i32.const 0) (i32.const 0) (i32.const 0)))
+               (i32.const 0)))"#,
+        code = u64::from(Code::Blake2b256),
+    )
+}
+
+/// Builds the hashing actor, appending a syscall ABI custom section for each of the given
+/// payloads.
+fn hash_actor(digest_len: u32, abi_payloads: &[&[u8]]) -> Vec<u8> {
+    declare_abi(&hash_wat(digest_len), abi_payloads)
+}
+
+/// Builds an actor from WAT, appending a syscall ABI custom section for each of the given
+/// payloads.
+fn declare_abi(wat: &str, abi_payloads: &[&[u8]]) -> Vec<u8> {
+    let mut wasm = wat::parse_str(wat).unwrap();
+    let name = SYSCALL_ABI_SECTION.as_bytes();
+    for payload in abi_payloads {
+        wasm.push(0);
+        wasm.push((1 + name.len() + payload.len()) as u8);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name);
+        wasm.extend_from_slice(payload);
+    }
+    wasm
+}
+
+/// An actor importing `self::set_root`, `actor::create_actor`, and `actor::upgrade_actor` with the
+/// signatures of the given syscall ABI version (which CID syscalls take the CID's length from
+/// version 2). It sets the passed CID as its state root, and exits with `ERROR_BASE + errno`.
+fn set_root_actor(signatures: u32, cid: &[u8], abi_payloads: &[&[u8]]) -> Vec<u8> {
+    let (set_root, create_actor, upgrade_actor, args) = match signatures {
+        1 => (
+            "i32",
+            "i64 i32 i32 i32",
+            "i32 i32 i32",
+            "(i32.const 16)".to_owned(),
+        ),
+        _ => (
+            "i32 i32",
+            "i64 i32 i32 i32 i32",
+            "i32 i32 i32 i32",
+            format!("(i32.const 16) (i32.const {})", cid.len()),
+        ),
+    };
+    let data: String = cid.iter().map(|b| format!("\\{b:02x}")).collect();
+    let wat = format!(
+        r#"(module
+             (import "vm" "exit" (func $exit (param i32 i32 i32 i32) (result i32)))
+             (import "self" "set_root" (func $set_root (param {set_root}) (result i32)))
+             (import "actor" "create_actor" (func (param {create_actor}) (result i32)))
+             (import "actor" "upgrade_actor" (func (param {upgrade_actor}) (result i32)))
+             (memory (export "memory") 1)
+             (data (i32.const 16) "{data}")
+             (func (export "invoke") (param $x i32) (result i32)
+               (drop (call $exit (i32.add (i32.const {ERROR_BASE}) (call $set_root {args}))
+                 (i32.const 0) (i32.const 0) (i32.const 0)))
+               (i32.const 0)))"#
+    );
+    declare_abi(&wat, abi_payloads)
+}
+
+fn invoke(nv: NetworkVersion, wasm_bin: &[u8]) -> ApplyRet {
+    let mut tester = new_tester(nv, StateTreeVersion::V5, MemoryBlockstore::default()).unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 1_000_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+fn exit_code(nv: NetworkVersion, wasm_bin: &[u8]) -> ExitCode {
+    invoke(nv, wasm_bin).msg_receipt.exit_code
+}
+
+fn cids() -> (Vec<u8>, Vec<u8>) {
+    let v0 = Cid::new_v0(Multihash::wrap(0x12, &[1; 32]).unwrap())
+        .unwrap()
+        .to_bytes();
+    let v1 = Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"root")).to_bytes();
+    (v0, v1)
+}
+
+fn errno(e: ErrorNumber) -> ExitCode {
+    ExitCode::new(ERROR_BASE + e as u32)
+}
+
+#[test]
+fn declarations_ignored_before_nv29() {
+    let nv = NetworkVersion::V21;
+
+    // Every actor targets version 1, whatever it declares.
+    let declarations: [&[&[u8]]; 6] = [&[], &[&[1]], &[&[2]], &[&[3]], &[&[2, 0]], &[&[2], &[2]]];
+    for payloads in declarations {
+        assert_eq!(
+            exit_code(nv, &hash_actor(4, payloads)),
+            ExitCode::new(LENGTH_BASE + 4),
+            "{payloads:?}"
+        );
+    }
+
+    let (v0, v1) = cids();
+    assert_eq!(
+        exit_code(nv, &set_root_actor(1, &v0, &[&[2]])),
+        errno(ErrorNumber::NotFound)
+    );
+    assert_eq!(
+        exit_code(nv, &set_root_actor(2, &v1, &[&[2]])),
+        ExitCode::SYS_ASSERTION_FAILED
+    );
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn v1_truncates_digest() {
+    let nv = NetworkVersion::V29;
+    // Actors without a declaration target version 1.
+    assert_eq!(
+        exit_code(nv, &hash_actor(4, &[])),
+        ExitCode::new(LENGTH_BASE + 4)
+    );
+    assert_eq!(
+        exit_code(nv, &hash_actor(4, &[&[1]])),
+        ExitCode::new(LENGTH_BASE + 4)
+    );
+    assert_eq!(
+        exit_code(nv, &hash_actor(64, &[&[1]])),
+        ExitCode::new(LENGTH_BASE + 32)
+    );
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn v2_rejects_short_buffers() {
+    let nv = NetworkVersion::V29;
+    assert_eq!(
+        exit_code(nv, &hash_actor(4, &[&[2]])),
+        errno(ErrorNumber::BufferTooSmall)
+    );
+    assert_eq!(
+        exit_code(nv, &hash_actor(32, &[&[2]])),
+        ExitCode::new(LENGTH_BASE + 32)
+    );
+    assert_eq!(
+        exit_code(nv, &hash_actor(64, &[&[2]])),
+        ExitCode::new(LENGTH_BASE + 32)
+    );
+}
+
+/// Asserts that the actor fails to instantiate with `SYS_UNSUPPORTED_ABI` and the given message.
+#[cfg(feature = "nv29-dev")]
+fn assert_unsupported(abi_payloads: &[&[u8]], message: &str) {
+    let res = invoke(NetworkVersion::V29, &hash_actor(32, abi_payloads));
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::SYS_UNSUPPORTED_ABI);
+    let failure = res.failure_info.expect("expected failure info").to_string();
+    assert!(failure.contains(message), "{failure}");
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn unsupported_versions() {
+    assert_unsupported(&[&[0]], "syscall ABI version 0");
+    assert_unsupported(&[&[3]], "syscall ABI version 3");
+    assert_unsupported(
+        &[&[0xff, 0xff, 0xff, 0xff, 0x0f]],
+        "syscall ABI version 4294967295",
+    );
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn malformed_declarations() {
+    const INVALID: &str = "invalid syscall ABI declaration";
+    assert_unsupported(&[&[]], INVALID);
+    assert_unsupported(&[&[2, 0]], INVALID);
+    assert_unsupported(&[&[0x80]], INVALID);
+    assert_unsupported(&[&[2], &[2]], INVALID);
+}
+
+#[test]
+#[cfg(feature = "nv29-dev")]
+fn cid_syscall_signatures() {
+    let nv = NetworkVersion::V29;
+    let (v0, v1) = cids();
+
+    // Actors without a declaration, or declaring version 1, link against the original signatures,
+    // which accept any CID (neither is reachable).
+    assert_eq!(
+        exit_code(nv, &set_root_actor(1, &v0, &[])),
+        errno(ErrorNumber::NotFound)
+    );
+    assert_eq!(
+        exit_code(nv, &set_root_actor(1, &v1, &[])),
+        errno(ErrorNumber::NotFound)
+    );
+    assert_eq!(
+        exit_code(nv, &set_root_actor(1, &v0, &[&[1]])),
+        errno(ErrorNumber::NotFound)
+    );
+
+    // From version 2, they take the CID's length, and only accept allowed CIDs.
+    assert_eq!(
+        exit_code(nv, &set_root_actor(2, &v1, &[&[2]])),
+        errno(ErrorNumber::NotFound)
+    );
+    assert_eq!(
+        exit_code(nv, &set_root_actor(2, &v0, &[&[2]])),
+        errno(ErrorNumber::IllegalCid)
+    );
+
+    // Actors importing the signatures of another version fail to link.
+    assert_eq!(
+        exit_code(nv, &set_root_actor(2, &v1, &[])),
+        ExitCode::SYS_ASSERTION_FAILED
+    );
+    assert_eq!(
+        exit_code(nv, &set_root_actor(1, &v1, &[&[2]])),
+        ExitCode::SYS_ASSERTION_FAILED
+    );
+}
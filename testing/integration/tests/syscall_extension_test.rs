@@ -0,0 +1,223 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Embedders can register their own syscall modules, which actors import like built-in syscalls.
+use bundles::*;
+use fvm::call_manager::DefaultCallManager;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::{DefaultMachine, NetworkConfig};
+use fvm_integration_tests::custom_kernel::DefaultCustomKernel;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+use fvm_shared::chainid::ChainID;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use multihash_codetable::{Code, MultihashDigest};
+use num_traits::Zero;
+
+mod bundles;
+
+type TestKernel =
+    DefaultCustomKernel<DefaultCallManager<DefaultMachine<MemoryBlockstore, DummyExterns>>>;
+
+/// An example extension, verifying commitments posted by a bridged chain.
+mod bridge {
+    use fvm::kernel::{CryptoOps, Result};
+    use fvm::syscalls::{Context, SyscallBinder};
+    use multihash_codetable::Code;
+
+    use super::TestKernel;
+
+    pub const NAMESPACE: &str = "bridge";
+
+    pub fn bind(binder: &mut SyscallBinder<'_, TestKernel>) {
+        binder.bind("verify_commitment", verify_commitment);
+    }
+
+    /// Returns 1 if the 32 bytes at `digest_off` are the blake2b-256 digest of the data, 0
+    /// otherwise. Only the kernel's crypto operations are needed.
+    pub fn verify_commitment(
+        context: Context<'_, impl CryptoOps>,
+        data_off: u32,
+        data_len: u32,
+        digest_off: u32,
+    ) -> Result<u32> {
+        let digest = context.memory.try_slice(digest_off, 32)?;
+        let data = context.memory.try_slice(data_off, data_len)?;
+        let hash = context.kernel.hash(Code::Blake2b256.into(), data)?;
+        Ok((hash.digest() == digest) as u32)
+    }
+}
+
+const DATA: &[u8] = b"bridged block header";
+/// Where the actor stores the digest it checks.
+const DIGEST_OFFSET: u32 = 64;
+/// The exit code of the actor if the commitment doesn't verify.
+const EXIT_UNVERIFIED: u32 = 18;
+/// The exit code of the actor if the syscall fails.
+const EXIT_SYSCALL_FAILED: u32 = 17;
+
+/// An actor that checks `digest` (stored at `digest_off`, or out of bounds) against `DATA` with the
+/// bridge syscall.
+fn bridge_wat(digest: &[u8], digest_off: u32) -> String {
+    let escape = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("\\{b:02x}"))
+            .collect::<String>()
+    };
+    format!(
+        r#"(module
+             (import "bridge" "verify_commitment" (func $verify (param i32 i32 i32 i32) (result i32)))
+             (import "vm" "exit" (func $exit (param i32 i32 i32 i32) (result i32)))
+             (memory (export "memory") 1)
+             (data (i32.const 0) "{data}")
+             (data (i32.const {DIGEST_OFFSET}) "{digest}")
+             (func (export "invoke") (param $x i32) (result i32)
+               (if (call $verify (i32.const 1024) (i32.const 0) (i32.const {data_len}) (i32.const {digest_off}))
+                 (then (drop (call $exit (i32.const {EXIT_SYSCALL_FAILED}) (i32.const 0) (i32.const 0) (i32.const 0)))))
+               (if (i32.eqz (i32.load (i32.const 1024)))
+                 (then (drop (call $exit (i32.const {EXIT_UNVERIFIED}) (i32.const 0) (i32.const 0) (i32.const 0)))))
+               (i32.const 0)))"#,
+        data = escape(DATA),
+        digest = escape(digest),
+        data_len = DATA.len(),
+    )
+}
+
+/// Runs the bridge actor in a machine with the bridge extension.
+fn run_bridge_actor(digest: &[u8], digest_off: u32) -> ApplyRet {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let wasm_bin = wat::parse_str(bridge_wat(digest, digest_off)).unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(&wasm_bin, state_cid, actor_address, TokenAmount::zero())
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                nc.with_syscall_module(bridge::NAMESPACE, bridge::bind);
+            },
+            |_| (),
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender.1,
+        to: actor_address,
+        gas_limit: 10_000_000,
+        method_num: 1,
+        ..Message::default()
+    };
+    tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap()
+}
+
+#[test]
+fn extension_syscall_called_from_actor() {
+    let digest = Code::Blake2b256.digest(DATA);
+    let res = run_bridge_actor(digest.digest(), DIGEST_OFFSET);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+
+    let res = run_bridge_actor(&[0; 32], DIGEST_OFFSET);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(EXIT_UNVERIFIED));
+}
+
+#[test]
+fn extension_syscall_errors_returned_to_actor() {
+    // The digest is out of bounds, so the syscall fails like a built-in syscall would.
+    let digest = Code::Blake2b256.digest(DATA);
+    let res = run_bridge_actor(digest.digest(), 1 << 16);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::new(EXIT_SYSCALL_FAILED)
+    );
+}
+
+fn bridge_config(nc: &mut NetworkConfig) -> &mut NetworkConfig {
+    nc.with_syscall_module(bridge::NAMESPACE, bridge::bind)
+}
+
+#[test]
+fn extensions_rejected_on_mainnet() {
+    let mut nc = NetworkConfig::mainnet(NetworkVersion::V21);
+    nc.validate().unwrap();
+    let err = bridge_config(&mut nc).validate().unwrap_err();
+    assert_eq!(err.field, "syscall_extensions");
+
+    // Building a mainnet machine fails too.
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let err = tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |nc| {
+                bridge_config(nc.chain_id(ChainID::from(314u64)));
+            },
+            |_| (),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("syscall_extensions"), "{err}");
+}
+
+#[test]
+fn invalid_namespaces_rejected() {
+    for namespace in ["ipld", "crypto", "", "Bridge", "bridge-v2"] {
+        let mut nc = NetworkConfig::new(NetworkVersion::V21);
+        nc.with_syscall_module(namespace, bridge::bind);
+        let err = nc.validate().unwrap_err();
+        assert_eq!(err.field, "syscall_extensions", "{namespace}");
+    }
+
+    // The same namespace can't be registered twice.
+    let mut nc = NetworkConfig::new(NetworkVersion::V21);
+    bridge_config(bridge_config(&mut nc));
+    nc.validate().unwrap_err();
+
+    // Nor can the same syscall be bound twice.
+    let mut nc = NetworkConfig::new(NetworkVersion::V21);
+    nc.with_syscall_module::<TestKernel>("bridge", |binder| {
+        bridge::bind(binder);
+        bridge::bind(binder);
+    });
+    nc.validate().unwrap_err();
+}
+
+#[test]
+fn extensions_golden() {
+    let mut nc = NetworkConfig::new(NetworkVersion::V21);
+    assert!(nc.syscall_extension_names().is_empty());
+    let empty = nc.syscall_extensions_fingerprint();
+
+    bridge_config(&mut nc).validate().unwrap();
+    assert_eq!(nc.syscall_extension_names(), ["bridge::verify_commitment"]);
+    assert_ne!(nc.syscall_extensions_fingerprint(), empty);
+    assert_eq!(
+        nc.syscall_extensions_fingerprint().to_string(),
+        "bafy2bzacedtemjtkcfypl5ea7h6gebf2xo4wueuhaxioiobxo5rqb2ooi6xj4"
+    );
+}
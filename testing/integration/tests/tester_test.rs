@@ -0,0 +1,132 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! End-to-end tests of a Rust test actor, compiled to Wasm and run by the machine through
+//! `Tester::execute_message`.
+use bundles::*;
+use cid::Cid;
+use fvm::executor::ApplyRet;
+use fvm::machine::Machine;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, Tester};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{IPLD_RAW, RawBytes, from_slice};
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::state::{ActorState, StateTreeVersion};
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::STATE_ROOT_ACTOR_BINARY;
+use multihash_codetable::{Code, MultihashDigest};
+
+mod bundles;
+
+const ACTOR_ID: ActorID = 10000;
+
+fn setup() -> (Tester<MemoryBlockstore, DummyExterns>, Account) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            STATE_ROOT_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::from_atto(0),
+        )
+        .unwrap();
+    (tester, sender)
+}
+
+fn actor(tester: &Tester<MemoryBlockstore, DummyExterns>, id: ActorID) -> ActorState {
+    let executor = tester.executor.as_ref().unwrap();
+    executor.state_tree().get_actor(id).unwrap().unwrap()
+}
+
+fn call(
+    tester: &mut Tester<MemoryBlockstore, DummyExterns>,
+    sender: &Account,
+    method: u64,
+    value: u64,
+) -> ApplyRet {
+    tester
+        .execute_message(
+            sender.1,
+            Address::new_id(ACTOR_ID),
+            method,
+            RawBytes::default(),
+            TokenAmount::from_atto(value),
+        )
+        .unwrap()
+}
+
+#[test]
+fn state_transaction_committed() {
+    let (mut tester, sender) = setup();
+    tester.instantiate_machine(DummyExterns).unwrap();
+
+    // Method 2 sets the state root three times. The last root is committed, along with the
+    // transferred value.
+    let res = call(&mut tester, &sender, 2, 100);
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    let root: Cid = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(
+        root,
+        Cid::new_v1(IPLD_RAW, Code::Blake2b256.digest(b"third"))
+    );
+    let state = actor(&tester, ACTOR_ID);
+    assert_eq!(state.state, root);
+    assert_eq!(state.balance, TokenAmount::from_atto(100));
+
+    // The tester picks the sender's next sequence number.
+    assert_eq!(actor(&tester, sender.0).sequence, 1);
+    let res = call(&mut tester, &sender, 2, 0);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::OK);
+    assert_eq!(actor(&tester, sender.0).sequence, 2);
+}
+
+#[test]
+fn abort_reverts_state_transaction() {
+    let (mut tester, sender) = setup();
+    tester.instantiate_machine(DummyExterns).unwrap();
+    let initial = actor(&tester, ACTOR_ID);
+
+    // Method 3 sets the state root twice, then aborts. Neither the root nor the value transfer is
+    // committed.
+    let res = call(&mut tester, &sender, 3, 100);
+    assert_eq!(res.msg_receipt.exit_code, ExitCode::new(42));
+    let info = res.failure_info.expect("expected failure info").to_string();
+    assert!(info.contains("aborting after setting the root"), "{info}");
+    assert_eq!(actor(&tester, ACTOR_ID), initial);
+
+    // The message is still included, so the sender's sequence number is bumped.
+    assert_eq!(actor(&tester, sender.0).sequence, 1);
+}
+
+#[test]
+fn execute_before_instantiation_fails() {
+    let (mut tester, sender) = setup();
+    let err = tester
+        .execute_message(
+            sender.1,
+            Address::new_id(ACTOR_ID),
+            2,
+            RawBytes::default(),
+            TokenAmount::from_atto(0),
+        )
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("hasn't been instantiated"),
+        "{err}"
+    );
+}
@@ -0,0 +1,221 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use bundles::*;
+use fvm::executor::{AwardBlockRewardParams, BlockMessages, ChainMessage, Executor};
+use fvm::machine::{CRON_ACTOR_ID, Machine, REWARD_ACTOR_ID};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::{CborStore, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::{ActorID, MethodNum};
+use fvm_test_actors::wasm_bin::RECORDER_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+const GAS_LIMIT: u64 = 10_000_000;
+const BASE_FEE: u64 = 100;
+
+/// Sets up three accounts (the last of which can't afford any gas), and records the calls to the
+/// reward and cron actors.
+fn setup() -> ([Account; 3], BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+
+    let accounts: [Account; 3] = tester.create_accounts().unwrap();
+    for (id, _) in &accounts[..2] {
+        tester
+            .state_tree
+            .as_mut()
+            .unwrap()
+            .mutate_actor(*id, |actor| {
+                actor.balance = TokenAmount::from_whole(1);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    for id in [REWARD_ACTOR_ID, CRON_ACTOR_ID] {
+        tester
+            .set_actor_from_bin(
+                RECORDER_ACTOR_BINARY,
+                state_cid,
+                Address::new_id(id),
+                TokenAmount::zero(),
+            )
+            .unwrap();
+    }
+
+    tester.instantiate_machine(DummyExterns).unwrap();
+    (accounts, tester.executor.unwrap())
+}
+
+/// A transfer of 1 atto to the receiver.
+fn transfer(from: &Account, to: &Account, sequence: u64) -> ChainMessage {
+    ChainMessage {
+        message: Message {
+            from: from.1,
+            to: to.1,
+            sequence,
+            value: TokenAmount::from_atto(1),
+            gas_limit: GAS_LIMIT,
+            gas_fee_cap: TokenAmount::from_atto(BASE_FEE * 2),
+            gas_premium: TokenAmount::from_atto(10),
+            ..Message::default()
+        },
+        raw_length: 100,
+    }
+}
+
+/// Returns the calls recorded by the recorder actor.
+fn recorded_calls(executor: &BasicExecutor, id: ActorID) -> Vec<(ActorID, MethodNum, RawBytes)> {
+    let root = executor.state_tree().get_actor(id).unwrap().unwrap().state;
+    executor
+        .state_tree()
+        .store()
+        .get_cbor(&root)
+        .unwrap()
+        .unwrap()
+}
+
+fn sequence(executor: &BasicExecutor, account: &Account) -> u64 {
+    executor
+        .state_tree()
+        .get_actor(account.0)
+        .unwrap()
+        .unwrap()
+        .sequence
+}
+
+#[test]
+fn apply_tipset() {
+    let ([a, b, c], mut executor) = setup();
+
+    let miners = [Address::new_id(1000), Address::new_id(1001)];
+    let blocks = [
+        BlockMessages {
+            miner: miners[0],
+            win_count: 1,
+            messages: vec![
+                transfer(&a, &b, 0),
+                transfer(&a, &b, 1),
+                transfer(&b, &a, 0),
+            ],
+        },
+        BlockMessages {
+            miner: miners[1],
+            win_count: 2,
+            messages: vec![
+                // Already applied in the first block.
+                transfer(&a, &b, 1),
+                // Out of order: the nonce gap means this is never applied.
+                transfer(&a, &b, 3),
+                transfer(&a, &b, 2),
+                // Already applied in the first block.
+                transfer(&b, &a, 0),
+                // The sender can't afford the gas, so the miner is penalized.
+                transfer(&c, &a, 0),
+            ],
+        },
+    ];
+    let ret = executor.apply_tipset(&blocks).unwrap();
+
+    // Only the messages with the expected nonces are applied.
+    let applied: Vec<Vec<usize>> = ret
+        .blocks
+        .iter()
+        .map(|block| block.messages.iter().map(|(i, _)| *i).collect())
+        .collect();
+    assert_eq!(applied, [vec![0, 1, 2], vec![2, 4]]);
+    let exit_codes: Vec<_> = ret
+        .receipts()
+        .into_iter()
+        .map(|receipt| receipt.exit_code)
+        .collect();
+    assert_eq!(
+        exit_codes,
+        [
+            ExitCode::OK,
+            ExitCode::OK,
+            ExitCode::OK,
+            ExitCode::OK,
+            ExitCode::SYS_SENDER_STATE_INVALID
+        ]
+    );
+    assert_eq!(sequence(&executor, &a), 3);
+    assert_eq!(sequence(&executor, &b), 1);
+    assert_eq!(sequence(&executor, &c), 0);
+
+    // Each block's rewards and penalties are totalled, and awarded after the block's messages.
+    assert!(ret.blocks[0].penalty.is_zero());
+    assert_eq!(
+        ret.blocks[1].penalty,
+        TokenAmount::from_atto(BASE_FEE * GAS_LIMIT)
+    );
+    for block in &ret.blocks {
+        let tips = block
+            .messages
+            .iter()
+            .fold(TokenAmount::zero(), |acc, (_, ret)| acc + &ret.miner_tip);
+        assert!(tips.is_positive());
+        assert_eq!(block.gas_reward, tips);
+        assert!(block.reward.msg_receipt.exit_code.is_success());
+    }
+
+    let rewards = recorded_calls(&executor, REWARD_ACTOR_ID);
+    assert_eq!(rewards.len(), 2);
+    for (((caller, method, params), block), (miner, win_count)) in rewards
+        .iter()
+        .zip(&ret.blocks)
+        .zip([(miners[0], 1), (miners[1], 2)])
+    {
+        assert_eq!((*caller, *method), (0, 2));
+        let params: AwardBlockRewardParams = params.deserialize().unwrap();
+        assert_eq!(
+            params,
+            AwardBlockRewardParams {
+                miner,
+                penalty: block.penalty.clone(),
+                gas_reward: block.gas_reward.clone(),
+                win_count,
+            }
+        );
+    }
+
+    // Cron runs once, at the end.
+    assert!(ret.cron.msg_receipt.exit_code.is_success());
+    assert_eq!(
+        recorded_calls(&executor, CRON_ACTOR_ID),
+        [(0, 2, RawBytes::default())]
+    );
+
+    // The state was flushed.
+    assert_eq!(Executor::flush(&mut executor).unwrap(), ret.state_root);
+}
+
+#[test]
+fn failed_cron_fails_the_tipset() {
+    let ([a, b, _], mut executor) = setup();
+
+    // Without a cron actor, the cron message fails.
+    executor.state_tree_mut().delete_actor(CRON_ACTOR_ID);
+
+    let blocks = [BlockMessages {
+        miner: Address::new_id(1000),
+        win_count: 1,
+        messages: vec![transfer(&a, &b, 0)],
+    }];
+    let err = executor.apply_tipset(&blocks).unwrap_err();
+    assert!(err.to_string().contains("cron"), "{err:?}");
+}
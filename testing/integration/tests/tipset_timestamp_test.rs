@@ -0,0 +1,129 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Actors observe the tipset timestamp configured by the node, which is also recorded at the start
+//! of the execution trace.
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::trace::ExecutionEvent;
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::Tester;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::from_slice;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::MESSAGE_CONTEXT_ACTOR_BINARY;
+
+mod bundles;
+
+const EPOCH: ChainEpoch = 100;
+const TIMESTAMP: u64 = 1_700_000_000;
+
+fn setup() -> (Tester<MemoryBlockstore, DummyExterns>, Address, Address) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    let actor_address = Address::new_id(10000);
+    tester
+        .set_actor_from_bin(
+            MESSAGE_CONTEXT_ACTOR_BINARY,
+            state_cid,
+            actor_address,
+            TokenAmount::from_atto(0),
+        )
+        .unwrap();
+    (tester, sender.1, actor_address)
+}
+
+/// Runs method 4 of the message context actor, which returns the epoch and timestamp it observes.
+fn run(epoch: ChainEpoch, timestamp: u64) -> ApplyRet {
+    let (mut tester, sender, actor_address) = setup();
+    tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.set_epoch(epoch).set_timestamp(timestamp);
+            },
+        )
+        .unwrap();
+
+    let message = Message {
+        from: sender,
+        to: actor_address,
+        gas_limit: 1000000000,
+        method_num: 4,
+        ..Message::default()
+    };
+    let res = tester
+        .executor
+        .unwrap()
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert!(
+        res.msg_receipt.exit_code.is_success(),
+        "{:?}",
+        res.failure_info
+    );
+    res
+}
+
+#[test]
+fn tipset_timestamp_round_trips() {
+    let res = run(EPOCH, TIMESTAMP);
+    let observed: (ChainEpoch, u64) = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(observed, (EPOCH, TIMESTAMP));
+
+    // Genesis may have a zero timestamp.
+    let res = run(0, 0);
+    let observed: (ChainEpoch, u64) = from_slice(res.msg_receipt.return_data.bytes()).unwrap();
+    assert_eq!(observed, (0, 0));
+}
+
+#[test]
+fn tipset_timestamp_traced() {
+    let res = run(EPOCH, TIMESTAMP);
+    assert!(
+        matches!(
+            res.exec_trace.first(),
+            Some(ExecutionEvent::NetworkContext {
+                epoch: EPOCH,
+                timestamp: TIMESTAMP
+            })
+        ),
+        "{:?}",
+        res.exec_trace.first()
+    );
+    // The context is only recorded once per message.
+    assert_eq!(
+        res.exec_trace
+            .iter()
+            .filter(|event| matches!(event, ExecutionEvent::NetworkContext { .. }))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn zero_timestamp_after_genesis_rejected() {
+    let (mut tester, _, _) = setup();
+    let err = tester
+        .instantiate_machine_with_config(
+            DummyExterns,
+            |_| (),
+            |mc| {
+                mc.set_epoch(EPOCH);
+            },
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("timestamp"), "{err}");
+}
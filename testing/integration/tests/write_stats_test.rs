@@ -0,0 +1,108 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! When tracing, the machine reports the blocks each message writes, and how many of them end up
+//! in the state.
+use bundles::*;
+use fvm::executor::{ApplyKind, ApplyRet, Executor};
+use fvm::machine::{Machine, MachineContext, WriteStats};
+use fvm_integration_tests::dummy::DummyExterns;
+use fvm_integration_tests::tester::{Account, BasicExecutor};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_test_actors::wasm_bin::STATE_ROOT_ACTOR_BINARY;
+use num_traits::Zero;
+
+mod bundles;
+
+const ACTOR_ID: ActorID = 10000;
+
+fn setup(configure_mc: impl FnOnce(&mut MachineContext)) -> (Account, BasicExecutor) {
+    let mut tester = new_tester(
+        NetworkVersion::V21,
+        StateTreeVersion::V5,
+        MemoryBlockstore::default(),
+    )
+    .unwrap();
+    let [sender] = tester.create_accounts().unwrap();
+
+    let state_cid = tester.set_state(&[(); 0]).unwrap();
+    tester
+        .set_actor_from_bin(
+            STATE_ROOT_ACTOR_BINARY,
+            state_cid,
+            Address::new_id(ACTOR_ID),
+            TokenAmount::zero(),
+        )
+        .unwrap();
+    tester
+        .instantiate_machine_with_config(DummyExterns, |_| (), configure_mc)
+        .unwrap();
+    (sender, tester.executor.unwrap())
+}
+
+/// Has the state root actor write the blocks "first", "second", and "third", setting each as its
+/// state root in turn.
+fn set_roots(sender: &Account, executor: &mut BasicExecutor, sequence: u64) -> ApplyRet {
+    let message = Message {
+        from: sender.1,
+        to: Address::new_id(ACTOR_ID),
+        gas_limit: 1_000_000_000,
+        method_num: 2,
+        sequence,
+        ..Message::default()
+    };
+    let res = executor
+        .execute_message(message, ApplyKind::Explicit, 100)
+        .unwrap();
+    assert_eq!(
+        res.msg_receipt.exit_code,
+        ExitCode::OK,
+        "{:?}",
+        res.failure_info
+    );
+    res
+}
+
+#[test]
+fn orphaned_blocks_are_not_flushed() {
+    let (sender, mut executor) = setup(|mc| {
+        mc.enable_tracing();
+    });
+    assert_eq!(executor.write_stats(), Some(&WriteStats::default()));
+
+    let stats = set_roots(&sender, &mut executor, 0)
+        .write_stats
+        .expect("expected write stats");
+
+    // The three roots, along with the new actor states and state-tree nodes, are written...
+    assert!(stats.blocks_written > 3, "{stats:?}");
+    // ...but the first two roots are orphaned by the last.
+    assert!(
+        stats.blocks_flushed <= stats.blocks_written - 2,
+        "{stats:?}"
+    );
+    assert!(
+        stats.bytes_flushed <= stats.bytes_written - (b"first".len() + b"second".len()) as u64,
+        "{stats:?}"
+    );
+
+    // The machine totals the stats of every message.
+    let mut total = stats;
+    total += set_roots(&sender, &mut executor, 1)
+        .write_stats
+        .expect("expected write stats");
+    assert_eq!(executor.write_stats(), Some(&total));
+}
+
+#[test]
+fn not_reported_without_tracing() {
+    let (sender, mut executor) = setup(|_| ());
+    assert_eq!(set_roots(&sender, &mut executor, 0).write_stats, None);
+    assert_eq!(executor.write_stats(), Some(&WriteStats::default()));
+}
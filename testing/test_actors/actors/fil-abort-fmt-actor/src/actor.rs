@@ -0,0 +1,23 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_sdk as sdk;
+
+/// The exit code the actor aborts with.
+const EXIT_CODE: u32 = 0x42;
+
+/// Aborts with a message formatted by `abort_fmt`. See `fil_abort_format_actor` for the same actor
+/// using `format!`.
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    // Derive the arguments from the method number so the compiler can't format them up-front.
+    let method = sdk::message::method_number() as i64;
+    match method {
+        1 => sdk::vm::abort_fmt(
+            EXIT_CODE,
+            "bad sector %u at epoch %d",
+            &[method + 41, -method],
+        ),
+        2 => sdk::vm::abort_fmt(EXIT_CODE, "100%% %d %u %x %d", &[-method, -method]),
+        _ => sdk::vm::abort_fmt(EXIT_CODE, "unrecognized method %d", &[method]),
+    }
+}
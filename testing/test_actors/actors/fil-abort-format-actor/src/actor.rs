@@ -0,0 +1,17 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_sdk as sdk;
+
+/// The exit code the actor aborts with.
+const EXIT_CODE: u32 = 0x42;
+
+/// Aborts with a message formatted by `format!`, to compare against `fil_abort_fmt_actor`.
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    let method = sdk::message::method_number() as i64;
+    let message = match method {
+        1 => format!("bad sector {} at epoch {}", method + 41, -method),
+        _ => format!("unrecognized method {}", method),
+    };
+    sdk::vm::abort(EXIT_CODE, Some(&message))
+}
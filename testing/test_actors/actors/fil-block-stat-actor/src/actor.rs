@@ -0,0 +1,49 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Tests `ipld::block_stat_by_cid`, which is only available from nv29 (so, unlike the IPLD actor,
+//! this actor can't be deployed before then).
+use fvm_ipld_encoding::{BytesSer, DAG_CBOR, to_vec};
+use fvm_sdk as sdk;
+use fvm_shared::MAX_CID_LEN;
+use fvm_shared::address::Address;
+use fvm_shared::error::ErrorNumber;
+
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    let bytes = to_vec(&BytesSer(
+        &(0..10 << 10).map(|b| (b % 256) as u8).collect::<Vec<u8>>(),
+    ))
+    .unwrap();
+    let k = sdk::ipld::put_with_hash(0xb220, DAG_CBOR, &bytes).unwrap();
+
+    // Reachable blocks can be stat-ed.
+    assert_eq!(sdk::ipld::get_size(&k).unwrap(), Some(bytes.len() as u32));
+    assert!(sdk::ipld::has(&k).unwrap());
+
+    unsafe {
+        let k_bytes = k.to_bytes();
+        let fvm_shared::sys::out::ipld::IpldStat { codec, size } =
+            sdk::sys::ipld::block_stat_by_cid(k_bytes.as_ptr()).unwrap();
+        assert_eq!(codec, DAG_CBOR);
+        assert_eq!(size, bytes.len() as u32);
+
+        // Test for invalid Cid
+        let buf = [0u8; MAX_CID_LEN];
+        let res = sdk::sys::ipld::block_stat_by_cid(buf.as_ptr());
+        assert_eq!(res, Err(ErrorNumber::IllegalArgument));
+    }
+
+    // Our code exists in the blockstore, but isn't reachable, so it must not be visible.
+    let code = sdk::actor::get_actor_code_cid(&Address::new_id(sdk::message::receiver())).unwrap();
+    assert_eq!(sdk::ipld::get_size(&code).unwrap(), None);
+    assert!(!sdk::ipld::has(&code).unwrap());
+    unsafe {
+        let code_bytes = code.to_bytes();
+        let res = sdk::sys::ipld::block_stat_by_cid(code_bytes.as_ptr());
+        assert_eq!(res, Err(ErrorNumber::NotFound));
+    }
+
+    0
+}
@@ -0,0 +1,4 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+#[cfg(target_arch = "wasm32")]
+mod actor;
@@ -0,0 +1,110 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Tests `crypto::verify_consensus_fault_report` against `crypto::verify_consensus_fault`. The
+//! former is only available from nv29 so, unlike the proofs actor, this actor can't be deployed
+//! before then.
+use cid::Cid;
+use cid::multihash::Multihash;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_sdk as sdk;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultReport, ConsensusFaultType, MinerPower};
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::sector::StoragePower;
+
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    // Headers the test externs don't understand are never at fault.
+    check_consensus_fault(b"header one", b"header two", &[], None);
+
+    let parent = header_cid(&header(1000, 9, &[]));
+    let h1 = header(1000, 10, &[parent]);
+
+    // Not a fault: the same block twice, a valid child, or blocks from different miners.
+    let child = header(1000, 11, &[header_cid(&h1)]);
+    let other_miner = header(1001, 10, &[]);
+    for h2 in [&h1, &child, &other_miner] {
+        check_consensus_fault(&h1, h2, &[], None);
+    }
+
+    let double_fork = header(1000, 10, &[]);
+    check_consensus_fault(
+        &h1,
+        &double_fork,
+        &[],
+        Some(ConsensusFault::new(
+            1000,
+            10,
+            ConsensusFaultType::DoubleForkMining,
+        )),
+    );
+
+    let time_offset = header(1000, 12, &[parent]);
+    check_consensus_fault(
+        &h1,
+        &time_offset,
+        &[],
+        Some(ConsensusFault::new(
+            1000,
+            12,
+            ConsensusFaultType::TimeOffsetMining,
+        )),
+    );
+
+    // The miner mines on top of a sibling of its own block.
+    let sibling = header(1001, 10, &[parent]);
+    let grinding = header(1000, 11, &[header_cid(&sibling)]);
+    check_consensus_fault(
+        &h1,
+        &grinding,
+        &sibling,
+        Some(ConsensusFault::new(
+            1000,
+            11,
+            ConsensusFaultType::ParentGrinding,
+        )),
+    );
+
+    0
+}
+
+/// Encodes a block header in the simplified format understood by the test externs.
+fn header(miner: ActorID, epoch: ChainEpoch, parents: &[Cid]) -> Vec<u8> {
+    to_vec(&(Address::new_id(miner), epoch, parents)).unwrap()
+}
+
+fn header_cid(header: &[u8]) -> Cid {
+    Cid::new_v1(
+        DAG_CBOR,
+        Multihash::wrap(
+            SupportedHashes::Blake2b256 as u64,
+            &sdk::crypto::hash_blake2b(header),
+        )
+        .unwrap(),
+    )
+}
+
+/// Checks both ways of verifying a consensus fault. When reporting a fault, the test externs
+/// return a power of `miner * 2^64 + epoch` (raw), and ten times that (quality-adjusted).
+fn check_consensus_fault(h1: &[u8], h2: &[u8], extra: &[u8], expected: Option<ConsensusFault>) {
+    let res = sdk::crypto::verify_consensus_fault(h1, h2, extra).unwrap();
+    assert_eq!(res, expected);
+
+    let report = sdk::crypto::verify_consensus_fault_report(h1, h2, extra).unwrap();
+    let expected = expected.map(|fault| {
+        let raw_byte_power =
+            StoragePower::from(((fault.target.id().unwrap() as u128) << 64) | fault.epoch as u128);
+        ConsensusFaultReport {
+            fault,
+            power: MinerPower {
+                quality_adj_power: &raw_byte_power * 10,
+                raw_byte_power,
+            },
+        }
+    });
+    assert_eq!(report, expected);
+}
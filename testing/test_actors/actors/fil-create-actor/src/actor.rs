@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0, MIT
 use actors_v12_runtime::runtime::builtins::Type;
 use fvm_sdk as sdk;
+use fvm_shared::IPLD_RAW;
 use fvm_shared::address::{Address, SECP_PUB_LEN};
-use fvm_shared::error::ErrorNumber;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::sys::SendFlags;
 
 #[unsafe(no_mangle)]
 pub fn invoke(_: u32) -> u32 {
@@ -76,6 +79,42 @@ pub fn invoke(_: u32) -> u32 {
             let res = sdk::actor::create_actor(1001, &acct_cid, Some(acct_addr));
             assert_eq!(res, Err(ErrorNumber::Forbidden));
         }
+        // our actor ID is allowed to call create actor: every actor created in the message, even
+        // by an aborted call, changes the next actor address
+        3 => {
+            let msig_cid = sdk::actor::get_code_cid_for_type(Type::Multisig as i32);
+
+            let first = sdk::actor::next_actor_address();
+            sdk::actor::create_actor(2000, &msig_cid, None).unwrap();
+
+            // create an actor in a call that aborts
+            let second = sdk::actor::next_actor_address();
+            assert_ne!(first, second);
+            let res = sdk::send::send(
+                &Address::new_id(sdk::message::receiver()),
+                4,
+                None,
+                TokenAmount::default(),
+                None,
+                SendFlags::empty(),
+            )
+            .unwrap();
+            assert_eq!(res.exit_code, ExitCode::new(ExitCode::FIRST_USER_EXIT_CODE));
+            assert_eq!(None, sdk::actor::get_actor_code_cid(&Address::new_id(2001)));
+
+            let third = sdk::actor::next_actor_address();
+            assert_ne!(second, third);
+            sdk::actor::create_actor(2002, &msig_cid, None).unwrap();
+
+            let addresses = [first, second, third].map(|addr| addr.to_bytes()).concat();
+            return sdk::ipld::put_block(IPLD_RAW, &addresses).unwrap();
+        }
+        // creates an actor, then aborts
+        4 => {
+            let msig_cid = sdk::actor::get_code_cid_for_type(Type::Multisig as i32);
+            sdk::actor::create_actor(2001, &msig_cid, None).unwrap();
+            sdk::vm::abort(ExitCode::FIRST_USER_EXIT_CODE, Some("aborted"));
+        }
         _ => {
             sdk::vm::abort(
                 fvm_shared::error::ExitCode::FIRST_USER_EXIT_CODE,
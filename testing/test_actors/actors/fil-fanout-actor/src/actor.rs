@@ -0,0 +1,45 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{CBOR, to_vec};
+use fvm_sdk as sdk;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ErrorNumber;
+use fvm_shared::sys::SendFlags;
+use fvm_shared::{METHOD_SEND, Response};
+
+/// Sends nothing to the caller in a loop until the message's send limit is reached, then returns
+/// the number of sends that succeeded.
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    let caller = Address::new_id(sdk::message::caller());
+    let send = || {
+        sdk::send::send(
+            &caller,
+            METHOD_SEND,
+            None,
+            TokenAmount::default(),
+            None,
+            SendFlags::empty(),
+        )
+    };
+
+    let mut sends: u64 = 0;
+    let err = loop {
+        match send() {
+            Ok(Response { exit_code, .. }) => {
+                assert!(exit_code.is_success(), "send failed with {exit_code}");
+                sends += 1;
+            }
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(err, ErrorNumber::LimitExceeded);
+
+    // The limit applies to the whole message: we can keep running, but can't send anymore.
+    assert_eq!(send().unwrap_err(), ErrorNumber::LimitExceeded);
+
+    sdk::ipld::put_block(CBOR, &to_vec(&sends).unwrap()).unwrap()
+}
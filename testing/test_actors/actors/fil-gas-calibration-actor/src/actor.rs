@@ -83,12 +83,7 @@ fn on_block(p: OnBlockParams) -> Result<()> {
     for i in 0..p.iterations {
         random_mutations(&mut data, p.seed + i as u64, MUTATION_COUNT);
 
-        let cid = fvm_sdk::ipld::put(
-            SupportedHashes::Blake2b256.into(),
-            32,
-            IPLD_RAW,
-            data.as_slice(),
-        )?;
+        let cid = fvm_sdk::ipld::put_raw(data.as_slice())?;
 
         // First just put it to the side, because if we read it back now, then strangely the times of puts go down by 10x in the beginning
         // and only in later go up to where they are when they are the only thing we do. The distribution takes the shape of a sloping V.
@@ -249,8 +244,8 @@ fn on_scan_ipld_links(p: OnScanIpldLinksParams) -> Result<()> {
             p.cbor_field_count,
             p.cbor_link_count,
         );
-        let cid =
-            fvm_sdk::ipld::put(SupportedHashes::Blake2b256.into(), 32, DAG_CBOR, &obj).unwrap();
+        let cid = fvm_sdk::ipld::put_with_hash(SupportedHashes::Blake2b256.into(), DAG_CBOR, &obj)
+            .unwrap();
         test_cids.push(cid);
         let res = fvm_sdk::ipld::get(&cid).unwrap();
         assert_eq!(obj, res);
@@ -32,10 +32,7 @@ impl fvm_ipld_blockstore::Blockstore for Blockstore {
     where
         D: AsRef<[u8]>,
     {
-        // TODO: Don't hard-code the size. Unfortunately, there's no good way to get it from the
-        //  codec at the moment.
-        const SIZE: u32 = 32;
-        let k = sdk::ipld::put(code.into(), SIZE, block.codec, block.data.as_ref())
+        let k = sdk::ipld::put_with_hash(code.into(), block.codec, block.data.as_ref())
             .map_err(|e| anyhow!("put failed with {:?}", e))?;
         Ok(k)
     }
@@ -48,9 +48,8 @@ impl State {
                 Some(format!("failed to serialize state: {:?}", err).as_str()),
             ),
         };
-        let cid = match fvm_sdk::ipld::put(
+        let cid = match fvm_sdk::ipld::put_with_hash(
             SupportedHashes::Blake2b256.into(),
-            32,
             DAG_CBOR,
             serialized.as_slice(),
         ) {
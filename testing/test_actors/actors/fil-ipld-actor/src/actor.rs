@@ -1,6 +1,6 @@
 // Copyright 2021-2023 Protocol Labs
 // SPDX-License-Identifier: Apache-2.0, MIT
-use fvm_ipld_encoding::{BytesSer, DAG_CBOR, to_vec};
+use fvm_ipld_encoding::{BytesSer, CBOR, DAG_CBOR, IPLD_RAW, cid_of, to_vec};
 use fvm_sdk as sdk;
 use fvm_shared::MAX_CID_LEN;
 use fvm_shared::error::ErrorNumber;
@@ -20,7 +20,9 @@ pub fn invoke(_: u32) -> u32 {
     test_read_block();
     test_create_block();
     test_stat_block();
+    test_stat_block_links();
     test_link_block();
+    test_typed_put_get();
 
     0
 }
@@ -29,7 +31,7 @@ fn test_open_block() {
     let test_bytes = gen_test_bytes(1 << 10);
 
     unsafe {
-        let cid = sdk::ipld::put(0xb220, 32, DAG_CBOR, &test_bytes).unwrap();
+        let cid = sdk::ipld::put_with_hash(0xb220, DAG_CBOR, &test_bytes).unwrap();
 
         // The cid should be valid
         let mut buf = [0u8; MAX_CID_LEN];
@@ -53,7 +55,7 @@ fn test_open_block() {
 
 fn test_read_block() {
     let test_bytes = gen_test_bytes(10 << 10);
-    let k = sdk::ipld::put(0xb220, 32, DAG_CBOR, &test_bytes).unwrap();
+    let k = sdk::ipld::put_with_hash(0xb220, DAG_CBOR, &test_bytes).unwrap();
     {
         let block = sdk::ipld::get(&k).unwrap();
         assert_eq!(test_bytes, block);
@@ -115,6 +117,41 @@ fn test_read_block() {
             );
         }
 
+        // Test windowed reads with read_at.
+        {
+            let len = test_bytes.len() as u32;
+            let mut window = [0u8; 100];
+            for (offset, copied) in [
+                (0, 100),       // start
+                (len / 2, 100), // middle
+                (len - 10, 10), // straddling the end
+                (len, 0),       // end
+                (len + 10, 0),  // past the end
+            ] {
+                window.fill(0);
+                assert_eq!(
+                    sdk::ipld::read_at(id, offset, &mut window),
+                    Ok(copied),
+                    "unexpected read length at offset {offset}"
+                );
+                let start = offset.min(len) as usize;
+                assert_eq!(
+                    &window[..copied as usize],
+                    &test_bytes[start..start + copied as usize],
+                    "unexpected data at offset {offset}"
+                );
+            }
+
+            // The empty block has nothing to read.
+            assert_eq!(sdk::ipld::read_at(sdk::ipld::UNIT, 0, &mut window), Ok(0));
+
+            // The offset plus the window must fit in an i32.
+            assert_eq!(
+                sdk::ipld::read_at(id, i32::MAX as u32, &mut window),
+                Err(ErrorNumber::IllegalArgument)
+            );
+        }
+
         // Test get_block with no hint
         assert_eq!(
             test_bytes,
@@ -143,6 +180,20 @@ fn test_read_block() {
             "can read with an over-estimated size"
         );
 
+        // Test get_block with a zero hint.
+        assert_eq!(
+            test_bytes,
+            sdk::ipld::get_block(id, Some(0)).unwrap(),
+            "can read with a zero hint"
+        );
+
+        // Test reading the (multi-kilobyte) block as message parameters, in windows.
+        let params = sdk::message::params_raw(id)
+            .unwrap()
+            .expect("expected parameters");
+        assert_eq!(params.codec, DAG_CBOR, "params have an incorrect codec");
+        assert_eq!(test_bytes, params.data, "can read the block as params");
+
         // Test an offset that overflows an i32:
         let res = sdk::sys::ipld::block_read(id, (i32::MAX as u32) + 1, buf.as_mut_ptr(), 0);
         assert_eq!(res, Err(ErrorNumber::IllegalArgument));
@@ -180,6 +231,15 @@ fn test_create_block() {
             sdk::sys::ipld::block_create(DAG_CBOR, test_bytes.as_ptr(), test_bytes.len() as u32);
         assert_eq!(res, Err(ErrorNumber::LimitExceeded));
     }
+
+    // Blocks of exactly the maximum size are allowed, one more byte isn't.
+    let max_block = vec![0u8; sdk::ipld::MAX_BLOCK_SIZE as usize];
+    sdk::ipld::put_block(IPLD_RAW, &max_block).expect("should be within block limit");
+    let over_block = vec![0u8; sdk::ipld::MAX_BLOCK_SIZE as usize + 1];
+    assert_eq!(
+        sdk::ipld::put_block(IPLD_RAW, &over_block),
+        Err(ErrorNumber::LimitExceeded)
+    );
 }
 
 fn test_stat_block() {
@@ -205,6 +265,36 @@ fn test_stat_block() {
     }
 }
 
+fn test_stat_block_links() {
+    let child = gen_test_bytes(10);
+    let k1 = sdk::ipld::put_with_hash(0xb220, DAG_CBOR, &child).unwrap();
+    let k2 = sdk::ipld::put_with_hash(0xb220, IPLD_RAW, &child).unwrap();
+
+    // Links are counted in DAG-CBOR blocks, including duplicates.
+    let parent = to_vec(&[k1, k2, k1]).unwrap();
+    let parent_id = sdk::ipld::put_block(DAG_CBOR, &parent).unwrap();
+    let fvm_shared::sys::out::ipld::IpldStatLinks { codec, size, links } =
+        sdk::ipld::stat(parent_id).unwrap();
+    assert_eq!(codec, DAG_CBOR);
+    assert_eq!(size, parent.len() as u32);
+    assert_eq!(links, 3);
+
+    // Repeated stats are consistent.
+    assert_eq!(
+        sdk::ipld::stat(parent_id).unwrap(),
+        sdk::ipld::stat(parent_id).unwrap()
+    );
+
+    // Blocks without links, and non-DAG-CBOR blocks, have no links.
+    let child_id = sdk::ipld::put_block(DAG_CBOR, &child).unwrap();
+    assert_eq!({ sdk::ipld::stat(child_id).unwrap().links }, 0);
+    let raw_id = sdk::ipld::put_block(IPLD_RAW, &parent).unwrap();
+    assert_eq!({ sdk::ipld::stat(raw_id).unwrap().links }, 0);
+
+    // Test that giving invalid block id results in InvalidHandle error
+    assert_eq!(sdk::ipld::stat(1919), Err(ErrorNumber::InvalidHandle));
+}
+
 fn test_link_block() {
     let bytes = gen_test_bytes(10 << 10);
 
@@ -250,3 +340,48 @@ fn test_link_block() {
         assert_eq!(res, Err(ErrorNumber::IllegalCid));
     }
 }
+
+fn test_typed_put_get() {
+    // CBOR blocks get the same CIDs as they would off-chain.
+    let value = (1u64, String::from("two"), vec![3u8; 100]);
+    let k = sdk::ipld::put_cbor(&value).unwrap();
+    assert_eq!(k, cid_of(&value).unwrap());
+    assert_eq!(sdk::ipld::get_cbor(&k).unwrap(), Some(value.clone()));
+
+    // Unreachable blocks aren't found, and blocks must be CBOR.
+    let missing = cid_of(&"missing").unwrap();
+    assert_eq!(sdk::ipld::get_cbor::<String>(&missing).unwrap(), None);
+    let raw = sdk::ipld::put_raw(&to_vec(&value).unwrap()).unwrap();
+    assert_eq!(raw.codec(), IPLD_RAW);
+    assert_eq!(
+        raw.hash().digest(),
+        sdk::crypto::hash_blake2b(&to_vec(&value).unwrap())
+    );
+    assert_eq!(
+        sdk::ipld::get_cbor::<(u64, String, Vec<u8>)>(&raw),
+        Err(ErrorNumber::IllegalCodec)
+    );
+
+    // Blocks that don't decode as the requested type fail.
+    let cbor = sdk::ipld::put_with_hash(0xb220, CBOR, &to_vec(&value).unwrap()).unwrap();
+    assert_eq!(sdk::ipld::get_cbor(&cbor).unwrap(), Some(value));
+    assert_eq!(
+        sdk::ipld::get_cbor::<u64>(&cbor),
+        Err(ErrorNumber::Serialization)
+    );
+
+    // The digest size is derived from the hash function.
+    assert_eq!(
+        sdk::ipld::put_with_hash(0xb220, IPLD_RAW, b"data")
+            .unwrap()
+            .hash()
+            .size(),
+        32
+    );
+    let identity = sdk::ipld::put_with_hash(fvm_shared::IDENTITY_HASH, IPLD_RAW, b"data").unwrap();
+    assert_eq!(identity.hash().digest(), b"data");
+    assert_eq!(
+        sdk::ipld::put_with_hash(0x1919, IPLD_RAW, b"data"),
+        Err(ErrorNumber::IllegalCid)
+    );
+}
@@ -0,0 +1,58 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{CBOR, from_slice, to_vec, tuple::*};
+use fvm_sdk as sdk;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+
+/// The message-level context observed by an actor.
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct MessageInfo {
+    origin: ActorID,
+    nonce: u64,
+    gas_premium: TokenAmount,
+}
+
+fn message_info() -> MessageInfo {
+    MessageInfo {
+        origin: sdk::message::origin(),
+        nonce: sdk::message::nonce(),
+        gas_premium: sdk::message::gas_premium(),
+    }
+}
+
+/// Method 2 returns the message context. Method 3 returns the message context, along with the
+/// message context observed by a nested call to method 2 on this actor. Method 4 returns the
+/// current epoch and the tipset timestamp.
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    let ret = match sdk::message::method_number() {
+        2 => to_vec(&message_info()),
+        3 => {
+            let resp = sdk::send::send(
+                &Address::new_id(sdk::message::receiver()),
+                2,
+                None,
+                TokenAmount::default(),
+                None,
+                Default::default(),
+            )
+            .unwrap();
+            assert!(resp.exit_code.is_success());
+            let nested: MessageInfo = from_slice(&resp.return_data.unwrap().data).unwrap();
+            to_vec(&(message_info(), nested))
+        }
+        4 => to_vec(&(sdk::network::curr_epoch(), sdk::network::tipset_timestamp())),
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unknown method number"),
+        ),
+    }
+    .unwrap();
+
+    sdk::ipld::put_block(CBOR, &ret).unwrap()
+}
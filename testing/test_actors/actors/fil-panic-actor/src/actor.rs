@@ -0,0 +1,26 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
+
+/// The exit code used by the custom panic handler (method 2).
+const PANIC_EXIT_CODE: ExitCode = ExitCode::new(0x42);
+
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    let method = sdk::message::method_number();
+    match method {
+        // Panics with the default panic handler.
+        1 => sdk::vm::set_panic_handler(),
+        // Panics with a panic handler using a custom exit code.
+        2 => sdk::vm::set_panic_handler_with_exit_code(PANIC_EXIT_CODE),
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unrecognized method"),
+        ),
+    }
+
+    // Index out of bounds (based on the method number so the compiler can't see it coming).
+    let values = [1u32, 2, 3];
+    values[method as usize + 2]
+}
@@ -0,0 +1,27 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::IPLD_RAW;
+use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
+
+/// Reads the parameters through the SDK, and returns their length (as 8 big-endian bytes) followed
+/// by their blake2b-256 digest.
+///
+/// The actor's memory starts out without room for a heap (the linker only reserves the stack and
+/// static data), so reading large parameters grows it.
+#[unsafe(no_mangle)]
+pub fn invoke(params: u32) -> u32 {
+    sdk::initialize();
+
+    let params = match sdk::message::params_raw(params).unwrap() {
+        Some(params) => params,
+        None => sdk::vm::abort(
+            ExitCode::USR_ILLEGAL_ARGUMENT.value(),
+            Some("expected parameters"),
+        ),
+    };
+
+    let mut ret = (params.data.len() as u64).to_be_bytes().to_vec();
+    ret.extend_from_slice(&sdk::crypto::hash_blake2b(&params.data));
+    sdk::ipld::put_block(IPLD_RAW, &ret).unwrap()
+}
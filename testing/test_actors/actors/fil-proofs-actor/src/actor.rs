@@ -0,0 +1,257 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Calls each of the proof-related crypto functions exposed by the SDK. The integration tests run
+//! this actor on a kernel that stubs out proof verification so that we exercise the syscall ABI
+//! (argument encoding and return values), not the proofs themselves.
+use cid::Cid;
+use cid::multihash::Multihash;
+use fvm_ipld_encoding::{DAG_CBOR, to_vec};
+use fvm_sdk as sdk;
+use fvm_shared::ActorID;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::commcid::{data_commitment_v1_to_cid, replica_commitment_v1_to_cid};
+use fvm_shared::consensus::{ConsensusFault, ConsensusFaultType};
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::error::ErrorNumber;
+use fvm_shared::piece::{PaddedPieceSize, PieceInfo};
+use fvm_shared::randomness::Randomness;
+use fvm_shared::sector::{
+    AggregateSealVerifyInfo, AggregateSealVerifyProofAndInfos, PoStProof, RegisteredAggregateProof,
+    RegisteredPoStProof, RegisteredSealProof, RegisteredUpdateProof, ReplicaUpdateInfo,
+    SealVerifyInfo, SectorID, SectorNumber, WindowPoStVerifyInfo,
+};
+
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    test_compute_unsealed_sector_cid();
+    test_compute_unsealed_sector_cids();
+    test_verify_post();
+    test_verify_post_batch();
+    test_verify_consensus_fault();
+    test_verify_aggregate_seals();
+    test_verify_replica_update();
+    test_batch_verify_seals();
+
+    0
+}
+
+fn test_compute_unsealed_sector_cid() {
+    // A single piece filling the entire sector has the same commitment as the sector.
+    let piece_cid = data_commitment_v1_to_cid(&[1u8; 32]).unwrap();
+    let pieces = [PieceInfo {
+        size: PaddedPieceSize(2048),
+        cid: piece_cid,
+    }];
+    let unsealed_cid =
+        sdk::crypto::compute_unsealed_sector_cid(RegisteredSealProof::StackedDRG2KiBV1P1, &pieces)
+            .unwrap();
+    assert_eq!(unsealed_cid, piece_cid);
+
+    // Invalid proof types are rejected by the syscall.
+    assert_eq!(
+        sdk::crypto::compute_unsealed_sector_cid(RegisteredSealProof::Invalid(1000), &pieces),
+        Err(ErrorNumber::IllegalArgument)
+    );
+}
+
+fn test_compute_unsealed_sector_cids() {
+    let piece_cid = data_commitment_v1_to_cid(&[1u8; 32]).unwrap();
+    let full = [PieceInfo {
+        size: PaddedPieceSize(2048),
+        cid: piece_cid,
+    }];
+    let oversized = [PieceInfo {
+        size: PaddedPieceSize(4096),
+        cid: piece_cid,
+    }];
+
+    // A mixed batch, including invalid entries. Each entry matches the equivalent single call.
+    let batch: [(RegisteredSealProof, &[PieceInfo]); 4] = [
+        (RegisteredSealProof::StackedDRG2KiBV1P1, &full),
+        (RegisteredSealProof::Invalid(1000), &full),
+        (RegisteredSealProof::StackedDRG2KiBV1P1, &[]),
+        (RegisteredSealProof::StackedDRG2KiBV1P1, &oversized),
+    ];
+    let results = sdk::crypto::compute_unsealed_sector_cids(&batch).unwrap();
+    assert_eq!(results.len(), batch.len());
+    assert_eq!(results[0], Ok(piece_cid));
+    assert_eq!(results[1], Err(ErrorNumber::IllegalArgument));
+    for (i, (proof_type, pieces)) in batch.iter().enumerate() {
+        assert_eq!(
+            results[i],
+            sdk::crypto::compute_unsealed_sector_cid(*proof_type, pieces),
+            "batch entry {i}"
+        );
+    }
+
+    // An empty batch is fine.
+    assert_eq!(sdk::crypto::compute_unsealed_sector_cids(&[]), Ok(vec![]));
+}
+
+fn test_verify_post() {
+    // Mixing proof types is rejected before any proof is verified.
+    let info = WindowPoStVerifyInfo {
+        randomness: Randomness([0u8; 32]),
+        proofs: vec![
+            PoStProof {
+                post_proof: RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+                proof_bytes: vec![],
+            },
+            PoStProof {
+                post_proof: RegisteredPoStProof::StackedDRGWindow8MiBV1P1,
+                proof_bytes: vec![],
+            },
+        ],
+        challenged_sectors: vec![],
+        prover: 1000,
+    };
+    assert_eq!(
+        sdk::crypto::verify_post(&info),
+        Err(ErrorNumber::IllegalArgument)
+    );
+}
+
+fn test_verify_post_batch() {
+    // Malformed entries (here, mixing proof types) fail individually instead of failing the batch.
+    // We use more than 8 entries to span multiple bytes of the result bitfield.
+    let info = WindowPoStVerifyInfo {
+        randomness: Randomness([0u8; 32]),
+        proofs: vec![
+            PoStProof {
+                post_proof: RegisteredPoStProof::StackedDRGWindow2KiBV1P1,
+                proof_bytes: vec![],
+            },
+            PoStProof {
+                post_proof: RegisteredPoStProof::StackedDRGWindow8MiBV1P1,
+                proof_bytes: vec![],
+            },
+        ],
+        challenged_sectors: vec![],
+        prover: 1000,
+    };
+    let batch = vec![info; 9];
+    assert_eq!(sdk::crypto::verify_post_batch(&batch), Ok(vec![false; 9]));
+
+    assert_eq!(sdk::crypto::verify_post_batch(&[]), Ok(vec![]));
+}
+
+/// Encodes a block header in the simplified format understood by the test externs.
+fn header(miner: ActorID, epoch: ChainEpoch, parents: &[Cid]) -> Vec<u8> {
+    to_vec(&(Address::new_id(miner), epoch, parents)).unwrap()
+}
+
+fn header_cid(header: &[u8]) -> Cid {
+    Cid::new_v1(
+        DAG_CBOR,
+        Multihash::wrap(
+            SupportedHashes::Blake2b256 as u64,
+            &sdk::crypto::hash_blake2b(header),
+        )
+        .unwrap(),
+    )
+}
+
+fn test_verify_consensus_fault() {
+    // Headers the test externs don't understand are never at fault.
+    let res = sdk::crypto::verify_consensus_fault(b"header one", b"header two", &[]).unwrap();
+    assert!(res.is_none());
+
+    let parent = header_cid(&header(1000, 9, &[]));
+    let h1 = header(1000, 10, &[parent]);
+
+    // Not a fault: the same block twice, a valid child, or blocks from different miners.
+    let child = header(1000, 11, &[header_cid(&h1)]);
+    let other_miner = header(1001, 10, &[]);
+    for h2 in [&h1, &child, &other_miner] {
+        let res = sdk::crypto::verify_consensus_fault(&h1, h2, &[]).unwrap();
+        assert!(res.is_none());
+    }
+
+    let double_fork = header(1000, 10, &[]);
+    let res = sdk::crypto::verify_consensus_fault(&h1, &double_fork, &[]).unwrap();
+    assert_eq!(
+        res,
+        Some(ConsensusFault::new(
+            1000,
+            10,
+            ConsensusFaultType::DoubleForkMining
+        ))
+    );
+
+    let time_offset = header(1000, 12, &[parent]);
+    let res = sdk::crypto::verify_consensus_fault(&h1, &time_offset, &[]).unwrap();
+    assert_eq!(
+        res,
+        Some(ConsensusFault::new(
+            1000,
+            12,
+            ConsensusFaultType::TimeOffsetMining
+        ))
+    );
+
+    // The miner mines on top of a sibling of its own block.
+    let sibling = header(1001, 10, &[parent]);
+    let grinding = header(1000, 11, &[header_cid(&sibling)]);
+    let res = sdk::crypto::verify_consensus_fault(&h1, &grinding, &sibling).unwrap();
+    assert_eq!(
+        res,
+        Some(ConsensusFault::new(
+            1000,
+            11,
+            ConsensusFaultType::ParentGrinding
+        ))
+    );
+}
+
+fn test_verify_aggregate_seals() {
+    let info = AggregateSealVerifyProofAndInfos {
+        miner: 1000,
+        seal_proof: RegisteredSealProof::StackedDRG2KiBV1P1,
+        aggregate_proof: RegisteredAggregateProof::SnarkPackV2,
+        proof: vec![0u8; 32],
+        infos: vec![AggregateSealVerifyInfo {
+            sector_number: SectorNumber::new(1).unwrap(),
+            randomness: Randomness([0u8; 32]),
+            interactive_randomness: Randomness([0u8; 32]),
+            sealed_cid: replica_commitment_v1_to_cid(&[2u8; 32]).unwrap(),
+            unsealed_cid: data_commitment_v1_to_cid(&[1u8; 32]).unwrap(),
+        }],
+    };
+    assert_eq!(sdk::crypto::verify_aggregate_seals(&info), Ok(true));
+}
+
+fn test_verify_replica_update() {
+    let info = ReplicaUpdateInfo {
+        update_proof_type: RegisteredUpdateProof::StackedDRG2KiBV1,
+        old_sealed_cid: replica_commitment_v1_to_cid(&[2u8; 32]).unwrap(),
+        new_sealed_cid: replica_commitment_v1_to_cid(&[3u8; 32]).unwrap(),
+        new_unsealed_cid: data_commitment_v1_to_cid(&[1u8; 32]).unwrap(),
+        proof: vec![0u8; 32],
+    };
+    assert_eq!(sdk::crypto::verify_replica_update(&info), Ok(true));
+}
+
+fn test_batch_verify_seals() {
+    let seal = |number| SealVerifyInfo {
+        registered_proof: RegisteredSealProof::StackedDRG2KiBV1P1,
+        sector_id: SectorID {
+            miner: 1000,
+            number: SectorNumber::new(number).unwrap(),
+        },
+        deal_ids: vec![],
+        randomness: Randomness([0u8; 32]),
+        interactive_randomness: Randomness([0u8; 32]),
+        proof: vec![0u8; 32],
+        sealed_cid: replica_commitment_v1_to_cid(&[2u8; 32]).unwrap(),
+        unsealed_cid: data_commitment_v1_to_cid(&[1u8; 32]).unwrap(),
+    };
+
+    // One result per seal, in order.
+    let res = sdk::crypto::batch_verify_seals(&[seal(1), seal(2), seal(3)]).unwrap();
+    assert_eq!(res, vec![true, true, true]);
+
+    assert_eq!(sdk::crypto::batch_verify_seals(&[]), Ok(vec![]));
+}
@@ -0,0 +1,30 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{BytesDe, IPLD_RAW, from_slice};
+use fvm_sdk as sdk;
+use fvm_shared::clock::ChainEpoch;
+
+/// Draws randomness from the chain (or, if `beacon` is set, from the beacon) at the given epoch,
+/// mixing in the entropy the way builtin actors do (the FVM only sees the epoch), and returns the
+/// resulting 32 bytes.
+///
+/// Takes a CBOR tuple of `(beacon, epoch, entropy)`.
+#[unsafe(no_mangle)]
+pub fn invoke(params_id: u32) -> u32 {
+    sdk::initialize();
+
+    let params = sdk::message::params_raw(params_id).unwrap().unwrap();
+    let (beacon, epoch, BytesDe(entropy)): (bool, ChainEpoch, BytesDe) =
+        from_slice(&params.data).unwrap();
+
+    let randomness = if beacon {
+        sdk::rand::get_beacon_randomness(epoch)
+    } else {
+        sdk::rand::get_chain_randomness(epoch)
+    }
+    .unwrap();
+
+    let mut data = randomness.to_vec();
+    data.extend_from_slice(&entropy);
+    sdk::ipld::put_block(IPLD_RAW, &sdk::crypto::hash_blake2b(&data)).unwrap()
+}
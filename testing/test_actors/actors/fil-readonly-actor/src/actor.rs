@@ -99,7 +99,7 @@ fn invoke_method(blk: u32, method: u64) -> u32 {
             );
 
             // Writing should succeed.
-            let cid = sdk::ipld::put(0xb220, 32, 0x55, b"foo").unwrap();
+            let cid = sdk::ipld::put_raw(b"foo").unwrap();
 
             // Setting root should fail.
             let err = sdk::sself::set_root(&cid).expect_err("successfully set root");
@@ -0,0 +1,30 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::RawBytes;
+use fvm_sdk as sdk;
+use fvm_shared::{ActorID, MethodNum};
+
+/// Records every call in its state, a list of `(caller, method, params)`. The state must initially
+/// be an empty list.
+#[unsafe(no_mangle)]
+pub fn invoke(params_id: u32) -> u32 {
+    sdk::initialize();
+
+    let params = sdk::message::params_raw(params_id)
+        .unwrap()
+        .map(|blk| blk.data)
+        .unwrap_or_default();
+
+    let root = sdk::sself::root().unwrap();
+    let mut calls: Vec<(ActorID, MethodNum, RawBytes)> =
+        sdk::ipld::get_cbor(&root).unwrap().unwrap();
+    calls.push((
+        sdk::message::caller(),
+        sdk::message::method_number(),
+        RawBytes::new(params),
+    ));
+    let root = sdk::ipld::put_cbor(&calls).unwrap();
+    sdk::sself::set_root(&root).unwrap();
+
+    sdk::NO_DATA_BLOCK_ID
+}
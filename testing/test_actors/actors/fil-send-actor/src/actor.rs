@@ -0,0 +1,71 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{CBOR, to_vec, tuple::*};
+use fvm_sdk as sdk;
+use fvm_sdk::SyscallResult;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::{MethodNum, Response};
+
+#[derive(Serialize_tuple, Deserialize_tuple, PartialEq, Eq, Clone, Debug)]
+struct Params {
+    dest: Address,
+    method: MethodNum,
+    value: TokenAmount,
+}
+
+/// Sends a message to the specified actor and returns the exit code of that send.
+///
+/// Method 3 instead takes a list of messages, sends them in order, and returns the list of exit
+/// codes. Method 4 does the same, but returns the list of syscall error numbers instead, with 0
+/// for sends that didn't fail.
+#[unsafe(no_mangle)]
+pub fn invoke(params_id: u32) -> u32 {
+    sdk::initialize();
+
+    let params = {
+        let msg_params = sdk::message::params_raw(params_id).unwrap().unwrap();
+        assert_eq!(msg_params.codec, fvm_ipld_encoding::CBOR);
+        msg_params.data
+    };
+
+    let ret = match sdk::message::method_number() {
+        3 => {
+            let params: Vec<Params> = fvm_ipld_encoding::from_slice(&params).unwrap();
+            let exit_codes: Vec<ExitCode> = params.into_iter().map(send).collect();
+            to_vec(&exit_codes)
+        }
+        4 => {
+            let params: Vec<Params> = fvm_ipld_encoding::from_slice(&params).unwrap();
+            let errors: Vec<u32> = params.into_iter().map(try_send).collect();
+            to_vec(&errors)
+        }
+        _ => to_vec(&send(fvm_ipld_encoding::from_slice(&params).unwrap())),
+    }
+    .unwrap();
+
+    sdk::ipld::put_block(CBOR, &ret).unwrap()
+}
+
+fn send(params: Params) -> ExitCode {
+    raw_send(params).unwrap().exit_code
+}
+
+fn try_send(params: Params) -> u32 {
+    match raw_send(params) {
+        Ok(_) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+fn raw_send(params: Params) -> SyscallResult<Response> {
+    sdk::send::send(
+        &params.dest,
+        params.method,
+        None,
+        params.value,
+        None,
+        Default::default(),
+    )
+}
@@ -4,6 +4,7 @@ use cid::Cid;
 use cid::multihash::Multihash;
 use fvm_ipld_encoding::{DAG_CBOR, to_vec};
 use fvm_sdk as sdk;
+use fvm_shared::error::ExitCode;
 use fvm_shared::{crypto::hash::SupportedHashes, econ::TokenAmount};
 use sdk::error::{ActorDeleteError, StateReadError, StateUpdateError};
 
@@ -27,7 +28,7 @@ pub fn invoke(_: u32) -> u32 {
 
     // test setting the root cid for the caling actor returns the correct root
     //
-    let cid = sdk::ipld::put(0xb220, 32, 0x55, b"foo").unwrap();
+    let cid = sdk::ipld::put_raw(b"foo").unwrap();
     sdk::sself::set_root(&cid).unwrap();
     let root = sdk::sself::root().unwrap();
     assert_eq!(root, cid);
@@ -56,5 +57,13 @@ pub fn invoke(_: u32) -> u32 {
     // calling destroy on an already destroyed actor should succeed (no-op)
     sdk::sself::self_destruct(false).expect("deleting an already deleted actor should succeed");
 
+    // Method 2 fails after deleting the actor, reverting the deletion.
+    if sdk::message::method_number() == 2 {
+        sdk::vm::abort(
+            ExitCode::FIRST_USER_EXIT_CODE,
+            Some("failed after self-destructing"),
+        );
+    }
+
     0
 }
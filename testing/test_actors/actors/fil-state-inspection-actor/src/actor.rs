@@ -0,0 +1,39 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use fvm_ipld_encoding::{CBOR, from_slice};
+use fvm_sdk as sdk;
+use fvm_shared::ActorID;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+
+/// Takes the ID of another actor as its parameter.
+///
+/// - Method 2 reads the actor's state root block and returns it.
+/// - Method 3 asserts that the actor's state may not be inspected.
+#[unsafe(no_mangle)]
+pub fn invoke(params_id: u32) -> u32 {
+    sdk::initialize();
+
+    let params = sdk::message::params_raw(params_id).unwrap().unwrap();
+    let actor_id: ActorID = from_slice(&params.data).unwrap();
+
+    match sdk::message::method_number() {
+        2 => {
+            let root = sdk::actor::state_root_of(actor_id).unwrap();
+            // The state is readable, but it isn't ours.
+            assert_ne!(root, sdk::sself::root().unwrap());
+            let state = sdk::ipld::get(&root).unwrap();
+            sdk::ipld::put_block(CBOR, &state).unwrap()
+        }
+        3 => {
+            assert_eq!(
+                sdk::actor::state_root_of(actor_id),
+                Err(ErrorNumber::Forbidden)
+            );
+            sdk::NO_DATA_BLOCK_ID
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unknown method number"),
+        ),
+    }
+}
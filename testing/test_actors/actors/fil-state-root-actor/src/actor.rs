@@ -0,0 +1,131 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use cid::Cid;
+use cid::multihash::Multihash;
+use fvm_ipld_encoding::{CBOR, IPLD_RAW, from_slice, to_vec};
+use fvm_sdk as sdk;
+use fvm_shared::MAX_CID_LEN;
+use fvm_shared::address::Address;
+use fvm_shared::crypto::hash::SupportedHashes;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+
+/// The exit code with which method 3 aborts.
+const ABORT_EXIT_CODE: u32 = 42;
+
+/// Writes a raw block, returning its CID.
+fn put(data: &[u8]) -> Cid {
+    sdk::ipld::put_raw(data).unwrap()
+}
+
+/// Writes a raw block and sets it as the state root.
+fn put_root(data: &[u8]) -> Cid {
+    let cid = put(data);
+    sdk::sself::set_root(&cid).unwrap();
+    assert_eq!(sdk::sself::root().unwrap(), cid);
+    cid
+}
+
+/// - Method 2 sets the state root three times, writing a new block before each, and returns the
+///   final root. Setting an unwritten (unreachable) root fails.
+/// - Method 3 sets the state root twice, then aborts.
+/// - Method 4 sets the state root, then calls method 5 on itself, which returns the root it
+///   observed and sets a new root. It returns the root it observes after the call.
+/// - Method 6 sets the state root, calls method 5 on itself, then calls method 7 on itself, which
+///   sets a new root and aborts. It returns the root it observes after both calls.
+#[unsafe(no_mangle)]
+pub fn invoke(_: u32) -> u32 {
+    sdk::initialize();
+
+    let ret = match sdk::message::method_number() {
+        2 => {
+            put_root(b"first");
+            let second = put_root(b"second");
+
+            // The root must be reachable, even if it's otherwise a valid CID.
+            let unwritten = Cid::new_v1(
+                IPLD_RAW,
+                Multihash::wrap(
+                    SupportedHashes::Blake2b256 as u64,
+                    &sdk::crypto::hash_blake2b(b"unwritten"),
+                )
+                .unwrap(),
+            );
+            let mut buf = [0u8; MAX_CID_LEN];
+            unwritten
+                .write_bytes(&mut buf[..])
+                .expect("CID encoding should not fail");
+            assert_eq!(
+                unsafe { sdk::sys::sself::set_root(buf.as_ptr()) },
+                Err(ErrorNumber::NotFound)
+            );
+            assert_eq!(sdk::sself::root().unwrap(), second);
+
+            to_vec(&put_root(b"third"))
+        }
+        3 => {
+            put_root(b"first");
+            put_root(b"second");
+            sdk::vm::abort(ABORT_EXIT_CODE, Some("aborting after setting the root"))
+        }
+        4 => {
+            let outer = put_root(b"outer");
+            let resp = sdk::send::send(
+                &Address::new_id(sdk::message::receiver()),
+                5,
+                None,
+                TokenAmount::default(),
+                None,
+                Default::default(),
+            )
+            .unwrap();
+            assert!(resp.exit_code.is_success());
+            let (observed, nested): (Cid, Cid) =
+                from_slice(&resp.return_data.unwrap().data).unwrap();
+            assert_eq!(observed, outer);
+            // And we observe the nested call's root.
+            let root = sdk::sself::root().unwrap();
+            assert_eq!(root, nested);
+            to_vec(&root)
+        }
+        5 => {
+            let observed = sdk::sself::root().unwrap();
+            to_vec(&(observed, put_root(b"nested")))
+        }
+        6 => {
+            put_root(b"outer");
+            let send_self = |method| {
+                sdk::send::send(
+                    &Address::new_id(sdk::message::receiver()),
+                    method,
+                    None,
+                    TokenAmount::default(),
+                    None,
+                    Default::default(),
+                )
+                .unwrap()
+            };
+            let resp = send_self(5);
+            assert!(resp.exit_code.is_success());
+            let (_, nested): (Cid, Cid) = from_slice(&resp.return_data.unwrap().data).unwrap();
+
+            // The aborted call's root is discarded.
+            let resp = send_self(7);
+            assert_eq!(resp.exit_code.value(), ABORT_EXIT_CODE);
+            let root = sdk::sself::root().unwrap();
+            assert_eq!(root, nested);
+            to_vec(&root)
+        }
+        7 => {
+            put_root(b"reverted");
+            sdk::vm::abort(ABORT_EXIT_CODE, Some("aborting after setting the root"))
+        }
+        _ => sdk::vm::abort(
+            ExitCode::USR_UNHANDLED_MESSAGE.value(),
+            Some("unknown method number"),
+        ),
+    }
+    .unwrap();
+
+    sdk::ipld::put_block(CBOR, &ret).unwrap()
+}
@@ -270,6 +270,23 @@ fn test_expected_hash() {
     assert_eq!(blake_arr.as_slice(), blake_vec.as_slice());
     assert_eq!(blake_local.digest(), blake_vec.as_slice());
 
+    // check every buffer-returning hash method against a known blake2b-256 digest
+    {
+        const ABC_BLAKE2B_256: [u8; 32] = [
+            0xbd, 0xdd, 0x81, 0x3c, 0x63, 0x42, 0x39, 0x72, 0x31, 0x71, 0xef, 0x3f, 0xee, 0x98,
+            0x57, 0x9b, 0x94, 0x96, 0x4e, 0x3b, 0xb1, 0xcb, 0x3e, 0x42, 0x72, 0x62, 0xc8, 0xc0,
+            0x68, 0xd5, 0x23, 0x19,
+        ];
+        assert_eq!(sdk::crypto::hash_blake2b(b"abc"), ABC_BLAKE2B_256);
+        assert_eq!(
+            sdk::crypto::hash_owned(SharedSupportedHashes::Blake2b256, b"abc"),
+            ABC_BLAKE2B_256
+        );
+        let mut buffer = [0u8; 64];
+        let len = sdk::crypto::hash_into(SharedSupportedHashes::Blake2b256, b"abc", &mut buffer);
+        assert_eq!(&buffer[..len], ABC_BLAKE2B_256);
+    }
+
     // macros dont work so im stuck with writing this out manually
 
     // blake2b512
@@ -116,6 +116,13 @@ pub fn invoke(_: u32) -> u32 {
             let res = sdk::actor::upgrade_actor(&new_code_cid, None);
             assert_eq!(res, Err(ErrorNumber::IllegalOperation));
         }
+        // test that upgrading to builtin actor code fails with Forbidden
+        6 => {
+            let caller = Address::new_id(sdk::message::caller());
+            let account_code_cid = sdk::actor::get_actor_code_cid(&caller).unwrap();
+            let res = sdk::actor::upgrade_actor(&account_code_cid, None);
+            assert_eq!(res, Err(ErrorNumber::Forbidden));
+        }
         // test that calling an upgrade with actor already on the call stack fails
         99 => {
             let new_code_cid = sdk::actor::get_actor_code_cid(&Address::new_id(10000)).unwrap();
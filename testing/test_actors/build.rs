@@ -31,9 +31,26 @@ const ACTORS: &[(&str, &str)] = &[
     ("CREATE_ACTOR_BINARY", "fil_create_actor"),
     ("OOM_ACTOR_BINARY", "fil_oom_actor"),
     ("SSELF_ACTOR_BINARY", "fil_sself_actor"),
+    ("SEND_ACTOR_BINARY", "fil_send_actor"),
     ("UPGRADE_ACTOR_BINARY", "fil_upgrade_actor"),
     ("UPGRADE_RECEIVE_ACTOR_BINARY", "fil_upgrade_receive_actor"),
     ("CUSTOM_SYSCALL_ACTOR_BINARY", "fil_custom_syscall_actor"),
+    ("PROOFS_ACTOR_BINARY", "fil_proofs_actor"),
+    ("MESSAGE_CONTEXT_ACTOR_BINARY", "fil_message_context_actor"),
+    ("STATE_ROOT_ACTOR_BINARY", "fil_state_root_actor"),
+    (
+        "STATE_INSPECTION_ACTOR_BINARY",
+        "fil_state_inspection_actor",
+    ),
+    ("PANIC_ACTOR_BINARY", "fil_panic_actor"),
+    ("RANDOMNESS_ACTOR_BINARY", "fil_randomness_actor"),
+    ("RECORDER_ACTOR_BINARY", "fil_recorder_actor"),
+    ("PARAMS_ACTOR_BINARY", "fil_params_actor"),
+    ("ABORT_FMT_ACTOR_BINARY", "fil_abort_fmt_actor"),
+    ("ABORT_FORMAT_ACTOR_BINARY", "fil_abort_format_actor"),
+    ("FANOUT_ACTOR_BINARY", "fil_fanout_actor"),
+    ("BLOCK_STAT_ACTOR_BINARY", "fil_block_stat_actor"),
+    ("CONSENSUS_FAULT_ACTOR_BINARY", "fil_consensus_fault_actor"),
 ];
 
 const WASM_TARGET: &str = "wasm32-unknown-unknown";
@@ -0,0 +1,67 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io::Write;
+use std::path::Path;
+
+use cid::Cid;
+use fvm::machine::{ActorDiff, StateDiff, diff_state};
+use serde_json::{Value, json};
+
+use crate::{import_car, json};
+
+pub fn run(
+    as_json: bool,
+    out: &mut impl Write,
+    car: &Path,
+    a: &Cid,
+    b: &Cid,
+) -> anyhow::Result<()> {
+    let store = import_car(car)?;
+    let diffs = diff_state(&store, a, b)?;
+    if as_json {
+        return json::write(out, &Value::Array(diffs.iter().map(diff_json).collect()));
+    }
+    if diffs.is_empty() {
+        writeln!(out, "no differences")?;
+    }
+    for diff in &diffs {
+        writeln!(out, "{}", diff)?;
+    }
+    Ok(())
+}
+
+fn diff_json(diff: &ActorDiff) -> Value {
+    match diff {
+        ActorDiff::Added(id, actor) => json!({
+            "actor": id,
+            "change": "added",
+            "after": json::actor(actor),
+        }),
+        ActorDiff::Deleted(id, actor) => json!({
+            "actor": id,
+            "change": "deleted",
+            "before": json::actor(actor),
+        }),
+        ActorDiff::Modified(id, diff) => json!({
+            "actor": id,
+            "change": "modified",
+            "code": json::change(&diff.code, json::cid),
+            "balance": json::change(&diff.balance, json::token_amount),
+            "sequence": json::change(&diff.sequence, |s| json!(s)),
+            "delegated_address": json::change(&diff.delegated_address, json::address),
+            "state": diff.state.as_ref().map_or(Value::Null, state_diff_json),
+        }),
+    }
+}
+
+fn state_diff_json(diff: &StateDiff) -> Value {
+    let path: Vec<_> = diff
+        .path
+        .iter()
+        .map(|pair| json::change(&Some(*pair), json::cid))
+        .collect();
+    json!({
+        "path": path,
+        "blocks": json::change(&diff.blocks, json::ipld),
+    })
+}
@@ -0,0 +1,75 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! JSON rendering, following the DAG-JSON conventions for links and bytes.
+use std::io::Write;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use cid::Cid;
+use fvm::state_tree::ActorState;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipld_core::ipld::Ipld;
+use serde_json::{Value, json};
+
+/// Writes a value as pretty-printed JSON, followed by a newline.
+pub fn write(out: &mut impl Write, value: &Value) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+pub fn cid(cid: &Cid) -> Value {
+    json!({ "/": cid.to_string() })
+}
+
+pub fn bytes(bytes: &[u8]) -> Value {
+    json!({ "/": { "bytes": STANDARD_NO_PAD.encode(bytes) } })
+}
+
+/// Token amounts are rendered as strings of attoFIL, as they don't fit in a JSON number.
+pub fn token_amount(amount: &TokenAmount) -> Value {
+    Value::String(amount.atto().to_string())
+}
+
+pub fn address(address: &Option<Address>) -> Value {
+    address.map_or(Value::Null, |a| Value::String(a.to_string()))
+}
+
+pub fn actor(actor: &ActorState) -> Value {
+    json!({
+        "code": cid(&actor.code),
+        "state": cid(&actor.state),
+        "sequence": actor.sequence,
+        "balance": token_amount(&actor.balance),
+        "delegated_address": address(&actor.delegated_address),
+    })
+}
+
+/// A `(before, after)` pair, or null if the value didn't change.
+pub fn change<T>(change: &Option<(T, T)>, f: impl Fn(&T) -> Value) -> Value {
+    match change {
+        Some((before, after)) => json!({ "before": f(before), "after": f(after) }),
+        None => Value::Null,
+    }
+}
+
+pub fn ipld(value: &Ipld) -> Value {
+    match value {
+        Ipld::Null => Value::Null,
+        Ipld::Bool(b) => Value::Bool(*b),
+        // Integers outside of the range of JSON numbers (as implemented by serde_json) are
+        // rendered as strings.
+        Ipld::Integer(i) => match (i64::try_from(*i), u64::try_from(*i)) {
+            (Ok(i), _) => i.into(),
+            (_, Ok(u)) => u.into(),
+            _ => Value::String(i.to_string()),
+        },
+        Ipld::Float(f) => json!(f),
+        Ipld::String(s) => Value::String(s.clone()),
+        Ipld::Bytes(b) => bytes(b),
+        Ipld::List(list) => Value::Array(list.iter().map(ipld).collect()),
+        Ipld::Map(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), ipld(v))).collect()),
+        Ipld::Link(link) => cid(link),
+    }
+}
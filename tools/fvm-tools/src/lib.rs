@@ -0,0 +1,115 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Command-line tools for operators: thin wrappers over the FVM's state diff, state query,
+//! snapshot import, test vector and execution archive utilities.
+
+mod diff;
+mod json;
+mod query;
+mod receipt;
+mod replay;
+
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+use cid::Cid;
+use clap::{Parser, Subcommand};
+use fvm::machine::{SnapshotOptions, import_snapshot};
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_shared::address::Address;
+
+/// Inspect FVM state, test vectors and execution archives.
+#[derive(Parser, Debug)]
+#[command(name = "fvm-tools")]
+pub struct Cli {
+    /// Print JSON instead of human-readable text.
+    #[arg(long, global = true, default_value = "false")]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compare two state trees, listing the actors that differ.
+    Diff {
+        /// CAR file containing both state trees.
+        car: PathBuf,
+        /// The first state root.
+        #[arg(value_parser = parse_cid)]
+        root_a: Cid,
+        /// The second state root.
+        #[arg(value_parser = parse_cid)]
+        root_b: Cid,
+    },
+    /// Look up a value in an actor's state.
+    Query {
+        /// CAR file containing the state tree.
+        car: PathBuf,
+        /// The state root.
+        #[arg(value_parser = parse_cid)]
+        root: Cid,
+        /// The actor's address, or ID.
+        #[arg(value_parser = parse_actor)]
+        actor: Address,
+        /// A `/`-separated path of map keys and list indices into the actor's state.
+        #[arg(default_value = "")]
+        path: String,
+    },
+    /// Run a message test vector, checking its postconditions.
+    Replay {
+        /// The test vector (JSON) file.
+        vector: PathBuf,
+    },
+    /// Show a record of an execution archive.
+    InspectReceipt {
+        /// The archive file.
+        archive: PathBuf,
+        /// The index of the record, starting at 0.
+        index: usize,
+    },
+}
+
+/// Runs the command, writing its output to `out`.
+pub fn run(cli: &Cli, out: &mut impl Write) -> anyhow::Result<()> {
+    match &cli.command {
+        Command::Diff {
+            car,
+            root_a,
+            root_b,
+        } => diff::run(cli.json, out, car, root_a, root_b),
+        Command::Query {
+            car,
+            root,
+            actor,
+            path,
+        } => query::run(cli.json, out, car, root, actor, path),
+        Command::Replay { vector } => replay::run(cli.json, out, vector),
+        Command::InspectReceipt { archive, index } => receipt::run(cli.json, out, archive, *index),
+    }
+}
+
+/// Imports a CAR file (e.g., a chain snapshot) into a new in-memory store.
+fn import_car(path: &Path) -> anyhow::Result<MemoryBlockstore> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let store = MemoryBlockstore::default();
+    import_snapshot(BufReader::new(file), &store, SnapshotOptions::default())
+        .with_context(|| format!("failed to import {}", path.display()))?;
+    Ok(store)
+}
+
+fn parse_cid(s: &str) -> Result<Cid, String> {
+    Cid::from_str(s).map_err(|e| e.to_string())
+}
+
+/// Parses an address, also accepting a bare actor ID.
+fn parse_actor(s: &str) -> Result<Address, String> {
+    match s.parse::<u64>() {
+        Ok(id) => Ok(Address::new_id(id)),
+        Err(_) => Address::from_str(s).map_err(|e| e.to_string()),
+    }
+}
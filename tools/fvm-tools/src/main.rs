@@ -0,0 +1,14 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io;
+
+use clap::Parser;
+use fvm_tools::Cli;
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = fvm_tools::run(&cli, &mut io::stdout().lock()) {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
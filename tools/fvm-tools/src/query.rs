@@ -0,0 +1,37 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io::Write;
+use std::path::Path;
+
+use cid::Cid;
+use fvm::machine::query_state;
+use fvm_shared::address::Address;
+use serde_json::json;
+
+use crate::{import_car, json};
+
+pub fn run(
+    as_json: bool,
+    out: &mut impl Write,
+    car: &Path,
+    root: &Cid,
+    actor: &Address,
+    path: &str,
+) -> anyhow::Result<()> {
+    let store = import_car(car)?;
+    let query = query_state(&store, root, actor, path)?;
+    if as_json {
+        return json::write(
+            out,
+            &json!({
+                "actor": query.actor,
+                "block": json::cid(&query.block),
+                "value": json::ipld(&query.value),
+            }),
+        );
+    }
+    writeln!(out, "actor: {}", query.actor)?;
+    writeln!(out, "block: {}", query.block)?;
+    writeln!(out, "value: {:?}", query.value)?;
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, bail};
+use fvm::trace::archive::{ArchivedApplyRet, read_archive};
+
+use crate::json;
+
+pub fn run(as_json: bool, out: &mut impl Write, path: &Path, index: usize) -> anyhow::Result<()> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut count = 0;
+    for res in read_archive(BufReader::new(file)) {
+        let record = res.with_context(|| format!("failed to read record {}", count))?;
+        if count == index {
+            return write_record(as_json, out, index, &record);
+        }
+        count += 1;
+    }
+    bail!(
+        "record {} not found, the archive has {} records",
+        index,
+        count
+    )
+}
+
+fn write_record(
+    as_json: bool,
+    out: &mut impl Write,
+    index: usize,
+    record: &ArchivedApplyRet,
+) -> anyhow::Result<()> {
+    if as_json {
+        let value = serde_json::from_str(&record.to_json()?)?;
+        return json::write(out, &value);
+    }
+
+    let receipt = &record.msg_receipt;
+    writeln!(out, "record {} (schema version {})", index, record.version)?;
+    writeln!(out, "exit code: {}", receipt.exit_code)?;
+    writeln!(out, "gas used: {}", receipt.gas_used)?;
+    match record.return_codec {
+        Some(codec) => writeln!(
+            out,
+            "return data (codec {:#x}): {}",
+            codec,
+            hex::encode(receipt.return_data.bytes())
+        )?,
+        None => writeln!(out, "return data: none")?,
+    }
+    match &receipt.events_root {
+        Some(root) => writeln!(out, "events root: {}", root)?,
+        None => writeln!(out, "events root: none")?,
+    }
+    writeln!(
+        out,
+        "gas: {} burned, {} refunded",
+        record.gas_burned, record.gas_refund
+    )?;
+    writeln!(
+        out,
+        "fees: base fee burn {}, over-estimation burn {}, miner tip {}, penalty {}, refund {}",
+        record.base_fee_burn,
+        record.over_estimation_burn,
+        record.miner_tip,
+        record.penalty,
+        record.refund
+    )?;
+    writeln!(out, "events: {}", record.events.len())?;
+    writeln!(out, "trace: {} events", record.exec_trace.len())?;
+    if let Some(failure) = &record.failure_info {
+        writeln!(out, "failure: {}", failure)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, bail, ensure};
+use fvm::engine::MultiEngine;
+use fvm_conformance_tests::driver::{VariantResult, run_variant};
+use fvm_conformance_tests::vector::MessageVector;
+use serde_json::{Value, json};
+
+use crate::json;
+
+pub fn run(as_json: bool, out: &mut impl Write, path: &Path) -> anyhow::Result<()> {
+    let vector = MessageVector::from_file(path)
+        .with_context(|| format!("failed to load vector {}", path.display()))?;
+    let results = replay(&vector)?;
+
+    if as_json {
+        let results: Vec<_> = results
+            .iter()
+            .map(|res| {
+                let (result, reason) = match res {
+                    VariantResult::Ok { .. } => ("ok", None),
+                    VariantResult::Skipped { reason, .. } => ("skipped", Some(reason.clone())),
+                    VariantResult::Failed { reason, .. } => ("failed", Some(format!("{reason:#}"))),
+                };
+                json!({ "variant": res.id(), "result": result, "reason": reason })
+            })
+            .collect();
+        json::write(out, &Value::Array(results))?;
+    } else {
+        for res in &results {
+            match res {
+                VariantResult::Ok { id } => writeln!(out, "ok: {}", id)?,
+                VariantResult::Skipped { id, reason } => {
+                    writeln!(out, "skipped: {} ({})", id, reason)?
+                }
+                VariantResult::Failed { id, reason } => {
+                    writeln!(out, "failed: {}: {:#}", id, reason)?
+                }
+            }
+        }
+    }
+
+    let failed = results
+        .iter()
+        .filter(|res| matches!(res, VariantResult::Failed { .. }))
+        .count();
+    if failed > 0 {
+        bail!("{} of {} variants failed", failed, results.len());
+    }
+    Ok(())
+}
+
+/// Runs all the variants of the vector, skipping them if the vector isn't supported.
+fn replay(vector: &MessageVector) -> anyhow::Result<Vec<VariantResult>> {
+    let variants = &vector.preconditions.variants;
+    if !vector.is_supported() {
+        return Ok(variants
+            .iter()
+            .map(|variant| VariantResult::Skipped {
+                id: variant.id.clone(),
+                reason: "selector not supported".to_owned(),
+            })
+            .collect());
+    }
+
+    let (bs, roots) = vector
+        .seed_blockstore()
+        .context("failed to load the vector's state")?;
+    let pre_root = &vector.preconditions.state_tree.root_cid;
+    ensure!(
+        roots.contains(pre_root),
+        "the vector's state doesn't contain the precondition state root {}",
+        pre_root
+    );
+
+    let engines = MultiEngine::new(1);
+    Ok(variants
+        .iter()
+        .map(|variant| {
+            run_variant(bs.clone(), vector, variant, &engines, true, None, None).unwrap_or_else(
+                |reason| VariantResult::Failed {
+                    id: variant.id.clone(),
+                    reason,
+                },
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,343 @@
+// Copyright 2021-2023 Protocol Labs
+// SPDX-License-Identifier: Apache-2.0, MIT
+//! Golden tests of the CLI output, over small fixtures written to the target directory.
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+use cid::Cid;
+use clap::Parser;
+use fvm::state_tree::{ActorState, StateTree};
+use fvm::trace::ExecutionEvent;
+use fvm::trace::archive::{ARCHIVE_VERSION, ArchivedApplyRet, write_archive};
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_car::{Block, CarHeader, CarWriter};
+use fvm_ipld_encoding::{CborStore, IPLD_RAW, RawBytes};
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::receipt::Receipt;
+use fvm_shared::state::StateTreeVersion;
+use fvm_tools::Cli;
+use ipld_core::ipld::Ipld;
+use multihash_codetable::Code;
+use serde_json::{Value, json};
+
+/// Runs the CLI with the given arguments, returning its output.
+fn run(args: &[&str]) -> anyhow::Result<String> {
+    let cli = Cli::try_parse_from(std::iter::once("fvm-tools").chain(args.iter().copied()))?;
+    let mut out = Vec::new();
+    fvm_tools::run(&cli, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+fn run_json(args: &[&str]) -> Value {
+    let mut args = args.to_vec();
+    args.push("--json");
+    serde_json::from_str(&run(&args).unwrap()).unwrap()
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("fvm-tools-{name}"))
+}
+
+/// Collects the blocks put into it.
+#[derive(Default)]
+struct Collector(RefCell<Vec<(Cid, Vec<u8>)>>);
+
+impl Blockstore for Collector {
+    fn get(&self, _: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        self.0.borrow_mut().push((*k, block.into()));
+        Ok(())
+    }
+}
+
+fn actor(state: Cid, balance: u64, sequence: u64) -> ActorState {
+    ActorState::new(
+        Cid::default(),
+        state,
+        TokenAmount::from_atto(balance),
+        sequence,
+        None,
+    )
+}
+
+struct StateFixture {
+    car: String,
+    root_a: Cid,
+    root_b: Cid,
+    state_a: Cid,
+    state_b: Cid,
+    child_a: Cid,
+    child_b: Cid,
+}
+
+/// Writes a CAR with two state trees: actor 100 is modified between them, actor 101 is deleted,
+/// and actor 102 is unchanged.
+fn state_fixture(name: &str) -> StateFixture {
+    let bs = MemoryBlockstore::default();
+    let child_a = bs.put_cbor(&"a", Code::Blake2b256).unwrap();
+    let child_b = bs.put_cbor(&"b", Code::Blake2b256).unwrap();
+    let state_a = bs.put_cbor(&(child_a, 1u8), Code::Blake2b256).unwrap();
+    let state_b = bs.put_cbor(&(child_b, 1u8), Code::Blake2b256).unwrap();
+
+    let mut tree = StateTree::new(&bs, StateTreeVersion::V5).unwrap();
+    tree.set_actor(100, actor(state_a, 10, 1));
+    tree.set_actor(101, actor(state_a, 20, 2));
+    tree.set_actor(102, actor(state_a, 30, 3));
+    let root_a = tree.flush().unwrap();
+    tree.set_actor(100, actor(state_b, 5, 3));
+    tree.delete_actor(101);
+    let root_b = tree.flush().unwrap();
+
+    let collector = Collector::default();
+    bs.copy_to(&collector).unwrap();
+    let path = fixture_path(name);
+    let mut writer = CarWriter::new(
+        CarHeader::from(vec![root_a, root_b]),
+        File::create(&path).unwrap(),
+    )
+    .unwrap();
+    for block in collector.0.into_inner() {
+        writer.write(Block::from(block)).unwrap();
+    }
+    writer.flush().unwrap();
+
+    StateFixture {
+        car: path.to_str().unwrap().to_owned(),
+        root_a,
+        root_b,
+        state_a,
+        state_b,
+        child_a,
+        child_b,
+    }
+}
+
+#[test]
+fn diff() {
+    let f = state_fixture("diff.car");
+    let (root_a, root_b) = (f.root_a.to_string(), f.root_b.to_string());
+
+    assert_eq!(
+        run(&["diff", &f.car, &root_a, &root_a]).unwrap(),
+        "no differences\n"
+    );
+    assert_eq!(
+        run(&["diff", &f.car, &root_a, &root_b]).unwrap(),
+        format!(
+            "actor 100 modified:\
+             \n  balance: 0.00000000000000001 -> 0.000000000000000005 (-0.000000000000000005)\
+             \n  sequence: 1 -> 3 (+2)\
+             \n  state: {} -> {}\
+             \n    first differing block (depth 1): {} -> {}\
+             \n    before: String(\"a\")\
+             \n    after: String(\"b\")\
+             \nactor 101 deleted: code {}, balance 0.00000000000000002, sequence 2\n",
+            f.state_a,
+            f.state_b,
+            f.child_a,
+            f.child_b,
+            Cid::default(),
+        )
+    );
+
+    assert_eq!(run_json(&["diff", &f.car, &root_a, &root_a]), json!([]));
+    let cid = |c: Cid| json!({ "/": c.to_string() });
+    assert_eq!(
+        run_json(&["diff", &f.car, &root_a, &root_b]),
+        json!([
+            {
+                "actor": 100,
+                "change": "modified",
+                "code": null,
+                "balance": { "before": "10", "after": "5" },
+                "sequence": { "before": 1, "after": 3 },
+                "delegated_address": null,
+                "state": {
+                    "path": [
+                        { "before": cid(f.state_a), "after": cid(f.state_b) },
+                        { "before": cid(f.child_a), "after": cid(f.child_b) },
+                    ],
+                    "blocks": { "before": "a", "after": "b" },
+                },
+            },
+            {
+                "actor": 101,
+                "change": "deleted",
+                "before": {
+                    "code": cid(Cid::default()),
+                    "state": cid(f.state_a),
+                    "sequence": 2,
+                    "balance": "20",
+                    "delegated_address": null,
+                },
+            },
+        ])
+    );
+}
+
+#[test]
+fn query() {
+    let f = state_fixture("query.car");
+    let root_b = f.root_b.to_string();
+
+    assert_eq!(
+        run(&["query", &f.car, &root_b, "f0100", "1"]).unwrap(),
+        format!("actor: 100\nblock: {}\nvalue: Integer(1)\n", f.state_b)
+    );
+    // Actors can be given by ID, and the path defaults to the state root.
+    assert_eq!(
+        run(&["query", &f.car, &root_b, "100"]).unwrap(),
+        format!(
+            "actor: 100\nblock: {}\nvalue: {:?}\n",
+            f.state_b,
+            Ipld::List(vec![Ipld::Link(f.child_b), Ipld::Integer(1)])
+        )
+    );
+    assert_eq!(
+        run_json(&["query", &f.car, &root_b, "100", "0/"]),
+        json!({
+            "actor": 100,
+            "block": { "/": f.state_b.to_string() },
+            "value": { "/": f.child_b.to_string() },
+        })
+    );
+
+    let err = run(&["query", &f.car, &root_b, "101"]).unwrap_err();
+    assert_eq!(format!("{err:#}"), "actor f0101 not found");
+    let err = run(&["query", &f.car, &root_b, "100", "0/x"]).unwrap_err();
+    assert_eq!(format!("{err:#}"), "can't look up `x` in a string at /0");
+}
+
+#[test]
+fn inspect_receipt() {
+    let record = |exit_code: ExitCode, gas_used: u64| ArchivedApplyRet {
+        version: ARCHIVE_VERSION,
+        msg_receipt: Receipt {
+            exit_code,
+            return_data: RawBytes::new(b"ret".to_vec()),
+            gas_used,
+            events_root: None,
+        },
+        penalty: TokenAmount::from_atto(0),
+        miner_tip: TokenAmount::from_atto(100),
+        base_fee_burn: TokenAmount::from_atto(200),
+        over_estimation_burn: TokenAmount::from_atto(0),
+        refund: TokenAmount::from_atto(300),
+        gas_refund: 10,
+        gas_burned: 20,
+        failure_info: (!exit_code.is_success()).then(|| "message failed".to_owned()),
+        exec_trace: vec![
+            ExecutionEvent::Log("hello".into()),
+            ExecutionEvent::CallReturn(exit_code, None),
+        ],
+        events: vec![],
+        return_codec: Some(IPLD_RAW),
+        miner_tip_recipient: Some(2),
+        proof_gas_used: 0,
+        created_actors: vec![],
+        deleted_actors: vec![],
+    };
+    let records = [record(ExitCode::OK, 1234), record(ExitCode::new(16), 5678)];
+    let path = fixture_path("receipts.archive");
+    write_archive(File::create(&path).unwrap(), &records).unwrap();
+    let path = path.to_str().unwrap();
+
+    assert_eq!(
+        run(&["inspect-receipt", path, "1"]).unwrap(),
+        "record 1 (schema version 2)\
+         \nexit code: 16\
+         \ngas used: 5678\
+         \nreturn data (codec 0x55): 726574\
+         \nevents root: none\
+         \ngas: 20 burned, 10 refunded\
+         \nfees: base fee burn 0.0000000000000002, over-estimation burn 0.0, miner tip \
+         0.0000000000000001, penalty 0.0, refund 0.0000000000000003\
+         \nevents: 0\
+         \ntrace: 2 events\
+         \nfailure: message failed\n"
+    );
+    // The JSON output is the archive's JSON encoding of the record.
+    let expected: Value = serde_json::from_str(&records[0].to_json().unwrap()).unwrap();
+    assert_eq!(run_json(&["inspect-receipt", path, "0"]), expected);
+
+    let err = run(&["inspect-receipt", path, "2"]).unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "record 2 not found, the archive has 2 records"
+    );
+}
+
+/// Writes a message vector with no messages, and the given selector and CAR.
+fn vector_fixture(name: &str, selector: Value, car: &str) -> String {
+    let root = json!({ "/": Cid::default().to_string() });
+    let vector = json!({
+        "class": "message",
+        "selector": selector,
+        "car": car,
+        "preconditions": {
+            "state_tree": { "root_cid": root },
+            "variants": [
+                { "id": "v1", "epoch": 100, "nv": 18 },
+                { "id": "v2", "epoch": 200, "nv": 18 },
+            ],
+        },
+        "apply_messages": [],
+        "postconditions": {
+            "state_tree": { "root_cid": root },
+            "receipts": [],
+        },
+    });
+    let path = fixture_path(name);
+    fs::write(&path, vector.to_string()).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn replay() {
+    let path = vector_fixture("chaos.json", json!({ "chaos_actor": "true" }), "");
+    assert_eq!(
+        run(&["replay", &path]).unwrap(),
+        "skipped: v1 (selector not supported)\nskipped: v2 (selector not supported)\n"
+    );
+    assert_eq!(
+        run_json(&["replay", &path]),
+        json!([
+            { "variant": "v1", "result": "skipped", "reason": "selector not supported" },
+            { "variant": "v2", "result": "skipped", "reason": "selector not supported" },
+        ])
+    );
+
+    // "bm90IGd6aXA=" isn't a gzipped CAR.
+    let path = vector_fixture("corrupt.json", Value::Null, "bm90IGd6aXA=");
+    let err = run(&["replay", &path]).unwrap_err();
+    assert!(
+        format!("{err:#}").starts_with("failed to load the vector's state: "),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn errors() {
+    let missing = fixture_path("missing.car");
+    let missing = missing.to_str().unwrap();
+    let root = Cid::default().to_string();
+    let err = run(&["diff", missing, &root, &root]).unwrap_err();
+    assert!(
+        format!("{err:#}").starts_with(&format!("failed to open {missing}: ")),
+        "{err:#}"
+    );
+
+    // Malformed arguments are rejected before running the command.
+    let err = run(&["diff", missing, "not-a-cid", &root]).unwrap_err();
+    assert!(
+        err.to_string().contains("invalid value 'not-a-cid'"),
+        "{err}"
+    );
+    let err = run(&["query", missing, &root, "x100"]).unwrap_err();
+    assert!(err.to_string().contains("invalid value 'x100'"), "{err}");
+}